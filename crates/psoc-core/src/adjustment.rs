@@ -12,7 +12,7 @@ use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 use uuid::Uuid;
 
-use crate::{Document, PixelData, RgbaPixel, Selection};
+use crate::{Document, PixelData, Point, RgbaPixel, Selection};
 
 /// Core trait for all image adjustments and filters
 ///
@@ -104,7 +104,6 @@ impl AdjustmentScope {
             Self::EntireLayer => true,
             Self::Selection => {
                 if let Some(selection) = selection {
-                    use crate::geometry::Point;
                     selection.contains_point(Point::new(x as f32, y as f32))
                 } else {
                     false
@@ -119,23 +118,45 @@ impl AdjustmentScope {
         }
     }
 
-    /// Get the bounding rectangle of this scope
+    /// How strongly the adjustment applies to the given pixel, from 0 to 255
+    ///
+    /// `(x, y)` is a pixel of a layer placed at `offset`; selections are looked
+    /// up in document coordinates and report their fractional coverage, so
+    /// adjustments fade out along anti-aliased and feathered edges.
+    pub fn coverage(&self, x: u32, y: u32, selection: Option<&Selection>, offset: Point) -> u8 {
+        match (self, selection) {
+            (Self::Selection, Some(selection)) => selection.layer_coverage(x, y, offset),
+            _ if self.contains_point(x, y, selection) => 255,
+            _ => 0,
+        }
+    }
+
+    /// Get the bounding rectangle of this scope in the pixels of a layer placed at `offset`
     pub fn get_bounds(
         &self,
         layer_width: u32,
         layer_height: u32,
         selection: Option<&Selection>,
+        offset: Point,
     ) -> Option<(u32, u32, u32, u32)> {
         match self {
             Self::EntireLayer => Some((0, 0, layer_width, layer_height)),
             Self::Selection => {
                 if let Some(selection) = selection {
                     selection.bounds().map(|rect| {
+                        // Include every pixel the edges partially cover
+                        let x = rect.x - offset.x;
+                        let y = rect.y - offset.y;
+                        let left = x.floor().clamp(0.0, layer_width as f32) as u32;
+                        let top = y.floor().clamp(0.0, layer_height as f32) as u32;
+                        let right = (x + rect.width).ceil().clamp(0.0, layer_width as f32) as u32;
+                        let bottom =
+                            (y + rect.height).ceil().clamp(0.0, layer_height as f32) as u32;
                         (
-                            rect.x.max(0.0) as u32,
-                            rect.y.max(0.0) as u32,
-                            rect.width.min(layer_width as f32) as u32,
-                            rect.height.min(layer_height as f32) as u32,
+                            left,
+                            top,
+                            right.saturating_sub(left),
+                            bottom.saturating_sub(top),
                         )
                     })
                 } else {
@@ -267,6 +288,9 @@ impl AdjustmentRegistry {
 }
 
 /// Apply an adjustment to a document
+///
+/// While Quick Mask mode is active, or an alpha channel is being edited, the
+/// adjustment edits that mask instead of a layer.
 pub fn apply_adjustment_to_document(
     document: &mut Document,
     application: &AdjustmentApplication,
@@ -280,6 +304,12 @@ pub fn apply_adjustment_to_document(
     // Set the adjustment parameters
     adjustment.set_parameters(application.parameters.clone())?;
 
+    // Adjustments and filters edit the Quick Mask or alpha channel itself
+    if let Some(mask) = document.editing_mask_mut() {
+        adjustment.apply(mask)?;
+        return Ok(());
+    }
+
     // Clone the selection to avoid borrowing issues
     let selection_clone = document.selection.clone();
 
@@ -288,6 +318,9 @@ pub fn apply_adjustment_to_document(
         .get_layer_mut(application.layer_index)
         .ok_or_else(|| anyhow::anyhow!("Layer index {} out of bounds", application.layer_index))?;
 
+    // Selections are in document coordinates, the layer's pixels start at its offset
+    let offset = layer.offset;
+
     // Get the layer's pixel data
     let pixel_data = layer
         .pixel_data
@@ -305,6 +338,7 @@ pub fn apply_adjustment_to_document(
                 adjustment.as_ref(),
                 &application.scope,
                 Some(&selection_clone),
+                offset,
             )?;
         }
     }
@@ -321,11 +355,12 @@ fn apply_adjustment_with_scope(
     adjustment: &dyn Adjustment,
     scope: &AdjustmentScope,
     selection: Option<&Selection>,
+    offset: Point,
 ) -> Result<()> {
     let (width, height) = pixel_data.dimensions();
 
     // Get the bounds of the scope
-    let bounds = scope.get_bounds(width, height, selection);
+    let bounds = scope.get_bounds(width, height, selection, offset);
     if bounds.is_none() {
         return Ok(()); // Nothing to process
     }
@@ -352,17 +387,30 @@ fn apply_adjustment_with_scope(
     // Apply the adjustment to the temp data
     adjustment.apply(&mut temp_data)?;
 
-    // Copy the result back, respecting the scope
+    // Copy the result back, blending by how much of each pixel the scope covers
     for y in 0..scope_height {
         for x in 0..scope_width {
             let dst_x = start_x + x;
             let dst_y = start_y + y;
-            if dst_x < width && dst_y < height && scope.contains_point(dst_x, dst_y, selection) {
-                let adjusted_pixel = temp_data.get_pixel(x, y).ok_or_else(|| {
-                    anyhow::anyhow!("Failed to get adjusted pixel at ({}, {})", x, y)
-                })?;
-                pixel_data.set_pixel(dst_x, dst_y, adjusted_pixel)?;
+            if dst_x >= width || dst_y >= height {
+                continue;
             }
+            let coverage = scope.coverage(dst_x, dst_y, selection, offset);
+            if coverage == 0 {
+                continue;
+            }
+            let adjusted_pixel = temp_data
+                .get_pixel(x, y)
+                .ok_or_else(|| anyhow::anyhow!("Failed to get adjusted pixel at ({}, {})", x, y))?;
+            let pixel = if coverage == 255 {
+                adjusted_pixel
+            } else {
+                let original = pixel_data.get_pixel(dst_x, dst_y).ok_or_else(|| {
+                    anyhow::anyhow!("Failed to get pixel at ({}, {})", dst_x, dst_y)
+                })?;
+                original.lerp(adjusted_pixel, coverage as f32 / 255.0)
+            };
+            pixel_data.set_pixel(dst_x, dst_y, pixel)?;
         }
     }
 
@@ -440,7 +488,7 @@ mod tests {
         assert!(scope.contains_point(0, 0, None));
         assert!(scope.contains_point(100, 100, None));
 
-        let bounds = scope.get_bounds(200, 150, None);
+        let bounds = scope.get_bounds(200, 150, None, Point::origin());
         assert_eq!(bounds, Some((0, 0, 200, 150)));
     }
 
@@ -458,7 +506,7 @@ mod tests {
         assert!(!scope.contains_point(9, 20, None));
         assert!(!scope.contains_point(60, 20, None));
 
-        let bounds = scope.get_bounds(200, 150, None);
+        let bounds = scope.get_bounds(200, 150, None, Point::origin());
         assert_eq!(bounds, Some((10, 20, 50, 30)));
     }
 
@@ -550,4 +598,103 @@ mod tests {
         let pixel = pixel_data.get_pixel(0, 0).unwrap();
         assert!(pixel.r > 100); // Should be brighter
     }
+
+    #[test]
+    fn test_selection_adjustment_blends_partial_pixels() {
+        let mut document = Document::new("Test".to_string(), 10, 10);
+        let mut layer = Layer::new_pixel("Test Layer".to_string(), 10, 10);
+        layer.fill(RgbaPixel::new(100, 100, 100, 255));
+        document.add_layer(layer);
+        document.set_selection(Selection::rectangle(2.5, 2.5, 4.0, 4.0));
+
+        let mut registry = AdjustmentRegistry::new();
+        registry.register(Box::new(MockAdjustment::new(1.0)));
+        let application = AdjustmentApplication::new(
+            "mock_brightness".to_string(),
+            serde_json::json!({ "brightness": 1.0 }),
+            AdjustmentScope::Selection,
+            0,
+        );
+        apply_adjustment_to_document(&mut document, &application, &registry).unwrap();
+
+        let pixel_data = document.get_layer(0).unwrap().pixel_data.as_ref().unwrap();
+        let red = |x, y| pixel_data.get_pixel(x, y).unwrap().r;
+        assert_eq!(red(4, 4), 200); // Fully inside
+        assert_eq!(red(0, 0), 100); // Outside
+        assert!((149..=151).contains(&red(2, 4))); // Half covered edge
+        assert!((124..=126).contains(&red(6, 6))); // Quarter covered corner
+    }
+
+    #[test]
+    fn test_selection_adjustment_follows_layer_offset() {
+        let mut document = Document::new("Test".to_string(), 20, 20);
+        let mut layer = Layer::new_pixel("Offset Layer".to_string(), 10, 10);
+        layer.fill(RgbaPixel::new(100, 100, 100, 255));
+        layer.set_position(Point::new(8.0, 6.0));
+        document.add_layer(layer);
+        // Covers layer pixels (2..5, 4..7), with a soft edge
+        let selection = Selection::rectangle(10.0, 10.0, 3.0, 3.0).modify(
+            crate::SelectionModification::Feather { radius: 1.0 },
+            20,
+            20,
+        );
+        document.set_selection(selection.clone());
+
+        let mut registry = AdjustmentRegistry::new();
+        registry.register(Box::new(MockAdjustment::new(1.0)));
+        let application = AdjustmentApplication::new(
+            "mock_brightness".to_string(),
+            serde_json::json!({ "brightness": 1.0 }),
+            AdjustmentScope::Selection,
+            0,
+        );
+        apply_adjustment_to_document(&mut document, &application, &registry).unwrap();
+
+        let pixel_data = document.get_layer(0).unwrap().pixel_data.as_ref().unwrap();
+        for y in 0..10 {
+            for x in 0..10 {
+                let coverage = selection.coverage(x + 8, y + 6) as i32;
+                let expected = 100 + 100 * coverage / 255;
+                let red = pixel_data.get_pixel(x, y).unwrap().r as i32;
+                assert!(
+                    (red - expected).abs() <= 1,
+                    "pixel ({}, {}): {} vs {}",
+                    x,
+                    y,
+                    red,
+                    expected
+                );
+            }
+        }
+        // Layer pixel (3, 5) sits at document (11, 11), inside the selection
+        assert!(pixel_data.get_pixel(3, 5).unwrap().r > 150);
+        assert_eq!(pixel_data.get_pixel(9, 9).unwrap().r, 100);
+    }
+
+    #[test]
+    fn test_adjustment_edits_quick_mask() {
+        let mut document = Document::new("Test".to_string(), 4, 4);
+        let mut layer = Layer::new_pixel("Test Layer".to_string(), 4, 4);
+        layer.fill(RgbaPixel::new(100, 100, 100, 255));
+        document.add_layer(layer);
+        document.set_selection(Selection::rectangle(0.0, 0.0, 2.0, 4.0));
+        document.enter_quick_mask();
+
+        let mut registry = AdjustmentRegistry::new();
+        registry.register(Box::new(MockAdjustment::new(-0.5)));
+        let application = AdjustmentApplication::new(
+            "mock_brightness".to_string(),
+            serde_json::json!({ "brightness": -0.5 }),
+            AdjustmentScope::EntireLayer,
+            0,
+        );
+        apply_adjustment_to_document(&mut document, &application, &registry).unwrap();
+
+        let quick_mask = document.quick_mask.as_ref().unwrap();
+        assert_eq!(quick_mask.get_pixel(0, 0).unwrap().r, 127);
+        assert_eq!(quick_mask.get_pixel(3, 0).unwrap().r, 0);
+        // The layer is left alone
+        let layer = document.get_layer(0).unwrap();
+        assert_eq!(layer.get_pixel(0, 0).unwrap().r, 100);
+    }
 }
@@ -0,0 +1,179 @@
+//! Export assets and density presets
+//!
+//! This module provides:
+//! - Assets marking a layer or a rectangular slice of the canvas for export
+//! - Presets describing the scale, file name suffix and format of each output
+//!
+//! Assets are stored on the document, so they are saved with the project.
+
+use crate::geometry::Rect;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Output format of an asset preset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AssetFormat {
+    /// PNG image
+    #[default]
+    Png,
+    /// JPEG image
+    Jpeg,
+    /// TIFF image
+    Tiff,
+    /// WebP image
+    Webp,
+}
+
+impl AssetFormat {
+    /// File extension without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Tiff => "tiff",
+            Self::Webp => "webp",
+        }
+    }
+}
+
+impl std::fmt::Display for AssetFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Png => write!(f, "PNG"),
+            Self::Jpeg => write!(f, "JPEG"),
+            Self::Tiff => write!(f, "TIFF"),
+            Self::Webp => write!(f, "WebP"),
+        }
+    }
+}
+
+/// One output of an asset: scale, file name suffix and format
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetPreset {
+    /// Scale relative to the document pixels
+    pub scale: f32,
+    /// Appended to the asset name, e.g. `@2x`
+    pub suffix: String,
+    /// Output format
+    pub format: AssetFormat,
+}
+
+impl AssetPreset {
+    /// Create a preset
+    pub fn new(scale: f32, suffix: impl Into<String>, format: AssetFormat) -> Self {
+        Self {
+            scale,
+            suffix: suffix.into(),
+            format,
+        }
+    }
+
+    /// The usual 1x, `@2x` and `@3x` PNG densities
+    pub fn densities() -> Vec<Self> {
+        vec![
+            Self::new(1.0, "", AssetFormat::Png),
+            Self::new(2.0, "@2x", AssetFormat::Png),
+            Self::new(3.0, "@3x", AssetFormat::Png),
+        ]
+    }
+
+    /// File name for an asset exported with this preset
+    pub fn file_name(&self, asset_name: &str) -> String {
+        format!("{}{}.{}", asset_name, self.suffix, self.format.extension())
+    }
+
+    /// Output size for a source of the given size, at least one pixel
+    pub fn scaled_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let scale = self.scale.max(0.0);
+        (
+            ((width as f32 * scale).round() as u32).max(1),
+            ((height as f32 * scale).round() as u32).max(1),
+        )
+    }
+}
+
+/// What part of the document an asset exports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AssetSource {
+    /// A layer on its own, trimmed to its content
+    Layer(Uuid),
+    /// A rectangle of the composited canvas
+    Slice(Rect),
+}
+
+/// A named export asset with its presets
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Asset {
+    /// Unique asset identifier
+    pub id: Uuid,
+    /// Base file name
+    pub name: String,
+    /// Exported content
+    pub source: AssetSource,
+    /// Outputs written on export
+    pub presets: Vec<AssetPreset>,
+}
+
+impl Asset {
+    /// Create an asset with the default density presets
+    pub fn new(name: impl Into<String>, source: AssetSource) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            source,
+            presets: AssetPreset::densities(),
+        }
+    }
+
+    /// Create an asset exporting a layer
+    pub fn from_layer(name: impl Into<String>, layer_id: Uuid) -> Self {
+        Self::new(name, AssetSource::Layer(layer_id))
+    }
+
+    /// Create an asset exporting a rectangular slice
+    pub fn from_slice(name: impl Into<String>, rect: Rect) -> Self {
+        Self::new(name, AssetSource::Slice(rect))
+    }
+
+    /// Replace the presets
+    pub fn with_presets(mut self, presets: Vec<AssetPreset>) -> Self {
+        self.presets = presets;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_file_names() {
+        let presets = AssetPreset::densities();
+        let names: Vec<_> = presets.iter().map(|p| p.file_name("icon")).collect();
+        assert_eq!(names, ["icon.png", "icon@2x.png", "icon@3x.png"]);
+
+        let half = AssetPreset::new(0.5, "", AssetFormat::Webp);
+        assert_eq!(half.file_name("icon"), "icon.webp");
+    }
+
+    #[test]
+    fn test_scaled_size_rounds_and_keeps_one_pixel() {
+        let preset = AssetPreset::new(0.5, "", AssetFormat::Png);
+        assert_eq!(preset.scaled_size(25, 3), (13, 2));
+        assert_eq!(preset.scaled_size(1, 1), (1, 1));
+        assert_eq!(
+            AssetPreset::new(3.0, "@3x", AssetFormat::Png).scaled_size(24, 16),
+            (72, 48)
+        );
+    }
+
+    #[test]
+    fn test_asset_serde_round_trip() {
+        let asset = Asset::from_slice("banner", Rect::new(1.0, 2.0, 30.0, 40.0))
+            .with_presets(vec![AssetPreset::new(0.5, "_small", AssetFormat::Jpeg)]);
+
+        let json = serde_json::to_string(&asset).unwrap();
+        let restored: Asset = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, asset);
+    }
+}
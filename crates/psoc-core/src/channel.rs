@@ -0,0 +1,139 @@
+//! Alpha channels for storing selections
+//!
+//! An alpha channel is a named grayscale image the size of the canvas, kept
+//! with the document so a selection can be saved and loaded again later.
+//! Like layer masks, white is selected and black is not.
+
+use crate::pixel::{PixelData, RgbaPixel};
+use crate::selection::{MaskSelection, Selection};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A named grayscale channel saved with the document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlphaChannel {
+    /// Unique channel identifier
+    pub id: Uuid,
+    /// Channel name
+    pub name: String,
+    /// Channel contents; the red channel holds the value
+    pub pixel_data: PixelData,
+}
+
+impl AlphaChannel {
+    /// Create a black (empty) channel
+    pub fn new(name: String, width: u32, height: u32) -> Self {
+        let mut pixel_data = PixelData::new_grayscale(width, height);
+        pixel_data.fill(RgbaPixel::new(0, 0, 0, 255));
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            pixel_data,
+        }
+    }
+
+    /// Create a channel holding a selection rasterized over the canvas
+    pub fn from_selection(name: String, selection: &Selection, width: u32, height: u32) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            pixel_data: selection_to_gray(selection, width, height),
+        }
+    }
+
+    /// Channel dimensions
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.pixel_data.dimensions()
+    }
+
+    /// Channel value at a pixel, 0 outside the channel
+    pub fn value(&self, x: u32, y: u32) -> u8 {
+        self.pixel_data.get_pixel(x, y).map_or(0, |pixel| pixel.r)
+    }
+
+    /// Turn the channel into a mask selection
+    pub fn to_selection(&self) -> Selection {
+        Selection::Mask(gray_to_mask(&self.pixel_data))
+    }
+
+    /// Follow the canvas when it is resized or cropped, see [`reframe_gray`]
+    pub fn reframe(&mut self, x: i32, y: i32, width: u32, height: u32) {
+        self.pixel_data = reframe_gray(&self.pixel_data, x, y, width, height);
+    }
+}
+
+/// Move a grayscale mask onto a new canvas whose top-left corner is at
+/// (`x`, `y`) on the old one
+///
+/// Areas that were outside the old canvas come out black (unselected).
+pub fn reframe_gray(gray: &PixelData, x: i32, y: i32, width: u32, height: u32) -> PixelData {
+    let mut reframed = PixelData::new_grayscale(width, height);
+    reframed.fill(RgbaPixel::new(0, 0, 0, 255));
+    for new_y in 0..height {
+        for new_x in 0..width {
+            let old_x = new_x as i64 + x as i64;
+            let old_y = new_y as i64 + y as i64;
+            if old_x < 0 || old_y < 0 || old_x > u32::MAX as i64 || old_y > u32::MAX as i64 {
+                continue;
+            }
+            if let Some(pixel) = gray.get_pixel(old_x as u32, old_y as u32) {
+                let _ = reframed.set_pixel(new_x, new_y, pixel);
+            }
+        }
+    }
+    reframed
+}
+
+/// Rasterize a selection into a grayscale image (white = selected)
+pub fn selection_to_gray(selection: &Selection, width: u32, height: u32) -> PixelData {
+    let rasterized = selection.rasterize(width, height);
+    let mut gray = PixelData::new_grayscale(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let value = rasterized.get_pixel(x, y);
+            let _ = gray.set_pixel(x, y, RgbaPixel::new(value, value, value, 255));
+        }
+    }
+    gray
+}
+
+/// Read a grayscale image back into a mask selection
+pub fn gray_to_mask(gray: &PixelData) -> MaskSelection {
+    let (width, height) = gray.dimensions();
+    let mut mask_data = Vec::with_capacity(width as usize * height as usize);
+    for y in 0..height {
+        for x in 0..width {
+            mask_data.push(gray.get_pixel(x, y).map_or(0, |pixel| pixel.r));
+        }
+    }
+    MaskSelection::new(width, height, mask_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Point;
+
+    #[test]
+    fn test_channel_round_trips_selection() {
+        let selection = Selection::rectangle(1.0, 1.0, 2.0, 2.0);
+        let channel = AlphaChannel::from_selection("Alpha 1".to_string(), &selection, 4, 4);
+
+        assert_eq!(channel.dimensions(), (4, 4));
+        assert_eq!(channel.value(0, 0), 0);
+        assert_eq!(channel.value(1, 1), 255);
+        assert_eq!(channel.value(2, 2), 255);
+        assert_eq!(channel.value(3, 3), 0);
+
+        let restored = channel.to_selection();
+        assert!(restored.contains_point(Point::new(1.5, 1.5)));
+        assert!(!restored.contains_point(Point::new(3.5, 0.5)));
+    }
+
+    #[test]
+    fn test_new_channel_is_empty() {
+        let channel = AlphaChannel::new("Alpha 1".to_string(), 3, 2);
+        assert_eq!(channel.dimensions(), (3, 2));
+        assert!((0..2).all(|y| (0..3).all(|x| channel.value(x, y) == 0)));
+    }
+}
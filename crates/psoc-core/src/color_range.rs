@@ -0,0 +1,329 @@
+//! Color Range selection
+//!
+//! Selects pixels across the whole image by color rather than by
+//! connectivity. A target describes which colors count as a match - one or
+//! more sampled colors, a tonal range or skin tones - and the fuzziness
+//! controls how far outside the target a pixel can be before its selection
+//! strength falls off to zero. The result is a soft [`MaskSelection`].
+
+use crate::document::Document;
+use crate::pixel::{PixelData, RgbaPixel};
+use crate::rendering::RenderEngine;
+use crate::selection::MaskSelection;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Upper bound of the shadows tonal range (luma, 0-255)
+pub const SHADOWS_MAX: f32 = 64.0;
+/// Lower bound of the midtones tonal range (luma, 0-255)
+pub const MIDTONES_MIN: f32 = 96.0;
+/// Upper bound of the midtones tonal range (luma, 0-255)
+pub const MIDTONES_MAX: f32 = 160.0;
+/// Lower bound of the highlights tonal range (luma, 0-255)
+pub const HIGHLIGHTS_MIN: f32 = 192.0;
+
+/// Chroma box (Cb, Cr) commonly used to detect skin in YCbCr space
+const SKIN_CB: (f32, f32) = (77.0, 127.0);
+const SKIN_CR: (f32, f32) = (133.0, 173.0);
+
+/// Which colors a Color Range selection matches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ColorRangeTarget {
+    /// Colors close to any of the sampled colors
+    SampledColors(Vec<RgbaPixel>),
+    /// Dark tones
+    Shadows,
+    /// Middle tones
+    Midtones,
+    /// Bright tones
+    Highlights,
+    /// Skin tones, matched by chroma regardless of brightness
+    SkinTones,
+}
+
+impl Default for ColorRangeTarget {
+    fn default() -> Self {
+        Self::SampledColors(Vec::new())
+    }
+}
+
+impl std::fmt::Display for ColorRangeTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorRangeTarget::SampledColors(_) => write!(f, "Sampled Colors"),
+            ColorRangeTarget::Shadows => write!(f, "Shadows"),
+            ColorRangeTarget::Midtones => write!(f, "Midtones"),
+            ColorRangeTarget::Highlights => write!(f, "Highlights"),
+            ColorRangeTarget::SkinTones => write!(f, "Skin Tones"),
+        }
+    }
+}
+
+/// Color Range selection parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColorRange {
+    /// Colors to match
+    pub target: ColorRangeTarget,
+    /// Distance (0-200) over which selection strength falls off outside the target
+    pub fuzziness: f32,
+    /// Select everything that does not match instead
+    pub invert: bool,
+}
+
+impl Default for ColorRange {
+    fn default() -> Self {
+        Self::new(ColorRangeTarget::default())
+    }
+}
+
+impl ColorRange {
+    /// Maximum fuzziness value
+    pub const MAX_FUZZINESS: f32 = 200.0;
+
+    /// Create a color range for a target with the default fuzziness
+    pub fn new(target: ColorRangeTarget) -> Self {
+        Self {
+            target,
+            fuzziness: 40.0,
+            invert: false,
+        }
+    }
+
+    /// Set the fuzziness, clamped to 0-200
+    pub fn with_fuzziness(mut self, fuzziness: f32) -> Self {
+        self.fuzziness = fuzziness.clamp(0.0, Self::MAX_FUZZINESS);
+        self
+    }
+
+    /// Set whether the selection is inverted
+    pub fn with_invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    /// Add a sampled color, switching the target to sampled colors if needed
+    pub fn add_sample(&mut self, color: RgbaPixel) {
+        match &mut self.target {
+            ColorRangeTarget::SampledColors(samples) => {
+                if !samples.contains(&color) {
+                    samples.push(color);
+                }
+            }
+            _ => self.target = ColorRangeTarget::SampledColors(vec![color]),
+        }
+    }
+
+    /// Selection strength (0-255) of a single pixel
+    ///
+    /// Transparent pixels are scaled down by their alpha so empty areas of the
+    /// image are never selected.
+    pub fn strength(&self, pixel: RgbaPixel) -> u8 {
+        let distance = match &self.target {
+            ColorRangeTarget::SampledColors(samples) => samples
+                .iter()
+                .map(|sample| rgb_distance(pixel, *sample))
+                .fold(None, |nearest: Option<f32>, d| {
+                    Some(nearest.map_or(d, |n| n.min(d)))
+                }),
+            ColorRangeTarget::Shadows => Some(range_distance(luma(pixel), 0.0, SHADOWS_MAX)),
+            ColorRangeTarget::Midtones => {
+                Some(range_distance(luma(pixel), MIDTONES_MIN, MIDTONES_MAX))
+            }
+            ColorRangeTarget::Highlights => {
+                Some(range_distance(luma(pixel), HIGHLIGHTS_MIN, 255.0))
+            }
+            ColorRangeTarget::SkinTones => Some(skin_distance(pixel)),
+        };
+
+        let matched = distance.map_or(0.0, |d| falloff(d, self.fuzziness));
+        let value = matched * (pixel.a as f32 / 255.0);
+        let value = if self.invert { 1.0 - value } else { value };
+        (value * 255.0).round() as u8
+    }
+
+    /// Compute the selection strength of every pixel, row by row
+    pub fn mask_values(&self, pixels: &PixelData) -> Vec<u8> {
+        let (width, height) = pixels.dimensions();
+        let mut values = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = pixels
+                    .get_pixel(x, y)
+                    .unwrap_or_else(RgbaPixel::transparent);
+                values.push(self.strength(pixel));
+            }
+        }
+        values
+    }
+
+    /// Build a soft mask selection covering the whole image
+    pub fn select(&self, pixels: &PixelData) -> MaskSelection {
+        let (width, height) = pixels.dimensions();
+        MaskSelection::new(width, height, self.mask_values(pixels))
+    }
+
+    /// Build a soft mask selection from the flattened document
+    pub fn select_in_document(&self, document: &Document) -> Result<MaskSelection> {
+        let flattened = RenderEngine::new().render_document(document)?;
+        Ok(self.select(&flattened))
+    }
+
+    /// Render the mask as a grayscale image (white = selected) for previewing
+    pub fn preview(&self, pixels: &PixelData) -> PixelData {
+        let (width, height) = pixels.dimensions();
+        let mut preview = PixelData::new_grayscale(width, height);
+        for (index, value) in self.mask_values(pixels).into_iter().enumerate() {
+            let x = index as u32 % width;
+            let y = index as u32 / width;
+            let _ = preview.set_pixel(x, y, RgbaPixel::new(value, value, value, 255));
+        }
+        preview
+    }
+}
+
+/// Rec. 601 luma (0-255)
+fn luma(pixel: RgbaPixel) -> f32 {
+    0.299 * pixel.r as f32 + 0.587 * pixel.g as f32 + 0.114 * pixel.b as f32
+}
+
+/// Euclidean distance between two colors in RGB space
+fn rgb_distance(a: RgbaPixel, b: RgbaPixel) -> f32 {
+    let dr = a.r as f32 - b.r as f32;
+    let dg = a.g as f32 - b.g as f32;
+    let db = a.b as f32 - b.b as f32;
+    (dr * dr + dg * dg + db * db).sqrt()
+}
+
+/// Distance of a value from the closed range `[min, max]`
+fn range_distance(value: f32, min: f32, max: f32) -> f32 {
+    if value < min {
+        min - value
+    } else if value > max {
+        value - max
+    } else {
+        0.0
+    }
+}
+
+/// Distance of a pixel's chroma from the skin tone box
+fn skin_distance(pixel: RgbaPixel) -> f32 {
+    let (r, g, b) = (pixel.r as f32, pixel.g as f32, pixel.b as f32);
+    let cb = 128.0 - 0.168_736 * r - 0.331_264 * g + 0.5 * b;
+    let cr = 128.0 + 0.5 * r - 0.418_688 * g - 0.081_312 * b;
+    let dcb = range_distance(cb, SKIN_CB.0, SKIN_CB.1);
+    let dcr = range_distance(cr, SKIN_CR.0, SKIN_CR.1);
+    (dcb * dcb + dcr * dcr).sqrt()
+}
+
+/// Linear falloff from 1.0 at the target to 0.0 at `fuzziness` away from it
+fn falloff(distance: f32, fuzziness: f32) -> f32 {
+    if distance <= 0.0 {
+        1.0
+    } else if fuzziness <= 0.0 {
+        0.0
+    } else {
+        (1.0 - distance / fuzziness).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image_from(colors: &[RgbaPixel]) -> PixelData {
+        let mut pixels = PixelData::new_rgba(colors.len() as u32, 1);
+        for (x, color) in colors.iter().enumerate() {
+            pixels.set_pixel(x as u32, 0, *color).unwrap();
+        }
+        pixels
+    }
+
+    #[test]
+    fn test_sampled_colors_fall_off_with_fuzziness() {
+        let image = image_from(&[
+            RgbaPixel::rgb(200, 0, 0),
+            RgbaPixel::rgb(180, 0, 0),
+            RgbaPixel::rgb(0, 0, 200),
+            RgbaPixel::rgb(0, 200, 0),
+        ]);
+        let mut range = ColorRange::new(ColorRangeTarget::Shadows).with_fuzziness(40.0);
+        range.add_sample(RgbaPixel::rgb(200, 0, 0));
+        range.add_sample(RgbaPixel::rgb(0, 0, 200));
+
+        let values = range.select(&image).mask_data;
+        assert_eq!(values[0], 255);
+        // 20 units away with a fuzziness of 40 is half selected
+        assert_eq!(values[1], 128);
+        assert_eq!(values[2], 255);
+        assert_eq!(values[3], 0);
+
+        let hard = range.clone().with_fuzziness(0.0);
+        assert_eq!(hard.mask_values(&image), vec![255, 0, 255, 0]);
+    }
+
+    #[test]
+    fn test_tonal_ranges() {
+        let image = image_from(&[
+            RgbaPixel::rgb(10, 10, 10),
+            RgbaPixel::rgb(128, 128, 128),
+            RgbaPixel::rgb(240, 240, 240),
+        ]);
+
+        let shadows = ColorRange::new(ColorRangeTarget::Shadows).with_fuzziness(20.0);
+        let midtones = ColorRange::new(ColorRangeTarget::Midtones).with_fuzziness(20.0);
+        let highlights = ColorRange::new(ColorRangeTarget::Highlights).with_fuzziness(20.0);
+
+        assert_eq!(shadows.mask_values(&image), vec![255, 0, 0]);
+        assert_eq!(midtones.mask_values(&image), vec![0, 255, 0]);
+        assert_eq!(highlights.mask_values(&image), vec![0, 0, 255]);
+
+        // Luma 80 sits between shadows and midtones, inside both falloffs
+        let between = RgbaPixel::rgb(80, 80, 80);
+        assert!(shadows.strength(between) > 0 && shadows.strength(between) < 255);
+        assert!(midtones.strength(between) > 0 && midtones.strength(between) < 255);
+    }
+
+    #[test]
+    fn test_skin_tones() {
+        let range = ColorRange::new(ColorRangeTarget::SkinTones).with_fuzziness(10.0);
+        assert_eq!(range.strength(RgbaPixel::rgb(224, 172, 145)), 255);
+        assert_eq!(range.strength(RgbaPixel::rgb(141, 85, 36)), 255);
+        assert_eq!(range.strength(RgbaPixel::rgb(30, 90, 220)), 0);
+        assert_eq!(range.strength(RgbaPixel::rgb(40, 200, 40)), 0);
+    }
+
+    #[test]
+    fn test_invert_and_transparency() {
+        let image = image_from(&[
+            RgbaPixel::rgb(0, 0, 0),
+            RgbaPixel::rgb(255, 255, 255),
+            RgbaPixel::new(0, 0, 0, 0),
+        ]);
+        let range = ColorRange::new(ColorRangeTarget::Shadows).with_fuzziness(0.0);
+        assert_eq!(range.mask_values(&image), vec![255, 0, 0]);
+
+        let inverted = range.with_invert(true);
+        assert_eq!(inverted.mask_values(&image), vec![0, 255, 255]);
+    }
+
+    #[test]
+    fn test_preview_and_document_selection() {
+        let mut document = Document::new("Range".to_string(), 2, 1);
+        let mut layer = crate::layer::Layer::new_pixel("Layer".to_string(), 2, 1);
+        layer.set_pixel(0, 0, RgbaPixel::rgb(0, 0, 0)).unwrap();
+        layer
+            .set_pixel(1, 0, RgbaPixel::rgb(255, 255, 255))
+            .unwrap();
+        document.add_layer(layer);
+
+        let range = ColorRange::new(ColorRangeTarget::Highlights);
+        let selection = range.select_in_document(&document).unwrap();
+        assert_eq!((selection.width, selection.height), (2, 1));
+        assert_eq!(selection.mask_data, vec![0, 255]);
+
+        let flattened = RenderEngine::new().render_document(&document).unwrap();
+        let preview = range.preview(&flattened);
+        assert_eq!(preview.get_pixel(0, 0), Some(RgbaPixel::rgb(0, 0, 0)));
+        assert_eq!(preview.get_pixel(1, 0), Some(RgbaPixel::rgb(255, 255, 255)));
+    }
+}
@@ -127,8 +127,11 @@ impl CommandHistory {
         // Execute the command
         command.execute(document)?;
 
-        // Clear redo stack when a new command is executed
+        // Clear redo stack when a new command is executed. Undone commands
+        // stay in the undo stack past the current position, so drop them too;
+        // otherwise undoing past the new command would undo them a second time.
         self.redo_stack.clear();
+        self.undo_stack.truncate(self.current_position);
 
         // Add command to undo stack
         self.undo_stack.push(command);
@@ -491,6 +494,98 @@ mod tests {
         assert!(!history.can_redo());
     }
 
+    /// Command that appends its execute/undo calls to a shared log
+    #[derive(Debug)]
+    struct LoggingCommand {
+        metadata: CommandMetadata,
+        log: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl LoggingCommand {
+        fn boxed(
+            description: &str,
+            log: &std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+        ) -> Box<Self> {
+            Box::new(Self {
+                metadata: CommandMetadata::new(description.to_string()),
+                log: log.clone(),
+            })
+        }
+    }
+
+    impl Command for LoggingCommand {
+        fn id(&self) -> Uuid {
+            self.metadata.id
+        }
+
+        fn description(&self) -> &str {
+            &self.metadata.description
+        }
+
+        fn execute(&self, _document: &mut Document) -> Result<()> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("execute {}", self.metadata.description));
+            Ok(())
+        }
+
+        fn undo(&self, _document: &mut Document) -> Result<()> {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("undo {}", self.metadata.description));
+            Ok(())
+        }
+
+        fn timestamp(&self) -> std::time::SystemTime {
+            self.metadata.timestamp
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    #[test]
+    fn test_new_command_discards_undone_commands() {
+        let mut history = CommandHistory::new();
+        let mut document = Document::new("Test".to_string(), 100, 100);
+        let log = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        history
+            .execute_command(LoggingCommand::boxed("A", &log), &mut document)
+            .unwrap();
+        history
+            .execute_command(LoggingCommand::boxed("B", &log), &mut document)
+            .unwrap();
+        assert!(history.undo(&mut document).unwrap());
+
+        // A new command replaces the undone one instead of stacking after it
+        history
+            .execute_command(LoggingCommand::boxed("C", &log), &mut document)
+            .unwrap();
+        assert!(!history.can_redo());
+        assert!(!history.redo(&mut document).unwrap());
+        assert_eq!(history.undo_count(), 2);
+
+        assert!(history.undo(&mut document).unwrap());
+        assert!(history.undo(&mut document).unwrap());
+        assert!(!history.undo(&mut document).unwrap());
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "execute A",
+                "execute B",
+                "undo B",
+                "execute C",
+                "undo C",
+                "undo A"
+            ]
+        );
+    }
+
     #[test]
     fn test_command_descriptions() {
         let mut history = CommandHistory::new();
@@ -3,13 +3,15 @@
 //! This module defines the document structure for the PSOC image editor,
 //! including document metadata, layer management, and document operations.
 
+use crate::asset::Asset;
+use crate::channel::{gray_to_mask, reframe_gray, selection_to_gray, AlphaChannel};
 use crate::color::ColorSpace as DocumentColorSpace;
 use crate::command::CommandHistory;
-use crate::geometry::{Point, Rect, Size};
+use crate::geometry::{Path, Point, Rect, Size};
 use crate::icc::IccProfile;
 use crate::layer::Layer;
 use crate::pixel::{PixelData, RgbaPixel};
-use crate::selection::Selection;
+use crate::selection::{Selection, SelectionMode};
 use anyhow::{Context, Result};
 use image::{DynamicImage, GenericImageView};
 use serde::{Deserialize, Serialize};
@@ -118,6 +120,29 @@ pub struct DocumentMetadata {
     pub created_with_version: String,
     /// Custom metadata fields
     pub custom_fields: HashMap<String, String>,
+    /// EXIF/XMP/IPTC blocks carried over from the source image
+    #[serde(default)]
+    pub embedded: EmbeddedMetadata,
+}
+
+/// Raw camera and editorial metadata embedded in an imported image
+///
+/// The blocks are kept verbatim so they can be written back on export.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddedMetadata {
+    /// EXIF data as a TIFF structure, without the `Exif\0\0` header
+    pub exif: Option<Vec<u8>>,
+    /// XMP packet
+    pub xmp: Option<String>,
+    /// IPTC-IIM records
+    pub iptc: Option<Vec<u8>>,
+}
+
+impl EmbeddedMetadata {
+    /// Check whether no metadata blocks are present
+    pub fn is_empty(&self) -> bool {
+        self.exif.is_none() && self.xmp.is_none() && self.iptc.is_none()
+    }
 }
 
 impl DocumentMetadata {
@@ -133,6 +158,7 @@ impl DocumentMetadata {
             modified_at: now,
             created_with_version: env!("CARGO_PKG_VERSION").to_string(),
             custom_fields: HashMap::new(),
+            embedded: EmbeddedMetadata::default(),
         }
     }
 
@@ -169,8 +195,29 @@ pub struct Document {
     pub canvas_bounds: Rect,
     /// Current selection
     pub selection: Selection,
+    /// Selection being edited as a grayscale mask while Quick Mask mode is on
+    #[serde(skip)]
+    pub quick_mask: Option<PixelData>,
+    /// Saved selections, stored as named grayscale channels
+    #[serde(default)]
+    pub channels: Vec<AlphaChannel>,
+    /// Alpha channel being edited in place of the active layer
+    #[serde(skip)]
+    pub active_channel: Option<usize>,
+    /// Vector paths, drawn and edited with the pen tool
+    #[serde(default)]
+    pub paths: Vec<Path>,
+    /// Path shown on the canvas and edited by the pen tool
+    #[serde(skip)]
+    pub active_path: Option<usize>,
+    /// Export assets (layers and slices with density presets)
+    #[serde(default)]
+    pub assets: Vec<Asset>,
     /// Whether document has unsaved changes
     pub is_dirty: bool,
+    /// Edit counter, bumped every time the document is marked dirty
+    #[serde(skip)]
+    pub revision: u64,
     /// File path (if document was loaded from or saved to a file)
     pub file_path: Option<std::path::PathBuf>,
     /// Command history for undo/redo operations
@@ -199,9 +246,16 @@ impl Document {
             active_layer_index: None,
             canvas_bounds,
             selection: Selection::default(),
+            assets: Vec::new(),
             is_dirty: false,
+            revision: 0,
             file_path: None,
             command_history: CommandHistory::new(),
+            quick_mask: None,
+            channels: Vec::new(),
+            active_channel: None,
+            paths: Vec::new(),
+            active_path: None,
         }
     }
 
@@ -344,6 +398,44 @@ impl Document {
             .and_then(|index| self.get_layer(index))
     }
 
+    /// Check whether any layer still has pixels waiting to be decoded
+    pub fn has_pending_layers(&self) -> bool {
+        self.layers.iter().any(Layer::is_pending)
+    }
+
+    /// Decode the pending pixels of a layer
+    ///
+    /// Returns whether anything was decoded. The document does not become dirty.
+    pub fn load_layer_pixels(&mut self, index: usize) -> Result<bool> {
+        let layer = self
+            .layers
+            .get_mut(index)
+            .context("Layer index out of bounds")?;
+        layer
+            .load_pending()
+            .with_context(|| format!("Failed to load layer '{}'", layer.name))
+    }
+
+    /// Decode the visible layers and the active layer, which rendering and editing need
+    pub fn load_layers_in_use(&mut self) -> Result<bool> {
+        let mut loaded = false;
+        for index in 0..self.layers.len() {
+            if self.layers[index].visible || self.active_layer_index == Some(index) {
+                loaded |= self.load_layer_pixels(index)?;
+            }
+        }
+        Ok(loaded)
+    }
+
+    /// Decode every pending layer, e.g. before exporting the document
+    pub fn load_pending_layers(&mut self) -> Result<bool> {
+        let mut loaded = false;
+        for index in 0..self.layers.len() {
+            loaded |= self.load_layer_pixels(index)?;
+        }
+        Ok(loaded)
+    }
+
     /// Get mutable active layer
     pub fn active_layer_mut(&mut self) -> Option<&mut Layer> {
         if let Some(index) = self.active_layer_index {
@@ -373,9 +465,28 @@ impl Document {
         self.layers.is_empty()
     }
 
+    /// Add an export asset
+    pub fn add_asset(&mut self, asset: Asset) {
+        self.assets.push(asset);
+        self.mark_dirty();
+    }
+
+    /// Remove an export asset by id
+    pub fn remove_asset(&mut self, id: Uuid) -> Option<Asset> {
+        let index = self.assets.iter().position(|asset| asset.id == id)?;
+        self.mark_dirty();
+        Some(self.assets.remove(index))
+    }
+
+    /// Find the index of a layer by id
+    pub fn layer_index_by_id(&self, id: Uuid) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.id == id)
+    }
+
     /// Mark document as dirty (has unsaved changes)
     pub fn mark_dirty(&mut self) {
         self.is_dirty = true;
+        self.revision += 1;
         self.metadata.touch();
     }
 
@@ -494,16 +605,64 @@ impl Document {
     }
 
     /// Resize document
+    ///
+    /// Alpha channels and the Quick Mask are extended or cut at the right and
+    /// bottom edges to follow the canvas.
     pub fn resize(&mut self, new_width: u32, new_height: u32) -> Result<()> {
         self.size = Size::new(new_width as f32, new_height as f32);
         self.canvas_bounds = Rect::new(0.0, 0.0, new_width as f32, new_height as f32);
 
         // TODO: Implement layer resizing/cropping logic
+        self.reframe_masks(0, 0, new_width, new_height);
+
+        self.mark_dirty();
+        Ok(())
+    }
 
+    /// Crop the canvas to a rectangle
+    ///
+    /// Layers, paths and the selection are shifted so the corner of the
+    /// rectangle becomes the origin; alpha channels and the Quick Mask are cut
+    /// to the new canvas.
+    pub fn crop(&mut self, rect: Rect) -> Result<()> {
+        let x = rect.x.round() as i32;
+        let y = rect.y.round() as i32;
+        let width = rect.width.round();
+        let height = rect.height.round();
+        if width < 1.0 || height < 1.0 {
+            return Err(anyhow::anyhow!(
+                "Crop rectangle is empty: {}x{}",
+                rect.width,
+                rect.height
+            ));
+        }
+
+        let (dx, dy) = (-x as f32, -y as f32);
+        for layer in &mut self.layers {
+            layer.offset = Point::new(layer.offset.x + dx, layer.offset.y + dy);
+        }
+        for path in &mut self.paths {
+            path.translate(dx, dy);
+        }
+        self.selection.translate(dx, dy);
+        self.reframe_masks(x, y, width as u32, height as u32);
+
+        self.size = Size::new(width, height);
+        self.canvas_bounds = Rect::new(0.0, 0.0, width, height);
         self.mark_dirty();
         Ok(())
     }
 
+    /// Move alpha channels and the Quick Mask onto a new canvas
+    fn reframe_masks(&mut self, x: i32, y: i32, width: u32, height: u32) {
+        for channel in &mut self.channels {
+            channel.reframe(x, y, width, height);
+        }
+        if let Some(quick_mask) = &mut self.quick_mask {
+            *quick_mask = reframe_gray(quick_mask, x, y, width, height);
+        }
+    }
+
     /// Duplicate layer
     pub fn duplicate_layer(&mut self, index: usize) -> Result<()> {
         let layer = self
@@ -523,24 +682,31 @@ impl Document {
 
     /// Execute a command and add it to the history
     pub fn execute_command(&mut self, command: Box<dyn crate::Command>) -> Result<()> {
-        // Execute the command first
-        command.execute(self)?;
-
-        // Add to history (we'll implement a simpler version for now)
-        // TODO: Implement proper command history with undo/redo
-        Ok(())
+        self.with_history(|history, document| history.execute_command(command, document))
     }
 
     /// Undo the last command
     pub fn undo(&mut self) -> Result<bool> {
-        // TODO: Implement proper undo functionality
-        Ok(false)
+        self.with_history(|history, document| history.undo(document))
     }
 
     /// Redo the last undone command
     pub fn redo(&mut self) -> Result<bool> {
-        // TODO: Implement proper redo functionality
-        Ok(false)
+        self.with_history(|history, document| history.redo(document))
+    }
+
+    /// Run a history operation against this document
+    ///
+    /// The history is taken out of the document while commands run, since they
+    /// need the document mutably.
+    fn with_history<T>(
+        &mut self,
+        operation: impl FnOnce(&mut CommandHistory, &mut Document) -> Result<T>,
+    ) -> Result<T> {
+        let mut history = std::mem::take(&mut self.command_history);
+        let result = operation(&mut history, self);
+        self.command_history = history;
+        result
     }
 
     /// Check if undo is available
@@ -626,6 +792,212 @@ impl Document {
     pub fn selection_bounds(&self) -> Option<Rect> {
         self.selection.bounds()
     }
+
+    /// Check whether Quick Mask mode is active
+    pub fn is_quick_mask_active(&self) -> bool {
+        self.quick_mask.is_some()
+    }
+
+    /// Enter Quick Mask mode, turning the selection into an editable mask
+    ///
+    /// The mask covers the canvas and is stored like a layer mask: white is
+    /// selected and black is not. Does nothing if Quick Mask is already active.
+    pub fn enter_quick_mask(&mut self) {
+        if self.quick_mask.is_some() {
+            return;
+        }
+
+        let (width, height) = self.dimensions();
+        self.quick_mask = Some(selection_to_gray(&self.selection, width, height));
+    }
+
+    /// Leave Quick Mask mode, turning the mask back into a selection
+    ///
+    /// A mask that is white everywhere leaves no selection. Returns whether
+    /// Quick Mask mode was active.
+    pub fn exit_quick_mask(&mut self) -> bool {
+        let Some(mask) = self.quick_mask.take() else {
+            return false;
+        };
+
+        let mask = gray_to_mask(&mask);
+        let selection = if mask.mask_data.iter().all(|&value| value == 255) {
+            Selection::None
+        } else {
+            Selection::Mask(mask)
+        };
+        self.set_selection(selection);
+        true
+    }
+
+    /// Mask that painting and adjustments currently edit instead of the active layer
+    ///
+    /// Quick Mask takes precedence over an alpha channel being edited.
+    pub fn editing_mask_mut(&mut self) -> Option<&mut PixelData> {
+        if let Some(quick_mask) = self.quick_mask.as_mut() {
+            return Some(quick_mask);
+        }
+        let index = self.active_channel?;
+        self.channels
+            .get_mut(index)
+            .map(|channel| &mut channel.pixel_data)
+    }
+
+    /// Name for the next new alpha channel ("Alpha 1", "Alpha 2", ...)
+    pub fn next_channel_name(&self) -> String {
+        let mut number = self.channels.len() + 1;
+        while self
+            .channels
+            .iter()
+            .any(|channel| channel.name == format!("Alpha {}", number))
+        {
+            number += 1;
+        }
+        format!("Alpha {}", number)
+    }
+
+    /// Insert an alpha channel at an index
+    pub fn insert_channel(&mut self, index: usize, channel: AlphaChannel) -> Result<()> {
+        if index > self.channels.len() {
+            return Err(anyhow::anyhow!("Channel index out of bounds: {}", index));
+        }
+        self.channels.insert(index, channel);
+        if let Some(active) = self.active_channel.as_mut() {
+            if *active >= index {
+                *active += 1;
+            }
+        }
+        self.mark_dirty();
+        Ok(())
+    }
+
+    /// Remove an alpha channel, leaving it if it was being edited
+    pub fn remove_channel(&mut self, index: usize) -> Result<AlphaChannel> {
+        if index >= self.channels.len() {
+            return Err(anyhow::anyhow!("Channel index out of bounds: {}", index));
+        }
+        let channel = self.channels.remove(index);
+        self.active_channel = match self.active_channel {
+            Some(active) if active == index => None,
+            Some(active) if active > index => Some(active - 1),
+            active => active,
+        };
+        self.mark_dirty();
+        Ok(channel)
+    }
+
+    /// Save the current selection as a new alpha channel, returning its index
+    pub fn save_selection_to_channel(&mut self, name: String) -> usize {
+        let (width, height) = self.dimensions();
+        let channel = AlphaChannel::from_selection(name, &self.selection, width, height);
+        self.channels.push(channel);
+        self.mark_dirty();
+        self.channels.len() - 1
+    }
+
+    /// Selection an alpha channel would produce when combined with the current one
+    pub fn channel_selection(&self, index: usize, mode: SelectionMode) -> Result<Selection> {
+        let channel = self
+            .channels
+            .get(index)
+            .ok_or_else(|| anyhow::anyhow!("Channel index out of bounds: {}", index))?;
+        let (width, height) = self.dimensions();
+        Ok(self
+            .selection
+            .combine(&channel.to_selection(), mode, width, height))
+    }
+
+    /// Load an alpha channel as the selection, combined with the current one
+    pub fn load_channel_selection(&mut self, index: usize, mode: SelectionMode) -> Result<()> {
+        let selection = self.channel_selection(index, mode)?;
+        self.set_selection(selection);
+        Ok(())
+    }
+
+    /// Start or stop editing an alpha channel as a grayscale image
+    pub fn set_active_channel(&mut self, index: Option<usize>) -> Result<()> {
+        if let Some(index) = index {
+            if index >= self.channels.len() {
+                return Err(anyhow::anyhow!("Channel index out of bounds: {}", index));
+            }
+        }
+        self.active_channel = index;
+        Ok(())
+    }
+
+    /// Name for the next new path ("Path 1", "Path 2", ...)
+    pub fn next_path_name(&self) -> String {
+        let mut number = self.paths.len() + 1;
+        while self
+            .paths
+            .iter()
+            .any(|path| path.name == format!("Path {}", number))
+        {
+            number += 1;
+        }
+        format!("Path {}", number)
+    }
+
+    /// Insert a path at an index
+    pub fn insert_path(&mut self, index: usize, path: Path) -> Result<()> {
+        if index > self.paths.len() {
+            return Err(anyhow::anyhow!("Path index out of bounds: {}", index));
+        }
+        self.paths.insert(index, path);
+        if let Some(active) = self.active_path.as_mut() {
+            if *active >= index {
+                *active += 1;
+            }
+        }
+        self.mark_dirty();
+        Ok(())
+    }
+
+    /// Remove a path, deactivating it if it was active
+    pub fn remove_path(&mut self, index: usize) -> Result<Path> {
+        if index >= self.paths.len() {
+            return Err(anyhow::anyhow!("Path index out of bounds: {}", index));
+        }
+        let path = self.paths.remove(index);
+        self.active_path = match self.active_path {
+            Some(active) if active == index => None,
+            Some(active) if active > index => Some(active - 1),
+            active => active,
+        };
+        self.mark_dirty();
+        Ok(path)
+    }
+
+    /// Show and edit a path, or none
+    pub fn set_active_path(&mut self, index: Option<usize>) -> Result<()> {
+        if let Some(index) = index {
+            if index >= self.paths.len() {
+                return Err(anyhow::anyhow!("Path index out of bounds: {}", index));
+            }
+        }
+        self.active_path = index;
+        Ok(())
+    }
+
+    /// The active path, if any
+    pub fn active_path(&self) -> Option<&Path> {
+        self.paths.get(self.active_path?)
+    }
+
+    /// The active path, created and activated first if there is none
+    pub fn active_path_or_new(&mut self) -> &mut Path {
+        let index = match self.active_path.filter(|&index| index < self.paths.len()) {
+            Some(index) => index,
+            None => {
+                let path = Path::new(self.next_path_name());
+                self.paths.push(path);
+                self.paths.len() - 1
+            }
+        };
+        self.active_path = Some(index);
+        self.mark_dirty();
+        &mut self.paths[index]
+    }
 }
 
 #[cfg(test)]
@@ -643,6 +1015,18 @@ mod tests {
         assert!(!doc.is_dirty);
     }
 
+    #[test]
+    fn test_mark_dirty_bumps_revision() {
+        let mut doc = Document::new("Test".to_string(), 10, 10);
+        doc.mark_dirty();
+        doc.mark_clean();
+        let saved = doc.revision;
+
+        doc.mark_dirty();
+        assert!(doc.is_dirty);
+        assert!(doc.revision > saved);
+    }
+
     #[test]
     fn test_layer_management() {
         let mut doc = Document::new("Test".to_string(), 100, 100);
@@ -834,4 +1218,143 @@ mod tests {
         assert!(doc1.icc_profile.is_none());
         assert!(doc2.icc_profile.is_none());
     }
+
+    #[test]
+    fn test_paths() {
+        use crate::geometry::Subpath;
+
+        let mut doc = Document::new("Test".to_string(), 20, 20);
+        assert!(doc.active_path().is_none());
+
+        doc.active_path_or_new()
+            .subpaths
+            .push(Subpath::rectangle(Rect::new(0.0, 0.0, 5.0, 5.0)));
+        assert_eq!(doc.paths.len(), 1);
+        assert_eq!(doc.paths[0].name, "Path 1");
+        assert_eq!(doc.active_path, Some(0));
+        // The active path is reused rather than a new one created
+        doc.active_path_or_new();
+        assert_eq!(doc.paths.len(), 1);
+
+        doc.insert_path(0, Path::new(doc.next_path_name())).unwrap();
+        assert_eq!(doc.paths[0].name, "Path 2");
+        assert_eq!(doc.active_path, Some(1));
+        assert_eq!(doc.active_path().unwrap().anchor_count(), 4);
+
+        doc.remove_path(1).unwrap();
+        assert_eq!(doc.active_path, None);
+        assert!(doc.set_active_path(Some(3)).is_err());
+        doc.set_active_path(Some(0)).unwrap();
+        assert_eq!(doc.active_path().unwrap().name, "Path 2");
+    }
+
+    #[test]
+    fn test_resize_reframes_channels_and_quick_mask() {
+        let mut doc = Document::new("Resize".to_string(), 10, 10);
+        doc.set_selection(Selection::rectangle(0.0, 0.0, 5.0, 10.0));
+        doc.save_selection_to_channel("Alpha 1".to_string());
+        doc.enter_quick_mask();
+
+        doc.resize(20, 6).unwrap();
+        assert_eq!(doc.channels[0].dimensions(), (20, 6));
+        assert_eq!(doc.channels[0].value(2, 2), 255);
+        // Added canvas is unselected
+        assert_eq!(doc.channels[0].value(15, 2), 0);
+        assert_eq!(doc.quick_mask.as_ref().unwrap().dimensions(), (20, 6));
+    }
+
+    #[test]
+    fn test_pending_layers_load_when_in_use() {
+        fn pending(name: &str, visible: bool) -> Layer {
+            let mut stored = Layer::new_pixel(name.to_string(), 4, 4);
+            stored.fill(RgbaPixel::new(0, 128, 255, 255));
+            let mut layer = stored.clone();
+            layer.visible = visible;
+            layer.pixel_data = None;
+            layer.pending_pixels =
+                Some(crate::layer::PendingPixels::new(move || Ok(stored.clone())));
+            layer
+        }
+
+        let mut doc = Document::new("Pending".to_string(), 4, 4);
+        doc.add_layer(pending("Visible", true));
+        doc.add_layer(pending("Hidden", false));
+        doc.add_layer(pending("Active", false));
+        doc.set_active_layer(2).unwrap();
+        doc.mark_clean();
+
+        assert!(doc.load_layers_in_use().unwrap());
+        assert!(!doc.layers[0].is_pending());
+        assert!(doc.layers[1].is_pending());
+        assert!(doc.layers[1].pixel_data.is_none());
+        assert_eq!(
+            doc.layers[2].get_pixel(1, 1),
+            Some(RgbaPixel::new(0, 128, 255, 255))
+        );
+        assert!(!doc.load_layers_in_use().unwrap());
+
+        // Reading without loading leaves the layer pending
+        assert!(doc.layers[1].loaded().unwrap().pixel_data.is_some());
+        assert!(doc.layers[1].is_pending());
+
+        assert!(doc.load_pending_layers().unwrap());
+        assert!(!doc.has_pending_layers());
+        assert!(!doc.is_dirty);
+    }
+
+    #[test]
+    fn test_failed_pending_load_keeps_layer_pending() {
+        let mut doc = Document::new("Broken".to_string(), 4, 4);
+        let mut layer = Layer::new_pixel("Broken".to_string(), 4, 4);
+        layer.pixel_data = None;
+        layer.pending_pixels = Some(crate::layer::PendingPixels::new(|| {
+            Err(anyhow::anyhow!("file went away"))
+        }));
+        doc.add_layer(layer);
+
+        let error = doc.load_pending_layers().unwrap_err();
+        assert!(format!("{:#}", error).contains("file went away"));
+        assert!(doc.layers[0].is_pending());
+    }
+
+    #[test]
+    fn test_alpha_channels() {
+        let mut doc = Document::new("Channels".to_string(), 10, 10);
+        doc.set_selection(Selection::rectangle(0.0, 0.0, 5.0, 10.0));
+        assert_eq!(doc.next_channel_name(), "Alpha 1");
+        let index = doc.save_selection_to_channel(doc.next_channel_name());
+        assert_eq!(index, 0);
+        assert_eq!(doc.channels[0].name, "Alpha 1");
+        assert_eq!(doc.next_channel_name(), "Alpha 2");
+
+        // Intersecting with a right-hand selection leaves the overlap only
+        doc.set_selection(Selection::rectangle(3.0, 0.0, 7.0, 10.0));
+        doc.load_channel_selection(0, SelectionMode::Intersect)
+            .unwrap();
+        assert!(doc.selection.contains_point(Point::new(4.5, 5.0)));
+        assert!(!doc.selection.contains_point(Point::new(1.5, 5.0)));
+        assert!(!doc.selection.contains_point(Point::new(7.5, 5.0)));
+        assert!(doc
+            .load_channel_selection(3, SelectionMode::Replace)
+            .is_err());
+
+        // An edited channel is the paint target unless Quick Mask is on
+        doc.set_active_channel(Some(0)).unwrap();
+        doc.editing_mask_mut()
+            .unwrap()
+            .set_pixel(9, 9, RgbaPixel::white())
+            .unwrap();
+        assert_eq!(doc.channels[0].value(9, 9), 255);
+        doc.enter_quick_mask();
+        assert!(std::ptr::eq(
+            doc.editing_mask_mut().unwrap(),
+            doc.quick_mask.as_mut().unwrap()
+        ));
+        doc.exit_quick_mask();
+
+        let removed = doc.remove_channel(0).unwrap();
+        assert_eq!(removed.name, "Alpha 1");
+        assert_eq!(doc.active_channel, None);
+        assert!(doc.editing_mask_mut().is_none());
+    }
 }
@@ -363,3 +363,311 @@ impl<'de> Deserialize<'de> for Transform {
         Ok(Self::from_array(array))
     }
 }
+
+/// Length of the handles approximating a quarter ellipse with one cubic
+/// bezier, as a fraction of the radius
+const ELLIPSE_KAPPA: f32 = 0.552_284_8;
+
+/// An anchor point of a bezier path with its two control handles
+///
+/// Handles are absolute positions. A handle lying on its anchor means the
+/// curve on that side leaves the anchor straight towards its neighbour.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PathAnchor {
+    /// Anchor position
+    pub point: Point,
+    /// Handle shaping the curve arriving at the anchor
+    pub handle_in: Point,
+    /// Handle shaping the curve leaving the anchor
+    pub handle_out: Point,
+}
+
+impl PathAnchor {
+    /// Create an anchor without handles
+    pub fn corner(point: Point) -> Self {
+        Self {
+            point,
+            handle_in: point,
+            handle_out: point,
+        }
+    }
+
+    /// Create an anchor with symmetric handles, the outgoing one at `handle_out`
+    pub fn smooth(point: Point, handle_out: Point) -> Self {
+        Self {
+            point,
+            handle_in: Point::new(2.0 * point.x - handle_out.x, 2.0 * point.y - handle_out.y),
+            handle_out,
+        }
+    }
+
+    /// Whether both handles lie on the anchor
+    pub fn is_corner(&self) -> bool {
+        self.handle_in == self.point && self.handle_out == self.point
+    }
+
+    /// Move the anchor together with its handles
+    pub fn translate(&mut self, dx: f32, dy: f32) {
+        self.point = self.point.translate(dx, dy);
+        self.handle_in = self.handle_in.translate(dx, dy);
+        self.handle_out = self.handle_out.translate(dx, dy);
+    }
+}
+
+/// A connected run of anchors joined by cubic bezier segments
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Subpath {
+    /// Anchors in drawing order
+    pub anchors: Vec<PathAnchor>,
+    /// Whether the last anchor joins back to the first
+    pub closed: bool,
+}
+
+impl Subpath {
+    /// Create an empty open subpath
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a subpath of straight segments through the given points
+    pub fn from_points(points: &[Point], closed: bool) -> Self {
+        Self {
+            anchors: points.iter().copied().map(PathAnchor::corner).collect(),
+            closed,
+        }
+    }
+
+    /// Create a closed rectangular subpath
+    pub fn rectangle(rect: Rect) -> Self {
+        Self::from_points(
+            &[
+                rect.top_left(),
+                rect.top_right(),
+                rect.bottom_right(),
+                rect.bottom_left(),
+            ],
+            true,
+        )
+    }
+
+    /// Create a closed elliptical subpath from four bezier segments
+    pub fn ellipse(center: Point, radius_x: f32, radius_y: f32) -> Self {
+        let kx = radius_x * ELLIPSE_KAPPA;
+        let ky = radius_y * ELLIPSE_KAPPA;
+        let anchor = |dx: f32, dy: f32, hx: f32, hy: f32| PathAnchor {
+            point: center.translate(dx, dy),
+            handle_in: center.translate(dx - hx, dy - hy),
+            handle_out: center.translate(dx + hx, dy + hy),
+        };
+        Self {
+            anchors: vec![
+                anchor(radius_x, 0.0, 0.0, ky),
+                anchor(0.0, radius_y, -kx, 0.0),
+                anchor(-radius_x, 0.0, 0.0, -ky),
+                anchor(0.0, -radius_y, kx, 0.0),
+            ],
+            closed: true,
+        }
+    }
+
+    /// Bezier segments as `[start, control 1, control 2, end]`, including the
+    /// closing segment of a closed subpath
+    pub fn segments(&self) -> Vec<[Point; 4]> {
+        let count = self.anchors.len();
+        let segment_count = match (count, self.closed) {
+            (0 | 1, _) => 0,
+            (_, true) => count,
+            (_, false) => count - 1,
+        };
+        (0..segment_count)
+            .map(|i| {
+                let start = &self.anchors[i];
+                let end = &self.anchors[(i + 1) % count];
+                [start.point, start.handle_out, end.handle_in, end.point]
+            })
+            .collect()
+    }
+
+    /// Approximate the subpath with straight lines no further than
+    /// `tolerance` pixels from the curve
+    ///
+    /// Closed subpaths end with their first point again.
+    pub fn flatten(&self, tolerance: f32) -> Vec<Point> {
+        let Some(first) = self.anchors.first() else {
+            return Vec::new();
+        };
+        let mut points = vec![first.point];
+        for [p0, c1, c2, p3] in self.segments() {
+            if c1 == p0 && c2 == p3 {
+                points.push(p3);
+                continue;
+            }
+            // Wang's formula for the number of uniform steps within tolerance
+            let dd = |a: Point, b: Point, c: Point| {
+                Point::new(a.x - 2.0 * b.x + c.x, a.y - 2.0 * b.y + c.y)
+                    .distance_to(&Point::origin())
+            };
+            let deviation = dd(p0, c1, c2).max(dd(c1, c2, p3));
+            let steps =
+                ((0.75 * deviation / tolerance.max(0.01)).sqrt().ceil() as usize).clamp(1, 256);
+            for step in 1..=steps {
+                points.push(cubic_point(p0, c1, c2, p3, step as f32 / steps as f32));
+            }
+        }
+        points
+    }
+}
+
+/// Point at parameter `t` on a cubic bezier
+fn cubic_point(p0: Point, c1: Point, c2: Point, p3: Point, t: f32) -> Point {
+    let u = 1.0 - t;
+    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+    Point::new(
+        a * p0.x + b * c1.x + c * c2.x + d * p3.x,
+        a * p0.y + b * c1.y + c * c2.y + d * p3.y,
+    )
+}
+
+/// A named vector path made of cubic bezier subpaths
+///
+/// Paths are kept with the document independently of layers. Filling or
+/// converting a path to a selection uses the even-odd rule, treating open
+/// subpaths as if they were closed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Path {
+    /// Path name
+    pub name: String,
+    /// Subpaths in drawing order
+    pub subpaths: Vec<Subpath>,
+}
+
+impl Path {
+    /// Flattening tolerance in pixels used for rendering and selections
+    pub const TOLERANCE: f32 = 0.25;
+
+    /// Create an empty path
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            subpaths: Vec::new(),
+        }
+    }
+
+    /// Whether the path has no anchors
+    pub fn is_empty(&self) -> bool {
+        self.subpaths
+            .iter()
+            .all(|subpath| subpath.anchors.is_empty())
+    }
+
+    /// Total number of anchors in all subpaths
+    pub fn anchor_count(&self) -> usize {
+        self.subpaths
+            .iter()
+            .map(|subpath| subpath.anchors.len())
+            .sum()
+    }
+
+    /// Approximate every subpath with straight lines, see [`Subpath::flatten`]
+    pub fn flatten(&self, tolerance: f32) -> Vec<Vec<Point>> {
+        self.subpaths
+            .iter()
+            .map(|subpath| subpath.flatten(tolerance))
+            .filter(|points| !points.is_empty())
+            .collect()
+    }
+
+    /// Bounding rectangle of the curves, if the path has anchors
+    pub fn bounds(&self) -> Option<Rect> {
+        let points: Vec<Point> = self
+            .flatten(Self::TOLERANCE)
+            .into_iter()
+            .flatten()
+            .collect();
+        let first = points.first()?;
+        let (mut min, mut max) = (*first, *first);
+        for point in &points {
+            min = Point::new(min.x.min(point.x), min.y.min(point.y));
+            max = Point::new(max.x.max(point.x), max.y.max(point.y));
+        }
+        Some(Rect::from_points(min, max))
+    }
+
+    /// Whether a point is inside the filled path (even-odd rule)
+    pub fn contains_point(&self, point: Point) -> bool {
+        let mut inside = false;
+        for polygon in self.flatten(Self::TOLERANCE) {
+            let count = polygon.len();
+            for i in 0..count {
+                let (a, b) = (polygon[i], polygon[(i + 1) % count]);
+                if (a.y > point.y) != (b.y > point.y)
+                    && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+                {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+
+    /// Move every anchor and handle by an offset
+    pub fn translate(&mut self, dx: f32, dy: f32) {
+        for anchor in self.subpaths.iter_mut().flat_map(|s| s.anchors.iter_mut()) {
+            anchor.translate(dx, dy);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_flatten_lines_and_curves() {
+        let square = Subpath::rectangle(Rect::new(0.0, 0.0, 10.0, 10.0));
+        assert_eq!(square.segments().len(), 4);
+        // Straight segments are not subdivided; the outline returns to its start
+        let outline = square.flatten(0.25);
+        assert_eq!(outline.len(), 5);
+        assert_eq!(outline.first(), outline.last());
+
+        let circle = Subpath::ellipse(Point::new(20.0, 20.0), 10.0, 10.0);
+        let points = circle.flatten(0.1);
+        assert!(points.len() > 16);
+        for point in &points {
+            let radius = point.distance_to(&Point::new(20.0, 20.0));
+            assert!((radius - 10.0).abs() < 0.1, "{}", radius);
+        }
+
+        let mut open = Subpath::from_points(&[Point::new(0.0, 0.0), Point::new(5.0, 0.0)], false);
+        assert_eq!(open.segments().len(), 1);
+        open.anchors.truncate(1);
+        assert!(open.segments().is_empty());
+        assert_eq!(open.flatten(0.25), vec![Point::new(0.0, 0.0)]);
+    }
+
+    #[test]
+    fn test_path_even_odd_fill_and_bounds() {
+        let mut path = Path::new("Frame".to_string());
+        assert!(path.is_empty());
+        assert_eq!(path.bounds(), None);
+
+        path.subpaths
+            .push(Subpath::rectangle(Rect::new(0.0, 0.0, 30.0, 30.0)));
+        path.subpaths
+            .push(Subpath::rectangle(Rect::new(10.0, 10.0, 10.0, 10.0)));
+        assert_eq!(path.anchor_count(), 8);
+        assert!(path.contains_point(Point::new(5.0, 5.0)));
+        // The inner square cuts a hole
+        assert!(!path.contains_point(Point::new(15.0, 15.0)));
+        assert!(!path.contains_point(Point::new(35.0, 5.0)));
+        assert_eq!(path.bounds(), Some(Rect::new(0.0, 0.0, 30.0, 30.0)));
+
+        path.translate(5.0, 0.0);
+        assert!(path.contains_point(Point::new(33.0, 5.0)));
+        assert_eq!(
+            PathAnchor::smooth(Point::new(5.0, 5.0), Point::new(8.0, 5.0)).handle_in,
+            Point::new(2.0, 5.0)
+        );
+    }
+}
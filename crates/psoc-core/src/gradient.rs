@@ -5,13 +5,14 @@
 //! - Color stop management
 //! - Gradient interpolation algorithms
 //! - Gradient rendering and application
+//! - Preset management with built-in and user gradients
 
 use crate::color::{HslColor, HsvColor};
 use crate::geometry::{Point, Rect};
 use crate::pixel::RgbaPixel;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 /// Types of gradients supported
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -459,6 +460,9 @@ pub struct GradientManager {
     gradients: BTreeMap<String, Gradient>,
     /// Currently selected gradient
     current_gradient: Option<String>,
+    /// Names of the built-in gradients
+    #[serde(default)]
+    built_in: BTreeSet<String>,
 }
 
 impl GradientManager {
@@ -496,6 +500,8 @@ impl GradientManager {
         self.gradients
             .insert("Transparent to Black".to_string(), transparent);
 
+        self.built_in = self.gradients.keys().cloned().collect();
+
         // Set default current gradient
         self.current_gradient = Some("Black to White".to_string());
     }
@@ -535,6 +541,11 @@ impl GradientManager {
             .and_then(|name| self.gradients.get(name))
     }
 
+    /// Name of the current gradient
+    pub fn current_gradient_name(&self) -> Option<&str> {
+        self.current_gradient.as_deref()
+    }
+
     /// Set current gradient
     pub fn set_current_gradient(&mut self, name: &str) -> bool {
         if self.gradients.contains_key(name) {
@@ -554,12 +565,88 @@ impl GradientManager {
     pub fn count(&self) -> usize {
         self.gradients.len()
     }
+
+    /// Check whether a gradient is one of the built-in presets
+    pub fn is_built_in(&self, name: &str) -> bool {
+        self.built_in.contains(name)
+    }
+
+    /// Gradients added by the user, in name order
+    ///
+    /// These are the gradients that need to be persisted; built-in presets
+    /// are recreated by [`GradientManager::new`].
+    pub fn user_gradients(&self) -> Vec<Gradient> {
+        self.gradients
+            .iter()
+            .filter(|(name, _)| !self.built_in.contains(*name))
+            .map(|(_, gradient)| gradient.clone())
+            .collect()
+    }
+
+    /// Add user gradients, e.g. from an imported preset library
+    ///
+    /// A user gradient with the same name is replaced, while a name taken by a
+    /// built-in preset gets a numeric suffix. Returns the names used, in
+    /// order.
+    pub fn import_gradients(
+        &mut self,
+        gradients: impl IntoIterator<Item = Gradient>,
+    ) -> Vec<String> {
+        let mut names = Vec::new();
+        for mut gradient in gradients {
+            let base = match gradient.name.trim() {
+                "" => "Imported Gradient".to_string(),
+                name => name.to_string(),
+            };
+            let mut name = base.clone();
+            let mut suffix = 2;
+            while self.built_in.contains(&name) || names.contains(&name) {
+                name = format!("{} {}", base, suffix);
+                suffix += 1;
+            }
+
+            gradient.name = name.clone();
+            self.add_gradient(gradient);
+            names.push(name);
+        }
+        names
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_manager_import_keeps_built_ins() {
+        let mut manager = GradientManager::new();
+        assert!(manager.is_built_in("Rainbow"));
+        assert!(manager.user_gradients().is_empty());
+
+        let imported = manager.import_gradients([
+            Gradient::new("Rainbow".to_string(), GradientType::Linear),
+            Gradient::new("Sunset".to_string(), GradientType::Linear),
+            Gradient::new("Sunset".to_string(), GradientType::Radial),
+        ]);
+        assert_eq!(imported, ["Rainbow 2", "Sunset", "Sunset 2"]);
+        assert!(manager.is_built_in("Rainbow"));
+
+        let user: Vec<_> = manager
+            .user_gradients()
+            .into_iter()
+            .map(|gradient| gradient.name)
+            .collect();
+        assert_eq!(user, ["Rainbow 2", "Sunset", "Sunset 2"]);
+
+        // Importing again replaces the user gradient instead of duplicating it
+        manager.import_gradients([Gradient::new("Sunset".to_string(), GradientType::Diamond)]);
+        assert_eq!(manager.user_gradients().len(), 3);
+        assert_eq!(
+            manager.get_gradient("Sunset").unwrap().gradient_type,
+            GradientType::Diamond
+        );
+    }
+
     #[test]
     fn test_color_stop_creation() {
         let stop = ColorStop::new(0.5, RgbaPixel::new(255, 0, 0, 255));
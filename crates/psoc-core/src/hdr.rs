@@ -0,0 +1,252 @@
+//! High dynamic range pixel data and tone mapping
+//!
+//! This module provides:
+//! - Floating-point RGBA pixel storage that is never clamped
+//! - Exposure/gamma and filmic tone mapping to 8-bit display pixels
+
+use crate::pixel::{PixelData, RgbaPixel};
+use anyhow::Result;
+use image::Rgba32FImage;
+use serde::{Deserialize, Serialize};
+
+/// Tone mapping curve used to display HDR data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ToneMapOperator {
+    /// Scale by the exposure and gamma-encode, clipping values above 1.0
+    #[default]
+    ExposureGamma,
+    /// Filmic curve (ACES fit) that rolls off highlights instead of clipping
+    Filmic,
+}
+
+impl std::fmt::Display for ToneMapOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToneMapOperator::ExposureGamma => write!(f, "Exposure/Gamma"),
+            ToneMapOperator::Filmic => write!(f, "Filmic"),
+        }
+    }
+}
+
+/// Tone mapping settings for displaying HDR data
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ToneMapping {
+    /// Tone mapping curve
+    pub operator: ToneMapOperator,
+    /// Exposure adjustment in stops
+    pub exposure: f32,
+    /// Display gamma
+    pub gamma: f32,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        Self {
+            operator: ToneMapOperator::ExposureGamma,
+            exposure: 0.0,
+            gamma: 2.2,
+        }
+    }
+}
+
+impl ToneMapping {
+    /// Create tone mapping settings
+    pub fn new(operator: ToneMapOperator, exposure: f32, gamma: f32) -> Self {
+        Self {
+            operator,
+            exposure,
+            gamma: gamma.max(0.1),
+        }
+    }
+
+    /// Filmic tone mapping with neutral exposure
+    pub fn filmic() -> Self {
+        Self {
+            operator: ToneMapOperator::Filmic,
+            ..Self::default()
+        }
+    }
+
+    /// Set the exposure in stops
+    pub fn with_exposure(mut self, exposure: f32) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    /// Map a linear color channel to an 8-bit display value
+    pub fn map_channel(&self, value: f32) -> u8 {
+        let exposed = value.max(0.0) * self.exposure.exp2();
+        let mapped = match self.operator {
+            ToneMapOperator::ExposureGamma => exposed,
+            ToneMapOperator::Filmic => {
+                let x = exposed;
+                (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+            }
+        };
+        let encoded = mapped.clamp(0.0, 1.0).powf(1.0 / self.gamma.max(0.1));
+        (encoded * 255.0).round() as u8
+    }
+
+    /// Map an HDR pixel (linear RGB, straight alpha) to a display pixel
+    pub fn map_pixel(&self, pixel: [f32; 4]) -> RgbaPixel {
+        RgbaPixel::new(
+            self.map_channel(pixel[0]),
+            self.map_channel(pixel[1]),
+            self.map_channel(pixel[2]),
+            (pixel[3].clamp(0.0, 1.0) * 255.0).round() as u8,
+        )
+    }
+
+    /// Tone map HDR pixel data to 8-bit display pixels
+    pub fn apply(&self, hdr: &HdrPixelData) -> Result<PixelData> {
+        let mut result = PixelData::new_rgba(hdr.width, hdr.height);
+        for y in 0..hdr.height {
+            for x in 0..hdr.width {
+                result.set_pixel(x, y, self.map_pixel(hdr.get_pixel(x, y)))?;
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Decode an 8-bit sRGB channel to linear light
+pub fn srgb_to_linear(value: u8) -> f32 {
+    let v = value as f32 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Floating-point RGBA pixel data in linear light
+///
+/// Values are kept as loaded, so they may exceed 1.0 or be negative.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HdrPixelData {
+    width: u32,
+    height: u32,
+    /// Interleaved RGBA samples, row by row
+    data: Vec<f32>,
+}
+
+impl HdrPixelData {
+    /// Create HDR pixel data from interleaved RGBA samples
+    pub fn new(width: u32, height: u32, data: Vec<f32>) -> Result<Self> {
+        let expected = width as usize * height as usize * 4;
+        if data.len() != expected {
+            return Err(anyhow::anyhow!(
+                "Expected {} RGBA samples for {}x{}, got {}",
+                expected,
+                width,
+                height,
+                data.len()
+            ));
+        }
+        Ok(Self {
+            width,
+            height,
+            data,
+        })
+    }
+
+    /// Create HDR pixel data from a 32-bit float image
+    pub fn from_image(image: &Rgba32FImage) -> Self {
+        Self {
+            width: image.width(),
+            height: image.height(),
+            data: image.as_raw().clone(),
+        }
+    }
+
+    /// Convert to a 32-bit float image
+    pub fn to_image(&self) -> Rgba32FImage {
+        Rgba32FImage::from_raw(self.width, self.height, self.data.clone())
+            .expect("HDR data length matches its dimensions")
+    }
+
+    /// Get dimensions
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Get the RGBA samples of a pixel, or zeros outside the image
+    pub fn get_pixel(&self, x: u32, y: u32) -> [f32; 4] {
+        if x >= self.width || y >= self.height {
+            return [0.0; 4];
+        }
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        [
+            self.data[index],
+            self.data[index + 1],
+            self.data[index + 2],
+            self.data[index + 3],
+        ]
+    }
+
+    /// Largest color sample, useful for choosing an exposure
+    pub fn max_value(&self) -> f32 {
+        self.data
+            .chunks_exact(4)
+            .flat_map(|pixel| &pixel[..3])
+            .copied()
+            .fold(0.0, f32::max)
+    }
+
+    /// Interleaved RGBA samples
+    pub fn as_raw(&self) -> &[f32] {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hdr_data_length_is_checked() {
+        assert!(HdrPixelData::new(2, 2, vec![0.0; 16]).is_ok());
+        assert!(HdrPixelData::new(2, 2, vec![0.0; 15]).is_err());
+    }
+
+    #[test]
+    fn test_values_above_one_are_kept() {
+        let hdr = HdrPixelData::new(1, 1, vec![8.0, 0.5, -1.0, 1.0]).unwrap();
+        assert_eq!(hdr.get_pixel(0, 0), [8.0, 0.5, -1.0, 1.0]);
+        assert_eq!(hdr.max_value(), 8.0);
+        assert_eq!(hdr.to_image().get_pixel(0, 0).0[0], 8.0);
+    }
+
+    #[test]
+    fn test_exposure_gamma_mapping() {
+        let mapping = ToneMapping::default();
+        assert_eq!(mapping.map_channel(0.0), 0);
+        assert_eq!(mapping.map_channel(1.0), 255);
+        assert_eq!(mapping.map_channel(4.0), 255);
+
+        // One stop down brings 2.0 back to white
+        assert_eq!(mapping.with_exposure(-1.0).map_channel(2.0), 255);
+        assert!(mapping.with_exposure(-2.0).map_channel(2.0) < 255);
+    }
+
+    #[test]
+    fn test_filmic_rolls_off_highlights() {
+        let filmic = ToneMapping::filmic();
+        let bright = filmic.map_channel(2.0);
+        let brighter = filmic.map_channel(4.0);
+        assert!(bright < brighter);
+        assert!(brighter < 255);
+        assert!(filmic.map_channel(0.18) < filmic.map_channel(0.5));
+    }
+
+    #[test]
+    fn test_apply_tone_mapping() {
+        let hdr = HdrPixelData::new(2, 1, vec![1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.5]).unwrap();
+        let pixels = ToneMapping::default().apply(&hdr).unwrap();
+        assert_eq!(
+            pixels.get_pixel(0, 0),
+            Some(RgbaPixel::new(255, 255, 255, 255))
+        );
+        assert_eq!(pixels.get_pixel(1, 0), Some(RgbaPixel::new(0, 0, 0, 128)));
+    }
+}
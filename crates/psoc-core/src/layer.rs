@@ -4,11 +4,15 @@
 //! layer types, blend modes, and layer operations.
 
 use crate::geometry::{Point, Rect, Size, Transform};
+use crate::hdr::{srgb_to_linear, HdrPixelData, ToneMapping};
 use crate::pixel::{PixelData, RgbaPixel};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 // use std::collections::HashMap; // Commented out - not currently used
+use std::borrow::Cow;
+use std::fmt;
 use std::path::PathBuf;
+use std::sync::Arc;
 use uuid::Uuid;
 
 /// Layer blend modes
@@ -445,6 +449,13 @@ pub enum SmartObjectContentType {
         /// Serialized document data
         document_data: Vec<u8>,
     },
+    /// Embedded SVG vector graphic, rasterized at the current scale
+    EmbeddedSvg {
+        /// Original file path (for reference)
+        original_path: Option<PathBuf>,
+        /// SVG source data
+        svg_data: Vec<u8>,
+    },
 }
 
 /// Smart transformation parameters for non-destructive editing
@@ -534,6 +545,38 @@ impl Default for LayerType {
     }
 }
 
+/// Non-rendering information attached to a layer
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayerMetadata {
+    /// How long the layer is shown when used as an animation frame, in milliseconds
+    pub frame_delay_ms: Option<u32>,
+}
+
+/// Loader for layer pixels that are decoded the first time the layer is used
+///
+/// The loader returns the layer as it was stored; only its pixel data, mask
+/// and HDR source are taken over.
+#[derive(Clone)]
+pub struct PendingPixels(Arc<dyn Fn() -> Result<Layer> + Send + Sync>);
+
+impl PendingPixels {
+    /// Create a loader from a function decoding the stored layer
+    pub fn new(load: impl Fn() -> Result<Layer> + Send + Sync + 'static) -> Self {
+        Self(Arc::new(load))
+    }
+
+    /// Decode the stored layer
+    pub fn load(&self) -> Result<Layer> {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for PendingPixels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PendingPixels(..)")
+    }
+}
+
 /// Layer data structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Layer {
@@ -561,6 +604,25 @@ pub struct Layer {
     pub locked: bool,
     /// Layer mask (optional)
     pub mask: Option<PixelData>,
+    /// Additional layer metadata
+    #[serde(default)]
+    pub metadata: LayerMetadata,
+    /// Floating-point source pixels for layers loaded from HDR images
+    ///
+    /// `pixel_data` then holds a tone-mapped 8-bit version for editing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hdr_data: Option<HdrPixelData>,
+    /// Tone-mapped pixels that `hdr_data` was last in sync with
+    ///
+    /// Pixels of `pixel_data` that differ from these have been edited since,
+    /// and take precedence over the HDR source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hdr_base: Option<PixelData>,
+    /// Pixels still waiting to be decoded from the file the layer was opened from
+    ///
+    /// While this is set, `pixel_data`, `mask` and the HDR fields are empty.
+    #[serde(skip)]
+    pub pending_pixels: Option<PendingPixels>,
 }
 
 impl Layer {
@@ -583,6 +645,10 @@ impl Layer {
             bounds,
             locked: false,
             mask: None,
+            metadata: LayerMetadata::default(),
+            hdr_data: None,
+            hdr_base: None,
+            pending_pixels: None,
         }
     }
 
@@ -615,6 +681,10 @@ impl Layer {
             bounds: Rect::new(position.x, position.y, 100.0, font_size), // Placeholder bounds
             locked: false,
             mask: None,
+            metadata: LayerMetadata::default(),
+            hdr_data: None,
+            hdr_base: None,
+            pending_pixels: None,
         }
     }
 
@@ -642,6 +712,10 @@ impl Layer {
             bounds: Rect::new(0.0, 0.0, 0.0, 0.0), // Adjustment layers have no bounds
             locked: false,
             mask: None,
+            metadata: LayerMetadata::default(),
+            hdr_data: None,
+            hdr_base: None,
+            pending_pixels: None,
         }
     }
 
@@ -678,6 +752,10 @@ impl Layer {
             bounds,
             locked: false,
             mask: None,
+            metadata: LayerMetadata::default(),
+            hdr_data: None,
+            hdr_base: None,
+            pending_pixels: None,
         }
     }
 
@@ -771,6 +849,130 @@ impl Layer {
         self.visible && self.effective_opacity() > 0.0
     }
 
+    /// Check if layer carries floating-point HDR pixels
+    ///
+    /// HDR data no longer matching the layer size (after a resize or crop)
+    /// is ignored.
+    pub fn is_hdr(&self) -> bool {
+        self.hdr_sources().is_some()
+    }
+
+    /// HDR source, the pixels it was in sync with and the current pixels
+    fn hdr_sources(&self) -> Option<(&HdrPixelData, &PixelData, &PixelData)> {
+        let hdr = self.hdr_data.as_ref()?;
+        let base = self.hdr_base.as_ref()?;
+        let pixels = self.pixel_data.as_ref()?;
+        let size = hdr.dimensions();
+        (base.dimensions() == size && pixels.dimensions() == size).then_some((hdr, base, pixels))
+    }
+
+    /// Attach HDR source pixels that the current 8-bit pixels were mapped from
+    pub fn attach_hdr(&mut self, hdr_data: HdrPixelData) {
+        self.hdr_base = self.pixel_data.clone();
+        self.hdr_data = Some(hdr_data);
+    }
+
+    /// Linear RGBA value of a pixel of an HDR layer
+    ///
+    /// Pixels edited since the layer was tone-mapped are written through from
+    /// their 8-bit values; the rest keep their HDR samples.
+    pub fn hdr_pixel(&self, x: u32, y: u32) -> Option<[f32; 4]> {
+        let (hdr, base, pixels) = self.hdr_sources()?;
+        let current = pixels.get_pixel(x, y)?;
+        if base.get_pixel(x, y) == Some(current) {
+            return Some(hdr.get_pixel(x, y));
+        }
+        Some([
+            srgb_to_linear(current.r),
+            srgb_to_linear(current.g),
+            srgb_to_linear(current.b),
+            current.a as f32 / 255.0,
+        ])
+    }
+
+    /// HDR pixels of the layer with 8-bit edits written through
+    pub fn hdr_pixels(&self) -> Option<HdrPixelData> {
+        let (width, height) = self.hdr_sources()?.0.dimensions();
+        let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height {
+            for x in 0..width {
+                data.extend(self.hdr_pixel(x, y)?);
+            }
+        }
+        HdrPixelData::new(width, height, data).ok()
+    }
+
+    /// Display pixels of an HDR layer given its tone-mapped source
+    ///
+    /// `mapped` is borrowed as-is unless some pixels were edited since the
+    /// layer was tone-mapped, in which case those are copied over it.
+    pub fn hdr_display<'a>(&self, mapped: &'a PixelData) -> Option<Cow<'a, PixelData>> {
+        let (hdr, base, pixels) = self.hdr_sources()?;
+        if mapped.dimensions() != hdr.dimensions() {
+            return None;
+        }
+
+        let (width, height) = hdr.dimensions();
+        let mut display = Cow::Borrowed(mapped);
+        for y in 0..height {
+            for x in 0..width {
+                let Some(current) = pixels.get_pixel(x, y) else {
+                    continue;
+                };
+                if base.get_pixel(x, y) != Some(current) {
+                    display.to_mut().set_pixel(x, y, current).ok()?;
+                }
+            }
+        }
+        Some(display)
+    }
+
+    /// Create a pixel layer from HDR data, tone-mapped for editing
+    pub fn from_hdr(
+        name: String,
+        hdr_data: HdrPixelData,
+        tone_mapping: &ToneMapping,
+    ) -> Result<Self> {
+        let (width, height) = hdr_data.dimensions();
+        let mut layer = Self::new_pixel(name, width, height);
+        layer.pixel_data = Some(tone_mapping.apply(&hdr_data)?);
+        layer.attach_hdr(hdr_data);
+        Ok(layer)
+    }
+
+    /// Check if the layer's pixels are still waiting to be decoded
+    pub fn is_pending(&self) -> bool {
+        self.pending_pixels.is_some()
+    }
+
+    /// Decode pending pixels, mask and HDR source into the layer
+    ///
+    /// Returns whether anything was decoded. On failure the layer stays pending.
+    pub fn load_pending(&mut self) -> Result<bool> {
+        let Some(pending) = &self.pending_pixels else {
+            return Ok(false);
+        };
+
+        let stored = pending.load()?;
+        self.pixel_data = stored.pixel_data;
+        self.mask = stored.mask;
+        self.hdr_data = stored.hdr_data;
+        self.hdr_base = stored.hdr_base;
+        self.pending_pixels = None;
+        Ok(true)
+    }
+
+    /// The layer with its pixels decoded, without keeping the decoded copy
+    pub fn loaded(&self) -> Result<Cow<'_, Layer>> {
+        if !self.is_pending() {
+            return Ok(Cow::Borrowed(self));
+        }
+
+        let mut layer = self.clone();
+        layer.load_pending()?;
+        Ok(Cow::Owned(layer))
+    }
+
     /// Check if layer has a mask
     pub fn has_mask(&self) -> bool {
         self.mask.is_some()
@@ -1318,4 +1520,21 @@ mod tests {
         assert_eq!(masked_pixel.b, 0);
         assert!(masked_pixel.a < 255); // Alpha reduced by mask
     }
+
+    #[test]
+    fn test_hdr_layer_survives_serde_roundtrip() {
+        let hdr = HdrPixelData::new(2, 1, vec![4.0, 1.0, 0.25, 1.0, 0.0, 0.0, 0.0, 1.0]).unwrap();
+        let mut layer =
+            Layer::from_hdr("Render".to_string(), hdr, &ToneMapping::default()).unwrap();
+        let painted = RgbaPixel::new(10, 200, 30, 255);
+        layer.set_pixel(1, 0, painted).unwrap();
+
+        let json = serde_json::to_string(&layer).unwrap();
+        let restored: Layer = serde_json::from_str(&json).unwrap();
+
+        assert!(restored.is_hdr());
+        assert_eq!(restored.hdr_pixel(0, 0), Some([4.0, 1.0, 0.25, 1.0]));
+        assert_eq!(restored.hdr_pixel(1, 0), layer.hdr_pixel(1, 0));
+        assert_eq!(restored.get_pixel(1, 0), Some(painted));
+    }
 }
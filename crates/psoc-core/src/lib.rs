@@ -6,15 +6,22 @@
 
 pub mod adjustment;
 pub mod adjustments;
+pub mod asset;
+pub mod channel;
 pub mod color;
+pub mod color_range;
 pub mod command;
 pub mod document;
 pub mod geometry;
 pub mod gradient;
+pub mod hdr;
 pub mod icc;
 pub mod layer;
+pub mod live_wire;
+pub mod magic_wand;
 pub mod math;
 pub mod pixel;
+pub mod refine_edge;
 pub mod rendering;
 pub mod selection;
 pub mod smart_object;
@@ -22,15 +29,22 @@ pub mod smart_object;
 // Re-export commonly used types
 pub use adjustment::*;
 pub use adjustments::*;
+pub use asset::*;
+pub use channel::*;
 pub use color::{ColorAdjustment, ColorConverter, HslColor, HsvColor};
+pub use color_range::*;
 pub use command::*;
 pub use document::*;
 pub use geometry::*;
 pub use gradient::*;
+pub use hdr::*;
 pub use icc::{CmsConfig, ColorManager, IccProfile, RenderingIntent};
 pub use layer::*;
+pub use live_wire::*;
+pub use magic_wand::*;
 pub use math::*;
 pub use pixel::*;
+pub use refine_edge::*;
 pub use rendering::*;
 pub use selection::*;
 pub use smart_object::*;
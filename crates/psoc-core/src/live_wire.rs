@@ -0,0 +1,351 @@
+//! Edge detection and live-wire path search for the magnetic lasso
+//!
+//! An [`EdgeMap`] holds the normalized gradient magnitude of an image. The
+//! live-wire search finds the cheapest 8-connected pixel path between two
+//! points, where stepping onto a strong edge is cheap, so the path hugs the
+//! edges between them.
+
+use crate::geometry::Point;
+use crate::pixel::PixelData;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Extra pixels searched around the bounding box of a live-wire segment
+pub const LIVE_WIRE_MARGIN: u32 = 24;
+
+/// Cost of stepping onto a pixel with no edge at all, relative to a perfect edge
+const NO_EDGE_COST: f32 = 1.0;
+/// Cost of stepping onto the strongest edge; kept above zero so paths stay short
+const EDGE_COST: f32 = 0.02;
+
+/// Normalized gradient magnitude of an image
+#[derive(Clone)]
+pub struct EdgeMap {
+    width: u32,
+    height: u32,
+    /// Gradient magnitude per pixel, 0.0 (flat) to 1.0 (strongest edge in the image)
+    magnitude: Vec<f32>,
+}
+
+impl std::fmt::Debug for EdgeMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EdgeMap")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EdgeMap {
+    /// Compute the Sobel gradient magnitude of an image's luminance
+    ///
+    /// Transparent pixels count as black, so layer edges are edges too.
+    pub fn from_pixels(pixels: &PixelData) -> Self {
+        let (width, height) = pixels.dimensions();
+        let luma: Vec<f32> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                pixels.get_pixel(x, y).map_or(0.0, |p| {
+                    let alpha = p.a as f32 / 255.0;
+                    (0.299 * p.r as f32 + 0.587 * p.g as f32 + 0.114 * p.b as f32) * alpha
+                })
+            })
+            .collect();
+
+        let sample = |x: i64, y: i64| -> f32 {
+            let x = x.clamp(0, width as i64 - 1) as u32;
+            let y = y.clamp(0, height as i64 - 1) as u32;
+            luma[(y * width + x) as usize]
+        };
+
+        let mut magnitude = Vec::with_capacity(luma.len());
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                let gx = sample(x + 1, y - 1) + 2.0 * sample(x + 1, y) + sample(x + 1, y + 1)
+                    - sample(x - 1, y - 1)
+                    - 2.0 * sample(x - 1, y)
+                    - sample(x - 1, y + 1);
+                let gy = sample(x - 1, y + 1) + 2.0 * sample(x, y + 1) + sample(x + 1, y + 1)
+                    - sample(x - 1, y - 1)
+                    - 2.0 * sample(x, y - 1)
+                    - sample(x + 1, y - 1);
+                magnitude.push((gx * gx + gy * gy).sqrt());
+            }
+        }
+
+        let max = magnitude.iter().copied().fold(0.0f32, f32::max);
+        if max > 0.0 {
+            magnitude.iter_mut().for_each(|m| *m /= max);
+        }
+
+        Self {
+            width,
+            height,
+            magnitude,
+        }
+    }
+
+    /// Edge map dimensions
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Normalized gradient magnitude at a pixel, 0.0 outside the map
+    pub fn magnitude(&self, x: u32, y: u32) -> f32 {
+        if x >= self.width || y >= self.height {
+            return 0.0;
+        }
+        self.magnitude[(y * self.width + x) as usize]
+    }
+
+    /// Move a point onto the strongest edge within `radius` pixels
+    ///
+    /// Edges weaker than `contrast` are ignored; if none is strong enough the
+    /// point is only clamped to the image. Ties go to the nearest pixel.
+    pub fn snap(&self, point: Point, radius: u32, contrast: f32) -> Point {
+        let Some((cx, cy)) = self.pixel_of(point) else {
+            return point;
+        };
+
+        let radius = radius as i64;
+        let mut best: Option<(f32, i64, u32, u32)> = None;
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let distance = dx * dx + dy * dy;
+                if distance > radius * radius {
+                    continue;
+                }
+                let (x, y) = (cx as i64 + dx, cy as i64 + dy);
+                if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+                    continue;
+                }
+                let magnitude = self.magnitude(x as u32, y as u32);
+                if magnitude < contrast {
+                    continue;
+                }
+                let better = match best {
+                    None => true,
+                    Some((m, d, _, _)) => magnitude > m || (magnitude == m && distance < d),
+                };
+                if better {
+                    best = Some((magnitude, distance, x as u32, y as u32));
+                }
+            }
+        }
+
+        let (x, y) = best.map_or((cx, cy), |(_, _, x, y)| (x, y));
+        pixel_center(x, y)
+    }
+
+    /// Cheapest 8-connected path from `from` to `to` that follows strong edges
+    ///
+    /// Edges weaker than `contrast` cost as much as flat areas. The search is
+    /// limited to the segment's bounding box grown by [`LIVE_WIRE_MARGIN`].
+    /// The returned points are pixel centers and include both ends.
+    pub fn shortest_path(&self, from: Point, to: Point, contrast: f32) -> Vec<Point> {
+        let (Some(start), Some(goal)) = (self.pixel_of(from), self.pixel_of(to)) else {
+            return vec![from, to];
+        };
+
+        // Search window
+        let min_x = start.0.min(goal.0).saturating_sub(LIVE_WIRE_MARGIN);
+        let min_y = start.1.min(goal.1).saturating_sub(LIVE_WIRE_MARGIN);
+        let max_x = (start.0.max(goal.0) + LIVE_WIRE_MARGIN).min(self.width - 1);
+        let max_y = (start.1.max(goal.1) + LIVE_WIRE_MARGIN).min(self.height - 1);
+        let window_width = (max_x - min_x + 1) as usize;
+        let window_height = (max_y - min_y + 1) as usize;
+        let index = |x: u32, y: u32| (y - min_y) as usize * window_width + (x - min_x) as usize;
+
+        let mut cost = vec![f32::INFINITY; window_width * window_height];
+        let mut previous = vec![usize::MAX; window_width * window_height];
+        let mut queue = BinaryHeap::new();
+
+        cost[index(start.0, start.1)] = 0.0;
+        queue.push(Frontier {
+            cost: 0.0,
+            x: start.0,
+            y: start.1,
+        });
+
+        while let Some(Frontier {
+            cost: current,
+            x,
+            y,
+        }) = queue.pop()
+        {
+            if (x, y) == goal {
+                break;
+            }
+            if current > cost[index(x, y)] {
+                continue;
+            }
+
+            for (dx, dy) in NEIGHBOURS {
+                let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+                if nx < min_x as i64 || ny < min_y as i64 || nx > max_x as i64 || ny > max_y as i64
+                {
+                    continue;
+                }
+                let (nx, ny) = (nx as u32, ny as u32);
+                let step = if dx != 0 && dy != 0 {
+                    std::f32::consts::SQRT_2
+                } else {
+                    1.0
+                };
+                let next = current + step * self.step_cost(nx, ny, contrast);
+                let neighbour = index(nx, ny);
+                if next < cost[neighbour] {
+                    cost[neighbour] = next;
+                    previous[neighbour] = index(x, y);
+                    queue.push(Frontier {
+                        cost: next,
+                        x: nx,
+                        y: ny,
+                    });
+                }
+            }
+        }
+
+        // Walk back from the goal
+        let mut path = Vec::new();
+        let mut current = index(goal.0, goal.1);
+        let start_index = index(start.0, start.1);
+        loop {
+            let x = (current % window_width) as u32 + min_x;
+            let y = (current / window_width) as u32 + min_y;
+            path.push(pixel_center(x, y));
+            if current == start_index || previous[current] == usize::MAX {
+                break;
+            }
+            current = previous[current];
+        }
+        path.reverse();
+        path
+    }
+
+    fn step_cost(&self, x: u32, y: u32, contrast: f32) -> f32 {
+        let magnitude = self.magnitude(x, y);
+        let magnitude = if magnitude < contrast { 0.0 } else { magnitude };
+        NO_EDGE_COST - (NO_EDGE_COST - EDGE_COST) * magnitude
+    }
+
+    fn pixel_of(&self, point: Point) -> Option<(u32, u32)> {
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+        let x = point.x.floor().clamp(0.0, (self.width - 1) as f32) as u32;
+        let y = point.y.floor().clamp(0.0, (self.height - 1) as f32) as u32;
+        Some((x, y))
+    }
+}
+
+const NEIGHBOURS: [(i64, i64); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+fn pixel_center(x: u32, y: u32) -> Point {
+    Point::new(x as f32 + 0.5, y as f32 + 0.5)
+}
+
+/// Priority queue entry ordered so the cheapest pixel pops first
+struct Frontier {
+    cost: f32,
+    x: u32,
+    y: u32,
+}
+
+impl PartialEq for Frontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Frontier {}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixel::RgbaPixel;
+
+    /// Black image with a white square from (10, 10) to (29, 29)
+    fn square_image() -> PixelData {
+        let mut pixels = PixelData::new_rgba(40, 40);
+        pixels.fill(RgbaPixel::black());
+        for y in 10..30 {
+            for x in 10..30 {
+                pixels.set_pixel(x, y, RgbaPixel::white()).unwrap();
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn test_edge_map_finds_square_outline() {
+        let edges = EdgeMap::from_pixels(&square_image());
+        assert_eq!(edges.dimensions(), (40, 40));
+        assert!(edges.magnitude(10, 20) > 0.5);
+        assert!(edges.magnitude(29, 20) > 0.5);
+        assert_eq!(edges.magnitude(20, 20), 0.0);
+        assert_eq!(edges.magnitude(2, 2), 0.0);
+    }
+
+    #[test]
+    fn test_snap_moves_to_nearest_strong_edge() {
+        let edges = EdgeMap::from_pixels(&square_image());
+        let snapped = edges.snap(Point::new(5.5, 20.5), 6, 0.1);
+        assert!((9.0..=11.0).contains(&snapped.x));
+        assert_eq!(snapped.y, 20.5);
+
+        // Nothing within reach leaves the point where it was
+        let far = edges.snap(Point::new(2.5, 2.5), 3, 0.1);
+        assert_eq!(far, Point::new(2.5, 2.5));
+    }
+
+    #[test]
+    fn test_shortest_path_follows_edges() {
+        let edges = EdgeMap::from_pixels(&square_image());
+        // Two corners of the square: the straight diagonal crosses flat areas,
+        // the cheap route runs along the outline
+        let from = Point::new(10.5, 10.5);
+        let to = Point::new(29.5, 29.5);
+        let path = edges.shortest_path(from, to, 0.1);
+
+        assert_eq!(path.first(), Some(&from));
+        assert_eq!(path.last(), Some(&to));
+        let on_edge = path
+            .iter()
+            .filter(|p| edges.magnitude(p.x as u32, p.y as u32) > 0.3)
+            .count();
+        assert!(
+            on_edge * 10 >= path.len() * 9,
+            "{} of {}",
+            on_edge,
+            path.len()
+        );
+
+        // Consecutive points are 8-connected neighbours
+        for pair in path.windows(2) {
+            assert!((pair[0].x - pair[1].x).abs() <= 1.0);
+            assert!((pair[0].y - pair[1].y).abs() <= 1.0);
+        }
+    }
+}
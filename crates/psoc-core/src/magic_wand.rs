@@ -0,0 +1,367 @@
+//! Magic Wand selection
+//!
+//! Selects pixels similar to the one that was clicked. Similarity is measured
+//! with a [`ToleranceMetric`] - full color, brightness only or hue only - and
+//! a pixel matches when its distance from the seed color is within the
+//! tolerance. Contiguous selections grow from the seed with a scanline flood
+//! fill, which visits each row span once instead of pushing every pixel, so
+//! it stays fast and bounded in memory on very large images. With
+//! anti-aliasing the hard edge of the region is given fractional coverage.
+
+use crate::pixel::{PixelData, RgbaPixel};
+use crate::selection::MaskSelection;
+use serde::{Deserialize, Serialize};
+
+/// Saturation below which a color has no meaningful hue
+const ACHROMATIC_SATURATION: f32 = 0.05;
+
+/// How the distance between two colors is measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ToleranceMetric {
+    /// Euclidean distance over red, green, blue and alpha
+    #[default]
+    Rgb,
+    /// Difference in luminance
+    Luminance,
+    /// Difference in hue around the color wheel, scaled to 0-255
+    Hue,
+}
+
+impl ToleranceMetric {
+    /// All metrics in display order
+    pub const ALL: [ToleranceMetric; 3] = [
+        ToleranceMetric::Rgb,
+        ToleranceMetric::Luminance,
+        ToleranceMetric::Hue,
+    ];
+
+    /// Distance between two colors, on a 0-255 scale
+    ///
+    /// Luminance and hue also count the difference in alpha, so transparent
+    /// areas never match opaque ones. Gray colors have no hue and only match
+    /// other grays.
+    pub fn distance(&self, a: RgbaPixel, b: RgbaPixel) -> f32 {
+        let da = (a.a as f32 - b.a as f32).abs();
+        match self {
+            ToleranceMetric::Rgb => {
+                let dr = a.r as f32 - b.r as f32;
+                let dg = a.g as f32 - b.g as f32;
+                let db = a.b as f32 - b.b as f32;
+                (dr * dr + dg * dg + db * db + da * da).sqrt()
+            }
+            ToleranceMetric::Luminance => (luma(a) - luma(b)).abs().max(da),
+            ToleranceMetric::Hue => {
+                let difference = match (hue(a), hue(b)) {
+                    (Some(ha), Some(hb)) => {
+                        let degrees = (ha - hb).abs();
+                        degrees.min(360.0 - degrees) / 180.0 * 255.0
+                    }
+                    (None, None) => 0.0,
+                    _ => 255.0,
+                };
+                difference.max(da)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ToleranceMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToleranceMetric::Rgb => write!(f, "RGB"),
+            ToleranceMetric::Luminance => write!(f, "Luminance"),
+            ToleranceMetric::Hue => write!(f, "Hue"),
+        }
+    }
+}
+
+/// Magic Wand selection parameters
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MagicWand {
+    /// Largest distance (0-255) from the seed color that still matches
+    pub tolerance: f32,
+    /// How color distance is measured
+    pub metric: ToleranceMetric,
+    /// Only select pixels connected to the seed
+    pub contiguous: bool,
+    /// Give the region's edge fractional coverage
+    pub anti_alias: bool,
+}
+
+impl Default for MagicWand {
+    fn default() -> Self {
+        Self::new(32.0)
+    }
+}
+
+impl MagicWand {
+    /// Maximum tolerance value
+    pub const MAX_TOLERANCE: f32 = 255.0;
+
+    /// Create a contiguous, anti-aliased RGB magic wand with a tolerance
+    pub fn new(tolerance: f32) -> Self {
+        Self {
+            tolerance: tolerance.clamp(0.0, Self::MAX_TOLERANCE),
+            metric: ToleranceMetric::Rgb,
+            contiguous: true,
+            anti_alias: true,
+        }
+    }
+
+    /// Set the tolerance metric
+    pub fn with_metric(mut self, metric: ToleranceMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Set whether only connected pixels are selected
+    pub fn with_contiguous(mut self, contiguous: bool) -> Self {
+        self.contiguous = contiguous;
+        self
+    }
+
+    /// Set whether the edge is anti-aliased
+    pub fn with_anti_alias(mut self, anti_alias: bool) -> Self {
+        self.anti_alias = anti_alias;
+        self
+    }
+
+    /// Whether a color is within tolerance of the seed color
+    pub fn matches(&self, color: RgbaPixel, seed: RgbaPixel) -> bool {
+        self.metric.distance(color, seed) <= self.tolerance
+    }
+
+    /// Select the pixels similar to the one at `(x, y)`
+    ///
+    /// Returns `None` when the seed lies outside the image.
+    pub fn select(&self, pixels: &PixelData, x: u32, y: u32) -> Option<MaskSelection> {
+        let (width, height) = pixels.dimensions();
+        let seed = pixels.get_pixel(x, y)?;
+        let matches = |x: u32, y: u32| {
+            pixels
+                .get_pixel(x, y)
+                .is_some_and(|pixel| self.matches(pixel, seed))
+        };
+
+        let mut mask = vec![0u8; width as usize * height as usize];
+        if self.contiguous {
+            scanline_fill(&mut mask, width, height, x, y, matches);
+        } else {
+            for (index, value) in mask.iter_mut().enumerate() {
+                let (x, y) = (index as u32 % width, index as u32 / width);
+                if matches(x, y) {
+                    *value = 255;
+                }
+            }
+        }
+
+        if self.anti_alias {
+            soften_edges(&mut mask, width, height);
+        }
+        Some(MaskSelection::new(width, height, mask))
+    }
+}
+
+/// Flood fill matching pixels connected to `(x, y)`, one row span at a time
+fn scanline_fill(
+    mask: &mut [u8],
+    width: u32,
+    height: u32,
+    x: u32,
+    y: u32,
+    matches: impl Fn(u32, u32) -> bool,
+) {
+    let index = |x: u32, y: u32| y as usize * width as usize + x as usize;
+    let mut seeds = vec![(x, y)];
+
+    while let Some((x, y)) = seeds.pop() {
+        if mask[index(x, y)] != 0 || !matches(x, y) {
+            continue;
+        }
+
+        // Widen to the full matching span on this row
+        let mut left = x;
+        while left > 0 && mask[index(left - 1, y)] == 0 && matches(left - 1, y) {
+            left -= 1;
+        }
+        let mut right = x;
+        while right + 1 < width && mask[index(right + 1, y)] == 0 && matches(right + 1, y) {
+            right += 1;
+        }
+        mask[index(left, y)..=index(right, y)].fill(255);
+
+        // Seed each run of matching pixels on the rows above and below
+        for row in [y.checked_sub(1), Some(y + 1).filter(|&row| row < height)]
+            .into_iter()
+            .flatten()
+        {
+            let mut in_run = false;
+            for x in left..=right {
+                let open = mask[index(x, row)] == 0 && matches(x, row);
+                if open && !in_run {
+                    seeds.push((x, row));
+                }
+                in_run = open;
+            }
+        }
+    }
+}
+
+/// Replace each pixel by the mean of its 3x3 neighborhood
+///
+/// The inside and outside of the region are unchanged; only pixels along
+/// its edge end up partially selected.
+fn soften_edges(mask: &mut [u8], width: u32, height: u32) {
+    let (width, height) = (width as usize, height as usize);
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    // Horizontal sums of three, with the edge pixel repeated past the border
+    let mut rows = vec![0u16; mask.len()];
+    for y in 0..height {
+        let row = &mask[y * width..(y + 1) * width];
+        for x in 0..width {
+            rows[y * width + x] = row[x.saturating_sub(1)] as u16
+                + row[x] as u16
+                + row[(x + 1).min(width - 1)] as u16;
+        }
+    }
+
+    for y in 0..height {
+        let above = y.saturating_sub(1) * width;
+        let below = (y + 1).min(height - 1) * width;
+        for x in 0..width {
+            let sum = rows[above + x] as u32 + rows[y * width + x] as u32 + rows[below + x] as u32;
+            mask[y * width + x] = ((sum + 4) / 9) as u8;
+        }
+    }
+}
+
+/// Rec. 601 luma of a color (0-255)
+fn luma(pixel: RgbaPixel) -> f32 {
+    0.299 * pixel.r as f32 + 0.587 * pixel.g as f32 + 0.114 * pixel.b as f32
+}
+
+/// Hue in degrees, or `None` for grays
+fn hue(pixel: RgbaPixel) -> Option<f32> {
+    let hsl = crate::color::HslColor::from_rgba(pixel);
+    (hsl.s > ACHROMATIC_SATURATION).then_some(hsl.h)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 6x3 image: a red run on the left, a blue wall in column 3, red again on the right
+    fn striped_image() -> PixelData {
+        let mut pixels = PixelData::new_rgba(6, 3);
+        for y in 0..3 {
+            for x in 0..6 {
+                let color = if x == 3 {
+                    RgbaPixel::rgb(0, 0, 255)
+                } else {
+                    RgbaPixel::rgb(200, 0, 0)
+                };
+                pixels.set_pixel(x, y, color).unwrap();
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn test_contiguous_and_global_selection() {
+        let pixels = striped_image();
+        let wand = MagicWand::new(10.0).with_anti_alias(false);
+
+        let mask = wand.select(&pixels, 0, 1).unwrap();
+        assert_eq!(mask.get_pixel(2, 2), 255);
+        assert_eq!(mask.get_pixel(3, 0), 0);
+        assert_eq!(mask.get_pixel(5, 1), 0);
+
+        let mask = wand.with_contiguous(false).select(&pixels, 0, 1).unwrap();
+        assert_eq!(mask.get_pixel(5, 1), 255);
+        assert_eq!(mask.get_pixel(3, 1), 0);
+
+        assert!(wand.select(&pixels, 6, 0).is_none());
+    }
+
+    #[test]
+    fn test_scanline_fill_follows_winding_region() {
+        // A serpentine corridor that forces the fill up, down and back again
+        let mut pixels = PixelData::new_rgba(7, 5);
+        pixels.fill(RgbaPixel::black());
+        let corridor = [
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (1, 4),
+            (2, 4),
+            (2, 3),
+            (2, 2),
+            (2, 1),
+            (2, 0),
+            (3, 0),
+            (4, 0),
+            (4, 1),
+            (4, 2),
+            (4, 3),
+            (4, 4),
+            (5, 4),
+            (6, 4),
+        ];
+        for &(x, y) in &corridor {
+            pixels.set_pixel(x, y, RgbaPixel::white()).unwrap();
+        }
+
+        let mask = MagicWand::new(0.0)
+            .with_anti_alias(false)
+            .select(&pixels, 6, 4)
+            .unwrap();
+        let selected = (0..5)
+            .flat_map(|y| (0..7).map(move |x| (x, y)))
+            .filter(|&(x, y)| mask.get_pixel(x, y) == 255)
+            .count();
+        assert_eq!(selected, corridor.len());
+        assert_eq!(mask.get_pixel(1, 0), 0);
+    }
+
+    #[test]
+    fn test_tolerance_metrics() {
+        let dark_red = RgbaPixel::rgb(100, 0, 0);
+        let bright_red = RgbaPixel::rgb(255, 40, 40);
+        let green = RgbaPixel::rgb(0, 60, 0);
+
+        // Same hue at a different brightness matches only by hue
+        assert!(ToleranceMetric::Hue.distance(dark_red, bright_red) < 5.0);
+        assert!(ToleranceMetric::Rgb.distance(dark_red, bright_red) > 100.0);
+        // Similar brightness but different hue matches only by luminance
+        assert!(ToleranceMetric::Luminance.distance(dark_red, green) < 6.0);
+        assert!(ToleranceMetric::Hue.distance(dark_red, green) > 150.0);
+        // Grays have no hue and only match each other
+        let gray = RgbaPixel::rgb(128, 128, 128);
+        assert_eq!(ToleranceMetric::Hue.distance(gray, RgbaPixel::white()), 0.0);
+        assert_eq!(ToleranceMetric::Hue.distance(gray, dark_red), 255.0);
+        // Alpha always counts
+        let clear_red = RgbaPixel::new(100, 0, 0, 0);
+        assert_eq!(
+            ToleranceMetric::Luminance.distance(dark_red, clear_red),
+            255.0
+        );
+    }
+
+    #[test]
+    fn test_anti_alias_softens_only_the_edge() {
+        let pixels = striped_image();
+        let mask = MagicWand::new(10.0).select(&pixels, 0, 1).unwrap();
+
+        assert_eq!(mask.get_pixel(0, 1), 255);
+        let inner_edge = mask.get_pixel(2, 1);
+        let outer_edge = mask.get_pixel(3, 1);
+        assert!(inner_edge > 0 && inner_edge < 255);
+        assert!(outer_edge > 0 && outer_edge < inner_edge);
+        assert_eq!(mask.get_pixel(5, 1), 0);
+    }
+}
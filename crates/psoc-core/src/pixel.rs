@@ -135,7 +135,7 @@ impl From<RgbaPixel> for Rgba<u8> {
 }
 
 /// Pixel data storage format
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PixelData {
     /// RGBA data stored as a 3D array (height, width, channels)
     Rgba(Array3<Channel>),
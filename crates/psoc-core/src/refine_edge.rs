@@ -0,0 +1,485 @@
+//! Refine Edge: matting soft and detailed selection edges
+//!
+//! Selections drawn around hair, fur or blurred edges are either too hard or
+//! in the wrong place, which leaves a halo of background around the subject.
+//! Refine Edge treats a band of pixels around the selection boundary as
+//! unknown and recomputes their alpha from the image with a color guided
+//! filter (He, Sun and Tang). The filter fits the local linear model
+//! `alpha = a · color + b` in every window, the same model closed-form matting
+//! is built on, so the refined edge follows the image instead of the outline.
+//!
+//! Decontaminating replaces the colors of partly selected pixels with an
+//! estimate of the pure foreground color, removing the background fringe when
+//! the result is output to a layer.
+
+use crate::layer::Layer;
+use crate::pixel::{PixelData, RgbaPixel};
+use crate::selection::{distance_field, MaskSelection, Selection};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Parameters for refining a selection edge
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RefineEdge {
+    /// Distance from the selection edge, in pixels, within which alpha is recomputed
+    pub radius: u32,
+    /// Smoothness of the refined edge, 0 (most detail) to [`Self::MAX_SMOOTHNESS`]
+    pub smoothness: f32,
+}
+
+impl Default for RefineEdge {
+    fn default() -> Self {
+        Self {
+            radius: 8,
+            smoothness: 20.0,
+        }
+    }
+}
+
+impl RefineEdge {
+    /// Largest edge radius
+    pub const MAX_RADIUS: u32 = 64;
+    /// Largest smoothness value
+    pub const MAX_SMOOTHNESS: f32 = 100.0;
+
+    /// Create refine edge parameters
+    pub fn new(radius: u32, smoothness: f32) -> Self {
+        Self {
+            radius: radius.clamp(1, Self::MAX_RADIUS),
+            smoothness: smoothness.clamp(0.0, Self::MAX_SMOOTHNESS),
+        }
+    }
+
+    /// Guided filter regularization for the current smoothness
+    ///
+    /// Colors are normalized to 0-1, so this is compared against the color
+    /// variance in each window: flat windows are smoothed, edges are kept.
+    fn epsilon(&self) -> f32 {
+        let t = self.smoothness / Self::MAX_SMOOTHNESS;
+        1e-5 + 0.05 * t * t
+    }
+
+    /// Window radius of the guided filter
+    fn window(&self) -> usize {
+        self.radius.max(1) as usize
+    }
+
+    /// Recompute the alpha of the band around a selection's edge
+    ///
+    /// Pixels further than [`radius`](Self::radius) from the edge keep their
+    /// coverage; the mask covers the whole image.
+    pub fn refine(&self, selection: &Selection, image: &PixelData) -> MaskSelection {
+        let (width, height) = image.dimensions();
+        let mut mask = selection.rasterize(width, height);
+        let (w, h) = (width as usize, height as usize);
+        if w == 0 || h == 0 {
+            return mask;
+        }
+
+        let band = self.band(&mask, w, h);
+        let Some((x0, y0, x1, y1)) = bounds_of(&band, w, h) else {
+            return mask;
+        };
+
+        // Filter only the band's bounding box plus one window
+        let r = self.window();
+        let x0 = x0.saturating_sub(r);
+        let y0 = y0.saturating_sub(r);
+        let x1 = (x1 + r + 1).min(w);
+        let y1 = (y1 + r + 1).min(h);
+        let (cw, ch) = (x1 - x0, y1 - y0);
+
+        let mut guide = [
+            vec![0.0f32; cw * ch],
+            vec![0.0; cw * ch],
+            vec![0.0; cw * ch],
+        ];
+        let mut coarse = vec![0.0f32; cw * ch];
+        for y in 0..ch {
+            for x in 0..cw {
+                let i = y * cw + x;
+                let pixel = image
+                    .get_pixel((x0 + x) as u32, (y0 + y) as u32)
+                    .unwrap_or_else(RgbaPixel::transparent);
+                // Transparent areas count as black, like the edge detector
+                let alpha = pixel.a as f32 / 255.0;
+                guide[0][i] = pixel.r as f32 / 255.0 * alpha;
+                guide[1][i] = pixel.g as f32 / 255.0 * alpha;
+                guide[2][i] = pixel.b as f32 / 255.0 * alpha;
+                coarse[i] = mask.mask_data[(y0 + y) * w + x0 + x] as f32 / 255.0;
+            }
+        }
+
+        let refined = guided_filter(&guide, &coarse, cw, ch, r, self.epsilon());
+        for y in 0..ch {
+            for x in 0..cw {
+                let index = (y0 + y) * w + x0 + x;
+                if band[index] {
+                    mask.mask_data[index] =
+                        (refined[y * cw + x] * 255.0).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+        mask
+    }
+
+    /// Pixels within the radius of the selection edge, on either side
+    fn band(&self, mask: &MaskSelection, w: usize, h: usize) -> Vec<bool> {
+        let selected: Vec<bool> = mask.mask_data.iter().map(|&value| value > 127).collect();
+        let to_unselected = distance_field(&selected, w, h, false);
+        let to_selected = distance_field(&selected, w, h, true);
+        let limit = (self.radius as f32).powi(2);
+        selected
+            .iter()
+            .enumerate()
+            .map(|(i, &inside)| {
+                let soft = mask.mask_data[i] != 0 && mask.mask_data[i] != 255;
+                soft || if inside {
+                    to_unselected[i] <= limit
+                } else {
+                    to_selected[i] <= limit
+                }
+            })
+            .collect()
+    }
+
+    /// Replace the colors of partly selected pixels with their foreground color
+    ///
+    /// Each edge pixel is modelled as `alpha · F + (1 - alpha) · B`. Nearby
+    /// foreground and background colors are estimated with alpha-weighted
+    /// averages, and `F` is solved from the pixel's own color. `amount` blends
+    /// between the original (0.0) and fully decontaminated (1.0) colors.
+    pub fn decontaminate(
+        &self,
+        image: &PixelData,
+        alpha: &MaskSelection,
+        amount: f32,
+    ) -> PixelData {
+        let (width, height) = image.dimensions();
+        let (w, h) = (width as usize, height as usize);
+        let mut output = image.clone();
+        let amount = amount.clamp(0.0, 1.0);
+        if w == 0 || h == 0 || amount == 0.0 {
+            return output;
+        }
+
+        let alpha_at = |x: usize, y: usize| alpha.get_pixel(x as u32, y as u32) as f32 / 255.0;
+        let pixels: Vec<RgbaPixel> = (0..h)
+            .flat_map(|y| (0..w).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                image
+                    .get_pixel(x as u32, y as u32)
+                    .unwrap_or_else(RgbaPixel::transparent)
+            })
+            .collect();
+        let alphas: Vec<f32> = (0..h)
+            .flat_map(|y| (0..w).map(move |x| (x, y)))
+            .map(|(x, y)| alpha_at(x, y))
+            .collect();
+        if alphas.iter().all(|&a| a == 0.0 || a == 1.0) {
+            return output;
+        }
+
+        let r = self.window();
+        let foreground_weight: Vec<f32> = alphas.iter().map(|a| a * a).collect();
+        let background_weight: Vec<f32> = alphas.iter().map(|a| (1.0 - a) * (1.0 - a)).collect();
+        let mean_fw = box_mean(&foreground_weight, w, h, r);
+        let mean_bw = box_mean(&background_weight, w, h, r);
+
+        let mut foreground = Vec::with_capacity(3);
+        let mut background = Vec::with_capacity(3);
+        for channel in 0..3 {
+            let values: Vec<f32> = pixels
+                .iter()
+                .map(|p| p.to_array()[channel] as f32 / 255.0)
+                .collect();
+            let weighted = |weights: &[f32]| -> Vec<f32> {
+                values.iter().zip(weights).map(|(v, w)| v * w).collect()
+            };
+            foreground.push(box_mean(&weighted(&foreground_weight), w, h, r));
+            background.push(box_mean(&weighted(&background_weight), w, h, r));
+        }
+
+        for (i, pixel) in pixels.iter().enumerate() {
+            let a = alphas[i];
+            if a == 0.0 || a == 1.0 {
+                continue;
+            }
+            let color = pixel.to_array();
+            let mut decontaminated = [0u8; 3];
+            for channel in 0..3 {
+                let value = color[channel] as f32 / 255.0;
+                let estimate = |sums: &[Vec<f32>], weights: &[f32]| {
+                    if weights[i] > 1e-6 {
+                        sums[channel][i] / weights[i]
+                    } else {
+                        value
+                    }
+                };
+                let f_estimate = estimate(&foreground, &mean_fw);
+                let b_estimate = estimate(&background, &mean_bw);
+                // Solving for F is unstable at low alpha, so lean on the estimate there
+                let solved = ((value - (1.0 - a) * b_estimate) / a.max(0.02)).clamp(0.0, 1.0);
+                let f = a * solved + (1.0 - a) * f_estimate;
+                let blended = value + amount * (f - value);
+                decontaminated[channel] = (blended * 255.0).round().clamp(0.0, 255.0) as u8;
+            }
+            let _ = output.set_pixel(
+                (i % w) as u32,
+                (i / w) as u32,
+                RgbaPixel::new(
+                    decontaminated[0],
+                    decontaminated[1],
+                    decontaminated[2],
+                    pixel.a,
+                ),
+            );
+        }
+        output
+    }
+
+    /// Build a layer holding the image with the refined alpha as its mask
+    ///
+    /// With `decontaminate` set, edge colors are decontaminated by that amount.
+    pub fn output_layer(
+        &self,
+        name: String,
+        image: &PixelData,
+        alpha: &MaskSelection,
+        decontaminate: Option<f32>,
+    ) -> Result<Layer> {
+        let (width, height) = image.dimensions();
+        let mut layer = Layer::new_pixel(name, width, height);
+        layer.pixel_data = Some(match decontaminate {
+            Some(amount) => self.decontaminate(image, alpha, amount),
+            None => image.clone(),
+        });
+        layer.create_mask(width, height)?;
+        for y in 0..height {
+            for x in 0..width {
+                let value = alpha.get_pixel(x, y);
+                layer.set_mask_pixel(x, y, RgbaPixel::new(value, value, value, 255))?;
+            }
+        }
+        Ok(layer)
+    }
+}
+
+/// Bounding box `(x0, y0, x1, y1)` of the set pixels, inclusive
+fn bounds_of(band: &[bool], w: usize, h: usize) -> Option<(usize, usize, usize, usize)> {
+    let mut bounds: Option<(usize, usize, usize, usize)> = None;
+    for y in 0..h {
+        for x in 0..w {
+            if band[y * w + x] {
+                bounds = Some(match bounds {
+                    None => (x, y, x, y),
+                    Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                });
+            }
+        }
+    }
+    bounds
+}
+
+/// Color guided filter of `input` with a three-channel guide
+fn guided_filter(
+    guide: &[Vec<f32>; 3],
+    input: &[f32],
+    w: usize,
+    h: usize,
+    r: usize,
+    epsilon: f32,
+) -> Vec<f32> {
+    let mean = |data: &[f32]| box_mean(data, w, h, r);
+    let product =
+        |a: &[f32], b: &[f32]| -> Vec<f32> { a.iter().zip(b).map(|(x, y)| x * y).collect() };
+
+    let mean_i: Vec<Vec<f32>> = guide.iter().map(|channel| mean(channel)).collect();
+    let mean_p = mean(input);
+    let mean_ip: Vec<Vec<f32>> = guide
+        .iter()
+        .map(|channel| mean(&product(channel, input)))
+        .collect();
+    // Upper triangle of the guide covariance: rr, rg, rb, gg, gb, bb
+    let pairs = [(0, 0), (0, 1), (0, 2), (1, 1), (1, 2), (2, 2)];
+    let mean_ii: Vec<Vec<f32>> = pairs
+        .iter()
+        .map(|&(a, b)| mean(&product(&guide[a], &guide[b])))
+        .collect();
+
+    let mut coefficients = [vec![0.0f32; w * h], vec![0.0; w * h], vec![0.0; w * h]];
+    let mut offset = vec![0.0f32; w * h];
+    for i in 0..w * h {
+        let mu = [mean_i[0][i], mean_i[1][i], mean_i[2][i]];
+        let cov_ip = [
+            mean_ip[0][i] - mu[0] * mean_p[i],
+            mean_ip[1][i] - mu[1] * mean_p[i],
+            mean_ip[2][i] - mu[2] * mean_p[i],
+        ];
+        let mut sigma = [0.0f32; 6];
+        for (k, &(a, b)) in pairs.iter().enumerate() {
+            sigma[k] = mean_ii[k][i] - mu[a] * mu[b];
+        }
+        let a = solve_symmetric(
+            [
+                sigma[0] + epsilon,
+                sigma[1],
+                sigma[2],
+                sigma[3] + epsilon,
+                sigma[4],
+                sigma[5] + epsilon,
+            ],
+            cov_ip,
+        );
+        for channel in 0..3 {
+            coefficients[channel][i] = a[channel];
+        }
+        offset[i] = mean_p[i] - a[0] * mu[0] - a[1] * mu[1] - a[2] * mu[2];
+    }
+
+    let mean_a: Vec<Vec<f32>> = coefficients.iter().map(|channel| mean(channel)).collect();
+    let mean_b = mean(&offset);
+    (0..w * h)
+        .map(|i| {
+            mean_a[0][i] * guide[0][i]
+                + mean_a[1][i] * guide[1][i]
+                + mean_a[2][i] * guide[2][i]
+                + mean_b[i]
+        })
+        .collect()
+}
+
+/// Solve `M x = v` for a symmetric 3x3 matrix given as its upper triangle
+/// `[m00, m01, m02, m11, m12, m22]`
+fn solve_symmetric(m: [f32; 6], v: [f32; 3]) -> [f32; 3] {
+    let [a, b, c, d, e, f] = m.map(f64::from);
+    let v = v.map(f64::from);
+    // Adjugate of [[a, b, c], [b, d, e], [c, e, f]]
+    let i00 = d * f - e * e;
+    let i01 = c * e - b * f;
+    let i02 = b * e - c * d;
+    let i11 = a * f - c * c;
+    let i12 = b * c - a * e;
+    let i22 = a * d - b * b;
+    let determinant = a * i00 + b * i01 + c * i02;
+    if determinant.abs() < 1e-18 {
+        return [0.0; 3];
+    }
+    [
+        ((i00 * v[0] + i01 * v[1] + i02 * v[2]) / determinant) as f32,
+        ((i01 * v[0] + i11 * v[1] + i12 * v[2]) / determinant) as f32,
+        ((i02 * v[0] + i12 * v[1] + i22 * v[2]) / determinant) as f32,
+    ]
+}
+
+/// Mean over a `(2r + 1)²` window, shrinking the window at the image edges
+fn box_mean(data: &[f32], w: usize, h: usize, r: usize) -> Vec<f32> {
+    let pass = |source: &[f32], step: usize, length: usize, count: usize, line: usize| {
+        let mut out = vec![0.0f32; source.len()];
+        let mut prefix = vec![0.0f64; length + 1];
+        for l in 0..count {
+            let base = l * line;
+            for i in 0..length {
+                prefix[i + 1] = prefix[i] + source[base + i * step] as f64;
+            }
+            for i in 0..length {
+                let lo = i.saturating_sub(r);
+                let hi = (i + r + 1).min(length);
+                out[base + i * step] = ((prefix[hi] - prefix[lo]) / (hi - lo) as f64) as f32;
+            }
+        }
+        out
+    };
+    let horizontal = pass(data, 1, w, h, w);
+    pass(&horizontal, w, h, w, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Black on the left, white from x = 20, with a selection edge at x = 24
+    fn step_image() -> (PixelData, Selection) {
+        let mut image = PixelData::new_rgba(40, 20);
+        for y in 0..20 {
+            for x in 0..40 {
+                let color = if x >= 20 {
+                    RgbaPixel::white()
+                } else {
+                    RgbaPixel::black()
+                };
+                image.set_pixel(x, y, color).unwrap();
+            }
+        }
+        (image, Selection::rectangle(24.0, 0.0, 16.0, 20.0))
+    }
+
+    #[test]
+    fn test_refine_edge_moves_alpha_to_image_edge() {
+        let (image, selection) = step_image();
+        let refined = RefineEdge::new(8, 0.0).refine(&selection, &image);
+
+        // The white pixels left of the drawn edge join the selection
+        assert!(
+            refined.get_pixel(21, 10) > 100,
+            "{}",
+            refined.get_pixel(21, 10)
+        );
+        // Black pixels stay out even inside the band
+        assert!(
+            refined.get_pixel(18, 10) < 30,
+            "{}",
+            refined.get_pixel(18, 10)
+        );
+        assert!(refined.get_pixel(30, 10) > 200);
+        // Outside the band nothing changes
+        assert_eq!(refined.get_pixel(5, 10), 0);
+        assert_eq!(refined.get_pixel(39, 10), 255);
+
+        let selection = selection.refine_edge(&image, &RefineEdge::default());
+        assert!(matches!(selection, Selection::Mask(_)));
+    }
+
+    #[test]
+    fn test_refine_edge_without_selection_edge() {
+        let (image, _) = step_image();
+        // No selection selects everything, so there is no edge to refine
+        let refined = RefineEdge::default().refine(&Selection::None, &image);
+        assert!(refined.mask_data.iter().all(|&value| value == 255));
+    }
+
+    #[test]
+    fn test_decontaminate_removes_background_fringe() {
+        // Red subject on a blue background, with a column of mixed pixels
+        let mut image = PixelData::new_rgba(20, 10);
+        let mut mask = vec![0u8; 200];
+        for y in 0..10 {
+            for x in 0..20 {
+                let (color, alpha) = match x {
+                    0..=9 => (RgbaPixel::rgb(255, 0, 0), 255),
+                    10 => (RgbaPixel::rgb(128, 0, 127), 128),
+                    _ => (RgbaPixel::rgb(0, 0, 255), 0),
+                };
+                image.set_pixel(x, y, color).unwrap();
+                mask[(y * 20 + x) as usize] = alpha;
+            }
+        }
+        let alpha = MaskSelection::new(20, 10, mask);
+        let refine = RefineEdge::new(4, 20.0);
+
+        let cleaned = refine.decontaminate(&image, &alpha, 1.0);
+        let edge = cleaned.get_pixel(10, 5).unwrap();
+        assert!(edge.r > 200 && edge.b < 50, "{:?}", edge);
+        // Fully selected and unselected pixels keep their colors
+        assert_eq!(cleaned.get_pixel(5, 5), image.get_pixel(5, 5));
+        assert_eq!(cleaned.get_pixel(15, 5), image.get_pixel(15, 5));
+
+        let layer = refine
+            .output_layer("Refined".to_string(), &image, &alpha, Some(1.0))
+            .unwrap();
+        assert_eq!(layer.get_mask_pixel(5, 5).unwrap().r, 255);
+        assert_eq!(layer.get_mask_pixel(10, 5).unwrap().r, 128);
+        assert_eq!(layer.get_mask_pixel(15, 5).unwrap().r, 0);
+        assert_eq!(layer.get_pixel(10, 5), Some(edge));
+    }
+}
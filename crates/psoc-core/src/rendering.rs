@@ -59,8 +59,20 @@ impl RenderEngine {
     }
 
     /// Render document to a single flattened image
-    #[instrument(skip(self, document))]
     pub fn render_document(&mut self, document: &Document) -> Result<PixelData> {
+        self.render_document_with(document, |_| None)
+    }
+
+    /// Render document, compositing some layers from substitute pixels
+    ///
+    /// `layer_pixels` may return pixels to draw in place of a layer's own
+    /// `pixel_data`, such as a display version of an HDR layer.
+    #[instrument(skip(self, document, layer_pixels))]
+    pub fn render_document_with<'p>(
+        &mut self,
+        document: &Document,
+        layer_pixels: impl Fn(&Layer) -> Option<&'p PixelData>,
+    ) -> Result<PixelData> {
         debug!(
             "Rendering document: {}x{} with {} layers",
             document.size.width,
@@ -115,7 +127,7 @@ impl RenderEngine {
                 }
                 // Handle other layer types with pixel data
                 _ => {
-                    if let Some(layer_data) = &layer.pixel_data {
+                    if let Some(layer_data) = layer_pixels(layer).or(layer.pixel_data.as_ref()) {
                         self.composite_layer(&mut result, layer, layer_data)?;
                     }
                 }
@@ -207,7 +219,7 @@ impl RenderEngine {
         &self,
         result: &mut PixelData,
         layer: &Layer,
-        _layer_data: &PixelData,
+        layer_data: &PixelData,
         params: &CompositionParams,
     ) -> Result<()> {
         // Create tiles for parallel processing
@@ -242,7 +254,11 @@ impl RenderEngine {
                         }
 
                         // Get pixel with mask applied
-                        if let Some(layer_pixel) = layer.get_masked_pixel(x, y) {
+                        if let Some(mut layer_pixel) = layer_data.get_pixel(x, y) {
+                            if let Some(mask_pixel) = layer.get_mask_pixel(x, y) {
+                                let mask_value = mask_pixel.r as f32 / 255.0;
+                                layer_pixel.a = (layer_pixel.a as f32 * mask_value) as u8;
+                            }
                             if let Some(base_pixel) = result.get_pixel(doc_x as u32, doc_y as u32) {
                                 let blended = blend_mode.blend(base_pixel, layer_pixel, opacity);
                                 tile_updates.push((doc_x as u32, doc_y as u32, blended));
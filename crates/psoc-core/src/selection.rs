@@ -2,12 +2,23 @@
 //!
 //! This module provides selection functionality including rectangular selections,
 //! selection masks, and selection operations.
+//!
+//! Refinements such as feather, expand, contract, border and smooth work on a
+//! rasterized [`MaskSelection`] covering the whole canvas, so they apply to every
+//! selection shape. Combining two selections with a [`SelectionMode`] works the
+//! same way.
+//!
+//! Pixel operations such as fills and adjustments use [`Selection::coverage`],
+//! which reports how much of each pixel a selection covers so that curved and
+//! diagonal edges blend smoothly instead of leaving stair-steps.
 
+use crate::geometry::{Path, Point, Rect, Subpath};
+use crate::pixel::PixelData;
+use crate::refine_edge::RefineEdge;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 
-use crate::geometry::{Point, Rect};
-
 /// Selection types supported by PSOC
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub enum Selection {
@@ -80,6 +91,39 @@ impl Selection {
         }
     }
 
+    /// Fraction of the pixel at `(x, y)` covered by the selection, from 0 to 255
+    ///
+    /// Rectangles are covered analytically, ellipses and lassos are
+    /// supersampled where their outline crosses the pixel and masks report
+    /// their stored value.
+    pub fn coverage(&self, x: u32, y: u32) -> u8 {
+        match self {
+            Selection::None => 255,
+            Selection::Rectangle(rect) => rect.coverage(x, y),
+            Selection::Ellipse(ellipse) => ellipse.coverage(x, y),
+            Selection::Lasso(lasso) => lasso.coverage(x, y),
+            Selection::Mask(mask) => mask.value_at(pixel_center(x, y)),
+        }
+    }
+
+    /// Coverage of pixel `(x, y)` of a layer placed at `offset` in the document
+    ///
+    /// Selections live in document coordinates, so the layer pixel is looked
+    /// up where it lands on the canvas. Pixels left of or above the canvas are
+    /// sampled at their center.
+    pub fn layer_coverage(&self, x: u32, y: u32, offset: Point) -> u8 {
+        let doc_x = (x as f32 + offset.x).round();
+        let doc_y = (y as f32 + offset.y).round();
+        if doc_x < 0.0 || doc_y < 0.0 {
+            return if self.contains_point(Point::new(doc_x + 0.5, doc_y + 0.5)) {
+                255
+            } else {
+                0
+            };
+        }
+        self.coverage(doc_x as u32, doc_y as u32)
+    }
+
     /// Get the bounding rectangle of the selection
     pub fn bounds(&self) -> Option<Rect> {
         match self {
@@ -139,6 +183,117 @@ impl Selection {
             Selection::Mask(mask) => mask.scale(factor),
         }
     }
+
+    /// Rasterize the selection into a mask covering a canvas of the given size
+    ///
+    /// Each pixel holds the selection's [`coverage`](Self::coverage), so edges
+    /// stay anti-aliased; the result is never inverted.
+    pub fn rasterize(&self, width: u32, height: u32) -> MaskSelection {
+        let mut mask_data = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            for x in 0..width {
+                mask_data.push(self.coverage(x, y));
+            }
+        }
+        MaskSelection::new(width, height, mask_data)
+    }
+
+    /// Apply a refinement, returning a mask selection over a canvas of the given size
+    ///
+    /// Having no selection stays that way: there is no edge to refine.
+    pub fn modify(&self, modification: SelectionModification, width: u32, height: u32) -> Self {
+        if self.is_select_all() {
+            return Selection::None;
+        }
+        let mut mask = self.rasterize(width, height);
+        mask.modify(modification);
+        Selection::Mask(mask)
+    }
+
+    /// Recompute the alpha around the selection edge from the image beneath it
+    ///
+    /// See [`RefineEdge`]; the result is a mask selection the size of `image`.
+    pub fn refine_edge(&self, image: &PixelData, refine: &RefineEdge) -> Self {
+        Selection::Mask(refine.refine(self, image))
+    }
+
+    /// Selection filling a path on a canvas of the given size
+    ///
+    /// A path with a single subpath becomes a lasso selection; several
+    /// subpaths are filled into a mask with the even-odd rule, so inner
+    /// subpaths cut holes.
+    pub fn from_path(path: &Path, width: u32, height: u32) -> Self {
+        let polygons = path.flatten(Path::TOLERANCE);
+        match polygons.as_slice() {
+            [polygon] if polygon.len() >= 3 => Selection::lasso(polygon.clone()),
+            _ => Selection::Mask(fill_polygons(&polygons, width, height)),
+        }
+    }
+
+    /// Outline of the selection as a path
+    ///
+    /// Rectangles, ellipses and lassos convert exactly. Other selections are
+    /// traced along the edges of their rasterized pixels, with staircases
+    /// simplified to straight segments.
+    pub fn to_path(&self, name: String, width: u32, height: u32) -> Path {
+        let mut path = Path::new(name);
+        match self {
+            Selection::None => path.subpaths.push(Subpath::rectangle(Rect::new(
+                0.0,
+                0.0,
+                width as f32,
+                height as f32,
+            ))),
+            Selection::Rectangle(rect) if !rect.inverted => {
+                path.subpaths.push(Subpath::rectangle(rect.rect))
+            }
+            Selection::Ellipse(ellipse) if !ellipse.inverted => path.subpaths.push(
+                Subpath::ellipse(ellipse.center, ellipse.radius_x, ellipse.radius_y),
+            ),
+            Selection::Lasso(lasso) if !lasso.inverted && lasso.points.len() >= 3 => path
+                .subpaths
+                .push(Subpath::from_points(&lasso.points, true)),
+            _ => path.subpaths = trace_outlines(&self.rasterize(width, height)),
+        }
+        path
+    }
+
+    /// Combine this selection with another one according to a selection mode
+    ///
+    /// Mask values are treated as coverage, so soft edges combine like alpha:
+    /// add is `a + b - ab`, subtract is `a(1 - b)` and intersect is `ab`.
+    /// Having no selection ([`Selection::None`]) is where a new combined
+    /// selection starts in every mode: adding or intersecting yields `other`
+    /// and subtracting leaves no selection.
+    pub fn combine(&self, other: &Selection, mode: SelectionMode, width: u32, height: u32) -> Self {
+        match (mode, self) {
+            (SelectionMode::Replace, _) => return other.clone(),
+            (SelectionMode::Add | SelectionMode::Intersect, Selection::None) => {
+                return other.clone()
+            }
+            (SelectionMode::Subtract, Selection::None) => return Selection::None,
+            _ => {}
+        }
+
+        let current = self.rasterize(width, height);
+        let operand = other.rasterize(width, height);
+        let mask_data = current
+            .mask_data
+            .iter()
+            .zip(&operand.mask_data)
+            .map(|(&a, &b)| {
+                let (a, b) = (a as u32, b as u32);
+                let value = match mode {
+                    SelectionMode::Add => a + b - (a * b + 127) / 255,
+                    SelectionMode::Subtract => (a * (255 - b) + 127) / 255,
+                    SelectionMode::Intersect => (a * b + 127) / 255,
+                    SelectionMode::Replace => b,
+                };
+                value as u8
+            })
+            .collect();
+        Selection::Mask(MaskSelection::new(width, height, mask_data))
+    }
 }
 
 impl fmt::Display for Selection {
@@ -197,6 +352,22 @@ impl RectangleSelection {
         }
     }
 
+    /// Fraction of a pixel covered by the selection, from the exact overlap area
+    pub fn coverage(&self, x: u32, y: u32) -> u8 {
+        let overlap = |start: f32, pixel: u32, length: f32| {
+            let pixel = pixel as f32;
+            ((start + length).min(pixel + 1.0) - start.max(pixel)).clamp(0.0, 1.0)
+        };
+        let area =
+            overlap(self.rect.x, x, self.rect.width) * overlap(self.rect.y, y, self.rect.height);
+        let value = (area * 255.0).round() as u8;
+        if self.inverted {
+            255 - value
+        } else {
+            value
+        }
+    }
+
     /// Get the bounding rectangle
     pub fn bounds(&self) -> Rect {
         self.rect
@@ -324,6 +495,26 @@ impl EllipseSelection {
         }
     }
 
+    /// Fraction of a pixel covered by the selection, estimated by supersampling
+    pub fn coverage(&self, x: u32, y: u32) -> u8 {
+        supersample(
+            x,
+            y,
+            |center| self.edge_distance(center),
+            |point| self.contains_point(point),
+        )
+    }
+
+    /// Lower bound on the distance from a point to the outline
+    ///
+    /// The normalized radius changes by at most one over the smaller radius
+    /// per unit of distance.
+    fn edge_distance(&self, point: Point) -> f32 {
+        let dx = (point.x - self.center.x) / self.radius_x;
+        let dy = (point.y - self.center.y) / self.radius_y;
+        ((dx * dx + dy * dy).sqrt() - 1.0).abs() * self.radius_x.abs().min(self.radius_y.abs())
+    }
+
     /// Get the bounding rectangle
     pub fn bounds(&self) -> Rect {
         Rect::new(
@@ -457,6 +648,38 @@ impl LassoSelection {
         }
     }
 
+    /// Fraction of a pixel covered by the selection, estimated by supersampling
+    pub fn coverage(&self, x: u32, y: u32) -> u8 {
+        supersample(
+            x,
+            y,
+            |center| self.edge_distance(center),
+            |point| self.contains_point(point),
+        )
+    }
+
+    /// Distance from a point to the nearest edge of the polygon
+    ///
+    /// Points well outside the bounds return their distance to the bounds
+    /// without visiting the edges.
+    fn edge_distance(&self, point: Point) -> f32 {
+        let bounds = self.bounds();
+        let outside_x = (bounds.x - point.x).max(point.x - (bounds.x + bounds.width));
+        let outside_y = (bounds.y - point.y).max(point.y - (bounds.y + bounds.height));
+        let outside = outside_x.max(outside_y);
+        if outside > HALF_PIXEL_DIAGONAL || self.points.is_empty() {
+            return outside;
+        }
+
+        let mut previous = self.points[self.points.len() - 1];
+        let mut distance = f32::INFINITY;
+        for &point_on_edge in &self.points {
+            distance = distance.min(segment_distance(point, previous, point_on_edge));
+            previous = point_on_edge;
+        }
+        distance
+    }
+
     /// Get the bounding rectangle
     pub fn bounds(&self) -> Rect {
         if let Some(cached) = self.bounds_cache {
@@ -688,6 +911,425 @@ impl MaskSelection {
         }
         0
     }
+
+    /// Selection strength at an image position, taking the offset and inversion into account
+    pub fn value_at(&self, point: Point) -> u8 {
+        let local_x = (point.x - self.offset.x).floor();
+        let local_y = (point.y - self.offset.y).floor();
+        let value = if local_x < 0.0
+            || local_y < 0.0
+            || local_x >= self.width as f32
+            || local_y >= self.height as f32
+        {
+            0
+        } else {
+            self.get_pixel(local_x as u32, local_y as u32)
+        };
+
+        if self.inverted {
+            255 - value
+        } else {
+            value
+        }
+    }
+
+    /// Apply a refinement to the mask in place
+    ///
+    /// Expand, contract, border and smooth produce a hard-edged mask; feather
+    /// produces soft values. Pixels outside the mask never count as unselected,
+    /// so selections touching the mask edge are not contracted from it.
+    pub fn modify(&mut self, modification: SelectionModification) {
+        let (width, height) = (self.width as usize, self.height as usize);
+        if width == 0 || height == 0 {
+            return;
+        }
+        let selected: Vec<bool> = self.mask_data.iter().map(|&value| value > 127).collect();
+
+        match modification {
+            SelectionModification::Feather { radius } => {
+                gaussian_blur(&mut self.mask_data, width, height, radius / 2.0);
+            }
+            SelectionModification::Expand { pixels } => {
+                let distance = distance_field(&selected, width, height, true);
+                let limit = (pixels as f32).powi(2);
+                self.set_from(|index| distance[index] <= limit);
+            }
+            SelectionModification::Contract { pixels } => {
+                let distance = distance_field(&selected, width, height, false);
+                let limit = (pixels as f32).powi(2);
+                self.set_from(|index| distance[index] > limit);
+            }
+            SelectionModification::Border { width: border } => {
+                // A band straddling the edge, half inside and half outside
+                let outside = distance_field(&selected, width, height, true);
+                let inside = distance_field(&selected, width, height, false);
+                let inner = ((border / 2) as f32).powi(2);
+                let outer = (border.div_ceil(2) as f32).powi(2);
+                self.set_from(|index| {
+                    if selected[index] {
+                        inside[index] <= inner
+                    } else {
+                        outside[index] <= outer
+                    }
+                });
+            }
+            SelectionModification::Smooth { radius } => {
+                let radius = radius as usize;
+                let counts = SummedArea::new(&selected, width, height);
+                self.set_from(|index| {
+                    let (x, y) = (index % width, index / width);
+                    let x0 = x.saturating_sub(radius);
+                    let y0 = y.saturating_sub(radius);
+                    let x1 = (x + radius + 1).min(width);
+                    let y1 = (y + radius + 1).min(height);
+                    let total = (x1 - x0) * (y1 - y0);
+                    counts.sum(x0, y0, x1, y1) * 2 > total
+                });
+            }
+        }
+    }
+
+    fn set_from(&mut self, selected: impl Fn(usize) -> bool) {
+        for (index, value) in self.mask_data.iter_mut().enumerate() {
+            *value = if selected(index) { 255 } else { 0 };
+        }
+    }
+}
+
+/// Refinements that can be applied to any selection
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SelectionModification {
+    /// Soften the edge with a Gaussian falloff of the given radius in pixels
+    Feather { radius: f32 },
+    /// Grow the selection outward by a number of pixels
+    Expand { pixels: u32 },
+    /// Shrink the selection inward by a number of pixels
+    Contract { pixels: u32 },
+    /// Replace the selection with a band of the given width around its edge
+    Border { width: u32 },
+    /// Round off corners and remove specks smaller than the given radius
+    Smooth { radius: u32 },
+}
+
+impl fmt::Display for SelectionModification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectionModification::Feather { radius } => write!(f, "Feather {}px", radius),
+            SelectionModification::Expand { pixels } => write!(f, "Expand {}px", pixels),
+            SelectionModification::Contract { pixels } => write!(f, "Contract {}px", pixels),
+            SelectionModification::Border { width } => write!(f, "Border {}px", width),
+            SelectionModification::Smooth { radius } => write!(f, "Smooth {}px", radius),
+        }
+    }
+}
+
+/// Separable Gaussian blur of a single-channel buffer, clamping at the edges
+fn gaussian_blur(data: &mut [u8], width: usize, height: usize, sigma: f32) {
+    if sigma <= 0.0 {
+        return;
+    }
+    let radius = (sigma * 3.0).ceil() as isize;
+    let kernel: Vec<f32> = (-radius..=radius)
+        .map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f32 = kernel.iter().sum();
+
+    let blur = |source: &[f32], step: usize, length: usize, count: usize, line: usize| {
+        let mut out = vec![0.0f32; source.len()];
+        for l in 0..count {
+            let base = l * line;
+            for i in 0..length {
+                let mut sum = 0.0;
+                for (k, weight) in kernel.iter().enumerate() {
+                    let j = (i as isize + k as isize - radius).clamp(0, length as isize - 1);
+                    sum += source[base + j as usize * step] * weight;
+                }
+                out[base + i * step] = sum / total;
+            }
+        }
+        out
+    };
+
+    let values: Vec<f32> = data.iter().map(|&value| value as f32).collect();
+    let horizontal = blur(&values, 1, width, height, width);
+    let vertical = blur(&horizontal, width, height, width, 1);
+    for (value, blurred) in data.iter_mut().zip(vertical) {
+        *value = blurred.round().clamp(0.0, 255.0) as u8;
+    }
+}
+
+/// Fill polygons into an anti-aliased mask with the even-odd rule
+///
+/// Each pixel row is sampled at four heights, with exact horizontal coverage.
+fn fill_polygons(polygons: &[Vec<Point>], width: u32, height: u32) -> MaskSelection {
+    const SUBSAMPLES: usize = 4;
+    let (w, h) = (width as usize, height as usize);
+    let mut coverage = vec![0.0f32; w * h];
+    let mut crossings = Vec::new();
+
+    for y in 0..h {
+        let row = &mut coverage[y * w..(y + 1) * w];
+        for sample in 0..SUBSAMPLES {
+            let sample_y = y as f32 + (sample as f32 + 0.5) / SUBSAMPLES as f32;
+            crossings.clear();
+            for polygon in polygons {
+                let count = polygon.len();
+                for i in 0..count {
+                    let (a, b) = (polygon[i], polygon[(i + 1) % count]);
+                    if (a.y > sample_y) != (b.y > sample_y) {
+                        crossings.push(a.x + (sample_y - a.y) / (b.y - a.y) * (b.x - a.x));
+                    }
+                }
+            }
+            crossings.sort_by(f32::total_cmp);
+
+            for span in crossings.chunks_exact(2) {
+                let start = span[0].clamp(0.0, w as f32);
+                let end = span[1].clamp(0.0, w as f32);
+                let mut x = start.floor() as usize;
+                while x < w && (x as f32) < end {
+                    let covered = end.min(x as f32 + 1.0) - start.max(x as f32);
+                    row[x] += covered.max(0.0) / SUBSAMPLES as f32;
+                    x += 1;
+                }
+            }
+        }
+    }
+
+    let mask_data = coverage
+        .into_iter()
+        .map(|value| (value * 255.0).round().clamp(0.0, 255.0) as u8)
+        .collect();
+    MaskSelection::new(width, height, mask_data)
+}
+
+/// Closed outlines around the selected pixels of a mask
+///
+/// Pixel edges between selected and unselected pixels are linked into loops
+/// running clockwise around selected areas, then simplified.
+fn trace_outlines(mask: &MaskSelection) -> Vec<Subpath> {
+    let (w, h) = (mask.width as i64, mask.height as i64);
+    let selected = |x: i64, y: i64| {
+        x >= 0 && y >= 0 && x < w && y < h && mask.mask_data[(y * w + x) as usize] > 127
+    };
+
+    let mut outgoing: HashMap<(i64, i64), Vec<(i64, i64)>> = HashMap::new();
+    let mut add_edge = |from: (i64, i64), to: (i64, i64)| {
+        outgoing.entry(from).or_default().push(to);
+    };
+    for y in 0..h {
+        for x in 0..w {
+            if !selected(x, y) {
+                continue;
+            }
+            if !selected(x, y - 1) {
+                add_edge((x, y), (x + 1, y));
+            }
+            if !selected(x + 1, y) {
+                add_edge((x + 1, y), (x + 1, y + 1));
+            }
+            if !selected(x, y + 1) {
+                add_edge((x + 1, y + 1), (x, y + 1));
+            }
+            if !selected(x - 1, y) {
+                add_edge((x, y + 1), (x, y));
+            }
+        }
+    }
+
+    // Every vertex has as many edges in as out, so each walk returns to its start
+    let mut starts: Vec<(i64, i64)> = outgoing.keys().copied().collect();
+    starts.sort_by_key(|&(x, y)| (y, x));
+    let mut subpaths = Vec::new();
+    for start in starts {
+        while let Some(mut current) = outgoing.get_mut(&start).and_then(|edges| edges.pop()) {
+            let mut outline = vec![Point::new(start.0 as f32, start.1 as f32)];
+            while current != start {
+                outline.push(Point::new(current.0 as f32, current.1 as f32));
+                match outgoing.get_mut(&current).and_then(|edges| edges.pop()) {
+                    Some(next) => current = next,
+                    None => break,
+                }
+            }
+            let outline = simplify_outline(&outline, 0.75);
+            if outline.len() >= 3 {
+                subpaths.push(Subpath::from_points(&outline, true));
+            }
+        }
+    }
+    subpaths
+}
+
+/// Douglas-Peucker simplification of a closed outline
+fn simplify_outline(points: &[Point], tolerance: f32) -> Vec<Point> {
+    let count = points.len();
+    if count < 4 {
+        return points.to_vec();
+    }
+
+    // Split the loop at the point furthest from the first one
+    let far = (1..count)
+        .max_by(|&a, &b| {
+            points[0]
+                .distance_to(&points[a])
+                .total_cmp(&points[0].distance_to(&points[b]))
+        })
+        .unwrap_or(count / 2);
+    let mut closed = points.to_vec();
+    closed.push(points[0]);
+    let mut keep = vec![false; count + 1];
+    keep[0] = true;
+    keep[far] = true;
+    douglas_peucker(&closed, 0, far, tolerance, &mut keep);
+    douglas_peucker(&closed, far, count, tolerance, &mut keep);
+
+    (0..count).filter(|&i| keep[i]).map(|i| points[i]).collect()
+}
+
+fn douglas_peucker(points: &[Point], first: usize, last: usize, tolerance: f32, keep: &mut [bool]) {
+    if last <= first + 1 {
+        return;
+    }
+    let (a, b) = (points[first], points[last]);
+    let (index, distance) = (first + 1..last)
+        .map(|i| (i, segment_distance(points[i], a, b)))
+        .fold((first, 0.0f32), |best, candidate| {
+            if candidate.1 > best.1 {
+                candidate
+            } else {
+                best
+            }
+        });
+    if distance > tolerance {
+        keep[index] = true;
+        douglas_peucker(points, first, index, tolerance, keep);
+        douglas_peucker(points, index, last, tolerance, keep);
+    }
+}
+
+/// Distance from a point to the segment between `a` and `b`
+fn segment_distance(point: Point, a: Point, b: Point) -> f32 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length_squared = dx * dx + dy * dy;
+    if length_squared == 0.0 {
+        return point.distance_to(&a);
+    }
+    let t = (((point.x - a.x) * dx + (point.y - a.y) * dy) / length_squared).clamp(0.0, 1.0);
+    point.distance_to(&Point::new(a.x + t * dx, a.y + t * dy))
+}
+
+/// Squared Euclidean distance from every pixel to the nearest pixel whose
+/// selection state equals `target`
+///
+/// Uses the linear-time transform of Felzenszwalb and Huttenlocher.
+pub(crate) fn distance_field(
+    selected: &[bool],
+    width: usize,
+    height: usize,
+    target: bool,
+) -> Vec<f32> {
+    let mut field: Vec<f32> = selected
+        .iter()
+        .map(|&state| if state == target { 0.0 } else { f32::INFINITY })
+        .collect();
+
+    let mut line = vec![0.0f32; width.max(height)];
+    for y in 0..height {
+        line[..width].copy_from_slice(&field[y * width..(y + 1) * width]);
+        let row = distance_1d(&line[..width]);
+        field[y * width..(y + 1) * width].copy_from_slice(&row);
+    }
+    for x in 0..width {
+        for y in 0..height {
+            line[y] = field[y * width + x];
+        }
+        let column = distance_1d(&line[..height]);
+        for (y, value) in column.into_iter().enumerate() {
+            field[y * width + x] = value;
+        }
+    }
+    field
+}
+
+/// One-dimensional squared distance transform of a sampled function
+fn distance_1d(f: &[f32]) -> Vec<f32> {
+    let n = f.len();
+    let mut result = vec![f32::INFINITY; n];
+    // Parabola vertices and the boundaries between them
+    let mut vertices = vec![0usize; n];
+    let mut boundaries = vec![0.0f32; n + 1];
+    let mut k = 0usize;
+    let mut found = false;
+
+    for q in 0..n {
+        if f[q].is_infinite() {
+            continue;
+        }
+        if !found {
+            found = true;
+            vertices[0] = q;
+            boundaries[0] = f32::NEG_INFINITY;
+            boundaries[1] = f32::INFINITY;
+            continue;
+        }
+        loop {
+            let v = vertices[k];
+            let intersection =
+                ((f[q] + (q * q) as f32) - (f[v] + (v * v) as f32)) / (2.0 * (q as f32 - v as f32));
+            // The first boundary is -inf, so this stops at the first parabola
+            if intersection <= boundaries[k] {
+                k -= 1;
+                continue;
+            }
+            k += 1;
+            vertices[k] = q;
+            boundaries[k] = intersection;
+            boundaries[k + 1] = f32::INFINITY;
+            break;
+        }
+    }
+    if !found {
+        return result;
+    }
+
+    k = 0;
+    for (q, value) in result.iter_mut().enumerate() {
+        while boundaries[k + 1] < q as f32 {
+            k += 1;
+        }
+        let v = vertices[k];
+        let offset = q as f32 - v as f32;
+        *value = offset * offset + f[v];
+    }
+    result
+}
+
+/// Summed-area table for counting selected pixels in rectangles
+struct SummedArea {
+    sums: Vec<usize>,
+    stride: usize,
+}
+
+impl SummedArea {
+    fn new(selected: &[bool], width: usize, height: usize) -> Self {
+        let stride = width + 1;
+        let mut sums = vec![0usize; stride * (height + 1)];
+        for y in 0..height {
+            let mut row = 0;
+            for x in 0..width {
+                row += selected[y * width + x] as usize;
+                sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row;
+            }
+        }
+        Self { sums, stride }
+    }
+
+    /// Number of selected pixels in the half-open rectangle [x0, x1) x [y0, y1)
+    fn sum(&self, x0: usize, y0: usize, x1: usize, y1: usize) -> usize {
+        self.sums[y1 * self.stride + x1] + self.sums[y0 * self.stride + x0]
+            - self.sums[y0 * self.stride + x1]
+            - self.sums[y1 * self.stride + x0]
+    }
 }
 
 impl fmt::Display for MaskSelection {
@@ -700,6 +1342,51 @@ impl fmt::Display for MaskSelection {
     }
 }
 
+/// Samples per axis used to estimate the coverage of curved edges
+const COVERAGE_SAMPLES: u32 = 4;
+
+/// Center of the pixel at `(x, y)`
+fn pixel_center(x: u32, y: u32) -> Point {
+    Point::new(x as f32 + 0.5, y as f32 + 0.5)
+}
+
+/// Distance from a pixel center to its corners
+const HALF_PIXEL_DIAGONAL: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Coverage of a pixel from a grid of samples
+///
+/// `edge_distance` gives a lower bound on the distance from the pixel center
+/// to the shape's outline. Pixels the outline cannot reach are fully in or
+/// fully out, so they are sampled once at their center.
+fn supersample(
+    x: u32,
+    y: u32,
+    edge_distance: impl FnOnce(Point) -> f32,
+    contains: impl Fn(Point) -> bool,
+) -> u8 {
+    let center = pixel_center(x, y);
+    if edge_distance(center) > HALF_PIXEL_DIAGONAL {
+        return if contains(center) { 255 } else { 0 };
+    }
+
+    let (left, top) = (x as f32, y as f32);
+    let step = 1.0 / COVERAGE_SAMPLES as f32;
+    let mut inside = 0;
+    for sy in 0..COVERAGE_SAMPLES {
+        for sx in 0..COVERAGE_SAMPLES {
+            let point = Point::new(
+                left + (sx as f32 + 0.5) * step,
+                top + (sy as f32 + 0.5) * step,
+            );
+            if contains(point) {
+                inside += 1;
+            }
+        }
+    }
+    ((inside * 255 + COVERAGE_SAMPLES * COVERAGE_SAMPLES / 2)
+        / (COVERAGE_SAMPLES * COVERAGE_SAMPLES)) as u8
+}
+
 /// Selection operation modes
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum SelectionMode {
@@ -1012,4 +1699,324 @@ mod tests {
         assert_eq!(bounds.width, 10.0);
         assert_eq!(bounds.height, 10.0);
     }
+
+    fn selected_count(mask: &MaskSelection) -> usize {
+        mask.mask_data.iter().filter(|&&value| value > 127).count()
+    }
+
+    #[test]
+    fn test_rasterize_shapes_and_inverted_masks() {
+        let rect = Selection::rectangle(2.0, 3.0, 4.0, 2.0).rasterize(10, 10);
+        assert_eq!(selected_count(&rect), 8);
+        assert_eq!(rect.get_pixel(2, 3), 255);
+        assert_eq!(rect.get_pixel(6, 3), 0);
+
+        let all = Selection::None.rasterize(4, 4);
+        assert!(all.mask_data.iter().all(|&value| value == 255));
+
+        // Partial values survive, inversion and offsets are resolved
+        let mut mask = MaskSelection::new_with_offset(2, 1, vec![64, 255], Point::new(1.0, 1.0));
+        mask.inverted = true;
+        let raster = Selection::Mask(mask).rasterize(4, 3);
+        assert!(!raster.inverted);
+        assert_eq!(raster.get_pixel(0, 0), 255);
+        assert_eq!(raster.get_pixel(1, 1), 191);
+        assert_eq!(raster.get_pixel(2, 1), 0);
+    }
+
+    #[test]
+    fn test_expand_and_contract_are_round() {
+        let square = Selection::rectangle(10.0, 10.0, 10.0, 10.0);
+
+        let Selection::Mask(expanded) =
+            square.modify(SelectionModification::Expand { pixels: 3 }, 40, 40)
+        else {
+            panic!("Expected mask selection");
+        };
+        assert_eq!(expanded.get_pixel(7, 15), 255);
+        assert_eq!(expanded.get_pixel(6, 15), 0);
+        // Corners are rounded rather than square
+        assert_eq!(expanded.get_pixel(7, 7), 0);
+        assert_eq!(expanded.get_pixel(8, 8), 255);
+
+        let Selection::Mask(contracted) =
+            square.modify(SelectionModification::Contract { pixels: 2 }, 40, 40)
+        else {
+            panic!("Expected mask selection");
+        };
+        assert_eq!(selected_count(&contracted), 36);
+        assert_eq!(contracted.get_pixel(12, 12), 255);
+        assert_eq!(contracted.get_pixel(11, 12), 0);
+    }
+
+    #[test]
+    fn test_contract_ignores_canvas_edges() {
+        let Selection::Mask(contracted) = Selection::rectangle(0.0, 0.0, 8.0, 8.0).modify(
+            SelectionModification::Contract { pixels: 5 },
+            8,
+            8,
+        ) else {
+            panic!("Expected mask selection");
+        };
+        assert_eq!(selected_count(&contracted), 64);
+
+        // Without a selection there is nothing to modify
+        assert_eq!(
+            Selection::None.modify(SelectionModification::Contract { pixels: 5 }, 8, 8),
+            Selection::None
+        );
+    }
+
+    #[test]
+    fn test_border_straddles_the_edge() {
+        let Selection::Mask(border) = Selection::rectangle(10.0, 10.0, 10.0, 10.0).modify(
+            SelectionModification::Border { width: 4 },
+            30,
+            30,
+        ) else {
+            panic!("Expected mask selection");
+        };
+        for x in 8..12 {
+            assert_eq!(border.get_pixel(x, 15), 255, "x = {}", x);
+        }
+        assert_eq!(border.get_pixel(7, 15), 0);
+        assert_eq!(border.get_pixel(12, 15), 0);
+        assert_eq!(border.get_pixel(15, 15), 0);
+    }
+
+    #[test]
+    fn test_feather_softens_the_edge() {
+        let Selection::Mask(feathered) = Selection::rectangle(10.0, 0.0, 10.0, 4.0).modify(
+            SelectionModification::Feather { radius: 4.0 },
+            30,
+            4,
+        ) else {
+            panic!("Expected mask selection");
+        };
+        let row: Vec<u8> = (0..30).map(|x| feathered.get_pixel(x, 1)).collect();
+        assert!(row[15] > 240);
+        assert_eq!(row[0], 0);
+        assert!(row[10] > 64 && row[10] < 192);
+        assert!(row[5..=15].windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn test_smooth_removes_specks_and_fills_holes() {
+        let mut data = vec![0u8; 20 * 20];
+        for y in 5..15 {
+            for x in 5..15 {
+                data[y * 20 + x] = 255;
+            }
+        }
+        data[10 * 20 + 10] = 0; // Hole
+        data[2 * 20 + 2] = 255; // Speck
+        let mut mask = MaskSelection::new(20, 20, data);
+        mask.modify(SelectionModification::Smooth { radius: 1 });
+
+        assert_eq!(mask.get_pixel(10, 10), 255);
+        assert_eq!(mask.get_pixel(2, 2), 0);
+        assert_eq!(mask.get_pixel(7, 7), 255);
+        // The square's corners are rounded off
+        assert_eq!(mask.get_pixel(5, 5), 0);
+    }
+
+    #[test]
+    fn test_combine_modes() {
+        let left = Selection::rectangle(0.0, 0.0, 6.0, 4.0);
+        let right = Selection::rectangle(4.0, 0.0, 6.0, 4.0);
+
+        let Selection::Mask(union) = left.combine(&right, SelectionMode::Add, 12, 4) else {
+            panic!("Expected mask selection");
+        };
+        assert_eq!(selected_count(&union), 40);
+
+        let Selection::Mask(difference) = left.combine(&right, SelectionMode::Subtract, 12, 4)
+        else {
+            panic!("Expected mask selection");
+        };
+        assert_eq!(selected_count(&difference), 16);
+        assert_eq!(difference.get_pixel(3, 1), 255);
+        assert_eq!(difference.get_pixel(4, 1), 0);
+
+        let Selection::Mask(overlap) = left.combine(&right, SelectionMode::Intersect, 12, 4) else {
+            panic!("Expected mask selection");
+        };
+        assert_eq!(selected_count(&overlap), 8);
+
+        assert_eq!(left.combine(&right, SelectionMode::Replace, 12, 4), right);
+        assert_eq!(
+            Selection::None.combine(&right, SelectionMode::Add, 12, 4),
+            right
+        );
+    }
+
+    #[test]
+    fn test_coverage_is_fractional_along_edges() {
+        let rect = Selection::rectangle(1.25, 0.0, 2.5, 4.0);
+        assert_eq!(rect.coverage(0, 1), 0);
+        assert_eq!(rect.coverage(1, 1), 191);
+        assert_eq!(rect.coverage(2, 1), 255);
+        assert_eq!(rect.coverage(3, 1), 191);
+
+        let inverted = RectangleSelection::new_inverted(1.25, 0.0, 2.5, 4.0);
+        assert_eq!(Selection::Rectangle(inverted).coverage(1, 1), 64);
+
+        let ellipse = Selection::ellipse(10.0, 10.0, 6.0, 6.0);
+        assert_eq!(ellipse.coverage(10, 10), 255);
+        assert_eq!(ellipse.coverage(0, 0), 0);
+        let diagonal_edge = ellipse.coverage(14, 14);
+        assert!(diagonal_edge > 0 && diagonal_edge < 255);
+        // Opposite edges are covered symmetrically
+        assert_eq!(ellipse.coverage(14, 14), ellipse.coverage(5, 5));
+
+        let triangle = Selection::lasso(vec![
+            Point::new(0.0, 0.0),
+            Point::new(8.0, 0.0),
+            Point::new(0.0, 8.0),
+        ]);
+        // Pixels on the diagonal are cut in half
+        assert!((96..=160).contains(&triangle.coverage(3, 4)));
+        assert_eq!(triangle.coverage(1, 1), 255);
+        assert_eq!(triangle.coverage(6, 6), 0);
+
+        assert_eq!(Selection::None.coverage(3, 3), 255);
+    }
+
+    #[test]
+    fn test_layer_coverage_follows_layer_offset() {
+        let rect = Selection::rectangle(4.0, 4.0, 4.0, 4.0);
+        let offset = Point::new(3.0, 2.0);
+
+        // Layer pixel (1, 2) lands on document pixel (4, 4)
+        assert_eq!(rect.layer_coverage(1, 2, offset), 255);
+        assert_eq!(rect.layer_coverage(4, 4, offset), 255);
+        assert_eq!(rect.layer_coverage(5, 6, offset), 0);
+
+        // Pixels pushed off the top left of the canvas
+        let shifted = Point::new(-3.0, -3.0);
+        assert_eq!(rect.layer_coverage(1, 1, shifted), 0);
+        assert_eq!(Selection::None.layer_coverage(1, 1, shifted), 255);
+        let inverted = Selection::Rectangle(RectangleSelection::new_inverted(4.0, 4.0, 4.0, 4.0));
+        assert_eq!(inverted.layer_coverage(1, 1, shifted), 255);
+    }
+
+    #[test]
+    fn test_coverage_only_skips_pixels_away_from_edges() {
+        let full = |selection: &Selection, x: u32, y: u32| {
+            let inside = (0..16)
+                .filter(|sample| {
+                    selection.contains_point(Point::new(
+                        x as f32 + ((sample % 4) as f32 + 0.5) / 4.0,
+                        y as f32 + ((sample / 4) as f32 + 0.5) / 4.0,
+                    ))
+                })
+                .count() as u32;
+            ((inside * 255 + 8) / 16) as u8
+        };
+
+        let shapes = [
+            Selection::ellipse(10.3, 9.7, 7.2, 3.1),
+            Selection::lasso(vec![
+                Point::new(1.2, 1.0),
+                Point::new(17.5, 4.3),
+                Point::new(6.0, 18.6),
+            ]),
+        ];
+        for shape in &shapes {
+            for y in 0..20 {
+                for x in 0..20 {
+                    assert_eq!(
+                        shape.coverage(x, y),
+                        full(shape, x, y),
+                        "{} at {x},{y}",
+                        shape
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_combine_soft_edges() {
+        let soft = Selection::mask(2, 1, vec![128, 255]);
+        let half = Selection::mask(2, 1, vec![128, 0]);
+
+        let Selection::Mask(union) = soft.combine(&half, SelectionMode::Add, 2, 1) else {
+            panic!("Expected mask selection");
+        };
+        assert_eq!(union.mask_data, [192, 255]);
+
+        let Selection::Mask(overlap) = soft.combine(&half, SelectionMode::Intersect, 2, 1) else {
+            panic!("Expected mask selection");
+        };
+        assert_eq!(overlap.mask_data, [64, 0]);
+
+        let Selection::Mask(difference) = soft.combine(&half, SelectionMode::Subtract, 2, 1) else {
+            panic!("Expected mask selection");
+        };
+        assert_eq!(difference.mask_data, [64, 255]);
+
+        // Nothing is selected yet, so there is nothing to subtract from
+        assert_eq!(
+            Selection::None.combine(&half, SelectionMode::Subtract, 2, 1),
+            Selection::None
+        );
+    }
+
+    #[test]
+    fn test_path_to_selection() {
+        let mut path = Path::new("Path 1".to_string());
+        path.subpaths
+            .push(Subpath::rectangle(Rect::new(2.0, 2.0, 16.0, 16.0)));
+        let single = Selection::from_path(&path, 20, 20);
+        assert!(matches!(single, Selection::Lasso(_)));
+        assert!(single.contains_point(Point::new(10.0, 10.0)));
+
+        // A second subpath inside the first cuts a hole
+        path.subpaths
+            .push(Subpath::rectangle(Rect::new(6.0, 6.0, 8.0, 8.0)));
+        let framed = Selection::from_path(&path, 20, 20);
+        assert_eq!(framed.coverage(3, 3), 255);
+        assert_eq!(framed.coverage(10, 10), 0);
+        assert_eq!(framed.coverage(0, 0), 0);
+
+        // Half-covered pixels are anti-aliased
+        let mut half = Path::new("Half".to_string());
+        half.subpaths
+            .push(Subpath::rectangle(Rect::new(0.5, 0.0, 5.0, 4.0)));
+        half.subpaths
+            .push(Subpath::rectangle(Rect::new(10.0, 0.0, 2.0, 4.0)));
+        let mask = Selection::from_path(&half, 12, 4);
+        assert_eq!(mask.coverage(0, 1), 128);
+        assert_eq!(mask.coverage(2, 1), 255);
+    }
+
+    #[test]
+    fn test_selection_to_path() {
+        let rectangle = Selection::rectangle(1.0, 2.0, 3.0, 4.0).to_path("R".to_string(), 10, 10);
+        assert_eq!(rectangle.subpaths.len(), 1);
+        assert_eq!(rectangle.bounds(), Some(Rect::new(1.0, 2.0, 3.0, 4.0)));
+
+        let ellipse = Selection::ellipse(10.0, 10.0, 5.0, 3.0).to_path("E".to_string(), 20, 20);
+        assert_eq!(ellipse.anchor_count(), 4);
+        assert!(ellipse.contains_point(Point::new(13.0, 10.0)));
+
+        // Masks are traced: a square becomes its four corners
+        let mut data = vec![0u8; 100];
+        for y in 2..6 {
+            for x in 3..8 {
+                data[y * 10 + x] = 255;
+            }
+        }
+        let traced = Selection::mask(10, 10, data).to_path("M".to_string(), 10, 10);
+        assert_eq!(traced.subpaths.len(), 1);
+        assert_eq!(traced.anchor_count(), 4);
+        assert!(traced.subpaths[0].closed);
+        assert_eq!(traced.bounds(), Some(Rect::new(3.0, 2.0, 5.0, 4.0)));
+
+        // Round-tripping a traced path selects the same pixels
+        let restored = Selection::from_path(&traced, 10, 10);
+        assert_eq!(restored.coverage(5, 3), 255);
+        assert_eq!(restored.coverage(1, 1), 0);
+    }
 }
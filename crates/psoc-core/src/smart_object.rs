@@ -109,6 +109,24 @@ impl SmartObjectManager {
                 self.cache_content(content_hash, placeholder.clone());
                 Ok(placeholder)
             }
+            SmartObjectContentType::EmbeddedSvg { svg_data, .. } => {
+                let content_hash = format!("svg:{}", self.calculate_content_hash(svg_data));
+
+                if let Some(cached_image) = self.content_cache.get(&content_hash) {
+                    return Ok(cached_image.clone());
+                }
+
+                // Rasterize at the SVG's intrinsic size
+                let size = svg_size(svg_data)?;
+                let image = rasterize_svg(
+                    svg_data,
+                    size.width.round().max(1.0) as u32,
+                    size.height.round().max(1.0) as u32,
+                )?;
+
+                self.cache_content(content_hash, image.clone());
+                Ok(image)
+            }
         }
     }
 
@@ -133,16 +151,33 @@ impl SmartObjectManager {
             return Ok(cached_result.clone());
         }
 
-        // Load the original content
-        let original_image = self.load_content(content_type)?;
+        let transformed_image = if let SmartObjectContentType::EmbeddedSvg { svg_data, .. } =
+            content_type
+        {
+            // Vector content is rasterized directly at the scaled size so it
+            // stays crisp instead of being resampled
+            let (scale_x, scale_y) = Self::effective_scale(smart_transform);
+            let width = ((original_size.width * scale_x) as u32).max(1);
+            let height = ((original_size.height * scale_y) as u32).max(1);
+            let image = rasterize_svg(svg_data, width, height)?;
+
+            let unscaled = SmartTransform {
+                scale: (1.0, 1.0),
+                ..smart_transform.clone()
+            };
+            self.apply_smart_transform(
+                image,
+                Size::new(width as f32, height as f32),
+                &unscaled,
+                target_size,
+            )?
+        } else {
+            // Load the original content
+            let original_image = self.load_content(content_type)?;
 
-        // Apply smart transformations
-        let transformed_image = self.apply_smart_transform(
-            original_image,
-            original_size,
-            smart_transform,
-            target_size,
-        )?;
+            // Apply smart transformations
+            self.apply_smart_transform(original_image, original_size, smart_transform, target_size)?
+        };
 
         // Convert to PixelData
         let pixel_data = PixelData::from_image(&transformed_image)?;
@@ -162,12 +197,7 @@ impl SmartObjectManager {
         target_size: Option<Size>,
     ) -> Result<DynamicImage> {
         // Calculate final dimensions
-        let (scale_x, scale_y) = if smart_transform.maintain_aspect_ratio {
-            let scale = smart_transform.scale.0.min(smart_transform.scale.1);
-            (scale, scale)
-        } else {
-            smart_transform.scale
-        };
+        let (scale_x, scale_y) = Self::effective_scale(smart_transform);
 
         let final_width = (original_size.width * scale_x) as u32;
         let final_height = (original_size.height * scale_y) as u32;
@@ -284,6 +314,15 @@ impl SmartObjectManager {
 
     // Private helper methods
 
+    fn effective_scale(smart_transform: &SmartTransform) -> (f32, f32) {
+        if smart_transform.maintain_aspect_ratio {
+            let scale = smart_transform.scale.0.min(smart_transform.scale.1);
+            (scale, scale)
+        } else {
+            smart_transform.scale
+        }
+    }
+
     fn calculate_content_hash(&self, data: &[u8]) -> String {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
@@ -345,6 +384,10 @@ impl SmartObjectManager {
                 "document".hash(&mut hasher);
                 self.calculate_content_hash(document_data).hash(&mut hasher);
             }
+            SmartObjectContentType::EmbeddedSvg { svg_data, .. } => {
+                "svg".hash(&mut hasher);
+                self.calculate_content_hash(svg_data).hash(&mut hasher);
+            }
         }
 
         // Hash transformation parameters
@@ -385,3 +428,46 @@ impl SmartObjectManager {
         self.render_cache.insert(key, result);
     }
 }
+
+/// Get the intrinsic size of an SVG document
+pub fn svg_size(svg_data: &[u8]) -> Result<Size> {
+    let tree = parse_svg(svg_data)?;
+    let size = tree.size();
+    Ok(Size::new(size.width(), size.height()))
+}
+
+/// Rasterize an SVG document to the given pixel size
+///
+/// The drawing is stretched to fill the requested size, so callers should
+/// keep the intrinsic aspect ratio when it matters.
+pub fn rasterize_svg(svg_data: &[u8], width: u32, height: u32) -> Result<DynamicImage> {
+    let tree = parse_svg(svg_data)?;
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| anyhow::anyhow!("Invalid SVG raster size: {}x{}", width, height))?;
+
+    let size = tree.size();
+    let transform = resvg::tiny_skia::Transform::from_scale(
+        width as f32 / size.width(),
+        height as f32 / size.height(),
+    );
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    // tiny-skia stores premultiplied alpha
+    let data = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    let image = image::RgbaImage::from_raw(width, height, data)
+        .ok_or_else(|| anyhow::anyhow!("SVG raster buffer has the wrong size"))?;
+
+    Ok(DynamicImage::ImageRgba8(image))
+}
+
+fn parse_svg(svg_data: &[u8]) -> Result<resvg::usvg::Tree> {
+    resvg::usvg::Tree::from_data(svg_data, &resvg::usvg::Options::default())
+        .context("Failed to parse SVG data")
+}
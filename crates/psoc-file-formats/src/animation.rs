@@ -0,0 +1,723 @@
+//! Animated image support
+//!
+//! This module imports animated GIF, APNG and animated WebP files as one pixel
+//! layer per frame, storing each frame's delay in the layer metadata, and
+//! exports selected layers back out as animation frames.
+
+use crate::limits::{run_with_time_limit, DecodeBudget, DecodeLimits};
+use crate::metadata::decode_image_within;
+use crate::webp::WebpOptions;
+use anyhow::{Context, Result};
+use image::{AnimationDecoder, ImageDecoder, RgbaImage};
+use psoc_core::{Document, Layer, PixelData, RenderEngine, RgbaPixel};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use tracing::{debug, info, instrument};
+
+/// Delay used for frames that do not carry one
+pub const DEFAULT_FRAME_DELAY_MS: u32 = 100;
+
+/// Canvas-sized buffers an animation decoder holds at once: the current and
+/// previous canvas plus scratch buffers for the frame being decoded
+const DECODER_WORKING_CANVASES: u64 = 4;
+
+/// Alpha below which a pixel is written as the GIF transparent color
+const GIF_ALPHA_THRESHOLD: u8 = 128;
+
+/// Animated file formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat {
+    /// Animated GIF
+    Gif,
+    /// Animated PNG
+    Apng,
+    /// Animated WebP
+    Webp,
+}
+
+impl AnimationFormat {
+    /// Get the format from a file extension
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "gif" => Some(Self::Gif),
+            "png" | "apng" => Some(Self::Apng),
+            "webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+
+    /// Get the format from a file path
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        path.as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_extension)
+    }
+
+    /// Human readable format name
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Gif => "GIF",
+            Self::Apng => "APNG",
+            Self::Webp => "WebP",
+        }
+    }
+}
+
+/// A single decoded animation frame covering the whole canvas
+#[derive(Debug, Clone)]
+pub struct AnimationFrame {
+    /// Frame pixels
+    pub image: RgbaImage,
+    /// How long the frame is shown, in milliseconds
+    pub delay_ms: u32,
+}
+
+/// How many times an exported animation plays
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoopCount {
+    /// Loop forever
+    #[default]
+    Infinite,
+    /// Play the given number of times in total
+    Finite(u16),
+}
+
+/// Palette options for GIF export
+#[derive(Debug, Clone)]
+pub struct GifPaletteOptions {
+    /// Maximum palette size (2-256), including the transparent entry
+    pub max_colors: u16,
+    /// Share one palette across all frames instead of one per frame
+    pub global_palette: bool,
+    /// NeuQuant sampling factor (1-30, where 1 is slowest and best)
+    pub quantizer_speed: i32,
+}
+
+impl Default for GifPaletteOptions {
+    fn default() -> Self {
+        Self {
+            max_colors: 256,
+            global_palette: false,
+            quantizer_speed: 10,
+        }
+    }
+}
+
+/// Animation export options
+#[derive(Debug, Clone)]
+pub struct AnimationOptions {
+    /// How many times the animation plays
+    pub loop_count: LoopCount,
+    /// Delay for layers without a stored frame delay, in milliseconds
+    pub default_delay_ms: u32,
+    /// GIF palette options
+    pub palette: GifPaletteOptions,
+    /// WebP frame encoding options (the ICC profile is ignored)
+    pub webp: WebpOptions,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            loop_count: LoopCount::Infinite,
+            default_delay_ms: DEFAULT_FRAME_DELAY_MS,
+            palette: GifPaletteOptions::default(),
+            webp: WebpOptions::lossless(),
+        }
+    }
+}
+
+impl AnimationOptions {
+    /// Set the loop count
+    pub fn with_loop_count(mut self, loop_count: LoopCount) -> Self {
+        self.loop_count = loop_count;
+        self
+    }
+
+    /// Set the GIF palette size, clamped to 2-256
+    pub fn with_max_colors(mut self, max_colors: u16) -> Self {
+        self.palette.max_colors = max_colors.clamp(2, 256);
+        self
+    }
+}
+
+/// Check whether a file holds more than one frame
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn is_animated<P: AsRef<Path>>(path: P) -> Result<bool> {
+    let path = path.as_ref();
+    let Some(format) = AnimationFormat::from_path(path) else {
+        return Ok(false);
+    };
+    let reader = open_reader(path, format)?;
+
+    let animated = match format {
+        AnimationFormat::Gif => {
+            let decoder =
+                image::codecs::gif::GifDecoder::new(reader).context("Failed to read GIF")?;
+            decoder.into_frames().take(2).count() > 1
+        }
+        AnimationFormat::Apng => image::codecs::png::PngDecoder::new(reader)
+            .context("Failed to read PNG")?
+            .is_apng()
+            .context("Failed to read PNG animation control")?,
+        AnimationFormat::Webp => image::codecs::webp::WebPDecoder::new(reader)
+            .context("Failed to read WebP")?
+            .has_animation(),
+    };
+
+    Ok(animated)
+}
+
+/// Decode every frame of an animated GIF, APNG or WebP file
+///
+/// Still images decode to a single frame.
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_animation_frames<P: AsRef<Path>>(path: P) -> Result<Vec<AnimationFrame>> {
+    load_animation_frames_with_limits(path, &DecodeLimits::default())
+}
+
+/// Decode every frame of an animation, rejecting frames over the given limits
+///
+/// The canvas size is checked before decoding and every frame is counted
+/// against one shared memory budget.
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_animation_frames_with_limits<P: AsRef<Path>>(
+    path: P,
+    limits: &DecodeLimits,
+) -> Result<Vec<AnimationFrame>> {
+    let path = path.as_ref().to_path_buf();
+    let limits = *limits;
+    run_with_time_limit(limits.max_decode_time, move || {
+        load_frames_within(&path, &mut DecodeBudget::new(&limits))
+    })
+}
+
+fn load_frames_within(path: &Path, budget: &mut DecodeBudget) -> Result<Vec<AnimationFrame>> {
+    let format = AnimationFormat::from_path(path)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported animation format: {}", path.display()))?;
+    debug!(
+        "Loading {} animation from: {}",
+        format.name(),
+        path.display()
+    );
+
+    let reader = open_reader(path, format)?;
+    let (frames, working) = match format {
+        AnimationFormat::Gif => {
+            let mut decoder =
+                image::codecs::gif::GifDecoder::new(reader).context("Failed to read GIF")?;
+            let working = limit_decoder(&mut decoder, budget, format)?;
+            (decoder.into_frames(), working)
+        }
+        AnimationFormat::Apng => {
+            let mut decoder =
+                image::codecs::png::PngDecoder::new(reader).context("Failed to read PNG")?;
+            if !decoder
+                .is_apng()
+                .context("Failed to read PNG animation control")?
+            {
+                return still_frame(path, format, budget);
+            }
+            let working = limit_decoder(&mut decoder, budget, format)?;
+            let decoder = decoder.apng().context("Failed to read APNG")?;
+            (decoder.into_frames(), working)
+        }
+        AnimationFormat::Webp => {
+            let mut decoder =
+                image::codecs::webp::WebPDecoder::new(reader).context("Failed to read WebP")?;
+            if !decoder.has_animation() {
+                return still_frame(path, format, budget);
+            }
+            let working = limit_decoder(&mut decoder, budget, format)?;
+            (decoder.into_frames(), working)
+        }
+    };
+
+    let mut decoded = Vec::new();
+    for frame in frames {
+        budget.check_time()?;
+        let frame = frame.with_context(|| format!("Failed to decode {} frames", format.name()))?;
+        let (width, height) = frame.buffer().dimensions();
+        budget.reserve_pixels(width, height, 4)?;
+
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        decoded.push(AnimationFrame {
+            delay_ms: numer.checked_div(denom).unwrap_or(0),
+            image: frame.into_buffer(),
+        });
+    }
+    budget.release(working);
+
+    if decoded.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Animation has no frames: {}",
+            path.display()
+        ));
+    }
+
+    debug!("Decoded {} animation frames", decoded.len());
+    Ok(decoded)
+}
+
+/// Load an animated file as a document with one layer per frame
+///
+/// The first frame becomes the bottom layer and each frame's delay is stored
+/// in its layer metadata.
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_animation_document<P: AsRef<Path>>(path: P) -> Result<Document> {
+    load_animation_document_with_limits(path, &DecodeLimits::default())
+}
+
+/// Load an animated file as layers, rejecting frames over the given limits
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_animation_document_with_limits<P: AsRef<Path>>(
+    path: P,
+    limits: &DecodeLimits,
+) -> Result<Document> {
+    let path = path.as_ref().to_path_buf();
+    let limits = *limits;
+    run_with_time_limit(limits.max_decode_time, move || {
+        load_document_within(&path, &mut DecodeBudget::new(&limits))
+    })
+}
+
+fn load_document_within(path: &Path, budget: &mut DecodeBudget) -> Result<Document> {
+    let frames = load_frames_within(path, budget)?;
+    let title = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Untitled")
+        .to_string();
+
+    let (width, height) = frames[0].image.dimensions();
+    let mut document = Document::new(title, width, height);
+    for (index, frame) in frames.into_iter().enumerate() {
+        // Each layer keeps its own copy of the frame's pixels
+        budget.reserve_pixels(frame.image.width(), frame.image.height(), 4)?;
+        let image = image::DynamicImage::ImageRgba8(frame.image);
+        let mut layer = Layer::new_pixel(
+            format!("Frame {}", index + 1),
+            image.width(),
+            image.height(),
+        );
+        layer.pixel_data = Some(PixelData::from_image(&image)?);
+        layer.metadata.frame_delay_ms = Some(frame.delay_ms);
+        document.add_layer(layer);
+    }
+    document.set_active_layer(document.layers.len() - 1)?;
+    document.mark_clean();
+
+    info!(
+        frames = document.layers.len(),
+        size = format!("{}x{}", width, height),
+        "Loaded animation as layers"
+    );
+
+    Ok(document)
+}
+
+/// Render the given layers of a document as canvas-sized animation frames
+///
+/// Each layer is rendered on its own over a transparent background. Layers
+/// without a stored frame delay use `default_delay_ms`.
+pub fn document_frames(
+    document: &Document,
+    layer_indices: &[usize],
+    default_delay_ms: u32,
+) -> Result<Vec<AnimationFrame>> {
+    let mut render_engine = RenderEngine::new();
+    let mut frame_document = Document::new(
+        document.metadata.title.clone(),
+        document.size.width as u32,
+        document.size.height as u32,
+    );
+    frame_document.background_color = RgbaPixel::transparent();
+
+    layer_indices
+        .iter()
+        .map(|&index| {
+            let layer = document
+                .layers
+                .get(index)
+                .ok_or_else(|| anyhow::anyhow!("Layer index {} out of bounds", index))?;
+
+            let mut frame_layer = layer.clone();
+            frame_layer.visible = true;
+            frame_document.layers = vec![frame_layer];
+
+            let image = render_engine
+                .render_document(&frame_document)?
+                .to_image()?
+                .to_rgba8();
+            Ok(AnimationFrame {
+                image,
+                delay_ms: layer.metadata.frame_delay_ms.unwrap_or(default_delay_ms),
+            })
+        })
+        .collect()
+}
+
+/// Export the given layers of a document as an animation
+///
+/// The format is chosen from the file extension.
+#[instrument(skip_all, fields(path = %path.as_ref().display(), frames = layer_indices.len()))]
+pub fn export_animation<P: AsRef<Path>>(
+    document: &Document,
+    layer_indices: &[usize],
+    path: P,
+    options: &AnimationOptions,
+) -> Result<()> {
+    let frames = document_frames(document, layer_indices, options.default_delay_ms)?;
+    save_animation(&frames, path, options)
+}
+
+/// Save frames as an animated GIF, APNG or WebP file
+#[instrument(skip_all, fields(path = %path.as_ref().display(), frames = frames.len()))]
+pub fn save_animation<P: AsRef<Path>>(
+    frames: &[AnimationFrame],
+    path: P,
+    options: &AnimationOptions,
+) -> Result<()> {
+    let path = path.as_ref();
+    let format = AnimationFormat::from_path(path)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported animation format: {}", path.display()))?;
+
+    let data = encode_animation(frames, format, options)?;
+    std::fs::write(path, data).with_context(|| {
+        format!(
+            "Failed to save {} animation to: {}",
+            format.name(),
+            path.display()
+        )
+    })?;
+
+    info!(
+        format = format.name(),
+        frames = frames.len(),
+        "Successfully saved animation"
+    );
+
+    Ok(())
+}
+
+/// Encode frames into memory
+///
+/// All frames must have the same dimensions.
+pub fn encode_animation(
+    frames: &[AnimationFrame],
+    format: AnimationFormat,
+    options: &AnimationOptions,
+) -> Result<Vec<u8>> {
+    let first = frames
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("An animation needs at least one frame"))?;
+    let (width, height) = first.image.dimensions();
+    if frames
+        .iter()
+        .any(|frame| frame.image.dimensions() != (width, height))
+    {
+        return Err(anyhow::anyhow!(
+            "All animation frames must be {}x{}",
+            width,
+            height
+        ));
+    }
+
+    match format {
+        AnimationFormat::Gif => encode_gif(frames, width, height, options),
+        AnimationFormat::Apng => encode_apng(frames, width, height, options),
+        AnimationFormat::Webp => encode_animated_webp(frames, width, height, options),
+    }
+}
+
+fn open_reader(path: &Path, format: AnimationFormat) -> Result<BufReader<File>> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open {} file: {}", format.name(), path.display()))?;
+    Ok(BufReader::new(file))
+}
+
+/// Check the canvas size of an animation and reserve the decoder's working memory
+///
+/// Returns the reserved bytes, which are released once decoding finishes.
+fn limit_decoder(
+    decoder: &mut impl ImageDecoder,
+    budget: &mut DecodeBudget,
+    format: AnimationFormat,
+) -> Result<u64> {
+    let (width, height) = decoder.dimensions();
+    budget.reserve_pixels(width, height, 4 * DECODER_WORKING_CANVASES)?;
+    let working = decoder
+        .total_bytes()
+        .saturating_mul(DECODER_WORKING_CANVASES);
+
+    let mut limits = budget.image_limits();
+    limits.max_alloc = Some(working);
+    decoder
+        .set_limits(limits)
+        .with_context(|| format!("{} animation exceeds the decode limits", format.name()))?;
+    Ok(working)
+}
+
+fn still_frame(
+    path: &Path,
+    format: AnimationFormat,
+    budget: &mut DecodeBudget,
+) -> Result<Vec<AnimationFrame>> {
+    let decoded = decode_image_within(path, format.name(), budget)?;
+    let (width, height) = (decoded.image.width(), decoded.image.height());
+    budget.reserve_pixels(width, height, 4)?;
+    Ok(vec![AnimationFrame {
+        image: decoded.image.into_rgba8(),
+        delay_ms: DEFAULT_FRAME_DELAY_MS,
+    }])
+}
+
+fn encode_gif(
+    frames: &[AnimationFrame],
+    width: u32,
+    height: u32,
+    options: &AnimationOptions,
+) -> Result<Vec<u8>> {
+    let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(anyhow::anyhow!(
+            "GIF frames are limited to 65535x65535, got {}x{}",
+            width,
+            height
+        ));
+    };
+
+    let transparent = frames.iter().any(|frame| {
+        frame
+            .image
+            .pixels()
+            .any(|pixel| pixel[3] < GIF_ALPHA_THRESHOLD)
+    });
+    let max_colors = options.palette.max_colors.clamp(2, 256) as usize;
+    let colors = if transparent {
+        max_colors - 1
+    } else {
+        max_colors
+    };
+    let speed = options.palette.quantizer_speed.clamp(1, 30);
+
+    let global = options
+        .palette
+        .global_palette
+        .then(|| quantize(frames.iter().map(|frame| &frame.image), colors, speed));
+    let global_palette = global
+        .as_ref()
+        .map(|quantizer| gif_palette(quantizer, transparent))
+        .unwrap_or_default();
+
+    let mut buffer = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut buffer, width, height, &global_palette)
+            .context("Failed to start GIF encoder")?;
+        match options.loop_count {
+            LoopCount::Infinite => encoder.set_repeat(gif::Repeat::Infinite),
+            // The NETSCAPE extension counts repeats after the first play
+            LoopCount::Finite(plays) if plays > 1 => {
+                encoder.set_repeat(gif::Repeat::Finite(plays - 1))
+            }
+            LoopCount::Finite(_) => Ok(()),
+        }
+        .context("Failed to write GIF loop count")?;
+
+        for frame in frames {
+            let local;
+            let (quantizer, palette) = match &global {
+                Some(quantizer) => (quantizer, None),
+                None => {
+                    local = quantize(std::iter::once(&frame.image), colors, speed);
+                    (&local, Some(gif_palette(&local, transparent)))
+                }
+            };
+            let transparent_index = transparent.then_some(quantizer.color_map_rgb().len() / 3);
+
+            let indices: Vec<u8> = frame
+                .image
+                .pixels()
+                .map(|pixel| match transparent_index {
+                    Some(index) if pixel[3] < GIF_ALPHA_THRESHOLD => index as u8,
+                    _ => quantizer.index_of(&[pixel[0], pixel[1], pixel[2], 255]) as u8,
+                })
+                .collect();
+
+            let gif_frame = gif::Frame {
+                width,
+                height,
+                buffer: indices.into(),
+                palette,
+                transparent: transparent_index.map(|index| index as u8),
+                // Frames cover the whole canvas, so transparent pixels must not show the previous one
+                dispose: if transparent {
+                    gif::DisposalMethod::Background
+                } else {
+                    gif::DisposalMethod::Keep
+                },
+                delay: (frame.delay_ms.div_ceil(10)).min(u16::MAX as u32) as u16,
+                ..gif::Frame::default()
+            };
+            encoder
+                .write_frame(&gif_frame)
+                .context("Failed to write GIF frame")?;
+        }
+    }
+
+    Ok(buffer)
+}
+
+/// Build a NeuQuant palette from the opaque pixels of the given images
+fn quantize<'a>(
+    images: impl Iterator<Item = &'a RgbaImage>,
+    colors: usize,
+    speed: i32,
+) -> color_quant::NeuQuant {
+    let mut pixels: Vec<u8> = images
+        .flat_map(|image| image.pixels())
+        .filter(|pixel| pixel[3] >= GIF_ALPHA_THRESHOLD)
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+        .collect();
+    if pixels.is_empty() {
+        pixels.extend_from_slice(&[0, 0, 0, 255]);
+    }
+    color_quant::NeuQuant::new(speed, colors, &pixels)
+}
+
+/// RGB palette for a quantizer, with a trailing entry for the transparent color
+fn gif_palette(quantizer: &color_quant::NeuQuant, transparent: bool) -> Vec<u8> {
+    let mut palette = quantizer.color_map_rgb();
+    if transparent {
+        palette.extend_from_slice(&[0, 0, 0]);
+    }
+    palette
+}
+
+fn encode_apng(
+    frames: &[AnimationFrame],
+    width: u32,
+    height: u32,
+    options: &AnimationOptions,
+) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(BufWriter::new(&mut buffer), width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let plays = match options.loop_count {
+            LoopCount::Infinite => 0,
+            LoopCount::Finite(plays) => plays.max(1) as u32,
+        };
+        encoder
+            .set_animated(frames.len() as u32, plays)
+            .context("Failed to set APNG animation control")?;
+
+        let mut writer = encoder
+            .write_header()
+            .context("Failed to write APNG header")?;
+        for frame in frames {
+            writer
+                .set_frame_delay(frame.delay_ms.min(u16::MAX as u32) as u16, 1000)
+                .context("Failed to set APNG frame delay")?;
+            writer
+                .write_image_data(frame.image.as_raw())
+                .context("Failed to write APNG frame")?;
+        }
+        writer.finish().context("Failed to finish APNG")?;
+    }
+
+    Ok(buffer)
+}
+
+fn encode_animated_webp(
+    frames: &[AnimationFrame],
+    width: u32,
+    height: u32,
+    options: &AnimationOptions,
+) -> Result<Vec<u8>> {
+    let mut config = ::webp::WebPConfig::new()
+        .map_err(|_| anyhow::anyhow!("Failed to create WebP encoder configuration"))?;
+    config.lossless = options.webp.lossless as i32;
+    config.quality = options.webp.quality.min(100) as f32;
+
+    let mut encoder = ::webp::AnimEncoder::new(width, height, &config);
+    encoder.set_loop_count(match options.loop_count {
+        LoopCount::Infinite => 0,
+        LoopCount::Finite(plays) => plays.max(1) as i32,
+    });
+
+    // WebP stores the time each frame starts rather than its duration
+    let mut timestamp = 0i32;
+    for frame in frames {
+        encoder.add_frame(::webp::AnimFrame::from_rgba(
+            frame.image.as_raw(),
+            width,
+            height,
+            timestamp,
+        ));
+        timestamp = timestamp.saturating_add(frame.delay_ms.min(i32::MAX as u32) as i32);
+    }
+
+    let memory = encoder
+        .try_encode()
+        .map_err(|e| anyhow::anyhow!("Failed to encode animated WebP: {:?}", e))?;
+    Ok(memory.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(color: [u8; 4], delay_ms: u32) -> AnimationFrame {
+        AnimationFrame {
+            image: RgbaImage::from_pixel(8, 6, image::Rgba(color)),
+            delay_ms,
+        }
+    }
+
+    #[test]
+    fn test_animation_format_from_path() {
+        assert_eq!(
+            AnimationFormat::from_path("a.gif"),
+            Some(AnimationFormat::Gif)
+        );
+        assert_eq!(
+            AnimationFormat::from_path("a.PNG"),
+            Some(AnimationFormat::Apng)
+        );
+        assert_eq!(
+            AnimationFormat::from_path("a.webp"),
+            Some(AnimationFormat::Webp)
+        );
+        assert_eq!(AnimationFormat::from_path("a.jpg"), None);
+    }
+
+    #[test]
+    fn test_encode_rejects_mismatched_frames() {
+        let mut frames = vec![frame([255, 0, 0, 255], 100), frame([0, 255, 0, 255], 100)];
+        frames[1].image = RgbaImage::new(4, 4);
+        assert!(
+            encode_animation(&frames, AnimationFormat::Gif, &AnimationOptions::default()).is_err()
+        );
+        assert!(
+            encode_animation(&[], AnimationFormat::Apng, &AnimationOptions::default()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_gif_palette_respects_max_colors() -> Result<()> {
+        let frames = vec![frame([255, 0, 0, 255], 50), frame([0, 0, 0, 0], 50)];
+        let options = AnimationOptions::default().with_max_colors(16);
+        let data = encode_animation(&frames, AnimationFormat::Gif, &options)?;
+
+        let mut decoder = gif::DecodeOptions::new().read_info(data.as_slice())?;
+        while let Some(gif_frame) = decoder.read_next_frame()? {
+            let palette = gif_frame.palette.as_ref().expect("local palette");
+            assert!(palette.len() / 3 <= 16);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+//! Multi-scale asset export
+//!
+//! Every asset stored on a document is rendered once at document resolution
+//! and then written for each of its presets, resampled with a Lanczos filter
+//! to the preset scale.
+
+use crate::{layer_export, ImageIO};
+use anyhow::{Context, Result};
+use image::imageops::{self, FilterType};
+use image::DynamicImage;
+use psoc_core::{Asset, AssetPreset, AssetSource, Document, PixelData, RenderEngine};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, instrument, warn};
+
+/// Render an asset at document resolution
+///
+/// Layer assets are trimmed to their content and slices are clipped to the
+/// canvas. `None` is returned when nothing is left to export.
+pub fn render_asset(document: &Document, asset: &Asset) -> Result<Option<PixelData>> {
+    match &asset.source {
+        AssetSource::Layer(layer_id) => {
+            let index = document
+                .layer_index_by_id(*layer_id)
+                .with_context(|| format!("Layer of asset '{}' no longer exists", asset.name))?;
+            layer_export::render_layer(document, index, true)
+        }
+        AssetSource::Slice(rect) => {
+            let canvas_width = document.size.width.max(0.0);
+            let canvas_height = document.size.height.max(0.0);
+            let left = rect.x.max(0.0).round();
+            let top = rect.y.max(0.0).round();
+            let right = (rect.x + rect.width).min(canvas_width).round();
+            let bottom = (rect.y + rect.height).min(canvas_height).round();
+            if right <= left || bottom <= top {
+                return Ok(None);
+            }
+
+            let engine = RenderEngine::new();
+            let pixels = engine.render_region(
+                document,
+                left as u32,
+                top as u32,
+                (right - left) as u32,
+                (bottom - top) as u32,
+            )?;
+            Ok(Some(pixels))
+        }
+    }
+}
+
+/// File name for an asset written with a preset
+///
+/// The asset name and suffix are sanitized like layer file names, so they
+/// cannot point outside the export directory.
+pub fn asset_file_name(index: usize, asset: &Asset, preset: &AssetPreset) -> String {
+    let stem = layer_export::layer_file_name(
+        "{name}",
+        "",
+        index,
+        &format!("{}{}", asset.name, preset.suffix),
+    );
+    format!("{}.{}", stem, preset.format.extension())
+}
+
+/// Resample an image with premultiplied alpha
+///
+/// Filtering straight alpha lets the color of transparent pixels bleed into
+/// the edges of the visible ones.
+fn resize_premultiplied(image: &DynamicImage, width: u32, height: u32) -> DynamicImage {
+    let mut premultiplied = image.to_rgba32f();
+    for pixel in premultiplied.pixels_mut() {
+        let alpha = pixel[3];
+        for channel in &mut pixel.0[..3] {
+            *channel *= alpha;
+        }
+    }
+
+    let mut resized = imageops::resize(&premultiplied, width, height, FilterType::Lanczos3);
+    for pixel in resized.pixels_mut() {
+        let alpha = pixel[3].clamp(0.0, 1.0);
+        for channel in &mut pixel.0[..3] {
+            *channel = if alpha > 0.0 {
+                (*channel / alpha).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+        }
+        pixel[3] = alpha;
+    }
+
+    DynamicImage::ImageRgba8(DynamicImage::ImageRgba32F(resized).to_rgba8())
+}
+
+/// Export every asset of a document at each of its presets
+///
+/// Returns the written paths. Assets that render empty are skipped.
+#[instrument(skip_all, fields(directory = %directory.as_ref().display()))]
+pub fn export_assets<P: AsRef<Path>>(document: &Document, directory: P) -> Result<Vec<PathBuf>> {
+    let directory = directory.as_ref();
+    std::fs::create_dir_all(directory)
+        .with_context(|| format!("Failed to create directory: {}", directory.display()))?;
+
+    let mut written = Vec::new();
+    let mut used_names = HashSet::new();
+    for (index, asset) in document.assets.iter().enumerate() {
+        let Some(pixels) = render_asset(document, asset)? else {
+            warn!("Skipping empty asset '{}'", asset.name);
+            continue;
+        };
+        let source = pixels.to_image()?;
+
+        for preset in &asset.presets {
+            let file_name = asset_file_name(index, asset, preset);
+            if !used_names.insert(file_name.to_lowercase()) {
+                return Err(anyhow::anyhow!(
+                    "Asset output '{}' would be written more than once",
+                    file_name
+                ));
+            }
+
+            let (width, height) = preset.scaled_size(source.width(), source.height());
+            let image = if (width, height) == (source.width(), source.height()) {
+                source.clone()
+            } else {
+                resize_premultiplied(&source, width, height)
+            };
+
+            let path = directory.join(&file_name);
+            debug!(
+                "Exporting asset '{}' at {}x to: {}",
+                asset.name,
+                preset.scale,
+                path.display()
+            );
+            ImageIO::save_image(&image, &path)?;
+            written.push(path);
+        }
+    }
+
+    info!(
+        assets = document.assets.len(),
+        files = written.len(),
+        "Successfully exported assets"
+    );
+
+    Ok(written)
+}
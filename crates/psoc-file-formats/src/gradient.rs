@@ -0,0 +1,715 @@
+//! Gradient preset files
+//!
+//! This module imports gradient libraries from other editors and exports
+//! single gradients:
+//! - GIMP gradients (`.ggr`), import and export
+//! - Photoshop gradient libraries (`.grd`, version 5 descriptor format),
+//!   import only
+//!
+//! Stops and midpoints map directly onto [`ColorStop`]. Both formats can
+//! blend each segment differently while a [`Gradient`] has one
+//! [`InterpolationMethod`], so the method is chosen from the segments:
+//! HSV segments select [`InterpolationMethod::Hsv`], sine segments and
+//! Photoshop smoothness select [`InterpolationMethod::Smooth`].
+
+use crate::palette::{lab_to_rgba, to_u8, Reader};
+use anyhow::{bail, Context, Result};
+use psoc_core::{ColorStop, Gradient, HsvColor, InterpolationMethod, RgbaPixel};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::{debug, info, instrument, warn};
+
+/// Supported gradient preset formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GradientFormat {
+    /// GIMP gradient
+    Ggr,
+    /// Photoshop gradient library
+    Grd,
+}
+
+impl GradientFormat {
+    /// Detect the gradient format from a file extension
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "ggr" => Some(Self::Ggr),
+            "grd" => Some(Self::Grd),
+            _ => None,
+        }
+    }
+
+    /// File extension without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Ggr => "ggr",
+            Self::Grd => "grd",
+        }
+    }
+}
+
+/// Read every gradient in a preset file
+///
+/// Gradients without a name use the file stem.
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn import_gradients<P: AsRef<Path>>(path: P) -> Result<Vec<Gradient>> {
+    let path = path.as_ref();
+    let format = GradientFormat::from_path(path)
+        .with_context(|| format!("Unsupported gradient file: {}", path.display()))?;
+    let data = std::fs::read(path)
+        .with_context(|| format!("Failed to read gradient file: {}", path.display()))?;
+    let fallback_name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Imported Gradient".to_string());
+
+    let mut gradients = match format {
+        GradientFormat::Ggr => vec![decode_ggr(&data)?],
+        GradientFormat::Grd => decode_grd(&data)?,
+    };
+    if gradients.is_empty() {
+        bail!("No gradients found in: {}", path.display());
+    }
+    for gradient in &mut gradients {
+        if gradient.name.trim().is_empty() {
+            gradient.name = fallback_name.clone();
+        }
+    }
+
+    info!(
+        count = gradients.len(),
+        format = ?format,
+        "Successfully imported gradients"
+    );
+    Ok(gradients)
+}
+
+/// Export a gradient as a GIMP `.ggr` file
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn export_ggr<P: AsRef<Path>>(gradient: &Gradient, path: P) -> Result<()> {
+    let path = path.as_ref();
+    debug!(
+        "Exporting gradient '{}' to: {}",
+        gradient.name,
+        path.display()
+    );
+    std::fs::write(path, encode_ggr(gradient))
+        .with_context(|| format!("Failed to write gradient file: {}", path.display()))?;
+    info!("Successfully exported gradient '{}'", gradient.name);
+    Ok(())
+}
+
+/// Build a gradient from stops in position order
+fn gradient_from_stops(
+    name: String,
+    stops: Vec<ColorStop>,
+    interpolation: InterpolationMethod,
+) -> Gradient {
+    Gradient {
+        name,
+        stops: stops
+            .into_iter()
+            .enumerate()
+            .map(|(key, stop)| (key as u32, stop))
+            .collect::<BTreeMap<_, _>>(),
+        interpolation,
+        ..Gradient::default()
+    }
+}
+
+/// Stop with a midpoint, dropping midpoints that are effectively centered
+fn stop_with_midpoint(position: f32, color: RgbaPixel, midpoint: f32) -> ColorStop {
+    if (midpoint - 0.5).abs() < 1e-3 {
+        ColorStop::new(position, color)
+    } else {
+        ColorStop::with_midpoint(position, color, midpoint.clamp(0.01, 0.99))
+    }
+}
+
+fn float_color(values: &[f32]) -> RgbaPixel {
+    RgbaPixel::new(
+        to_u8(values[0]),
+        to_u8(values[1]),
+        to_u8(values[2]),
+        to_u8(values[3]),
+    )
+}
+
+/// Parse a GIMP gradient
+pub fn decode_ggr(data: &[u8]) -> Result<Gradient> {
+    let text = String::from_utf8_lossy(data);
+    let mut lines = text.lines().map(str::trim);
+    if lines.next().map(|line| line.trim_start_matches('\u{feff}')) != Some("GIMP Gradient") {
+        bail!("Missing 'GIMP Gradient' header");
+    }
+
+    let mut line = lines.next().context("Missing segment count")?;
+    let mut name = String::new();
+    if let Some(value) = line.strip_prefix("Name:") {
+        name = value.trim().to_string();
+        line = lines.next().context("Missing segment count")?;
+    }
+    let count: usize = line
+        .parse()
+        .with_context(|| format!("Invalid segment count: {}", line))?;
+
+    let mut stops: Vec<ColorStop> = Vec::new();
+    let mut has_hsv = false;
+    let mut all_sine = true;
+    for index in 0..count {
+        let line = lines
+            .next()
+            .with_context(|| format!("Missing segment {} of {}", index + 1, count))?;
+        let values: Vec<f32> = line
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .with_context(|| format!("Invalid segment {}: {}", index + 1, line))?;
+        if values.len() < 11 {
+            bail!("Invalid segment {}: {}", index + 1, line);
+        }
+
+        let (left, middle, right) = (values[0], values[1], values[2]);
+        let left_color = float_color(&values[3..7]);
+        let right_color = float_color(&values[7..11]);
+        let blending = values.get(11).copied().unwrap_or(0.0) as u32;
+        let coloring = values.get(12).copied().unwrap_or(0.0) as u32;
+
+        has_hsv |= coloring != 0;
+        all_sine &= blending == 2;
+        if blending >= 3 {
+            warn!(
+                "GIMP gradient blending type {} is imported as linear",
+                blending
+            );
+        }
+
+        let span = right - left;
+        let midpoint = if span > 0.0 {
+            (middle - left) / span
+        } else {
+            0.5
+        };
+        stops.push(stop_with_midpoint(left, left_color, midpoint));
+
+        // Segments share end points; keep both colors only at hard edges
+        let next_left = lines.clone().next().and_then(|next| {
+            let values: Vec<f32> = next
+                .split_whitespace()
+                .take(7)
+                .filter_map(|value| value.parse().ok())
+                .collect();
+            (values.len() == 7).then(|| float_color(&values[3..7]))
+        });
+        if index + 1 == count || next_left != Some(right_color) {
+            stops.push(ColorStop::new(right, right_color));
+        }
+    }
+
+    let interpolation = if has_hsv {
+        InterpolationMethod::Hsv
+    } else if all_sine && count > 0 {
+        InterpolationMethod::Smooth
+    } else {
+        InterpolationMethod::Linear
+    };
+    if stops.is_empty() {
+        bail!("Gradient has no segments");
+    }
+    Ok(gradient_from_stops(name, stops, interpolation))
+}
+
+/// Serialize a gradient as a GIMP gradient
+///
+/// GIMP segments must cover the whole range, so the first and last colors
+/// are extended to the ends.
+pub fn encode_ggr(gradient: &Gradient) -> Vec<u8> {
+    let mut stops: Vec<ColorStop> = gradient.sorted_stops().into_iter().cloned().collect();
+    if let Some(first) = stops.first().cloned() {
+        if first.position > 0.0 {
+            stops.insert(0, ColorStop::new(0.0, first.color));
+        }
+    }
+    if let Some(last) = stops.last().cloned() {
+        if last.position < 1.0 || stops.len() == 1 {
+            stops.push(ColorStop::new(1.0, last.color));
+        }
+    }
+
+    let (blending, coloring) = match gradient.interpolation {
+        InterpolationMethod::Linear => (0, 0),
+        InterpolationMethod::Smooth => (2, 0),
+        InterpolationMethod::Hsl | InterpolationMethod::Hsv => (0, 1),
+    };
+    let channels = |color: RgbaPixel| {
+        [color.r, color.g, color.b, color.a]
+            .map(|channel| format!("{:.6}", channel as f32 / 255.0))
+            .join(" ")
+    };
+
+    let segments: Vec<String> = stops
+        .windows(2)
+        .filter(|pair| pair[1].position > pair[0].position)
+        .map(|pair| {
+            let (left, right) = (pair[0].position, pair[1].position);
+            let middle = left + pair[0].midpoint.unwrap_or(0.5) * (right - left);
+            format!(
+                "{:.6} {:.6} {:.6} {} {} {} {}",
+                left,
+                middle,
+                right,
+                channels(pair[0].color),
+                channels(pair[1].color),
+                blending,
+                coloring
+            )
+        })
+        .collect();
+
+    let mut text = String::from("GIMP Gradient\n");
+    text.push_str(&format!("Name: {}\n", gradient.name.replace('\n', " ")));
+    text.push_str(&format!("{}\n", segments.len()));
+    for segment in segments {
+        text.push_str(&segment);
+        text.push('\n');
+    }
+    text.into_bytes()
+}
+
+/// Value in a Photoshop action descriptor
+#[derive(Debug, Clone)]
+enum DescriptorValue {
+    Descriptor(Descriptor),
+    List(Vec<DescriptorValue>),
+    Number(f64),
+    Text(String),
+    Enum(String),
+    Other,
+}
+
+/// Photoshop action descriptor: a class with keyed values
+#[derive(Debug, Clone, Default)]
+struct Descriptor {
+    items: Vec<(String, DescriptorValue)>,
+}
+
+impl Descriptor {
+    fn get(&self, key: &str) -> Option<&DescriptorValue> {
+        self.items
+            .iter()
+            .find(|(item_key, _)| item_key.trim_end() == key)
+            .map(|(_, value)| value)
+    }
+
+    fn number(&self, key: &str) -> Option<f64> {
+        match self.get(key)? {
+            DescriptorValue::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn text(&self, key: &str) -> Option<&str> {
+        match self.get(key)? {
+            DescriptorValue::Text(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn enumerated(&self, key: &str) -> Option<&str> {
+        match self.get(key)? {
+            DescriptorValue::Enum(value) => Some(value.trim_end()),
+            _ => None,
+        }
+    }
+
+    fn descriptor(&self, key: &str) -> Option<&Descriptor> {
+        match self.get(key)? {
+            DescriptorValue::Descriptor(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn descriptors(&self, key: &str) -> Vec<&Descriptor> {
+        match self.get(key) {
+            Some(DescriptorValue::List(values)) => values
+                .iter()
+                .filter_map(|value| match value {
+                    DescriptorValue::Descriptor(descriptor) => Some(descriptor),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Nesting limit for descriptors, guarding against malicious files
+const MAX_DESCRIPTOR_DEPTH: usize = 32;
+
+fn read_unicode(reader: &mut Reader) -> Result<String> {
+    let length = reader.u32()? as usize;
+    if length * 2 > reader.remaining() {
+        bail!("String length {} exceeds file size", length);
+    }
+    reader.utf16(length)
+}
+
+/// Class or key identifier: a length-prefixed string, or a 4-byte code
+fn read_id(reader: &mut Reader) -> Result<String> {
+    let length = match reader.u32()? as usize {
+        0 => 4,
+        length => length,
+    };
+    Ok(String::from_utf8_lossy(reader.bytes(length)?).into_owned())
+}
+
+fn read_descriptor(reader: &mut Reader, depth: usize) -> Result<Descriptor> {
+    if depth > MAX_DESCRIPTOR_DEPTH {
+        bail!("Descriptor nesting is too deep");
+    }
+    read_unicode(reader)?;
+    read_id(reader)?;
+    let count = reader.u32()? as usize;
+    // Every item needs at least a 4-byte key, a type and some data
+    if count * 9 > reader.remaining() {
+        bail!("Descriptor item count {} exceeds file size", count);
+    }
+
+    let mut items = Vec::with_capacity(count);
+    for _ in 0..count {
+        let key = read_id(reader)?;
+        let value = read_value(reader, depth)?;
+        items.push((key, value));
+    }
+    Ok(Descriptor { items })
+}
+
+fn read_value(reader: &mut Reader, depth: usize) -> Result<DescriptorValue> {
+    let value_type = reader.bytes(4)?;
+    Ok(match value_type {
+        b"Objc" | b"GlbO" => DescriptorValue::Descriptor(read_descriptor(reader, depth + 1)?),
+        b"VlLs" => {
+            let count = reader.u32()? as usize;
+            if count * 4 > reader.remaining() {
+                bail!("List length {} exceeds file size", count);
+            }
+            let mut values = Vec::with_capacity(count);
+            for _ in 0..count {
+                values.push(read_value(reader, depth + 1)?);
+            }
+            DescriptorValue::List(values)
+        }
+        b"doub" => DescriptorValue::Number(reader.f64()?),
+        b"UntF" => {
+            reader.bytes(4)?;
+            DescriptorValue::Number(reader.f64()?)
+        }
+        b"long" => DescriptorValue::Number(reader.u32()? as i32 as f64),
+        b"comp" => DescriptorValue::Number(reader.u64()? as i64 as f64),
+        b"bool" => {
+            reader.u8()?;
+            DescriptorValue::Other
+        }
+        b"TEXT" => DescriptorValue::Text(read_unicode(reader)?),
+        b"enum" => {
+            read_id(reader)?;
+            DescriptorValue::Enum(read_id(reader)?)
+        }
+        b"type" | b"GlbC" => {
+            read_unicode(reader)?;
+            read_id(reader)?;
+            DescriptorValue::Other
+        }
+        b"alis" | b"tdta" => {
+            let length = reader.u32()? as usize;
+            reader.bytes(length)?;
+            DescriptorValue::Other
+        }
+        b"UnFl" => {
+            reader.bytes(4)?;
+            let count = reader.u32()? as usize;
+            reader.bytes(count.checked_mul(8).context("Invalid unit list length")?)?;
+            DescriptorValue::Other
+        }
+        other => bail!(
+            "Unsupported descriptor value type '{}'",
+            String::from_utf8_lossy(other)
+        ),
+    })
+}
+
+/// Color of a Photoshop `Clr ` descriptor
+fn descriptor_color(color: &Descriptor) -> Option<RgbaPixel> {
+    let value = |key: &str| color.number(key).map(|value| value as f32);
+    let percent = |key: &str| value(key).map(|value| value / 100.0);
+
+    if let (Some(r), Some(g), Some(b)) = (value("Rd"), value("Grn"), value("Bl")) {
+        return Some(RgbaPixel::new(
+            to_u8(r / 255.0),
+            to_u8(g / 255.0),
+            to_u8(b / 255.0),
+            255,
+        ));
+    }
+    if let (Some(h), Some(s), Some(v)) = (value("H"), percent("Strt"), percent("Brgh")) {
+        return Some(HsvColor::hsv(h, s, v).to_rgba());
+    }
+    if let (Some(c), Some(m), Some(y), Some(k)) = (
+        percent("Cyn"),
+        percent("Mgnt"),
+        percent("Ylw"),
+        percent("Blck"),
+    ) {
+        let ink = |value: f32| to_u8((1.0 - value) * (1.0 - k));
+        return Some(RgbaPixel::new(ink(c), ink(m), ink(y), 255));
+    }
+    if let (Some(l), Some(a), Some(b)) = (value("Lmnc"), value("A"), value("B")) {
+        return Some(lab_to_rgba(l, a, b));
+    }
+    if let Some(gray) = percent("Gry") {
+        let gray = to_u8(1.0 - gray);
+        return Some(RgbaPixel::new(gray, gray, gray, 255));
+    }
+    None
+}
+
+/// Photoshop stop: location and midpoint in 0..1, plus a value
+///
+/// The midpoint of a stop lies between the previous stop and this one.
+#[derive(Debug, Clone, Copy)]
+struct GrdStop<T> {
+    location: f32,
+    midpoint: f32,
+    value: T,
+}
+
+fn grd_stops<T>(list: Vec<&Descriptor>, value: impl Fn(&Descriptor) -> T) -> Vec<GrdStop<T>> {
+    let mut stops: Vec<GrdStop<T>> = list
+        .into_iter()
+        .map(|stop| GrdStop {
+            location: (stop.number("Lctn").unwrap_or(0.0) / 4096.0).clamp(0.0, 1.0) as f32,
+            midpoint: (stop.number("Mdpn").unwrap_or(50.0) / 100.0).clamp(0.05, 0.95) as f32,
+            value: value(stop),
+        })
+        .collect();
+    stops.sort_by(|a, b| a.location.total_cmp(&b.location));
+    stops
+}
+
+/// Interpolate Photoshop stops at a location, honoring midpoints
+fn grd_value_at<T: Copy>(stops: &[GrdStop<T>], location: f32, mix: impl Fn(T, T, f32) -> T) -> T {
+    let index = stops.partition_point(|stop| stop.location <= location);
+    if index == 0 {
+        return stops[0].value;
+    }
+    if index == stops.len() {
+        return stops[index - 1].value;
+    }
+
+    let (before, after) = (&stops[index - 1], &stops[index]);
+    let span = after.location - before.location;
+    if span <= 0.0 {
+        return after.value;
+    }
+    let t = (location - before.location) / span;
+    let t = if t <= after.midpoint {
+        0.5 * t / after.midpoint
+    } else {
+        0.5 + 0.5 * (t - after.midpoint) / (1.0 - after.midpoint)
+    };
+    mix(before.value, after.value, t)
+}
+
+fn mix_rgb(a: RgbaPixel, b: RgbaPixel, t: f32) -> RgbaPixel {
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    RgbaPixel::new(channel(a.r, b.r), channel(a.g, b.g), channel(a.b, b.b), 255)
+}
+
+/// Convert one Photoshop gradient to color stops
+///
+/// Color stops are kept one-to-one with their midpoints. Transparency
+/// stops become the alpha of every stop, and transparency stops between
+/// color stops add extra stops at their locations.
+fn grd_gradient(gradient: &Descriptor) -> Option<Gradient> {
+    let name = gradient.text("Nm").unwrap_or_default();
+    // Localized preset names look like `$$$/Gradients/Name=Name`
+    let name = match name.strip_prefix("$$$/") {
+        Some(localized) => localized.split_once('=').map_or(name, |(_, name)| name),
+        None => name,
+    }
+    .to_string();
+
+    if gradient.enumerated("GrdF") == Some("ClNs") {
+        warn!("Skipping noise gradient '{}'", name);
+        return None;
+    }
+
+    let colors = grd_stops(gradient.descriptors("Clrs"), |stop| {
+        stop.descriptor("Clr")
+            .and_then(descriptor_color)
+            .unwrap_or_else(|| match stop.enumerated("Type") {
+                Some("BckC") => RgbaPixel::white(),
+                _ => RgbaPixel::black(),
+            })
+    });
+    if colors.is_empty() {
+        warn!("Skipping gradient '{}' without color stops", name);
+        return None;
+    }
+    let mut opacities = grd_stops(gradient.descriptors("Trns"), |stop| {
+        (stop.number("Opct").unwrap_or(100.0) / 100.0).clamp(0.0, 1.0) as f32
+    });
+    if opacities.is_empty() {
+        opacities.push(GrdStop {
+            location: 0.0,
+            midpoint: 0.5,
+            value: 1.0,
+        });
+    }
+
+    let alpha_at = |location: f32| {
+        to_u8(grd_value_at(&opacities, location, |a, b, t| {
+            a + (b - a) * t
+        }))
+    };
+    let mut stops: Vec<ColorStop> = Vec::new();
+    for (index, stop) in colors.iter().enumerate() {
+        let mut color = stop.value;
+        color.a = alpha_at(stop.location);
+        let next = colors.get(index + 1);
+        let extra: Vec<f32> = opacities
+            .iter()
+            .map(|opacity| opacity.location)
+            .filter(|&location| {
+                next.is_some_and(|next| location > stop.location && location < next.location)
+            })
+            .collect();
+
+        match next {
+            Some(next) if extra.is_empty() => {
+                stops.push(stop_with_midpoint(stop.location, color, next.midpoint))
+            }
+            _ => stops.push(ColorStop::new(stop.location, color)),
+        }
+        for location in extra {
+            let mut color = grd_value_at(&colors, location, mix_rgb);
+            color.a = alpha_at(location);
+            stops.push(ColorStop::new(location, color));
+        }
+    }
+
+    // Smoothness ranges from 0 to 4096 (100%)
+    let interpolation = if gradient.number("Intr").unwrap_or(0.0) >= 2048.0 {
+        InterpolationMethod::Smooth
+    } else {
+        InterpolationMethod::Linear
+    };
+    Some(gradient_from_stops(name, stops, interpolation))
+}
+
+/// Parse a Photoshop gradient library
+pub fn decode_grd(data: &[u8]) -> Result<Vec<Gradient>> {
+    let mut reader = Reader::new(data);
+    if reader.bytes(4)? != b"8BGR" {
+        bail!("Missing 8BGR signature");
+    }
+    let version = reader.u16()?;
+    if version != 5 {
+        bail!(
+            "Unsupported Photoshop gradient version {} (only version 5 is supported)",
+            version
+        );
+    }
+    let descriptor_version = reader.u32()?;
+    if descriptor_version != 16 {
+        bail!("Unsupported descriptor version {}", descriptor_version);
+    }
+
+    let root = read_descriptor(&mut reader, 0)?;
+    let gradients = root
+        .descriptors("GrdL")
+        .into_iter()
+        .filter_map(|preset| preset.descriptor("Grad"))
+        .filter_map(grd_gradient)
+        .collect();
+    Ok(gradients)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stops(gradient: &Gradient) -> Vec<(f32, RgbaPixel, Option<f32>)> {
+        gradient
+            .sorted_stops()
+            .into_iter()
+            .map(|stop| (stop.position, stop.color, stop.midpoint))
+            .collect()
+    }
+
+    #[test]
+    fn test_ggr_segments_to_stops() {
+        let ggr = "GIMP Gradient\nName: Hard edge\n2\n\
+            0.0 0.1 0.5 1 0 0 1 0 0 1 1 0 0\n\
+            0.5 0.75 1.0 0 1 0 1 1 1 1 0.5 0 0\n";
+        let gradient = decode_ggr(ggr.as_bytes()).unwrap();
+
+        assert_eq!(gradient.name, "Hard edge");
+        assert_eq!(gradient.interpolation, InterpolationMethod::Linear);
+        assert_eq!(
+            stops(&gradient),
+            [
+                (0.0, RgbaPixel::new(255, 0, 0, 255), Some(0.2)),
+                (0.5, RgbaPixel::new(0, 0, 255, 255), None),
+                (0.5, RgbaPixel::new(0, 255, 0, 255), None),
+                (1.0, RgbaPixel::new(255, 255, 255, 128), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ggr_round_trip_keeps_midpoints() {
+        let mut gradient = Gradient::new("Skewed".to_string(), psoc_core::GradientType::Linear);
+        gradient.update_stop(0, ColorStop::with_midpoint(0.0, RgbaPixel::black(), 0.25));
+        gradient.interpolation = InterpolationMethod::Smooth;
+
+        let decoded = decode_ggr(&encode_ggr(&gradient)).unwrap();
+        assert_eq!(decoded.name, "Skewed");
+        assert_eq!(decoded.interpolation, InterpolationMethod::Smooth);
+        assert_eq!(
+            stops(&decoded),
+            [
+                (0.0, RgbaPixel::black(), Some(0.25)),
+                (1.0, RgbaPixel::white(), None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_grd_midpoint_between_stops() {
+        let colors = [
+            GrdStop {
+                location: 0.0,
+                midpoint: 0.5,
+                value: 0.0f32,
+            },
+            GrdStop {
+                location: 1.0,
+                midpoint: 0.25,
+                value: 1.0,
+            },
+        ];
+        let mix = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        assert_eq!(grd_value_at(&colors, 0.25, mix), 0.5);
+        assert_eq!(grd_value_at(&colors, -1.0, mix), 0.0);
+        assert_eq!(grd_value_at(&colors, 2.0, mix), 1.0);
+    }
+
+    #[test]
+    fn test_invalid_files_are_errors() {
+        assert!(decode_ggr(b"GIMP Palette\n").is_err());
+        assert!(decode_ggr(b"GIMP Gradient\n2\n0 0.5 1 0 0 0 1 1 1 1 1 0 0\n").is_err());
+        assert!(decode_grd(b"8BGR\x00\x03\x00\x01").is_err());
+        assert!(decode_grd(b"8BGR\x00\x05\x00\x00\x00\x10\xff\xff\xff\xff").is_err());
+    }
+}
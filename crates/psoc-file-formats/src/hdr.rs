@@ -0,0 +1,334 @@
+//! High dynamic range format support
+//!
+//! This module loads OpenEXR and Radiance HDR images as floating-point data
+//! without clamping, and exports documents back to OpenEXR. Loaded images
+//! become a single layer that keeps the float pixels alongside a tone-mapped
+//! 8-bit copy used for editing.
+
+use crate::limits::{run_with_time_limit, DecodeBudget, DecodeLimits};
+use anyhow::{Context, Result};
+use image::{ImageDecoder, ImageFormat, Rgba32FImage};
+use psoc_core::{srgb_to_linear, BlendMode, Document, HdrPixelData, Layer, LayerType, ToneMapping};
+use std::path::Path;
+use tracing::{debug, info, instrument, warn};
+
+/// HDR image formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HdrFormat {
+    /// OpenEXR
+    Exr,
+    /// Radiance RGBE
+    Radiance,
+}
+
+impl HdrFormat {
+    /// Get the format from a file extension
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "exr" => Some(Self::Exr),
+            "hdr" => Some(Self::Radiance),
+            _ => None,
+        }
+    }
+
+    /// Get the format from a file path
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        path.as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_extension)
+    }
+
+    /// Get the image format for the image crate
+    pub fn to_image_format(self) -> ImageFormat {
+        match self {
+            Self::Exr => ImageFormat::OpenExr,
+            Self::Radiance => ImageFormat::Hdr,
+        }
+    }
+
+    /// Human readable format name
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Exr => "OpenEXR",
+            Self::Radiance => "Radiance HDR",
+        }
+    }
+}
+
+/// Check if a path has an HDR file extension
+pub fn is_hdr_file<P: AsRef<Path>>(path: P) -> bool {
+    HdrFormat::from_path(path).is_some()
+}
+
+/// Load an OpenEXR or Radiance HDR image as floating-point RGBA
+///
+/// Values are not clamped; images without alpha get an alpha of 1.0.
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_hdr_image<P: AsRef<Path>>(path: P) -> Result<HdrPixelData> {
+    load_hdr_image_with_limits(path, &DecodeLimits::default())
+}
+
+/// Load an HDR image as floating-point RGBA, rejecting files over the given limits
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_hdr_image_with_limits<P: AsRef<Path>>(
+    path: P,
+    limits: &DecodeLimits,
+) -> Result<HdrPixelData> {
+    let path = path.as_ref().to_path_buf();
+    let limits = *limits;
+    run_with_time_limit(limits.max_decode_time, move || {
+        load_hdr_within(&path, &mut DecodeBudget::new(&limits))
+    })
+}
+
+fn load_hdr_within(path: &Path, budget: &mut DecodeBudget) -> Result<HdrPixelData> {
+    let format = HdrFormat::from_path(path)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported HDR format: {}", path.display()))?;
+    debug!("Loading {} image from: {}", format.name(), path.display());
+
+    let mut reader = image::ImageReader::open(path)
+        .with_context(|| format!("Failed to open {} file: {}", format.name(), path.display()))?;
+    reader.set_format(format.to_image_format());
+    let mut decoder = reader.into_decoder().with_context(|| {
+        format!(
+            "Failed to create {} decoder for: {}",
+            format.name(),
+            path.display()
+        )
+    })?;
+
+    let (width, height) = decoder.dimensions();
+    budget.check_dimensions(width, height)?;
+    budget.reserve(decoder.total_bytes())?;
+    decoder
+        .set_limits(budget.image_limits())
+        .with_context(|| format!("{} image exceeds the decode limits", format.name()))?;
+
+    let image = image::DynamicImage::from_decoder(decoder).with_context(|| {
+        format!(
+            "Failed to decode {} image: {}",
+            format.name(),
+            path.display()
+        )
+    })?;
+
+    // The float copy holds four f32 samples per pixel
+    budget.reserve_pixels(width, height, 16)?;
+    let hdr = HdrPixelData::from_image(&image.into_rgba32f());
+    debug!(
+        "Loaded HDR image {}x{} with peak value {}",
+        hdr.dimensions().0,
+        hdr.dimensions().1,
+        hdr.max_value()
+    );
+
+    Ok(hdr)
+}
+
+/// Load an HDR image as a single-layer document, tone-mapped for editing
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_hdr_document<P: AsRef<Path>>(path: P, tone_mapping: &ToneMapping) -> Result<Document> {
+    load_hdr_document_with_limits(path, tone_mapping, &DecodeLimits::default())
+}
+
+/// Load an HDR image as a single-layer document, rejecting files over the given limits
+///
+/// The tone-mapped 8-bit copy counts against the same memory budget.
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_hdr_document_with_limits<P: AsRef<Path>>(
+    path: P,
+    tone_mapping: &ToneMapping,
+    limits: &DecodeLimits,
+) -> Result<Document> {
+    let path = path.as_ref().to_path_buf();
+    let tone_mapping = *tone_mapping;
+    let limits = *limits;
+    run_with_time_limit(limits.max_decode_time, move || {
+        load_hdr_document_within(&path, &tone_mapping, &mut DecodeBudget::new(&limits))
+    })
+}
+
+fn load_hdr_document_within(
+    path: &Path,
+    tone_mapping: &ToneMapping,
+    budget: &mut DecodeBudget,
+) -> Result<Document> {
+    let hdr = load_hdr_within(path, budget)?;
+    let title = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Untitled")
+        .to_string();
+
+    let (width, height) = hdr.dimensions();
+    budget.reserve_pixels(width, height, 4)?;
+    let mut document = Document::new(title, width, height);
+    document.add_layer(Layer::from_hdr(
+        "Background".to_string(),
+        hdr,
+        tone_mapping,
+    )?);
+    document.set_active_layer(0)?;
+    document.mark_clean();
+
+    info!(
+        size = format!("{}x{}", width, height),
+        "Loaded HDR document"
+    );
+
+    Ok(document)
+}
+
+/// Save floating-point pixels as an OpenEXR file
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn save_exr<P: AsRef<Path>>(hdr: &HdrPixelData, path: P) -> Result<()> {
+    let path = path.as_ref();
+    debug!("Saving OpenEXR image to: {}", path.display());
+
+    image::DynamicImage::ImageRgba32F(hdr.to_image())
+        .save_with_format(path, ImageFormat::OpenExr)
+        .with_context(|| format!("Failed to save OpenEXR image to: {}", path.display()))?;
+
+    Ok(())
+}
+
+/// Export a document to OpenEXR, compositing in linear floating point
+///
+/// HDR layers contribute their float pixels unclamped; 8-bit layers are
+/// converted from sRGB to linear. Layers are combined with normal blending.
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn export_exr<P: AsRef<Path>>(document: &Document, path: P) -> Result<()> {
+    let path = path.as_ref();
+    let hdr = flatten_hdr(document)?;
+    save_exr(&hdr, path)?;
+
+    info!(
+        layers = document.layers.len(),
+        size = format!("{}x{}", document.size.width, document.size.height),
+        "Successfully exported OpenEXR document"
+    );
+
+    Ok(())
+}
+
+/// Composite the visible pixel layers of a document in linear floating point
+pub fn flatten_hdr(document: &Document) -> Result<HdrPixelData> {
+    let width = document.size.width as u32;
+    let height = document.size.height as u32;
+    let mut result = Rgba32FImage::new(width, height);
+
+    for layer in &document.layers {
+        if !layer.is_effectively_visible() {
+            continue;
+        }
+        if !matches!(layer.layer_type, LayerType::Pixel) || !layer.has_pixel_data() {
+            warn!(
+                "Skipping non-pixel layer '{}' in OpenEXR export",
+                layer.name
+            );
+            continue;
+        }
+        if layer.blend_mode != BlendMode::Normal {
+            warn!(
+                "Layer '{}' uses {:?} blending, exporting it as Normal",
+                layer.name, layer.blend_mode
+            );
+        }
+
+        let opacity = layer.effective_opacity().clamp(0.0, 1.0);
+        let offset_x = layer.offset.x.round() as i64;
+        let offset_y = layer.offset.y.round() as i64;
+        let Some((layer_width, layer_height)) = layer.dimensions() else {
+            continue;
+        };
+
+        for y in 0..layer_height {
+            for x in 0..layer_width {
+                let (dx, dy) = (x as i64 + offset_x, y as i64 + offset_y);
+                if dx < 0 || dy < 0 || dx >= width as i64 || dy >= height as i64 {
+                    continue;
+                }
+
+                let Some(source) = layer_sample(layer, x, y) else {
+                    continue;
+                };
+                let source_alpha = source[3].clamp(0.0, 1.0) * opacity;
+                if source_alpha <= 0.0 {
+                    continue;
+                }
+
+                let target = &mut result.get_pixel_mut(dx as u32, dy as u32).0;
+                let target_alpha = target[3];
+                let out_alpha = source_alpha + target_alpha * (1.0 - source_alpha);
+                for channel in 0..3 {
+                    target[channel] = (source[channel] * source_alpha
+                        + target[channel] * target_alpha * (1.0 - source_alpha))
+                        / out_alpha;
+                }
+                target[3] = out_alpha;
+            }
+        }
+    }
+
+    Ok(HdrPixelData::from_image(&result))
+}
+
+/// Linear RGBA sample of a layer pixel with its mask applied
+fn layer_sample(layer: &Layer, x: u32, y: u32) -> Option<[f32; 4]> {
+    let mask = layer
+        .get_mask_pixel(x, y)
+        .map(|mask| mask.r as f32 / 255.0)
+        .unwrap_or(1.0);
+
+    let mut sample = match layer.hdr_pixel(x, y) {
+        Some(sample) => sample,
+        None => {
+            let pixel = layer.get_pixel(x, y)?;
+            [
+                srgb_to_linear(pixel.r),
+                srgb_to_linear(pixel.g),
+                srgb_to_linear(pixel.b),
+                pixel.a as f32 / 255.0,
+            ]
+        }
+    };
+    sample[3] *= mask;
+    Some(sample)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hdr_format_from_path() {
+        assert_eq!(HdrFormat::from_path("render.EXR"), Some(HdrFormat::Exr));
+        assert_eq!(HdrFormat::from_path("sky.hdr"), Some(HdrFormat::Radiance));
+        assert_eq!(HdrFormat::from_path("image.png"), None);
+        assert!(is_hdr_file("a.exr"));
+    }
+
+    #[test]
+    fn test_srgb_to_linear() {
+        assert_eq!(srgb_to_linear(0), 0.0);
+        assert!((srgb_to_linear(255) - 1.0).abs() < 1e-6);
+        assert!((srgb_to_linear(188) - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_flatten_keeps_values_above_one() -> Result<()> {
+        let hdr = HdrPixelData::new(1, 1, vec![6.0, 2.0, 0.25, 1.0])?;
+        let mut document = Document::new("HDR".to_string(), 1, 1);
+        document.add_layer(Layer::from_hdr(
+            "Render".to_string(),
+            hdr,
+            &ToneMapping::default(),
+        )?);
+
+        let flattened = flatten_hdr(&document)?;
+        assert_eq!(flattened.get_pixel(0, 0), [6.0, 2.0, 0.25, 1.0]);
+
+        Ok(())
+    }
+}
@@ -1,9 +1,12 @@
 //! JPEG format support
 //!
-//! This module provides JPEG image loading and saving functionality with ICC profile support.
+//! This module provides JPEG image loading and saving functionality with ICC profile
+//! and EXIF/XMP/IPTC metadata support.
 
+use crate::limits::DecodeLimits;
+use crate::metadata;
 use anyhow::{Context, Result};
-use psoc_core::{ColorManager, IccProfile};
+use psoc_core::{ColorManager, EmbeddedMetadata, IccProfile};
 use std::fs::File;
 use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
@@ -16,6 +19,8 @@ pub struct JpegLoadResult {
     pub image: image::DynamicImage,
     /// Embedded ICC profile, if any
     pub icc_profile: Option<IccProfile>,
+    /// Embedded EXIF/XMP/IPTC metadata
+    pub metadata: EmbeddedMetadata,
 }
 
 /// Load a JPEG image from a file path
@@ -28,11 +33,21 @@ pub fn load_jpeg<P: AsRef<Path>>(path: P) -> Result<image::DynamicImage> {
 /// Load a JPEG image with ICC profile from a file path
 #[instrument(skip_all, fields(path = %path.as_ref().display()))]
 pub fn load_jpeg_with_profile<P: AsRef<Path>>(path: P) -> Result<JpegLoadResult> {
+    load_jpeg_with_limits(path, &DecodeLimits::default())
+}
+
+/// Load a JPEG image with ICC profile, rejecting files over the given limits
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_jpeg_with_limits<P: AsRef<Path>>(
+    path: P,
+    limits: &DecodeLimits,
+) -> Result<JpegLoadResult> {
     let path = path.as_ref();
     debug!("Loading JPEG image with profile from: {}", path.display());
 
-    let image = image::open(path)
-        .with_context(|| format!("Failed to load JPEG image from: {}", path.display()))?;
+    // Decode with metadata, rotating the pixels according to the EXIF orientation
+    let decoded = metadata::decode_image(path, "JPEG", limits)?;
+    let image = decoded.image;
 
     // JPEG doesn't support transparency, so convert RGBA to RGB if needed
     let image = match image.color() {
@@ -46,7 +61,11 @@ pub fn load_jpeg_with_profile<P: AsRef<Path>>(path: P) -> Result<JpegLoadResult>
     // Try to extract ICC profile from JPEG file
     let icc_profile = extract_jpeg_icc_profile(path)?;
 
-    Ok(JpegLoadResult { image, icc_profile })
+    Ok(JpegLoadResult {
+        image,
+        icc_profile,
+        metadata: decoded.metadata,
+    })
 }
 
 /// Extract ICC profile from JPEG file
@@ -189,6 +208,10 @@ pub struct JpegOptions {
     pub optimize_huffman: bool,
     /// ICC profile to embed (optional)
     pub icc_profile: Option<IccProfile>,
+    /// EXIF/XMP/IPTC metadata to embed (optional)
+    pub metadata: Option<EmbeddedMetadata>,
+    /// Remove GPS location, serial numbers and other private fields from the metadata
+    pub strip_private_metadata: bool,
 }
 
 impl Default for JpegOptions {
@@ -198,6 +221,8 @@ impl Default for JpegOptions {
             progressive: false, // Standard baseline JPEG
             optimize_huffman: true,
             icc_profile: None,
+            metadata: None,
+            strip_private_metadata: false,
         }
     }
 }
@@ -210,6 +235,8 @@ impl JpegOptions {
             progressive: true,
             optimize_huffman: true,
             icc_profile: None,
+            metadata: None,
+            strip_private_metadata: false,
         }
     }
 
@@ -220,6 +247,8 @@ impl JpegOptions {
             progressive: true,
             optimize_huffman: true,
             icc_profile: None,
+            metadata: None,
+            strip_private_metadata: false,
         }
     }
 
@@ -251,14 +280,26 @@ pub fn save_jpeg_with_options<P: AsRef<Path>>(
 
     if options.icc_profile.is_some() {
         // Use custom JPEG encoding with ICC profile
-        save_jpeg_with_icc_profile(&image, path, options)
+        save_jpeg_with_icc_profile(&image, path, options)?;
     } else {
         // Use standard save method
         image
             .save_with_format(path, image::ImageFormat::Jpeg)
             .with_context(|| format!("Failed to save JPEG image to: {}", path.display()))?;
-        Ok(())
     }
+
+    if let Some(ref embedded) = options.metadata {
+        metadata::embed_metadata_in_file(
+            path,
+            "JPEG",
+            embedded,
+            options.strip_private_metadata,
+            (image.width(), image.height()),
+            metadata::embed_jpeg_metadata,
+        )?;
+    }
+
+    Ok(())
 }
 
 /// Save a JPEG image with embedded ICC profile
@@ -391,6 +432,7 @@ mod tests {
         let result = JpegLoadResult {
             image: dynamic_img,
             icc_profile: None,
+            metadata: EmbeddedMetadata::default(),
         };
 
         assert_eq!(result.image.width(), 15);
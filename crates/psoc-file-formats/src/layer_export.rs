@@ -0,0 +1,243 @@
+//! Export layers to individual files
+//!
+//! Each exported layer is rendered on its own over a transparent canvas with
+//! `RenderEngine::render_region`, optionally trimmed to its content, and saved
+//! in any format supported by [`ImageIO`]. File names come from a template
+//! such as `{doc}_{index}_{name}`.
+
+use crate::{ImageIO, SupportedFormat};
+use anyhow::{Context, Result};
+use psoc_core::{Document, PixelData, RenderEngine, RgbaPixel};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, instrument, warn};
+
+/// Default file name template for exported layers
+pub const DEFAULT_LAYER_FILENAME_TEMPLATE: &str = "{doc}_{index}_{name}";
+
+/// Options for exporting layers to individual files
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerExportOptions {
+    /// File name template without extension
+    ///
+    /// `{doc}` is the document title, `{index}` the 1-based layer position
+    /// (zero-padded to two digits) and `{name}` the layer name.
+    pub template: String,
+    /// Output image format
+    pub format: SupportedFormat,
+    /// Crop each file to the bounds of the layer's visible pixels
+    pub trim_to_content: bool,
+    /// Layers to export; `None` exports every visible layer
+    ///
+    /// Explicitly listed layers are exported even when hidden.
+    pub layer_indices: Option<Vec<usize>>,
+}
+
+impl Default for LayerExportOptions {
+    fn default() -> Self {
+        Self {
+            template: DEFAULT_LAYER_FILENAME_TEMPLATE.to_string(),
+            format: SupportedFormat::Png,
+            trim_to_content: false,
+            layer_indices: None,
+        }
+    }
+}
+
+impl LayerExportOptions {
+    /// Set the file name template
+    pub fn with_template(mut self, template: impl Into<String>) -> Self {
+        self.template = template.into();
+        self
+    }
+
+    /// Set the output format
+    pub fn with_format(mut self, format: SupportedFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Enable or disable trimming to content
+    pub fn with_trim_to_content(mut self, trim_to_content: bool) -> Self {
+        self.trim_to_content = trim_to_content;
+        self
+    }
+
+    /// Export only the given layers
+    pub fn with_layers(mut self, layer_indices: Vec<usize>) -> Self {
+        self.layer_indices = Some(layer_indices);
+        self
+    }
+}
+
+/// Expand a file name template for one layer
+///
+/// Characters that are not valid in file names are replaced with `_`.
+pub fn layer_file_name(template: &str, document_title: &str, index: usize, name: &str) -> String {
+    let expanded = template
+        .replace("{doc}", document_title)
+        .replace("{index}", &format!("{:02}", index + 1))
+        .replace("{name}", name);
+
+    let sanitized: String = expanded
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let trimmed = sanitized.trim().trim_matches('.');
+
+    if trimmed.is_empty() {
+        format!("layer_{:02}", index + 1)
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Render a single layer at canvas size over a transparent background
+///
+/// With `trim_to_content`, the result is cropped to the layer's visible
+/// pixels; `None` is returned when there are none.
+pub fn render_layer(
+    document: &Document,
+    index: usize,
+    trim_to_content: bool,
+) -> Result<Option<PixelData>> {
+    let layer = document
+        .layers
+        .get(index)
+        .with_context(|| format!("Layer index {} out of range", index))?;
+
+    let mut single = Document::new(
+        document.metadata.title.clone(),
+        document.size.width as u32,
+        document.size.height as u32,
+    );
+    single.background_color = RgbaPixel::transparent();
+    let mut layer = layer.clone();
+    layer.visible = true;
+    single.layers.push(layer);
+
+    let width = document.size.width as u32;
+    let height = document.size.height as u32;
+    let engine = RenderEngine::new();
+    let full = engine.render_region(&single, 0, 0, width, height)?;
+    if !trim_to_content {
+        return Ok(Some(full));
+    }
+
+    match content_bounds(&full) {
+        Some((x, y, w, h)) => Ok(Some(engine.render_region(&single, x, y, w, h)?)),
+        None => Ok(None),
+    }
+}
+
+/// Export layers to individual files in a directory
+///
+/// Returns the written paths in export order. Layers that are empty after
+/// trimming are skipped.
+#[instrument(skip_all, fields(directory = %directory.as_ref().display()))]
+pub fn export_layers<P: AsRef<Path>>(
+    document: &Document,
+    directory: P,
+    options: &LayerExportOptions,
+) -> Result<Vec<PathBuf>> {
+    let directory = directory.as_ref();
+    std::fs::create_dir_all(directory)
+        .with_context(|| format!("Failed to create directory: {}", directory.display()))?;
+
+    let indices: Vec<usize> = match &options.layer_indices {
+        Some(indices) => indices.clone(),
+        None => (0..document.layers.len())
+            .filter(|&index| document.layers[index].visible)
+            .collect(),
+    };
+
+    let mut used_names = HashSet::new();
+    let mut written = Vec::with_capacity(indices.len());
+    for index in indices {
+        let Some(pixels) = render_layer(document, index, options.trim_to_content)? else {
+            warn!("Skipping empty layer {} in layer export", index);
+            continue;
+        };
+
+        let base = layer_file_name(
+            &options.template,
+            &document.metadata.title,
+            index,
+            &document.layers[index].name,
+        );
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while !used_names.insert(name.to_lowercase()) {
+            name = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+
+        let path = directory.join(format!("{}.{}", name, options.format.extension()));
+        debug!("Exporting layer {} to: {}", index, path.display());
+        ImageIO::save_image(&pixels.to_image()?, &path)?;
+        written.push(path);
+    }
+
+    info!(
+        count = written.len(),
+        format = ?options.format,
+        "Successfully exported layers"
+    );
+
+    Ok(written)
+}
+
+/// Bounds `(x, y, width, height)` of the non-transparent pixels
+fn content_bounds(pixels: &PixelData) -> Option<(u32, u32, u32, u32)> {
+    let (width, height) = pixels.dimensions();
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+
+    for y in 0..height {
+        for x in 0..width {
+            if pixels.get_pixel(x, y).is_some_and(|pixel| pixel.a > 0) {
+                bounds = Some(match bounds {
+                    Some((min_x, min_y, max_x, max_y)) => {
+                        (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+                    }
+                    None => (x, y, x, y),
+                });
+            }
+        }
+    }
+
+    bounds.map(|(min_x, min_y, max_x, max_y)| (min_x, min_y, max_x - min_x + 1, max_y - min_y + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layer_file_name_template() {
+        assert_eq!(
+            layer_file_name(DEFAULT_LAYER_FILENAME_TEMPLATE, "Poster", 0, "Logo"),
+            "Poster_01_Logo"
+        );
+        assert_eq!(
+            layer_file_name("{name}-{index}", "Poster", 11, "a/b: c?"),
+            "a_b_ c_-12"
+        );
+        assert_eq!(layer_file_name("{name}", "Poster", 2, "  "), "layer_03");
+    }
+
+    #[test]
+    fn test_content_bounds() {
+        let mut pixels = PixelData::new_rgba(5, 4);
+        assert_eq!(content_bounds(&pixels), None);
+
+        pixels.set_pixel(1, 2, RgbaPixel::new(0, 0, 0, 1)).unwrap();
+        pixels
+            .set_pixel(3, 1, RgbaPixel::new(0, 0, 0, 255))
+            .unwrap();
+        assert_eq!(content_bounds(&pixels), Some((1, 1, 3, 2)));
+    }
+}
@@ -7,20 +7,46 @@ use anyhow::Result;
 use std::path::Path;
 use tracing::{debug, info, instrument};
 
+pub mod animation;
+pub mod asset_export;
+pub mod gradient;
+pub mod hdr;
 pub mod jpeg;
+pub mod layer_export;
+pub mod limits;
+pub mod metadata;
+pub mod migration;
+pub mod openraster;
+pub mod palette;
+pub mod pdf;
 pub mod png;
 pub mod project;
+pub mod tiff;
+pub mod webp;
 
 // Re-export commonly used types
+pub use self::tiff::*;
+pub use self::webp::*;
+pub use animation::*;
+pub use asset_export::*;
+pub use gradient::*;
+pub use hdr::*;
 pub use jpeg::*;
+pub use layer_export::*;
+pub use limits::*;
+pub use metadata::*;
+pub use migration::*;
+pub use openraster::*;
+pub use palette::*;
+pub use pdf::*;
 pub use png::*;
 pub use project::*;
 
 // Re-export image types for convenience
 pub use image::{DynamicImage, ImageFormat};
 
-// Re-export ICC profile types
-pub use psoc_core::{ColorManager, IccProfile};
+// Re-export ICC profile and metadata types
+pub use psoc_core::{ColorManager, EmbeddedMetadata, IccProfile};
 
 /// Image loading result with optional ICC profile
 #[derive(Debug)]
@@ -29,6 +55,8 @@ pub struct ImageLoadResult {
     pub image: image::DynamicImage,
     /// Embedded ICC profile, if any
     pub icc_profile: Option<IccProfile>,
+    /// Embedded EXIF/XMP/IPTC metadata
+    pub metadata: EmbeddedMetadata,
 }
 
 /// Supported image formats
@@ -36,6 +64,8 @@ pub struct ImageLoadResult {
 pub enum SupportedFormat {
     Png,
     Jpeg,
+    Tiff,
+    Webp,
 }
 
 /// All supported file formats (images and projects)
@@ -45,6 +75,18 @@ pub enum FileFormat {
     Png,
     /// JPEG image format
     Jpeg,
+    /// TIFF image format
+    Tiff,
+    /// WebP image format
+    Webp,
+    /// GIF image format, imported as one layer per frame
+    Gif,
+    /// OpenEXR high dynamic range format
+    OpenExr,
+    /// Radiance HDR format
+    RadianceHdr,
+    /// OpenRaster layered format
+    OpenRaster,
     /// PSOC project format
     Project,
 }
@@ -55,6 +97,8 @@ impl SupportedFormat {
         match ext.to_lowercase().as_str() {
             "png" => Some(Self::Png),
             "jpg" | "jpeg" => Some(Self::Jpeg),
+            "tif" | "tiff" => Some(Self::Tiff),
+            "webp" => Some(Self::Webp),
             _ => None,
         }
     }
@@ -72,6 +116,8 @@ impl SupportedFormat {
         match self {
             Self::Png => image::ImageFormat::Png,
             Self::Jpeg => image::ImageFormat::Jpeg,
+            Self::Tiff => image::ImageFormat::Tiff,
+            Self::Webp => image::ImageFormat::WebP,
         }
     }
 
@@ -80,6 +126,8 @@ impl SupportedFormat {
         match self {
             Self::Png => "png",
             Self::Jpeg => "jpg",
+            Self::Tiff => "tiff",
+            Self::Webp => "webp",
         }
     }
 
@@ -88,6 +136,19 @@ impl SupportedFormat {
         match self {
             Self::Png => "image/png",
             Self::Jpeg => "image/jpeg",
+            Self::Tiff => "image/tiff",
+            Self::Webp => "image/webp",
+        }
+    }
+}
+
+impl std::fmt::Display for SupportedFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Png => write!(f, "PNG"),
+            Self::Jpeg => write!(f, "JPEG"),
+            Self::Tiff => write!(f, "TIFF"),
+            Self::Webp => write!(f, "WebP"),
         }
     }
 }
@@ -98,6 +159,12 @@ impl FileFormat {
         match ext.to_lowercase().as_str() {
             "png" => Some(Self::Png),
             "jpg" | "jpeg" => Some(Self::Jpeg),
+            "tif" | "tiff" => Some(Self::Tiff),
+            "webp" => Some(Self::Webp),
+            "gif" => Some(Self::Gif),
+            "exr" => Some(Self::OpenExr),
+            "hdr" => Some(Self::RadianceHdr),
+            "ora" => Some(Self::OpenRaster),
             "psoc" => Some(Self::Project),
             _ => None,
         }
@@ -116,13 +183,34 @@ impl FileFormat {
         match self {
             Self::Png => "png",
             Self::Jpeg => "jpg",
+            Self::Tiff => "tiff",
+            Self::Webp => "webp",
+            Self::Gif => "gif",
+            Self::OpenExr => "exr",
+            Self::RadianceHdr => "hdr",
+            Self::OpenRaster => "ora",
             Self::Project => "psoc",
         }
     }
 
     /// Check if this is an image format
     pub fn is_image(self) -> bool {
-        matches!(self, Self::Png | Self::Jpeg)
+        matches!(self, Self::Png | Self::Jpeg | Self::Tiff | Self::Webp)
+    }
+
+    /// Check if this is an animation format
+    pub fn is_animation(self) -> bool {
+        matches!(self, Self::Gif)
+    }
+
+    /// Check if this is a floating-point high dynamic range format
+    pub fn is_hdr(self) -> bool {
+        matches!(self, Self::OpenExr | Self::RadianceHdr)
+    }
+
+    /// Check if this is a layered interchange format
+    pub fn is_layered(self) -> bool {
+        matches!(self, Self::OpenRaster)
     }
 
     /// Check if this is a project format
@@ -135,6 +223,12 @@ impl FileFormat {
         match self {
             Self::Png => "image/png",
             Self::Jpeg => "image/jpeg",
+            Self::Tiff => "image/tiff",
+            Self::Webp => "image/webp",
+            Self::Gif => "image/gif",
+            Self::OpenExr => "image/x-exr",
+            Self::RadianceHdr => "image/vnd.radiance",
+            Self::OpenRaster => OPENRASTER_MIME_TYPE,
             Self::Project => "application/x-psoc-project",
         }
     }
@@ -154,6 +248,17 @@ impl ImageIO {
     /// Load an image with ICC profile from a file path
     #[instrument(skip_all, fields(path = %path.as_ref().display()))]
     pub fn load_image_with_profile<P: AsRef<Path>>(path: P) -> Result<ImageLoadResult> {
+        Self::load_image_with_limits(path, &DecodeLimits::default())
+    }
+
+    /// Load an image with ICC profile, rejecting files over the given limits
+    ///
+    /// Failures caused by the limits carry a [`DecodeLimitError`].
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub fn load_image_with_limits<P: AsRef<Path>>(
+        path: P,
+        limits: &DecodeLimits,
+    ) -> Result<ImageLoadResult> {
         let path = path.as_ref();
         debug!("Loading image with profile from: {}", path.display());
 
@@ -162,17 +267,35 @@ impl ImageIO {
 
         let result = match format {
             SupportedFormat::Png => {
-                let png_result = png::load_png_with_profile(path)?;
+                let png_result = png::load_png_with_limits(path, limits)?;
                 ImageLoadResult {
                     image: png_result.image,
                     icc_profile: png_result.icc_profile,
+                    metadata: png_result.metadata,
                 }
             }
             SupportedFormat::Jpeg => {
-                let jpeg_result = jpeg::load_jpeg_with_profile(path)?;
+                let jpeg_result = jpeg::load_jpeg_with_limits(path, limits)?;
                 ImageLoadResult {
                     image: jpeg_result.image,
                     icc_profile: jpeg_result.icc_profile,
+                    metadata: jpeg_result.metadata,
+                }
+            }
+            SupportedFormat::Tiff => {
+                let tiff_result = self::tiff::load_tiff_with_limits(path, limits)?;
+                ImageLoadResult {
+                    image: tiff_result.image,
+                    icc_profile: tiff_result.icc_profile,
+                    metadata: tiff_result.metadata,
+                }
+            }
+            SupportedFormat::Webp => {
+                let webp_result = self::webp::load_webp_with_limits(path, limits)?;
+                ImageLoadResult {
+                    image: webp_result.image,
+                    icc_profile: webp_result.icc_profile,
+                    metadata: webp_result.metadata,
                 }
             }
         };
@@ -200,6 +323,8 @@ impl ImageIO {
         match format {
             SupportedFormat::Png => png::save_png(image, path)?,
             SupportedFormat::Jpeg => jpeg::save_jpeg(image, path)?,
+            SupportedFormat::Tiff => self::tiff::save_tiff(image, path)?,
+            SupportedFormat::Webp => self::webp::save_webp(image, path)?,
         }
 
         info!(
@@ -212,9 +337,74 @@ impl ImageIO {
         Ok(())
     }
 
+    /// Save an image, embedding EXIF/XMP/IPTC metadata where the format supports it
+    ///
+    /// With `strip_private`, GPS location, serial numbers and other private
+    /// fields are removed from the written metadata.
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub fn save_image_with_metadata<P: AsRef<Path>>(
+        image: &image::DynamicImage,
+        path: P,
+        metadata: &EmbeddedMetadata,
+        strip_private: bool,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        if metadata.is_empty() {
+            return Self::save_image(image, path);
+        }
+
+        let format = SupportedFormat::from_path(path)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported file format: {}", path.display()))?;
+        let metadata = Some(metadata.clone());
+
+        match format {
+            SupportedFormat::Png => png::save_png_with_options(
+                image,
+                path,
+                &PngOptions {
+                    metadata,
+                    strip_private_metadata: strip_private,
+                    ..PngOptions::default()
+                },
+            )?,
+            SupportedFormat::Jpeg => jpeg::save_jpeg_with_options(
+                image,
+                path,
+                &JpegOptions {
+                    metadata,
+                    strip_private_metadata: strip_private,
+                    ..JpegOptions::default()
+                },
+            )?,
+            SupportedFormat::Tiff => self::tiff::save_tiff_with_options(
+                image,
+                path,
+                &TiffOptions {
+                    metadata,
+                    strip_private_metadata: strip_private,
+                    ..TiffOptions::default()
+                },
+            )?,
+            SupportedFormat::Webp => {
+                debug!("WebP export does not write metadata");
+                self::webp::save_webp(image, path)?
+            }
+        }
+
+        info!(
+            width = image.width(),
+            height = image.height(),
+            format = ?format,
+            strip_private,
+            "Successfully saved image with metadata"
+        );
+
+        Ok(())
+    }
+
     /// Get supported image file extensions
     pub fn supported_extensions() -> Vec<&'static str> {
-        vec!["png", "jpg", "jpeg"]
+        vec!["png", "jpg", "jpeg", "tif", "tiff", "webp"]
     }
 
     /// Get supported project file extensions
@@ -222,16 +412,36 @@ impl ImageIO {
         vec!["psoc"]
     }
 
+    /// Get supported layered interchange file extensions
+    pub fn supported_layered_extensions() -> Vec<&'static str> {
+        vec!["ora"]
+    }
+
+    /// Get supported animation file extensions
+    ///
+    /// Animated PNG and WebP files use the image extensions.
+    pub fn supported_animation_extensions() -> Vec<&'static str> {
+        vec!["gif"]
+    }
+
+    /// Get supported high dynamic range file extensions
+    pub fn supported_hdr_extensions() -> Vec<&'static str> {
+        vec!["exr", "hdr"]
+    }
+
     /// Get all supported file extensions
     pub fn all_supported_extensions() -> Vec<&'static str> {
         let mut extensions = Self::supported_extensions();
+        extensions.extend(Self::supported_animation_extensions());
+        extensions.extend(Self::supported_hdr_extensions());
+        extensions.extend(Self::supported_layered_extensions());
         extensions.extend(Self::supported_project_extensions());
         extensions
     }
 
     /// Get file filter string for image file dialogs
     pub fn image_file_filter() -> String {
-        "Image Files (*.png, *.jpg, *.jpeg)|*.png;*.jpg;*.jpeg".to_string()
+        "Image Files (*.png, *.jpg, *.jpeg, *.tif, *.tiff, *.webp)|*.png;*.jpg;*.jpeg;*.tif;*.tiff;*.webp".to_string()
     }
 
     /// Get file filter string for project file dialogs
@@ -239,9 +449,24 @@ impl ImageIO {
         "PSOC Project Files (*.psoc)|*.psoc".to_string()
     }
 
+    /// Get file filter string for layered interchange file dialogs
+    pub fn layered_file_filter() -> String {
+        "OpenRaster Files (*.ora)|*.ora".to_string()
+    }
+
+    /// Get file filter string for animation file dialogs
+    pub fn animation_file_filter() -> String {
+        "Animation Files (*.gif, *.png, *.webp)|*.gif;*.png;*.webp".to_string()
+    }
+
+    /// Get file filter string for high dynamic range file dialogs
+    pub fn hdr_file_filter() -> String {
+        "HDR Files (*.exr, *.hdr)|*.exr;*.hdr".to_string()
+    }
+
     /// Get file filter string for all supported files
     pub fn all_files_filter() -> String {
-        "All Supported Files (*.png, *.jpg, *.jpeg, *.psoc)|*.png;*.jpg;*.jpeg;*.psoc|Image Files (*.png, *.jpg, *.jpeg)|*.png;*.jpg;*.jpeg|PSOC Project Files (*.psoc)|*.psoc".to_string()
+        "All Supported Files (*.png, *.jpg, *.jpeg, *.tif, *.tiff, *.webp, *.gif, *.exr, *.hdr, *.ora, *.psoc)|*.png;*.jpg;*.jpeg;*.tif;*.tiff;*.webp;*.gif;*.exr;*.hdr;*.ora;*.psoc|Image Files (*.png, *.jpg, *.jpeg, *.tif, *.tiff, *.webp)|*.png;*.jpg;*.jpeg;*.tif;*.tiff;*.webp|GIF Files (*.gif)|*.gif|HDR Files (*.exr, *.hdr)|*.exr;*.hdr|OpenRaster Files (*.ora)|*.ora|PSOC Project Files (*.psoc)|*.psoc".to_string()
     }
 }
 
@@ -252,6 +477,18 @@ impl FileIO {
     /// Load a document from any supported file format
     #[instrument(skip_all, fields(path = %path.as_ref().display()))]
     pub fn load_document<P: AsRef<Path>>(path: P) -> Result<psoc_core::Document> {
+        Self::load_document_with_limits(path, &DecodeLimits::default())
+    }
+
+    /// Load a document, rejecting images and projects over the given limits
+    ///
+    /// Limits apply to every format, per layer for projects and OpenRaster
+    /// files and per frame for animations.
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub fn load_document_with_limits<P: AsRef<Path>>(
+        path: P,
+        limits: &DecodeLimits,
+    ) -> Result<psoc_core::Document> {
         use psoc_core::Document;
 
         let path = path.as_ref();
@@ -261,16 +498,30 @@ impl FileIO {
             .ok_or_else(|| anyhow::anyhow!("Unsupported file format: {}", path.display()))?;
 
         let document = match format {
-            FileFormat::Project => project::load_project(path)?,
-            FileFormat::Png | FileFormat::Jpeg => {
+            FileFormat::Project => project::load_project_with_limits(path, limits)?,
+            FileFormat::OpenRaster => openraster::load_ora_with_limits(path, limits)?,
+            FileFormat::Gif => animation::load_animation_document_with_limits(path, limits)?,
+            FileFormat::OpenExr | FileFormat::RadianceHdr => hdr::load_hdr_document_with_limits(
+                path,
+                &psoc_core::ToneMapping::default(),
+                limits,
+            )?,
+            FileFormat::Png | FileFormat::Webp if animation::is_animated(path)? => {
+                animation::load_animation_document_with_limits(path, limits)?
+            }
+            FileFormat::Png | FileFormat::Jpeg | FileFormat::Tiff | FileFormat::Webp => {
                 // Load as image with ICC profile and convert to document
-                let result = ImageIO::load_image_with_profile(path)?;
+                let result = ImageIO::load_image_with_limits(path, limits)?;
                 let title = path
                     .file_stem()
                     .and_then(|s| s.to_str())
                     .unwrap_or("Untitled")
                     .to_string();
-                Document::from_image_with_profile(title, &result.image, result.icc_profile)?
+                let mut document =
+                    Document::from_image_with_profile(title, &result.image, result.icc_profile)?;
+                document.metadata.embedded = result.metadata;
+                metadata::apply_embedded_metadata(&mut document.metadata);
+                document
             }
         };
 
@@ -284,15 +535,129 @@ impl FileIO {
         Ok(document)
     }
 
+    /// Open a document for editing
+    ///
+    /// Like [`Self::load_document`], except that project layers are decoded
+    /// when they are first used rather than up front.
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub fn open_document<P: AsRef<Path>>(path: P) -> Result<psoc_core::Document> {
+        Self::open_document_with_limits(path, &DecodeLimits::default())
+    }
+
+    /// Open a document for editing, rejecting images and layers over the given limits
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub fn open_document_with_limits<P: AsRef<Path>>(
+        path: P,
+        limits: &DecodeLimits,
+    ) -> Result<psoc_core::Document> {
+        let path = path.as_ref();
+        if FileFormat::from_path(path) == Some(FileFormat::Project) {
+            return project::open_project_with_limits(path, limits);
+        }
+        Self::load_document_with_limits(path, limits)
+    }
+
     /// Save a document to a project file
     #[instrument(skip_all, fields(path = %path.as_ref().display()))]
     pub fn save_project<P: AsRef<Path>>(document: &psoc_core::Document, path: P) -> Result<()> {
         project::save_project(document, path)
     }
 
-    /// Export a document as a flattened image
+    /// Load the embedded thumbnail of a project without decoding its layers
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub fn load_project_thumbnail<P: AsRef<Path>>(path: P) -> Result<Option<DynamicImage>> {
+        project::load_project_thumbnail(path)
+    }
+
+    /// Save a document as a layered OpenRaster file
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub fn save_openraster<P: AsRef<Path>>(document: &psoc_core::Document, path: P) -> Result<()> {
+        openraster::save_ora(document, path)
+    }
+
+    /// Export the given layers of a document as an animated GIF, APNG or WebP
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub fn export_animation<P: AsRef<Path>>(
+        document: &psoc_core::Document,
+        layer_indices: &[usize],
+        path: P,
+        options: &AnimationOptions,
+    ) -> Result<()> {
+        animation::export_animation(document, layer_indices, path, options)
+    }
+
+    /// Export a document to OpenEXR without clamping HDR layers
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub fn export_exr<P: AsRef<Path>>(document: &psoc_core::Document, path: P) -> Result<()> {
+        hdr::export_exr(document, path)
+    }
+
+    /// Export layers to individual files in a directory
+    #[instrument(skip_all, fields(directory = %directory.as_ref().display()))]
+    pub fn export_layers<P: AsRef<Path>>(
+        document: &psoc_core::Document,
+        directory: P,
+        options: &LayerExportOptions,
+    ) -> Result<Vec<std::path::PathBuf>> {
+        layer_export::export_layers(document, directory, options)
+    }
+
+    /// Export every asset of a document at each of its scale presets
+    #[instrument(skip_all, fields(directory = %directory.as_ref().display()))]
+    pub fn export_assets<P: AsRef<Path>>(
+        document: &psoc_core::Document,
+        directory: P,
+    ) -> Result<Vec<std::path::PathBuf>> {
+        asset_export::export_assets(document, directory)
+    }
+
+    /// Import a color palette (`.gpl`, `.aco`, `.ase` or hex list)
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub fn import_palette<P: AsRef<Path>>(path: P) -> Result<Palette> {
+        palette::import_palette(path)
+    }
+
+    /// Export a color palette in the format given by the file extension
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub fn export_palette<P: AsRef<Path>>(palette: &Palette, path: P) -> Result<()> {
+        palette::export_palette(palette, path)
+    }
+
+    /// Import every gradient in a GIMP `.ggr` or Photoshop `.grd` file
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub fn import_gradients<P: AsRef<Path>>(path: P) -> Result<Vec<psoc_core::Gradient>> {
+        gradient::import_gradients(path)
+    }
+
+    /// Export a gradient as a GIMP `.ggr` file
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub fn export_gradient<P: AsRef<Path>>(gradient: &psoc_core::Gradient, path: P) -> Result<()> {
+        gradient::export_ggr(gradient, path)
+    }
+
+    /// Export a flattened document as a PDF proof at its physical size
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub fn export_pdf<P: AsRef<Path>>(
+        document: &psoc_core::Document,
+        path: P,
+        options: &PdfExportOptions,
+    ) -> Result<()> {
+        pdf::export_pdf(document, path, options)
+    }
+
+    /// Export a document as a flattened image, keeping its embedded metadata
     #[instrument(skip_all, fields(path = %path.as_ref().display()))]
     pub fn export_flattened<P: AsRef<Path>>(document: &psoc_core::Document, path: P) -> Result<()> {
+        Self::export_flattened_with_metadata(document, path, false)
+    }
+
+    /// Export a document as a flattened image, optionally stripping private metadata
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub fn export_flattened_with_metadata<P: AsRef<Path>>(
+        document: &psoc_core::Document,
+        path: P,
+        strip_private_metadata: bool,
+    ) -> Result<()> {
         use psoc_core::RenderEngine;
 
         let path = path.as_ref();
@@ -306,7 +671,12 @@ impl FileIO {
         let image = pixel_data.to_image()?;
 
         // Save using ImageIO
-        ImageIO::save_image(&image, path)?;
+        ImageIO::save_image_with_metadata(
+            &image,
+            path,
+            &document.metadata.embedded,
+            strip_private_metadata,
+        )?;
 
         info!(
             layers = document.layers.len(),
@@ -332,6 +702,7 @@ mod tests {
         let result = ImageLoadResult {
             image: dynamic_img,
             icc_profile: None,
+            metadata: EmbeddedMetadata::default(),
         };
 
         assert_eq!(result.image.width(), 40);
@@ -360,4 +731,79 @@ mod tests {
         assert_eq!(image1.height(), result2.image.height());
         assert!(result2.icc_profile.is_none()); // No profile in test image
     }
+
+    #[test]
+    fn test_supported_format_tiff_and_webp() {
+        assert_eq!(
+            SupportedFormat::from_extension("tif"),
+            Some(SupportedFormat::Tiff)
+        );
+        assert_eq!(
+            SupportedFormat::from_extension("TIFF"),
+            Some(SupportedFormat::Tiff)
+        );
+        assert_eq!(
+            SupportedFormat::from_extension("webp"),
+            Some(SupportedFormat::Webp)
+        );
+        assert_eq!(SupportedFormat::Tiff.to_image_format(), ImageFormat::Tiff);
+        assert_eq!(SupportedFormat::Webp.mime_type(), "image/webp");
+        assert!(FileFormat::Tiff.is_image());
+        assert!(FileFormat::Webp.is_image());
+    }
+
+    #[test]
+    fn test_image_io_tiff_and_webp_roundtrip() {
+        use image::{ImageBuffer, Rgb};
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().unwrap();
+        let img: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::new(30, 20);
+        let dynamic_img = image::DynamicImage::ImageRgb8(img);
+
+        for name in ["test.tif", "test.webp"] {
+            let file_path = temp_dir.path().join(name);
+            ImageIO::save_image(&dynamic_img, &file_path).unwrap();
+            let result = ImageIO::load_image_with_profile(&file_path).unwrap();
+            assert_eq!(result.image.width(), 30);
+            assert_eq!(result.image.height(), 20);
+        }
+    }
+
+    #[test]
+    fn test_file_format_openraster() {
+        assert_eq!(
+            FileFormat::from_extension("ora"),
+            Some(FileFormat::OpenRaster)
+        );
+        assert_eq!(
+            FileFormat::from_path("art.ORA"),
+            Some(FileFormat::OpenRaster)
+        );
+        assert!(FileFormat::OpenRaster.is_layered());
+        assert!(!FileFormat::OpenRaster.is_image());
+        assert_eq!(FileFormat::OpenRaster.mime_type(), "image/openraster");
+        assert!(ImageIO::all_supported_extensions().contains(&"ora"));
+    }
+
+    #[test]
+    fn test_file_io_openraster_roundtrip() {
+        use psoc_core::{Document, Layer};
+        use tempfile::tempdir;
+
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("roundtrip.ora");
+
+        let mut document = Document::new("Roundtrip".to_string(), 20, 10);
+        document.add_layer(Layer::new_pixel("Base".to_string(), 20, 10));
+        document.add_layer(Layer::new_pixel("Top".to_string(), 5, 5));
+
+        FileIO::save_openraster(&document, &file_path).unwrap();
+        let loaded = FileIO::load_document(&file_path).unwrap();
+
+        assert_eq!(loaded.metadata.title, "roundtrip");
+        assert_eq!(loaded.dimensions(), (20, 10));
+        assert_eq!(loaded.layers.len(), 2);
+        assert_eq!(loaded.layers[1].name, "Top");
+    }
 }
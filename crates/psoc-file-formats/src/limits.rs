@@ -0,0 +1,290 @@
+//! Resource limits for decoding untrusted files
+//!
+//! Image and project files declare their own dimensions, so a corrupt or
+//! malicious file can ask for far more memory than it contains. Loaders check
+//! every allocation against [`DecodeLimits`] before making it and fail with a
+//! [`DecodeLimitError`], which callers can recover from an [`anyhow::Error`]
+//! with `downcast_ref`.
+//!
+//! Limits are enforced by every loader: still images, GIF, APNG and WebP
+//! animations, OpenRaster files, OpenEXR and Radiance HDR images and projects.
+
+use anyhow::{Context, Result};
+use std::io::Read;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// Default maximum image width and height in pixels
+pub const DEFAULT_MAX_DIMENSION: u32 = 32_768;
+
+/// Default memory budget for a single decode (2 GiB)
+pub const DEFAULT_MAX_MEMORY: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Default time limit for a single decode
+pub const DEFAULT_MAX_DECODE_TIME: Duration = Duration::from_secs(120);
+
+/// Largest embedded ICC profile that is read
+pub(crate) const MAX_ICC_PROFILE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Limits applied while decoding a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum width of any image or layer in pixels
+    pub max_width: u32,
+    /// Maximum height of any image or layer in pixels
+    pub max_height: u32,
+    /// Maximum number of bytes allocated for pixel data and file contents
+    pub max_memory: u64,
+    /// Maximum wall-clock time for the whole decode, `None` for no limit
+    pub max_decode_time: Option<Duration>,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_width: DEFAULT_MAX_DIMENSION,
+            max_height: DEFAULT_MAX_DIMENSION,
+            max_memory: DEFAULT_MAX_MEMORY,
+            max_decode_time: Some(DEFAULT_MAX_DECODE_TIME),
+        }
+    }
+}
+
+impl DecodeLimits {
+    /// Limits that never reject a file
+    pub fn unlimited() -> Self {
+        Self {
+            max_width: u32::MAX,
+            max_height: u32::MAX,
+            max_memory: u64::MAX,
+            max_decode_time: None,
+        }
+    }
+
+    /// Set the maximum image width and height
+    pub fn with_max_dimensions(mut self, max_width: u32, max_height: u32) -> Self {
+        self.max_width = max_width;
+        self.max_height = max_height;
+        self
+    }
+
+    /// Set the memory budget in bytes
+    pub fn with_max_memory(mut self, max_memory: u64) -> Self {
+        self.max_memory = max_memory;
+        self
+    }
+
+    /// Set the decode time limit
+    pub fn with_max_decode_time(mut self, max_decode_time: Option<Duration>) -> Self {
+        self.max_decode_time = max_decode_time;
+        self
+    }
+}
+
+/// A decode was stopped because the file exceeded a [`DecodeLimits`] value
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DecodeLimitError {
+    #[error("Image size {width}x{height} exceeds the limit of {max_width}x{max_height}")]
+    DimensionsTooLarge {
+        width: u32,
+        height: u32,
+        max_width: u32,
+        max_height: u32,
+    },
+    #[error("Decoding needs {required} bytes, exceeding the memory budget of {budget} bytes")]
+    MemoryBudgetExceeded { required: u64, budget: u64 },
+    #[error("Decoding exceeded the time limit of {limit:?}")]
+    TimedOut { limit: Duration },
+}
+
+/// Running account of the memory and time spent on one decode
+#[derive(Debug)]
+pub(crate) struct DecodeBudget {
+    limits: DecodeLimits,
+    used: u64,
+    started: Instant,
+}
+
+impl DecodeBudget {
+    /// Start accounting for a new decode
+    pub fn new(limits: &DecodeLimits) -> Self {
+        Self {
+            limits: *limits,
+            used: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Reject dimensions over the configured maximum
+    pub fn check_dimensions(&self, width: u32, height: u32) -> Result<(), DecodeLimitError> {
+        if width > self.limits.max_width || height > self.limits.max_height {
+            return Err(DecodeLimitError::DimensionsTooLarge {
+                width,
+                height,
+                max_width: self.limits.max_width,
+                max_height: self.limits.max_height,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check the dimensions of a pixel buffer and reserve its memory
+    pub fn reserve_pixels(
+        &mut self,
+        width: u32,
+        height: u32,
+        bytes_per_pixel: u64,
+    ) -> Result<(), DecodeLimitError> {
+        self.check_dimensions(width, height)?;
+        let bytes = (width as u64)
+            .saturating_mul(height as u64)
+            .saturating_mul(bytes_per_pixel);
+        self.reserve(bytes)
+    }
+
+    /// Reserve memory against the budget
+    pub fn reserve(&mut self, bytes: u64) -> Result<(), DecodeLimitError> {
+        let required = self.used.saturating_add(bytes);
+        if required > self.limits.max_memory {
+            return Err(DecodeLimitError::MemoryBudgetExceeded {
+                required,
+                budget: self.limits.max_memory,
+            });
+        }
+        self.used = required;
+        Ok(())
+    }
+
+    /// Return memory to the budget once a temporary buffer has been freed
+    pub fn release(&mut self, bytes: u64) {
+        self.used = self.used.saturating_sub(bytes);
+    }
+
+    /// Fail if the decode has run past its time limit
+    pub fn check_time(&self) -> Result<(), DecodeLimitError> {
+        match self.limits.max_decode_time {
+            Some(limit) if self.started.elapsed() > limit => {
+                Err(DecodeLimitError::TimedOut { limit })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Memory left in the budget
+    pub fn remaining(&self) -> u64 {
+        self.limits.max_memory.saturating_sub(self.used)
+    }
+
+    /// Equivalent limits for decoders from the `image` crate
+    pub fn image_limits(&self) -> image::Limits {
+        let mut limits = image::Limits::no_limits();
+        limits.max_image_width = Some(self.limits.max_width);
+        limits.max_image_height = Some(self.limits.max_height);
+        limits.max_alloc = Some(self.remaining());
+        limits
+    }
+
+    /// Read a stream to the end, failing once it grows past the budget
+    pub fn read_to_end<R: Read>(&mut self, reader: R, what: &str) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        let remaining = self.remaining();
+        reader
+            .take(remaining.saturating_add(1))
+            .read_to_end(&mut data)
+            .with_context(|| format!("Failed to read {}", what))?;
+        self.reserve(data.len() as u64)?;
+        Ok(data)
+    }
+}
+
+/// Run a decode on a worker thread, giving up once the time limit passes
+///
+/// Decoders cannot be interrupted, so a decode that times out keeps running in
+/// the background until it finishes; its memory is still bounded by the budget.
+pub(crate) fn run_with_time_limit<T, F>(limit: Option<Duration>, decode: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let Some(limit) = limit else {
+        return decode();
+    };
+
+    let (sender, receiver) = mpsc::sync_channel(1);
+    std::thread::Builder::new()
+        .name("psoc-decode".to_string())
+        .spawn(move || {
+            // The receiver is gone if the decode already timed out
+            let _ = sender.send(decode());
+        })
+        .context("Failed to start decode thread")?;
+
+    match receiver.recv_timeout(limit) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(DecodeLimitError::TimedOut { limit }.into()),
+        Err(RecvTimeoutError::Disconnected) => Err(anyhow::anyhow!("Decode thread panicked")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_accumulates_reservations() {
+        let limits = DecodeLimits::default().with_max_memory(1000);
+        let mut budget = DecodeBudget::new(&limits);
+        assert!(budget.reserve_pixels(10, 10, 4).is_ok());
+        assert!(budget.reserve_pixels(10, 10, 4).is_ok());
+        assert_eq!(budget.remaining(), 200);
+        assert_eq!(
+            budget.reserve_pixels(10, 10, 4),
+            Err(DecodeLimitError::MemoryBudgetExceeded {
+                required: 1200,
+                budget: 1000
+            })
+        );
+        // A failed reservation does not use up the budget
+        assert_eq!(budget.remaining(), 200);
+
+        budget.release(400);
+        assert_eq!(budget.remaining(), 600);
+    }
+
+    #[test]
+    fn test_dimensions_are_checked_per_axis() {
+        let limits = DecodeLimits::unlimited().with_max_dimensions(100, 50);
+        let budget = DecodeBudget::new(&limits);
+        assert!(budget.check_dimensions(100, 50).is_ok());
+        assert!(matches!(
+            budget.check_dimensions(40, 51),
+            Err(DecodeLimitError::DimensionsTooLarge { height: 51, .. })
+        ));
+    }
+
+    #[test]
+    fn test_read_to_end_stops_at_budget() {
+        let limits = DecodeLimits::default().with_max_memory(8);
+        let mut budget = DecodeBudget::new(&limits);
+        let error = budget
+            .read_to_end(std::io::repeat(7), "endless stream")
+            .unwrap_err();
+        assert!(error.downcast_ref::<DecodeLimitError>().is_some());
+    }
+
+    #[test]
+    fn test_time_limit_abandons_slow_decode() {
+        let result = run_with_time_limit(Some(Duration::from_millis(20)), || {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(())
+        });
+        let error = result.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<DecodeLimitError>(),
+            Some(DecodeLimitError::TimedOut { .. })
+        ));
+
+        let value = run_with_time_limit(Some(Duration::from_secs(5)), || Ok(42)).unwrap();
+        assert_eq!(value, 42);
+    }
+}
@@ -0,0 +1,1083 @@
+//! Embedded image metadata (EXIF, XMP and IPTC)
+//!
+//! Metadata blocks are read on import, summarized into [`DocumentMetadata`] and
+//! kept verbatim in [`EmbeddedMetadata`] so they can be written back on JPEG,
+//! PNG and TIFF export. Pixels are rotated according to the EXIF orientation
+//! on import, so exported metadata never carries an orientation.
+
+use crate::limits::{run_with_time_limit, DecodeBudget, DecodeLimits};
+use anyhow::{Context, Result};
+use exif::{Field, In, Tag as ExifTag, Value};
+use image::{metadata::Orientation, ImageDecoder};
+use psoc_core::{DocumentMetadata, EmbeddedMetadata};
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// Key used in [`DocumentMetadata::custom_fields`] for the copyright notice
+pub const COPYRIGHT_FIELD: &str = "copyright";
+
+/// Prefix of EXIF summary keys in [`DocumentMetadata::custom_fields`]
+pub const EXIF_FIELD_PREFIX: &str = "exif:";
+
+/// EXIF tags copied into the document's custom fields on import
+///
+/// GPS tags are left out: custom fields are not filtered when private
+/// metadata is stripped, so location data must stay in the EXIF block.
+const SUMMARY_TAGS: &[ExifTag] = &[
+    ExifTag::Make,
+    ExifTag::Model,
+    ExifTag::LensModel,
+    ExifTag::DateTimeOriginal,
+    ExifTag::ExposureTime,
+    ExifTag::FNumber,
+    ExifTag::PhotographicSensitivity,
+    ExifTag::FocalLength,
+];
+
+/// EXIF tags identifying the camera, its owner or the exact shot
+const PRIVATE_EXIF_TAGS: &[ExifTag] = &[
+    ExifTag::MakerNote,
+    ExifTag::ImageUniqueID,
+    ExifTag::CameraOwnerName,
+    ExifTag::BodySerialNumber,
+    ExifTag::LensSerialNumber,
+];
+
+/// Primary IFD tags describing the source image layout rather than its content
+const STRUCTURAL_TIFF_TAGS: &[u16] = &[
+    0x00fe, // NewSubfileType
+    0x0100, // ImageWidth
+    0x0101, // ImageLength
+    0x0102, // BitsPerSample
+    0x0103, // Compression
+    0x0106, // PhotometricInterpretation
+    0x0111, // StripOffsets
+    0x0112, // Orientation
+    0x0115, // SamplesPerPixel
+    0x0116, // RowsPerStrip
+    0x0117, // StripByteCounts
+    0x011a, // XResolution
+    0x011b, // YResolution
+    0x011c, // PlanarConfiguration
+    0x0128, // ResolutionUnit
+    0x013d, // Predictor
+    0x0140, // ColorMap
+    0x0142, // TileWidth
+    0x0143, // TileLength
+    0x0144, // TileOffsets
+    0x0145, // TileByteCounts
+    0x0152, // ExtraSamples
+    0x0153, // SampleFormat
+    0x0201, // JPEGInterchangeFormat
+    0x0202, // JPEGInterchangeFormatLength
+    0x0211, // YCbCrCoefficients
+    0x0212, // YCbCrSubSampling
+    0x0213, // YCbCrPositioning
+    0x02bc, // XMP packet
+    0x83bb, // IPTC-NAA
+    0x8773, // ICC profile
+];
+
+/// XMP properties holding the orientation, which no longer applies after import
+const XMP_ORIENTATION_PROPERTIES: &[&str] = &["tiff:Orientation"];
+
+/// XMP properties identifying the camera, its owner or the exact shot
+const PRIVATE_XMP_PROPERTIES: &[&str] = &[
+    "aux:SerialNumber",
+    "aux:LensSerialNumber",
+    "aux:OwnerName",
+    "exifEX:BodySerialNumber",
+    "exifEX:LensSerialNumber",
+    "exifEX:CameraOwnerName",
+    "exif:ImageUniqueID",
+    "photoshop:City",
+    "photoshop:State",
+    "photoshop:Country",
+    "Iptc4xmpCore:Location",
+    "Iptc4xmpCore:CountryCode",
+];
+
+/// XMP property prefixes holding location data
+const PRIVATE_XMP_PREFIXES: &[&str] = &["exif:GPS"];
+
+/// IPTC datasets (record 2) holding location data
+const PRIVATE_IPTC_DATASETS: &[u8] = &[90, 92, 95, 100, 101];
+
+/// IPTC record 2 datasets summarized on import
+const IPTC_KEYWORDS: u8 = 25;
+const IPTC_BYLINE: u8 = 80;
+const IPTC_COPYRIGHT: u8 = 116;
+const IPTC_CAPTION: u8 = 120;
+
+/// Photoshop image resource holding IPTC records
+const PHOTOSHOP_IPTC_RESOURCE: u16 = 0x0404;
+
+/// Identifier of JPEG APP1 EXIF segments
+const JPEG_EXIF_HEADER: &[u8] = b"Exif\0\0";
+/// Identifier of JPEG APP1 XMP segments
+const JPEG_XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+/// Identifier of JPEG APP13 Photoshop segments
+const JPEG_PHOTOSHOP_HEADER: &[u8] = b"Photoshop 3.0\0";
+/// PNG iTXt keyword for XMP packets
+const PNG_XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+/// Dublin Core namespace used by XMP
+const DC_NAMESPACE: &str = "http://purl.org/dc/elements/1.1/";
+
+/// An image decoded with its ICC profile and embedded metadata
+#[derive(Debug)]
+pub(crate) struct DecodedImage {
+    /// The decoded image, rotated upright
+    pub image: image::DynamicImage,
+    /// Raw embedded ICC profile, if any
+    pub icc_profile: Option<Vec<u8>>,
+    /// Embedded EXIF/XMP/IPTC blocks
+    pub metadata: EmbeddedMetadata,
+}
+
+/// Decode an image, reading its metadata and applying the EXIF orientation
+///
+/// The declared dimensions and buffer size are checked against `limits` before
+/// any pixels are decoded.
+pub(crate) fn decode_image(
+    path: &Path,
+    format_name: &'static str,
+    limits: &DecodeLimits,
+) -> Result<DecodedImage> {
+    let path = path.to_path_buf();
+    let limits = *limits;
+    run_with_time_limit(limits.max_decode_time, move || {
+        decode_image_within(&path, format_name, &mut DecodeBudget::new(&limits))
+    })
+}
+
+pub(crate) fn decode_image_within(
+    path: &Path,
+    format_name: &str,
+    budget: &mut DecodeBudget,
+) -> Result<DecodedImage> {
+    let mut decoder = image::ImageReader::open(path)
+        .with_context(|| format!("Failed to open {} file: {}", format_name, path.display()))?
+        .with_guessed_format()
+        .with_context(|| format!("Failed to detect {} format", format_name))?
+        .into_decoder()
+        .with_context(|| {
+            format!(
+                "Failed to create {} decoder for: {}",
+                format_name,
+                path.display()
+            )
+        })?;
+
+    let (width, height) = decoder.dimensions();
+    budget.check_dimensions(width, height)?;
+    budget.reserve(decoder.total_bytes())?;
+    decoder
+        .set_limits(budget.image_limits())
+        .with_context(|| format!("{} image exceeds the decode limits", format_name))?;
+
+    let icc_profile = decoder.icc_profile().unwrap_or_else(|e| {
+        warn!("Failed to read {} ICC profile: {}", format_name, e);
+        None
+    });
+    let metadata = read_embedded_metadata(&mut decoder);
+    let orientation = decoder.orientation().unwrap_or_else(|e| {
+        warn!("Failed to read {} orientation: {}", format_name, e);
+        Orientation::NoTransforms
+    });
+
+    let mut image = image::DynamicImage::from_decoder(decoder).with_context(|| {
+        format!(
+            "Failed to load {} image from: {}",
+            format_name,
+            path.display()
+        )
+    })?;
+
+    if orientation != Orientation::NoTransforms {
+        debug!(?orientation, "Applying EXIF orientation");
+        image.apply_orientation(orientation);
+    }
+
+    Ok(DecodedImage {
+        image,
+        icc_profile,
+        metadata,
+    })
+}
+
+/// Read the EXIF, XMP and IPTC blocks exposed by an image decoder
+///
+/// Unreadable blocks are logged and skipped rather than failing the import.
+pub fn read_embedded_metadata<D: ImageDecoder>(decoder: &mut D) -> EmbeddedMetadata {
+    let exif = decoder
+        .exif_metadata()
+        .unwrap_or_else(|e| {
+            warn!("Failed to read EXIF metadata: {}", e);
+            None
+        })
+        .map(|data| match data.strip_prefix(JPEG_EXIF_HEADER) {
+            Some(tiff) => tiff.to_vec(),
+            None => data,
+        });
+
+    let xmp = decoder
+        .xmp_metadata()
+        .unwrap_or_else(|e| {
+            warn!("Failed to read XMP metadata: {}", e);
+            None
+        })
+        .and_then(|data| match String::from_utf8(data) {
+            Ok(xmp) => Some(xmp.trim_end_matches('\0').to_string()),
+            Err(_) => {
+                warn!("Ignoring XMP packet that is not valid UTF-8");
+                None
+            }
+        });
+
+    let iptc = decoder
+        .iptc_metadata()
+        .unwrap_or_else(|e| {
+            warn!("Failed to read IPTC metadata: {}", e);
+            None
+        })
+        .and_then(|data| normalize_iptc(&data));
+
+    EmbeddedMetadata { exif, xmp, iptc }
+}
+
+/// Read EXIF, XMP and IPTC blocks stored directly in a TIFF file's primary IFD
+///
+/// The image decoder does not reliably expose these for TIFF, so they are read
+/// from the container and EXIF is re-encoded as a standalone block.
+pub(crate) fn read_tiff_metadata(path: &Path) -> Result<EmbeddedMetadata> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open TIFF file: {}", path.display()))?;
+    let exif = match exif::Reader::new().read_from_container(&mut BufReader::new(file)) {
+        Ok(exif) => exif,
+        Err(exif::Error::NotFound(_)) => return Ok(EmbeddedMetadata::default()),
+        Err(e) => return Err(e).context("Failed to read TIFF metadata"),
+    };
+
+    let iptc = exif
+        .get_field(ExifTag(exif::Context::Tiff, 0x83bb), In::PRIMARY)
+        .and_then(|field| match &field.value {
+            Value::Undefined(data, _) | Value::Byte(data) => Some(data.clone()),
+            // Photoshop stores IPTC as LONG, so undo the file's byte order
+            Value::Long(values) => Some(
+                values
+                    .iter()
+                    .flat_map(|v| {
+                        if exif.little_endian() {
+                            v.to_le_bytes()
+                        } else {
+                            v.to_be_bytes()
+                        }
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .and_then(|data| normalize_iptc(&data));
+
+    let xmp = exif
+        .get_field(ExifTag(exif::Context::Tiff, 0x02bc), In::PRIMARY)
+        .and_then(|field| match &field.value {
+            Value::Undefined(data, _) | Value::Byte(data) => Some(
+                String::from_utf8_lossy(data)
+                    .trim_end_matches('\0')
+                    .to_string(),
+            ),
+            _ => None,
+        });
+
+    let fields: Vec<Field> = exif
+        .fields()
+        .filter(|field| is_exportable_exif_field(field, false))
+        .cloned()
+        .collect();
+
+    Ok(EmbeddedMetadata {
+        exif: encode_exif(&fields, exif.little_endian())?,
+        xmp,
+        iptc,
+    })
+}
+
+/// Fill the document's author, description, keywords and custom fields from its embedded metadata
+///
+/// Values already set on the document are kept. XMP takes precedence over IPTC,
+/// which takes precedence over EXIF.
+pub fn apply_embedded_metadata(metadata: &mut DocumentMetadata) {
+    let embedded = metadata.embedded.clone();
+
+    if let Some(ref xmp) = embedded.xmp {
+        match XmpSummary::parse(xmp) {
+            Ok(summary) => summary.apply(metadata),
+            Err(e) => warn!("Failed to parse XMP metadata: {}", e),
+        }
+    }
+
+    if let Some(ref iptc) = embedded.iptc {
+        for (record, dataset, value) in parse_iptc(iptc) {
+            if record != 2 {
+                continue;
+            }
+            let value = String::from_utf8_lossy(value).trim().to_string();
+            if value.is_empty() {
+                continue;
+            }
+            match dataset {
+                IPTC_KEYWORDS => push_keyword(metadata, value),
+                IPTC_BYLINE => {
+                    metadata.author.get_or_insert(value);
+                }
+                IPTC_CAPTION => {
+                    metadata.description.get_or_insert(value);
+                }
+                IPTC_COPYRIGHT => {
+                    metadata
+                        .custom_fields
+                        .entry(COPYRIGHT_FIELD.to_string())
+                        .or_insert(value);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(ref data) = embedded.exif {
+        let exif = match exif::Reader::new().read_raw(data.clone()) {
+            Ok(exif) => exif,
+            Err(e) => {
+                warn!("Failed to parse EXIF metadata: {}", e);
+                return;
+            }
+        };
+
+        if let Some(artist) = exif
+            .get_field(ExifTag::Artist, In::PRIMARY)
+            .and_then(ascii_value)
+        {
+            metadata.author.get_or_insert(artist);
+        }
+        if let Some(description) = exif
+            .get_field(ExifTag::ImageDescription, In::PRIMARY)
+            .and_then(ascii_value)
+        {
+            metadata.description.get_or_insert(description);
+        }
+        if let Some(copyright) = exif
+            .get_field(ExifTag::Copyright, In::PRIMARY)
+            .and_then(ascii_value)
+        {
+            metadata
+                .custom_fields
+                .entry(COPYRIGHT_FIELD.to_string())
+                .or_insert(copyright);
+        }
+
+        for tag in SUMMARY_TAGS {
+            if let Some(field) = exif.get_field(*tag, In::PRIMARY) {
+                let value = ascii_value(field)
+                    .unwrap_or_else(|| field.display_value().with_unit(&exif).to_string());
+                metadata
+                    .custom_fields
+                    .entry(format!("{}{}", EXIF_FIELD_PREFIX, tag))
+                    .or_insert(value);
+            }
+        }
+    }
+}
+
+/// Metadata prepared for writing into an exported image
+#[derive(Debug, Default)]
+pub(crate) struct PreparedMetadata {
+    /// EXIF fields to write
+    pub exif_fields: Vec<Field>,
+    /// Byte order of the source EXIF block
+    pub exif_little_endian: bool,
+    /// XMP packet to write
+    pub xmp: Option<String>,
+    /// IPTC-IIM records to write
+    pub iptc: Option<Vec<u8>>,
+}
+
+impl PreparedMetadata {
+    /// Filter embedded metadata for an exported image of the given size
+    ///
+    /// Orientation and layout tags are always dropped. With `strip_private`, GPS
+    /// data, serial numbers, maker notes and IPTC/XMP location fields are removed too.
+    pub fn new(metadata: &EmbeddedMetadata, strip_private: bool, dimensions: (u32, u32)) -> Self {
+        let mut prepared = Self::default();
+
+        if let Some(ref data) = metadata.exif {
+            match exif::Reader::new().read_raw(data.clone()) {
+                Ok(exif) => {
+                    prepared.exif_little_endian = exif.little_endian();
+                    prepared.exif_fields = exif
+                        .fields()
+                        .filter(|field| is_exportable_exif_field(field, strip_private))
+                        .map(|field| match field.tag {
+                            ExifTag::PixelXDimension => Field {
+                                value: Value::Long(vec![dimensions.0]),
+                                ..field.clone()
+                            },
+                            ExifTag::PixelYDimension => Field {
+                                value: Value::Long(vec![dimensions.1]),
+                                ..field.clone()
+                            },
+                            _ => field.clone(),
+                        })
+                        .collect();
+                }
+                Err(e) => warn!("Dropping unreadable EXIF metadata: {}", e),
+            }
+        }
+
+        prepared.xmp = metadata.xmp.as_deref().map(|xmp| {
+            strip_xmp_properties(xmp, |name| {
+                XMP_ORIENTATION_PROPERTIES.contains(&name)
+                    || (strip_private
+                        && (PRIVATE_XMP_PROPERTIES.contains(&name)
+                            || PRIVATE_XMP_PREFIXES
+                                .iter()
+                                .any(|prefix| name.starts_with(prefix))))
+            })
+        });
+
+        prepared.iptc = metadata.iptc.as_deref().and_then(|iptc| {
+            let records: Vec<_> = parse_iptc(iptc)
+                .into_iter()
+                .filter(|(record, dataset, _)| {
+                    !(strip_private && *record == 2 && PRIVATE_IPTC_DATASETS.contains(dataset))
+                })
+                .collect();
+            (!records.is_empty()).then(|| encode_iptc(&records))
+        });
+
+        prepared
+    }
+
+    /// Encode the EXIF fields as a standalone TIFF structure
+    pub fn encoded_exif(&self) -> Result<Option<Vec<u8>>> {
+        encode_exif(&self.exif_fields, self.exif_little_endian)
+    }
+}
+
+/// Insert metadata segments into an encoded JPEG stream
+///
+/// Segments are placed after the SOI marker and any JFIF header. Blocks that do
+/// not fit in a single JPEG segment are skipped with a warning.
+pub fn embed_jpeg_metadata(
+    jpeg: &[u8],
+    metadata: &EmbeddedMetadata,
+    strip_private: bool,
+    dimensions: (u32, u32),
+) -> Result<Vec<u8>> {
+    if !jpeg.starts_with(&[0xFF, 0xD8]) {
+        return Err(anyhow::anyhow!("Not a JPEG stream"));
+    }
+
+    let prepared = PreparedMetadata::new(metadata, strip_private, dimensions);
+
+    // Keep the JFIF APP0 segment first, as required by the JFIF specification
+    let mut insert_at = 2;
+    if jpeg.len() >= 6 && jpeg[2..4] == [0xFF, 0xE0] {
+        insert_at += 2 + u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+    }
+    if insert_at > jpeg.len() {
+        return Err(anyhow::anyhow!("Truncated JPEG header"));
+    }
+
+    let mut segments = Vec::new();
+    if let Some(exif) = prepared.encoded_exif()? {
+        push_jpeg_segment(&mut segments, 0xE1, JPEG_EXIF_HEADER, &exif, "EXIF");
+    }
+    if let Some(ref xmp) = prepared.xmp {
+        push_jpeg_segment(&mut segments, 0xE1, JPEG_XMP_HEADER, xmp.as_bytes(), "XMP");
+    }
+    if let Some(ref iptc) = prepared.iptc {
+        let resource = photoshop_iptc_resource(iptc);
+        push_jpeg_segment(
+            &mut segments,
+            0xED,
+            JPEG_PHOTOSHOP_HEADER,
+            &resource,
+            "IPTC",
+        );
+    }
+
+    let mut output = Vec::with_capacity(jpeg.len() + segments.len());
+    output.extend_from_slice(&jpeg[..insert_at]);
+    output.extend_from_slice(&segments);
+    output.extend_from_slice(&jpeg[insert_at..]);
+    Ok(output)
+}
+
+/// Insert metadata chunks into an encoded PNG stream
+///
+/// EXIF is written as an `eXIf` chunk and XMP as an `iTXt` chunk. PNG has no
+/// standard IPTC chunk, so IPTC records are only preserved in JPEG and TIFF.
+pub fn embed_png_metadata(
+    png: &[u8],
+    metadata: &EmbeddedMetadata,
+    strip_private: bool,
+    dimensions: (u32, u32),
+) -> Result<Vec<u8>> {
+    const SIGNATURE: &[u8] = &[137, 80, 78, 71, 13, 10, 26, 10];
+    if !png.starts_with(SIGNATURE) || png.len() < 16 || &png[12..16] != b"IHDR" {
+        return Err(anyhow::anyhow!("Not a PNG stream"));
+    }
+
+    let prepared = PreparedMetadata::new(metadata, strip_private, dimensions);
+
+    // Chunks go right after IHDR so they precede the image data
+    let ihdr_length = u32::from_be_bytes([png[8], png[9], png[10], png[11]]) as usize;
+    let insert_at = 8 + 12 + ihdr_length;
+    if insert_at > png.len() {
+        return Err(anyhow::anyhow!("Truncated PNG header"));
+    }
+
+    let mut chunks = Vec::new();
+    if let Some(exif) = prepared.encoded_exif()? {
+        push_png_chunk(&mut chunks, b"eXIf", &exif);
+    }
+    if let Some(ref xmp) = prepared.xmp {
+        let mut data = Vec::with_capacity(PNG_XMP_KEYWORD.len() + xmp.len() + 5);
+        data.extend_from_slice(PNG_XMP_KEYWORD.as_bytes());
+        // Null separator, uncompressed, compression method, empty language and translated keyword
+        data.extend_from_slice(&[0, 0, 0, 0, 0]);
+        data.extend_from_slice(xmp.as_bytes());
+        push_png_chunk(&mut chunks, b"iTXt", &data);
+    }
+    if prepared.iptc.is_some() {
+        debug!("PNG has no standard IPTC chunk, skipping IPTC metadata");
+    }
+
+    let mut output = Vec::with_capacity(png.len() + chunks.len());
+    output.extend_from_slice(&png[..insert_at]);
+    output.extend_from_slice(&chunks);
+    output.extend_from_slice(&png[insert_at..]);
+    Ok(output)
+}
+
+/// Inserts metadata into an encoded image: `(bytes, metadata, strip_private, dimensions)`
+pub(crate) type EmbedFn = fn(&[u8], &EmbeddedMetadata, bool, (u32, u32)) -> Result<Vec<u8>>;
+
+/// Rewrite an encoded image file with metadata inserted by `embed`
+pub(crate) fn embed_metadata_in_file(
+    path: &Path,
+    format_name: &str,
+    metadata: &EmbeddedMetadata,
+    strip_private: bool,
+    dimensions: (u32, u32),
+    embed: EmbedFn,
+) -> Result<()> {
+    if metadata.is_empty() {
+        return Ok(());
+    }
+
+    let encoded = std::fs::read(path)
+        .with_context(|| format!("Failed to read {} file: {}", format_name, path.display()))?;
+    let output = embed(&encoded, metadata, strip_private, dimensions).with_context(|| {
+        format!(
+            "Failed to embed metadata in {} file: {}",
+            format_name,
+            path.display()
+        )
+    })?;
+    std::fs::write(path, output)
+        .with_context(|| format!("Failed to write {} file: {}", format_name, path.display()))?;
+
+    debug!(strip_private, "Embedded {} metadata", format_name);
+    Ok(())
+}
+
+/// Check whether an EXIF field should be carried over on export
+fn is_exportable_exif_field(field: &Field, strip_private: bool) -> bool {
+    if field.ifd_num != In::PRIMARY {
+        // Thumbnails are stale once the image has been edited
+        return false;
+    }
+
+    match field.tag.context() {
+        exif::Context::Tiff => !STRUCTURAL_TIFF_TAGS.contains(&field.tag.number()),
+        exif::Context::Gps => !strip_private,
+        exif::Context::Exif => !(strip_private && PRIVATE_EXIF_TAGS.contains(&field.tag)),
+        _ => true,
+    }
+}
+
+/// Encode EXIF fields as a standalone TIFF structure
+fn encode_exif(fields: &[Field], little_endian: bool) -> Result<Option<Vec<u8>>> {
+    if fields.is_empty() {
+        return Ok(None);
+    }
+
+    let mut writer = exif::experimental::Writer::new();
+    for field in fields {
+        writer.push_field(field);
+    }
+
+    let mut buffer = Cursor::new(Vec::new());
+    writer
+        .write(&mut buffer, little_endian)
+        .context("Failed to encode EXIF metadata")?;
+    Ok(Some(buffer.into_inner()))
+}
+
+/// Get the first string of an ASCII EXIF field
+fn ascii_value(field: &Field) -> Option<String> {
+    match field.value {
+        Value::Ascii(ref strings) => strings
+            .first()
+            .map(|s| String::from_utf8_lossy(s).trim().to_string())
+            .filter(|s| !s.is_empty()),
+        _ => None,
+    }
+}
+
+/// Add a keyword unless it is already present
+fn push_keyword(metadata: &mut DocumentMetadata, keyword: String) {
+    if !metadata.keywords.contains(&keyword) {
+        metadata.keywords.push(keyword);
+    }
+}
+
+/// Extract raw IPTC-IIM records from either raw records or a Photoshop resource block
+fn normalize_iptc(data: &[u8]) -> Option<Vec<u8>> {
+    let data = data.strip_prefix(JPEG_PHOTOSHOP_HEADER).unwrap_or(data);
+
+    if data.first() == Some(&0x1C) {
+        return Some(data.to_vec());
+    }
+
+    // Photoshop image resources: "8BIM", id, padded Pascal name, size, padded data
+    let mut pos = 0;
+    while pos + 12 <= data.len() && &data[pos..pos + 4] == b"8BIM" {
+        let id = u16::from_be_bytes([data[pos + 4], data[pos + 5]]);
+        let name_len = data[pos + 6] as usize;
+        let name_size = (name_len + 2) & !1;
+        let size_at = pos + 6 + name_size;
+        if size_at + 4 > data.len() {
+            break;
+        }
+        let size = u32::from_be_bytes([
+            data[size_at],
+            data[size_at + 1],
+            data[size_at + 2],
+            data[size_at + 3],
+        ]) as usize;
+        let start = size_at + 4;
+        let end = start.checked_add(size)?;
+        if end > data.len() {
+            break;
+        }
+        if id == PHOTOSHOP_IPTC_RESOURCE {
+            return Some(data[start..end].to_vec());
+        }
+        pos = end + (size & 1);
+    }
+
+    None
+}
+
+/// Split IPTC-IIM data into (record, dataset, value) entries
+fn parse_iptc(data: &[u8]) -> Vec<(u8, u8, &[u8])> {
+    let mut records = Vec::new();
+    let mut pos = 0;
+
+    while pos + 5 <= data.len() && data[pos] == 0x1C {
+        let record = data[pos + 1];
+        let dataset = data[pos + 2];
+        let length = u16::from_be_bytes([data[pos + 3], data[pos + 4]]) as usize;
+        // Extended datasets (length with the high bit set) are not used for text fields
+        if length & 0x8000 != 0 || pos + 5 + length > data.len() {
+            break;
+        }
+        records.push((record, dataset, &data[pos + 5..pos + 5 + length]));
+        pos += 5 + length;
+    }
+
+    records
+}
+
+/// Encode (record, dataset, value) entries as IPTC-IIM data
+fn encode_iptc(records: &[(u8, u8, &[u8])]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for (record, dataset, value) in records {
+        data.extend_from_slice(&[0x1C, *record, *dataset]);
+        data.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        data.extend_from_slice(value);
+    }
+    data
+}
+
+/// Wrap IPTC records in a Photoshop image resource block
+fn photoshop_iptc_resource(iptc: &[u8]) -> Vec<u8> {
+    let mut resource = Vec::with_capacity(iptc.len() + 13);
+    resource.extend_from_slice(b"8BIM");
+    resource.extend_from_slice(&PHOTOSHOP_IPTC_RESOURCE.to_be_bytes());
+    // Empty Pascal name, padded to an even length
+    resource.extend_from_slice(&[0, 0]);
+    resource.extend_from_slice(&(iptc.len() as u32).to_be_bytes());
+    resource.extend_from_slice(iptc);
+    if iptc.len() % 2 == 1 {
+        resource.push(0);
+    }
+    resource
+}
+
+/// Append a JPEG marker segment, skipping payloads that exceed the segment size limit
+fn push_jpeg_segment(output: &mut Vec<u8>, marker: u8, header: &[u8], payload: &[u8], kind: &str) {
+    let length = 2 + header.len() + payload.len();
+    if length > u16::MAX as usize {
+        warn!(
+            "{} metadata is too large for a JPEG segment ({} bytes), skipping",
+            kind,
+            payload.len()
+        );
+        return;
+    }
+
+    output.extend_from_slice(&[0xFF, marker]);
+    output.extend_from_slice(&(length as u16).to_be_bytes());
+    output.extend_from_slice(header);
+    output.extend_from_slice(payload);
+}
+
+/// Append a PNG chunk with its CRC
+fn push_png_chunk(output: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    let mut crc = flate2::Crc::new();
+    crc.update(chunk_type);
+    crc.update(data);
+
+    output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    output.extend_from_slice(chunk_type);
+    output.extend_from_slice(data);
+    output.extend_from_slice(&crc.sum().to_be_bytes());
+}
+
+/// Remove XMP properties, whether written as elements or as attributes
+fn strip_xmp_properties(xmp: &str, is_stripped: impl Fn(&str) -> bool) -> String {
+    let mut output = String::with_capacity(xmp.len());
+    let mut pos = 0;
+
+    while let Some(offset) = xmp[pos..].find('<') {
+        let start = pos + offset;
+        output.push_str(&xmp[pos..start]);
+
+        let tag_end = find_tag_end(xmp, start);
+        let tag = &xmp[start..tag_end];
+        if tag.starts_with("</") || tag.starts_with("<?") || tag.starts_with("<!") {
+            output.push_str(tag);
+            pos = tag_end;
+            continue;
+        }
+
+        let name = xml_name(&tag[1..]);
+        if is_stripped(name) {
+            if tag.ends_with("/>") {
+                pos = tag_end;
+                continue;
+            }
+            let closing = format!("</{}>", name);
+            if let Some(close) = xmp[tag_end..].find(&closing) {
+                pos = tag_end + close + closing.len();
+                continue;
+            }
+        }
+
+        output.push_str(&strip_xml_attributes(tag, &is_stripped));
+        pos = tag_end;
+    }
+
+    output.push_str(&xmp[pos..]);
+    output
+}
+
+/// Find the end (exclusive) of the XML tag starting at `start`, honoring quoted values
+fn find_tag_end(xml: &str, start: usize) -> usize {
+    let mut quote = None;
+    for (offset, c) in xml[start..].char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '>') => return start + offset + 1,
+            _ => {}
+        }
+    }
+    xml.len()
+}
+
+/// Read an XML name from the start of a string
+fn xml_name(text: &str) -> &str {
+    let end = text
+        .find(|c: char| c.is_whitespace() || matches!(c, '>' | '/' | '='))
+        .unwrap_or(text.len());
+    &text[..end]
+}
+
+/// Remove matching attributes from a start tag, keeping the rest of the text as is
+fn strip_xml_attributes(tag: &str, is_stripped: &impl Fn(&str) -> bool) -> String {
+    let mut output = String::with_capacity(tag.len());
+    let name_end = 1 + xml_name(&tag[1..]).len();
+    output.push_str(&tag[..name_end]);
+
+    let mut pos = name_end;
+    while pos < tag.len() {
+        let rest = &tag[pos..];
+        let attribute_start = pos + (rest.len() - rest.trim_start().len());
+        let name = xml_name(&tag[attribute_start..]);
+        if name.is_empty() {
+            output.push_str(&tag[pos..]);
+            break;
+        }
+
+        // Attribute value: optional whitespace, '=', optional whitespace, quoted string
+        let mut end = attribute_start + name.len();
+        let after_name = &tag[end..];
+        let after_equals = after_name.trim_start();
+        if let Some(value) = after_equals.strip_prefix('=') {
+            let value_text = value.trim_start();
+            end = tag.len() - value_text.len();
+            if let Some(quote) = value_text
+                .chars()
+                .next()
+                .filter(|c| matches!(c, '"' | '\''))
+            {
+                end = match value_text[1..].find(quote) {
+                    Some(close) => end + close + 2,
+                    None => tag.len(),
+                };
+            }
+        }
+
+        if !is_stripped(name) {
+            output.push_str(&tag[pos..end]);
+        }
+        pos = end;
+    }
+
+    output
+}
+
+/// Dublin Core fields read from an XMP packet
+#[derive(Debug, Default)]
+struct XmpSummary {
+    creator: Option<String>,
+    description: Option<String>,
+    rights: Option<String>,
+    subjects: Vec<String>,
+}
+
+impl XmpSummary {
+    /// Parse the Dublin Core properties of an XMP packet
+    fn parse(xmp: &str) -> Result<Self> {
+        let document = roxmltree::Document::parse(xmp).context("Invalid XMP packet")?;
+        let mut summary = Self::default();
+
+        for node in document
+            .descendants()
+            .filter(|node| node.tag_name().namespace() == Some(DC_NAMESPACE))
+        {
+            let mut values: Vec<String> = node
+                .descendants()
+                .filter(|n| n.tag_name().name() == "li")
+                .filter_map(|n| n.text())
+                .map(|text| text.trim().to_string())
+                .filter(|text| !text.is_empty())
+                .collect();
+            if values.is_empty() {
+                values.extend(
+                    node.text()
+                        .map(str::trim)
+                        .filter(|text| !text.is_empty())
+                        .map(str::to_string),
+                );
+            }
+
+            match node.tag_name().name() {
+                "creator" => summary.creator = summary.creator.or(Some(values.join(", "))),
+                "description" => summary.description = values.into_iter().next(),
+                "rights" => summary.rights = values.into_iter().next(),
+                "subject" => summary.subjects.extend(values),
+                _ => {}
+            }
+        }
+
+        summary.creator = summary.creator.filter(|creator| !creator.is_empty());
+        Ok(summary)
+    }
+
+    /// Copy the summary into document metadata without overwriting existing values
+    fn apply(self, metadata: &mut DocumentMetadata) {
+        if let Some(creator) = self.creator {
+            metadata.author.get_or_insert(creator);
+        }
+        if let Some(description) = self.description {
+            metadata.description.get_or_insert(description);
+        }
+        if let Some(rights) = self.rights {
+            metadata
+                .custom_fields
+                .entry(COPYRIGHT_FIELD.to_string())
+                .or_insert(rights);
+        }
+        for subject in self.subjects {
+            push_keyword(metadata, subject);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_XMP: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:exif="http://ns.adobe.com/exif/1.0/" xmlns:tiff="http://ns.adobe.com/tiff/1.0/"
+    exif:GPSLatitude="48,51.5N" tiff:Orientation="6" tiff:Make="PSOC">
+   <dc:creator><rdf:Seq><rdf:li>Ada Lovelace</rdf:li></rdf:Seq></dc:creator>
+   <dc:subject><rdf:Bag><rdf:li>paris</rdf:li><rdf:li>night</rdf:li></rdf:Bag></dc:subject>
+   <exif:GPSLongitude>2,21.0E</exif:GPSLongitude>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+
+    fn sample_exif() -> Vec<u8> {
+        let fields = vec![
+            Field {
+                tag: ExifTag::Make,
+                ifd_num: In::PRIMARY,
+                value: Value::Ascii(vec![b"PSOC Camera".to_vec()]),
+            },
+            Field {
+                tag: ExifTag::Orientation,
+                ifd_num: In::PRIMARY,
+                value: Value::Short(vec![6]),
+            },
+            Field {
+                tag: ExifTag::Artist,
+                ifd_num: In::PRIMARY,
+                value: Value::Ascii(vec![b"Grace Hopper".to_vec()]),
+            },
+            Field {
+                tag: ExifTag::BodySerialNumber,
+                ifd_num: In::PRIMARY,
+                value: Value::Ascii(vec![b"SN-1234".to_vec()]),
+            },
+            Field {
+                tag: ExifTag::GPSLatitudeRef,
+                ifd_num: In::PRIMARY,
+                value: Value::Ascii(vec![b"N".to_vec()]),
+            },
+        ];
+        encode_exif(&fields, false).unwrap().unwrap()
+    }
+
+    fn sample_iptc() -> Vec<u8> {
+        encode_iptc(&[
+            (2, IPTC_KEYWORDS, b"sunset".as_slice()),
+            (2, IPTC_BYLINE, b"Iptc Author".as_slice()),
+            (2, 90, b"Paris".as_slice()),
+        ])
+    }
+
+    fn sample_metadata() -> EmbeddedMetadata {
+        EmbeddedMetadata {
+            exif: Some(sample_exif()),
+            xmp: Some(SAMPLE_XMP.to_string()),
+            iptc: Some(sample_iptc()),
+        }
+    }
+
+    #[test]
+    fn test_apply_embedded_metadata() {
+        let mut metadata = DocumentMetadata::new("Photo".to_string());
+        metadata.embedded = sample_metadata();
+        apply_embedded_metadata(&mut metadata);
+
+        // XMP wins over IPTC and EXIF
+        assert_eq!(metadata.author.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(metadata.keywords, vec!["paris", "night", "sunset"]);
+        assert_eq!(
+            metadata.custom_fields.get("exif:Make").map(String::as_str),
+            Some("PSOC Camera")
+        );
+    }
+
+    #[test]
+    fn test_location_stays_out_of_custom_fields() {
+        let mut metadata = DocumentMetadata::new("Photo".to_string());
+        metadata.embedded = sample_metadata();
+        apply_embedded_metadata(&mut metadata);
+
+        assert!(metadata
+            .custom_fields
+            .keys()
+            .all(|key| !key.contains("GPS")));
+
+        // The location is only kept in the EXIF block, where stripping removes it
+        let prepared = PreparedMetadata::new(&metadata.embedded, true, (10, 20));
+        assert!(prepared
+            .exif_fields
+            .iter()
+            .all(|field| field.tag.context() != exif::Context::Gps));
+    }
+
+    #[test]
+    fn test_prepare_drops_orientation_and_keeps_private_by_default() {
+        let prepared = PreparedMetadata::new(&sample_metadata(), false, (10, 20));
+        let tags: Vec<_> = prepared.exif_fields.iter().map(|f| f.tag).collect();
+
+        assert!(!tags.contains(&ExifTag::Orientation));
+        assert!(tags.contains(&ExifTag::BodySerialNumber));
+        assert!(tags.contains(&ExifTag::GPSLatitudeRef));
+
+        let xmp = prepared.xmp.unwrap();
+        assert!(!xmp.contains("tiff:Orientation"));
+        assert!(xmp.contains("exif:GPSLatitude"));
+        assert!(xmp.contains("tiff:Make=\"PSOC\""));
+    }
+
+    #[test]
+    fn test_prepare_strips_private_fields() {
+        let prepared = PreparedMetadata::new(&sample_metadata(), true, (10, 20));
+        let tags: Vec<_> = prepared.exif_fields.iter().map(|f| f.tag).collect();
+
+        assert!(tags.contains(&ExifTag::Make));
+        assert!(!tags.contains(&ExifTag::BodySerialNumber));
+        assert!(!tags.contains(&ExifTag::GPSLatitudeRef));
+
+        let xmp = prepared.xmp.unwrap();
+        assert!(!xmp.contains("GPS"));
+        assert!(xmp.contains("Ada Lovelace"));
+        assert!(roxmltree::Document::parse(&xmp).is_ok());
+
+        let iptc = prepared.iptc.unwrap();
+        let datasets: Vec<_> = parse_iptc(&iptc).into_iter().map(|(_, d, _)| d).collect();
+        assert_eq!(datasets, vec![IPTC_KEYWORDS, IPTC_BYLINE]);
+    }
+
+    #[test]
+    fn test_normalize_photoshop_iptc() {
+        let iptc = sample_iptc();
+        let mut block = JPEG_PHOTOSHOP_HEADER.to_vec();
+        block.extend_from_slice(&photoshop_iptc_resource(&iptc));
+
+        assert_eq!(normalize_iptc(&block), Some(iptc.clone()));
+        assert_eq!(normalize_iptc(&iptc), Some(iptc));
+        assert_eq!(normalize_iptc(b"garbage"), None);
+    }
+
+    #[test]
+    fn test_embed_rejects_foreign_streams() {
+        let metadata = sample_metadata();
+        assert!(embed_jpeg_metadata(b"not a jpeg", &metadata, false, (1, 1)).is_err());
+        assert!(embed_png_metadata(b"not a png", &metadata, false, (1, 1)).is_err());
+    }
+}
@@ -0,0 +1,387 @@
+//! PSOC project format migrations
+//!
+//! Older project files are upgraded one format version at a time until they
+//! reach [`PROJECT_FORMAT_VERSION`]. Each [`Migration`] parses the serialized
+//! project with a frozen copy of its `from` schema and writes it back out in
+//! the `to` schema, so the current [`ProjectFile`] is only ever parsed from
+//! RON that is already in the current format.
+//!
+//! To change the format: copy the current [`ProjectFile`] and any document
+//! types whose serialized form changes into a `v<major>_<minor>` module below,
+//! bump [`PROJECT_FORMAT_VERSION`], append a migration from the previous
+//! version, and add a fixture under `tests/fixtures`.
+
+use crate::project::{ProjectFile, PROJECT_FORMAT_VERSION};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::fmt;
+use tracing::{debug, info};
+
+/// A parsed `major.minor` project format version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FormatVersion {
+    /// Major version
+    pub major: u32,
+    /// Minor version
+    pub minor: u32,
+}
+
+impl FormatVersion {
+    /// Create a new format version
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+
+    /// Parse a `major.minor` version string
+    pub fn parse(version: &str) -> Result<Self> {
+        let (major, minor) = version
+            .trim()
+            .split_once('.')
+            .with_context(|| format!("Invalid project format version: {:?}", version))?;
+
+        Ok(Self {
+            major: major
+                .parse()
+                .with_context(|| format!("Invalid project format version: {:?}", version))?,
+            minor: minor
+                .parse()
+                .with_context(|| format!("Invalid project format version: {:?}", version))?,
+        })
+    }
+
+    /// The version written by this build
+    pub fn current() -> Self {
+        Self::parse(PROJECT_FORMAT_VERSION).expect("PROJECT_FORMAT_VERSION must be valid")
+    }
+}
+
+impl fmt::Display for FormatVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// A single upgrade step between two adjacent format versions
+pub struct Migration {
+    /// Version this step upgrades from
+    pub from: FormatVersion,
+    /// Version this step produces
+    pub to: FormatVersion,
+    /// Short summary of the schema change
+    pub description: &'static str,
+    /// Upgrade function, from project RON in the `from` format to the `to` format
+    pub apply: fn(&str) -> Result<String>,
+}
+
+impl fmt::Debug for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migration")
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
+/// All known migrations, ordered from oldest to newest
+static MIGRATIONS: &[Migration] = &[Migration {
+    from: FormatVersion::new(1, 0),
+    to: FormatVersion::new(2, 0),
+    description: "Move layer pixels out of the RON document into a tiled zip container",
+    apply: migrate_1_0_to_2_0,
+}];
+
+/// Get the registered migration chain
+pub fn migrations() -> &'static [Migration] {
+    MIGRATIONS
+}
+
+/// Check that a project version can be loaded by this build
+///
+/// Files written by a newer version of PSOC are rejected with a clear error
+/// instead of failing somewhere inside the document schema.
+pub fn check_project_version(version: &str) -> Result<FormatVersion> {
+    let found = FormatVersion::parse(version)?;
+    let current = FormatVersion::current();
+
+    if found > current {
+        return Err(anyhow::anyhow!(
+            "Project was saved by a newer version of PSOC (format {}, this version supports up to {}). Please update PSOC to open it.",
+            found,
+            current
+        ));
+    }
+
+    if found < current && !MIGRATIONS.iter().any(|m| m.from == found) {
+        return Err(anyhow::anyhow!(
+            "No migration available for project format {}",
+            found
+        ));
+    }
+
+    Ok(found)
+}
+
+/// Just the version field of a serialized project
+#[derive(Deserialize)]
+struct ProjectHeader {
+    version: String,
+}
+
+/// Upgrade serialized project RON to the current format version
+///
+/// Only the version field is read before the migrations run, so files from
+/// newer releases fail with a version error rather than a schema error.
+/// Returns the upgraded RON and the migrations that were applied, oldest first.
+pub fn migrate_project(content: &str) -> Result<(Cow<'_, str>, Vec<&'static Migration>)> {
+    let header: ProjectHeader =
+        ron::from_str(content).context("Failed to read project format version")?;
+    let mut version = check_project_version(&header.version)?;
+    let current = FormatVersion::current();
+    let mut content = Cow::Borrowed(content);
+    let mut applied = Vec::new();
+
+    while version < current {
+        let migration = MIGRATIONS
+            .iter()
+            .find(|m| m.from == version)
+            .with_context(|| format!("No migration available for project format {}", version))?;
+
+        debug!(
+            from = %migration.from,
+            to = %migration.to,
+            "Applying project migration: {}",
+            migration.description
+        );
+        content = Cow::Owned((migration.apply)(&content).with_context(|| {
+            format!(
+                "Failed to migrate project from format {} to {}",
+                migration.from, migration.to
+            )
+        })?);
+
+        version = migration.to;
+        applied.push(migration);
+    }
+
+    if !applied.is_empty() {
+        info!(
+            steps = applied.len(),
+            version = %version,
+            "Upgraded project to current format"
+        );
+    }
+
+    Ok((content, applied))
+}
+
+/// 1.0 → 2.0: v1 stores pixel data inline in the document, so there is no tile index
+///
+/// The document gained alpha channels, paths, assets, embedded metadata and
+/// per-layer metadata and HDR data, none of which a 1.0 file can carry.
+fn migrate_1_0_to_2_0(content: &str) -> Result<String> {
+    let project: v1_0::ProjectFile =
+        ron::from_str(content).context("Failed to parse 1.0 project document")?;
+
+    let upgraded = ProjectFile {
+        version: FormatVersion::new(2, 0).to_string(),
+        metadata: project.metadata,
+        document: project.document.into(),
+        layer_tiles: Vec::new(),
+        channel_tiles: Vec::new(),
+        selection_tiles: None,
+    };
+    ron::to_string(&upgraded).context("Failed to write 2.0 project document")
+}
+
+/// The 1.0 schema: one RON document with every pixel buffer inline
+mod v1_0 {
+    use crate::project::ProjectMetadata;
+    use chrono::{DateTime, Utc};
+    use psoc_core::{
+        BlendMode, ColorMode, CommandHistory, DocumentColorSpace, IccProfile, LayerType, PixelData,
+        Point, Rect, Resolution, RgbaPixel, Selection, Size, Transform,
+    };
+    use serde::Deserialize;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    #[derive(Deserialize)]
+    pub struct ProjectFile {
+        pub metadata: ProjectMetadata,
+        pub document: Document,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Document {
+        id: Uuid,
+        metadata: DocumentMetadata,
+        size: Size,
+        resolution: Resolution,
+        color_mode: ColorMode,
+        color_space: DocumentColorSpace,
+        icc_profile: Option<IccProfile>,
+        background_color: RgbaPixel,
+        layers: Vec<Layer>,
+        active_layer_index: Option<usize>,
+        canvas_bounds: Rect,
+        selection: Selection,
+        is_dirty: bool,
+        file_path: Option<PathBuf>,
+    }
+
+    #[derive(Deserialize)]
+    struct DocumentMetadata {
+        title: String,
+        author: Option<String>,
+        description: Option<String>,
+        keywords: Vec<String>,
+        created_at: DateTime<Utc>,
+        modified_at: DateTime<Utc>,
+        created_with_version: String,
+        custom_fields: HashMap<String, String>,
+    }
+
+    #[derive(Deserialize)]
+    struct Layer {
+        id: Uuid,
+        name: String,
+        layer_type: LayerType,
+        pixel_data: Option<PixelData>,
+        visible: bool,
+        opacity: f32,
+        blend_mode: BlendMode,
+        offset: Point,
+        transform: Transform,
+        bounds: Rect,
+        locked: bool,
+        mask: Option<PixelData>,
+    }
+
+    impl From<Document> for psoc_core::Document {
+        fn from(document: Document) -> Self {
+            Self {
+                id: document.id,
+                metadata: document.metadata.into(),
+                size: document.size,
+                resolution: document.resolution,
+                color_mode: document.color_mode,
+                color_space: document.color_space,
+                icc_profile: document.icc_profile,
+                background_color: document.background_color,
+                layers: document.layers.into_iter().map(Into::into).collect(),
+                active_layer_index: document.active_layer_index,
+                canvas_bounds: document.canvas_bounds,
+                selection: document.selection,
+                quick_mask: None,
+                channels: Vec::new(),
+                active_channel: None,
+                paths: Vec::new(),
+                active_path: None,
+                assets: Vec::new(),
+                is_dirty: document.is_dirty,
+                revision: 0,
+                file_path: document.file_path,
+                command_history: CommandHistory::new(),
+            }
+        }
+    }
+
+    impl From<DocumentMetadata> for psoc_core::DocumentMetadata {
+        fn from(metadata: DocumentMetadata) -> Self {
+            Self {
+                title: metadata.title,
+                author: metadata.author,
+                description: metadata.description,
+                keywords: metadata.keywords,
+                created_at: metadata.created_at,
+                modified_at: metadata.modified_at,
+                created_with_version: metadata.created_with_version,
+                custom_fields: metadata.custom_fields,
+                embedded: Default::default(),
+            }
+        }
+    }
+
+    impl From<Layer> for psoc_core::Layer {
+        fn from(layer: Layer) -> Self {
+            Self {
+                id: layer.id,
+                name: layer.name,
+                layer_type: layer.layer_type,
+                pixel_data: layer.pixel_data,
+                visible: layer.visible,
+                opacity: layer.opacity,
+                blend_mode: layer.blend_mode,
+                offset: layer.offset,
+                transform: layer.transform,
+                bounds: layer.bounds,
+                locked: layer.locked,
+                mask: layer.mask,
+                metadata: Default::default(),
+                hdr_data: None,
+                hdr_base: None,
+                pending_pixels: None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use psoc_core::Document;
+
+    #[test]
+    fn test_format_version_parse_and_order() -> Result<()> {
+        assert_eq!(FormatVersion::parse("1.0")?, FormatVersion::new(1, 0));
+        assert_eq!(FormatVersion::parse(" 2.10 ")?, FormatVersion::new(2, 10));
+        assert!(FormatVersion::new(1, 9) < FormatVersion::new(1, 10));
+        assert!(FormatVersion::new(1, 10) < FormatVersion::new(2, 0));
+        assert_eq!(FormatVersion::new(2, 0).to_string(), "2.0");
+
+        assert!(FormatVersion::parse("2").is_err());
+        assert!(FormatVersion::parse("two.zero").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migration_chain_is_contiguous() {
+        let current = FormatVersion::current();
+        let chain = migrations();
+
+        for pair in chain.windows(2) {
+            assert_eq!(pair[0].to, pair[1].from);
+        }
+        for migration in chain {
+            assert!(migration.from < migration.to);
+        }
+        assert_eq!(chain.last().map(|m| m.to), Some(current));
+    }
+
+    #[test]
+    fn test_current_version_needs_no_migration() -> Result<()> {
+        let project = ProjectFile::new(Document::new("Current".to_string(), 4, 4));
+        let content = ron::to_string(&project)?;
+
+        let (migrated, applied) = migrate_project(&content)?;
+        assert!(applied.is_empty());
+        assert!(matches!(migrated, Cow::Borrowed(_)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let error = check_project_version("99.0").unwrap_err();
+        assert!(error.to_string().contains("newer version"));
+    }
+
+    #[test]
+    fn test_unknown_old_version_is_rejected() {
+        assert!(check_project_version("0.5").is_err());
+    }
+}
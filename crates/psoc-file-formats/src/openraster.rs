@@ -0,0 +1,689 @@
+//! OpenRaster (.ora) format support
+//!
+//! This module provides loading and saving of OpenRaster files, the layered
+//! interchange format used by Krita, GIMP and MyPaint. An OpenRaster file is a
+//! zip archive holding a `mimetype` entry, a `stack.xml` layer description,
+//! one PNG per layer, a flattened `mergedimage.png` and a thumbnail.
+
+use crate::limits::{run_with_time_limit, DecodeBudget, DecodeLimits};
+use anyhow::{Context, Result};
+use image::ImageDecoder;
+use psoc_core::{BlendMode, Document, Layer, PixelData, Point, RenderEngine, Resolution};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Cursor, Read, Seek, Write};
+use std::path::Path;
+use tracing::{debug, info, instrument, warn};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// OpenRaster file extension
+pub const OPENRASTER_FILE_EXTENSION: &str = "ora";
+
+/// OpenRaster MIME type, stored uncompressed as the first archive entry
+pub const OPENRASTER_MIME_TYPE: &str = "image/openraster";
+
+/// OpenRaster specification version written to `stack.xml`
+const OPENRASTER_SPEC_VERSION: &str = "0.0.5";
+
+/// Maximum edge length of the embedded thumbnail
+const THUMBNAIL_MAX_SIZE: u32 = 256;
+
+/// OpenRaster-specific configuration options
+#[derive(Debug, Clone)]
+pub struct OraOptions {
+    /// Whether to embed the flattened `mergedimage.png`
+    pub include_merged_image: bool,
+    /// Whether to embed `Thumbnails/thumbnail.png`
+    pub include_thumbnail: bool,
+}
+
+impl Default for OraOptions {
+    fn default() -> Self {
+        Self {
+            include_merged_image: true,
+            include_thumbnail: true,
+        }
+    }
+}
+
+/// Map a PSOC blend mode to an OpenRaster `composite-op` value
+pub fn blend_mode_to_composite_op(mode: BlendMode) -> &'static str {
+    match mode {
+        BlendMode::Normal => "svg:src-over",
+        BlendMode::Multiply => "svg:multiply",
+        BlendMode::Screen => "svg:screen",
+        BlendMode::Overlay => "svg:overlay",
+        BlendMode::SoftLight => "svg:soft-light",
+        BlendMode::HardLight => "svg:hard-light",
+        BlendMode::ColorDodge => "svg:color-dodge",
+        BlendMode::ColorBurn => "svg:color-burn",
+        BlendMode::Darken => "svg:darken",
+        BlendMode::Lighten => "svg:lighten",
+        BlendMode::Difference => "svg:difference",
+        BlendMode::Exclusion => "svg:exclusion",
+        BlendMode::Hue => "svg:hue",
+        BlendMode::Saturation => "svg:saturation",
+        BlendMode::Color => "svg:color",
+        BlendMode::Luminosity => "svg:luminosity",
+    }
+}
+
+/// Map an OpenRaster `composite-op` value to a PSOC blend mode
+///
+/// Unknown operators fall back to [`BlendMode::Normal`].
+pub fn composite_op_to_blend_mode(op: &str) -> BlendMode {
+    match op {
+        "svg:multiply" => BlendMode::Multiply,
+        "svg:screen" => BlendMode::Screen,
+        "svg:overlay" => BlendMode::Overlay,
+        "svg:soft-light" => BlendMode::SoftLight,
+        "svg:hard-light" => BlendMode::HardLight,
+        "svg:color-dodge" => BlendMode::ColorDodge,
+        "svg:color-burn" => BlendMode::ColorBurn,
+        "svg:darken" => BlendMode::Darken,
+        "svg:lighten" => BlendMode::Lighten,
+        "svg:difference" => BlendMode::Difference,
+        "svg:exclusion" => BlendMode::Exclusion,
+        "svg:hue" => BlendMode::Hue,
+        "svg:saturation" => BlendMode::Saturation,
+        "svg:color" => BlendMode::Color,
+        "svg:luminosity" => BlendMode::Luminosity,
+        "svg:src-over" => BlendMode::Normal,
+        other => {
+            warn!(
+                "Unsupported OpenRaster composite-op '{}', using Normal",
+                other
+            );
+            BlendMode::Normal
+        }
+    }
+}
+
+/// Load an OpenRaster file as a layered document
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_ora<P: AsRef<Path>>(path: P) -> Result<Document> {
+    load_ora_with_limits(path, &DecodeLimits::default())
+}
+
+/// Load an OpenRaster file, rejecting layers and entries over the given limits
+///
+/// All layers share one memory budget and time limit.
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_ora_with_limits<P: AsRef<Path>>(path: P, limits: &DecodeLimits) -> Result<Document> {
+    let path = path.as_ref();
+    debug!("Loading OpenRaster file from: {}", path.display());
+
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open OpenRaster file: {}", path.display()))?;
+    let title = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Untitled")
+        .to_string();
+
+    let limits = *limits;
+    let mut document = run_with_time_limit(limits.max_decode_time, move || {
+        read_ora_with_limits(BufReader::new(file), title, &limits)
+    })
+    .with_context(|| format!("Failed to load OpenRaster file: {}", path.display()))?;
+    document.file_path = Some(path.to_path_buf());
+
+    info!(
+        layers = document.layers.len(),
+        size = format!("{}x{}", document.size.width, document.size.height),
+        "Successfully loaded OpenRaster file"
+    );
+
+    Ok(document)
+}
+
+/// Read an OpenRaster archive from any seekable reader
+pub fn read_ora<R: Read + Seek>(reader: R, title: String) -> Result<Document> {
+    read_ora_with_limits(reader, title, &DecodeLimits::default())
+}
+
+/// Read an OpenRaster archive, rejecting layers and entries over the given limits
+///
+/// The time limit is only checked between layers; use [`load_ora_with_limits`]
+/// to abandon a decode that runs too long.
+pub fn read_ora_with_limits<R: Read + Seek>(
+    reader: R,
+    title: String,
+    limits: &DecodeLimits,
+) -> Result<Document> {
+    let mut archive = ZipArchive::new(reader).context("Invalid OpenRaster zip archive")?;
+    let mut budget = DecodeBudget::new(limits);
+
+    if let Ok(mimetype) = archive.by_name("mimetype") {
+        let mut content = String::new();
+        mimetype
+            .take(OPENRASTER_MIME_TYPE.len() as u64 + 1)
+            .read_to_string(&mut content)
+            .context("Failed to read OpenRaster mimetype")?;
+        if content.trim() != OPENRASTER_MIME_TYPE {
+            return Err(anyhow::anyhow!(
+                "Unexpected OpenRaster mimetype: {}",
+                content.trim()
+            ));
+        }
+    } else {
+        warn!("OpenRaster archive has no mimetype entry");
+    }
+
+    let stack_xml = read_entry_to_string(&mut archive, "stack.xml", &mut budget)?;
+    let xml = roxmltree::Document::parse(&stack_xml).context("Failed to parse stack.xml")?;
+
+    let image = xml.root_element();
+    if image.tag_name().name() != "image" {
+        return Err(anyhow::anyhow!("stack.xml root element is not <image>"));
+    }
+
+    let width: u32 = parse_attr(&image, "w")?
+        .ok_or_else(|| anyhow::anyhow!("stack.xml is missing image width"))?;
+    let height: u32 = parse_attr(&image, "h")?
+        .ok_or_else(|| anyhow::anyhow!("stack.xml is missing image height"))?;
+    budget.check_dimensions(width, height)?;
+    let x_ppi: f32 = parse_attr(&image, "xres")?.unwrap_or(Resolution::default().x_ppi);
+    let y_ppi: f32 = parse_attr(&image, "yres")?.unwrap_or(Resolution::default().y_ppi);
+
+    let root_stack = image
+        .children()
+        .find(|node| node.has_tag_name("stack"))
+        .ok_or_else(|| anyhow::anyhow!("stack.xml has no root <stack>"))?;
+
+    // stack.xml lists layers from top to bottom, documents store them bottom to top
+    let mut layers = Vec::new();
+    collect_stack_layers(
+        &mut archive,
+        &mut budget,
+        root_stack,
+        Point::origin(),
+        true,
+        1.0,
+        &mut layers,
+    )?;
+    layers.reverse();
+
+    let mut document = Document::new(title, width, height);
+    document.resolution = Resolution::new(x_ppi, y_ppi);
+    for layer in layers {
+        document.add_layer(layer);
+    }
+    if !document.layers.is_empty() {
+        document.set_active_layer(document.layers.len() - 1)?;
+    }
+    document.mark_clean();
+
+    Ok(document)
+}
+
+/// Recursively collect layers from a `<stack>` element in top-to-bottom order
+///
+/// PSOC has no layer groups yet, so nested stacks are flattened into the
+/// layer list with their offset, visibility and opacity applied to their
+/// children. A stack's own composite-op cannot be kept and is dropped.
+fn collect_stack_layers<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    budget: &mut DecodeBudget,
+    stack: roxmltree::Node<'_, '_>,
+    origin: Point,
+    parent_visible: bool,
+    parent_opacity: f32,
+    layers: &mut Vec<Layer>,
+) -> Result<()> {
+    for node in stack.children().filter(|node| node.is_element()) {
+        let x: f32 = parse_attr(&node, "x")?.unwrap_or(0.0);
+        let y: f32 = parse_attr(&node, "y")?.unwrap_or(0.0);
+        let visible = parent_visible && node.attribute("visibility") != Some("hidden");
+        let opacity = parent_opacity
+            * parse_attr::<f32>(&node, "opacity")?
+                .unwrap_or(1.0)
+                .clamp(0.0, 1.0);
+        let position = origin.translate(x, y);
+
+        match node.tag_name().name() {
+            "stack" => {
+                let name = node.attribute("name").unwrap_or_default();
+                debug!("Flattening nested OpenRaster stack '{}'", name);
+                if let Some(op) = node
+                    .attribute("composite-op")
+                    .filter(|op| *op != "svg:src-over")
+                {
+                    warn!(
+                        "OpenRaster stack '{}' uses composite-op '{}', which cannot be kept when flattening groups; its layers keep their own blend modes",
+                        name, op
+                    );
+                }
+                collect_stack_layers(archive, budget, node, position, visible, opacity, layers)?;
+            }
+            "layer" => {
+                let src = node
+                    .attribute("src")
+                    .ok_or_else(|| anyhow::anyhow!("OpenRaster layer is missing src"))?;
+                if !src.to_ascii_lowercase().ends_with(".png") {
+                    warn!("Skipping non-PNG OpenRaster layer source: {}", src);
+                    continue;
+                }
+
+                budget.check_time()?;
+                let data = read_entry_to_bytes(archive, src, budget)?;
+                let image = decode_layer_png(&data, src, budget)?;
+                // The layer keeps its own RGBA copy of the decoded pixels
+                budget.reserve_pixels(image.width(), image.height(), 4)?;
+
+                let name = node
+                    .attribute("name")
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("Layer {}", layers.len() + 1));
+                let mut layer = Layer::new_pixel(name, image.width(), image.height());
+                layer.pixel_data = Some(PixelData::from_image(&image)?);
+                layer.set_position(position);
+                layer.visible = visible;
+                layer.opacity = opacity;
+                layer.blend_mode = node
+                    .attribute("composite-op")
+                    .map(composite_op_to_blend_mode)
+                    .unwrap_or_default();
+                layer.locked = node.attribute("edit-locked") == Some("true");
+
+                layers.push(layer);
+            }
+            other => {
+                debug!("Ignoring unsupported OpenRaster element <{}>", other);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Save a document as an OpenRaster file with default options
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn save_ora<P: AsRef<Path>>(document: &Document, path: P) -> Result<()> {
+    save_ora_with_options(document, path, &OraOptions::default())
+}
+
+/// Save a document as an OpenRaster file with specific options
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn save_ora_with_options<P: AsRef<Path>>(
+    document: &Document,
+    path: P,
+    options: &OraOptions,
+) -> Result<()> {
+    let path = path.as_ref();
+    debug!("Saving OpenRaster file to: {}", path.display());
+
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+    }
+
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create OpenRaster file: {}", path.display()))?;
+    write_ora(document, BufWriter::new(file), options)
+        .with_context(|| format!("Failed to write OpenRaster file: {}", path.display()))?;
+
+    info!(
+        layers = document.layers.len(),
+        size = format!("{}x{}", document.size.width, document.size.height),
+        "Successfully saved OpenRaster file"
+    );
+
+    Ok(())
+}
+
+/// Write a document as an OpenRaster archive to any seekable writer
+pub fn write_ora<W: Write + Seek>(
+    document: &Document,
+    writer: W,
+    options: &OraOptions,
+) -> Result<()> {
+    let mut zip = ZipWriter::new(writer);
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    // Layer PNGs are already deflate-compressed, so store them as-is
+    let png_entry = stored;
+    let xml_entry = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // The mimetype must be the first entry and must not be compressed
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(OPENRASTER_MIME_TYPE.as_bytes())?;
+
+    let (width, height) = document.dimensions();
+    let mut stack_entries = Vec::new();
+
+    for (index, layer) in document.layers.iter().enumerate() {
+        let Some(pixel_data) = &layer.pixel_data else {
+            warn!(
+                "Skipping layer '{}' without pixel data in OpenRaster export",
+                layer.name
+            );
+            continue;
+        };
+
+        let image = masked_layer_image(layer, pixel_data)?;
+        let src = format!("data/layer{}.png", index);
+        zip.start_file(src.as_str(), png_entry)?;
+        zip.write_all(&encode_png(&image)?)?;
+
+        stack_entries.push(format!(
+            "    <layer name=\"{}\" src=\"{}\" x=\"{}\" y=\"{}\" opacity=\"{:.3}\" visibility=\"{}\" composite-op=\"{}\"{}/>",
+            escape_xml(&layer.name),
+            src,
+            layer.offset.x.round() as i32,
+            layer.offset.y.round() as i32,
+            layer.opacity.clamp(0.0, 1.0),
+            if layer.visible { "visible" } else { "hidden" },
+            blend_mode_to_composite_op(layer.blend_mode),
+            if layer.locked { " edit-locked=\"true\"" } else { "" },
+        ));
+    }
+
+    // stack.xml lists layers from top to bottom
+    stack_entries.reverse();
+    let stack_xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<image version=\"{}\" w=\"{}\" h=\"{}\" xres=\"{}\" yres=\"{}\">\n  <stack>\n{}\n  </stack>\n</image>\n",
+        OPENRASTER_SPEC_VERSION,
+        width,
+        height,
+        document.resolution.x_ppi.round() as u32,
+        document.resolution.y_ppi.round() as u32,
+        stack_entries.join("\n"),
+    );
+    zip.start_file("stack.xml", xml_entry)?;
+    zip.write_all(stack_xml.as_bytes())?;
+
+    if options.include_merged_image || options.include_thumbnail {
+        let mut render_engine = RenderEngine::new();
+        let merged = render_engine.render_document(document)?.to_image()?;
+
+        if options.include_merged_image {
+            zip.start_file("mergedimage.png", png_entry)?;
+            zip.write_all(&encode_png(&merged)?)?;
+        }
+
+        if options.include_thumbnail {
+            let thumbnail = merged.thumbnail(THUMBNAIL_MAX_SIZE, THUMBNAIL_MAX_SIZE);
+            zip.start_file("Thumbnails/thumbnail.png", png_entry)?;
+            zip.write_all(&encode_png(&thumbnail)?)?;
+        }
+    }
+
+    zip.finish()
+        .context("Failed to finalize OpenRaster archive")?;
+    Ok(())
+}
+
+/// Check if a file path has the OpenRaster extension
+pub fn is_ora_file<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case(OPENRASTER_FILE_EXTENSION))
+        .unwrap_or(false)
+}
+
+/// Convert layer pixels to an image, baking the layer mask into alpha
+///
+/// OpenRaster has no layer masks, so the mask is applied on export.
+fn masked_layer_image(layer: &Layer, pixel_data: &PixelData) -> Result<image::DynamicImage> {
+    if !layer.has_mask() {
+        return pixel_data.to_image();
+    }
+
+    let (width, height) = pixel_data.dimensions();
+    let mut masked = pixel_data.clone();
+    for y in 0..height {
+        for x in 0..width {
+            if let Some(pixel) = layer.get_masked_pixel(x, y) {
+                masked.set_pixel(x, y, pixel)?;
+            }
+        }
+    }
+    masked.to_image()
+}
+
+/// Encode an image as PNG bytes
+fn encode_png(image: &image::DynamicImage) -> Result<Vec<u8>> {
+    let mut buffer = Cursor::new(Vec::new());
+    image
+        .write_to(&mut buffer, image::ImageFormat::Png)
+        .context("Failed to encode PNG")?;
+    Ok(buffer.into_inner())
+}
+
+/// Decode a layer PNG, checking its declared size before decoding the pixels
+fn decode_layer_png(
+    data: &[u8],
+    src: &str,
+    budget: &mut DecodeBudget,
+) -> Result<image::DynamicImage> {
+    let mut decoder = image::codecs::png::PngDecoder::new(Cursor::new(data))
+        .with_context(|| format!("Failed to read OpenRaster layer: {}", src))?;
+    let (width, height) = decoder.dimensions();
+    budget.check_dimensions(width, height)?;
+    budget.reserve(decoder.total_bytes())?;
+    decoder
+        .set_limits(budget.image_limits())
+        .with_context(|| format!("OpenRaster layer exceeds the decode limits: {}", src))?;
+    image::DynamicImage::from_decoder(decoder)
+        .with_context(|| format!("Failed to decode OpenRaster layer: {}", src))
+}
+
+/// Read a named archive entry as bytes
+///
+/// The declared entry size is not trusted; the entry is streamed and counted
+/// against the budget as it is decompressed.
+fn read_entry_to_bytes<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+    budget: &mut DecodeBudget,
+) -> Result<Vec<u8>> {
+    let entry = archive
+        .by_name(name)
+        .with_context(|| format!("OpenRaster archive is missing entry: {}", name))?;
+    budget.read_to_end(entry, &format!("OpenRaster entry: {}", name))
+}
+
+/// Read a named archive entry as UTF-8 text
+fn read_entry_to_string<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+    budget: &mut DecodeBudget,
+) -> Result<String> {
+    let data = read_entry_to_bytes(archive, name, budget)?;
+    String::from_utf8(data).with_context(|| format!("OpenRaster entry is not UTF-8: {}", name))
+}
+
+/// Parse an optional numeric attribute
+fn parse_attr<T: std::str::FromStr>(
+    node: &roxmltree::Node<'_, '_>,
+    name: &str,
+) -> Result<Option<T>> {
+    match node.attribute(name) {
+        Some(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| anyhow::anyhow!("Invalid value for attribute '{}': {}", name, value)),
+        None => Ok(None),
+    }
+}
+
+/// Escape a string for use in an XML attribute
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use psoc_core::RgbaPixel;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_blend_mode_composite_op_roundtrip() {
+        for mode in BlendMode::all() {
+            let op = blend_mode_to_composite_op(mode);
+            assert_eq!(composite_op_to_blend_mode(op), mode);
+        }
+        assert_eq!(composite_op_to_blend_mode("svg:plus"), BlendMode::Normal);
+    }
+
+    #[test]
+    fn test_is_ora_file() {
+        assert!(is_ora_file("test.ora"));
+        assert!(is_ora_file("test.ORA"));
+        assert!(!is_ora_file("test.png"));
+        assert!(!is_ora_file("test"));
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            escape_xml("a & <b> \"c\""),
+            "a &amp; &lt;b&gt; &quot;c&quot;"
+        );
+    }
+
+    #[test]
+    fn test_save_and_load_ora() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test.ora");
+
+        let mut document = Document::new("Layers".to_string(), 64, 48);
+        document.resolution = Resolution::print();
+
+        let mut background = Layer::new_pixel("Background".to_string(), 64, 48);
+        background.fill(RgbaPixel::new(255, 0, 0, 255));
+
+        let mut overlay = Layer::new_pixel("Overlay & <Top>".to_string(), 16, 8);
+        overlay.fill(RgbaPixel::new(0, 0, 255, 128));
+        overlay.set_position(Point::new(10.0, 20.0));
+        overlay.opacity = 0.5;
+        overlay.visible = false;
+        overlay.blend_mode = BlendMode::Multiply;
+
+        document.add_layer(background);
+        document.add_layer(overlay);
+
+        save_ora(&document, &file_path)?;
+        let loaded = load_ora(&file_path)?;
+
+        assert_eq!(loaded.dimensions(), (64, 48));
+        assert_eq!(loaded.resolution, Resolution::print());
+        assert_eq!(loaded.layers.len(), 2);
+        assert!(!loaded.is_dirty);
+
+        let background = &loaded.layers[0];
+        assert_eq!(background.name, "Background");
+        assert_eq!(background.dimensions(), Some((64, 48)));
+        assert_eq!(
+            background.get_pixel(5, 5),
+            Some(RgbaPixel::new(255, 0, 0, 255))
+        );
+
+        let overlay = &loaded.layers[1];
+        assert_eq!(overlay.name, "Overlay & <Top>");
+        assert_eq!(overlay.dimensions(), Some((16, 8)));
+        assert_eq!(overlay.offset, Point::new(10.0, 20.0));
+        assert_eq!(overlay.opacity, 0.5);
+        assert!(!overlay.visible);
+        assert_eq!(overlay.blend_mode, BlendMode::Multiply);
+        assert_eq!(
+            overlay.get_pixel(0, 0),
+            Some(RgbaPixel::new(0, 0, 255, 128))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ora_archive_layout() -> Result<()> {
+        let mut document = Document::new("Layout".to_string(), 8, 8);
+        document.add_layer(Layer::new_pixel("Layer".to_string(), 8, 8));
+
+        let mut buffer = Cursor::new(Vec::new());
+        write_ora(&document, &mut buffer, &OraOptions::default())?;
+
+        let mut archive = ZipArchive::new(Cursor::new(buffer.into_inner()))?;
+        assert_eq!(archive.by_index(0)?.name(), "mimetype");
+        assert_eq!(
+            archive.by_index(0)?.compression(),
+            CompressionMethod::Stored
+        );
+        assert!(archive.by_name("stack.xml").is_ok());
+        assert!(archive.by_name("data/layer0.png").is_ok());
+        assert!(archive.by_name("mergedimage.png").is_ok());
+        assert!(archive.by_name("Thumbnails/thumbnail.png").is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_ora_with_nested_stack() -> Result<()> {
+        let layer_png = encode_png(&image::DynamicImage::new_rgba8(4, 4))?;
+
+        let mut buffer = Cursor::new(Vec::new());
+        {
+            let mut zip = ZipWriter::new(&mut buffer);
+            let options = SimpleFileOptions::default();
+            zip.start_file("mimetype", options)?;
+            zip.write_all(OPENRASTER_MIME_TYPE.as_bytes())?;
+            zip.start_file("stack.xml", options)?;
+            zip.write_all(
+                br#"<image w="32" h="32"><stack>
+                    <layer name="Top" src="data/a.png" composite-op="svg:screen"/>
+                    <stack name="Group" x="5" y="6" visibility="hidden">
+                        <layer name="Inner" src="data/b.png" x="1" y="2"/>
+                    </stack>
+                </stack></image>"#,
+            )?;
+            zip.start_file("data/a.png", options)?;
+            zip.write_all(&layer_png)?;
+            zip.start_file("data/b.png", options)?;
+            zip.write_all(&layer_png)?;
+            zip.finish()?;
+        }
+
+        let document = read_ora(Cursor::new(buffer.into_inner()), "Nested".to_string())?;
+
+        assert_eq!(document.layers.len(), 2);
+        assert_eq!(document.layers[0].name, "Inner");
+        assert_eq!(document.layers[0].offset, Point::new(6.0, 8.0));
+        assert!(!document.layers[0].visible);
+        assert_eq!(document.layers[1].name, "Top");
+        assert_eq!(document.layers[1].blend_mode, BlendMode::Screen);
+        assert_eq!(document.active_layer_index, Some(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_ora_rejects_wrong_mimetype() -> Result<()> {
+        let mut buffer = Cursor::new(Vec::new());
+        {
+            let mut zip = ZipWriter::new(&mut buffer);
+            zip.start_file("mimetype", SimpleFileOptions::default())?;
+            zip.write_all(b"application/zip")?;
+            zip.finish()?;
+        }
+
+        let result = read_ora(Cursor::new(buffer.into_inner()), "Bad".to_string());
+        assert!(result.is_err());
+
+        Ok(())
+    }
+}
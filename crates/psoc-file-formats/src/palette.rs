@@ -0,0 +1,678 @@
+//! Color palette files
+//!
+//! This module reads and writes swatch palettes in the formats used by other
+//! editors:
+//! - GIMP palettes (`.gpl`)
+//! - Adobe Photoshop color swatches (`.aco`, versions 1 and 2)
+//! - Adobe swatch exchange (`.ase`)
+//! - Plain hex color lists (`.hex`, `.txt`), one `#RRGGBB` or `#RRGGBBAA`
+//!   per line
+//!
+//! Only the colors and the palette name are kept. Swatches in HSB, CMYK, Lab
+//! or grayscale are converted to sRGB on import; exports are always RGB.
+
+use anyhow::{bail, Context, Result};
+use psoc_core::{HsvColor, RgbaPixel};
+use std::path::Path;
+use tracing::{debug, info, instrument, warn};
+
+/// Supported palette file formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteFormat {
+    /// GIMP palette
+    Gpl,
+    /// Adobe Photoshop color swatches
+    Aco,
+    /// Adobe swatch exchange
+    Ase,
+    /// Plain list of hex colors
+    Hex,
+}
+
+impl PaletteFormat {
+    /// All palette formats
+    pub const ALL: [Self; 4] = [Self::Gpl, Self::Aco, Self::Ase, Self::Hex];
+
+    /// Detect the palette format from a file extension
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "gpl" => Some(Self::Gpl),
+            "aco" => Some(Self::Aco),
+            "ase" => Some(Self::Ase),
+            "hex" | "txt" => Some(Self::Hex),
+            _ => None,
+        }
+    }
+
+    /// File extension without the dot
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Gpl => "gpl",
+            Self::Aco => "aco",
+            Self::Ase => "ase",
+            Self::Hex => "hex",
+        }
+    }
+
+    /// Human readable format name
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Gpl => "GIMP Palette",
+            Self::Aco => "Adobe Color Swatches",
+            Self::Ase => "Adobe Swatch Exchange",
+            Self::Hex => "Hex Color List",
+        }
+    }
+}
+
+/// A named list of colors read from or written to a palette file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    /// Palette name
+    pub name: String,
+    /// Swatch colors in file order
+    pub colors: Vec<RgbaPixel>,
+}
+
+impl Palette {
+    /// Create a palette
+    pub fn new(name: impl Into<String>, colors: Vec<RgbaPixel>) -> Self {
+        Self {
+            name: name.into(),
+            colors,
+        }
+    }
+}
+
+/// Check if a path has a palette file extension
+pub fn is_palette_file<P: AsRef<Path>>(path: P) -> bool {
+    PaletteFormat::from_path(path).is_some()
+}
+
+/// Read a palette file, detecting the format from its extension
+///
+/// Formats without a stored name use the file stem as the palette name.
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn import_palette<P: AsRef<Path>>(path: P) -> Result<Palette> {
+    let path = path.as_ref();
+    let format = PaletteFormat::from_path(path)
+        .with_context(|| format!("Unsupported palette file: {}", path.display()))?;
+    let data = std::fs::read(path)
+        .with_context(|| format!("Failed to read palette file: {}", path.display()))?;
+    let fallback_name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Imported".to_string());
+
+    let palette = decode_palette(&data, format, &fallback_name)
+        .with_context(|| format!("Failed to parse palette file: {}", path.display()))?;
+    info!(
+        colors = palette.colors.len(),
+        format = ?format,
+        "Successfully imported palette '{}'",
+        palette.name
+    );
+    Ok(palette)
+}
+
+/// Write a palette file, detecting the format from its extension
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn export_palette<P: AsRef<Path>>(palette: &Palette, path: P) -> Result<()> {
+    let path = path.as_ref();
+    let format = PaletteFormat::from_path(path)
+        .with_context(|| format!("Unsupported palette file: {}", path.display()))?;
+    debug!(
+        "Exporting palette '{}' as {:?} to: {}",
+        palette.name,
+        format,
+        path.display()
+    );
+    std::fs::write(path, encode_palette(palette, format))
+        .with_context(|| format!("Failed to write palette file: {}", path.display()))?;
+    info!(
+        colors = palette.colors.len(),
+        "Successfully exported palette '{}'", palette.name
+    );
+    Ok(())
+}
+
+/// Parse palette file contents
+pub fn decode_palette(data: &[u8], format: PaletteFormat, fallback_name: &str) -> Result<Palette> {
+    let (name, colors) = match format {
+        PaletteFormat::Gpl => decode_gpl(data)?,
+        PaletteFormat::Aco => (None, decode_aco(data)?),
+        PaletteFormat::Ase => decode_ase(data)?,
+        PaletteFormat::Hex => (None, decode_hex(data)?),
+    };
+    if colors.is_empty() {
+        bail!("Palette contains no colors");
+    }
+    Ok(Palette::new(
+        name.filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| fallback_name.to_string()),
+        colors,
+    ))
+}
+
+/// Serialize a palette
+pub fn encode_palette(palette: &Palette, format: PaletteFormat) -> Vec<u8> {
+    match format {
+        PaletteFormat::Gpl => encode_gpl(palette),
+        PaletteFormat::Aco => encode_aco(palette),
+        PaletteFormat::Ase => encode_ase(palette),
+        PaletteFormat::Hex => encode_hex(palette),
+    }
+}
+
+/// `#rrggbb`, or `#rrggbbaa` for translucent colors
+fn hex_string(color: RgbaPixel) -> String {
+    if color.a == 255 {
+        format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+    } else {
+        format!(
+            "#{:02x}{:02x}{:02x}{:02x}",
+            color.r, color.g, color.b, color.a
+        )
+    }
+}
+
+fn decode_gpl(data: &[u8]) -> Result<(Option<String>, Vec<RgbaPixel>)> {
+    let text = String::from_utf8_lossy(data);
+    let mut lines = text.lines();
+    let header = lines.next().unwrap_or_default();
+    if header.trim_start_matches('\u{feff}').trim() != "GIMP Palette" {
+        bail!("Missing 'GIMP Palette' header");
+    }
+
+    let mut name = None;
+    let mut colors = Vec::new();
+    for (number, line) in lines.enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(value) = line.strip_prefix("Name:") {
+            name = Some(value.trim().to_string());
+            continue;
+        }
+        if line.starts_with("Columns:") {
+            continue;
+        }
+
+        let channels: Vec<u8> = line
+            .split_whitespace()
+            .take(3)
+            .map(|value| value.parse::<u8>())
+            .collect::<Result<_, _>>()
+            .with_context(|| format!("Invalid color on line {}: {}", number + 2, line))?;
+        if channels.len() < 3 {
+            bail!("Invalid color on line {}: {}", number + 2, line);
+        }
+        colors.push(RgbaPixel::new(channels[0], channels[1], channels[2], 255));
+    }
+
+    Ok((name, colors))
+}
+
+fn encode_gpl(palette: &Palette) -> Vec<u8> {
+    let mut text = String::from("GIMP Palette\n");
+    text.push_str(&format!("Name: {}\n", palette.name.replace('\n', " ")));
+    text.push_str(&format!("Columns: {}\n#\n", palette.colors.len().min(16)));
+    for color in &palette.colors {
+        text.push_str(&format!(
+            "{:3} {:3} {:3}\t{}\n",
+            color.r,
+            color.g,
+            color.b,
+            hex_string(*color)
+        ));
+    }
+    text.into_bytes()
+}
+
+fn decode_hex(data: &[u8]) -> Result<Vec<RgbaPixel>> {
+    let text = String::from_utf8_lossy(data);
+    let mut colors = Vec::new();
+    for line in text.lines() {
+        for token in line.split(|c: char| c.is_whitespace() || c == ',' || c == ';') {
+            if token.is_empty() {
+                continue;
+            }
+            // Anything that is not a color ends the line, e.g. a comment
+            match parse_hex_color(token) {
+                Some(color) => colors.push(color),
+                None => break,
+            }
+        }
+    }
+    Ok(colors)
+}
+
+/// Parse `#rgb`, `#rrggbb` or `#rrggbbaa`, with `#` or `0x` optional
+fn parse_hex_color(token: &str) -> Option<RgbaPixel> {
+    let digits = token
+        .strip_prefix('#')
+        .or_else(|| token.strip_prefix("0x"))
+        .unwrap_or(token);
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |index: usize| u8::from_str_radix(&digits[index * 2..index * 2 + 2], 16).ok();
+    match digits.len() {
+        3 => {
+            let value = u16::from_str_radix(digits, 16).ok()?;
+            let expand = |nibble: u16| (nibble as u8 & 0xf) * 17;
+            Some(RgbaPixel::new(
+                expand(value >> 8),
+                expand(value >> 4),
+                expand(value),
+                255,
+            ))
+        }
+        6 => Some(RgbaPixel::new(channel(0)?, channel(1)?, channel(2)?, 255)),
+        8 => Some(RgbaPixel::new(
+            channel(0)?,
+            channel(1)?,
+            channel(2)?,
+            channel(3)?,
+        )),
+        _ => None,
+    }
+}
+
+fn encode_hex(palette: &Palette) -> Vec<u8> {
+    palette
+        .colors
+        .iter()
+        .map(|color| hex_string(*color) + "\n")
+        .collect::<String>()
+        .into_bytes()
+}
+
+/// Big-endian cursor over Adobe swatch and preset file bytes
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.data.len() - self.position
+    }
+
+    pub(crate) fn bytes(&mut self, count: usize) -> Result<&'a [u8]> {
+        if count > self.remaining() {
+            bail!("Unexpected end of file at byte {}", self.position);
+        }
+        let bytes = &self.data[self.position..self.position + count];
+        self.position += count;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub(crate) fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64> {
+        Ok(((self.u32()? as u64) << 32) | self.u32()? as u64)
+    }
+
+    pub(crate) fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_bits(self.u64()?))
+    }
+
+    /// UTF-16BE string of `length` code units, dropping the NUL terminator
+    pub(crate) fn utf16(&mut self, length: usize) -> Result<String> {
+        let bytes = self.bytes(length * 2)?;
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .take_while(|&unit| unit != 0)
+            .collect();
+        Ok(String::from_utf16_lossy(&units))
+    }
+}
+
+/// UTF-16BE code units of a string followed by a NUL terminator
+fn utf16_with_nul(text: &str) -> Vec<u8> {
+    text.encode_utf16()
+        .chain(std::iter::once(0))
+        .flat_map(u16::to_be_bytes)
+        .collect()
+}
+
+pub(crate) fn to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Convert CIE L*a*b* (D50, as used by Adobe swatches) to sRGB
+pub(crate) fn lab_to_rgba(l: f32, a: f32, b: f32) -> RgbaPixel {
+    let fy = (l + 16.0) / 116.0;
+    let fx = fy + a / 500.0;
+    let fz = fy - b / 200.0;
+    let inverse = |t: f32| {
+        if t > 6.0 / 29.0 {
+            t * t * t
+        } else {
+            3.0 * (6.0f32 / 29.0).powi(2) * (t - 4.0 / 29.0)
+        }
+    };
+    let (x, y, z) = (inverse(fx) * 0.96422, inverse(fy), inverse(fz) * 0.82521);
+
+    // XYZ (D50) to linear sRGB, Bradford adapted
+    let linear = [
+        3.133_856 * x - 1.6168667 * y - 0.4906146 * z,
+        -0.9787684 * x + 1.9161415 * y + 0.0334540 * z,
+        0.0719453 * x - 0.2289914 * y + 1.4052427 * z,
+    ];
+    let [r, g, b] = linear.map(|c| {
+        let c = c.clamp(0.0, 1.0);
+        if c <= 0.0031308 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    });
+    RgbaPixel::new(to_u8(r), to_u8(g), to_u8(b), 255)
+}
+
+/// Color of one `.aco` record, or `None` for unsupported color spaces
+fn aco_color(space: u16, w: u16, x: u16, y: u16, z: u16) -> Option<RgbaPixel> {
+    let unit = |value: u16| value as f32 / 65535.0;
+    match space {
+        // RGB
+        0 => Some(RgbaPixel::new(
+            (w >> 8) as u8,
+            (x >> 8) as u8,
+            (y >> 8) as u8,
+            255,
+        )),
+        // HSB
+        1 => Some(HsvColor::hsv(unit(w) * 360.0, unit(x), unit(y)).to_rgba()),
+        // CMYK, stored inverted (0 is full ink)
+        2 => {
+            let k = unit(z);
+            Some(RgbaPixel::new(
+                to_u8(unit(w) * k),
+                to_u8(unit(x) * k),
+                to_u8(unit(y) * k),
+                255,
+            ))
+        }
+        // Lab: L in 0..10000, a and b signed in -12800..12700
+        7 => Some(lab_to_rgba(
+            w as f32 / 100.0,
+            x as i16 as f32 / 100.0,
+            y as i16 as f32 / 100.0,
+        )),
+        // Grayscale, 0..10000 ink coverage
+        8 => {
+            let gray = to_u8(1.0 - w.min(10000) as f32 / 10000.0);
+            Some(RgbaPixel::new(gray, gray, gray, 255))
+        }
+        _ => None,
+    }
+}
+
+fn decode_aco(data: &[u8]) -> Result<Vec<RgbaPixel>> {
+    let mut reader = Reader::new(data);
+    let mut version = reader.u16()?;
+    if version != 1 && version != 2 {
+        bail!("Unsupported ACO version {}", version);
+    }
+
+    loop {
+        let count = reader.u16()? as usize;
+        // A version 1 record is 10 bytes; version 2 adds at least a name length
+        if count * 10 > reader.remaining() {
+            bail!("ACO color count {} exceeds file size", count);
+        }
+
+        let mut colors = Vec::with_capacity(count);
+        for _ in 0..count {
+            let space = reader.u16()?;
+            let (w, x, y, z) = (reader.u16()?, reader.u16()?, reader.u16()?, reader.u16()?);
+            if version == 2 {
+                let length = reader.u32()? as usize;
+                reader.utf16(length)?;
+            }
+            match aco_color(space, w, x, y, z) {
+                Some(color) => colors.push(color),
+                None => warn!("Skipping ACO swatch in unsupported color space {}", space),
+            }
+        }
+
+        // Version 1 data may be followed by the same colors in version 2
+        if version == 1 && reader.remaining() >= 4 {
+            let mut peek = Reader::new(&data[reader.position..]);
+            if peek.u16()? == 2 {
+                reader.u16()?;
+                version = 2;
+                continue;
+            }
+        }
+        return Ok(colors);
+    }
+}
+
+fn encode_aco(palette: &Palette) -> Vec<u8> {
+    let count = palette.colors.len().min(u16::MAX as usize);
+    let colors = &palette.colors[..count];
+    let mut out = Vec::new();
+
+    for version in [1u16, 2] {
+        out.extend_from_slice(&version.to_be_bytes());
+        out.extend_from_slice(&(count as u16).to_be_bytes());
+        for color in colors {
+            out.extend_from_slice(&0u16.to_be_bytes());
+            for channel in [color.r, color.g, color.b] {
+                out.extend_from_slice(&(channel as u16 * 257).to_be_bytes());
+            }
+            out.extend_from_slice(&0u16.to_be_bytes());
+            if version == 2 {
+                let name = hex_string(*color);
+                out.extend_from_slice(&(name.encode_utf16().count() as u32 + 1).to_be_bytes());
+                out.extend_from_slice(&utf16_with_nul(&name));
+            }
+        }
+    }
+
+    out
+}
+
+const ASE_SIGNATURE: &[u8; 4] = b"ASEF";
+const ASE_GROUP_START: u16 = 0xC001;
+const ASE_GROUP_END: u16 = 0xC002;
+const ASE_COLOR: u16 = 0x0001;
+
+fn decode_ase(data: &[u8]) -> Result<(Option<String>, Vec<RgbaPixel>)> {
+    let mut reader = Reader::new(data);
+    if reader.bytes(4)? != ASE_SIGNATURE {
+        bail!("Missing ASEF signature");
+    }
+    let major = reader.u16()?;
+    reader.u16()?;
+    if major != 1 {
+        bail!("Unsupported ASE version {}", major);
+    }
+    let block_count = reader.u32()?;
+
+    let mut name = None;
+    let mut colors = Vec::new();
+    for _ in 0..block_count {
+        if reader.remaining() == 0 {
+            break;
+        }
+        let block_type = reader.u16()?;
+        let length = reader.u32()? as usize;
+        let mut block = Reader::new(reader.bytes(length)?);
+
+        match block_type {
+            ASE_GROUP_START => {
+                let length = block.u16()? as usize;
+                let group = block.utf16(length)?;
+                name.get_or_insert(group);
+            }
+            ASE_COLOR => {
+                let length = block.u16()? as usize;
+                block.utf16(length)?;
+                let model = block.bytes(4)?;
+                let color = match model {
+                    b"RGB " => {
+                        let (r, g, b) = (block.f32()?, block.f32()?, block.f32()?);
+                        RgbaPixel::new(to_u8(r), to_u8(g), to_u8(b), 255)
+                    }
+                    b"CMYK" => {
+                        let (c, m, y, k) = (block.f32()?, block.f32()?, block.f32()?, block.f32()?);
+                        let ink = |value: f32| to_u8((1.0 - value) * (1.0 - k));
+                        RgbaPixel::new(ink(c), ink(m), ink(y), 255)
+                    }
+                    b"LAB " => {
+                        let (l, a, b) = (block.f32()?, block.f32()?, block.f32()?);
+                        lab_to_rgba(l * 100.0, a, b)
+                    }
+                    b"Gray" => {
+                        let gray = to_u8(block.f32()?);
+                        RgbaPixel::new(gray, gray, gray, 255)
+                    }
+                    other => {
+                        warn!(
+                            "Skipping ASE swatch in unsupported color model {:?}",
+                            String::from_utf8_lossy(other)
+                        );
+                        continue;
+                    }
+                };
+                colors.push(color);
+            }
+            ASE_GROUP_END => {}
+            other => debug!("Skipping unknown ASE block type {:#06x}", other),
+        }
+    }
+
+    Ok((name, colors))
+}
+
+fn encode_ase(palette: &Palette) -> Vec<u8> {
+    fn block(out: &mut Vec<u8>, block_type: u16, body: &[u8]) {
+        out.extend_from_slice(&block_type.to_be_bytes());
+        out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        out.extend_from_slice(body);
+    }
+    fn named(name: &str) -> Vec<u8> {
+        let mut body = (name.encode_utf16().count() as u16 + 1)
+            .to_be_bytes()
+            .to_vec();
+        body.extend_from_slice(&utf16_with_nul(name));
+        body
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(ASE_SIGNATURE);
+    out.extend_from_slice(&1u16.to_be_bytes());
+    out.extend_from_slice(&0u16.to_be_bytes());
+    out.extend_from_slice(&(palette.colors.len() as u32 + 2).to_be_bytes());
+
+    block(&mut out, ASE_GROUP_START, &named(&palette.name));
+    for color in &palette.colors {
+        let mut body = named(&hex_string(*color));
+        body.extend_from_slice(b"RGB ");
+        for channel in [color.r, color.g, color.b] {
+            body.extend_from_slice(&(channel as f32 / 255.0).to_be_bytes());
+        }
+        // Normal (non-global, non-spot) color
+        body.extend_from_slice(&2u16.to_be_bytes());
+        block(&mut out, ASE_COLOR, &body);
+    }
+    block(&mut out, ASE_GROUP_END, &[]);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brand() -> Palette {
+        Palette::new(
+            "Brand",
+            vec![
+                RgbaPixel::new(255, 0, 0, 255),
+                RgbaPixel::new(18, 52, 86, 255),
+                RgbaPixel::new(255, 255, 255, 255),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_round_trip_every_format() {
+        let palette = brand();
+        for format in PaletteFormat::ALL {
+            let encoded = encode_palette(&palette, format);
+            let decoded = decode_palette(&encoded, format, "Brand").unwrap();
+            assert_eq!(decoded, palette, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_hex_list_parsing() {
+        let text = "; brand colors\n#FF0000 red\n0x00ff00, 00f\n#11223380\nnot a color\n";
+        let colors = decode_hex(text.as_bytes()).unwrap();
+        assert_eq!(
+            colors,
+            [
+                RgbaPixel::new(255, 0, 0, 255),
+                RgbaPixel::new(0, 255, 0, 255),
+                RgbaPixel::new(0, 0, 255, 255),
+                RgbaPixel::new(0x11, 0x22, 0x33, 0x80),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aco_color_spaces() {
+        assert_eq!(
+            aco_color(1, 0, 65535, 65535, 0),
+            Some(RgbaPixel::new(255, 0, 0, 255))
+        );
+        assert_eq!(
+            aco_color(2, 65535, 0, 65535, 65535),
+            Some(RgbaPixel::new(255, 0, 255, 255))
+        );
+        assert_eq!(
+            aco_color(8, 10000, 0, 0, 0),
+            Some(RgbaPixel::new(0, 0, 0, 255))
+        );
+        let white = aco_color(7, 10000, 0, 0, 0).unwrap();
+        assert!(white.r >= 254 && white.g >= 254 && white.b >= 254);
+        assert_eq!(aco_color(9, 0, 0, 0, 0), None);
+    }
+
+    #[test]
+    fn test_truncated_files_are_errors() {
+        let aco = encode_palette(&brand(), PaletteFormat::Aco);
+        assert!(decode_palette(&aco[..7], PaletteFormat::Aco, "x").is_err());
+        let ase = encode_palette(&brand(), PaletteFormat::Ase);
+        assert!(decode_palette(&ase[..20], PaletteFormat::Ase, "x").is_err());
+        assert!(decode_palette(b"JASC-PAL\n", PaletteFormat::Gpl, "x").is_err());
+    }
+}
@@ -0,0 +1,444 @@
+//! PDF proof export
+//!
+//! This module writes the flattened document into a single-page PDF sized
+//! from the document resolution, with optional bleed, crop marks and an ICC
+//! output intent taken from the document's color profile.
+
+use anyhow::{Context, Result};
+use flate2::{write::ZlibEncoder, Compression};
+use psoc_core::icc::ColorSpace;
+use psoc_core::{Document, IccProfile, PixelData, RenderEngine, RgbaPixel};
+use std::io::Write;
+use std::path::Path;
+use tracing::{debug, info, instrument};
+
+/// PDF points per inch
+const POINTS_PER_INCH: f32 = 72.0;
+/// Millimetres per inch
+const MM_PER_INCH: f32 = 25.4;
+/// Length of each crop mark in points
+const CROP_MARK_LENGTH: f32 = 12.0;
+/// Gap between the bleed edge and the start of a crop mark in points
+const CROP_MARK_OFFSET: f32 = 3.0;
+/// Stroke width of crop marks in points
+const CROP_MARK_WIDTH: f32 = 0.25;
+
+/// Options for PDF export
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfExportOptions {
+    /// Bleed added around the trim area in millimetres
+    ///
+    /// The edge pixels of the image are extended into the bleed.
+    pub bleed_mm: f32,
+    /// Draw crop marks at the trim corners, outside the bleed
+    pub crop_marks: bool,
+    /// Embed the document ICC profile as the PDF output intent
+    pub output_intent: bool,
+}
+
+impl Default for PdfExportOptions {
+    fn default() -> Self {
+        Self {
+            bleed_mm: 0.0,
+            crop_marks: false,
+            output_intent: true,
+        }
+    }
+}
+
+impl PdfExportOptions {
+    /// Set the bleed in millimetres
+    pub fn with_bleed_mm(mut self, bleed_mm: f32) -> Self {
+        self.bleed_mm = bleed_mm.max(0.0);
+        self
+    }
+
+    /// Enable or disable crop marks
+    pub fn with_crop_marks(mut self, crop_marks: bool) -> Self {
+        self.crop_marks = crop_marks;
+        self
+    }
+
+    /// Enable or disable the ICC output intent
+    pub fn with_output_intent(mut self, output_intent: bool) -> Self {
+        self.output_intent = output_intent;
+        self
+    }
+}
+
+/// Check if a path has a PDF file extension
+pub fn is_pdf_file<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+}
+
+/// Export a flattened document as a PDF proof
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn export_pdf<P: AsRef<Path>>(
+    document: &Document,
+    path: P,
+    options: &PdfExportOptions,
+) -> Result<()> {
+    let path = path.as_ref();
+    debug!("Exporting PDF to: {}", path.display());
+
+    let data = encode_pdf(document, options)?;
+    std::fs::write(path, data)
+        .with_context(|| format!("Failed to write PDF file: {}", path.display()))?;
+
+    info!(
+        size = format!("{}x{}", document.size.width, document.size.height),
+        bleed_mm = options.bleed_mm,
+        crop_marks = options.crop_marks,
+        "Successfully exported PDF"
+    );
+
+    Ok(())
+}
+
+/// Encode a flattened document as PDF bytes
+pub fn encode_pdf(document: &Document, options: &PdfExportOptions) -> Result<Vec<u8>> {
+    let mut render_engine = RenderEngine::new();
+    let pixels = render_engine.render_document(document)?;
+
+    let resolution = document.resolution;
+    if resolution.x_ppi <= 0.0 || resolution.y_ppi <= 0.0 {
+        return Err(anyhow::anyhow!(
+            "Invalid document resolution: {}x{} PPI",
+            resolution.x_ppi,
+            resolution.y_ppi
+        ));
+    }
+
+    // Physical sizes in points
+    let (width_px, height_px) = pixels.dimensions();
+    let trim_width = width_px as f32 / resolution.x_ppi * POINTS_PER_INCH;
+    let trim_height = height_px as f32 / resolution.y_ppi * POINTS_PER_INCH;
+    let bleed = options.bleed_mm.max(0.0) / MM_PER_INCH * POINTS_PER_INCH;
+    let slug = if options.crop_marks {
+        CROP_MARK_OFFSET + CROP_MARK_LENGTH
+    } else {
+        0.0
+    };
+    let margin = bleed + slug;
+    let page_width = trim_width + 2.0 * margin;
+    let page_height = trim_height + 2.0 * margin;
+
+    // Extend the image into the bleed at the document resolution
+    let bleed_x = (options.bleed_mm.max(0.0) / MM_PER_INCH * resolution.x_ppi).round() as u32;
+    let bleed_y = (options.bleed_mm.max(0.0) / MM_PER_INCH * resolution.y_ppi).round() as u32;
+    let (image_width, image_height, rgb) = bleed_rgb(&pixels, bleed_x, bleed_y);
+    let image_rect = [
+        margin - bleed_x as f32 / resolution.x_ppi * POINTS_PER_INCH,
+        margin - bleed_y as f32 / resolution.y_ppi * POINTS_PER_INCH,
+        image_width as f32 / resolution.x_ppi * POINTS_PER_INCH,
+        image_height as f32 / resolution.y_ppi * POINTS_PER_INCH,
+    ];
+
+    let profile = match (&document.icc_profile, options.output_intent) {
+        (Some(profile), true) => Some(OutputProfile::from_profile(profile)?),
+        _ => None,
+    };
+
+    let mut writer = PdfWriter::new();
+    let catalog_id = writer.reserve();
+    let pages_id = writer.reserve();
+    let page_id = writer.reserve();
+    let image_id = writer.reserve();
+    let content_id = writer.reserve();
+
+    // Output intent and, for RGB profiles, an ICC-based image color space
+    let mut image_color_space = "/DeviceRGB".to_string();
+    let mut output_intent = String::new();
+    if let Some(profile) = &profile {
+        let profile_id = writer.reserve();
+        writer.stream(
+            profile_id,
+            &format!("/N {}", profile.components),
+            &profile.data,
+        )?;
+        if profile.components == 3 {
+            image_color_space = format!("[/ICCBased {} 0 R]", profile_id);
+        }
+        let condition = pdf_string(&profile.description);
+        output_intent = format!(
+            " /OutputIntents [<< /Type /OutputIntent /S /GTS_PDFX \
+             /OutputConditionIdentifier {condition} /Info {condition} \
+             /DestOutputProfile {profile_id} 0 R >>]"
+        );
+    }
+
+    writer.object(
+        catalog_id,
+        &format!("<< /Type /Catalog /Pages {pages_id} 0 R{output_intent} >>"),
+    );
+    writer.object(
+        pages_id,
+        &format!("<< /Type /Pages /Kids [{page_id} 0 R] /Count 1 >>"),
+    );
+    writer.object(
+        page_id,
+        &format!(
+            "<< /Type /Page /Parent {pages_id} 0 R \
+             /MediaBox [0 0 {} {}] /BleedBox [{} {} {} {}] /TrimBox [{} {} {} {}] \
+             /Resources << /XObject << /Im0 {image_id} 0 R >> >> /Contents {content_id} 0 R >>",
+            num(page_width),
+            num(page_height),
+            num(margin - bleed),
+            num(margin - bleed),
+            num(margin + trim_width + bleed),
+            num(margin + trim_height + bleed),
+            num(margin),
+            num(margin),
+            num(margin + trim_width),
+            num(margin + trim_height),
+        ),
+    );
+    writer.stream(
+        image_id,
+        &format!(
+            "/Type /XObject /Subtype /Image /Width {image_width} /Height {image_height} \
+             /ColorSpace {image_color_space} /BitsPerComponent 8"
+        ),
+        &rgb,
+    )?;
+
+    let mut content = format!(
+        "q {} 0 0 {} {} {} cm /Im0 Do Q\n",
+        num(image_rect[2]),
+        num(image_rect[3]),
+        num(image_rect[0]),
+        num(image_rect[1])
+    );
+    if options.crop_marks {
+        content.push_str(&crop_marks(margin, trim_width, trim_height, bleed));
+    }
+    writer.stream(content_id, "", content.as_bytes())?;
+
+    Ok(writer.finish(catalog_id))
+}
+
+/// Flatten onto white and extend the edge pixels outwards by the bleed
+fn bleed_rgb(pixels: &PixelData, bleed_x: u32, bleed_y: u32) -> (u32, u32, Vec<u8>) {
+    let (width, height) = pixels.dimensions();
+    let out_width = width + 2 * bleed_x;
+    let out_height = height + 2 * bleed_y;
+    let mut rgb = Vec::with_capacity(out_width as usize * out_height as usize * 3);
+
+    for y in 0..out_height {
+        let source_y = y.saturating_sub(bleed_y).min(height.saturating_sub(1));
+        for x in 0..out_width {
+            let source_x = x.saturating_sub(bleed_x).min(width.saturating_sub(1));
+            let pixel = pixels
+                .get_pixel(source_x, source_y)
+                .unwrap_or(RgbaPixel::new(255, 255, 255, 0));
+            let alpha = pixel.a as u32;
+            for channel in [pixel.r, pixel.g, pixel.b] {
+                rgb.push(((channel as u32 * alpha + 255 * (255 - alpha) + 127) / 255) as u8);
+            }
+        }
+    }
+
+    (out_width, out_height, rgb)
+}
+
+/// Content stream drawing corner crop marks outside the bleed
+fn crop_marks(margin: f32, trim_width: f32, trim_height: f32, bleed: f32) -> String {
+    let start = bleed + CROP_MARK_OFFSET;
+    let end = start + CROP_MARK_LENGTH;
+    let left = margin;
+    let right = margin + trim_width;
+    let bottom = margin;
+    let top = margin + trim_height;
+
+    let mut content = format!("q {} w 0 G\n", num(CROP_MARK_WIDTH));
+    for (x, y, dx, dy) in [
+        (left, bottom, -1.0, -1.0),
+        (right, bottom, 1.0, -1.0),
+        (left, top, -1.0, 1.0),
+        (right, top, 1.0, 1.0),
+    ] {
+        // Horizontal mark in line with the trim edge, then the vertical one
+        content.push_str(&format!(
+            "{} {} m {} {} l S\n",
+            num(x + dx * start),
+            num(y),
+            num(x + dx * end),
+            num(y)
+        ));
+        content.push_str(&format!(
+            "{} {} m {} {} l S\n",
+            num(x),
+            num(y + dy * start),
+            num(x),
+            num(y + dy * end)
+        ));
+    }
+    content.push_str("Q\n");
+    content
+}
+
+/// ICC profile data prepared for embedding
+struct OutputProfile {
+    data: Vec<u8>,
+    components: u8,
+    description: String,
+}
+
+impl OutputProfile {
+    fn from_profile(profile: &IccProfile) -> Result<Self> {
+        let components = match profile.color_space {
+            ColorSpace::Rgb => 3,
+            ColorSpace::Cmyk => 4,
+            ColorSpace::Gray => 1,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "ICC profile color space {:?} cannot be used as a PDF output intent",
+                    other
+                ))
+            }
+        };
+        let data = match &profile.raw_data {
+            Some(data) => data.clone(),
+            None => profile
+                .get_profile()?
+                .icc()
+                .map_err(|e| anyhow::anyhow!("Failed to serialize ICC profile: {:?}", e))?,
+        };
+
+        Ok(Self {
+            data,
+            components,
+            description: profile.description.clone(),
+        })
+    }
+}
+
+/// Minimal PDF object writer with a cross-reference table
+struct PdfWriter {
+    buffer: Vec<u8>,
+    offsets: Vec<Option<usize>>,
+}
+
+impl PdfWriter {
+    fn new() -> Self {
+        Self {
+            // Binary comment marks the file as containing binary data
+            buffer: b"%PDF-1.6\n%\xE2\xE3\xCF\xD3\n".to_vec(),
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Reserve the next object number
+    fn reserve(&mut self) -> usize {
+        self.offsets.push(None);
+        self.offsets.len()
+    }
+
+    fn begin(&mut self, id: usize) {
+        self.offsets[id - 1] = Some(self.buffer.len());
+        self.buffer
+            .extend_from_slice(format!("{} 0 obj\n", id).as_bytes());
+    }
+
+    fn object(&mut self, id: usize, body: &str) {
+        self.begin(id);
+        self.buffer.extend_from_slice(body.as_bytes());
+        self.buffer.extend_from_slice(b"\nendobj\n");
+    }
+
+    /// Write a Flate-compressed stream object with extra dictionary entries
+    fn stream(&mut self, id: usize, dictionary: &str, data: &[u8]) -> Result<()> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        let compressed = encoder.finish().context("Failed to compress PDF stream")?;
+
+        self.begin(id);
+        self.buffer.extend_from_slice(
+            format!(
+                "<< {} /Filter /FlateDecode /Length {} >>\nstream\n",
+                dictionary,
+                compressed.len()
+            )
+            .as_bytes(),
+        );
+        self.buffer.extend_from_slice(&compressed);
+        self.buffer.extend_from_slice(b"\nendstream\nendobj\n");
+        Ok(())
+    }
+
+    fn finish(mut self, root_id: usize) -> Vec<u8> {
+        let xref_offset = self.buffer.len();
+        let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+        for offset in &self.offsets {
+            xref.push_str(&format!("{:010} 00000 n \n", offset.unwrap_or(0)));
+        }
+        xref.push_str(&format!(
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.offsets.len() + 1,
+            root_id,
+            xref_offset
+        ));
+        self.buffer.extend_from_slice(xref.as_bytes());
+        self.buffer
+    }
+}
+
+/// Format a number for PDF with up to three decimals
+fn num(value: f32) -> String {
+    let formatted = format!("{:.3}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// Encode text as a PDF literal string
+fn pdf_string(text: &str) -> String {
+    let mut escaped = String::from("(");
+    for c in text
+        .chars()
+        .filter(|c| c.is_ascii() && !c.is_ascii_control())
+    {
+        if matches!(c, '(' | ')' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped.push(')');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_num_formatting() {
+        assert_eq!(num(72.0), "72");
+        assert_eq!(num(8.5039), "8.504");
+        assert_eq!(num(0.25), "0.25");
+    }
+
+    #[test]
+    fn test_pdf_string_escapes() {
+        assert_eq!(pdf_string("a (b) \\c"), "(a \\(b\\) \\\\c)");
+    }
+
+    #[test]
+    fn test_bleed_extends_edge_pixels_over_white() {
+        let mut pixels = PixelData::new_rgba(2, 1);
+        pixels
+            .set_pixel(0, 0, RgbaPixel::new(255, 0, 0, 255))
+            .unwrap();
+        pixels.set_pixel(1, 0, RgbaPixel::new(0, 0, 0, 0)).unwrap();
+
+        let (width, height, rgb) = bleed_rgb(&pixels, 1, 1);
+        assert_eq!((width, height), (4, 3));
+        // Left bleed column copies the red edge, right column the white one
+        assert_eq!(&rgb[0..3], &[255, 0, 0]);
+        assert_eq!(&rgb[9..12], &[255, 255, 255]);
+    }
+}
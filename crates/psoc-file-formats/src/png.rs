@@ -1,9 +1,12 @@
 //! PNG format support
 //!
-//! This module provides PNG image loading and saving functionality with ICC profile support.
+//! This module provides PNG image loading and saving functionality with ICC profile
+//! and EXIF/XMP metadata support.
 
+use crate::limits::{DecodeLimits, MAX_ICC_PROFILE_SIZE};
+use crate::metadata;
 use anyhow::{Context, Result};
-use psoc_core::{ColorManager, IccProfile};
+use psoc_core::{ColorManager, EmbeddedMetadata, IccProfile};
 use std::fs::File;
 use std::io::{BufReader, Read};
 use std::path::Path;
@@ -16,6 +19,8 @@ pub struct PngLoadResult {
     pub image: image::DynamicImage,
     /// Embedded ICC profile, if any
     pub icc_profile: Option<IccProfile>,
+    /// Embedded EXIF/XMP metadata
+    pub metadata: EmbeddedMetadata,
 }
 
 /// Load a PNG image from a file path
@@ -28,12 +33,21 @@ pub fn load_png<P: AsRef<Path>>(path: P) -> Result<image::DynamicImage> {
 /// Load a PNG image with ICC profile from a file path
 #[instrument(skip_all, fields(path = %path.as_ref().display()))]
 pub fn load_png_with_profile<P: AsRef<Path>>(path: P) -> Result<PngLoadResult> {
+    load_png_with_limits(path, &DecodeLimits::default())
+}
+
+/// Load a PNG image with ICC profile, rejecting files over the given limits
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_png_with_limits<P: AsRef<Path>>(
+    path: P,
+    limits: &DecodeLimits,
+) -> Result<PngLoadResult> {
     let path = path.as_ref();
     debug!("Loading PNG image with profile from: {}", path.display());
 
-    // Load the image using the standard image crate
-    let image = image::open(path)
-        .with_context(|| format!("Failed to load PNG image from: {}", path.display()))?;
+    // Decode with metadata, rotating the pixels according to the EXIF orientation
+    let decoded = metadata::decode_image(path, "PNG", limits)?;
+    let image = decoded.image;
 
     // Verify it's actually a PNG
     if !matches!(
@@ -49,7 +63,11 @@ pub fn load_png_with_profile<P: AsRef<Path>>(path: P) -> Result<PngLoadResult> {
     // Try to extract ICC profile from PNG file
     let icc_profile = extract_png_icc_profile(path)?;
 
-    Ok(PngLoadResult { image, icc_profile })
+    Ok(PngLoadResult {
+        image,
+        icc_profile,
+        metadata: decoded.metadata,
+    })
 }
 
 /// Extract ICC profile from PNG file
@@ -88,10 +106,19 @@ fn extract_png_icc_profile<P: AsRef<Path>>(path: P) -> Result<Option<IccProfile>
             // Found ICC profile chunk
             debug!("Found iCCP chunk with length: {}", length);
 
-            let mut chunk_data = vec![0u8; length as usize];
-            reader
-                .read_exact(&mut chunk_data)
+            // Read through `take` so a bogus length cannot force a huge allocation
+            let mut chunk_data = Vec::new();
+            (&mut reader)
+                .take(length.min(MAX_ICC_PROFILE_SIZE as u32) as u64)
+                .read_to_end(&mut chunk_data)
                 .context("Failed to read iCCP chunk data")?;
+            if chunk_data.len() != length as usize {
+                return Err(anyhow::anyhow!(
+                    "Invalid iCCP chunk: expected {} bytes, found {}",
+                    length,
+                    chunk_data.len()
+                ));
+            }
 
             // Skip CRC
             let mut crc = [0u8; 4];
@@ -156,11 +183,18 @@ fn parse_iccp_chunk(data: &[u8]) -> Result<Option<IccProfile>> {
 
     // Decompress the profile data
     use std::io::Cursor;
-    let mut decoder = flate2::read::ZlibDecoder::new(Cursor::new(compressed_data));
+    let decoder = flate2::read::ZlibDecoder::new(Cursor::new(compressed_data));
     let mut profile_data = Vec::new();
     decoder
+        .take(MAX_ICC_PROFILE_SIZE + 1)
         .read_to_end(&mut profile_data)
         .context("Failed to decompress ICC profile data")?;
+    if profile_data.len() as u64 > MAX_ICC_PROFILE_SIZE {
+        return Err(anyhow::anyhow!(
+            "Embedded ICC profile is larger than {} bytes",
+            MAX_ICC_PROFILE_SIZE
+        ));
+    }
 
     debug!(
         "Extracted ICC profile '{}' with {} bytes",
@@ -200,6 +234,10 @@ pub struct PngOptions {
     pub use_filtering: bool,
     /// ICC profile to embed (optional)
     pub icc_profile: Option<IccProfile>,
+    /// EXIF/XMP metadata to embed (optional)
+    pub metadata: Option<EmbeddedMetadata>,
+    /// Remove GPS location, serial numbers and other private fields from the metadata
+    pub strip_private_metadata: bool,
 }
 
 impl Default for PngOptions {
@@ -208,6 +246,8 @@ impl Default for PngOptions {
             compression_level: 6, // Default compression level
             use_filtering: true,
             icc_profile: None,
+            metadata: None,
+            strip_private_metadata: false,
         }
     }
 }
@@ -224,11 +264,24 @@ pub fn save_png_with_options<P: AsRef<Path>>(
 
     if options.icc_profile.is_some() {
         // Use custom PNG encoding with ICC profile
-        save_png_with_icc_profile(image, path, options)
+        save_png_with_icc_profile(image, path, options)?;
     } else {
         // Use standard save method
-        save_png(image, path)
+        save_png(image, path)?;
+    }
+
+    if let Some(ref embedded) = options.metadata {
+        metadata::embed_metadata_in_file(
+            path,
+            "PNG",
+            embedded,
+            options.strip_private_metadata,
+            (image.width(), image.height()),
+            metadata::embed_png_metadata,
+        )?;
     }
+
+    Ok(())
 }
 
 /// Save a PNG image with embedded ICC profile
@@ -298,6 +351,7 @@ mod tests {
             compression_level: 9,
             use_filtering: false,
             icc_profile: None,
+            ..PngOptions::default()
         };
 
         // Save the image with options
@@ -317,6 +371,7 @@ mod tests {
         let result = PngLoadResult {
             image: dynamic_img,
             icc_profile: None,
+            metadata: EmbeddedMetadata::default(),
         };
 
         assert_eq!(result.image.width(), 10);
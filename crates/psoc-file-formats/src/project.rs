@@ -3,19 +3,70 @@
 //! This module provides support for loading and saving PSOC project files (.psoc)
 //! which contain multi-layer documents with full layer information, metadata,
 //! and document settings.
+//!
+//! Version 2 projects are zip archives with the following layout:
+//!
+//! - `mimetype`: `application/x-psoc-project`, stored uncompressed as the first entry
+//! - `manifest.ron`: the [`ProjectFile`] with pixel data stripped from every layer
+//! - `layers/<index>/pixels/<column>_<row>.bin`: deflate-compressed raw tile bytes
+//! - `layers/<index>/mask/<column>_<row>.bin`: layer mask tiles, if the layer has a mask
+//! - `channels/<index>/<column>_<row>.bin`: alpha channel tiles
+//! - `selection/<column>_<row>.bin`: tiles of a mask selection
+//! - `thumbnail.png`: a small flattened preview
+//!
+//! Fully transparent tiles are omitted. Version 1 projects, which serialize the
+//! whole document as pretty RON text, are still readable.
+//!
+//! [`open_project`] keeps the archive open and leaves each layer's tiles in it
+//! until the layer is first used (see [`Layer::pending_pixels`]), while
+//! [`load_project`] decodes every layer up front. [`ProjectArchive`] can read
+//! the manifest, the thumbnail or a single layer without touching the rest.
 
+use crate::limits::{run_with_time_limit, DecodeBudget, DecodeLimits};
+use crate::migration::{check_project_version, migrate_project};
 use anyhow::{Context, Result};
-use psoc_core::Document;
+use ndarray::Array3;
+use psoc_core::{
+    AlphaChannel, CommandHistory, Document, HdrPixelData, Layer, MaskSelection, PendingPixels,
+    PixelData, RenderEngine, Selection,
+};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Cursor, Read, Seek, Write};
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 use tracing::{debug, info, instrument};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
 
 /// PSOC project file format version
-pub const PROJECT_FORMAT_VERSION: &str = "1.0";
+pub const PROJECT_FORMAT_VERSION: &str = "2.0";
+
+/// Version of the original RON-only project format
+pub const LEGACY_PROJECT_FORMAT_VERSION: &str = "1.0";
 
 /// PSOC project file extension
 pub const PROJECT_FILE_EXTENSION: &str = "psoc";
 
+/// PSOC project MIME type, stored uncompressed as the first archive entry
+pub const PROJECT_MIME_TYPE: &str = "application/x-psoc-project";
+
+/// Default edge length of a layer tile in pixels
+pub const DEFAULT_TILE_SIZE: u32 = 256;
+
+/// Archive entry holding the serialized [`ProjectFile`]
+const MANIFEST_ENTRY: &str = "manifest.ron";
+
+/// Archive entry holding the flattened preview
+const THUMBNAIL_ENTRY: &str = "thumbnail.png";
+
+/// Maximum edge length of the embedded thumbnail
+const THUMBNAIL_MAX_SIZE: u32 = 256;
+
+/// Local file header signature that starts every zip archive
+const ZIP_SIGNATURE: &[u8; 4] = b"PK\x03\x04";
+
 /// Project file container structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectFile {
@@ -25,6 +76,15 @@ pub struct ProjectFile {
     pub metadata: ProjectMetadata,
     /// The document data
     pub document: Document,
+    /// Tile index for each document layer (v2 only, same order as the layers)
+    #[serde(default)]
+    pub layer_tiles: Vec<LayerTiles>,
+    /// Tile index for each alpha channel (v2 only, same order as the channels)
+    #[serde(default)]
+    pub channel_tiles: Vec<TileGrid>,
+    /// Tile index of the selection mask, if the selection is a mask (v2 only)
+    #[serde(default)]
+    pub selection_tiles: Option<TileGrid>,
 }
 
 /// Project metadata
@@ -42,6 +102,55 @@ pub struct ProjectMetadata {
     pub tags: Vec<String>,
 }
 
+/// Tile index for a single layer
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LayerTiles {
+    /// Tiles of the layer pixel data
+    pub pixels: Option<TileGrid>,
+    /// Tiles of the layer mask
+    pub mask: Option<TileGrid>,
+    /// Tiles of the HDR source pixels, as little-endian `f32` RGBA samples
+    #[serde(default)]
+    pub hdr: Option<TileGrid>,
+}
+
+/// Pixel buffer split into fixed-size tiles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileGrid {
+    /// Buffer width in pixels
+    pub width: u32,
+    /// Buffer height in pixels
+    pub height: u32,
+    /// Bytes per pixel
+    pub channels: u8,
+    /// In-memory representation to restore
+    pub layout: TileLayout,
+    /// Tile edge length in pixels
+    pub tile_size: u32,
+    /// Stored tiles; missing tiles are fully transparent
+    pub tiles: Vec<TileRef>,
+}
+
+/// In-memory representation of a tiled pixel buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TileLayout {
+    /// [`PixelData::Rgba`]
+    Rgba,
+    /// [`PixelData::Raw`]
+    Raw,
+}
+
+/// Reference to a stored tile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileRef {
+    /// Tile column
+    pub column: u32,
+    /// Tile row
+    pub row: u32,
+    /// Archive entry holding the raw tile bytes
+    pub entry: String,
+}
+
 impl ProjectFile {
     /// Create a new project file from a document
     pub fn new(document: Document) -> Self {
@@ -57,6 +166,9 @@ impl ProjectFile {
                 tags: Vec::new(),
             },
             document,
+            layer_tiles: Vec::new(),
+            channel_tiles: Vec::new(),
+            selection_tiles: None,
         }
     }
 
@@ -65,37 +177,129 @@ impl ProjectFile {
         self.metadata.modified_at = chrono::Utc::now().to_rfc3339();
     }
 
-    /// Check if the project file version is compatible
+    /// Check if the project file version is current or can be migrated
     pub fn is_compatible(&self) -> bool {
-        // For now, only support exact version match
-        // In the future, we can implement version compatibility logic
-        self.version == PROJECT_FORMAT_VERSION
+        check_project_version(&self.version).is_ok()
     }
 }
 
-/// Load a PSOC project file
+/// Parse a serialized [`ProjectFile`] and upgrade it to the current format
+///
+/// Older files are migrated before the document is parsed, so only RON in the
+/// current format ever reaches the [`ProjectFile`] schema.
+pub fn parse_project_file(content: &str) -> Result<ProjectFile> {
+    let (content, _) = migrate_project(content)?;
+    ron::from_str(&content).context("Failed to parse project document")
+}
+
+/// Project-specific configuration options
+#[derive(Debug, Clone)]
+pub struct ProjectOptions {
+    /// Tile edge length in pixels
+    pub tile_size: u32,
+    /// Whether to embed a flattened thumbnail
+    pub include_thumbnail: bool,
+}
+
+impl Default for ProjectOptions {
+    fn default() -> Self {
+        Self {
+            tile_size: DEFAULT_TILE_SIZE,
+            include_thumbnail: true,
+        }
+    }
+}
+
+impl ProjectOptions {
+    /// Validate tile size value
+    pub fn with_tile_size(mut self, tile_size: u32) -> Self {
+        self.tile_size = tile_size.clamp(16, 4096);
+        self
+    }
+}
+
+/// Load a PSOC project file, decoding every layer
+///
+/// Both v2 archives and v1 RON files are accepted.
 #[instrument(skip_all, fields(path = %path.as_ref().display()))]
 pub fn load_project<P: AsRef<Path>>(path: P) -> Result<Document> {
+    load_project_with_limits(path, &DecodeLimits::default())
+}
+
+/// Load a PSOC project file, rejecting layers and entries over the given limits
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_project_with_limits<P: AsRef<Path>>(
+    path: P,
+    limits: &DecodeLimits,
+) -> Result<Document> {
     let path = path.as_ref();
     debug!("Loading PSOC project from: {}", path.display());
 
-    // Read the file content
-    let content = std::fs::read_to_string(path)
-        .with_context(|| format!("Failed to read project file: {}", path.display()))?;
+    if is_project_archive(path)? {
+        let mut archive = ProjectArchive::open_with_limits(path, limits)?;
+        let document = archive.load_document()?;
 
-    // Parse the RON format
-    let project_file: ProjectFile = ron::from_str(&content)
-        .with_context(|| format!("Failed to parse project file: {}", path.display()))?;
+        info!(
+            version = %archive.project().version,
+            created_with = %archive.project().metadata.created_with,
+            layers = document.layers.len(),
+            "Successfully loaded PSOC project"
+        );
 
-    // Check version compatibility
-    if !project_file.is_compatible() {
-        return Err(anyhow::anyhow!(
-            "Incompatible project file version: {} (expected: {})",
-            project_file.version,
-            PROJECT_FORMAT_VERSION
-        ));
+        return Ok(document);
     }
 
+    let path = path.to_path_buf();
+    let limits = *limits;
+    run_with_time_limit(limits.max_decode_time, move || {
+        load_legacy_project(&path, &limits)
+    })
+}
+
+/// Open a PSOC project file for editing, decoding layers when they are first used
+///
+/// v2 archives stay open for as long as the document has pending layers;
+/// v1 RON files carry their pixels inline and are loaded completely.
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn open_project<P: AsRef<Path>>(path: P) -> Result<Document> {
+    open_project_with_limits(path, &DecodeLimits::default())
+}
+
+/// Open a PSOC project file for editing, checking each layer load against the given limits
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn open_project_with_limits<P: AsRef<Path>>(
+    path: P,
+    limits: &DecodeLimits,
+) -> Result<Document> {
+    let path = path.as_ref();
+    if !is_project_archive(path)? {
+        return load_project_with_limits(path, limits);
+    }
+
+    debug!("Opening PSOC project from: {}", path.display());
+    let archive = ProjectArchive::open_with_limits(path, limits)?;
+    info!(
+        version = %archive.project().version,
+        created_with = %archive.project().metadata.created_with,
+        layers = archive.layer_count(),
+        "Opened PSOC project"
+    );
+    archive.into_document()
+}
+
+/// Load a v1 (RON) PSOC project file
+fn load_legacy_project(path: &Path, limits: &DecodeLimits) -> Result<Document> {
+    // Read the file content
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open project file: {}", path.display()))?;
+    let content = DecodeBudget::new(limits).read_to_end(file, "project file")?;
+    let content = String::from_utf8(content)
+        .with_context(|| format!("Project file is not UTF-8: {}", path.display()))?;
+
+    // Parse the RON format, upgrading older versions
+    let project_file = parse_project_file(&content)
+        .with_context(|| format!("Failed to parse project file: {}", path.display()))?;
+
     info!(
         version = %project_file.version,
         created_with = %project_file.metadata.created_with,
@@ -106,19 +310,33 @@ pub fn load_project<P: AsRef<Path>>(path: P) -> Result<Document> {
     Ok(project_file.document)
 }
 
-/// Save a document as a PSOC project file
+/// Load only the embedded thumbnail of a v2 project, without decoding any layers
 #[instrument(skip_all, fields(path = %path.as_ref().display()))]
-pub fn save_project<P: AsRef<Path>>(document: &Document, path: P) -> Result<()> {
+pub fn load_project_thumbnail<P: AsRef<Path>>(path: P) -> Result<Option<image::DynamicImage>> {
     let path = path.as_ref();
-    debug!("Saving PSOC project to: {}", path.display());
+    if !is_project_archive(path)? {
+        debug!("Legacy project has no embedded thumbnail");
+        return Ok(None);
+    }
 
-    // Create project file structure
-    let mut project_file = ProjectFile::new(document.clone());
-    project_file.touch();
+    ProjectArchive::open(path)?.thumbnail()
+}
 
-    // Serialize to RON format with pretty printing
-    let content = ron::ser::to_string_pretty(&project_file, ron::ser::PrettyConfig::default())
-        .context("Failed to serialize project file")?;
+/// Save a document as a PSOC project file with default options
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn save_project<P: AsRef<Path>>(document: &Document, path: P) -> Result<()> {
+    save_project_with_options(document, path, &ProjectOptions::default())
+}
+
+/// Save a document as a PSOC project file with specific options
+#[instrument(skip_all, fields(path = %path.as_ref().display(), tile_size = options.tile_size))]
+pub fn save_project_with_options<P: AsRef<Path>>(
+    document: &Document,
+    path: P,
+    options: &ProjectOptions,
+) -> Result<()> {
+    let path = path.as_ref();
+    debug!("Saving PSOC project to: {}", path.display());
 
     // Ensure the target directory exists
     if let Some(parent) = path.parent() {
@@ -128,9 +346,26 @@ pub fn save_project<P: AsRef<Path>>(document: &Document, path: P) -> Result<()>
         }
     }
 
-    // Write the file
-    std::fs::write(path, content)
-        .with_context(|| format!("Failed to write project file: {}", path.display()))?;
+    // Write next to the target and rename over it, so a document whose pending
+    // layers are still read from the old file can be saved in place
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("Invalid project path: {}", path.display()))?;
+    let temp_path = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+    let written = File::create(&temp_path)
+        .with_context(|| format!("Failed to create project file: {}", temp_path.display()))
+        .and_then(|file| {
+            write_project(document, BufWriter::new(file), options)
+                .with_context(|| format!("Failed to write project file: {}", path.display()))
+        })
+        .and_then(|()| {
+            std::fs::rename(&temp_path, path)
+                .with_context(|| format!("Failed to replace project file: {}", path.display()))
+        });
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(e);
+    }
 
     info!(
         layers = document.layers.len(),
@@ -141,6 +376,378 @@ pub fn save_project<P: AsRef<Path>>(document: &Document, path: P) -> Result<()>
     Ok(())
 }
 
+/// Write a document as a v2 project archive to any seekable writer
+pub fn write_project<W: Write + Seek>(
+    document: &Document,
+    writer: W,
+    options: &ProjectOptions,
+) -> Result<()> {
+    let tile_size = options.tile_size.clamp(16, 4096);
+    let mut zip = ZipWriter::new(writer);
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // The mimetype comes first and uncompressed so the format can be sniffed
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(PROJECT_MIME_TYPE.as_bytes())?;
+
+    let mut layer_tiles = Vec::with_capacity(document.layers.len());
+    for (index, layer) in document.layers.iter().enumerate() {
+        let layer = layer.loaded()?;
+        let pixels = match &layer.pixel_data {
+            Some(pixel_data) => Some(write_tiles(
+                &mut zip,
+                &format!("layers/{}/pixels", index),
+                pixel_data,
+                tile_size,
+                deflated,
+            )?),
+            None => None,
+        };
+        let mask = match &layer.mask {
+            Some(mask) => Some(write_tiles(
+                &mut zip,
+                &format!("layers/{}/mask", index),
+                mask,
+                tile_size,
+                deflated,
+            )?),
+            None => None,
+        };
+        let hdr = match layer.hdr_pixels() {
+            Some(hdr) => Some(write_tiles(
+                &mut zip,
+                &format!("layers/{}/hdr", index),
+                &hdr_to_raw(&hdr),
+                tile_size,
+                deflated,
+            )?),
+            None => None,
+        };
+        layer_tiles.push(LayerTiles { pixels, mask, hdr });
+    }
+
+    let mut channel_tiles = Vec::with_capacity(document.channels.len());
+    for (index, channel) in document.channels.iter().enumerate() {
+        channel_tiles.push(write_tiles(
+            &mut zip,
+            &format!("channels/{}", index),
+            &channel.pixel_data,
+            tile_size,
+            deflated,
+        )?);
+    }
+
+    let selection_tiles = match &document.selection {
+        Selection::Mask(mask) => Some(write_tiles(
+            &mut zip,
+            "selection",
+            &PixelData::Raw {
+                data: mask.mask_data.clone(),
+                width: mask.width,
+                height: mask.height,
+                channels: 1,
+            },
+            tile_size,
+            deflated,
+        )?),
+        _ => None,
+    };
+
+    let mut project_file = ProjectFile::new(document_skeleton(document));
+    project_file.layer_tiles = layer_tiles;
+    project_file.channel_tiles = channel_tiles;
+    project_file.selection_tiles = selection_tiles;
+    project_file.touch();
+
+    let manifest = ron::ser::to_string_pretty(&project_file, ron::ser::PrettyConfig::default())
+        .context("Failed to serialize project manifest")?;
+    zip.start_file(MANIFEST_ENTRY, deflated)?;
+    zip.write_all(manifest.as_bytes())?;
+
+    if options.include_thumbnail && !document.layers.is_empty() {
+        let mut render_engine = RenderEngine::new();
+        let merged = if document.has_pending_layers() {
+            let mut document = document.clone();
+            document.load_layers_in_use()?;
+            render_engine.render_document(&document)?.to_image()?
+        } else {
+            render_engine.render_document(document)?.to_image()?
+        };
+        // Only downscale; small documents keep their own size
+        let thumbnail =
+            if merged.width() > THUMBNAIL_MAX_SIZE || merged.height() > THUMBNAIL_MAX_SIZE {
+                merged.thumbnail(THUMBNAIL_MAX_SIZE, THUMBNAIL_MAX_SIZE)
+            } else {
+                merged
+            };
+
+        let mut buffer = Cursor::new(Vec::new());
+        thumbnail
+            .write_to(&mut buffer, image::ImageFormat::Png)
+            .context("Failed to encode project thumbnail")?;
+        zip.start_file(THUMBNAIL_ENTRY, stored)?;
+        zip.write_all(buffer.get_ref())?;
+    }
+
+    zip.finish().context("Failed to finalize project archive")?;
+    Ok(())
+}
+
+/// Open v2 project archive
+///
+/// Opening an archive only parses the manifest; layer tiles and the thumbnail
+/// are decoded when requested, so previews and recovery listings stay cheap.
+pub struct ProjectArchive<R: Read + Seek> {
+    archive: ZipArchive<R>,
+    project: ProjectFile,
+    limits: DecodeLimits,
+}
+
+impl ProjectArchive<BufReader<File>> {
+    /// Open a v2 project archive from a file path
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_with_limits(path, &DecodeLimits::default())
+    }
+
+    /// Open a v2 project archive whose loads are checked against the given limits
+    pub fn open_with_limits<P: AsRef<Path>>(path: P, limits: &DecodeLimits) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open project file: {}", path.display()))?;
+        Self::from_reader_with_limits(BufReader::new(file), limits)
+            .with_context(|| format!("Failed to read project archive: {}", path.display()))
+    }
+}
+
+impl<R: Read + Seek> ProjectArchive<R> {
+    /// Open a v2 project archive from any seekable reader
+    pub fn from_reader(reader: R) -> Result<Self> {
+        Self::from_reader_with_limits(reader, &DecodeLimits::default())
+    }
+
+    /// Open a v2 project archive from a reader, checking loads against the given limits
+    ///
+    /// Each call to [`Self::load_layer`], [`Self::load_document`] or
+    /// [`Self::thumbnail`] gets the full memory budget and time limit.
+    pub fn from_reader_with_limits(reader: R, limits: &DecodeLimits) -> Result<Self> {
+        let mut archive = ZipArchive::new(reader).context("Invalid project archive")?;
+
+        let mimetype =
+            read_entry_to_bytes(&mut archive, "mimetype", PROJECT_MIME_TYPE.len() as u64)?;
+        if mimetype != PROJECT_MIME_TYPE.as_bytes() {
+            return Err(anyhow::anyhow!(
+                "Unexpected project MIME type: {}",
+                String::from_utf8_lossy(&mimetype)
+            ));
+        }
+
+        let mut budget = DecodeBudget::new(limits);
+        let manifest = budget.read_to_end(
+            archive
+                .by_name(MANIFEST_ENTRY)
+                .with_context(|| format!("Project archive is missing entry: {}", MANIFEST_ENTRY))?,
+            "project manifest",
+        )?;
+        let manifest = String::from_utf8(manifest).context("Project manifest is not UTF-8")?;
+        let project = parse_project_file(&manifest).context("Failed to parse project manifest")?;
+
+        if project.layer_tiles.len() != project.document.layers.len() {
+            return Err(anyhow::anyhow!(
+                "Project manifest lists {} tile sets for {} layers",
+                project.layer_tiles.len(),
+                project.document.layers.len()
+            ));
+        }
+        if project.channel_tiles.len() != project.document.channels.len() {
+            return Err(anyhow::anyhow!(
+                "Project manifest lists {} tile sets for {} channels",
+                project.channel_tiles.len(),
+                project.document.channels.len()
+            ));
+        }
+
+        Ok(Self {
+            archive,
+            project,
+            limits: *limits,
+        })
+    }
+
+    /// The parsed manifest; layers, channels and mask selections carry no pixel data
+    pub fn project(&self) -> &ProjectFile {
+        &self.project
+    }
+
+    /// Number of layers in the project
+    pub fn layer_count(&self) -> usize {
+        self.project.document.layers.len()
+    }
+
+    /// The embedded thumbnail, if present
+    pub fn thumbnail(&mut self) -> Result<Option<image::DynamicImage>> {
+        if self.archive.index_for_name(THUMBNAIL_ENTRY).is_none() {
+            return Ok(None);
+        }
+
+        let mut budget = DecodeBudget::new(&self.limits);
+        let data =
+            budget.read_to_end(self.archive.by_name(THUMBNAIL_ENTRY)?, "project thumbnail")?;
+        let mut reader =
+            image::ImageReader::with_format(Cursor::new(data), image::ImageFormat::Png);
+        reader.limits(budget.image_limits());
+        let thumbnail = reader
+            .decode()
+            .context("Failed to decode project thumbnail")?;
+        Ok(Some(thumbnail))
+    }
+
+    /// Decode a single layer, including its pixel data and mask
+    pub fn load_layer(&mut self, index: usize) -> Result<Layer> {
+        let mut budget = DecodeBudget::new(&self.limits);
+        self.load_layer_within(index, &mut budget)
+    }
+
+    /// Decode every layer and return the complete document
+    ///
+    /// All layers share one memory budget and time limit.
+    pub fn load_document(&mut self) -> Result<Document> {
+        let mut budget = DecodeBudget::new(&self.limits);
+        let mut layers = Vec::with_capacity(self.layer_count());
+        for index in 0..self.layer_count() {
+            layers.push(self.load_layer_within(index, &mut budget)?);
+        }
+
+        let mut document = document_skeleton(&self.project.document);
+        document.layers = layers;
+        self.load_masks_within(&mut document, &mut budget)?;
+        Ok(document)
+    }
+
+    /// Build the document with each layer's tiles left in the archive
+    ///
+    /// Alpha channels and the selection are decoded now. Layers with stored
+    /// pixels get [`Layer::pending_pixels`] that share this archive, which
+    /// stays open until the last of them is dropped. Each layer load gets the
+    /// full memory budget and time limit.
+    pub fn into_document(mut self) -> Result<Document>
+    where
+        R: Send + 'static,
+    {
+        let mut budget = DecodeBudget::new(&self.limits);
+        let mut document = document_skeleton(&self.project.document);
+        self.load_masks_within(&mut document, &mut budget)?;
+
+        let stored: Vec<bool> = self
+            .project
+            .layer_tiles
+            .iter()
+            .map(|tiles| tiles.pixels.is_some() || tiles.mask.is_some() || tiles.hdr.is_some())
+            .collect();
+        let archive = Arc::new(Mutex::new(self));
+        for (index, layer) in document.layers.iter_mut().enumerate() {
+            if !stored[index] {
+                continue;
+            }
+            let archive = Arc::clone(&archive);
+            layer.pending_pixels = Some(PendingPixels::new(move || {
+                archive
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .load_layer(index)
+            }));
+        }
+
+        Ok(document)
+    }
+
+    /// Fill in alpha channel pixels and the pixels of a mask selection from their tiles
+    fn load_masks_within(
+        &mut self,
+        document: &mut Document,
+        budget: &mut DecodeBudget,
+    ) -> Result<()> {
+        for (channel, grid) in document
+            .channels
+            .iter_mut()
+            .zip(&self.project.channel_tiles)
+        {
+            channel.pixel_data = read_tiles(&mut self.archive, grid, budget)
+                .with_context(|| format!("Failed to load channel '{}'", channel.name))?;
+        }
+
+        let (Selection::Mask(mask), Some(grid)) =
+            (&mut document.selection, &self.project.selection_tiles)
+        else {
+            return Ok(());
+        };
+
+        let pixels =
+            read_tiles(&mut self.archive, grid, budget).context("Failed to load selection mask")?;
+        match pixels {
+            PixelData::Raw {
+                data,
+                width,
+                height,
+                channels: 1,
+            } if (width, height) == (mask.width, mask.height) => {
+                mask.mask_data = data;
+                Ok(())
+            }
+            _ => Err(anyhow::anyhow!(
+                "Invalid selection tile grid in project manifest"
+            )),
+        }
+    }
+
+    fn load_layer_within(&mut self, index: usize, budget: &mut DecodeBudget) -> Result<Layer> {
+        let mut layer = self
+            .project
+            .document
+            .layers
+            .get(index)
+            .cloned()
+            .with_context(|| format!("Layer index out of range: {}", index))?;
+        let tiles = self.project.layer_tiles[index].clone();
+
+        if let Some(grid) = &tiles.pixels {
+            layer.pixel_data = Some(
+                read_tiles(&mut self.archive, grid, budget)
+                    .with_context(|| format!("Failed to load pixels of layer '{}'", layer.name))?,
+            );
+        }
+        if let Some(grid) = &tiles.mask {
+            layer.mask = Some(
+                read_tiles(&mut self.archive, grid, budget)
+                    .with_context(|| format!("Failed to load mask of layer '{}'", layer.name))?,
+            );
+        }
+        if let Some(grid) = &tiles.hdr {
+            let raw = read_tiles(&mut self.archive, grid, budget)
+                .with_context(|| format!("Failed to load HDR pixels of layer '{}'", layer.name))?;
+            layer.attach_hdr(hdr_from_raw(raw)?);
+        }
+
+        Ok(layer)
+    }
+}
+
+/// Check whether a project file is a v2 archive rather than v1 RON text
+pub fn is_project_archive<P: AsRef<Path>>(path: P) -> Result<bool> {
+    let path = path.as_ref();
+    let mut file = File::open(path)
+        .with_context(|| format!("Failed to open project file: {}", path.display()))?;
+
+    let mut signature = [0u8; 4];
+    match file.read_exact(&mut signature) {
+        Ok(()) => Ok(&signature == ZIP_SIGNATURE),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => {
+            Err(e).with_context(|| format!("Failed to read project file: {}", path.display()))
+        }
+    }
+}
+
 /// Check if a file path has the PSOC project extension
 pub fn is_project_file<P: AsRef<Path>>(path: P) -> bool {
     path.as_ref()
@@ -150,11 +757,288 @@ pub fn is_project_file<P: AsRef<Path>>(path: P) -> bool {
         .unwrap_or(false)
 }
 
+/// Copy a document without any layer pixel data, masks, channel data or selection mask
+///
+/// Fields are listed explicitly so that pixel buffers are never cloned.
+fn document_skeleton(document: &Document) -> Document {
+    Document {
+        id: document.id,
+        metadata: document.metadata.clone(),
+        size: document.size,
+        resolution: document.resolution,
+        color_mode: document.color_mode,
+        color_space: document.color_space,
+        icc_profile: document.icc_profile.clone(),
+        background_color: document.background_color,
+        layers: document.layers.iter().map(layer_skeleton).collect(),
+        active_layer_index: document.active_layer_index,
+        canvas_bounds: document.canvas_bounds,
+        selection: selection_skeleton(&document.selection),
+        quick_mask: None,
+        channels: document.channels.iter().map(channel_skeleton).collect(),
+        active_channel: None,
+        paths: document.paths.clone(),
+        active_path: None,
+        assets: document.assets.clone(),
+        is_dirty: document.is_dirty,
+        revision: document.revision,
+        file_path: document.file_path.clone(),
+        command_history: CommandHistory::default(),
+    }
+}
+
+/// Copy a selection without the pixels of a mask selection
+fn selection_skeleton(selection: &Selection) -> Selection {
+    match selection {
+        Selection::Mask(mask) => Selection::Mask(MaskSelection {
+            width: mask.width,
+            height: mask.height,
+            mask_data: Vec::new(),
+            offset: mask.offset,
+            inverted: mask.inverted,
+        }),
+        selection => selection.clone(),
+    }
+}
+
+/// Copy an alpha channel without its pixel data
+fn channel_skeleton(channel: &AlphaChannel) -> AlphaChannel {
+    AlphaChannel {
+        id: channel.id,
+        name: channel.name.clone(),
+        pixel_data: PixelData::new_grayscale(0, 0),
+    }
+}
+
+/// Copy a layer without its pixel data or mask
+fn layer_skeleton(layer: &Layer) -> Layer {
+    Layer {
+        id: layer.id,
+        name: layer.name.clone(),
+        layer_type: layer.layer_type.clone(),
+        pixel_data: None,
+        visible: layer.visible,
+        opacity: layer.opacity,
+        blend_mode: layer.blend_mode,
+        offset: layer.offset,
+        transform: layer.transform,
+        bounds: layer.bounds,
+        locked: layer.locked,
+        mask: None,
+        metadata: layer.metadata.clone(),
+        hdr_data: None,
+        hdr_base: None,
+        pending_pixels: None,
+    }
+}
+
+/// Bytes per HDR pixel: four little-endian `f32` samples
+const HDR_PIXEL_BYTES: u8 = 16;
+
+/// Pack HDR samples into a raw buffer that can be tiled like 8-bit pixels
+fn hdr_to_raw(hdr: &HdrPixelData) -> PixelData {
+    let (width, height) = hdr.dimensions();
+    PixelData::Raw {
+        data: hdr
+            .as_raw()
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect(),
+        width,
+        height,
+        channels: HDR_PIXEL_BYTES,
+    }
+}
+
+/// Unpack HDR samples tiled by [`hdr_to_raw`]
+fn hdr_from_raw(raw: PixelData) -> Result<HdrPixelData> {
+    let PixelData::Raw {
+        data,
+        width,
+        height,
+        channels: HDR_PIXEL_BYTES,
+    } = raw
+    else {
+        return Err(anyhow::anyhow!("Invalid HDR tile grid in project manifest"));
+    };
+    let samples = data
+        .chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect();
+    HdrPixelData::new(width, height, samples)
+}
+
+/// Split a pixel buffer into tiles and write the non-empty ones to the archive
+fn write_tiles<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    prefix: &str,
+    pixel_data: &PixelData,
+    tile_size: u32,
+    entry_options: SimpleFileOptions,
+) -> Result<TileGrid> {
+    let (width, height, channels, layout, bytes): (u32, u32, u8, TileLayout, Cow<[u8]>) =
+        match pixel_data {
+            PixelData::Rgba(array) => {
+                let (h, w, c) = array.dim();
+                let bytes = match array.as_slice() {
+                    Some(slice) => Cow::Borrowed(slice),
+                    None => Cow::Owned(array.iter().copied().collect()),
+                };
+                (w as u32, h as u32, c as u8, TileLayout::Rgba, bytes)
+            }
+            PixelData::Raw {
+                data,
+                width,
+                height,
+                channels,
+            } => (
+                *width,
+                *height,
+                *channels,
+                TileLayout::Raw,
+                Cow::Borrowed(data.as_slice()),
+            ),
+        };
+
+    let stride = width as usize * channels as usize;
+    let mut tiles = Vec::new();
+
+    for row in 0..height.div_ceil(tile_size) {
+        for column in 0..width.div_ceil(tile_size) {
+            let x0 = column * tile_size;
+            let y0 = row * tile_size;
+            let tile_width = tile_size.min(width - x0) as usize;
+            let tile_height = tile_size.min(height - y0) as usize;
+            let row_bytes = tile_width * channels as usize;
+
+            let mut tile = Vec::with_capacity(row_bytes * tile_height);
+            for y in y0 as usize..y0 as usize + tile_height {
+                let start = y * stride + x0 as usize * channels as usize;
+                tile.extend_from_slice(&bytes[start..start + row_bytes]);
+            }
+
+            // Transparent tiles are implied by their absence
+            if tile.iter().all(|&byte| byte == 0) {
+                continue;
+            }
+
+            let entry = format!("{}/{}_{}.bin", prefix, column, row);
+            zip.start_file(entry.as_str(), entry_options)?;
+            zip.write_all(&tile)?;
+            tiles.push(TileRef { column, row, entry });
+        }
+    }
+
+    Ok(TileGrid {
+        width,
+        height,
+        channels,
+        layout,
+        tile_size,
+        tiles,
+    })
+}
+
+/// Reassemble a pixel buffer from its stored tiles
+fn read_tiles<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    grid: &TileGrid,
+    budget: &mut DecodeBudget,
+) -> Result<PixelData> {
+    if grid.tile_size == 0 || grid.channels == 0 {
+        return Err(anyhow::anyhow!("Invalid tile grid in project manifest"));
+    }
+    budget.reserve_pixels(grid.width, grid.height, grid.channels as u64)?;
+
+    let channels = grid.channels as usize;
+    let stride = (grid.width as usize)
+        .checked_mul(channels)
+        .context("Layer dimensions overflow")?;
+    let total = stride
+        .checked_mul(grid.height as usize)
+        .context("Layer dimensions overflow")?;
+    let mut bytes = vec![0u8; total];
+
+    for tile in &grid.tiles {
+        budget.check_time()?;
+        let x0 = tile.column.checked_mul(grid.tile_size);
+        let y0 = tile.row.checked_mul(grid.tile_size);
+        let (Some(x0), Some(y0)) = (x0, y0) else {
+            return Err(anyhow::anyhow!(
+                "Tile position out of range: {}",
+                tile.entry
+            ));
+        };
+        if x0 >= grid.width || y0 >= grid.height {
+            return Err(anyhow::anyhow!(
+                "Tile position out of range: {}",
+                tile.entry
+            ));
+        }
+
+        let tile_width = grid.tile_size.min(grid.width - x0) as usize;
+        let tile_height = grid.tile_size.min(grid.height - y0) as usize;
+        let row_bytes = tile_width * channels;
+
+        let data = read_entry_to_bytes(archive, &tile.entry, (row_bytes * tile_height) as u64)?;
+        if data.len() != row_bytes * tile_height {
+            return Err(anyhow::anyhow!(
+                "Tile {} has {} bytes, expected {}",
+                tile.entry,
+                data.len(),
+                row_bytes * tile_height
+            ));
+        }
+
+        for (i, src) in data.chunks_exact(row_bytes).enumerate() {
+            let start = (y0 as usize + i) * stride + x0 as usize * channels;
+            bytes[start..start + row_bytes].copy_from_slice(src);
+        }
+    }
+
+    match grid.layout {
+        TileLayout::Rgba => {
+            let array = Array3::from_shape_vec(
+                (grid.height as usize, grid.width as usize, channels),
+                bytes,
+            )
+            .context("Failed to rebuild layer pixel array")?;
+            Ok(PixelData::Rgba(array))
+        }
+        TileLayout::Raw => Ok(PixelData::Raw {
+            data: bytes,
+            width: grid.width,
+            height: grid.height,
+            channels: grid.channels,
+        }),
+    }
+}
+
+/// Read a named archive entry as bytes
+///
+/// At most `max_size + 1` bytes are read, so callers can detect oversized
+/// entries without trusting the size recorded in the archive.
+fn read_entry_to_bytes<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+    max_size: u64,
+) -> Result<Vec<u8>> {
+    let entry = archive
+        .by_name(name)
+        .with_context(|| format!("Project archive is missing entry: {}", name))?;
+    let mut data = Vec::with_capacity(entry.size().min(max_size) as usize);
+    entry
+        .take(max_size.saturating_add(1))
+        .read_to_end(&mut data)
+        .with_context(|| format!("Failed to read project entry: {}", name))?;
+    Ok(data)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use psoc_core::{Layer, RgbaPixel};
-    use tempfile::NamedTempFile;
+    use psoc_core::RgbaPixel;
+    use tempfile::{tempdir, NamedTempFile};
 
     #[test]
     fn test_project_file_creation() {
@@ -244,4 +1128,383 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_tiled_roundtrip_is_pixel_exact() -> Result<()> {
+        let mut document = Document::new("Tiles".to_string(), 70, 45);
+
+        // Dimensions that are not a multiple of the tile size
+        let mut layer = Layer::new_pixel("Gradient".to_string(), 70, 45);
+        for y in 0..45 {
+            for x in 0..70 {
+                layer.set_pixel(x, y, RgbaPixel::new(x as u8, y as u8, 200, 255))?;
+            }
+        }
+        let mut mask = PixelData::new_rgba(70, 45);
+        mask.fill(RgbaPixel::new(255, 255, 255, 255));
+        mask.set_pixel(3, 4, RgbaPixel::new(0, 0, 0, 255))?;
+        layer.mask = Some(mask);
+        document.add_layer(layer);
+
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("tiles.psoc");
+        save_project_with_options(
+            &document,
+            &path,
+            &ProjectOptions::default().with_tile_size(16),
+        )?;
+
+        let loaded = load_project(&path)?;
+        let original = &document.layers[0];
+        let restored = &loaded.layers[0];
+        for y in 0..45 {
+            for x in 0..70 {
+                assert_eq!(restored.get_pixel(x, y), original.get_pixel(x, y));
+            }
+        }
+        assert_eq!(
+            restored.mask.as_ref().unwrap().get_pixel(3, 4),
+            Some(RgbaPixel::new(0, 0, 0, 255))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_alpha_channels_roundtrip() -> Result<()> {
+        let mut document = Document::new("Channels".to_string(), 40, 30);
+        document.add_layer(Layer::new_pixel("Layer".to_string(), 40, 30));
+        document.set_selection(psoc_core::Selection::rectangle(5.0, 5.0, 10.0, 10.0));
+        document.save_selection_to_channel("Saved".to_string());
+
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("channels.psoc");
+        save_project_with_options(
+            &document,
+            &path,
+            &ProjectOptions::default().with_tile_size(16),
+        )?;
+
+        let loaded = load_project(&path)?;
+        assert_eq!(loaded.channels.len(), 1);
+        let channel = &loaded.channels[0];
+        assert_eq!(channel.name, "Saved");
+        assert_eq!(channel.id, document.channels[0].id);
+        assert_eq!(channel.dimensions(), (40, 30));
+        assert_eq!(channel.value(7, 7), 255);
+        assert_eq!(channel.value(30, 20), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mask_selection_is_stored_as_tiles() -> Result<()> {
+        let mut document = Document::new("Mask".to_string(), 40, 30);
+        document.add_layer(Layer::new_pixel("Layer".to_string(), 40, 30));
+        let mut mask_data = vec![0u8; 40 * 30];
+        mask_data[7 * 40 + 7] = 255;
+        mask_data[20 * 40 + 30] = 128;
+        let mask = MaskSelection::new(40, 30, mask_data);
+        document.set_selection(Selection::Mask(mask.clone()));
+
+        let mut buffer = Cursor::new(Vec::new());
+        write_project(
+            &document,
+            &mut buffer,
+            &ProjectOptions::default().with_tile_size(16),
+        )?;
+
+        buffer.set_position(0);
+        let mut archive = ProjectArchive::from_reader(buffer)?;
+        // The manifest only references the mask tiles
+        let Selection::Mask(skeleton) = &archive.project().document.selection else {
+            panic!("expected a mask selection in the manifest");
+        };
+        assert!(skeleton.mask_data.is_empty());
+        assert_eq!(
+            archive
+                .project()
+                .selection_tiles
+                .as_ref()
+                .unwrap()
+                .tiles
+                .len(),
+            2
+        );
+
+        let loaded = archive.load_document()?;
+        assert_eq!(loaded.selection, Selection::Mask(mask));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_paths_roundtrip() -> Result<()> {
+        let mut document = Document::new("Paths".to_string(), 40, 30);
+        document.add_layer(Layer::new_pixel("Layer".to_string(), 40, 30));
+        document
+            .active_path_or_new()
+            .subpaths
+            .push(psoc_core::Subpath::ellipse(
+                psoc_core::Point::new(20.0, 15.0),
+                8.0,
+                6.0,
+            ));
+
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("paths.psoc");
+        save_project(&document, &path)?;
+
+        let loaded = load_project(&path)?;
+        assert_eq!(loaded.paths, document.paths);
+        assert_eq!(loaded.active_path, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hdr_layer_roundtrip() -> Result<()> {
+        let hdr = HdrPixelData::new(2, 1, vec![12.5, 3.0, 0.5, 1.0, 0.0, 0.25, 1.0, 0.5])?;
+        let mut document = Document::new("HDR".to_string(), 2, 1);
+        let mut layer = Layer::from_hdr(
+            "Render".to_string(),
+            hdr,
+            &psoc_core::ToneMapping::default(),
+        )?;
+        // An edit made before saving is kept in the HDR pixels
+        layer.set_pixel(1, 0, RgbaPixel::new(0, 0, 0, 255))?;
+        document.add_layer(layer);
+
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("hdr.psoc");
+        save_project(&document, &path)?;
+
+        let loaded = load_project(&path)?;
+        let layer = &loaded.layers[0];
+        assert!(layer.is_hdr());
+        assert_eq!(layer.hdr_pixel(0, 0), Some([12.5, 3.0, 0.5, 1.0]));
+        assert_eq!(layer.hdr_pixel(1, 0), Some([0.0, 0.0, 0.0, 1.0]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_transparent_tiles_are_not_stored() -> Result<()> {
+        let mut document = Document::new("Sparse".to_string(), 64, 64);
+        let mut layer = Layer::new_pixel("Dot".to_string(), 64, 64);
+        layer.set_pixel(40, 40, RgbaPixel::new(255, 0, 0, 255))?;
+        document.add_layer(layer);
+
+        let mut buffer = Cursor::new(Vec::new());
+        write_project(
+            &document,
+            &mut buffer,
+            &ProjectOptions::default().with_tile_size(32),
+        )?;
+
+        buffer.set_position(0);
+        let archive = ProjectArchive::from_reader(buffer)?;
+        let grid = archive.project().layer_tiles[0].pixels.as_ref().unwrap();
+        assert_eq!(grid.tiles.len(), 1);
+        assert_eq!((grid.tiles[0].column, grid.tiles[0].row), (1, 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_archive_loads_single_layers() -> Result<()> {
+        let mut document = Document::new("Archive".to_string(), 32, 32);
+        let mut layer = Layer::new_pixel("Red".to_string(), 32, 32);
+        layer.fill(RgbaPixel::new(255, 0, 0, 255));
+        document.add_layer(layer);
+
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("archive.psoc");
+        save_project(&document, &path)?;
+        assert!(is_project_archive(&path)?);
+
+        let mut archive = ProjectArchive::open(&path)?;
+        assert_eq!(archive.project().version, PROJECT_FORMAT_VERSION);
+        assert_eq!(archive.layer_count(), 1);
+        // The manifest never carries pixel data
+        assert!(archive.project().document.layers[0].pixel_data.is_none());
+
+        let layer = archive.load_layer(0)?;
+        assert_eq!(layer.get_pixel(0, 0), Some(RgbaPixel::new(255, 0, 0, 255)));
+        assert!(archive.load_layer(1).is_err());
+
+        let thumbnail = load_project_thumbnail(&path)?.unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (32, 32));
+
+        Ok(())
+    }
+
+    /// Three 64x64 layers in distinct colors, only the first one visible
+    fn three_layer_document() -> Document {
+        let mut document = Document::new("Lazy".to_string(), 64, 64);
+        for (index, color) in [
+            RgbaPixel::new(255, 0, 0, 255),
+            RgbaPixel::new(0, 255, 0, 255),
+            RgbaPixel::new(0, 0, 255, 255),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut layer = Layer::new_pixel(format!("Layer {}", index), 64, 64);
+            layer.fill(color);
+            layer.visible = index == 0;
+            document.add_layer(layer);
+        }
+        document
+    }
+
+    #[test]
+    fn test_open_project_decodes_layers_on_first_use() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("lazy.psoc");
+        save_project(&three_layer_document(), &path)?;
+
+        let mut document = open_project(&path)?;
+        assert_eq!(document.layers.len(), 3);
+        for layer in &document.layers {
+            assert!(layer.is_pending());
+            assert!(layer.pixel_data.is_none());
+        }
+
+        // Only the visible layer is in use
+        assert!(document.load_layers_in_use()?);
+        assert_eq!(
+            document.layers[0].get_pixel(10, 10),
+            Some(RgbaPixel::new(255, 0, 0, 255))
+        );
+        assert!(document.layers[1].is_pending());
+        assert!(document.layers[2].is_pending());
+
+        assert!(document.load_layer_pixels(2)?);
+        assert_eq!(
+            document.layers[2].get_pixel(10, 10),
+            Some(RgbaPixel::new(0, 0, 255, 255))
+        );
+        assert!(document.layers[1].is_pending());
+        assert!(document.layers[1].pixel_data.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_project_does_not_decode_untouched_layers() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("budget.psoc");
+        save_project(&three_layer_document(), &path)?;
+
+        // Room for one decoded layer at a time, not all three
+        let limits = DecodeLimits::default()
+            .with_max_memory(2 * 64 * 64 * 4)
+            .with_max_decode_time(None);
+        assert!(load_project_with_limits(&path, &limits).is_err());
+
+        let mut document = open_project_with_limits(&path, &limits)?;
+        for index in 0..3 {
+            assert!(document.load_layer_pixels(index)?);
+        }
+        assert!(!document.has_pending_layers());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pending_layers_survive_saving_over_their_file() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("in_place.psoc");
+        save_project(&three_layer_document(), &path)?;
+
+        let mut document = open_project(&path)?;
+        document.layers[0].name = "Renamed".to_string();
+        save_project(&document, &path)?;
+
+        let reloaded = load_project(&path)?;
+        assert_eq!(reloaded.layers[0].name, "Renamed");
+        assert_eq!(
+            reloaded.layers[1].get_pixel(63, 63),
+            Some(RgbaPixel::new(0, 255, 0, 255))
+        );
+        // The open document still reads from the file it was opened from
+        assert!(document.load_pending_layers()?);
+        assert_eq!(
+            document.layers[2].get_pixel(0, 0),
+            Some(RgbaPixel::new(0, 0, 255, 255))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_ron_project_is_readable() -> Result<()> {
+        let mut document = Document::new("Legacy".to_string(), 8, 8);
+        let mut layer = Layer::new_pixel("Blue".to_string(), 8, 8);
+        layer.fill(RgbaPixel::new(0, 0, 255, 255));
+        document.add_layer(layer);
+
+        let mut project_file = ProjectFile::new(document);
+        project_file.version = LEGACY_PROJECT_FORMAT_VERSION.to_string();
+        let content = ron::ser::to_string_pretty(&project_file, ron::ser::PrettyConfig::default())?;
+
+        let temp_dir = tempdir()?;
+        let path = temp_dir.path().join("legacy.psoc");
+        std::fs::write(&path, content)?;
+
+        assert!(!is_project_archive(&path)?);
+        assert!(load_project_thumbnail(&path)?.is_none());
+
+        let loaded = load_project(&path)?;
+        assert_eq!(loaded.metadata.title, "Legacy");
+        assert_eq!(
+            loaded.layers[0].get_pixel(7, 7),
+            Some(RgbaPixel::new(0, 0, 255, 255))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_archive_is_much_smaller_than_ron() -> Result<()> {
+        let mut document = Document::new("Size".to_string(), 256, 256);
+        let mut layer = Layer::new_pixel("Fill".to_string(), 256, 256);
+        layer.fill(RgbaPixel::new(10, 20, 30, 255));
+        document.add_layer(layer);
+
+        let mut buffer = Cursor::new(Vec::new());
+        write_project(&document, &mut buffer, &ProjectOptions::default())?;
+
+        let ron_size = ron::ser::to_string_pretty(
+            &ProjectFile::new(document),
+            ron::ser::PrettyConfig::default(),
+        )?
+        .len();
+        assert!(buffer.get_ref().len() * 10 < ron_size);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_corrupt_tile_is_rejected() -> Result<()> {
+        let mut document = Document::new("Corrupt".to_string(), 16, 16);
+        let mut layer = Layer::new_pixel("Fill".to_string(), 16, 16);
+        layer.fill(RgbaPixel::new(1, 2, 3, 4));
+        document.add_layer(layer);
+
+        let mut buffer = Cursor::new(Vec::new());
+        write_project(&document, &mut buffer, &ProjectOptions::default())?;
+        buffer.set_position(0);
+        let mut archive = ProjectArchive::from_reader(buffer)?;
+
+        // Point the tile index at an entry of the wrong length
+        archive.project.layer_tiles[0]
+            .pixels
+            .as_mut()
+            .unwrap()
+            .tiles[0]
+            .entry = "mimetype".to_string();
+        assert!(archive.load_layer(0).is_err());
+
+        Ok(())
+    }
 }
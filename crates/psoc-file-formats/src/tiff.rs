@@ -0,0 +1,559 @@
+//! TIFF format support
+//!
+//! This module provides TIFF image loading and saving functionality with
+//! LZW/Deflate compression, 16-bit output, ICC profile and EXIF/XMP/IPTC
+//! metadata support.
+
+use crate::limits::DecodeLimits;
+use crate::metadata::{self, PreparedMetadata};
+use ::tiff::encoder::{
+    colortype, compression::DeflateLevel, Compression, DirectoryEncoder, Predictor, TiffEncoder,
+    TiffKind,
+};
+use ::tiff::tags::{Tag, Type};
+use ::tiff::Directory;
+use anyhow::{Context, Result};
+use exif::{Field, Value};
+use psoc_core::{ColorManager, EmbeddedMetadata, IccProfile};
+use std::fs::File;
+use std::io::{BufWriter, Seek, Write};
+use std::path::Path;
+use tracing::{debug, instrument, warn};
+
+/// TIFF loading result with optional ICC profile
+#[derive(Debug)]
+pub struct TiffLoadResult {
+    /// The loaded image
+    pub image: image::DynamicImage,
+    /// Embedded ICC profile, if any
+    pub icc_profile: Option<IccProfile>,
+    /// Embedded EXIF/XMP/IPTC metadata
+    pub metadata: EmbeddedMetadata,
+}
+
+/// Load a TIFF image from a file path
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_tiff<P: AsRef<Path>>(path: P) -> Result<image::DynamicImage> {
+    let result = load_tiff_with_profile(path)?;
+    Ok(result.image)
+}
+
+/// Load a TIFF image with ICC profile from a file path
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_tiff_with_profile<P: AsRef<Path>>(path: P) -> Result<TiffLoadResult> {
+    load_tiff_with_limits(path, &DecodeLimits::default())
+}
+
+/// Load a TIFF image with ICC profile, rejecting files over the given limits
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_tiff_with_limits<P: AsRef<Path>>(
+    path: P,
+    limits: &DecodeLimits,
+) -> Result<TiffLoadResult> {
+    let path = path.as_ref();
+    debug!("Loading TIFF image with profile from: {}", path.display());
+
+    // Decode with metadata, rotating the pixels according to the EXIF orientation
+    let decoded = metadata::decode_image(path, "TIFF", limits)?;
+    let image = decoded.image;
+    let mut embedded = decoded.metadata;
+
+    // The TIFF decoder does not expose EXIF or IPTC, so read them from the IFD directly
+    match metadata::read_tiff_metadata(path) {
+        Ok(tiff_metadata) => {
+            embedded.exif = tiff_metadata.exif;
+            embedded.iptc = tiff_metadata.iptc;
+            if embedded.xmp.is_none() {
+                embedded.xmp = tiff_metadata.xmp;
+            }
+        }
+        Err(e) => warn!("Failed to read TIFF metadata: {}", e),
+    }
+
+    let icc_profile = match decoded.icc_profile {
+        Some(data) => {
+            debug!("Found embedded TIFF ICC profile with {} bytes", data.len());
+            let mut color_manager =
+                ColorManager::new().context("Failed to create color manager")?;
+            Some(
+                color_manager
+                    .load_profile_from_data(&data, "TIFF Embedded Profile".to_string())
+                    .context("Failed to load ICC profile from data")?,
+            )
+        }
+        None => None,
+    };
+
+    Ok(TiffLoadResult {
+        image,
+        icc_profile,
+        metadata: embedded,
+    })
+}
+
+/// Save a TIFF image to a file path with default options
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn save_tiff<P: AsRef<Path>>(image: &image::DynamicImage, path: P) -> Result<()> {
+    save_tiff_with_options(image, path, &TiffOptions::default())
+}
+
+/// TIFF compression method
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TiffCompression {
+    /// No compression
+    None,
+    /// Lempel-Ziv-Welch compression (widely supported)
+    Lzw,
+    /// Deflate (zlib) compression with level 1-9
+    Deflate(u8),
+}
+
+impl TiffCompression {
+    /// Convert to the encoder compression setting
+    fn to_encoder_compression(self) -> Compression {
+        match self {
+            Self::None => Compression::Uncompressed,
+            Self::Lzw => Compression::Lzw,
+            Self::Deflate(level) => Compression::Deflate(match level {
+                0..=3 => DeflateLevel::Fast,
+                4..=7 => DeflateLevel::Balanced,
+                _ => DeflateLevel::Best,
+            }),
+        }
+    }
+
+    /// Whether horizontal differencing should be applied before compression
+    fn uses_predictor(self) -> bool {
+        !matches!(self, Self::None)
+    }
+}
+
+/// TIFF sample bit depth
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TiffBitDepth {
+    /// 8 bits per channel
+    Eight,
+    /// 16 bits per channel
+    Sixteen,
+}
+
+/// TIFF-specific configuration options
+#[derive(Debug, Clone)]
+pub struct TiffOptions {
+    /// Compression method
+    pub compression: TiffCompression,
+    /// Bits per channel
+    pub bit_depth: TiffBitDepth,
+    /// ICC profile to embed (optional)
+    pub icc_profile: Option<IccProfile>,
+    /// EXIF/XMP/IPTC metadata to embed (optional)
+    pub metadata: Option<EmbeddedMetadata>,
+    /// Remove GPS location, serial numbers and other private fields from the metadata
+    pub strip_private_metadata: bool,
+}
+
+impl Default for TiffOptions {
+    fn default() -> Self {
+        Self {
+            compression: TiffCompression::Lzw,
+            bit_depth: TiffBitDepth::Eight,
+            icc_profile: None,
+            metadata: None,
+            strip_private_metadata: false,
+        }
+    }
+}
+
+impl TiffOptions {
+    /// Create archival TIFF options (16-bit, maximum Deflate compression)
+    pub fn archival() -> Self {
+        Self {
+            compression: TiffCompression::Deflate(9),
+            bit_depth: TiffBitDepth::Sixteen,
+            icc_profile: None,
+            metadata: None,
+            strip_private_metadata: false,
+        }
+    }
+
+    /// Create uncompressed TIFF options for maximum compatibility
+    pub fn uncompressed() -> Self {
+        Self {
+            compression: TiffCompression::None,
+            bit_depth: TiffBitDepth::Eight,
+            icc_profile: None,
+            metadata: None,
+            strip_private_metadata: false,
+        }
+    }
+
+    /// Set the compression method
+    pub fn with_compression(mut self, compression: TiffCompression) -> Self {
+        self.compression = match compression {
+            TiffCompression::Deflate(level) => TiffCompression::Deflate(level.clamp(1, 9)),
+            other => other,
+        };
+        self
+    }
+}
+
+/// Save a TIFF image with specific options
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn save_tiff_with_options<P: AsRef<Path>>(
+    image: &image::DynamicImage,
+    path: P,
+    options: &TiffOptions,
+) -> Result<()> {
+    let path = path.as_ref();
+    debug!("Saving TIFF image with options to: {}", path.display());
+
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create TIFF file: {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+
+    write_tiff(image, &mut writer, options)
+        .with_context(|| format!("Failed to save TIFF image to: {}", path.display()))?;
+
+    writer
+        .flush()
+        .with_context(|| format!("Failed to flush TIFF file: {}", path.display()))?;
+
+    Ok(())
+}
+
+/// Encode a TIFF image with specific options to any seekable writer
+pub fn write_tiff<W: Write + Seek>(
+    image: &image::DynamicImage,
+    writer: W,
+    options: &TiffOptions,
+) -> Result<()> {
+    let compression = options.compression;
+    let predictor = if compression.uses_predictor() {
+        Predictor::Horizontal
+    } else {
+        Predictor::None
+    };
+
+    let mut encoder = TiffEncoder::new(writer)
+        .context("Failed to create TIFF encoder")?
+        .with_compression(compression.to_encoder_compression())
+        .with_predictor(predictor);
+
+    let icc_data = options
+        .icc_profile
+        .as_ref()
+        .and_then(|profile| profile.raw_data.as_deref());
+    if options.icc_profile.is_some() && icc_data.is_none() {
+        warn!("ICC profile has no raw data, saving TIFF without profile");
+    }
+
+    let (width, height) = (image.width(), image.height());
+    let has_alpha = image.color().has_alpha();
+
+    // EXIF and GPS directories must be written before the image directory that points to them
+    let prepared = options.metadata.as_ref().map(|embedded| {
+        PreparedMetadata::new(embedded, options.strip_private_metadata, (width, height))
+    });
+    let exif_directories = match prepared {
+        Some(ref prepared) => ExifDirectories {
+            exif: write_exif_directory(&mut encoder, prepared, exif::Context::Exif)?,
+            gps: write_exif_directory(&mut encoder, prepared, exif::Context::Gps)?,
+        },
+        None => ExifDirectories::default(),
+    };
+
+    match (options.bit_depth, has_alpha) {
+        (TiffBitDepth::Eight, true) => {
+            let data = image.to_rgba8();
+            let mut tiff_image = encoder.new_image::<colortype::RGBA8>(width, height)?;
+            write_directory_tags(
+                tiff_image.encoder(),
+                icc_data,
+                prepared.as_ref(),
+                &exif_directories,
+            )?;
+            tiff_image.write_data(data.as_raw())?;
+        }
+        (TiffBitDepth::Eight, false) => {
+            let data = image.to_rgb8();
+            let mut tiff_image = encoder.new_image::<colortype::RGB8>(width, height)?;
+            write_directory_tags(
+                tiff_image.encoder(),
+                icc_data,
+                prepared.as_ref(),
+                &exif_directories,
+            )?;
+            tiff_image.write_data(data.as_raw())?;
+        }
+        (TiffBitDepth::Sixteen, true) => {
+            let data = image.to_rgba16();
+            let mut tiff_image = encoder.new_image::<colortype::RGBA16>(width, height)?;
+            write_directory_tags(
+                tiff_image.encoder(),
+                icc_data,
+                prepared.as_ref(),
+                &exif_directories,
+            )?;
+            tiff_image.write_data(data.as_raw())?;
+        }
+        (TiffBitDepth::Sixteen, false) => {
+            let data = image.to_rgb16();
+            let mut tiff_image = encoder.new_image::<colortype::RGB16>(width, height)?;
+            write_directory_tags(
+                tiff_image.encoder(),
+                icc_data,
+                prepared.as_ref(),
+                &exif_directories,
+            )?;
+            tiff_image.write_data(data.as_raw())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Offsets of EXIF sub-directories referenced from the image directory
+#[derive(Debug, Default)]
+struct ExifDirectories {
+    exif: Option<u32>,
+    gps: Option<u32>,
+}
+
+/// Write the EXIF fields of one context as a standalone directory
+fn write_exif_directory<W: Write + Seek>(
+    encoder: &mut TiffEncoder<W>,
+    prepared: &PreparedMetadata,
+    context: exif::Context,
+) -> Result<Option<u32>> {
+    let fields: Vec<_> = prepared
+        .exif_fields
+        .iter()
+        .filter(|field| field.tag.context() == context)
+        .collect();
+    if fields.is_empty() {
+        return Ok(None);
+    }
+
+    let mut directory = encoder.extra_directory()?;
+    for field in fields {
+        write_exif_field(&mut directory, field)?;
+    }
+    Ok(Some(directory.finish_with_offsets()?.offset))
+}
+
+/// Write the ICC profile and metadata tags of the image directory
+fn write_directory_tags<W: Write + Seek, K: TiffKind>(
+    directory: &mut DirectoryEncoder<'_, W, K>,
+    icc_data: Option<&[u8]>,
+    prepared: Option<&PreparedMetadata>,
+    exif_directories: &ExifDirectories,
+) -> Result<()> {
+    if let Some(icc) = icc_data {
+        directory.write_tag(Tag::IccProfile, icc)?;
+    }
+
+    let Some(prepared) = prepared else {
+        return Ok(());
+    };
+
+    for field in prepared
+        .exif_fields
+        .iter()
+        .filter(|field| field.tag.context() == exif::Context::Tiff)
+    {
+        write_exif_field(directory, field)?;
+    }
+    if let Some(offset) = exif_directories.exif {
+        directory.write_tag(Tag::ExifDirectory, offset)?;
+    }
+    if let Some(offset) = exif_directories.gps {
+        directory.write_tag(Tag::GpsDirectory, offset)?;
+    }
+    if let Some(ref xmp) = prepared.xmp {
+        directory.write_tag(Tag::Unknown(TIFF_XMP_TAG), xmp.as_bytes())?;
+    }
+    if let Some(ref iptc) = prepared.iptc {
+        write_raw_tag(
+            directory,
+            Tag::Unknown(TIFF_IPTC_TAG),
+            Type::UNDEFINED,
+            iptc,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// TIFF tag holding the XMP packet
+const TIFF_XMP_TAG: u16 = 700;
+/// TIFF tag holding IPTC-IIM records
+const TIFF_IPTC_TAG: u16 = 33723;
+
+/// Write an EXIF field as a TIFF tag, keeping its value type
+fn write_exif_field<W: Write + Seek, K: TiffKind>(
+    directory: &mut DirectoryEncoder<'_, W, K>,
+    field: &Field,
+) -> Result<()> {
+    let tag = Tag::from_u16_exhaustive(field.tag.number());
+
+    match field.value {
+        Value::Byte(ref values) => directory.write_tag(tag, values.as_slice())?,
+        Value::Ascii(ref strings) => {
+            let text: Vec<_> = strings.iter().map(|s| String::from_utf8_lossy(s)).collect();
+            directory.write_tag(tag, text.join("\0").as_str())?;
+        }
+        Value::Short(ref values) => directory.write_tag(tag, values.as_slice())?,
+        Value::Long(ref values) => directory.write_tag(tag, values.as_slice())?,
+        Value::SByte(ref values) => directory.write_tag(tag, values.as_slice())?,
+        Value::SShort(ref values) => directory.write_tag(tag, values.as_slice())?,
+        Value::SLong(ref values) => directory.write_tag(tag, values.as_slice())?,
+        Value::Float(ref values) => directory.write_tag(tag, values.as_slice())?,
+        Value::Double(ref values) => directory.write_tag(tag, values.as_slice())?,
+        Value::Rational(ref values) => {
+            let bytes: Vec<u8> = values
+                .iter()
+                .flat_map(|r| [r.num, r.denom])
+                .flat_map(u32::to_ne_bytes)
+                .collect();
+            write_raw_tag(directory, tag, Type::RATIONAL, &bytes)?;
+        }
+        Value::SRational(ref values) => {
+            let bytes: Vec<u8> = values
+                .iter()
+                .flat_map(|r| [r.num, r.denom])
+                .flat_map(i32::to_ne_bytes)
+                .collect();
+            write_raw_tag(directory, tag, Type::SRATIONAL, &bytes)?;
+        }
+        Value::Undefined(ref bytes, _) => {
+            write_raw_tag(directory, tag, Type::UNDEFINED, bytes)?;
+        }
+        Value::Unknown(..) => debug!("Skipping EXIF field {} with unknown type", field.tag),
+    }
+
+    Ok(())
+}
+
+/// Write a tag from bytes already in the file's (native) byte order
+fn write_raw_tag<W: Write + Seek, K: TiffKind>(
+    directory: &mut DirectoryEncoder<'_, W, K>,
+    tag: Tag,
+    ty: Type,
+    bytes: &[u8],
+) -> Result<()> {
+    let entry = directory.write_entry_bytes(ty, bytes)?;
+    let mut entries = Directory::empty();
+    entries.extend([(tag, entry)]);
+    directory.extend_from(&entries);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb, Rgba};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_save_and_load_tiff() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test.tiff");
+
+        let img: ImageBuffer<Rgb<u8>, Vec<u8>> =
+            ImageBuffer::from_fn(64, 32, |x, y| Rgb([x as u8, y as u8, 128]));
+        let dynamic_img = image::DynamicImage::ImageRgb8(img);
+
+        save_tiff(&dynamic_img, &file_path)?;
+        assert!(file_path.exists());
+
+        let loaded_img = load_tiff(&file_path)?;
+        assert_eq!(loaded_img.width(), 64);
+        assert_eq!(loaded_img.height(), 32);
+        assert_eq!(loaded_img.to_rgb8(), dynamic_img.to_rgb8());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tiff_options_default() {
+        let options = TiffOptions::default();
+        assert_eq!(options.compression, TiffCompression::Lzw);
+        assert_eq!(options.bit_depth, TiffBitDepth::Eight);
+        assert!(options.icc_profile.is_none());
+    }
+
+    #[test]
+    fn test_tiff_options_presets() {
+        let archival = TiffOptions::archival();
+        assert_eq!(archival.compression, TiffCompression::Deflate(9));
+        assert_eq!(archival.bit_depth, TiffBitDepth::Sixteen);
+
+        let uncompressed = TiffOptions::uncompressed();
+        assert_eq!(uncompressed.compression, TiffCompression::None);
+    }
+
+    #[test]
+    fn test_deflate_level_clamping() {
+        let options = TiffOptions::default().with_compression(TiffCompression::Deflate(20));
+        assert_eq!(options.compression, TiffCompression::Deflate(9));
+
+        let options = TiffOptions::default().with_compression(TiffCompression::Deflate(0));
+        assert_eq!(options.compression, TiffCompression::Deflate(1));
+    }
+
+    #[test]
+    fn test_save_tiff_16_bit_with_alpha() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_16bit.tif");
+
+        let img: ImageBuffer<Rgba<u8>, Vec<u8>> =
+            ImageBuffer::from_fn(16, 16, |x, y| Rgba([x as u8 * 16, y as u8 * 16, 0, 200]));
+        let dynamic_img = image::DynamicImage::ImageRgba8(img);
+
+        save_tiff_with_options(&dynamic_img, &file_path, &TiffOptions::archival())?;
+
+        let loaded_img = load_tiff(&file_path)?;
+        assert_eq!(loaded_img.color(), image::ColorType::Rgba16);
+        assert_eq!(loaded_img.to_rgba8(), dynamic_img.to_rgba8());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compression_reduces_size() -> Result<()> {
+        let img: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::new(128, 128);
+        let dynamic_img = image::DynamicImage::ImageRgb8(img);
+
+        let mut uncompressed = std::io::Cursor::new(Vec::new());
+        write_tiff(
+            &dynamic_img,
+            &mut uncompressed,
+            &TiffOptions::uncompressed(),
+        )?;
+
+        for compression in [TiffCompression::Lzw, TiffCompression::Deflate(6)] {
+            let mut compressed = std::io::Cursor::new(Vec::new());
+            let options = TiffOptions::default().with_compression(compression);
+            write_tiff(&dynamic_img, &mut compressed, &options)?;
+            assert!(compressed.get_ref().len() < uncompressed.get_ref().len());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_tiff_with_profile_fallback() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_no_profile.tif");
+
+        let img: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::new(20, 20);
+        let dynamic_img = image::DynamicImage::ImageRgb8(img);
+        save_tiff(&dynamic_img, &file_path)?;
+
+        let result = load_tiff_with_profile(&file_path)?;
+        assert_eq!(result.image.width(), 20);
+        assert!(result.icc_profile.is_none());
+
+        Ok(())
+    }
+}
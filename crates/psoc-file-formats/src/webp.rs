@@ -0,0 +1,385 @@
+//! WebP format support
+//!
+//! This module provides WebP image loading and saving functionality with
+//! lossless and lossy (quality-controlled) encoding. ICC profiles are embedded
+//! in both; lossy output is wrapped in an extended (VP8X) container for it.
+
+use crate::limits::DecodeLimits;
+use crate::metadata;
+use anyhow::{Context, Result};
+use image::ImageEncoder;
+use psoc_core::{ColorManager, EmbeddedMetadata, IccProfile};
+use std::path::Path;
+use tracing::{debug, instrument};
+
+/// WebP loading result with optional ICC profile
+#[derive(Debug)]
+pub struct WebpLoadResult {
+    /// The loaded image
+    pub image: image::DynamicImage,
+    /// Embedded ICC profile, if any
+    pub icc_profile: Option<IccProfile>,
+    /// Embedded EXIF/XMP metadata
+    pub metadata: EmbeddedMetadata,
+}
+
+/// Load a WebP image from a file path
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_webp<P: AsRef<Path>>(path: P) -> Result<image::DynamicImage> {
+    let result = load_webp_with_profile(path)?;
+    Ok(result.image)
+}
+
+/// Load a WebP image with ICC profile from a file path
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_webp_with_profile<P: AsRef<Path>>(path: P) -> Result<WebpLoadResult> {
+    load_webp_with_limits(path, &DecodeLimits::default())
+}
+
+/// Load a WebP image with ICC profile, rejecting files over the given limits
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn load_webp_with_limits<P: AsRef<Path>>(
+    path: P,
+    limits: &DecodeLimits,
+) -> Result<WebpLoadResult> {
+    let path = path.as_ref();
+    debug!("Loading WebP image with profile from: {}", path.display());
+
+    // Decode with metadata, rotating the pixels according to the EXIF orientation
+    let decoded = metadata::decode_image(path, "WebP", limits)?;
+    let image = decoded.image;
+    let embedded = decoded.metadata;
+
+    let icc_profile = match decoded.icc_profile {
+        Some(data) => {
+            debug!("Found embedded WebP ICC profile with {} bytes", data.len());
+            let mut color_manager =
+                ColorManager::new().context("Failed to create color manager")?;
+            Some(
+                color_manager
+                    .load_profile_from_data(&data, "WebP Embedded Profile".to_string())
+                    .context("Failed to load ICC profile from data")?,
+            )
+        }
+        None => None,
+    };
+
+    Ok(WebpLoadResult {
+        image,
+        icc_profile,
+        metadata: embedded,
+    })
+}
+
+/// Save a WebP image to a file path with default options
+#[instrument(skip_all, fields(path = %path.as_ref().display()))]
+pub fn save_webp<P: AsRef<Path>>(image: &image::DynamicImage, path: P) -> Result<()> {
+    save_webp_with_options(image, path, &WebpOptions::default())
+}
+
+/// WebP-specific configuration options
+#[derive(Debug, Clone)]
+pub struct WebpOptions {
+    /// Whether to use lossless (VP8L) encoding
+    pub lossless: bool,
+    /// Quality level for lossy encoding (0-100, where 100 is maximum quality)
+    pub quality: u8,
+    /// ICC profile to embed (optional)
+    pub icc_profile: Option<IccProfile>,
+}
+
+impl Default for WebpOptions {
+    fn default() -> Self {
+        Self {
+            lossless: false,
+            quality: 80, // Visually close to the source at a fraction of PNG size
+            icc_profile: None,
+        }
+    }
+}
+
+impl WebpOptions {
+    /// Create lossless WebP options
+    pub fn lossless() -> Self {
+        Self {
+            lossless: true,
+            quality: 100,
+            icc_profile: None,
+        }
+    }
+
+    /// Create lossy WebP options with the given quality
+    pub fn lossy(quality: u8) -> Self {
+        Self::default().with_quality(quality)
+    }
+
+    /// Validate quality value
+    pub fn with_quality(mut self, quality: u8) -> Self {
+        self.quality = quality.min(100);
+        self
+    }
+}
+
+/// Save a WebP image with specific options
+#[instrument(skip_all, fields(path = %path.as_ref().display(), lossless = options.lossless))]
+pub fn save_webp_with_options<P: AsRef<Path>>(
+    image: &image::DynamicImage,
+    path: P,
+    options: &WebpOptions,
+) -> Result<()> {
+    let path = path.as_ref();
+    debug!("Saving WebP image with options to: {}", path.display());
+
+    let data = encode_webp(image, options)?;
+    std::fs::write(path, data)
+        .with_context(|| format!("Failed to save WebP image to: {}", path.display()))?;
+
+    Ok(())
+}
+
+/// Encode a WebP image with specific options into memory
+pub fn encode_webp(image: &image::DynamicImage, options: &WebpOptions) -> Result<Vec<u8>> {
+    let has_alpha = image.color().has_alpha();
+
+    if options.lossless {
+        let mut buffer = Vec::new();
+        let mut encoder = image::codecs::webp::WebPEncoder::new_lossless(&mut buffer);
+
+        if let Some(icc) = options
+            .icc_profile
+            .as_ref()
+            .and_then(|profile| profile.raw_data.clone())
+        {
+            encoder
+                .set_icc_profile(icc)
+                .map_err(|e| anyhow::anyhow!("Failed to embed ICC profile: {}", e))?;
+        }
+
+        if has_alpha {
+            let rgba = image.to_rgba8();
+            encoder
+                .write_image(
+                    rgba.as_raw(),
+                    rgba.width(),
+                    rgba.height(),
+                    image::ExtendedColorType::Rgba8,
+                )
+                .context("Failed to encode lossless WebP")?;
+        } else {
+            let rgb = image.to_rgb8();
+            encoder
+                .write_image(
+                    rgb.as_raw(),
+                    rgb.width(),
+                    rgb.height(),
+                    image::ExtendedColorType::Rgb8,
+                )
+                .context("Failed to encode lossless WebP")?;
+        }
+
+        return Ok(buffer);
+    }
+
+    let quality = options.quality.min(100) as f32;
+    let memory = if has_alpha {
+        let rgba = image.to_rgba8();
+        ::webp::Encoder::from_rgba(rgba.as_raw(), rgba.width(), rgba.height())
+            .encode_simple(false, quality)
+    } else {
+        let rgb = image.to_rgb8();
+        ::webp::Encoder::from_rgb(rgb.as_raw(), rgb.width(), rgb.height())
+            .encode_simple(false, quality)
+    }
+    .map_err(|e| anyhow::anyhow!("Failed to encode lossy WebP: {:?}", e))?;
+
+    match options
+        .icc_profile
+        .as_ref()
+        .and_then(|profile| profile.raw_data.as_deref())
+    {
+        Some(icc) => embed_icc_profile(&memory, icc, image.width(), image.height()),
+        None => Ok(memory.to_vec()),
+    }
+}
+
+/// VP8X flag marking an ICCP chunk
+const VP8X_ICC_FLAG: u8 = 0x20;
+/// VP8X flag marking alpha
+const VP8X_ALPHA_FLAG: u8 = 0x10;
+
+/// Append a RIFF chunk, padded to an even length
+fn push_chunk(buffer: &mut Vec<u8>, fourcc: &[u8; 4], payload: &[u8]) {
+    buffer.extend_from_slice(fourcc);
+    buffer.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(payload);
+    if payload.len() % 2 == 1 {
+        buffer.push(0);
+    }
+}
+
+/// Insert an ICCP chunk into an encoded WebP file
+///
+/// Simple files are converted to the extended format, whose VP8X header
+/// must come first and announce the profile.
+fn embed_icc_profile(webp: &[u8], icc: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+    if webp.len() < 12 || &webp[0..4] != b"RIFF" || &webp[8..12] != b"WEBP" {
+        return Err(anyhow::anyhow!("Encoded WebP has no RIFF header"));
+    }
+
+    // Split the body into chunks
+    let mut chunks = Vec::new();
+    let mut offset = 12;
+    while offset + 8 <= webp.len() {
+        let fourcc: [u8; 4] = webp[offset..offset + 4].try_into()?;
+        let size = u32::from_le_bytes(webp[offset + 4..offset + 8].try_into()?) as usize;
+        let end = offset + 8 + size;
+        if end > webp.len() {
+            return Err(anyhow::anyhow!("Encoded WebP chunk overruns the file"));
+        }
+        chunks.push((fourcc, &webp[offset + 8..end]));
+        offset = end + size % 2;
+    }
+
+    let mut header = match chunks.first() {
+        Some(([b'V', b'P', b'8', b'X'], payload)) => {
+            let header = payload.to_vec();
+            chunks.remove(0);
+            header
+        }
+        _ => {
+            let has_alpha = chunks
+                .iter()
+                .any(|(fourcc, _)| fourcc == b"ALPH" || fourcc == b"VP8L");
+            let mut header = vec![if has_alpha { VP8X_ALPHA_FLAG } else { 0 }, 0, 0, 0];
+            header.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+            header.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+            header
+        }
+    };
+    header[0] |= VP8X_ICC_FLAG;
+
+    let mut body = b"WEBP".to_vec();
+    push_chunk(&mut body, b"VP8X", &header);
+    push_chunk(&mut body, b"ICCP", icc);
+    for (fourcc, payload) in chunks.iter().filter(|(fourcc, _)| fourcc != b"ICCP") {
+        push_chunk(&mut body, fourcc, payload);
+    }
+
+    let mut output = b"RIFF".to_vec();
+    output.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    output.extend_from_slice(&body);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb, Rgba};
+    use tempfile::tempdir;
+
+    fn gradient_image() -> image::DynamicImage {
+        let img: ImageBuffer<Rgb<u8>, Vec<u8>> =
+            ImageBuffer::from_fn(64, 64, |x, y| Rgb([x as u8 * 4, y as u8 * 4, 96]));
+        image::DynamicImage::ImageRgb8(img)
+    }
+
+    #[test]
+    fn test_save_and_load_lossless_webp() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test.webp");
+
+        let img: ImageBuffer<Rgba<u8>, Vec<u8>> =
+            ImageBuffer::from_fn(32, 16, |x, y| Rgba([x as u8, y as u8, 7, 128]));
+        let dynamic_img = image::DynamicImage::ImageRgba8(img);
+
+        save_webp_with_options(&dynamic_img, &file_path, &WebpOptions::lossless())?;
+        assert!(file_path.exists());
+
+        let loaded_img = load_webp(&file_path)?;
+        assert_eq!(loaded_img.width(), 32);
+        assert_eq!(loaded_img.height(), 16);
+        assert_eq!(loaded_img.to_rgba8(), dynamic_img.to_rgba8());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_and_load_lossy_webp() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let file_path = temp_dir.path().join("test_lossy.webp");
+
+        save_webp(&gradient_image(), &file_path)?;
+
+        let result = load_webp_with_profile(&file_path)?;
+        assert_eq!(result.image.width(), 64);
+        assert_eq!(result.image.height(), 64);
+        assert!(result.icc_profile.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lossy_webp_keeps_icc_profile() -> Result<()> {
+        let icc = lcms2::Profile::new_srgb()
+            .icc()
+            .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        let profile = IccProfile::from_data(icc.clone(), "Test sRGB".to_string())?;
+        let options = WebpOptions {
+            icc_profile: Some(profile),
+            ..WebpOptions::lossy(70)
+        };
+
+        let translucent = image::DynamicImage::ImageRgba8(ImageBuffer::from_fn(9, 5, |x, y| {
+            Rgba([x as u8 * 20, y as u8 * 40, 50, 128])
+        }));
+        for image in [gradient_image(), translucent] {
+            let temp_dir = tempdir()?;
+            let file_path = temp_dir.path().join("profiled.webp");
+            save_webp_with_options(&image, &file_path, &options)?;
+
+            let result = load_webp_with_profile(&file_path)?;
+            assert_eq!(
+                (result.image.width(), result.image.height()),
+                (image.width(), image.height())
+            );
+            assert_eq!(result.image.color().has_alpha(), image.color().has_alpha());
+            let loaded = result.icc_profile.expect("ICC profile should be embedded");
+            assert_eq!(loaded.raw_data.as_deref(), Some(icc.as_slice()));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_webp_options_default() {
+        let options = WebpOptions::default();
+        assert!(!options.lossless);
+        assert_eq!(options.quality, 80);
+        assert!(options.icc_profile.is_none());
+    }
+
+    #[test]
+    fn test_webp_options_presets() {
+        assert!(WebpOptions::lossless().lossless);
+
+        let lossy = WebpOptions::lossy(60);
+        assert!(!lossy.lossless);
+        assert_eq!(lossy.quality, 60);
+    }
+
+    #[test]
+    fn test_quality_clamping() {
+        let options = WebpOptions::default().with_quality(150);
+        assert_eq!(options.quality, 100);
+    }
+
+    #[test]
+    fn test_lower_quality_produces_smaller_output() -> Result<()> {
+        let image = gradient_image();
+        let high = encode_webp(&image, &WebpOptions::lossy(95))?;
+        let low = encode_webp(&image, &WebpOptions::lossy(10))?;
+        assert!(low.len() < high.len());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+//! Animated image tests
+//!
+//! Animations must import as one layer per frame with their delays, and
+//! selected layers must export back out as frames in every animated format.
+
+use psoc_core::{Document, Layer, PixelData, RgbaPixel};
+use psoc_file_formats::{
+    load_animation_frames, AnimationFrame, AnimationOptions, FileIO, LoopCount,
+};
+use tempfile::tempdir;
+
+const DELAYS_MS: [u32; 3] = [100, 250, 40];
+const COLORS: [[u8; 4]; 3] = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+
+/// Document with one solid-colored layer per frame and a stored delay on each
+fn frame_document() -> Document {
+    let mut document = Document::new("Animation".to_string(), 6, 4);
+    for (index, (color, delay)) in COLORS.iter().zip(DELAYS_MS).enumerate() {
+        let mut layer = Layer::new_pixel(format!("Frame {}", index + 1), 6, 4);
+        let mut pixels = PixelData::new_rgba(6, 4);
+        pixels.fill(RgbaPixel::new(color[0], color[1], color[2], color[3]));
+        layer.pixel_data = Some(pixels);
+        layer.metadata.frame_delay_ms = Some(delay);
+        document.add_layer(layer);
+    }
+    document
+}
+
+fn assert_round_trip(extension: &str, lossy: bool) -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join(format!("animation.{}", extension));
+
+    FileIO::export_animation(
+        &frame_document(),
+        &[0, 1, 2],
+        &path,
+        &AnimationOptions::default(),
+    )?;
+
+    let document = FileIO::load_document(&path)?;
+    assert_eq!(document.layers.len(), 3, "{} frame count", extension);
+    assert_eq!(document.dimensions(), (6, 4));
+
+    for (layer, color) in document.layers.iter().zip(COLORS) {
+        let pixel = layer.get_pixel(2, 2).unwrap();
+        let tolerance = if lossy { 2 } else { 0 };
+        for (actual, expected) in [pixel.r, pixel.g, pixel.b, pixel.a].into_iter().zip(color) {
+            assert!(
+                actual.abs_diff(expected) <= tolerance,
+                "{}: {:?} != {:?}",
+                extension,
+                pixel,
+                color
+            );
+        }
+        assert!(layer.metadata.frame_delay_ms.is_some());
+    }
+
+    if !lossy {
+        let delays: Vec<_> = document
+            .layers
+            .iter()
+            .map(|layer| layer.metadata.frame_delay_ms.unwrap())
+            .collect();
+        assert_eq!(delays, DELAYS_MS, "{} frame delays", extension);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_gif_round_trip_as_layers() -> anyhow::Result<()> {
+    assert_round_trip("gif", false)
+}
+
+#[test]
+fn test_apng_round_trip_as_layers() -> anyhow::Result<()> {
+    assert_round_trip("png", false)
+}
+
+#[test]
+fn test_animated_webp_round_trip_as_layers() -> anyhow::Result<()> {
+    // libwebp re-encodes sub-frames and derives the last frame's duration
+    // itself, so colors may drift slightly and delays are not compared
+    assert_round_trip("webp", true)
+}
+
+#[test]
+fn test_export_selected_layers_only() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("selected.gif");
+
+    FileIO::export_animation(
+        &frame_document(),
+        &[2, 0],
+        &path,
+        &AnimationOptions::default().with_loop_count(LoopCount::Finite(3)),
+    )?;
+
+    let frames: Vec<AnimationFrame> = load_animation_frames(&path)?;
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].image.get_pixel(0, 0).0, COLORS[2]);
+    assert_eq!(frames[1].image.get_pixel(0, 0).0, COLORS[0]);
+    assert_eq!(frames[0].delay_ms, DELAYS_MS[2]);
+
+    Ok(())
+}
+
+#[test]
+fn test_still_png_still_loads_as_single_layer() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("still.png");
+    image::DynamicImage::new_rgba8(5, 5).save(&path)?;
+
+    let document = FileIO::load_document(&path)?;
+    assert_eq!(document.layers.len(), 1);
+    assert!(document.layers[0].metadata.frame_delay_ms.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_out_of_range_layer_is_rejected() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().join("bad.gif");
+    assert!(
+        FileIO::export_animation(&frame_document(), &[7], &path, &AnimationOptions::default())
+            .is_err()
+    );
+}
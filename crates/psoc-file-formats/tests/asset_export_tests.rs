@@ -0,0 +1,139 @@
+//! Asset export tests
+//!
+//! Assets must be written once per preset at the preset's scale, and the
+//! assets with their presets must survive a project save and load.
+
+use psoc_core::{
+    Asset, AssetFormat, AssetPreset, Document, Layer, PixelData, Point, Rect, RgbaPixel,
+};
+use psoc_file_formats::{load_project, save_project, FileIO};
+use tempfile::tempdir;
+
+/// 20x10 document with a 4x4 blue icon layer at (2, 3)
+fn icon_document() -> (Document, Layer) {
+    let mut document = Document::new("Icons".to_string(), 20, 10);
+    let mut icon = Layer::new_pixel("Icon".to_string(), 4, 4);
+    let mut pixels = PixelData::new_rgba(4, 4);
+    pixels.fill(RgbaPixel::new(0, 0, 255, 255));
+    icon.pixel_data = Some(pixels);
+    icon.offset = Point::new(2.0, 3.0);
+    document.add_layer(icon.clone());
+    (document, icon)
+}
+
+#[test]
+fn test_layer_asset_exports_every_density() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let (mut document, icon) = icon_document();
+    document.add_asset(Asset::from_layer("icon", icon.id));
+
+    let written = FileIO::export_assets(&document, temp_dir.path())?;
+    let names: Vec<_> = written
+        .iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, ["icon.png", "icon@2x.png", "icon@3x.png"]);
+
+    for (path, size) in written.iter().zip([4, 8, 12]) {
+        let image = image::open(path)?.to_rgba8();
+        assert_eq!(image.dimensions(), (size, size));
+        assert_eq!(image.get_pixel(size / 2, size / 2).0, [0, 0, 255, 255]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_slice_asset_with_custom_presets() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let (mut document, _) = icon_document();
+    // Slice reaching past the right edge is clipped to the canvas
+    document.add_asset(
+        Asset::from_slice("banner", Rect::new(10.0, 0.0, 20.0, 10.0)).with_presets(vec![
+            AssetPreset::new(0.5, "", AssetFormat::Webp),
+            AssetPreset::new(1.0, "_full", AssetFormat::Jpeg),
+        ]),
+    );
+
+    let written = FileIO::export_assets(&document, temp_dir.path())?;
+    assert!(written[0].ends_with("banner.webp"));
+    assert!(written[1].ends_with("banner_full.jpg"));
+    assert_eq!(image::open(&written[0])?.to_rgba8().dimensions(), (5, 5));
+    assert_eq!(image::open(&written[1])?.to_rgb8().dimensions(), (10, 10));
+
+    Ok(())
+}
+
+#[test]
+fn test_assets_persist_in_project() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("icons.psoc");
+    let (mut document, icon) = icon_document();
+    document.add_asset(
+        Asset::from_layer("icon", icon.id).with_presets(vec![AssetPreset::new(
+            0.5,
+            "-small",
+            AssetFormat::Webp,
+        )]),
+    );
+
+    save_project(&document, &path)?;
+    let loaded = load_project(&path)?;
+    assert_eq!(loaded.assets, document.assets);
+
+    Ok(())
+}
+
+#[test]
+fn test_asset_of_deleted_layer_is_an_error() {
+    let temp_dir = tempdir().unwrap();
+    let (mut document, _) = icon_document();
+    let removed = Layer::new_pixel("Removed".to_string(), 2, 2);
+    document.add_asset(Asset::from_layer("gone", removed.id));
+
+    assert!(FileIO::export_assets(&document, temp_dir.path()).is_err());
+}
+
+#[test]
+fn test_asset_names_cannot_leave_export_directory() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let export_dir = temp_dir.path().join("assets");
+    let (mut document, icon) = icon_document();
+    document.add_asset(
+        Asset::from_layer("../../escape", icon.id).with_presets(vec![AssetPreset::new(
+            1.0,
+            "/../x",
+            AssetFormat::Png,
+        )]),
+    );
+
+    let written = FileIO::export_assets(&document, &export_dir)?;
+    assert_eq!(written.len(), 1);
+    assert_eq!(written[0].parent(), Some(export_dir.as_path()));
+    assert!(!temp_dir.path().join("escape.png").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_scaled_assets_keep_edge_colors() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let (mut document, _) = icon_document();
+    // Blue icon surrounded by transparent black
+    document.background_color = RgbaPixel::transparent();
+    document.add_asset(
+        Asset::from_slice("edge", Rect::new(0.0, 0.0, 8.0, 9.0)).with_presets(vec![
+            AssetPreset::new(2.0, "@2x", AssetFormat::Png),
+            AssetPreset::new(0.5, "@half", AssetFormat::Png),
+        ]),
+    );
+
+    for path in FileIO::export_assets(&document, temp_dir.path())? {
+        let image = image::open(&path)?.to_rgba8();
+        for pixel in image.pixels().filter(|pixel| pixel[3] > 0) {
+            assert_eq!(&pixel.0[..3], &[0, 0, 255], "{}", path.display());
+        }
+    }
+
+    Ok(())
+}
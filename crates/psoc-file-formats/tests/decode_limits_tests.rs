@@ -0,0 +1,527 @@
+//! Decode limit regression tests
+//!
+//! Corrupt and malicious files must be rejected with a typed error before
+//! their declared size is allocated, and mutated files must never panic the
+//! PNG, JPEG or project loaders. OpenRaster layers and animation frames are
+//! checked one at a time against a shared budget.
+
+use psoc_core::{Document, Layer, RgbaPixel};
+use psoc_file_formats::{
+    DecodeLimitError, DecodeLimits, FileIO, ImageIO, ProjectArchive, ProjectFile,
+};
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+use tempfile::tempdir;
+
+/// Deterministic xorshift generator so failures are reproducible
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn limit_error(error: &anyhow::Error) -> Option<&DecodeLimitError> {
+    error.downcast_ref::<DecodeLimitError>()
+}
+
+/// Limits that run the decode inline so that panics reach the test
+fn inline_limits() -> DecodeLimits {
+    DecodeLimits::default()
+        .with_max_memory(64 * 1024 * 1024)
+        .with_max_decode_time(None)
+}
+
+fn sample_image() -> image::DynamicImage {
+    image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(16, 16, |x, y| {
+        image::Rgba([(x * 16) as u8, (y * 16) as u8, 128, 255])
+    }))
+}
+
+fn sample_project(path: &Path) -> anyhow::Result<()> {
+    let mut document = Document::new("Fuzz".to_string(), 16, 16);
+    let mut layer = Layer::new_pixel("Pixels".to_string(), 16, 16);
+    layer.fill(RgbaPixel::new(200, 10, 10, 255));
+    document.add_layer(layer);
+    FileIO::save_project(&document, path)
+}
+
+/// Rewrite a project archive, passing each entry through `edit`
+fn rewrite_project(
+    path: &Path,
+    mut edit: impl FnMut(&str, Vec<u8>) -> Vec<u8>,
+) -> anyhow::Result<Vec<u8>> {
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)?;
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        let name = entry.name().to_string();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        writer.start_file(name.as_str(), zip::write::SimpleFileOptions::default())?;
+        writer.write_all(&edit(&name, data))?;
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+/// A PNG header declaring 200000x200000 RGBA pixels, followed by a few bytes of image data
+fn huge_png() -> Vec<u8> {
+    let mut ihdr = b"IHDR".to_vec();
+    ihdr.extend_from_slice(&200_000u32.to_be_bytes());
+    ihdr.extend_from_slice(&200_000u32.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+    let mut idat = b"IDAT".to_vec();
+    idat.extend_from_slice(&[0x78, 0x9c, 0x63, 0x00, 0x00]);
+    let mut data = vec![137, 80, 78, 71, 13, 10, 26, 10];
+    for chunk in [ihdr, idat, b"IEND".to_vec()] {
+        data.extend_from_slice(&(chunk.len() as u32 - 4).to_be_bytes());
+        data.extend_from_slice(&chunk);
+        data.extend_from_slice(&crc32(&chunk).to_be_bytes());
+    }
+    data
+}
+
+/// A three-frame 16x16 document, one layer per frame
+fn sample_frames() -> Document {
+    let mut document = Document::new("Frames".to_string(), 16, 16);
+    for (index, color) in [[255, 0, 0], [0, 255, 0], [0, 0, 255]].iter().enumerate() {
+        let mut layer = Layer::new_pixel(format!("Frame {}", index + 1), 16, 16);
+        layer.fill(RgbaPixel::new(color[0], color[1], color[2], 255));
+        document.add_layer(layer);
+    }
+    document
+}
+
+/// Write an OpenRaster archive with the given stack.xml and layer PNGs
+fn write_ora(path: &Path, stack_xml: &str, layers: &[(&str, &[u8])]) -> anyhow::Result<()> {
+    let stored =
+        zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    let mut writer = zip::ZipWriter::new(std::fs::File::create(path)?);
+    writer.start_file("mimetype", stored)?;
+    writer.write_all(b"image/openraster")?;
+    writer.start_file("stack.xml", zip::write::SimpleFileOptions::default())?;
+    writer.write_all(stack_xml.as_bytes())?;
+    for (name, data) in layers {
+        writer.start_file(*name, stored)?;
+        writer.write_all(data)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+#[test]
+fn test_png_with_huge_declared_size_is_rejected() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("huge.png");
+    std::fs::write(&path, huge_png())?;
+
+    let error = ImageIO::load_image_with_profile(&path).unwrap_err();
+    assert!(
+        matches!(
+            limit_error(&error),
+            Some(DecodeLimitError::DimensionsTooLarge {
+                width: 200_000,
+                height: 200_000,
+                ..
+            })
+        ),
+        "{:?}",
+        error
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_png_over_memory_budget_is_rejected() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("image.png");
+    sample_image().save(&path)?;
+
+    let limits = DecodeLimits::default().with_max_memory(16 * 16 * 4 - 1);
+    let error = ImageIO::load_image_with_limits(&path, &limits).unwrap_err();
+    assert_eq!(
+        limit_error(&error),
+        Some(&DecodeLimitError::MemoryBudgetExceeded {
+            required: 16 * 16 * 4,
+            budget: 16 * 16 * 4 - 1
+        })
+    );
+
+    // The same file loads within a budget that fits it
+    let limits = DecodeLimits::default().with_max_memory(16 * 16 * 4);
+    assert!(ImageIO::load_image_with_limits(&path, &limits).is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn test_jpeg_with_forged_frame_size_is_rejected() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("forged.jpg");
+    sample_image().to_rgb8().save(&path)?;
+
+    // Patch the baseline frame header to claim 60000x60000 pixels
+    let mut data = std::fs::read(&path)?;
+    let sof = data
+        .windows(2)
+        .position(|marker| marker == [0xff, 0xc0])
+        .expect("baseline JPEG has a SOF0 marker");
+    data[sof + 5..sof + 7].copy_from_slice(&60_000u16.to_be_bytes());
+    data[sof + 7..sof + 9].copy_from_slice(&60_000u16.to_be_bytes());
+    std::fs::write(&path, data)?;
+
+    let limits = DecodeLimits::default().with_max_dimensions(4096, 4096);
+    let error = FileIO::load_document_with_limits(&path, &limits).unwrap_err();
+    assert!(
+        matches!(
+            limit_error(&error),
+            Some(DecodeLimitError::DimensionsTooLarge { .. })
+        ),
+        "{:?}",
+        error
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_project_layer_over_limits_is_rejected() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("layers.psoc");
+    sample_project(&path)?;
+
+    // A manifest that declares a layer far larger than its tiles
+    let forged = rewrite_project(&path, |name, data| {
+        if name != "manifest.ron" {
+            return data;
+        }
+        let mut project: ProjectFile = ron::from_str(std::str::from_utf8(&data).unwrap()).unwrap();
+        let grid = project.layer_tiles[0].pixels.as_mut().unwrap();
+        grid.width = 1_000_000;
+        grid.height = 1_000_000;
+        ron::to_string(&project).unwrap().into_bytes()
+    })?;
+
+    let mut archive = ProjectArchive::from_reader(Cursor::new(forged.clone()))?;
+    let error = archive.load_document().unwrap_err();
+    assert!(
+        matches!(
+            limit_error(&error),
+            Some(DecodeLimitError::DimensionsTooLarge { .. })
+        ),
+        "{:?}",
+        error
+    );
+
+    let limits = DecodeLimits::unlimited().with_max_memory(1024 * 1024);
+    let mut archive = ProjectArchive::from_reader_with_limits(Cursor::new(forged), &limits)?;
+    let error = archive.load_layer(0).unwrap_err();
+    assert!(matches!(
+        limit_error(&error),
+        Some(DecodeLimitError::MemoryBudgetExceeded { .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_project_limits_cover_manifest_and_time() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("limits.psoc");
+    sample_project(&path)?;
+
+    let limits = DecodeLimits::default().with_max_memory(64);
+    let error = ProjectArchive::open_with_limits(&path, &limits)
+        .err()
+        .expect("manifest exceeds a 64 byte budget");
+    assert!(matches!(
+        limit_error(&error),
+        Some(DecodeLimitError::MemoryBudgetExceeded { .. })
+    ));
+
+    let limits = DecodeLimits::default().with_max_decode_time(Some(Duration::ZERO));
+    let error = psoc_file_formats::load_project_with_limits(&path, &limits).unwrap_err();
+    assert!(matches!(
+        limit_error(&error),
+        Some(DecodeLimitError::TimedOut { .. })
+    ));
+
+    assert!(psoc_file_formats::load_project_with_limits(&path, &DecodeLimits::default()).is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn test_ora_layers_over_limits_are_rejected() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+
+    // A small canvas whose layer PNG declares a huge image
+    let path = temp_dir.path().join("huge_layer.ora");
+    write_ora(
+        &path,
+        r#"<image w="16" h="16"><stack><layer name="Huge" src="data/huge.png"/></stack></image>"#,
+        &[("data/huge.png", &huge_png())],
+    )?;
+    let error = FileIO::load_document(&path).unwrap_err();
+    assert!(
+        matches!(
+            limit_error(&error),
+            Some(DecodeLimitError::DimensionsTooLarge { width: 200_000, .. })
+        ),
+        "{:?}",
+        error
+    );
+
+    // A canvas over the dimension limit is rejected before any layer is read
+    let path = temp_dir.path().join("huge_canvas.ora");
+    write_ora(&path, r#"<image w="100000" h="16"><stack/></image>"#, &[])?;
+    let error = FileIO::load_document(&path).unwrap_err();
+    assert!(matches!(
+        limit_error(&error),
+        Some(DecodeLimitError::DimensionsTooLarge { width: 100_000, .. })
+    ));
+
+    // Layers share one budget: two fit, the third does not
+    let path = temp_dir.path().join("layers.ora");
+    FileIO::save_openraster(&sample_frames(), &path)?;
+    let limits = inline_limits().with_max_memory(16 * 1024);
+    assert!(FileIO::load_document_with_limits(&path, &limits).is_ok());
+    let limits = inline_limits().with_max_memory(3 * 1024);
+    let error = FileIO::load_document_with_limits(&path, &limits).unwrap_err();
+    assert!(
+        matches!(
+            limit_error(&error),
+            Some(DecodeLimitError::MemoryBudgetExceeded { .. })
+        ),
+        "{:?}",
+        error
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_ora_entries_are_capped_while_decompressing() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("bomb.ora");
+
+    // Eight megabytes of whitespace deflate to a few kilobytes
+    let padding = " ".repeat(8 * 1024 * 1024);
+    let stack_xml = format!(r#"<image w="16" h="16"><stack/></image>{}"#, padding);
+    write_ora(&path, &stack_xml, &[])?;
+    assert!(std::fs::metadata(&path)?.len() < 1024 * 1024);
+
+    let limits = inline_limits().with_max_memory(1024 * 1024);
+    let error = FileIO::load_document_with_limits(&path, &limits).unwrap_err();
+    assert_eq!(
+        limit_error(&error),
+        Some(&DecodeLimitError::MemoryBudgetExceeded {
+            required: 1024 * 1024 + 1,
+            budget: 1024 * 1024
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_animation_frames_over_limits_are_rejected() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let document = sample_frames();
+
+    for extension in ["png", "gif"] {
+        let path = temp_dir.path().join(format!("frames.{}", extension));
+        FileIO::export_animation(&document, &[0, 1, 2], &path, &Default::default())?;
+
+        let limits = inline_limits().with_max_dimensions(8, 8);
+        let error = FileIO::load_document_with_limits(&path, &limits).unwrap_err();
+        assert!(
+            matches!(
+                limit_error(&error),
+                Some(DecodeLimitError::DimensionsTooLarge {
+                    width: 16,
+                    height: 16,
+                    ..
+                })
+            ),
+            "{}: {:?}",
+            extension,
+            error
+        );
+
+        // Beside the decoder's four working canvases, each 16x16 frame is
+        // counted as it is decoded: two fit, the third does not
+        let limits = inline_limits().with_max_memory(6 * 16 * 16 * 4 + 100);
+        let error = FileIO::load_document_with_limits(&path, &limits).unwrap_err();
+        assert!(
+            matches!(
+                limit_error(&error),
+                Some(DecodeLimitError::MemoryBudgetExceeded { .. })
+            ),
+            "{}: {:?}",
+            extension,
+            error
+        );
+
+        let document = FileIO::load_document_with_limits(&path, &inline_limits())?;
+        assert_eq!(document.layers.len(), 3);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_hdr_over_limits_is_rejected() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+
+    // A Radiance header declaring 100000x100000 pixels with no pixel data
+    let path = temp_dir.path().join("huge.hdr");
+    std::fs::write(
+        &path,
+        b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 100000 +X 100000\n",
+    )?;
+    let error = FileIO::load_document(&path).unwrap_err();
+    assert!(
+        matches!(
+            limit_error(&error),
+            Some(DecodeLimitError::DimensionsTooLarge {
+                width: 100_000,
+                height: 100_000,
+                ..
+            })
+        ),
+        "{:?}",
+        error
+    );
+
+    // The float copy and the tone-mapped copy count against the budget
+    let path = temp_dir.path().join("image.hdr");
+    image::DynamicImage::ImageRgb32F(image::Rgb32FImage::from_pixel(
+        16,
+        16,
+        image::Rgb([4.0, 1.0, 0.5]),
+    ))
+    .save(&path)?;
+    let limits = inline_limits().with_max_memory(16 * 16 * 16);
+    let error = FileIO::load_document_with_limits(&path, &limits).unwrap_err();
+    assert!(
+        matches!(
+            limit_error(&error),
+            Some(DecodeLimitError::MemoryBudgetExceeded { .. })
+        ),
+        "{:?}",
+        error
+    );
+    assert!(FileIO::load_document_with_limits(&path, &inline_limits()).is_ok());
+
+    Ok(())
+}
+
+/// Apply a random corruption: byte flips, a truncation or an inflated length
+fn mutate(rng: &mut Rng, original: &[u8]) -> Vec<u8> {
+    let mut data = original.to_vec();
+    match rng.below(4) {
+        0 => {
+            for _ in 0..1 + rng.below(8) {
+                let index = rng.below(data.len());
+                data[index] ^= 1 << rng.below(8);
+            }
+        }
+        1 => data.truncate(rng.below(data.len())),
+        2 => {
+            let index = rng.below(data.len().saturating_sub(4).max(1));
+            let end = (index + 4).min(data.len());
+            for byte in &mut data[index..end] {
+                *byte = 0xff;
+            }
+        }
+        _ => {
+            let index = rng.below(data.len());
+            let extra: Vec<u8> = (0..rng.below(64)).map(|_| rng.next() as u8).collect();
+            data.splice(index..index, extra);
+        }
+    }
+    data
+}
+
+#[test]
+fn test_mutated_images_never_panic() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let png_path = temp_dir.path().join("seed.png");
+    let jpeg_path = temp_dir.path().join("seed.jpg");
+    sample_image().save(&png_path)?;
+    sample_image().to_rgb8().save(&jpeg_path)?;
+
+    let mut rng = Rng(0x5eed_1234_abcd_ef01);
+    for (seed_path, extension) in [(&png_path, "png"), (&jpeg_path, "jpg")] {
+        let original = std::fs::read(seed_path)?;
+        for round in 0..150 {
+            let path = temp_dir
+                .path()
+                .join(format!("mutant_{}.{}", round, extension));
+            std::fs::write(&path, mutate(&mut rng, &original))?;
+            // Success and failure are both fine; panics and aborts are not
+            if let Ok(result) = ImageIO::load_image_with_limits(&path, &inline_limits()) {
+                assert!(result.image.width() <= 16 && result.image.height() <= 16);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_mutated_projects_never_panic() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let seed_path = temp_dir.path().join("seed.psoc");
+    sample_project(&seed_path)?;
+    let original = std::fs::read(&seed_path)?;
+
+    let mut rng = Rng(0x0dd_ba11_cafe_f00d);
+    for round in 0..150 {
+        let path = temp_dir.path().join(format!("mutant_{}.psoc", round));
+        std::fs::write(&path, mutate(&mut rng, &original))?;
+        let _ = psoc_file_formats::load_project_with_limits(&path, &inline_limits());
+    }
+
+    // Corrupt manifests are checked as well as corrupt archives
+    for round in 0..100 {
+        let data = rewrite_project(&seed_path, |name, data| {
+            if name == "manifest.ron" {
+                mutate(&mut rng, &data)
+            } else {
+                data
+            }
+        })?;
+        let path = temp_dir.path().join(format!("manifest_{}.psoc", round));
+        std::fs::write(&path, data)?;
+        let _ = psoc_file_formats::load_project_with_limits(&path, &inline_limits());
+    }
+
+    Ok(())
+}
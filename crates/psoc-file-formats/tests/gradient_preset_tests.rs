@@ -0,0 +1,260 @@
+//! Gradient preset file tests
+//!
+//! GIMP and Photoshop gradient libraries must import with their stops,
+//! midpoints and interpolation, and exported `.ggr` files must read back.
+
+use psoc_core::{ColorStop, Gradient, GradientManager, InterpolationMethod, RgbaPixel};
+use psoc_file_formats::FileIO;
+use tempfile::tempdir;
+
+/// Minimal writer for Photoshop action descriptors
+mod descriptor {
+    pub fn unicode(text: &str) -> Vec<u8> {
+        let units: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+        let mut out = (units.len() as u32).to_be_bytes().to_vec();
+        for unit in units {
+            out.extend_from_slice(&unit.to_be_bytes());
+        }
+        out
+    }
+
+    pub fn id(id: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        if id.len() == 4 {
+            out.extend_from_slice(&0u32.to_be_bytes());
+        } else {
+            out.extend_from_slice(&(id.len() as u32).to_be_bytes());
+        }
+        out.extend_from_slice(id.as_bytes());
+        out
+    }
+
+    pub fn object(class: &str, items: Vec<(&str, Vec<u8>)>) -> Vec<u8> {
+        let mut out = unicode("");
+        out.extend(id(class));
+        out.extend_from_slice(&(items.len() as u32).to_be_bytes());
+        for (key, value) in items {
+            out.extend(id(key));
+            out.extend(value);
+        }
+        out
+    }
+
+    pub fn objc(class: &str, items: Vec<(&str, Vec<u8>)>) -> Vec<u8> {
+        let mut out = b"Objc".to_vec();
+        out.extend(object(class, items));
+        out
+    }
+
+    pub fn list(values: Vec<Vec<u8>>) -> Vec<u8> {
+        let mut out = b"VlLs".to_vec();
+        out.extend_from_slice(&(values.len() as u32).to_be_bytes());
+        for value in values {
+            out.extend(value);
+        }
+        out
+    }
+
+    pub fn double(value: f64) -> Vec<u8> {
+        let mut out = b"doub".to_vec();
+        out.extend_from_slice(&value.to_be_bytes());
+        out
+    }
+
+    pub fn unit(unit: &str, value: f64) -> Vec<u8> {
+        let mut out = b"UntF".to_vec();
+        out.extend_from_slice(unit.as_bytes());
+        out.extend_from_slice(&value.to_be_bytes());
+        out
+    }
+
+    pub fn long(value: i32) -> Vec<u8> {
+        let mut out = b"long".to_vec();
+        out.extend_from_slice(&value.to_be_bytes());
+        out
+    }
+
+    pub fn text(value: &str) -> Vec<u8> {
+        let mut out = b"TEXT".to_vec();
+        out.extend(unicode(value));
+        out
+    }
+
+    pub fn enumerated(kind: &str, value: &str) -> Vec<u8> {
+        let mut out = b"enum".to_vec();
+        out.extend(id(kind));
+        out.extend(id(value));
+        out
+    }
+}
+
+fn grd_file() -> Vec<u8> {
+    use descriptor::*;
+
+    let color_stop = |color: Vec<u8>, location: i32, midpoint: i32| {
+        objc(
+            "Clrt",
+            vec![
+                ("Clr ", color),
+                ("Type", enumerated("Clry", "UsrS")),
+                ("Lctn", long(location)),
+                ("Mdpn", long(midpoint)),
+            ],
+        )
+    };
+    let opacity_stop = |opacity: f64, location: i32| {
+        objc(
+            "TrnS",
+            vec![
+                ("Opct", unit("#Prc", opacity)),
+                ("Lctn", long(location)),
+                ("Mdpn", long(50)),
+            ],
+        )
+    };
+
+    let fade = objc(
+        "Grdn",
+        vec![(
+            "Grad",
+            objc(
+                "Grdn",
+                vec![
+                    ("Nm  ", text("$$$/Gradients/Fade=Fade")),
+                    ("GrdF", enumerated("GrdF", "CstS")),
+                    ("Intr", double(4096.0)),
+                    (
+                        "Clrs",
+                        list(vec![
+                            color_stop(
+                                objc(
+                                    "RGBC",
+                                    vec![
+                                        ("Rd  ", double(255.0)),
+                                        ("Grn ", double(0.0)),
+                                        ("Bl  ", double(0.0)),
+                                    ],
+                                ),
+                                0,
+                                50,
+                            ),
+                            color_stop(
+                                objc(
+                                    "HSBC",
+                                    vec![
+                                        ("H   ", unit("#Ang", 240.0)),
+                                        ("Strt", double(100.0)),
+                                        ("Brgh", double(100.0)),
+                                    ],
+                                ),
+                                4096,
+                                25,
+                            ),
+                        ]),
+                    ),
+                    (
+                        "Trns",
+                        list(vec![opacity_stop(100.0, 0), opacity_stop(0.0, 4096)]),
+                    ),
+                ],
+            ),
+        )],
+    );
+    let noise = objc(
+        "Grdn",
+        vec![(
+            "Grad",
+            objc(
+                "Grdn",
+                vec![
+                    ("Nm  ", text("Noise")),
+                    ("GrdF", enumerated("GrdF", "ClNs")),
+                ],
+            ),
+        )],
+    );
+
+    let mut data = b"8BGR".to_vec();
+    data.extend_from_slice(&5u16.to_be_bytes());
+    data.extend_from_slice(&16u32.to_be_bytes());
+    data.extend(object("null", vec![("GrdL", list(vec![fade, noise]))]));
+    data
+}
+
+#[test]
+fn test_import_photoshop_gradient_library() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("library.grd");
+    std::fs::write(&path, grd_file())?;
+
+    let gradients = FileIO::import_gradients(&path)?;
+    // The noise gradient is skipped
+    assert_eq!(gradients.len(), 1);
+
+    let fade = &gradients[0];
+    assert_eq!(fade.name, "Fade");
+    assert_eq!(fade.interpolation, InterpolationMethod::Smooth);
+    let stops = fade.sorted_stops();
+    assert_eq!(stops.len(), 2);
+    assert_eq!(
+        *stops[0],
+        ColorStop::with_midpoint(0.0, RgbaPixel::new(255, 0, 0, 255), 0.25)
+    );
+    assert_eq!(*stops[1], ColorStop::new(1.0, RgbaPixel::new(0, 0, 255, 0)));
+
+    Ok(())
+}
+
+#[test]
+fn test_import_gimp_gradient_with_hsv_segments() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("Sunrise.ggr");
+    std::fs::write(
+        &path,
+        "GIMP Gradient\n1\n0.000000 0.500000 1.000000 1 0 0 1 1 1 0 1 0 1\n",
+    )?;
+
+    let gradients = FileIO::import_gradients(&path)?;
+    assert_eq!(gradients.len(), 1);
+    // Old files without a name line are named after the file
+    assert_eq!(gradients[0].name, "Sunrise");
+    assert_eq!(gradients[0].interpolation, InterpolationMethod::Hsv);
+    assert_eq!(gradients[0].color_at(1.0), RgbaPixel::new(255, 255, 0, 255));
+
+    Ok(())
+}
+
+#[test]
+fn test_export_ggr_and_import_into_manager() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("brand.ggr");
+    let mut gradient = Gradient::linear_two_color(
+        RgbaPixel::new(18, 52, 86, 255),
+        RgbaPixel::new(255, 255, 255, 0),
+    );
+    gradient.name = "Brand".to_string();
+    gradient.add_stop(ColorStop::with_midpoint(
+        0.5,
+        RgbaPixel::new(200, 100, 0, 255),
+        0.3,
+    ));
+
+    FileIO::export_gradient(&gradient, &path)?;
+    let imported = FileIO::import_gradients(&path)?;
+    assert_eq!(imported[0].stops.len(), 3);
+    for position in [0.0, 0.2, 0.5, 0.65, 1.0] {
+        assert_eq!(
+            imported[0].color_at(position),
+            gradient.color_at(position),
+            "{}",
+            position
+        );
+    }
+
+    let mut manager = GradientManager::new();
+    let names = manager.import_gradients(imported);
+    assert_eq!(names, ["Brand"]);
+    assert_eq!(manager.user_gradients().len(), 1);
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+//! High dynamic range format tests
+//!
+//! EXR and Radiance HDR files must load without clamping, display through a
+//! tone map, and export back to EXR with their float values intact.
+
+use image::{ImageFormat, Rgb32FImage};
+use psoc_core::{HdrPixelData, RgbaPixel, ToneMapping};
+use psoc_file_formats::{load_hdr_image, save_exr, FileIO};
+use tempfile::tempdir;
+
+/// 2x1 render with a highlight well above display white
+fn render() -> HdrPixelData {
+    HdrPixelData::new(2, 1, vec![12.5, 3.0, 0.5, 1.0, 0.0, 0.25, 1.0, 0.5]).unwrap()
+}
+
+#[test]
+fn test_exr_round_trip_keeps_float_values() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("render.exr");
+
+    save_exr(&render(), &path)?;
+    assert_eq!(load_hdr_image(&path)?, render());
+
+    Ok(())
+}
+
+#[test]
+fn test_radiance_hdr_loads_without_clamping() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("sky.hdr");
+    let sky = Rgb32FImage::from_raw(1, 1, vec![8.0, 4.0, 2.0]).unwrap();
+    image::DynamicImage::ImageRgb32F(sky).save_with_format(&path, ImageFormat::Hdr)?;
+
+    let hdr = load_hdr_image(&path)?;
+    let [r, g, b, a] = hdr.get_pixel(0, 0);
+    assert!((r - 8.0).abs() < 0.1 && (g - 4.0).abs() < 0.1 && (b - 2.0).abs() < 0.1);
+    assert_eq!(a, 1.0);
+
+    Ok(())
+}
+
+#[test]
+fn test_exr_document_keeps_hdr_layer_and_exports_back() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let source = temp_dir.path().join("render.exr");
+    let exported = temp_dir.path().join("exported.exr");
+    save_exr(&render(), &source)?;
+
+    let document = FileIO::load_document(&source)?;
+    assert_eq!(document.layers.len(), 1);
+    let layer = &document.layers[0];
+    assert_eq!(layer.hdr_data.as_ref(), Some(&render()));
+
+    // The editable pixels are tone-mapped, so the highlight clips to white
+    let display = layer.get_pixel(0, 0).unwrap();
+    assert_eq!((display.r, display.g), (255, 255));
+    assert_eq!(
+        display,
+        ToneMapping::default().map_pixel(render().get_pixel(0, 0))
+    );
+
+    FileIO::export_exr(&document, &exported)?;
+    let reloaded = load_hdr_image(&exported)?;
+    assert_eq!(reloaded.get_pixel(0, 0), render().get_pixel(0, 0));
+    assert_eq!(reloaded.get_pixel(1, 0), render().get_pixel(1, 0));
+
+    Ok(())
+}
+
+#[test]
+fn test_edited_hdr_layer_exports_edits() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let source = temp_dir.path().join("render.exr");
+    let exported = temp_dir.path().join("edited.exr");
+    save_exr(&render(), &source)?;
+
+    // Paint the highlight pixel black, as a brush or fill would
+    let mut document = FileIO::load_document(&source)?;
+    document.layers[0].pixel_data.as_mut().unwrap().set_pixel(
+        0,
+        0,
+        RgbaPixel::new(0, 0, 0, 255),
+    )?;
+
+    FileIO::export_exr(&document, &exported)?;
+    let reloaded = load_hdr_image(&exported)?;
+    assert_eq!(reloaded.get_pixel(0, 0), [0.0, 0.0, 0.0, 1.0]);
+    // Untouched pixels keep their float values
+    assert_eq!(reloaded.get_pixel(1, 0), render().get_pixel(1, 0));
+
+    Ok(())
+}
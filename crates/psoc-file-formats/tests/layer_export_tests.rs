@@ -0,0 +1,99 @@
+//! Layer export tests
+//!
+//! Every exported layer must land in its own file, rendered without the
+//! other layers, named from the template and optionally trimmed.
+
+use psoc_core::{Document, Layer, PixelData, Point, RgbaPixel};
+use psoc_file_formats::{FileIO, LayerExportOptions, SupportedFormat};
+use tempfile::tempdir;
+
+/// 8x6 document with a red background layer, a hidden layer and a 2x3 blue
+/// patch offset to (4, 1)
+fn layered_document() -> Document {
+    let mut document = Document::new("Poster".to_string(), 8, 6);
+
+    let mut background = Layer::new_pixel("Background".to_string(), 8, 6);
+    background.fill(RgbaPixel::new(255, 0, 0, 255));
+    document.add_layer(background);
+
+    let mut hidden = Layer::new_pixel("Hidden".to_string(), 8, 6);
+    hidden.fill(RgbaPixel::new(0, 255, 0, 255));
+    hidden.visible = false;
+    document.add_layer(hidden);
+
+    let mut patch = Layer::new_pixel("Logo".to_string(), 2, 3);
+    let mut pixels = PixelData::new_rgba(2, 3);
+    pixels.fill(RgbaPixel::new(0, 0, 255, 255));
+    patch.pixel_data = Some(pixels);
+    patch.offset = Point::new(4.0, 1.0);
+    document.add_layer(patch);
+
+    document
+}
+
+#[test]
+fn test_exports_each_visible_layer_with_template_names() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let written = FileIO::export_layers(
+        &layered_document(),
+        temp_dir.path(),
+        &LayerExportOptions::default(),
+    )?;
+
+    let names: Vec<_> = written
+        .iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, ["Poster_01_Background.png", "Poster_03_Logo.png"]);
+
+    // The logo file holds only the logo, at canvas size
+    let logo = image::open(&written[1])?.to_rgba8();
+    assert_eq!(logo.dimensions(), (8, 6));
+    assert_eq!(logo.get_pixel(0, 0).0, [0, 0, 0, 0]);
+    assert_eq!(logo.get_pixel(4, 1).0, [0, 0, 255, 255]);
+
+    Ok(())
+}
+
+#[test]
+fn test_trim_to_content_and_selected_layers() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let options = LayerExportOptions::default()
+        .with_template("{name}")
+        .with_format(SupportedFormat::Tiff)
+        .with_trim_to_content(true)
+        .with_layers(vec![2, 1]);
+
+    let written = FileIO::export_layers(&layered_document(), temp_dir.path(), &options)?;
+    assert_eq!(written.len(), 2);
+    assert!(written[0].ends_with("Logo.tiff"));
+    assert!(written[1].ends_with("Hidden.tiff"));
+
+    let logo = image::open(&written[0])?.to_rgba8();
+    assert_eq!(logo.dimensions(), (2, 3));
+    assert!(logo.pixels().all(|pixel| pixel.0 == [0, 0, 255, 255]));
+
+    // Explicitly selected hidden layers are still exported
+    assert_eq!(image::open(&written[1])?.to_rgba8().dimensions(), (8, 6));
+
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_names_get_a_suffix() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let options = LayerExportOptions::default().with_template("{doc}");
+
+    let written = FileIO::export_layers(&layered_document(), temp_dir.path(), &options)?;
+    assert!(written[0].ends_with("Poster.png"));
+    assert!(written[1].ends_with("Poster_2.png"));
+
+    Ok(())
+}
+
+#[test]
+fn test_out_of_range_layer_is_rejected() {
+    let temp_dir = tempdir().unwrap();
+    let options = LayerExportOptions::default().with_layers(vec![9]);
+    assert!(FileIO::export_layers(&layered_document(), temp_dir.path(), &options).is_err());
+}
@@ -0,0 +1,231 @@
+//! Embedded metadata tests
+//!
+//! Images carrying EXIF/XMP/IPTC blocks must open upright, expose their
+//! metadata on the document and keep it when exported again.
+
+use exif::{Field, In, Tag, Value};
+use psoc_file_formats::{
+    load_jpeg_with_profile, load_png_with_profile, load_tiff_with_profile, EmbeddedMetadata,
+    FileIO, ImageIO,
+};
+use std::io::Cursor;
+use std::path::Path;
+use tempfile::tempdir;
+
+const XMP: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"><rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:exif="http://ns.adobe.com/exif/1.0/" exif:GPSAltitude="35/1"><dc:subject><rdf:Bag><rdf:li>harbor</rdf:li></rdf:Bag></dc:subject></rdf:Description></rdf:RDF></x:xmpmeta>"#;
+
+fn ascii(tag: Tag, text: &str) -> Field {
+    Field {
+        tag,
+        ifd_num: In::PRIMARY,
+        value: Value::Ascii(vec![text.as_bytes().to_vec()]),
+    }
+}
+
+/// EXIF block of a phone photo taken in portrait orientation
+fn phone_exif() -> Vec<u8> {
+    let fields = [
+        ascii(Tag::Make, "PSOC Phone"),
+        ascii(Tag::Artist, "Test Photographer"),
+        Field {
+            tag: Tag::Orientation,
+            ifd_num: In::PRIMARY,
+            value: Value::Short(vec![6]),
+        },
+        ascii(Tag::BodySerialNumber, "SERIAL-42"),
+        ascii(Tag::GPSLatitudeRef, "N"),
+        Field {
+            tag: Tag::GPSLatitude,
+            ifd_num: In::PRIMARY,
+            value: Value::Rational(vec![(48, 1).into(), (51, 1).into(), (30, 1).into()]),
+        },
+    ];
+
+    let mut writer = exif::experimental::Writer::new();
+    for field in &fields {
+        writer.push_field(field);
+    }
+    let mut buffer = Cursor::new(Vec::new());
+    writer.write(&mut buffer, false).unwrap();
+    buffer.into_inner()
+}
+
+/// Write a 4x2 JPEG whose EXIF says it must be rotated 90° clockwise
+fn write_phone_jpeg(path: &Path) {
+    let image = image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+        4,
+        2,
+        image::Rgb([200, 50, 50]),
+    ));
+    let mut jpeg = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut jpeg), image::ImageFormat::Jpeg)
+        .unwrap();
+
+    let exif = phone_exif();
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend_from_slice(&((exif.len() + 8) as u16).to_be_bytes());
+    segment.extend_from_slice(b"Exif\0\0");
+    segment.extend_from_slice(&exif);
+
+    let mut output = jpeg[..2].to_vec();
+    output.extend_from_slice(&segment);
+    output.extend_from_slice(&jpeg[2..]);
+    std::fs::write(path, output).unwrap();
+}
+
+fn read_exif(metadata: &EmbeddedMetadata) -> exif::Exif {
+    exif::Reader::new()
+        .read_raw(metadata.exif.clone().expect("EXIF block"))
+        .unwrap()
+}
+
+fn sample_metadata() -> EmbeddedMetadata {
+    EmbeddedMetadata {
+        exif: Some(phone_exif()),
+        xmp: Some(XMP.to_string()),
+        iptc: Some(vec![0x1C, 2, 25, 0, 4, b't', b'e', b's', b't']),
+    }
+}
+
+#[test]
+fn test_jpeg_orientation_is_applied_on_import() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("phone.jpg");
+    write_phone_jpeg(&path);
+
+    let result = load_jpeg_with_profile(&path)?;
+    assert_eq!((result.image.width(), result.image.height()), (2, 4));
+    assert!(result.metadata.exif.is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_document_metadata_is_populated_from_exif() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("phone.jpg");
+    write_phone_jpeg(&path);
+
+    let document = FileIO::load_document(&path)?;
+    assert_eq!(document.dimensions(), (2, 4));
+    assert_eq!(
+        document.metadata.author.as_deref(),
+        Some("Test Photographer")
+    );
+    assert_eq!(
+        document
+            .metadata
+            .custom_fields
+            .get("exif:Make")
+            .map(String::as_str),
+        Some("PSOC Phone")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_jpeg_export_preserves_metadata_without_orientation() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let source = temp_dir.path().join("phone.jpg");
+    let exported = temp_dir.path().join("exported.jpg");
+    write_phone_jpeg(&source);
+
+    let document = FileIO::load_document(&source)?;
+    FileIO::export_flattened(&document, &exported)?;
+
+    // Pixels are already upright, so reopening must not rotate them again
+    let result = load_jpeg_with_profile(&exported)?;
+    assert_eq!((result.image.width(), result.image.height()), (2, 4));
+
+    let exif = read_exif(&result.metadata);
+    assert!(exif.get_field(Tag::Orientation, In::PRIMARY).is_none());
+    assert!(exif.get_field(Tag::Make, In::PRIMARY).is_some());
+    assert!(exif.get_field(Tag::BodySerialNumber, In::PRIMARY).is_some());
+    assert!(exif.get_field(Tag::GPSLatitude, In::PRIMARY).is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_export_can_strip_private_metadata() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let source = temp_dir.path().join("phone.jpg");
+    let exported = temp_dir.path().join("shared.jpg");
+    write_phone_jpeg(&source);
+
+    let document = FileIO::load_document(&source)?;
+    FileIO::export_flattened_with_metadata(&document, &exported, true)?;
+
+    let exif = read_exif(&load_jpeg_with_profile(&exported)?.metadata);
+    assert!(exif.get_field(Tag::Make, In::PRIMARY).is_some());
+    assert!(exif.get_field(Tag::Artist, In::PRIMARY).is_some());
+    assert!(exif.get_field(Tag::BodySerialNumber, In::PRIMARY).is_none());
+    assert!(exif.get_field(Tag::GPSLatitude, In::PRIMARY).is_none());
+    assert!(exif.get_field(Tag::GPSLatitudeRef, In::PRIMARY).is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_png_round_trips_exif_and_xmp() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("image.png");
+    let image = image::DynamicImage::new_rgba8(3, 3);
+
+    ImageIO::save_image_with_metadata(&image, &path, &sample_metadata(), false)?;
+
+    let result = load_png_with_profile(&path)?;
+    let exif = read_exif(&result.metadata);
+    assert!(exif.get_field(Tag::Make, In::PRIMARY).is_some());
+    assert!(result.metadata.xmp.as_deref().unwrap().contains("harbor"));
+    assert!(result
+        .metadata
+        .xmp
+        .as_deref()
+        .unwrap()
+        .contains("GPSAltitude"));
+
+    Ok(())
+}
+
+#[test]
+fn test_tiff_round_trips_metadata() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("image.tiff");
+    let image = image::DynamicImage::new_rgb8(3, 3);
+
+    ImageIO::save_image_with_metadata(&image, &path, &sample_metadata(), false)?;
+
+    let result = load_tiff_with_profile(&path)?;
+    assert_eq!(result.image.to_rgb8(), image.to_rgb8());
+
+    let exif = read_exif(&result.metadata);
+    assert!(exif.get_field(Tag::Make, In::PRIMARY).is_some());
+    assert!(exif.get_field(Tag::BodySerialNumber, In::PRIMARY).is_some());
+    assert!(exif.get_field(Tag::GPSLatitude, In::PRIMARY).is_some());
+    assert!(exif.get_field(Tag::Orientation, In::PRIMARY).is_none());
+    assert_eq!(result.metadata.iptc, sample_metadata().iptc);
+    assert!(result.metadata.xmp.as_deref().unwrap().contains("harbor"));
+
+    Ok(())
+}
+
+#[test]
+fn test_tiff_export_strips_private_metadata() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("shared.tiff");
+    let image = image::DynamicImage::new_rgb8(3, 3);
+
+    ImageIO::save_image_with_metadata(&image, &path, &sample_metadata(), true)?;
+
+    let result = load_tiff_with_profile(&path)?;
+    let exif = read_exif(&result.metadata);
+    assert!(exif.get_field(Tag::Make, In::PRIMARY).is_some());
+    assert!(exif.get_field(Tag::BodySerialNumber, In::PRIMARY).is_none());
+    assert!(exif.get_field(Tag::GPSLatitude, In::PRIMARY).is_none());
+    assert!(!result.metadata.xmp.as_deref().unwrap().contains("GPS"));
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//! OpenRaster import tests against archives laid out like Krita and MyPaint write them
+
+use psoc_core::{BlendMode, Point};
+use psoc_file_formats::load_ora;
+use std::path::PathBuf;
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+#[test]
+fn test_nested_stacks_apply_opacity_offset_and_visibility() -> anyhow::Result<()> {
+    let document = load_ora(fixture_path("nested_stack.ora"))?;
+
+    assert_eq!(document.dimensions(), (16, 16));
+    let names: Vec<_> = document
+        .layers
+        .iter()
+        .map(|layer| layer.name.as_str())
+        .collect();
+    assert_eq!(names, ["Background", "Deep", "Inner", "Top"]);
+
+    let background = &document.layers[0];
+    assert_eq!(background.opacity, 1.0);
+    assert!(background.visible);
+
+    // Group (0.5) > Sub (0.5, hidden) > Deep (1.0)
+    let deep = &document.layers[1];
+    assert!((deep.opacity - 0.25).abs() < 1e-6);
+    assert_eq!(deep.offset, Point::new(6.0, 5.0));
+    assert!(!deep.visible);
+
+    // Group (0.5) > Inner (0.8); the group's multiply op is not representable
+    let inner = &document.layers[2];
+    assert!((inner.opacity - 0.4).abs() < 1e-6);
+    assert_eq!(inner.offset, Point::new(3.0, 4.0));
+    assert_eq!(inner.blend_mode, BlendMode::Screen);
+    assert!(inner.visible);
+
+    let top = &document.layers[3];
+    assert_eq!(top.opacity, 1.0);
+    assert_eq!(top.blend_mode, BlendMode::Normal);
+
+    Ok(())
+}
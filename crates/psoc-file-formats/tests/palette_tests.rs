@@ -0,0 +1,131 @@
+//! Palette file tests
+//!
+//! Palettes written by other tools must import with their colors and name,
+//! and palettes we export must read back unchanged.
+
+use psoc_core::RgbaPixel;
+use psoc_file_formats::{FileIO, Palette, PaletteFormat};
+use tempfile::tempdir;
+
+fn be16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+#[test]
+fn test_import_gimp_palette() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("brand.gpl");
+    std::fs::write(
+        &path,
+        "GIMP Palette\nName: Team Brand\nColumns: 4\n#\n255   0   0\tRed\n  0 128 255 Sky blue\n",
+    )?;
+
+    let palette = FileIO::import_palette(&path)?;
+    assert_eq!(palette.name, "Team Brand");
+    assert_eq!(
+        palette.colors,
+        [
+            RgbaPixel::new(255, 0, 0, 255),
+            RgbaPixel::new(0, 128, 255, 255)
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_import_version_1_aco_uses_file_name() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("swatches.aco");
+    let mut data = Vec::new();
+    be16(&mut data, 1);
+    be16(&mut data, 2);
+    // RGB green
+    for value in [0, 0, 0xffff, 0, 0] {
+        be16(&mut data, value);
+    }
+    // Grayscale at 50% ink
+    for value in [8, 5000, 0, 0, 0] {
+        be16(&mut data, value);
+    }
+    std::fs::write(&path, data)?;
+
+    let palette = FileIO::import_palette(&path)?;
+    assert_eq!(palette.name, "swatches");
+    assert_eq!(
+        palette.colors,
+        [
+            RgbaPixel::new(0, 255, 0, 255),
+            RgbaPixel::new(128, 128, 128, 255)
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_import_ase_with_cmyk_swatch() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("print.ase");
+
+    let mut body = Vec::new();
+    be16(&mut body, 2);
+    body.extend_from_slice(&[0, b'C', 0, 0]);
+    body.extend_from_slice(b"CMYK");
+    for value in [0.0f32, 1.0, 1.0, 0.0] {
+        body.extend_from_slice(&value.to_be_bytes());
+    }
+    be16(&mut body, 1);
+
+    let mut data = b"ASEF".to_vec();
+    be16(&mut data, 1);
+    be16(&mut data, 0);
+    data.extend_from_slice(&1u32.to_be_bytes());
+    be16(&mut data, 0x0001);
+    data.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    data.extend_from_slice(&body);
+    std::fs::write(&path, data)?;
+
+    let palette = FileIO::import_palette(&path)?;
+    assert_eq!(palette.name, "print");
+    assert_eq!(palette.colors, [RgbaPixel::new(255, 0, 0, 255)]);
+
+    Ok(())
+}
+
+#[test]
+fn test_export_and_reimport_every_format() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let palette = Palette::new(
+        "Brand",
+        vec![
+            RgbaPixel::new(12, 34, 56, 255),
+            RgbaPixel::new(200, 100, 0, 255),
+        ],
+    );
+
+    for format in PaletteFormat::ALL {
+        let path = temp_dir
+            .path()
+            .join(format!("exported.{}", format.extension()));
+        FileIO::export_palette(&palette, &path)?;
+        let imported = FileIO::import_palette(&path)?;
+        assert_eq!(imported.colors, palette.colors, "{:?}", format);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_unknown_extension_and_empty_palette_are_errors() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let unknown = temp_dir.path().join("colors.act");
+    std::fs::write(&unknown, [0u8; 768])?;
+    assert!(FileIO::import_palette(&unknown).is_err());
+
+    let empty = temp_dir.path().join("empty.hex");
+    std::fs::write(&empty, "; nothing here\n")?;
+    assert!(FileIO::import_palette(&empty).is_err());
+
+    Ok(())
+}
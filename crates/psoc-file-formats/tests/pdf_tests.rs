@@ -0,0 +1,99 @@
+//! PDF export tests
+//!
+//! Proofs must come out at the document's physical size, with bleed, crop
+//! marks and the output intent only when requested.
+
+use psoc_core::{Document, IccProfile, Resolution};
+use psoc_file_formats::{encode_pdf, FileIO, PdfExportOptions};
+use tempfile::tempdir;
+
+/// 300x150 px document at 150 PPI, i.e. 2x1 inches or 144x72 points
+fn proof_document() -> Document {
+    let mut document = Document::new("Proof".to_string(), 300, 150);
+    document.resolution = Resolution::square(150.0);
+    document
+}
+
+fn pdf_text(options: &PdfExportOptions, document: &Document) -> String {
+    String::from_utf8_lossy(&encode_pdf(document, options).unwrap()).into_owned()
+}
+
+#[test]
+fn test_page_size_follows_resolution() {
+    let pdf = pdf_text(&PdfExportOptions::default(), &proof_document());
+
+    assert!(pdf.starts_with("%PDF-1.6"));
+    assert!(pdf.contains("/MediaBox [0 0 144 72]"));
+    assert!(pdf.contains("/TrimBox [0 0 144 72]"));
+    assert!(pdf.contains("/Width 300 /Height 150"));
+    assert!(pdf.trim_end().ends_with("%%EOF"));
+}
+
+#[test]
+fn test_bleed_and_crop_marks_enlarge_the_page() {
+    let options = PdfExportOptions::default()
+        .with_bleed_mm(25.4)
+        .with_crop_marks(true);
+    let pdf = pdf_text(&options, &proof_document());
+
+    // 1 inch bleed (72 pt) plus 15 pt for the marks on every side
+    assert!(pdf.contains("/MediaBox [0 0 318 246]"));
+    assert!(pdf.contains("/BleedBox [15 15 303 231]"));
+    assert!(pdf.contains("/TrimBox [87 87 231 159]"));
+    // The image is extended by 150 px of bleed on every side
+    assert!(pdf.contains("/Width 600 /Height 450"));
+}
+
+#[test]
+fn test_output_intent_uses_document_profile() {
+    let mut document = proof_document();
+    assert!(!pdf_text(&PdfExportOptions::default(), &document).contains("/OutputIntents"));
+
+    document.icc_profile = Some(IccProfile::new_srgb());
+    let pdf = pdf_text(&PdfExportOptions::default(), &document);
+    assert!(pdf.contains("/OutputIntents"));
+    assert!(pdf.contains("/DestOutputProfile"));
+    assert!(pdf.contains("/ICCBased"));
+    assert!(pdf.contains("(sRGB IEC61966-2.1)"));
+
+    let without = PdfExportOptions::default().with_output_intent(false);
+    assert!(!pdf_text(&without, &document).contains("/OutputIntents"));
+}
+
+#[test]
+fn test_xref_offsets_point_at_objects() -> anyhow::Result<()> {
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("proof.pdf");
+    let mut document = proof_document();
+    document.icc_profile = Some(IccProfile::new_srgb());
+
+    FileIO::export_pdf(&document, &path, &PdfExportOptions::default())?;
+    let bytes = std::fs::read(&path)?;
+
+    // Everything from `startxref` on is plain ASCII
+    let marker = b"startxref\n";
+    let startxref = bytes
+        .windows(marker.len())
+        .rposition(|window| window == marker)
+        .unwrap();
+    let xref_start: usize = std::str::from_utf8(&bytes[startxref + marker.len()..])?
+        .lines()
+        .next()
+        .unwrap()
+        .parse()?;
+    assert!(bytes[xref_start..].starts_with(b"xref"));
+
+    let entries: Vec<usize> = std::str::from_utf8(&bytes[xref_start..])?
+        .lines()
+        .skip(3)
+        .take_while(|line| !line.starts_with("trailer"))
+        .map(|line| line[..10].parse().unwrap())
+        .collect();
+    assert_eq!(entries.len(), 6);
+    for (index, offset) in entries.into_iter().enumerate() {
+        let header = format!("{} 0 obj", index + 1);
+        assert!(bytes[offset..].starts_with(header.as_bytes()));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+//! Project format migration tests
+//!
+//! Each fixture under `tests/fixtures` is a project written by a release that
+//! saved that format version, and must keep loading into the current
+//! `Document` schema.
+
+use psoc_core::{BlendMode, RgbaPixel};
+use psoc_file_formats::{
+    load_project, migrate_project, migrations, parse_project_file, FormatVersion, ProjectFile,
+    PROJECT_FORMAT_VERSION,
+};
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+/// Check the document stored in every migration fixture
+fn assert_fixture_document(document: &psoc_core::Document) {
+    assert_eq!(document.metadata.title, "Migration Fixture");
+    assert_eq!(document.dimensions(), (4, 4));
+    assert_eq!(document.layers.len(), 2);
+
+    let background = &document.layers[0];
+    assert_eq!(background.name, "Background");
+    assert_eq!(
+        background.get_pixel(3, 3),
+        Some(RgbaPixel::new(255, 0, 0, 255))
+    );
+
+    let overlay = &document.layers[1];
+    assert_eq!(overlay.name, "Overlay");
+    assert_eq!(overlay.opacity, 0.5);
+    assert_eq!(overlay.blend_mode, BlendMode::Multiply);
+    assert_eq!((overlay.offset.x, overlay.offset.y), (1.0, 1.0));
+    assert_eq!(
+        overlay.get_pixel(0, 0),
+        Some(RgbaPixel::new(0, 0, 255, 128))
+    );
+}
+
+#[test]
+fn test_v1_0_fixture_loads() -> anyhow::Result<()> {
+    let document = load_project(fixture_path("project_v1_0.psoc"))?;
+    assert_fixture_document(&document);
+    Ok(())
+}
+
+#[test]
+fn test_v1_0_fixture_migrates_to_current_version() -> anyhow::Result<()> {
+    let content = std::fs::read_to_string(fixture_path("project_v1_0.psoc"))?;
+    assert!(content.contains("version: \"1.0\""));
+
+    let (migrated, applied) = migrate_project(&content)?;
+    assert_eq!(applied.len(), migrations().len());
+    assert_eq!(applied[0].from, FormatVersion::new(1, 0));
+
+    let project: ProjectFile = ron::from_str(&migrated)?;
+    assert_eq!(project.version, PROJECT_FORMAT_VERSION);
+    assert!(project.layer_tiles.is_empty());
+    assert_fixture_document(&project.document);
+
+    Ok(())
+}
+
+#[test]
+fn test_v2_0_fixture_loads() -> anyhow::Result<()> {
+    let document = load_project(fixture_path("project_v2_0.psoc"))?;
+    assert_fixture_document(&document);
+    Ok(())
+}
+
+#[test]
+fn test_migrated_v1_project_resaves_as_current_version() -> anyhow::Result<()> {
+    let document = load_project(fixture_path("project_v1_0.psoc"))?;
+
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("upgraded.psoc");
+    psoc_file_formats::save_project(&document, &path)?;
+
+    let archive = psoc_file_formats::ProjectArchive::open(&path)?;
+    assert_eq!(archive.project().version, PROJECT_FORMAT_VERSION);
+    assert_fixture_document(&load_project(&path)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_newer_version_fails_with_clear_error() -> anyhow::Result<()> {
+    let content = std::fs::read_to_string(fixture_path("project_v1_0.psoc"))?;
+    // A future release may also change the schema in ways this build cannot parse
+    let future = content
+        .replacen("version: \"1.0\"", "version: \"99.0\"", 1)
+        .replacen("layers: [", "layer_stack: [", 1);
+
+    let error = parse_project_file(&future).unwrap_err();
+    assert!(format!("{:#}", error).contains("newer version of PSOC"));
+
+    let temp_dir = tempdir()?;
+    let path = temp_dir.path().join("future.psoc");
+    std::fs::write(&path, future)?;
+    let error = load_project(&path).unwrap_err();
+    assert!(format!("{:#}", error).contains("newer version of PSOC"));
+
+    Ok(())
+}
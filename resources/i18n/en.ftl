@@ -27,6 +27,10 @@ menu-edit-select-all = Select All
 menu-edit-deselect = Deselect
 menu-edit-preferences = Preferences
 
+# Select Menu
+menu-select = Select
+menu-select-modify = Modify Selection...
+
 # View Menu
 menu-view = View
 menu-view-zoom-in = Zoom In
@@ -308,9 +312,11 @@ preferences-defaults-title = Default Settings
 preferences-default-tool = Default Tool
 preferences-default-format = Default Image Format
 preferences-auto-save = Auto-save Interval
+preferences-auto-save-retention = Recovery Snapshots
 preferences-max-undo = Max Undo History
 preferences-confirm-close = Confirm Before Closing Unsaved Documents
 preferences-remember-window = Remember Window State
+preferences-strip-private-metadata = Strip GPS and Private Metadata on Export
 preferences-disabled = Disabled
 
 # Advanced Preferences
@@ -329,6 +335,55 @@ preferences-apply = Apply
 preferences-cancel = Cancel
 preferences-reset = Reset to Defaults
 
+# Crash Recovery
+recovery-title = Recover Unsaved Work
+recovery-description = PSOC did not shut down properly. These documents have unsaved changes that can be restored.
+recovery-minutes-ago = min ago
+recovery-restore = Restore
+recovery-discard = Discard
+recovery-discard-all = Discard All
+recovery-later = Decide Later
+
+# PDF Export
+pdf-export-title = Export PDF
+pdf-export-bleed = Bleed (mm)
+pdf-export-crop-marks = Crop marks
+pdf-export-output-intent = Embed ICC output intent
+pdf-export-export = Export
+pdf-export-cancel = Cancel
+
+# Export Layers
+layer-export-title = Export Layers
+layer-export-template = File name template
+layer-export-template-help = {"{"}doc{"}"}, {"{"}index{"}"} and {"{"}name{"}"} are replaced by the document title, layer number and layer name
+layer-export-format = Format
+layer-export-trim = Trim to content
+layer-export-active-only = Active layer only
+layer-export-export = Choose Folder…
+layer-export-cancel = Cancel
+
+# Export Animation
+animation-export-title = Export Animation
+animation-export-frames = Frames (bottom layer first)
+animation-export-delay = Frame delay (ms)
+animation-export-override-delays = Use this delay for every frame
+animation-export-loops = Loop count (0 = forever)
+animation-export-colors = GIF palette colors
+animation-export-global-palette = Share one palette across frames
+animation-export-quantizer-speed = Quantizer speed (1 = best, 30 = fastest)
+animation-export-export = Choose File…
+animation-export-cancel = Cancel
+
+# Export Assets
+assets-title = Export Assets
+assets-empty = No assets yet. Add the active layer or a slice from the selection.
+assets-add-layer = Add Active Layer
+assets-add-slice = Add Slice from Selection
+assets-add-preset = Add Scale
+assets-remove = Remove
+assets-close = Close
+assets-export-all = Export All…
+
 # Common Terms
 untitled = Untitled
 
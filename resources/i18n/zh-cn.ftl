@@ -27,6 +27,10 @@ menu-edit-select-all = 全选
 menu-edit-deselect = 取消选择
 menu-edit-preferences = 首选项
 
+# 选择菜单
+menu-select = 选择
+menu-select-modify = 修改选区...
+
 # 视图菜单
 menu-view = 视图
 menu-view-zoom-in = 放大
@@ -308,9 +312,11 @@ preferences-defaults-title = 默认设置
 preferences-default-tool = 默认工具
 preferences-default-format = 默认图像格式
 preferences-auto-save = 自动保存间隔
+preferences-auto-save-retention = 恢复快照数量
 preferences-max-undo = 最大撤销历史
 preferences-confirm-close = 关闭未保存文档前确认
 preferences-remember-window = 记住窗口状态
+preferences-strip-private-metadata = 导出时移除 GPS 和隐私元数据
 preferences-disabled = 已禁用
 
 # 高级首选项
@@ -329,6 +335,55 @@ preferences-apply = 应用
 preferences-cancel = 取消
 preferences-reset = 重置为默认值
 
+# 崩溃恢复
+recovery-title = 恢复未保存的工作
+recovery-description = PSOC 未正常关闭。以下文档包含可以恢复的未保存更改。
+recovery-minutes-ago = 分钟前
+recovery-restore = 恢复
+recovery-discard = 丢弃
+recovery-discard-all = 全部丢弃
+recovery-later = 稍后决定
+
+# PDF 导出
+pdf-export-title = 导出 PDF
+pdf-export-bleed = 出血 (毫米)
+pdf-export-crop-marks = 裁切标记
+pdf-export-output-intent = 嵌入 ICC 输出意图
+pdf-export-export = 导出
+pdf-export-cancel = 取消
+
+# 导出图层
+layer-export-title = 导出图层
+layer-export-template = 文件名模板
+layer-export-template-help = {"{"}doc{"}"}、{"{"}index{"}"} 和 {"{"}name{"}"} 将替换为文档标题、图层序号和图层名称
+layer-export-format = 格式
+layer-export-trim = 裁剪到内容
+layer-export-active-only = 仅当前图层
+layer-export-export = 选择文件夹…
+layer-export-cancel = 取消
+
+# 导出动画
+animation-export-title = 导出动画
+animation-export-frames = 帧（从底层开始）
+animation-export-delay = 帧延迟（毫秒）
+animation-export-override-delays = 所有帧使用此延迟
+animation-export-loops = 循环次数（0 = 无限）
+animation-export-colors = GIF 调色板颜色数
+animation-export-global-palette = 所有帧共用一个调色板
+animation-export-quantizer-speed = 量化速度（1 = 最佳，30 = 最快）
+animation-export-export = 选择文件…
+animation-export-cancel = 取消
+
+# 导出资源
+assets-title = 导出资源
+assets-empty = 暂无资源。可添加当前图层或由选区创建切片。
+assets-add-layer = 添加当前图层
+assets-add-slice = 由选区添加切片
+assets-add-preset = 添加倍率
+assets-remove = 移除
+assets-close = 关闭
+assets-export-all = 全部导出…
+
 # 常用术语
 untitled = 未命名
 
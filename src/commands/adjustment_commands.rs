@@ -10,6 +10,8 @@ use psoc_core::{adjustment::AdjustmentApplication, Command, CommandMetadata, Doc
 use std::fmt::Debug;
 use uuid::Uuid;
 
+use super::MaskSnapshot;
+
 /// Command to apply an adjustment to a layer
 #[derive(Debug)]
 pub struct ApplyAdjustmentCommand {
@@ -121,6 +123,23 @@ impl Command for ApplyAdjustmentCommand {
     }
 }
 
+/// Apply an adjustment or filter to the document
+///
+/// Edits of the Quick Mask or an alpha channel are recorded in the document
+/// history so they can be undone.
+pub fn execute_adjustment(
+    document: &mut Document,
+    application: AdjustmentApplication,
+) -> Result<()> {
+    let snapshot = MaskSnapshot::capture(document);
+    let description = format!("Apply {} to", application.adjustment_id);
+    ApplyAdjustmentCommand::new(application).execute(document)?;
+    match snapshot {
+        Some(snapshot) => snapshot.commit(document, &description),
+        None => Ok(()),
+    }
+}
+
 /// Command to create an adjustment layer
 #[derive(Debug)]
 #[allow(dead_code)]
@@ -411,4 +430,35 @@ mod tests {
         // Commands for different layers should not be mergeable
         assert!(!command1.can_merge_with(&command3));
     }
+
+    #[test]
+    fn test_filter_on_quick_mask_can_be_undone() {
+        let mut document = Document::new("Test".to_string(), 20, 20);
+        document.add_layer(Layer::new_pixel("Layer".to_string(), 20, 20));
+        document.set_selection(psoc_core::Selection::rectangle(0.0, 0.0, 10.0, 20.0));
+        document.enter_quick_mask();
+        let before = document.quick_mask.clone().unwrap();
+
+        let application = AdjustmentApplication::new(
+            "gaussian_blur".to_string(),
+            serde_json::json!({ "radius": 3.0, "quality": 1.0 }),
+            AdjustmentScope::EntireLayer,
+            0,
+        );
+        execute_adjustment(&mut document, application).unwrap();
+        let edge = |document: &Document| {
+            document
+                .quick_mask
+                .as_ref()
+                .unwrap()
+                .get_pixel(10, 10)
+                .unwrap()
+                .r
+        };
+        assert_ne!(edge(&document), before.get_pixel(10, 10).unwrap().r);
+
+        assert!(document.undo().unwrap());
+        assert!(document.is_quick_mask_active());
+        assert_eq!(edge(&document), before.get_pixel(10, 10).unwrap().r);
+    }
 }
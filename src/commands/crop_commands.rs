@@ -3,29 +3,41 @@
 //! This module contains commands for cropping images and layers.
 
 use anyhow::Result;
-use psoc_core::{Command, CommandMetadata, Document, Rect};
+use psoc_core::{Command, CommandMetadata, Document, PixelData, Point, Rect, Selection};
 use std::fmt::Debug;
 use uuid::Uuid;
 
 /// Command to crop the entire document to a specified rectangle
+///
+/// Alpha channels, the Quick Mask and the selection are captured when the
+/// command is created so the crop can be undone.
 #[derive(Debug)]
 pub struct CropDocumentCommand {
     metadata: CommandMetadata,
     crop_rect: Rect,
-    #[allow(dead_code)]
-    original_size: Option<(u32, u32)>,
+    original_size: (u32, u32),
+    original_channels: Vec<PixelData>,
+    original_quick_mask: Option<PixelData>,
+    original_selection: Selection,
 }
 
 impl CropDocumentCommand {
     /// Create a new crop document command
-    pub fn new(crop_rect: Rect) -> Self {
+    pub fn new(crop_rect: Rect, document: &Document) -> Self {
         Self {
             metadata: CommandMetadata::new(format!(
                 "Crop Document to {}x{} at ({}, {})",
                 crop_rect.width, crop_rect.height, crop_rect.x, crop_rect.y
             )),
             crop_rect,
-            original_size: None,
+            original_size: document.dimensions(),
+            original_channels: document
+                .channels
+                .iter()
+                .map(|channel| channel.pixel_data.clone())
+                .collect(),
+            original_quick_mask: document.quick_mask.clone(),
+            original_selection: document.selection.clone(),
         }
     }
 
@@ -45,29 +57,27 @@ impl Command for CropDocumentCommand {
     }
 
     fn execute(&self, document: &mut Document) -> Result<()> {
-        // TODO: Store original size for undo in a proper way
-        // For now, we'll implement a simple version without storing state
-
-        // TODO: Implement actual crop operation
-        // This would involve:
-        // 1. Cropping all layers to the specified rectangle
-        // 2. Updating document dimensions
-        // 3. Adjusting layer positions if needed
-
-        // For now, just mark as dirty
-        document.mark_dirty();
-
-        Ok(())
+        document.crop(self.crop_rect)
     }
 
     fn undo(&self, document: &mut Document) -> Result<()> {
-        // TODO: Implement undo operation
-        // This would involve:
-        // 1. Restoring original document size
-        // 2. Restoring original layer content
-        // 3. Repositioning layers
+        // Shift layers and paths back by the same whole-pixel offset the crop used
+        let dx = self.crop_rect.x.round();
+        let dy = self.crop_rect.y.round();
+        for layer in &mut document.layers {
+            layer.offset = Point::new(layer.offset.x + dx, layer.offset.y + dy);
+        }
+        for path in &mut document.paths {
+            path.translate(dx, dy);
+        }
 
-        document.mark_dirty();
+        let (width, height) = self.original_size;
+        document.resize(width, height)?;
+        for (channel, pixels) in document.channels.iter_mut().zip(&self.original_channels) {
+            channel.pixel_data = pixels.clone();
+        }
+        document.quick_mask = self.original_quick_mask.clone();
+        document.selection = self.original_selection.clone();
         Ok(())
     }
 
@@ -178,7 +188,8 @@ mod tests {
     #[test]
     fn test_crop_document_command_creation() {
         let crop_rect = Rect::new(10.0, 20.0, 100.0, 80.0);
-        let command = CropDocumentCommand::new(crop_rect);
+        let command =
+            CropDocumentCommand::new(crop_rect, &Document::new("Test".to_string(), 200, 150));
 
         assert_eq!(command.crop_rect(), crop_rect);
         assert!(command.modifies_document());
@@ -188,14 +199,40 @@ mod tests {
     #[test]
     fn test_crop_document_command_execution() {
         let crop_rect = Rect::new(10.0, 20.0, 100.0, 80.0);
-        let command = CropDocumentCommand::new(crop_rect);
         let mut document = Document::new("Test".to_string(), 200, 150);
+        let command = CropDocumentCommand::new(crop_rect, &document);
 
         // Should execute without error
         assert!(command.execute(&mut document).is_ok());
         assert!(command.undo(&mut document).is_ok());
     }
 
+    #[test]
+    fn test_crop_document_moves_layers_and_cuts_channels() {
+        let mut document = Document::new("Test".to_string(), 40, 30);
+        document.add_layer(psoc_core::Layer::new_pixel("Layer".to_string(), 40, 30));
+        document.set_selection(Selection::rectangle(15.0, 10.0, 5.0, 5.0));
+        document.save_selection_to_channel("Alpha 1".to_string());
+        let original = document.channels[0].pixel_data.clone();
+
+        let command = CropDocumentCommand::new(Rect::new(10.0, 5.0, 20.0, 15.0), &document);
+        command.execute(&mut document).unwrap();
+        assert_eq!(document.dimensions(), (20, 15));
+        assert_eq!(document.layers[0].offset, Point::new(-10.0, -5.0));
+        assert_eq!(document.channels[0].dimensions(), (20, 15));
+        assert_eq!(document.channels[0].value(5, 5), 255);
+        assert_eq!(document.channels[0].value(0, 0), 0);
+
+        command.undo(&mut document).unwrap();
+        assert_eq!(document.dimensions(), (40, 30));
+        assert_eq!(document.layers[0].offset, Point::new(0.0, 0.0));
+        assert_eq!(document.channels[0].dimensions(), (40, 30));
+        assert_eq!(
+            document.channels[0].pixel_data.get_pixel(17, 12),
+            original.get_pixel(17, 12)
+        );
+    }
+
     #[test]
     fn test_crop_layer_command_creation() {
         let layer_id = Uuid::new_v4();
@@ -8,6 +8,7 @@ pub mod adjustment_commands;
 pub mod crop_commands;
 pub mod layer_commands;
 pub mod paint_commands;
+pub mod path_commands;
 pub mod selection_commands;
 pub mod smart_object_commands;
 pub mod transform_commands;
@@ -17,6 +18,7 @@ pub use adjustment_commands::*;
 pub use crop_commands::*;
 pub use layer_commands::*;
 pub use paint_commands::*;
+pub use path_commands::*;
 pub use selection_commands::*;
 pub use smart_object_commands::*;
 pub use transform_commands::*;
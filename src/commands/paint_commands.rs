@@ -6,7 +6,7 @@
 //! - Fill operations
 
 use anyhow::Result;
-use psoc_core::{Command, CommandMetadata, Document, PixelData, Point, RgbaPixel};
+use psoc_core::{Command, CommandMetadata, Document, PixelData, Point, RgbaPixel, Selection};
 use std::fmt::Debug;
 use uuid::Uuid;
 
@@ -106,11 +106,13 @@ impl BrushStrokeCommand {
 
     /// Apply the brush stroke to the layer
     fn apply_stroke(&self, document: &mut Document) -> Result<()> {
+        let selection = document.selection.clone();
         if let Some(layer) = document.get_layer_mut(self.layer_index) {
+            let offset = layer.offset;
             if let Some(pixel_data) = &mut layer.pixel_data {
                 // Apply brush stroke using the existing brush algorithm
                 for point in &self.stroke_points {
-                    self.apply_brush_at_point(pixel_data, *point);
+                    self.apply_brush_at_point(pixel_data, *point, &selection, offset);
                 }
                 document.mark_dirty();
             }
@@ -118,8 +120,16 @@ impl BrushStrokeCommand {
         Ok(())
     }
 
-    /// Apply brush effect at a single point
-    fn apply_brush_at_point(&self, pixel_data: &mut PixelData, center: Point) {
+    /// Apply brush effect at a single point, faded by the selection coverage
+    ///
+    /// `offset` is the layer position, used to find each pixel in the selection.
+    fn apply_brush_at_point(
+        &self,
+        pixel_data: &mut PixelData,
+        center: Point,
+        selection: &Selection,
+        offset: Point,
+    ) {
         let radius = self.brush_size / 2.0;
         let radius_squared = radius * radius;
 
@@ -145,9 +155,16 @@ impl BrushStrokeCommand {
                         1.0 - falloff.min(1.0)
                     };
 
+                    let coverage = selection.layer_coverage(x, y, offset);
+                    if coverage == 0 {
+                        continue;
+                    }
+
                     // Apply brush color with alpha blending
                     if let Some(current_pixel) = pixel_data.get_pixel(x, y) {
-                        let brush_alpha = (self.brush_color.a as f32 / 255.0) * strength;
+                        let brush_alpha = (self.brush_color.a as f32 / 255.0)
+                            * strength
+                            * (coverage as f32 / 255.0);
 
                         let blended = RgbaPixel {
                             r: (current_pixel.r as f32 * (1.0 - brush_alpha)
@@ -273,11 +290,13 @@ impl EraserStrokeCommand {
 
     /// Apply the eraser stroke to the layer
     fn apply_stroke(&self, document: &mut Document) -> Result<()> {
+        let selection = document.selection.clone();
         if let Some(layer) = document.get_layer_mut(self.layer_index) {
+            let offset = layer.offset;
             if let Some(pixel_data) = &mut layer.pixel_data {
                 // Apply eraser stroke
                 for point in &self.stroke_points {
-                    self.apply_eraser_at_point(pixel_data, *point);
+                    self.apply_eraser_at_point(pixel_data, *point, &selection, offset);
                 }
                 document.mark_dirty();
             }
@@ -285,8 +304,16 @@ impl EraserStrokeCommand {
         Ok(())
     }
 
-    /// Apply eraser effect at a single point
-    fn apply_eraser_at_point(&self, pixel_data: &mut PixelData, center: Point) {
+    /// Apply eraser effect at a single point, faded by the selection coverage
+    ///
+    /// `offset` is the layer position, used to find each pixel in the selection.
+    fn apply_eraser_at_point(
+        &self,
+        pixel_data: &mut PixelData,
+        center: Point,
+        selection: &Selection,
+        offset: Point,
+    ) {
         let radius = self.eraser_size / 2.0;
         let radius_squared = radius * radius;
 
@@ -312,6 +339,9 @@ impl EraserStrokeCommand {
                         1.0 - falloff.min(1.0)
                     };
 
+                    let strength =
+                        strength * (selection.layer_coverage(x, y, offset) as f32 / 255.0);
+
                     // Apply eraser by reducing alpha
                     if let Some(mut current_pixel) = pixel_data.get_pixel(x, y) {
                         let alpha_reduction = (255.0 * strength) as u8;
@@ -406,4 +436,55 @@ mod tests {
         // Should execute without error
         assert!(command.execute(&mut document).is_ok());
     }
+
+    #[test]
+    fn test_brush_stroke_blends_by_selection_coverage() {
+        let mut document = Document::new("Test".to_string(), 20, 20);
+        let mut layer = Layer::new_pixel("Test Layer".to_string(), 20, 20);
+        layer.pixel_data = Some(PixelData::new_rgba(20, 20));
+        document.add_layer(layer);
+        // The selection edge runs through the middle of column 10
+        document.set_selection(Selection::rectangle(0.0, 0.0, 10.5, 20.0));
+
+        let color = RgbaPixel::new(255, 0, 0, 255);
+        let command = BrushStrokeCommand::new(0, vec![Point::new(10.0, 10.0)], 8.0, 1.0, color);
+        command.execute(&mut document).unwrap();
+
+        let pixel_data = document.get_layer(0).unwrap().pixel_data.as_ref().unwrap();
+        let alpha = |x| pixel_data.get_pixel(x, 10).unwrap().a;
+        assert_eq!(alpha(9), 255);
+        assert!((126..=129).contains(&alpha(10)));
+        assert_eq!(alpha(11), 0);
+    }
+
+    #[test]
+    fn test_strokes_on_offset_layer_follow_feathered_selection() {
+        let mut document = Document::new("Test".to_string(), 40, 40);
+        let mut layer = Layer::new_pixel("Moved".to_string(), 20, 20);
+        layer.fill(RgbaPixel::new(0, 0, 255, 255));
+        layer.set_position(Point::new(10.0, 10.0));
+        document.add_layer(layer);
+        let selection = Selection::rectangle(14.0, 0.0, 8.0, 40.0).modify(
+            psoc_core::SelectionModification::Feather { radius: 2.0 },
+            40,
+            40,
+        );
+        document.set_selection(selection.clone());
+
+        let command = EraserStrokeCommand::new(0, vec![Point::new(5.0, 10.0)], 16.0, 1.0);
+        command.execute(&mut document).unwrap();
+
+        // Layer column x sits on document column x + 10
+        let layer = document.get_layer(0).unwrap();
+        let erased = |x: u32| 255 - layer.get_pixel(x, 10).unwrap().a as i32;
+        let coverage = |x: u32| selection.coverage(x + 10, 20) as i32;
+        assert_eq!(coverage(0), 0);
+        assert_eq!(erased(0), 0);
+        assert_eq!(coverage(7), 255);
+        assert_eq!(erased(7), 255);
+
+        // The feathered edge fades the eraser
+        let edge = (0..8).find(|&x| (1..255).contains(&coverage(x))).unwrap();
+        assert!((erased(edge) - coverage(edge)).abs() <= 1);
+    }
 }
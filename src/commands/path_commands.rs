@@ -0,0 +1,536 @@
+//! Path-related commands for undo/redo functionality
+//!
+//! This module contains commands for vector path operations including:
+//! - Adding and deleting paths
+//! - Editing a path's anchors with the pen tool
+//! - Stroking a path with the brush
+//! - Filling a path
+
+use anyhow::Result;
+use psoc_core::{Command, CommandMetadata, Document, Path, PixelData, Point, RgbaPixel, Selection};
+use std::fmt::Debug;
+use uuid::Uuid;
+
+use super::BrushStrokeCommand;
+
+/// Command to add a path to the document and make it active
+#[derive(Debug)]
+pub struct AddPathCommand {
+    metadata: CommandMetadata,
+    path: Path,
+    index: usize,
+}
+
+impl AddPathCommand {
+    /// Create a new add path command
+    pub fn new(path: Path, index: usize) -> Self {
+        Self {
+            metadata: CommandMetadata::new(format!("Add Path '{}'", path.name)),
+            path,
+            index,
+        }
+    }
+}
+
+impl Command for AddPathCommand {
+    fn id(&self) -> Uuid {
+        self.metadata.id
+    }
+
+    fn description(&self) -> &str {
+        &self.metadata.description
+    }
+
+    fn execute(&self, document: &mut Document) -> Result<()> {
+        document.insert_path(self.index, self.path.clone())?;
+        document.set_active_path(Some(self.index))
+    }
+
+    fn undo(&self, document: &mut Document) -> Result<()> {
+        document.remove_path(self.index)?;
+        Ok(())
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.metadata.timestamp
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Command to delete a path from the document
+#[derive(Debug)]
+pub struct DeletePathCommand {
+    metadata: CommandMetadata,
+    index: usize,
+    path: Option<Path>,
+}
+
+impl DeletePathCommand {
+    /// Create a new delete path command, capturing the path for undo
+    pub fn new(index: usize, document: &Document) -> Self {
+        let path = document.paths.get(index).cloned();
+        let name = path.as_ref().map(|path| path.name.as_str()).unwrap_or("");
+        Self {
+            metadata: CommandMetadata::new(format!("Delete Path '{}'", name)),
+            index,
+            path,
+        }
+    }
+}
+
+impl Command for DeletePathCommand {
+    fn id(&self) -> Uuid {
+        self.metadata.id
+    }
+
+    fn description(&self) -> &str {
+        &self.metadata.description
+    }
+
+    fn execute(&self, document: &mut Document) -> Result<()> {
+        document.remove_path(self.index)?;
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut Document) -> Result<()> {
+        let path = self
+            .path
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Path index out of bounds: {}", self.index))?;
+        document.insert_path(self.index, path)
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.metadata.timestamp
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Command to replace a path with an edited copy
+///
+/// Without a path before the edit, executing it adds the path and undoing
+/// it removes the path again.
+#[derive(Debug)]
+pub struct EditPathCommand {
+    metadata: CommandMetadata,
+    index: usize,
+    before: Option<Path>,
+    after: Path,
+}
+
+impl EditPathCommand {
+    /// Create a new edit path command
+    pub fn new(description: String, index: usize, before: Option<Path>, after: Path) -> Self {
+        Self {
+            metadata: CommandMetadata::new(description),
+            index,
+            before,
+            after,
+        }
+    }
+}
+
+impl Command for EditPathCommand {
+    fn id(&self) -> Uuid {
+        self.metadata.id
+    }
+
+    fn description(&self) -> &str {
+        &self.metadata.description
+    }
+
+    fn execute(&self, document: &mut Document) -> Result<()> {
+        if self.before.is_some() {
+            let path = document
+                .paths
+                .get_mut(self.index)
+                .ok_or_else(|| anyhow::anyhow!("Path index out of bounds: {}", self.index))?;
+            *path = self.after.clone();
+            document.mark_dirty();
+        } else {
+            document.insert_path(self.index, self.after.clone())?;
+        }
+        document.set_active_path(Some(self.index))
+    }
+
+    fn undo(&self, document: &mut Document) -> Result<()> {
+        match &self.before {
+            Some(before) => {
+                let path = document
+                    .paths
+                    .get_mut(self.index)
+                    .ok_or_else(|| anyhow::anyhow!("Path index out of bounds: {}", self.index))?;
+                *path = before.clone();
+                document.mark_dirty();
+            }
+            None => {
+                document.remove_path(self.index)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.metadata.timestamp
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Active path captured when a pen gesture starts
+///
+/// Committing it records the edit made since as a single undoable command.
+#[derive(Debug, Clone)]
+pub struct PathSnapshot {
+    /// Index of the active path, or of the path the gesture will create
+    index: usize,
+    path: Option<Path>,
+}
+
+impl PathSnapshot {
+    /// Capture the active path, or note where a new one will be added
+    pub fn capture(document: &Document) -> Self {
+        match document
+            .active_path
+            .filter(|&index| index < document.paths.len())
+        {
+            Some(index) => Self {
+                index,
+                path: Some(document.paths[index].clone()),
+            },
+            None => Self {
+                index: document.paths.len(),
+                path: None,
+            },
+        }
+    }
+
+    /// Record the edit made since the snapshot in the document history
+    pub fn commit(self, document: &mut Document, description: &str) -> Result<()> {
+        let Some(after) = document.paths.get(self.index).cloned() else {
+            return Ok(());
+        };
+        if self.path.as_ref() == Some(&after) {
+            return Ok(());
+        }
+
+        // Put the path back so the command applies the edit itself
+        match &self.path {
+            Some(before) => document.paths[self.index] = before.clone(),
+            None => {
+                document.remove_path(self.index)?;
+            }
+        }
+        document.execute_command(Box::new(EditPathCommand::new(
+            description.to_string(),
+            self.index,
+            self.path,
+            after,
+        )))
+    }
+}
+
+/// Capture a layer's pixels so a path operation on it can be undone
+fn backup_layer(document: &Document, layer_index: usize) -> Option<PixelData> {
+    document
+        .get_layer(layer_index)
+        .and_then(|layer| layer.pixel_data.clone())
+}
+
+/// Put back pixels captured with [`backup_layer`]
+fn restore_layer(
+    document: &mut Document,
+    layer_index: usize,
+    backup: &Option<PixelData>,
+) -> Result<()> {
+    let layer = document
+        .get_layer_mut(layer_index)
+        .ok_or_else(|| anyhow::anyhow!("Layer index {} out of bounds", layer_index))?;
+    layer.pixel_data = backup.clone();
+    document.mark_dirty();
+    Ok(())
+}
+
+/// Command to stroke a path with the brush
+///
+/// The path is flattened and dabbed at a quarter of the brush size, so strokes
+/// look like the brush tool dragged along the path.
+#[derive(Debug)]
+pub struct StrokePathCommand {
+    metadata: CommandMetadata,
+    layer_index: usize,
+    path: Path,
+    brush_size: f32,
+    brush_hardness: f32,
+    brush_color: RgbaPixel,
+    backup_data: Option<PixelData>,
+}
+
+impl StrokePathCommand {
+    /// Create a new stroke path command, capturing the layer for undo
+    pub fn new(
+        document: &Document,
+        layer_index: usize,
+        path: Path,
+        brush_size: f32,
+        brush_hardness: f32,
+        brush_color: RgbaPixel,
+    ) -> Self {
+        Self {
+            metadata: CommandMetadata::new(format!("Stroke Path '{}'", path.name)),
+            layer_index,
+            path,
+            brush_size,
+            brush_hardness,
+            brush_color,
+            backup_data: backup_layer(document, layer_index),
+        }
+    }
+
+    /// Brush dab positions along the path
+    fn dab_points(&self) -> Vec<Point> {
+        let spacing = (self.brush_size / 4.0).max(1.0);
+        let mut dabs = Vec::new();
+
+        for points in self.path.flatten(Path::TOLERANCE) {
+            let Some(&first) = points.first() else {
+                continue;
+            };
+            dabs.push(first);
+            // Distance walked since the last dab
+            let mut travelled = 0.0;
+            for pair in points.windows(2) {
+                let length = pair[0].distance_to(&pair[1]);
+                let mut along = spacing - travelled;
+                while along <= length {
+                    let t = along / length;
+                    dabs.push(Point::new(
+                        pair[0].x + (pair[1].x - pair[0].x) * t,
+                        pair[0].y + (pair[1].y - pair[0].y) * t,
+                    ));
+                    along += spacing;
+                }
+                travelled = length - (along - spacing);
+            }
+        }
+
+        dabs
+    }
+}
+
+impl Command for StrokePathCommand {
+    fn id(&self) -> Uuid {
+        self.metadata.id
+    }
+
+    fn description(&self) -> &str {
+        &self.metadata.description
+    }
+
+    fn execute(&self, document: &mut Document) -> Result<()> {
+        if document.get_layer(self.layer_index).is_none() {
+            return Err(anyhow::anyhow!(
+                "Layer index {} out of bounds",
+                self.layer_index
+            ));
+        }
+        BrushStrokeCommand::new(
+            self.layer_index,
+            self.dab_points(),
+            self.brush_size,
+            self.brush_hardness,
+            self.brush_color,
+        )
+        .execute(document)
+    }
+
+    fn undo(&self, document: &mut Document) -> Result<()> {
+        restore_layer(document, self.layer_index, &self.backup_data)
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.metadata.timestamp
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Command to fill the inside of a path with a color
+///
+/// The fill is anti-aliased along the path and limited to the current selection.
+#[derive(Debug)]
+pub struct FillPathCommand {
+    metadata: CommandMetadata,
+    layer_index: usize,
+    path: Path,
+    fill_color: RgbaPixel,
+    backup_data: Option<PixelData>,
+}
+
+impl FillPathCommand {
+    /// Create a new fill path command, capturing the layer for undo
+    pub fn new(document: &Document, layer_index: usize, path: Path, fill_color: RgbaPixel) -> Self {
+        Self {
+            metadata: CommandMetadata::new(format!("Fill Path '{}'", path.name)),
+            layer_index,
+            path,
+            fill_color,
+            backup_data: backup_layer(document, layer_index),
+        }
+    }
+}
+
+impl Command for FillPathCommand {
+    fn id(&self) -> Uuid {
+        self.metadata.id
+    }
+
+    fn description(&self) -> &str {
+        &self.metadata.description
+    }
+
+    fn execute(&self, document: &mut Document) -> Result<()> {
+        let selection = document.selection.clone();
+        let layer = document
+            .get_layer_mut(self.layer_index)
+            .ok_or_else(|| anyhow::anyhow!("Layer index {} out of bounds", self.layer_index))?;
+        let Some(pixel_data) = &mut layer.pixel_data else {
+            return Ok(());
+        };
+
+        let (width, height) = pixel_data.dimensions();
+        let inside = Selection::from_path(&self.path, width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let coverage = inside.coverage(x, y) as f32 * selection.coverage(x, y) as f32
+                    / (255.0 * 255.0);
+                if coverage == 0.0 {
+                    continue;
+                }
+                if let Some(current) = pixel_data.get_pixel(x, y) {
+                    let alpha = (self.fill_color.a as f32 / 255.0) * coverage;
+                    let blend = |under: u8, over: u8| {
+                        (under as f32 * (1.0 - alpha) + over as f32 * alpha).round() as u8
+                    };
+                    let blended = RgbaPixel {
+                        r: blend(current.r, self.fill_color.r),
+                        g: blend(current.g, self.fill_color.g),
+                        b: blend(current.b, self.fill_color.b),
+                        a: blend(current.a, 255),
+                    };
+                    pixel_data.set_pixel(x, y, blended)?;
+                }
+            }
+        }
+
+        document.mark_dirty();
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut Document) -> Result<()> {
+        restore_layer(document, self.layer_index, &self.backup_data)
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.metadata.timestamp
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use psoc_core::{Layer, Rect, Subpath};
+
+    fn test_document() -> Document {
+        let mut document = Document::new("Test".to_string(), 40, 40);
+        let mut layer = Layer::new_pixel("Test Layer".to_string(), 40, 40);
+        layer.pixel_data = Some(PixelData::new_rgba(40, 40));
+        document.add_layer(layer);
+        document
+    }
+
+    fn square_path() -> Path {
+        let mut path = Path::new("Square".to_string());
+        path.subpaths
+            .push(Subpath::rectangle(Rect::new(10.0, 10.0, 20.0, 20.0)));
+        path
+    }
+
+    fn alpha_at(document: &Document, x: u32, y: u32) -> u8 {
+        let layer = document.get_layer(0).unwrap();
+        layer
+            .pixel_data
+            .as_ref()
+            .unwrap()
+            .get_pixel(x, y)
+            .unwrap()
+            .a
+    }
+
+    #[test]
+    fn test_add_and_delete_path() {
+        let mut document = test_document();
+
+        let add = AddPathCommand::new(square_path(), 0);
+        add.execute(&mut document).unwrap();
+        assert_eq!(document.paths.len(), 1);
+        assert_eq!(document.active_path, Some(0));
+
+        let delete = DeletePathCommand::new(0, &document);
+        delete.execute(&mut document).unwrap();
+        assert!(document.paths.is_empty());
+        delete.undo(&mut document).unwrap();
+        assert_eq!(document.paths[0].name, "Square");
+
+        add.undo(&mut document).unwrap();
+        assert!(document.paths.is_empty());
+    }
+
+    #[test]
+    fn test_stroke_path_follows_outline() {
+        let mut document = test_document();
+        let color = RgbaPixel::new(255, 0, 0, 255);
+        let command = StrokePathCommand::new(&document, 0, square_path(), 4.0, 1.0, color);
+        command.execute(&mut document).unwrap();
+
+        // Every edge is painted, the inside and outside are not
+        assert_eq!(alpha_at(&document, 20, 10), 255);
+        assert_eq!(alpha_at(&document, 10, 20), 255);
+        assert_eq!(alpha_at(&document, 29, 25), 255);
+        assert_eq!(alpha_at(&document, 20, 20), 0);
+        assert_eq!(alpha_at(&document, 2, 2), 0);
+
+        command.undo(&mut document).unwrap();
+        assert_eq!(alpha_at(&document, 20, 10), 0);
+    }
+
+    #[test]
+    fn test_fill_path_respects_selection() {
+        let mut document = test_document();
+        document.set_selection(Selection::rectangle(0.0, 0.0, 20.0, 40.0));
+        let color = RgbaPixel::new(0, 0, 255, 255);
+        let command = FillPathCommand::new(&document, 0, square_path(), color);
+        command.execute(&mut document).unwrap();
+
+        assert_eq!(alpha_at(&document, 15, 15), 255);
+        assert_eq!(alpha_at(&document, 25, 15), 0);
+        assert_eq!(alpha_at(&document, 5, 15), 0);
+
+        command.undo(&mut document).unwrap();
+        assert_eq!(alpha_at(&document, 15, 15), 0);
+    }
+}
@@ -4,9 +4,15 @@
 //! - Creating and modifying selections
 //! - Moving selection contents
 //! - Selection transformations
+//! - Selection refinements (feather, expand, contract, border, smooth)
+//! - Entering, editing and leaving Quick Mask mode
+//! - Saving selections to alpha channels and editing them
 
 use anyhow::Result;
-use psoc_core::{Command, CommandMetadata, Document, Point, Selection};
+use psoc_core::{
+    AlphaChannel, Command, CommandMetadata, Document, PixelData, Point, Selection,
+    SelectionModification,
+};
 use std::fmt::Debug;
 use uuid::Uuid;
 
@@ -381,6 +387,449 @@ impl Command for TransformSelectionCommand {
     }
 }
 
+/// Command to refine the current selection
+///
+/// The selection is rasterized to a canvas-sized mask when the command is
+/// created, so executing and undoing only swap selections.
+#[derive(Debug)]
+pub struct ModifySelectionCommand {
+    metadata: CommandMetadata,
+    old_selection: Selection,
+    new_selection: Selection,
+    modification: SelectionModification,
+}
+
+impl ModifySelectionCommand {
+    /// Create a new modify selection command
+    pub fn new(modification: SelectionModification, document: &Document) -> Self {
+        let old_selection = document.selection.clone();
+        let (width, height) = document.dimensions();
+        let new_selection = old_selection.modify(modification, width, height);
+
+        Self {
+            metadata: CommandMetadata::new(format!("Modify Selection ({})", modification)),
+            old_selection,
+            new_selection,
+            modification,
+        }
+    }
+
+    /// The refinement applied by this command
+    pub fn modification(&self) -> SelectionModification {
+        self.modification
+    }
+}
+
+impl Command for ModifySelectionCommand {
+    fn id(&self) -> Uuid {
+        self.metadata.id
+    }
+
+    fn description(&self) -> &str {
+        &self.metadata.description
+    }
+
+    fn execute(&self, document: &mut Document) -> Result<()> {
+        document.set_selection(self.new_selection.clone());
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut Document) -> Result<()> {
+        document.set_selection(self.old_selection.clone());
+        Ok(())
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.metadata.timestamp
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Command to enter Quick Mask mode
+#[derive(Debug)]
+pub struct EnterQuickMaskCommand {
+    metadata: CommandMetadata,
+}
+
+impl EnterQuickMaskCommand {
+    /// Create a new enter Quick Mask command
+    pub fn new() -> Self {
+        Self {
+            metadata: CommandMetadata::new("Enter Quick Mask".to_string()),
+        }
+    }
+}
+
+impl Default for EnterQuickMaskCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Command for EnterQuickMaskCommand {
+    fn id(&self) -> Uuid {
+        self.metadata.id
+    }
+
+    fn description(&self) -> &str {
+        &self.metadata.description
+    }
+
+    fn execute(&self, document: &mut Document) -> Result<()> {
+        document.enter_quick_mask();
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut Document) -> Result<()> {
+        // Entering leaves the selection untouched, so dropping the mask restores it
+        document.quick_mask = None;
+        Ok(())
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.metadata.timestamp
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Command to leave Quick Mask mode, turning the mask into the selection
+#[derive(Debug)]
+pub struct ExitQuickMaskCommand {
+    metadata: CommandMetadata,
+    quick_mask: Option<PixelData>,
+    old_selection: Selection,
+}
+
+impl ExitQuickMaskCommand {
+    /// Create a new exit Quick Mask command
+    pub fn new(document: &Document) -> Self {
+        Self {
+            metadata: CommandMetadata::new("Exit Quick Mask".to_string()),
+            quick_mask: document.quick_mask.clone(),
+            old_selection: document.selection.clone(),
+        }
+    }
+}
+
+impl Command for ExitQuickMaskCommand {
+    fn id(&self) -> Uuid {
+        self.metadata.id
+    }
+
+    fn description(&self) -> &str {
+        &self.metadata.description
+    }
+
+    fn execute(&self, document: &mut Document) -> Result<()> {
+        document.exit_quick_mask();
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut Document) -> Result<()> {
+        document.quick_mask = self.quick_mask.clone();
+        document.set_selection(self.old_selection.clone());
+        Ok(())
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.metadata.timestamp
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Command recording an edit of the Quick Mask, such as a brush stroke or filter
+///
+/// The mask is captured before and after the edit; executing and undoing swap them.
+#[derive(Debug)]
+pub struct EditQuickMaskCommand {
+    metadata: CommandMetadata,
+    old_mask: PixelData,
+    new_mask: PixelData,
+}
+
+impl EditQuickMaskCommand {
+    /// Create a new edit Quick Mask command
+    pub fn new(description: String, old_mask: PixelData, new_mask: PixelData) -> Self {
+        Self {
+            metadata: CommandMetadata::new(description),
+            old_mask,
+            new_mask,
+        }
+    }
+}
+
+impl Command for EditQuickMaskCommand {
+    fn id(&self) -> Uuid {
+        self.metadata.id
+    }
+
+    fn description(&self) -> &str {
+        &self.metadata.description
+    }
+
+    fn execute(&self, document: &mut Document) -> Result<()> {
+        if !document.is_quick_mask_active() {
+            return Err(anyhow::anyhow!("Quick Mask mode is not active"));
+        }
+        document.quick_mask = Some(self.new_mask.clone());
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut Document) -> Result<()> {
+        if !document.is_quick_mask_active() {
+            return Err(anyhow::anyhow!("Quick Mask mode is not active"));
+        }
+        document.quick_mask = Some(self.old_mask.clone());
+        Ok(())
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.metadata.timestamp
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Mask being edited, captured when a stroke or filter starts
+///
+/// Committing it records the edit made since as a single undoable command.
+#[derive(Debug, Clone)]
+pub struct MaskSnapshot {
+    /// Alpha channel being edited, or `None` for the Quick Mask
+    channel: Option<usize>,
+    pixels: PixelData,
+}
+
+impl MaskSnapshot {
+    /// Capture the mask being edited: the Quick Mask, or else the active channel
+    pub fn capture(document: &Document) -> Option<Self> {
+        if let Some(quick_mask) = &document.quick_mask {
+            return Some(Self {
+                channel: None,
+                pixels: quick_mask.clone(),
+            });
+        }
+        let index = document.active_channel?;
+        let channel = document.channels.get(index)?;
+        Some(Self {
+            channel: Some(index),
+            pixels: channel.pixel_data.clone(),
+        })
+    }
+
+    /// Record the edit made since the snapshot in the document history
+    ///
+    /// Nothing is recorded if the mask is unchanged.
+    pub fn commit(self, document: &mut Document, description: &str) -> Result<()> {
+        let command: Box<dyn Command> = match self.channel {
+            None => {
+                let Some(after) = document.quick_mask.clone() else {
+                    return Ok(());
+                };
+                if after == self.pixels {
+                    return Ok(());
+                }
+                Box::new(EditQuickMaskCommand::new(
+                    format!("{} Quick Mask", description),
+                    self.pixels,
+                    after,
+                ))
+            }
+            Some(index) => {
+                let Some(channel) = document.channels.get(index) else {
+                    return Ok(());
+                };
+                if channel.pixel_data == self.pixels {
+                    return Ok(());
+                }
+                Box::new(EditChannelCommand::new(
+                    format!("{} {}", description, channel.name),
+                    index,
+                    self.pixels,
+                    channel.pixel_data.clone(),
+                ))
+            }
+        };
+        document.execute_command(command)
+    }
+}
+
+/// Command to save the current selection as a new alpha channel
+#[derive(Debug)]
+pub struct SaveSelectionToChannelCommand {
+    metadata: CommandMetadata,
+    name: String,
+}
+
+impl SaveSelectionToChannelCommand {
+    /// Create a new save selection command
+    pub fn new(name: String) -> Self {
+        Self {
+            metadata: CommandMetadata::new("Save Selection".to_string()),
+            name,
+        }
+    }
+}
+
+impl Command for SaveSelectionToChannelCommand {
+    fn id(&self) -> Uuid {
+        self.metadata.id
+    }
+
+    fn description(&self) -> &str {
+        &self.metadata.description
+    }
+
+    fn execute(&self, document: &mut Document) -> Result<()> {
+        document.save_selection_to_channel(self.name.clone());
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut Document) -> Result<()> {
+        // The channel was appended, so it is always the last one
+        let last = document
+            .channels
+            .len()
+            .checked_sub(1)
+            .ok_or_else(|| anyhow::anyhow!("No alpha channel to remove"))?;
+        document.remove_channel(last)?;
+        Ok(())
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.metadata.timestamp
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Command to delete an alpha channel
+#[derive(Debug)]
+pub struct DeleteChannelCommand {
+    metadata: CommandMetadata,
+    index: usize,
+    channel: Option<AlphaChannel>,
+}
+
+impl DeleteChannelCommand {
+    /// Create a new delete channel command
+    pub fn new(index: usize, document: &Document) -> Self {
+        Self {
+            metadata: CommandMetadata::new("Delete Channel".to_string()),
+            index,
+            channel: document.channels.get(index).cloned(),
+        }
+    }
+}
+
+impl Command for DeleteChannelCommand {
+    fn id(&self) -> Uuid {
+        self.metadata.id
+    }
+
+    fn description(&self) -> &str {
+        &self.metadata.description
+    }
+
+    fn execute(&self, document: &mut Document) -> Result<()> {
+        document.remove_channel(self.index)?;
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut Document) -> Result<()> {
+        let channel = self
+            .channel
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Channel index out of bounds: {}", self.index))?;
+        document.insert_channel(self.index, channel)
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.metadata.timestamp
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Command recording an edit of an alpha channel, such as a brush stroke or filter
+///
+/// The channel is captured before and after the edit; executing and undoing swap them.
+#[derive(Debug)]
+pub struct EditChannelCommand {
+    metadata: CommandMetadata,
+    index: usize,
+    old_pixels: PixelData,
+    new_pixels: PixelData,
+}
+
+impl EditChannelCommand {
+    /// Create a new edit channel command
+    pub fn new(
+        description: String,
+        index: usize,
+        old_pixels: PixelData,
+        new_pixels: PixelData,
+    ) -> Self {
+        Self {
+            metadata: CommandMetadata::new(description),
+            index,
+            old_pixels,
+            new_pixels,
+        }
+    }
+
+    fn set_pixels(&self, document: &mut Document, pixels: &PixelData) -> Result<()> {
+        let channel = document
+            .channels
+            .get_mut(self.index)
+            .ok_or_else(|| anyhow::anyhow!("Channel index out of bounds: {}", self.index))?;
+        channel.pixel_data = pixels.clone();
+        document.mark_dirty();
+        Ok(())
+    }
+}
+
+impl Command for EditChannelCommand {
+    fn id(&self) -> Uuid {
+        self.metadata.id
+    }
+
+    fn description(&self) -> &str {
+        &self.metadata.description
+    }
+
+    fn execute(&self, document: &mut Document) -> Result<()> {
+        self.set_pixels(document, &self.new_pixels)
+    }
+
+    fn undo(&self, document: &mut Document) -> Result<()> {
+        self.set_pixels(document, &self.old_pixels)
+    }
+
+    fn timestamp(&self) -> std::time::SystemTime {
+        self.metadata.timestamp
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -518,4 +967,132 @@ mod tests {
         assert!(command.undo(&mut document).is_ok());
         assert!(matches!(document.selection, Selection::None));
     }
+
+    #[test]
+    fn test_modify_selection_command() {
+        let mut document = Document::new("Test".to_string(), 40, 40);
+        let selection = Selection::rectangle(10.0, 10.0, 10.0, 10.0);
+        document.set_selection(selection.clone());
+
+        let command =
+            ModifySelectionCommand::new(SelectionModification::Expand { pixels: 2 }, &document);
+        assert_eq!(command.description(), "Modify Selection (Expand 2px)");
+        assert!(command.execute(&mut document).is_ok());
+
+        match &document.selection {
+            Selection::Mask(mask) => {
+                assert_eq!((mask.width, mask.height), (40, 40));
+                assert_eq!(mask.get_pixel(8, 15), 255);
+                assert_eq!(mask.get_pixel(7, 15), 0);
+            }
+            _ => panic!("Expected mask selection"),
+        }
+
+        // Test undo
+        assert!(command.undo(&mut document).is_ok());
+        assert_eq!(document.selection, selection);
+    }
+
+    #[test]
+    fn test_quick_mask_round_trip_and_undo() {
+        let mut document = Document::new("Test".to_string(), 20, 20);
+        let rectangle = Selection::rectangle(0.0, 0.0, 10.0, 20.0);
+        document.set_selection(rectangle.clone());
+
+        let enter = EnterQuickMaskCommand::new();
+        enter.execute(&mut document).unwrap();
+        assert!(document.is_quick_mask_active());
+        let before = document.quick_mask.clone().unwrap();
+        assert_eq!(before.get_pixel(5, 5).unwrap().r, 255);
+        assert_eq!(before.get_pixel(15, 5).unwrap().r, 0);
+
+        // Paint the right half into the mask
+        let mut after = before.clone();
+        for y in 0..20 {
+            for x in 10..20 {
+                after
+                    .set_pixel(x, y, psoc_core::RgbaPixel::new(255, 255, 255, 255))
+                    .unwrap();
+            }
+        }
+        let edit = EditQuickMaskCommand::new("Paint Quick Mask".to_string(), before, after);
+        edit.execute(&mut document).unwrap();
+
+        let exit = ExitQuickMaskCommand::new(&document);
+        exit.execute(&mut document).unwrap();
+        assert!(!document.is_quick_mask_active());
+        // A fully painted mask selects everything
+        assert_eq!(document.selection, Selection::None);
+
+        exit.undo(&mut document).unwrap();
+        assert!(document.is_quick_mask_active());
+        assert_eq!(document.selection, rectangle);
+
+        edit.undo(&mut document).unwrap();
+        exit.execute(&mut document).unwrap();
+        assert!(document.selection.contains_point(Point::new(5.5, 5.5)));
+        assert!(!document.selection.contains_point(Point::new(15.5, 5.5)));
+
+        // Undoing the exit and the entry leaves the original selection
+        exit.undo(&mut document).unwrap();
+        enter.undo(&mut document).unwrap();
+        assert!(!document.is_quick_mask_active());
+        assert_eq!(document.selection, rectangle);
+    }
+
+    #[test]
+    fn test_mask_snapshot_skips_unchanged_masks() {
+        let mut document = Document::new("Test".to_string(), 10, 10);
+        document.set_selection(Selection::rectangle(0.0, 0.0, 5.0, 5.0));
+        EnterQuickMaskCommand::new().execute(&mut document).unwrap();
+
+        let snapshot = MaskSnapshot::capture(&document).unwrap();
+        snapshot.commit(&mut document, "Paint").unwrap();
+        assert_eq!(document.command_history.undo_count(), 0);
+
+        let snapshot = MaskSnapshot::capture(&document).unwrap();
+        document
+            .quick_mask
+            .as_mut()
+            .unwrap()
+            .set_pixel(8, 8, psoc_core::RgbaPixel::new(255, 255, 255, 255))
+            .unwrap();
+        snapshot.commit(&mut document, "Paint").unwrap();
+        assert_eq!(document.command_history.undo_count(), 1);
+        assert_eq!(
+            document.command_history.undo_description(),
+            Some("Paint Quick Mask")
+        );
+    }
+
+    #[test]
+    fn test_alpha_channel_commands_undo() {
+        let mut document = Document::new("Test".to_string(), 10, 10);
+        document.set_selection(Selection::rectangle(0.0, 0.0, 5.0, 5.0));
+
+        let save = SaveSelectionToChannelCommand::new("Alpha 1".to_string());
+        save.execute(&mut document).unwrap();
+        assert_eq!(document.channels.len(), 1);
+        assert_eq!(document.channels[0].value(2, 2), 255);
+
+        let before = document.channels[0].pixel_data.clone();
+        let mut after = before.clone();
+        after
+            .set_pixel(8, 8, psoc_core::RgbaPixel::new(255, 255, 255, 255))
+            .unwrap();
+        let edit = EditChannelCommand::new("Paint Channel".to_string(), 0, before, after);
+        edit.execute(&mut document).unwrap();
+        assert_eq!(document.channels[0].value(8, 8), 255);
+        edit.undo(&mut document).unwrap();
+        assert_eq!(document.channels[0].value(8, 8), 0);
+
+        let delete = DeleteChannelCommand::new(0, &document);
+        delete.execute(&mut document).unwrap();
+        assert!(document.channels.is_empty());
+        delete.undo(&mut document).unwrap();
+        assert_eq!(document.channels[0].name, "Alpha 1");
+
+        save.undo(&mut document).unwrap();
+        assert!(document.channels.is_empty());
+    }
 }
@@ -61,7 +61,19 @@ impl Command for CreateSmartObjectFromImageCommand {
     }
 
     fn execute(&self, document: &mut Document) -> Result<()> {
-        let content_type = if self.embed_content {
+        let is_svg = self
+            .image_path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+
+        let content_type = if is_svg {
+            // Vector content is always embedded so it can be re-rasterized
+            SmartObjectContentType::EmbeddedSvg {
+                original_path: Some(self.image_path.clone()),
+                svg_data: std::fs::read(&self.image_path)?,
+            }
+        } else if self.embed_content {
             // Read the image file and embed it
             let image_data = std::fs::read(&self.image_path)?;
             let format = self
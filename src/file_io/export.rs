@@ -51,6 +51,8 @@ pub async fn export_image<P: AsRef<Path>>(image: &DynamicImage, path: P) -> Resu
 pub enum ExportOptions {
     Png(psoc_file_formats::PngOptions),
     Jpeg(psoc_file_formats::JpegOptions),
+    Tiff(psoc_file_formats::TiffOptions),
+    Webp(psoc_file_formats::WebpOptions),
 }
 
 impl Default for ExportOptions {
@@ -90,6 +92,12 @@ pub async fn export_image_with_options<P: AsRef<Path>>(
         ExportOptions::Jpeg(jpeg_options) => {
             psoc_file_formats::save_jpeg_with_options(&image_clone, &path_clone, &jpeg_options)
         }
+        ExportOptions::Tiff(tiff_options) => {
+            psoc_file_formats::save_tiff_with_options(&image_clone, &path_clone, &tiff_options)
+        }
+        ExportOptions::Webp(webp_options) => {
+            psoc_file_formats::save_webp_with_options(&image_clone, &path_clone, &webp_options)
+        }
     })
     .await
     .context("Failed to spawn image saving task")?
@@ -111,7 +119,7 @@ pub fn can_export<P: AsRef<Path>>(path: P) -> bool {
 
     // Check if extension is supported
     if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-        let supported_extensions = ["png", "jpg", "jpeg"];
+        let supported_extensions = ["png", "jpg", "jpeg", "tif", "tiff", "webp"];
         supported_extensions
             .iter()
             .any(|&supported| ext.to_lowercase() == supported)
@@ -132,6 +140,12 @@ pub fn get_recommended_export_options<P: AsRef<Path>>(path: P) -> Option<ExportO
             psoc_file_formats::SupportedFormat::Jpeg => Some(ExportOptions::Jpeg(
                 psoc_file_formats::JpegOptions::default(),
             )),
+            psoc_file_formats::SupportedFormat::Tiff => Some(ExportOptions::Tiff(
+                psoc_file_formats::TiffOptions::default(),
+            )),
+            psoc_file_formats::SupportedFormat::Webp => Some(ExportOptions::Webp(
+                psoc_file_formats::WebpOptions::default(),
+            )),
         }
     } else {
         None
@@ -206,6 +220,51 @@ pub fn estimate_export_size(
             // JPEG: roughly 0.5-2 bytes per pixel depending on quality
             pixel_count / 2 // Conservative estimate for default quality
         }
+        psoc_file_formats::SupportedFormat::Tiff => {
+            // TIFF (LZW): compresses less aggressively than PNG
+            let channels = if image.color().has_alpha() { 4 } else { 3 };
+            pixel_count * channels
+        }
+        psoc_file_formats::SupportedFormat::Webp => {
+            // WebP lossy: typically 25-35% smaller than JPEG at similar quality
+            pixel_count / 3
+        }
+    }
+}
+
+/// Estimate the file size for an export operation with specific options
+pub fn estimate_export_size_with_options(image: &DynamicImage, options: &ExportOptions) -> u64 {
+    let pixel_count = (image.width() * image.height()) as u64;
+    let channels = if image.color().has_alpha() { 4 } else { 3 };
+
+    match options {
+        ExportOptions::Png(_) => {
+            estimate_export_size(image, psoc_file_formats::SupportedFormat::Png)
+        }
+        ExportOptions::Jpeg(jpeg_options) => {
+            // Scale between ~0.1 and ~2 bytes per pixel with quality
+            pixel_count * (10 + jpeg_options.quality as u64 * 2) / 100
+        }
+        ExportOptions::Tiff(tiff_options) => {
+            let bytes_per_channel = match tiff_options.bit_depth {
+                psoc_file_formats::TiffBitDepth::Eight => 1,
+                psoc_file_formats::TiffBitDepth::Sixteen => 2,
+            };
+            let raw_size = pixel_count * channels * bytes_per_channel;
+            match tiff_options.compression {
+                psoc_file_formats::TiffCompression::None => raw_size,
+                psoc_file_formats::TiffCompression::Lzw => raw_size * 3 / 4,
+                psoc_file_formats::TiffCompression::Deflate(_) => raw_size * 2 / 3,
+            }
+        }
+        ExportOptions::Webp(webp_options) => {
+            if webp_options.lossless {
+                // Lossless WebP is roughly a quarter smaller than PNG
+                pixel_count * channels * 3 / 4
+            } else {
+                pixel_count * (5 + webp_options.quality as u64 * 3 / 2) / 100
+            }
+        }
     }
 }
 
@@ -241,6 +300,8 @@ mod tests {
         assert!(can_export("test.jpeg"));
         assert!(can_export("test.PNG"));
         assert!(can_export("test.JPG"));
+        assert!(can_export("test.tiff"));
+        assert!(can_export("test.webp"));
 
         // Test with unsupported extensions
         assert!(!can_export("test.txt"));
@@ -256,6 +317,12 @@ mod tests {
         let jpeg_options = get_recommended_export_options("test.jpg");
         assert!(matches!(jpeg_options, Some(ExportOptions::Jpeg(_))));
 
+        let tiff_options = get_recommended_export_options("test.tif");
+        assert!(matches!(tiff_options, Some(ExportOptions::Tiff(_))));
+
+        let webp_options = get_recommended_export_options("test.webp");
+        assert!(matches!(webp_options, Some(ExportOptions::Webp(_))));
+
         let unknown_options = get_recommended_export_options("test.txt");
         assert!(unknown_options.is_none());
     }
@@ -275,6 +342,52 @@ mod tests {
         assert!(jpeg_size < png_size);
     }
 
+    #[test]
+    fn test_estimate_export_size_with_options() {
+        let img: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::new(100, 100);
+        let dynamic_img = DynamicImage::ImageRgb8(img);
+
+        let low = estimate_export_size_with_options(
+            &dynamic_img,
+            &ExportOptions::Webp(psoc_file_formats::WebpOptions::lossy(10)),
+        );
+        let high = estimate_export_size_with_options(
+            &dynamic_img,
+            &ExportOptions::Webp(psoc_file_formats::WebpOptions::lossy(95)),
+        );
+        assert!(low < high);
+
+        let uncompressed = estimate_export_size_with_options(
+            &dynamic_img,
+            &ExportOptions::Tiff(psoc_file_formats::TiffOptions::uncompressed()),
+        );
+        let archival = estimate_export_size_with_options(
+            &dynamic_img,
+            &ExportOptions::Tiff(psoc_file_formats::TiffOptions::archival()),
+        );
+        assert_eq!(uncompressed, 100 * 100 * 3);
+        assert!(archival > uncompressed);
+    }
+
+    #[tokio::test]
+    async fn test_export_image_with_tiff_and_webp_options() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let img: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::new(50, 50);
+        let dynamic_img = DynamicImage::ImageRgb8(img);
+
+        let tiff_path = temp_dir.path().join("test_options.tif");
+        let options = ExportOptions::Tiff(psoc_file_formats::TiffOptions::archival());
+        export_image_with_options(&dynamic_img, &tiff_path, options).await?;
+        assert!(tiff_path.exists());
+
+        let webp_path = temp_dir.path().join("test_options.webp");
+        let options = ExportOptions::Webp(psoc_file_formats::WebpOptions::lossless());
+        export_image_with_options(&dynamic_img, &webp_path, options).await?;
+        assert!(webp_path.exists());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_export_image_with_options() -> Result<()> {
         let temp_dir = tempdir()?;
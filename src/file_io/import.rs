@@ -101,7 +101,7 @@ pub fn is_supported_import_extension<P: AsRef<Path>>(path: P) -> bool {
     let path = path.as_ref();
 
     if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-        let supported_extensions = ["png", "jpg", "jpeg"];
+        let supported_extensions = ["png", "jpg", "jpeg", "tif", "tiff", "webp"];
         supported_extensions
             .iter()
             .any(|&supported| ext.to_lowercase() == supported)
@@ -229,6 +229,9 @@ mod tests {
         assert!(is_supported_import_extension("test.jpeg"));
         assert!(is_supported_import_extension("test.PNG"));
         assert!(is_supported_import_extension("test.JPG"));
+        assert!(is_supported_import_extension("test.tif"));
+        assert!(is_supported_import_extension("test.tiff"));
+        assert!(is_supported_import_extension("test.webp"));
 
         // Test with unsupported extensions
         assert!(!is_supported_import_extension("test.txt"));
@@ -11,6 +11,7 @@ use tracing::{debug, info, instrument};
 
 pub mod export;
 pub mod import;
+pub mod recovery;
 
 #[cfg(test)]
 mod tests;
@@ -18,6 +19,7 @@ mod tests;
 // Re-export commonly used types
 pub use export::*;
 pub use import::*;
+pub use recovery::{RecoveryEntry, RecoveryManager, DEFAULT_RECOVERY_RETENTION};
 
 /// File I/O manager for the application
 #[derive(Debug, Clone)]
@@ -59,6 +61,9 @@ impl FileManager {
     }
 
     /// Load a document from any supported file format (images or projects)
+    ///
+    /// Only the visible and active layers of a project are decoded; the rest
+    /// stay pending until [`Document::load_layers_in_use`] needs them.
     #[instrument(skip_all, fields(path = %path.as_ref().display()))]
     pub async fn load_document<P: AsRef<Path>>(&self, path: P) -> Result<Document> {
         let path = path.as_ref();
@@ -66,7 +71,10 @@ impl FileManager {
 
         let path_clone = path.to_path_buf();
         let document = tokio::task::spawn_blocking(move || {
-            psoc_file_formats::FileIO::load_document(&path_clone)
+            // Decode what the first render needs here rather than on the UI thread
+            let mut document = psoc_file_formats::FileIO::open_document(&path_clone)?;
+            document.load_layers_in_use()?;
+            anyhow::Ok(document)
         })
         .await
         .map_err(|e| anyhow::anyhow!("Failed to spawn document loading task: {}", e))?
@@ -113,7 +121,10 @@ impl FileManager {
         let document_clone = document.clone();
         let path_clone = path.to_path_buf();
         tokio::task::spawn_blocking(move || {
-            psoc_file_formats::FileIO::export_flattened(&document_clone, &path_clone)
+            psoc_file_formats::FileIO::export_flattened(
+                &with_layers_loaded(document_clone)?,
+                &path_clone,
+            )
         })
         .await
         .map_err(|e| anyhow::anyhow!("Failed to spawn flattened export task: {}", e))?
@@ -123,6 +134,228 @@ impl FileManager {
         Ok(())
     }
 
+    /// Export layers to individual files in a directory
+    #[instrument(skip_all, fields(directory = %directory.as_ref().display()))]
+    pub async fn export_layers<P: AsRef<Path>>(
+        &self,
+        document: &Document,
+        directory: P,
+        options: &psoc_file_formats::LayerExportOptions,
+    ) -> Result<Vec<std::path::PathBuf>> {
+        let directory = directory.as_ref();
+        info!("Exporting layers to: {}", directory.display());
+
+        let document_clone = document.clone();
+        let directory_clone = directory.to_path_buf();
+        let options = options.clone();
+        let paths = tokio::task::spawn_blocking(move || {
+            psoc_file_formats::FileIO::export_layers(
+                &with_layers_loaded(document_clone)?,
+                &directory_clone,
+                &options,
+            )
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to spawn layer export task: {}", e))?
+        .map_err(|e| anyhow::anyhow!("Failed to export layers: {}", e))?;
+
+        debug!("Exported {} layer files", paths.len());
+        Ok(paths)
+    }
+
+    /// Export every asset of a document at each of its scale presets
+    #[instrument(skip_all, fields(directory = %directory.as_ref().display()))]
+    pub async fn export_assets<P: AsRef<Path>>(
+        &self,
+        document: &Document,
+        directory: P,
+    ) -> Result<Vec<std::path::PathBuf>> {
+        let directory = directory.as_ref();
+        info!("Exporting assets to: {}", directory.display());
+
+        let document_clone = document.clone();
+        let directory_clone = directory.to_path_buf();
+        let paths = tokio::task::spawn_blocking(move || {
+            psoc_file_formats::FileIO::export_assets(
+                &with_layers_loaded(document_clone)?,
+                &directory_clone,
+            )
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to spawn asset export task: {}", e))?
+        .map_err(|e| anyhow::anyhow!("Failed to export assets: {}", e))?;
+
+        debug!("Exported {} asset files", paths.len());
+        Ok(paths)
+    }
+
+    /// Import a color palette file
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub async fn import_palette<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<psoc_file_formats::Palette> {
+        let path_clone = path.as_ref().to_path_buf();
+        tokio::task::spawn_blocking(move || psoc_file_formats::FileIO::import_palette(&path_clone))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to spawn palette import task: {}", e))?
+    }
+
+    /// Export a color palette in the format given by the file extension
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub async fn export_palette<P: AsRef<Path>>(
+        &self,
+        palette: psoc_file_formats::Palette,
+        path: P,
+    ) -> Result<()> {
+        let path_clone = path.as_ref().to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            psoc_file_formats::FileIO::export_palette(&palette, &path_clone)
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to spawn palette export task: {}", e))?
+    }
+
+    /// Import every gradient in a `.ggr` or `.grd` file
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub async fn import_gradients<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<Vec<psoc_core::Gradient>> {
+        let path_clone = path.as_ref().to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            psoc_file_formats::FileIO::import_gradients(&path_clone)
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to spawn gradient import task: {}", e))?
+    }
+
+    /// Export a gradient as a GIMP `.ggr` file
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub async fn export_gradient<P: AsRef<Path>>(
+        &self,
+        gradient: psoc_core::Gradient,
+        path: P,
+    ) -> Result<()> {
+        let path_clone = path.as_ref().to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            psoc_file_formats::FileIO::export_gradient(&gradient, &path_clone)
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to spawn gradient export task: {}", e))?
+    }
+
+    /// Export a flattened document as a PDF proof
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub async fn export_pdf<P: AsRef<Path>>(
+        &self,
+        document: &Document,
+        path: P,
+        options: &psoc_file_formats::PdfExportOptions,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        info!("Exporting PDF to: {}", path.display());
+
+        let document_clone = document.clone();
+        let path_clone = path.to_path_buf();
+        let options = options.clone();
+        tokio::task::spawn_blocking(move || {
+            psoc_file_formats::FileIO::export_pdf(
+                &with_layers_loaded(document_clone)?,
+                &path_clone,
+                &options,
+            )
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to spawn PDF export task: {}", e))?
+        .map_err(|e| anyhow::anyhow!("Failed to export PDF: {}", e))?;
+
+        debug!("PDF exported successfully");
+        Ok(())
+    }
+
+    /// Export a document to OpenEXR, keeping HDR layers unclamped
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub async fn export_exr<P: AsRef<Path>>(&self, document: &Document, path: P) -> Result<()> {
+        let path = path.as_ref();
+        info!("Exporting OpenEXR document to: {}", path.display());
+
+        let document_clone = document.clone();
+        let path_clone = path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            psoc_file_formats::FileIO::export_exr(&with_layers_loaded(document_clone)?, &path_clone)
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to spawn OpenEXR export task: {}", e))?
+        .map_err(|e| anyhow::anyhow!("Failed to export OpenEXR document: {}", e))?;
+
+        debug!("OpenEXR document exported successfully");
+        Ok(())
+    }
+
+    /// Export a document as a flattened image, optionally stripping GPS and
+    /// other private fields from its embedded metadata
+    #[instrument(skip_all, fields(path = %path.as_ref().display()))]
+    pub async fn export_flattened_with_metadata<P: AsRef<Path>>(
+        &self,
+        document: &Document,
+        path: P,
+        strip_private_metadata: bool,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        info!(
+            strip_private_metadata,
+            "Exporting flattened document to: {}",
+            path.display()
+        );
+
+        let document_clone = document.clone();
+        let path_clone = path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            psoc_file_formats::FileIO::export_flattened_with_metadata(
+                &with_layers_loaded(document_clone)?,
+                &path_clone,
+                strip_private_metadata,
+            )
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to spawn flattened export task: {}", e))?
+        .map_err(|e| anyhow::anyhow!("Failed to export flattened document: {}", e))?;
+
+        debug!("Flattened document exported successfully");
+        Ok(())
+    }
+
+    /// Export the given layers of a document as an animated GIF, APNG or WebP
+    #[instrument(skip_all, fields(path = %path.as_ref().display(), frames = layer_indices.len()))]
+    pub async fn export_animation<P: AsRef<Path>>(
+        &self,
+        document: &Document,
+        layer_indices: Vec<usize>,
+        path: P,
+        options: psoc_file_formats::AnimationOptions,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        info!("Exporting animation to: {}", path.display());
+
+        let document_clone = document.clone();
+        let path_clone = path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            psoc_file_formats::FileIO::export_animation(
+                &with_layers_loaded(document_clone)?,
+                &layer_indices,
+                &path_clone,
+                &options,
+            )
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to spawn animation export task: {}", e))?
+        .map_err(|e| anyhow::anyhow!("Failed to export animation: {}", e))?;
+
+        debug!("Animation exported successfully");
+        Ok(())
+    }
+
     /// Get supported import file extensions (images and projects)
     pub fn supported_import_extensions() -> Vec<&'static str> {
         psoc_file_formats::ImageIO::all_supported_extensions()
@@ -154,6 +387,12 @@ impl FileManager {
     }
 }
 
+/// Decode any layers still pending in a copy of a document about to be exported
+fn with_layers_loaded(mut document: Document) -> Result<Document> {
+    document.load_pending_layers()?;
+    Ok(document)
+}
+
 impl Default for FileManager {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,533 @@
+//! Autosave and crash recovery
+//!
+//! Dirty documents are periodically written as project snapshots to a recovery
+//! directory next to the preferences file. Snapshots are removed when their
+//! document is saved or closed normally, so anything still present at startup
+//! was left behind by a session that ended unexpectedly.
+//!
+//! Snapshots are written on a background thread, so a save or close can race
+//! an autosave that is still in flight. Discarding a document's snapshots bumps
+//! its generation, and a snapshot started under an older generation is thrown
+//! away instead of being finalized.
+
+use anyhow::{Context, Result};
+use psoc_core::Document;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, instrument, warn};
+use uuid::Uuid;
+
+/// Name of the recovery directory inside the configuration directory
+pub const RECOVERY_DIR_NAME: &str = "recovery";
+
+/// Default number of snapshots kept per document
+pub const DEFAULT_RECOVERY_RETENTION: usize = 3;
+
+/// Extension used for recovery snapshots
+const SNAPSHOT_EXTENSION: &str = "psoc";
+
+/// Extension of snapshots that are still being written
+const TEMP_EXTENSION: &str = "tmp";
+
+/// A recoverable document found in the recovery directory
+#[derive(Debug, Clone)]
+pub struct RecoveryEntry {
+    /// Identifier of the recovered document
+    pub document_id: Uuid,
+    /// Document title at the time of the snapshot
+    pub title: String,
+    /// File the document was last loaded from or saved to, if any
+    pub original_path: Option<PathBuf>,
+    /// When the snapshot was written
+    pub saved_at: SystemTime,
+    /// Path of the newest snapshot
+    pub snapshot_path: PathBuf,
+}
+
+impl RecoveryEntry {
+    /// Minutes elapsed since the snapshot was written
+    pub fn minutes_ago(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(self.saved_at)
+            .unwrap_or_default()
+            .as_secs()
+            / 60
+    }
+}
+
+/// Writes, lists and cleans up autosave snapshots
+#[derive(Debug, Clone)]
+pub struct RecoveryManager {
+    /// Directory holding the snapshots
+    recovery_dir: PathBuf,
+    /// Number of snapshots kept per document
+    retention: usize,
+    /// Generation of each document's snapshots, bumped when they are discarded
+    generations: Arc<Mutex<HashMap<Uuid, u64>>>,
+}
+
+impl RecoveryManager {
+    /// Create a recovery manager for the given directory
+    pub fn new<P: Into<PathBuf>>(recovery_dir: P, retention: usize) -> Self {
+        Self {
+            recovery_dir: recovery_dir.into(),
+            retention: retention.max(1),
+            generations: Arc::default(),
+        }
+    }
+
+    /// Create a recovery manager using the directory next to a preferences file
+    pub fn for_preferences_path<P: AsRef<Path>>(preferences_path: P, retention: usize) -> Self {
+        let config_dir = preferences_path
+            .as_ref()
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        Self::new(config_dir.join(RECOVERY_DIR_NAME), retention)
+    }
+
+    /// Get the recovery directory
+    pub fn recovery_dir(&self) -> &Path {
+        &self.recovery_dir
+    }
+
+    /// Get the number of snapshots kept per document
+    pub fn retention(&self) -> usize {
+        self.retention
+    }
+
+    /// Set the number of snapshots kept per document (at least one)
+    pub fn set_retention(&mut self, retention: usize) {
+        self.retention = retention.max(1);
+    }
+
+    /// Get the current snapshot generation of a document
+    ///
+    /// Take this when an autosave is scheduled and pass it to
+    /// [`autosave_at`](Self::autosave_at), so that a discard issued in the
+    /// meantime is not undone by the late snapshot.
+    pub fn generation(&self, document_id: Uuid) -> u64 {
+        self.lock_generations()
+            .get(&document_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Write a snapshot of a document and prune older snapshots beyond the retention
+    pub fn autosave(&self, document: &Document) -> Result<Option<PathBuf>> {
+        self.autosave_at(document, self.generation(document.id))
+    }
+
+    /// Write a snapshot of a document taken at the given generation
+    ///
+    /// Returns `None` without leaving anything behind if the document's
+    /// snapshots were discarded after the generation was taken.
+    #[instrument(skip_all, fields(document_id = %document.id))]
+    pub fn autosave_at(&self, document: &Document, generation: u64) -> Result<Option<PathBuf>> {
+        fs::create_dir_all(&self.recovery_dir).with_context(|| {
+            format!(
+                "Failed to create recovery directory: {}",
+                self.recovery_dir.display()
+            )
+        })?;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let file_name = format!("{}-{}.{}", document.id, timestamp, SNAPSHOT_EXTENSION);
+        let snapshot_path = self.recovery_dir.join(&file_name);
+        let temp_path = self
+            .recovery_dir
+            .join(format!(".{}.{}", file_name, TEMP_EXTENSION));
+
+        // Write to a temporary file first so a crash mid-write never leaves a
+        // truncated snapshot that looks valid
+        let options = psoc_file_formats::ProjectOptions {
+            include_thumbnail: false,
+            ..Default::default()
+        };
+        if let Err(e) = psoc_file_formats::save_project_with_options(document, &temp_path, &options)
+        {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e.context("Failed to write recovery snapshot"));
+        }
+
+        // Hold the lock until the snapshot is in place so a discard cannot
+        // slip in between the check and the rename
+        let generations = self.lock_generations();
+        if generations.get(&document.id).copied().unwrap_or_default() != generation {
+            drop(generations);
+            debug!("Document was saved or closed during autosave, dropping snapshot");
+            fs::remove_file(&temp_path).with_context(|| {
+                format!(
+                    "Failed to remove recovery snapshot: {}",
+                    temp_path.display()
+                )
+            })?;
+            return Ok(None);
+        }
+        fs::rename(&temp_path, &snapshot_path).with_context(|| {
+            format!(
+                "Failed to finalize recovery snapshot: {}",
+                snapshot_path.display()
+            )
+        })?;
+        drop(generations);
+
+        self.prune(document.id)?;
+
+        debug!("Autosaved document to: {}", snapshot_path.display());
+        Ok(Some(snapshot_path))
+    }
+
+    /// List the newest readable snapshot of every recoverable document, newest first
+    #[instrument(skip_all)]
+    pub fn list_recoverable(&self) -> Result<Vec<RecoveryEntry>> {
+        let mut by_document: std::collections::HashMap<Uuid, Vec<(SystemTime, PathBuf)>> =
+            std::collections::HashMap::new();
+        for (document_id, saved_at, path) in self.snapshots()? {
+            by_document
+                .entry(document_id)
+                .or_default()
+                .push((saved_at, path));
+        }
+
+        let mut entries = Vec::new();
+        for (document_id, mut snapshots) in by_document {
+            snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.0));
+
+            // Fall back to older snapshots if the newest cannot be read
+            for (saved_at, snapshot_path) in snapshots {
+                match psoc_file_formats::ProjectArchive::open(&snapshot_path) {
+                    Ok(archive) => {
+                        let document = &archive.project().document;
+                        entries.push(RecoveryEntry {
+                            document_id,
+                            title: document.metadata.title.clone(),
+                            original_path: document.file_path.clone(),
+                            saved_at,
+                            snapshot_path,
+                        });
+                        break;
+                    }
+                    Err(e) => {
+                        warn!(
+                            path = %snapshot_path.display(),
+                            error = %e,
+                            "Skipping unreadable recovery snapshot"
+                        );
+                    }
+                }
+            }
+        }
+
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.saved_at));
+        info!(count = entries.len(), "Found recoverable documents");
+        Ok(entries)
+    }
+
+    /// Load the document from a recovery entry
+    ///
+    /// The restored document is marked dirty, since its changes were never saved.
+    #[instrument(skip_all, fields(document_id = %entry.document_id))]
+    pub fn restore(&self, entry: &RecoveryEntry) -> Result<Document> {
+        let mut document = psoc_file_formats::load_project(&entry.snapshot_path)
+            .with_context(|| format!("Failed to restore document '{}'", entry.title))?;
+        document.mark_dirty();
+
+        info!(title = %entry.title, "Restored document from recovery snapshot");
+        Ok(document)
+    }
+
+    /// Remove every snapshot of a document, including any still being written
+    pub fn discard(&self, document_id: Uuid) -> Result<()> {
+        let mut generations = self.lock_generations();
+        *generations.entry(document_id).or_default() += 1;
+
+        for (id, _, path) in self.snapshots()? {
+            if id == document_id {
+                fs::remove_file(&path).with_context(|| {
+                    format!("Failed to remove recovery snapshot: {}", path.display())
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove snapshots left half-written by an interrupted autosave
+    ///
+    /// Only call this when no autosave is running, i.e. at startup.
+    pub fn remove_temp_files(&self) -> Result<usize> {
+        if !self.recovery_dir.exists() {
+            return Ok(0);
+        }
+
+        let mut removed = 0;
+        let entries = fs::read_dir(&self.recovery_dir).with_context(|| {
+            format!(
+                "Failed to read recovery directory: {}",
+                self.recovery_dir.display()
+            )
+        })?;
+        for entry in entries {
+            let path = entry?.path();
+            if is_temp_snapshot_name(&path) {
+                debug!("Removing interrupted recovery snapshot: {}", path.display());
+                fs::remove_file(&path).with_context(|| {
+                    format!("Failed to remove recovery snapshot: {}", path.display())
+                })?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Remove every snapshot in the recovery directory
+    pub fn discard_all(&self) -> Result<()> {
+        for (_, _, path) in self.snapshots()? {
+            fs::remove_file(&path).with_context(|| {
+                format!("Failed to remove recovery snapshot: {}", path.display())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Remove the oldest snapshots of a document beyond the retention
+    fn prune(&self, document_id: Uuid) -> Result<()> {
+        let mut snapshots: Vec<_> = self
+            .snapshots()?
+            .into_iter()
+            .filter(|(id, _, _)| *id == document_id)
+            .collect();
+        snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.1));
+
+        for (_, _, path) in snapshots.into_iter().skip(self.retention) {
+            debug!("Pruning recovery snapshot: {}", path.display());
+            fs::remove_file(&path).with_context(|| {
+                format!("Failed to remove recovery snapshot: {}", path.display())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Lock the generation table, recovering it if a writer panicked
+    fn lock_generations(&self) -> MutexGuard<'_, HashMap<Uuid, u64>> {
+        self.generations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// All snapshots in the recovery directory as (document id, time, path)
+    fn snapshots(&self) -> Result<Vec<(Uuid, SystemTime, PathBuf)>> {
+        if !self.recovery_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut snapshots = Vec::new();
+        let entries = fs::read_dir(&self.recovery_dir).with_context(|| {
+            format!(
+                "Failed to read recovery directory: {}",
+                self.recovery_dir.display()
+            )
+        })?;
+        for entry in entries {
+            let path = entry?.path();
+            if let Some((document_id, saved_at)) = parse_snapshot_name(&path) {
+                snapshots.push((document_id, saved_at, path));
+            }
+        }
+        Ok(snapshots)
+    }
+}
+
+/// Check for `.<snapshot name>.tmp` files written by an unfinished autosave
+fn is_temp_snapshot_name(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some(TEMP_EXTENSION)
+        && path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('.'))
+}
+
+/// Parse `<document id>-<unix millis>.psoc` snapshot file names
+fn parse_snapshot_name(path: &Path) -> Option<(Uuid, SystemTime)> {
+    if path.extension().and_then(|ext| ext.to_str()) != Some(SNAPSHOT_EXTENSION) {
+        return None;
+    }
+
+    let stem = path.file_stem()?.to_str()?;
+    let (id, millis) = stem.rsplit_once('-')?;
+    let document_id = Uuid::parse_str(id).ok()?;
+    let millis: u64 = millis.parse().ok()?;
+
+    Some((document_id, UNIX_EPOCH + Duration::from_millis(millis)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use psoc_core::{Layer, RgbaPixel};
+    use tempfile::tempdir;
+
+    fn test_document(title: &str) -> Document {
+        let mut document = Document::new(title.to_string(), 16, 16);
+        let mut layer = Layer::new_pixel("Paint".to_string(), 16, 16);
+        layer.fill(RgbaPixel::new(10, 200, 30, 255));
+        document.add_layer(layer);
+        document
+    }
+
+    #[test]
+    fn test_for_preferences_path() {
+        let manager = RecoveryManager::for_preferences_path("/config/psoc/preferences.ron", 0);
+        assert_eq!(
+            manager.recovery_dir(),
+            Path::new("/config/psoc").join(RECOVERY_DIR_NAME)
+        );
+        assert_eq!(manager.retention(), 1);
+    }
+
+    #[test]
+    fn test_autosave_and_restore() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let manager = RecoveryManager::new(temp_dir.path().join("recovery"), 3);
+
+        let mut document = test_document("Unsaved Work");
+        document.file_path = Some(PathBuf::from("/tmp/work.png"));
+        manager.autosave(&document)?;
+
+        let entries = manager.list_recoverable()?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].document_id, document.id);
+        assert_eq!(entries[0].title, "Unsaved Work");
+        assert_eq!(
+            entries[0].original_path,
+            Some(PathBuf::from("/tmp/work.png"))
+        );
+
+        let restored = manager.restore(&entries[0])?;
+        assert!(restored.is_dirty);
+        assert_eq!(
+            restored.layers[0].get_pixel(5, 5),
+            Some(RgbaPixel::new(10, 200, 30, 255))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_retention_prunes_old_snapshots() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let manager = RecoveryManager::new(temp_dir.path(), 2);
+        let document = test_document("Busy");
+
+        for _ in 0..4 {
+            manager.autosave(&document)?;
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        assert_eq!(manager.snapshots()?.len(), 2);
+        assert_eq!(manager.list_recoverable()?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_discard() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let manager = RecoveryManager::new(temp_dir.path(), 3);
+        let first = test_document("First");
+        let second = test_document("Second");
+
+        manager.autosave(&first)?;
+        manager.autosave(&second)?;
+        assert_eq!(manager.list_recoverable()?.len(), 2);
+
+        manager.discard(first.id)?;
+        let entries = manager.list_recoverable()?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].title, "Second");
+
+        manager.discard_all()?;
+        assert!(manager.list_recoverable()?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unreadable_snapshots_fall_back_and_strays_are_ignored() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let manager = RecoveryManager::new(temp_dir.path(), 3);
+        let document = test_document("Fallback");
+        manager.autosave(&document)?;
+
+        // A newer but corrupt snapshot, plus unrelated files
+        let corrupt = temp_dir
+            .path()
+            .join(format!("{}-{}.psoc", document.id, u64::MAX / 2));
+        fs::write(&corrupt, b"not a project")?;
+        fs::write(temp_dir.path().join("notes.txt"), b"hello")?;
+        fs::write(temp_dir.path().join("garbage-123.psoc"), b"")?;
+
+        let entries = manager.list_recoverable()?;
+        assert_eq!(entries.len(), 1);
+        assert_ne!(entries[0].snapshot_path, corrupt);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_autosave_after_discard_leaves_nothing_behind() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let manager = RecoveryManager::new(temp_dir.path(), 3);
+        let document = test_document("Saved Meanwhile");
+
+        // The autosave was scheduled, then the document was saved before it ran
+        let generation = manager.generation(document.id);
+        manager.discard(document.id)?;
+        assert!(manager.autosave_at(&document, generation)?.is_none());
+        assert_eq!(fs::read_dir(temp_dir.path())?.count(), 0);
+
+        // Autosaves scheduled after the discard still work
+        assert!(manager.autosave(&document)?.is_some());
+        assert_eq!(manager.list_recoverable()?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_temp_files() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let manager = RecoveryManager::new(temp_dir.path(), 3);
+        let document = test_document("Interrupted");
+        manager.autosave(&document)?;
+
+        let interrupted = temp_dir
+            .path()
+            .join(format!(".{}-123.psoc.tmp", document.id));
+        fs::write(&interrupted, b"half a project")?;
+        fs::write(temp_dir.path().join("notes.tmp"), b"not ours")?;
+
+        assert_eq!(manager.remove_temp_files()?, 1);
+        assert!(!interrupted.exists());
+        assert!(temp_dir.path().join("notes.tmp").exists());
+        assert_eq!(manager.list_recoverable()?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_directory_has_nothing_to_recover() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let manager = RecoveryManager::new(temp_dir.path().join("missing"), 3);
+        assert!(manager.list_recoverable()?.is_empty());
+        assert_eq!(manager.remove_temp_files()?, 0);
+        manager.discard_all()?;
+
+        Ok(())
+    }
+}
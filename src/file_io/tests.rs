@@ -255,6 +255,43 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_load_project_leaves_hidden_layers_pending() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let project_path = temp_dir.path().join("hidden.psoc");
+
+        let mut document = Document::new("Hidden Layers".to_string(), 20, 20);
+        let mut visible = Layer::new_pixel("Visible".to_string(), 20, 20);
+        visible.fill(RgbaPixel::new(255, 0, 0, 255));
+        let mut hidden = Layer::new_pixel("Hidden".to_string(), 20, 20);
+        hidden.fill(RgbaPixel::new(0, 0, 255, 255));
+        hidden.visible = false;
+        document.add_layer(visible);
+        document.add_layer(hidden);
+
+        let file_manager = FileManager::new();
+        file_manager.save_project(&document, &project_path).await?;
+        let loaded = file_manager.load_document(&project_path).await?;
+
+        assert!(!loaded.layers[0].is_pending());
+        assert!(loaded.layers[1].is_pending());
+
+        // Exports decode pending layers on their own copy
+        let options = psoc_file_formats::LayerExportOptions {
+            layer_indices: Some(vec![1]),
+            ..Default::default()
+        };
+        let paths = file_manager
+            .export_layers(&loaded, temp_dir.path(), &options)
+            .await?;
+        assert_eq!(paths.len(), 1);
+        let exported = image::open(&paths[0])?.to_rgba8();
+        assert_eq!(exported.get_pixel(5, 5).0, [0, 0, 255, 255]);
+        assert!(loaded.layers[1].is_pending());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_export_flattened() -> anyhow::Result<()> {
         let temp_dir = tempdir()?;
@@ -290,6 +327,50 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_save_keeps_metadata_and_can_strip_gps() -> anyhow::Result<()> {
+        let temp_dir = tempdir()?;
+        let source_path = temp_dir.path().join("photo.png");
+        let kept_path = temp_dir.path().join("kept.png");
+        let stripped_path = temp_dir.path().join("stripped.png");
+
+        let mut document = Document::new("Photo".to_string(), 4, 4);
+        let mut layer = Layer::new_pixel("Background".to_string(), 4, 4);
+        layer.fill(RgbaPixel::new(10, 20, 30, 255));
+        document.add_layer(layer);
+        document.metadata.embedded.xmp = Some(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"><rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:exif="http://ns.adobe.com/exif/1.0/" exif:GPSAltitude="35/1" dc:format="image/png"/></rdf:RDF></x:xmpmeta>"#
+                .to_string(),
+        );
+
+        let file_manager = FileManager::new();
+        file_manager
+            .export_flattened_with_metadata(&document, &source_path, false)
+            .await?;
+
+        // Loading keeps the metadata so saving again writes it back
+        let loaded = file_manager.load_document(&source_path).await?;
+        let xmp = loaded.metadata.embedded.xmp.clone().unwrap();
+        assert!(xmp.contains("GPSAltitude"));
+
+        file_manager
+            .export_flattened_with_metadata(&loaded, &kept_path, false)
+            .await?;
+        file_manager
+            .export_flattened_with_metadata(&loaded, &stripped_path, true)
+            .await?;
+
+        let kept = file_manager.load_document(&kept_path).await?;
+        assert!(kept.metadata.embedded.xmp.unwrap().contains("GPSAltitude"));
+
+        let stripped = file_manager.load_document(&stripped_path).await?;
+        let stripped_xmp = stripped.metadata.embedded.xmp.unwrap();
+        assert!(!stripped_xmp.contains("GPSAltitude"));
+        assert!(stripped_xmp.contains("image/png"));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_project_roundtrip_with_complex_layers() -> anyhow::Result<()> {
         let temp_dir = tempdir()?;
@@ -5,6 +5,7 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use tracing::{debug, error, info, warn};
 
+use crate::file_io::RecoveryManager;
 use crate::ui::dialogs::preferences::UserPreferences;
 
 /// Preferences manager for handling user settings
@@ -195,6 +196,17 @@ impl PreferencesManager {
             changed = true;
         }
 
+        if self.preferences.defaults.auto_save_retention < 1
+            || self.preferences.defaults.auto_save_retention > 20
+        {
+            warn!(
+                "Invalid auto-save retention: {}, resetting to 3",
+                self.preferences.defaults.auto_save_retention
+            );
+            self.preferences.defaults.auto_save_retention = 3;
+            changed = true;
+        }
+
         if self.preferences.defaults.max_undo_history < 10
             || self.preferences.defaults.max_undo_history > 1000
         {
@@ -251,6 +263,22 @@ impl PreferencesManager {
         &self.preferences_path
     }
 
+    /// Get the autosave recovery directory, next to the preferences file
+    pub fn recovery_directory(&self) -> PathBuf {
+        crate::file_io::RecoveryManager::for_preferences_path(&self.preferences_path, 1)
+            .recovery_dir()
+            .to_path_buf()
+    }
+
+    /// Create a recovery manager configured from the given preferences
+    pub fn default_recovery_manager(preferences: &UserPreferences) -> Result<RecoveryManager> {
+        let preferences_path = Self::get_preferences_path()?;
+        Ok(RecoveryManager::for_preferences_path(
+            preferences_path,
+            preferences.defaults.auto_save_retention as usize,
+        ))
+    }
+
     /// Check if preferences file exists
     pub fn preferences_file_exists(&self) -> bool {
         self.preferences_path.exists()
@@ -327,6 +355,7 @@ mod tests {
         manager.preferences.interface.ui_scale = 5.0; // Invalid
         manager.preferences.performance.memory_limit = 100; // Invalid
         manager.preferences.defaults.max_undo_history = 5; // Invalid
+        manager.preferences.defaults.auto_save_retention = 0; // Invalid
         manager.preferences.defaults.default_canvas_color = [-1.0, 2.0, 0.5, 1.0]; // Invalid
 
         let changed = manager.validate_and_fix();
@@ -336,6 +365,7 @@ mod tests {
         assert_eq!(manager.preferences.interface.ui_scale, 1.0);
         assert_eq!(manager.preferences.performance.memory_limit, 2048);
         assert_eq!(manager.preferences.defaults.max_undo_history, 100);
+        assert_eq!(manager.preferences.defaults.auto_save_retention, 3);
         assert_eq!(
             manager.preferences.defaults.default_canvas_color,
             [1.0, 1.0, 0.5, 1.0]
@@ -366,6 +396,58 @@ mod tests {
         assert_eq!(loaded_preferences.performance.memory_limit, 4096);
     }
 
+    #[test]
+    fn test_preferences_without_auto_save_retention_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let preferences_path = temp_dir.path().join("old_preferences.ron");
+
+        // Preference files written before the retention setting existed
+        let content = ron::ser::to_string_pretty(
+            &UserPreferences::default(),
+            ron::ser::PrettyConfig::default(),
+        )
+        .unwrap()
+        .lines()
+        .filter(|line| !line.contains("auto_save_retention"))
+        .collect::<Vec<_>>()
+        .join("\n");
+        std::fs::write(&preferences_path, content).unwrap();
+
+        let loaded_preferences = PreferencesManager::load_preferences(&preferences_path).unwrap();
+        assert_eq!(loaded_preferences.defaults.auto_save_retention, 3);
+    }
+
+    #[test]
+    fn test_palettes_persist_in_preferences() {
+        use crate::ui::dialogs::color_palette::ColorPalette;
+        use psoc_core::RgbaPixel;
+
+        let temp_dir = TempDir::new().unwrap();
+        let preferences_path = temp_dir.path().join("preferences.ron");
+        let mut manager = PreferencesManager {
+            preferences_path: preferences_path.clone(),
+            preferences: UserPreferences::default(),
+        };
+
+        let mut brand = ColorPalette::new("Brand".to_string());
+        brand.add_color(RgbaPixel::new(18, 52, 86, 255));
+        let mut preferences = manager.preferences().clone();
+        preferences.palettes = vec![brand.clone()];
+        manager.update_preferences(preferences).unwrap();
+
+        let loaded_preferences = PreferencesManager::load_preferences(&preferences_path).unwrap();
+        assert_eq!(loaded_preferences.palettes, vec![brand]);
+    }
+
+    #[test]
+    fn test_recovery_directory_is_next_to_preferences() {
+        let manager = PreferencesManager::default();
+        assert_eq!(
+            manager.recovery_directory().parent(),
+            manager.preferences_path().parent()
+        );
+    }
+
     #[test]
     fn test_preferences_file_not_found() {
         let temp_dir = TempDir::new().unwrap();
@@ -8,13 +8,49 @@ pub use psoc_core::rendering::*;
 
 use crate::core::{Document, PixelData};
 use crate::utils::Result;
-use std::cell::RefCell;
+use psoc_core::{Layer, ToneMapping};
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use tracing::{debug, instrument};
+use uuid::Uuid;
 
 /// High-level rendering utilities for the PSOC application
 #[derive(Debug)]
 pub struct AppRenderer {
     engine: RefCell<psoc_core::rendering::RenderEngine>,
+    /// Tone mapping used to display layers with HDR data
+    tone_mapping: Cell<ToneMapping>,
+    /// Display pixels of HDR layers, keyed by layer id
+    hdr_cache: RefCell<HashMap<Uuid, HdrDisplay>>,
+}
+
+/// Cached display pixels of an HDR layer
+#[derive(Debug)]
+struct HdrDisplay {
+    /// Tone mapping `mapped` was produced with
+    tone_mapping: ToneMapping,
+    /// Tone-mapped HDR source
+    mapped: PixelData,
+    /// Layer pixels that `edited` was built against
+    source: PixelData,
+    /// `mapped` with 8-bit edits copied over, if the layer has any
+    edited: Option<PixelData>,
+}
+
+impl HdrDisplay {
+    /// Pixels to composite for the layer
+    fn pixels(&self) -> &PixelData {
+        self.edited.as_ref().unwrap_or(&self.mapped)
+    }
+}
+
+/// Mapped pixels with the layer's edits copied over, if it has any
+fn edited_display(layer: &Layer, mapped: &PixelData) -> Option<PixelData> {
+    match layer.hdr_display(mapped)? {
+        Cow::Owned(pixels) => Some(pixels),
+        Cow::Borrowed(_) => None,
+    }
 }
 
 impl Default for AppRenderer {
@@ -28,6 +64,8 @@ impl AppRenderer {
     pub fn new() -> Self {
         Self {
             engine: RefCell::new(psoc_core::rendering::RenderEngine::new()),
+            tone_mapping: Cell::new(ToneMapping::default()),
+            hdr_cache: RefCell::new(HashMap::new()),
         }
     }
 
@@ -38,16 +76,88 @@ impl AppRenderer {
                 parallel_enabled,
                 tile_size,
             )),
+            tone_mapping: Cell::new(ToneMapping::default()),
+            hdr_cache: RefCell::new(HashMap::new()),
         }
     }
 
+    /// Get the tone mapping used to display HDR layers
+    pub fn tone_mapping(&self) -> ToneMapping {
+        self.tone_mapping.get()
+    }
+
+    /// Set the tone mapping used to display HDR layers
+    pub fn set_tone_mapping(&self, tone_mapping: ToneMapping) {
+        debug!("Setting display tone mapping: {:?}", tone_mapping);
+        self.tone_mapping.set(tone_mapping);
+    }
+
     /// Render document for display in the UI
+    ///
+    /// Layers carrying HDR data are shown from their floating-point pixels
+    /// through the current tone mapping rather than their 8-bit copy. Their
+    /// display pixels are cached until the tone mapping or the layer changes.
     #[instrument(skip(self, document))]
     pub fn render_for_display(&self, document: &Document) -> Result<PixelData> {
         debug!("Rendering document for display");
+        if !document.layers.iter().any(|layer| layer.is_hdr()) {
+            self.hdr_cache.borrow_mut().clear();
+            return self
+                .engine
+                .borrow_mut()
+                .render_document(document)
+                .map_err(Into::into);
+        }
+
+        let tone_mapping = self.tone_mapping.get();
+        let mut cache = self.hdr_cache.borrow_mut();
+        cache.retain(|id, _| {
+            document
+                .layers
+                .iter()
+                .any(|layer| layer.id == *id && layer.is_hdr())
+        });
+
+        for layer in &document.layers {
+            let (Some(hdr), Some(pixels)) = (
+                layer.hdr_data.as_ref().filter(|_| layer.is_hdr()),
+                layer.pixel_data.as_ref(),
+            ) else {
+                continue;
+            };
+
+            match cache.get_mut(&layer.id) {
+                Some(display)
+                    if display.tone_mapping == tone_mapping
+                        && display.mapped.dimensions() == hdr.dimensions() =>
+                {
+                    // Only re-merge edits when the layer pixels changed
+                    if display.source != *pixels {
+                        display.edited = edited_display(layer, &display.mapped);
+                        display.source = pixels.clone();
+                    }
+                }
+                _ => {
+                    let mapped = tone_mapping.apply(hdr)?;
+                    let edited = edited_display(layer, &mapped);
+                    cache.insert(
+                        layer.id,
+                        HdrDisplay {
+                            tone_mapping,
+                            mapped,
+                            source: pixels.clone(),
+                            edited,
+                        },
+                    );
+                }
+            }
+        }
+
         self.engine
             .borrow_mut()
-            .render_document(document)
+            .render_document_with(document, |layer| {
+                cache.get(&layer.id).map(HdrDisplay::pixels)
+            })
             .map_err(Into::into)
     }
 
@@ -79,3 +189,88 @@ impl AppRenderer {
         f(&mut self.engine.borrow_mut())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use psoc_core::{HdrPixelData, Layer, RgbaPixel, ToneMapOperator};
+
+    fn hdr_document() -> Document {
+        let hdr = HdrPixelData::new(1, 1, vec![4.0, 1.0, 0.25, 1.0]).unwrap();
+        let mut document = Document::new("HDR".to_string(), 1, 1);
+        document.add_layer(
+            Layer::from_hdr("Render".to_string(), hdr, &ToneMapping::default()).unwrap(),
+        );
+        document
+    }
+
+    #[test]
+    fn test_render_for_display_uses_tone_mapping() {
+        let renderer = AppRenderer::new();
+        let document = hdr_document();
+
+        let default = renderer.render_for_display(&document).unwrap();
+        assert_eq!(default.get_pixel(0, 0).unwrap().r, 255);
+
+        renderer.set_tone_mapping(ToneMapping::new(ToneMapOperator::ExposureGamma, -3.0, 2.2));
+        let darker = renderer.render_for_display(&document).unwrap();
+        assert!(darker.get_pixel(0, 0).unwrap().r < 255);
+        assert!(darker.get_pixel(0, 0).unwrap().g < default.get_pixel(0, 0).unwrap().g);
+    }
+
+    #[test]
+    fn test_filmic_keeps_highlight_detail() {
+        let renderer = AppRenderer::new();
+        renderer.set_tone_mapping(ToneMapping::filmic());
+        let pixel = renderer
+            .render_for_display(&hdr_document())
+            .unwrap()
+            .get_pixel(0, 0)
+            .unwrap();
+        assert!(pixel.r < 255);
+        assert!(pixel.r > pixel.g);
+    }
+
+    #[test]
+    fn test_display_shows_edits_to_hdr_layer() {
+        let renderer = AppRenderer::new();
+        renderer.set_tone_mapping(ToneMapping::new(ToneMapOperator::ExposureGamma, -3.0, 2.2));
+        let mut document = hdr_document();
+        let painted = RgbaPixel::new(10, 200, 30, 255);
+        document.layers[0]
+            .pixel_data
+            .as_mut()
+            .unwrap()
+            .set_pixel(0, 0, painted)
+            .unwrap();
+
+        let pixel = renderer
+            .render_for_display(&document)
+            .unwrap()
+            .get_pixel(0, 0)
+            .unwrap();
+        assert_eq!(pixel, painted);
+    }
+
+    #[test]
+    fn test_display_cache_follows_edits_and_tone_mapping() {
+        let renderer = AppRenderer::new();
+        let mut document = hdr_document();
+        let mapped = renderer.render_for_display(&document).unwrap();
+
+        let painted = RgbaPixel::new(10, 200, 30, 255);
+        let original = document.layers[0].get_pixel(0, 0).unwrap();
+        document.layers[0].set_pixel(0, 0, painted).unwrap();
+        let edited = renderer.render_for_display(&document).unwrap();
+        assert_eq!(edited.get_pixel(0, 0), Some(painted));
+
+        // Reverting the edit shows the HDR source again
+        document.layers[0].set_pixel(0, 0, original).unwrap();
+        let reverted = renderer.render_for_display(&document).unwrap();
+        assert_eq!(reverted.get_pixel(0, 0), mapped.get_pixel(0, 0));
+
+        renderer.set_tone_mapping(ToneMapping::new(ToneMapOperator::ExposureGamma, -3.0, 2.2));
+        let darker = renderer.render_for_display(&document).unwrap();
+        assert_ne!(darker.get_pixel(0, 0), mapped.get_pixel(0, 0));
+    }
+}
@@ -4,6 +4,7 @@
 //! to our internal shortcut system types.
 
 use super::shortcut_types::{ShortcutKey, ShortcutModifiers};
+use crate::tools::tool_trait::{Key, KeyModifiers};
 
 /// Convert iced keyboard key to our ShortcutKey
 pub fn iced_key_to_shortcut_key(key: &iced::keyboard::Key) -> Option<ShortcutKey> {
@@ -60,6 +61,33 @@ pub fn iced_modifiers_to_shortcut_modifiers(
     }
 }
 
+/// Convert iced keyboard modifiers to the modifiers passed to tools
+pub fn iced_modifiers_to_tool_modifiers(modifiers: iced::keyboard::Modifiers) -> KeyModifiers {
+    KeyModifiers {
+        shift: modifiers.shift(),
+        ctrl: modifiers.control(),
+        alt: modifiers.alt(),
+        meta: modifiers.logo(),
+    }
+}
+
+/// Convert an iced key to a key forwarded to the active tool
+///
+/// Only the keys tools respond to are converted; everything else stays a
+/// shortcut.
+pub fn iced_key_to_tool_key(key: &iced::keyboard::Key) -> Option<Key> {
+    match key {
+        iced::keyboard::Key::Named(named_key) => match named_key {
+            iced::keyboard::key::Named::Enter => Some(Key::Enter),
+            iced::keyboard::key::Named::Escape => Some(Key::Escape),
+            iced::keyboard::key::Named::Backspace => Some(Key::Backspace),
+            iced::keyboard::key::Named::Delete => Some(Key::Delete),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 /// Check if a key event represents a printable character
 pub fn is_printable_key(key: &iced::keyboard::Key) -> bool {
     matches!(key, iced::keyboard::Key::Character(_))
@@ -202,6 +230,18 @@ mod tests {
         assert!(!shortcut_modifiers.meta);
     }
 
+    #[test]
+    fn test_tool_key_conversion() {
+        let enter = iced::keyboard::Key::Named(iced::keyboard::key::Named::Enter);
+        assert_eq!(iced_key_to_tool_key(&enter), Some(Key::Enter));
+
+        let backspace = iced::keyboard::Key::Named(iced::keyboard::key::Named::Backspace);
+        assert_eq!(iced_key_to_tool_key(&backspace), Some(Key::Backspace));
+
+        let character = iced::keyboard::Key::Character("a".into());
+        assert_eq!(iced_key_to_tool_key(&character), None);
+    }
+
     #[test]
     fn test_printable_key_detection() {
         let char_key = iced::keyboard::Key::Character("a".into());
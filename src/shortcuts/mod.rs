@@ -7,7 +7,10 @@ pub mod keyboard_events;
 pub mod shortcut_manager;
 pub mod shortcut_types;
 
-pub use keyboard_events::{iced_key_to_shortcut_key, iced_modifiers_to_shortcut_modifiers};
+pub use keyboard_events::{
+    iced_key_to_shortcut_key, iced_key_to_tool_key, iced_modifiers_to_shortcut_modifiers,
+    iced_modifiers_to_tool_modifiers,
+};
 pub use shortcut_manager::ShortcutManager;
 pub use shortcut_types::{Shortcut, ShortcutAction, ShortcutKey, ShortcutModifiers};
 
@@ -249,6 +249,21 @@ impl ShortcutManager {
                 ShortcutModifiers::new().with_ctrl(true),
                 ShortcutAction::DeselectAll,
             ),
+            Shortcut::new(
+                ShortcutKey::Character('q'),
+                ShortcutModifiers::new(),
+                ShortcutAction::ToggleQuickMask,
+            ),
+            Shortcut::new(
+                ShortcutKey::Character('o'),
+                ShortcutModifiers::new().with_ctrl(true).with_shift(true),
+                ShortcutAction::ColorRange,
+            ),
+            Shortcut::new(
+                ShortcutKey::Character('r'),
+                ShortcutModifiers::new().with_ctrl(true).with_alt(true),
+                ShortcutAction::RefineEdge,
+            ),
             // Tool shortcuts
             Shortcut::new(
                 ShortcutKey::Character('v'),
@@ -275,6 +290,11 @@ impl ShortcutManager {
                 ShortcutModifiers::new(),
                 ShortcutAction::TransformTool,
             ),
+            Shortcut::new(
+                ShortcutKey::Character('p'),
+                ShortcutModifiers::new(),
+                ShortcutAction::PenTool,
+            ),
             // View operations
             Shortcut::new(
                 ShortcutKey::Character('+'),
@@ -329,14 +349,18 @@ impl ShortcutManager {
             | ShortcutAction::Paste
             | ShortcutAction::SelectAll
             | ShortcutAction::DeselectAll
-            | ShortcutAction::InvertSelection => "Edit".to_string(),
+            | ShortcutAction::InvertSelection
+            | ShortcutAction::ToggleQuickMask
+            | ShortcutAction::ColorRange
+            | ShortcutAction::RefineEdge => "Edit".to_string(),
 
             ShortcutAction::SelectTool
             | ShortcutAction::BrushTool
             | ShortcutAction::EraserTool
             | ShortcutAction::MoveTool
             | ShortcutAction::TransformTool
-            | ShortcutAction::EyedropperTool => "Tools".to_string(),
+            | ShortcutAction::EyedropperTool
+            | ShortcutAction::PenTool => "Tools".to_string(),
 
             ShortcutAction::ZoomIn
             | ShortcutAction::ZoomOut
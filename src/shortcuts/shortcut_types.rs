@@ -124,6 +124,9 @@ pub enum ShortcutAction {
     SelectAll,
     DeselectAll,
     InvertSelection,
+    ToggleQuickMask,
+    ColorRange,
+    RefineEdge,
 
     // Tool operations
     SelectTool,
@@ -132,6 +135,7 @@ pub enum ShortcutAction {
     MoveTool,
     TransformTool,
     EyedropperTool,
+    PenTool,
 
     // View operations
     ZoomIn,
@@ -180,12 +184,16 @@ impl fmt::Display for ShortcutAction {
             ShortcutAction::SelectAll => "Select All",
             ShortcutAction::DeselectAll => "Deselect All",
             ShortcutAction::InvertSelection => "Invert Selection",
+            ShortcutAction::ToggleQuickMask => "Quick Mask",
+            ShortcutAction::ColorRange => "Color Range...",
+            ShortcutAction::RefineEdge => "Refine Edge...",
             ShortcutAction::SelectTool => "Select Tool",
             ShortcutAction::BrushTool => "Brush Tool",
             ShortcutAction::EraserTool => "Eraser Tool",
             ShortcutAction::MoveTool => "Move Tool",
             ShortcutAction::TransformTool => "Transform Tool",
             ShortcutAction::EyedropperTool => "Eyedropper Tool",
+            ShortcutAction::PenTool => "Pen Tool",
             ShortcutAction::ZoomIn => "Zoom In",
             ShortcutAction::ZoomOut => "Zoom Out",
             ShortcutAction::ZoomReset => "Zoom Reset",
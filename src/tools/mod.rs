@@ -16,6 +16,6 @@ pub use tool_manager::{ToolManager, ToolManagerError};
 pub use tool_trait::{Tool, ToolEvent, ToolResult, ToolState};
 pub use tools::{
     BrushTool, EllipseShapeTool, EllipseTool, EraserTool, GradientTool, LassoTool, LineTool,
-    MagicWandTool, MoveTool, PolygonTool, RectangleTool, SelectTool, TextTool, ToolType,
+    MagicWandTool, MoveTool, PenTool, PolygonTool, RectangleTool, SelectTool, TextTool, ToolType,
     TransformTool,
 };
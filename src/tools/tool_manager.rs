@@ -82,6 +82,8 @@ impl ToolManager {
         self.register_tool(ToolType::Crop, Box::new(CropTool::new()));
         // Eyedropper tool
         self.register_tool(ToolType::Eyedropper, Box::new(EyedropperTool::new()));
+        // Pen tool
+        self.register_tool(ToolType::Pen, Box::new(PenTool::new()));
     }
 
     /// Register a tool with the manager
@@ -90,6 +92,16 @@ impl ToolManager {
         self.tools.insert(tool_type, tool);
     }
 
+    /// Get a registered tool by its concrete type
+    pub fn tool<T: Tool + 'static>(&self, tool_type: ToolType) -> Option<&T> {
+        self.tools.get(&tool_type)?.as_any().downcast_ref()
+    }
+
+    /// Get a mutable registered tool by its concrete type
+    pub fn tool_mut<T: Tool + 'static>(&mut self, tool_type: ToolType) -> Option<&mut T> {
+        self.tools.get_mut(&tool_type)?.as_any_mut().downcast_mut()
+    }
+
     /// Get the currently active tool type
     pub fn active_tool_type(&self) -> Option<ToolType> {
         self.active_tool_type
@@ -252,6 +264,8 @@ impl ToolManager {
             ToolType::Crop => Box::new(CropTool::new()),
             // Eyedropper tool
             ToolType::Eyedropper => Box::new(EyedropperTool::new()),
+            // Pen tool
+            ToolType::Pen => Box::new(PenTool::new()),
         };
 
         Ok(tool)
@@ -514,7 +528,7 @@ mod tests {
     fn test_tool_manager_creation() {
         let manager = ToolManager::new();
         assert!(manager.active_tool_type.is_some());
-        assert_eq!(manager.available_tools().len(), 16); // Select, EllipseSelect, LassoSelect, MagicWand, Brush, Eraser, Move, Transform, Text, Gradient, Rectangle, Ellipse, Line, Polygon, Crop, Eyedropper
+        assert_eq!(manager.available_tools().len(), 17); // Select, EllipseSelect, LassoSelect, MagicWand, Brush, Eraser, Move, Transform, Text, Gradient, Rectangle, Ellipse, Line, Polygon, Crop, Eyedropper, Pen
     }
 
     #[test]
@@ -69,6 +69,8 @@ pub trait Tool: Debug + Send + Sync {
                 | ToolEvent::MouseReleased { .. }
                 | ToolEvent::MouseMoved { .. }
                 | ToolEvent::MouseDragged { .. }
+                | ToolEvent::KeyPressed { .. }
+                | ToolEvent::KeyReleased { .. }
         )
     }
 
@@ -89,6 +91,9 @@ pub trait Tool: Debug + Send + Sync {
         None
     }
 
+    /// Get a reference to the tool as Any for downcasting
+    fn as_any(&self) -> &dyn std::any::Any;
+
     /// Get a mutable reference to the tool as Any for downcasting
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
 }
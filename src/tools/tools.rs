@@ -6,12 +6,17 @@
 use tracing::debug;
 
 use super::tool_trait::{
-    Key, Tool, ToolCursor, ToolEvent, ToolOption, ToolOptionType, ToolOptionValue, ToolResult,
-    ToolState,
+    Key, KeyModifiers, Tool, ToolCursor, ToolEvent, ToolOption, ToolOptionType, ToolOptionValue,
+    ToolResult, ToolState,
+};
+use psoc_core::{
+    Document, EdgeMap, MagicWand, MaskSelection, Path, PathAnchor, Point, RgbaPixel, Selection,
+    SelectionMode, Subpath, ToleranceMetric,
 };
-use psoc_core::{Document, Point, RgbaPixel, Selection};
 use serde::{Deserialize, Serialize};
 
+use crate::commands::{MaskSnapshot, PathSnapshot};
+
 /// Tool types available in the application
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ToolType {
@@ -34,6 +39,8 @@ pub enum ToolType {
     Crop,
     // Eyedropper tool
     Eyedropper,
+    // Pen tool
+    Pen,
 }
 
 impl std::fmt::Display for ToolType {
@@ -55,8 +62,104 @@ impl std::fmt::Display for ToolType {
             ToolType::Polygon => write!(f, "Polygon"),
             ToolType::Crop => write!(f, "Crop"),
             ToolType::Eyedropper => write!(f, "Eyedropper"),
+            ToolType::Pen => write!(f, "Pen"),
+        }
+    }
+}
+
+/// Selection modes offered by the selection tools, in option order
+const SELECTION_MODES: [SelectionMode; 4] = [
+    SelectionMode::Replace,
+    SelectionMode::Add,
+    SelectionMode::Subtract,
+    SelectionMode::Intersect,
+];
+
+/// Selection mode for a gesture
+///
+/// Shift adds, Alt subtracts and Shift+Alt intersects; without either
+/// modifier the mode chosen in the tool options is used.
+pub fn selection_mode_for(modifiers: KeyModifiers, default: SelectionMode) -> SelectionMode {
+    match (modifiers.shift, modifiers.alt) {
+        (true, true) => SelectionMode::Intersect,
+        (true, false) => SelectionMode::Add,
+        (false, true) => SelectionMode::Subtract,
+        (false, false) => default,
+    }
+}
+
+/// The "mode" option shared by the selection tools
+fn selection_mode_option(mode: SelectionMode) -> ToolOption {
+    ToolOption {
+        name: "mode".to_string(),
+        display_name: "Mode".to_string(),
+        description: "How new selections combine with the current one".to_string(),
+        option_type: ToolOptionType::Enum(
+            SELECTION_MODES
+                .iter()
+                .map(|mode| mode.to_string())
+                .collect(),
+        ),
+        default_value: ToolOptionValue::String(mode.to_string()),
+    }
+}
+
+/// Parse a value of the "mode" option
+fn parse_selection_mode(value: &ToolOptionValue) -> Option<SelectionMode> {
+    match value {
+        ToolOptionValue::String(name) => SELECTION_MODES
+            .into_iter()
+            .find(|mode| mode.to_string() == *name),
+        ToolOptionValue::Choice(index) => SELECTION_MODES.get(*index).copied(),
+        _ => None,
+    }
+}
+
+/// Selection state captured when a selection gesture starts
+#[derive(Debug, Default)]
+struct SelectionGesture {
+    /// Selection before the gesture
+    base: Selection,
+    /// How the gesture's shape combines with `base`
+    mode: SelectionMode,
+}
+
+impl SelectionGesture {
+    fn begin(document: &Document, modifiers: KeyModifiers, default: SelectionMode) -> Self {
+        Self {
+            base: document.selection.clone(),
+            mode: selection_mode_for(modifiers, default),
+        }
+    }
+
+    /// Show the shape being drawn
+    ///
+    /// Combining rasterizes the whole canvas, so the combination is only
+    /// computed once the gesture is committed.
+    fn preview(&self, document: &mut Document, shape: Selection) {
+        document.set_selection(shape);
+    }
+
+    /// Combine the finished shape with the selection from before the gesture
+    fn commit(&self, document: &mut Document, shape: Selection) {
+        let (width, height) = document.dimensions();
+        let selection = self.base.combine(&shape, self.mode, width, height);
+        debug!("Combined selection ({}): {}", self.mode, selection);
+        document.set_selection(selection);
+    }
+
+    /// End the gesture without a shape, restoring the previous selection
+    fn cancel(&self, document: &mut Document) {
+        match self.mode {
+            SelectionMode::Replace => document.set_selection(Selection::None),
+            _ => document.set_selection(self.base.clone()),
         }
     }
+
+    /// Abandon the gesture, putting back the selection from before it
+    fn abort(&self, document: &mut Document) {
+        document.set_selection(self.base.clone());
+    }
 }
 
 /// Selection tool for making selections
@@ -66,6 +169,8 @@ pub struct SelectTool {
     is_selecting: bool,
     feather_radius: f32,
     anti_alias: bool,
+    mode: SelectionMode,
+    gesture: SelectionGesture,
 }
 
 impl SelectTool {
@@ -75,6 +180,8 @@ impl SelectTool {
             is_selecting: false,
             feather_radius: 0.0,
             anti_alias: true,
+            mode: SelectionMode::Replace,
+            gesture: SelectionGesture::default(),
         }
     }
 }
@@ -109,8 +216,13 @@ impl Tool for SelectTool {
         state: &mut ToolState,
     ) -> ToolResult<()> {
         match event {
-            ToolEvent::MousePressed { position, .. } => {
+            ToolEvent::MousePressed {
+                position,
+                modifiers,
+                ..
+            } => {
                 debug!("Selection started at: {:?}", position);
+                self.gesture = SelectionGesture::begin(document, modifiers, self.mode);
                 self.selection_start = Some(position);
                 self.is_selecting = true;
                 state.is_active = true;
@@ -124,7 +236,7 @@ impl Tool for SelectTool {
                     // Update selection rectangle in real-time
                     if let Some(start) = self.selection_start {
                         let selection = Selection::rectangle_from_points(start, position);
-                        document.set_selection(selection);
+                        self.gesture.preview(document, selection);
                     }
                 }
             }
@@ -138,7 +250,7 @@ impl Tool for SelectTool {
                     if let Some(start) = self.selection_start {
                         let selection = Selection::rectangle_from_points(start, position);
                         debug!("Created selection: {}", selection);
-                        document.set_selection(selection);
+                        self.gesture.commit(document, selection);
                     }
 
                     self.selection_start = None;
@@ -168,11 +280,17 @@ impl Tool for SelectTool {
                 option_type: ToolOptionType::Bool,
                 default_value: ToolOptionValue::Bool(self.anti_alias),
             },
+            selection_mode_option(self.mode),
         ]
     }
 
     fn set_option(&mut self, name: &str, value: ToolOptionValue) -> ToolResult<()> {
         match name {
+            "mode" => {
+                if let Some(mode) = parse_selection_mode(&value) {
+                    self.mode = mode;
+                }
+            }
             "feather" => {
                 if let ToolOptionValue::Float(radius) = value {
                     self.feather_radius = radius.clamp(0.0, 50.0);
@@ -192,10 +310,15 @@ impl Tool for SelectTool {
         match name {
             "feather" => Some(ToolOptionValue::Float(self.feather_radius)),
             "anti_alias" => Some(ToolOptionValue::Bool(self.anti_alias)),
+            "mode" => Some(ToolOptionValue::String(self.mode.to_string())),
             _ => None,
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -208,6 +331,8 @@ pub struct EllipseTool {
     is_selecting: bool,
     feather_radius: f32,
     anti_alias: bool,
+    mode: SelectionMode,
+    gesture: SelectionGesture,
 }
 
 impl EllipseTool {
@@ -217,6 +342,8 @@ impl EllipseTool {
             is_selecting: false,
             feather_radius: 0.0,
             anti_alias: true,
+            mode: SelectionMode::Replace,
+            gesture: SelectionGesture::default(),
         }
     }
 }
@@ -251,8 +378,13 @@ impl Tool for EllipseTool {
         state: &mut ToolState,
     ) -> ToolResult<()> {
         match event {
-            ToolEvent::MousePressed { position, .. } => {
+            ToolEvent::MousePressed {
+                position,
+                modifiers,
+                ..
+            } => {
                 debug!("Ellipse selection started at: {:?}", position);
+                self.gesture = SelectionGesture::begin(document, modifiers, self.mode);
                 self.selection_start = Some(position);
                 self.is_selecting = true;
                 state.is_active = true;
@@ -266,7 +398,7 @@ impl Tool for EllipseTool {
                     // Update preview selection
                     if let Some(start) = self.selection_start {
                         let selection = Selection::ellipse_from_points(start, position);
-                        document.set_selection(selection);
+                        self.gesture.preview(document, selection);
                     }
                 }
             }
@@ -280,7 +412,7 @@ impl Tool for EllipseTool {
                     if let Some(start) = self.selection_start {
                         let selection = Selection::ellipse_from_points(start, position);
                         debug!("Created ellipse selection: {}", selection);
-                        document.set_selection(selection);
+                        self.gesture.commit(document, selection);
                     }
 
                     self.selection_start = None;
@@ -310,11 +442,17 @@ impl Tool for EllipseTool {
                 option_type: ToolOptionType::Bool,
                 default_value: ToolOptionValue::Bool(self.anti_alias),
             },
+            selection_mode_option(self.mode),
         ]
     }
 
     fn set_option(&mut self, name: &str, value: ToolOptionValue) -> ToolResult<()> {
         match name {
+            "mode" => {
+                if let Some(mode) = parse_selection_mode(&value) {
+                    self.mode = mode;
+                }
+            }
             "feather" => {
                 if let ToolOptionValue::Float(radius) = value {
                     self.feather_radius = radius.clamp(0.0, 50.0);
@@ -334,10 +472,15 @@ impl Tool for EllipseTool {
         match name {
             "feather" => Some(ToolOptionValue::Float(self.feather_radius)),
             "anti_alias" => Some(ToolOptionValue::Bool(self.anti_alias)),
+            "mode" => Some(ToolOptionValue::String(self.mode.to_string())),
             _ => None,
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -350,6 +493,8 @@ pub struct BrushTool {
     brush_color: RgbaPixel,
     brush_hardness: f32,
     is_painting: bool,
+    /// Mask being painted, recorded for undo when the stroke ends
+    mask_snapshot: Option<MaskSnapshot>,
 }
 
 impl BrushTool {
@@ -359,6 +504,7 @@ impl BrushTool {
             brush_color: RgbaPixel::new(0, 0, 0, 255), // Black
             brush_hardness: 1.0,
             is_painting: false,
+            mask_snapshot: None,
         }
     }
 }
@@ -398,6 +544,7 @@ impl Tool for BrushTool {
                 self.is_painting = true;
                 state.is_active = true;
                 state.last_position = Some(position);
+                self.mask_snapshot = MaskSnapshot::capture(document);
 
                 // Start painting at this position
                 self.paint_at_position(position, document)?;
@@ -419,6 +566,9 @@ impl Tool for BrushTool {
                     debug!("Brush stroke completed");
                     self.is_painting = false;
                     state.is_active = false;
+                    if let Some(snapshot) = self.mask_snapshot.take() {
+                        snapshot.commit(document, "Paint")?;
+                    }
                     // TODO: Commit brush stroke to history
                 }
             }
@@ -497,6 +647,10 @@ impl Tool for BrushTool {
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -518,8 +672,16 @@ impl BrushTool {
             position, self.brush_size, self.brush_color, mask_editing_mode
         );
 
-        // Get the active layer
-        let active_layer = document.active_layer_mut();
+        // In Quick Mask mode or on an alpha channel the brush paints that mask
+        if let Some(mask) = document.editing_mask_mut() {
+            return self.paint_circular_brush_on_mask(position, mask);
+        }
+
+        // Get the active layer, keeping the selection readable while painting
+        let selection = &document.selection;
+        let active_layer = document
+            .active_layer_index
+            .and_then(|index| document.layers.get_mut(index));
         if active_layer.is_none() {
             debug!("No active layer to paint on");
             return Ok(());
@@ -543,10 +705,10 @@ impl BrushTool {
         }
 
         // Paint a circular brush at the position
-        if mask_editing_mode {
-            self.paint_circular_brush_on_mask(position, layer)?;
+        if let (true, Some(mask)) = (mask_editing_mode, layer.mask.as_mut()) {
+            self.paint_circular_brush_on_mask(position, mask)?;
         } else {
-            self.paint_circular_brush(position, layer)?;
+            self.paint_circular_brush(position, layer, selection)?;
         }
         document.mark_dirty();
 
@@ -580,8 +742,14 @@ impl BrushTool {
         Ok(())
     }
 
-    /// Paint a circular brush at the given position on the layer
-    fn paint_circular_brush(&self, center: Point, layer: &mut psoc_core::Layer) -> ToolResult<()> {
+    /// Paint a circular brush at the given position on the layer, faded by
+    /// the selection coverage
+    fn paint_circular_brush(
+        &self,
+        center: Point,
+        layer: &mut psoc_core::Layer,
+        selection: &Selection,
+    ) -> ToolResult<()> {
         let radius = self.brush_size / 2.0;
         let layer_dims = layer.dimensions();
 
@@ -590,6 +758,7 @@ impl BrushTool {
         }
 
         let (layer_width, layer_height) = layer_dims.unwrap();
+        let offset = layer.offset;
 
         // Calculate the bounding box of the brush
         let min_x = ((center.x - radius).floor() as i32).max(0);
@@ -610,7 +779,8 @@ impl BrushTool {
 
                 if distance <= radius {
                     // Calculate brush alpha based on distance and hardness
-                    let alpha = self.calculate_brush_alpha(distance, radius);
+                    let alpha = self.calculate_brush_alpha(distance, radius)
+                        * (selection.layer_coverage(x as u32, y as u32, offset) as f32 / 255.0);
 
                     if alpha > 0.0 {
                         // Blend the brush color with the existing pixel
@@ -720,17 +890,12 @@ impl BrushTool {
     fn paint_circular_brush_on_mask(
         &self,
         center: Point,
-        layer: &mut psoc_core::Layer,
+        mask: &mut psoc_core::PixelData,
     ) -> ToolResult<()> {
         let radius = self.brush_size / 2.0;
 
         // Get mask dimensions
-        let mask_dims = layer.mask_dimensions();
-        if mask_dims.is_none() {
-            return Ok(());
-        }
-
-        let (mask_width, mask_height) = mask_dims.unwrap();
+        let (mask_width, mask_height) = mask.dimensions();
 
         // Calculate the bounding box of the brush
         let min_x = ((center.x - radius).floor() as i32).max(0);
@@ -755,7 +920,7 @@ impl BrushTool {
 
                     if alpha > 0.0 {
                         // Paint on the mask (use brush color's grayscale value)
-                        self.paint_mask_pixel_at(x as u32, y as u32, alpha, layer)?;
+                        self.paint_mask_pixel_at(x as u32, y as u32, alpha, mask)?;
                     }
                 }
             }
@@ -770,11 +935,11 @@ impl BrushTool {
         x: u32,
         y: u32,
         alpha: f32,
-        layer: &mut psoc_core::Layer,
+        mask: &mut psoc_core::PixelData,
     ) -> ToolResult<()> {
         // Get the existing mask pixel
-        let existing_pixel = layer
-            .get_mask_pixel(x, y)
+        let existing_pixel = mask
+            .get_pixel(x, y)
             .unwrap_or(psoc_core::RgbaPixel::new(255, 255, 255, 255)); // Default to white (fully visible)
 
         // Convert brush color to grayscale for mask painting
@@ -795,7 +960,7 @@ impl BrushTool {
         let blended_pixel = self.blend_mask_pixels(existing_pixel, mask_pixel, alpha);
 
         // Set the blended pixel on the mask
-        layer.set_mask_pixel(x, y, blended_pixel)?;
+        mask.set_pixel(x, y, blended_pixel)?;
 
         Ok(())
     }
@@ -823,6 +988,8 @@ pub struct EraserTool {
     eraser_size: f32,
     eraser_hardness: f32,
     is_erasing: bool,
+    /// Mask being erased, recorded for undo when the stroke ends
+    mask_snapshot: Option<MaskSnapshot>,
 }
 
 impl EraserTool {
@@ -831,6 +998,7 @@ impl EraserTool {
             eraser_size: 10.0,
             eraser_hardness: 1.0,
             is_erasing: false,
+            mask_snapshot: None,
         }
     }
 }
@@ -870,6 +1038,7 @@ impl Tool for EraserTool {
                 self.is_erasing = true;
                 state.is_active = true;
                 state.last_position = Some(position);
+                self.mask_snapshot = MaskSnapshot::capture(document);
 
                 // Start erasing at this position
                 self.erase_at_position(position, document)?;
@@ -891,6 +1060,9 @@ impl Tool for EraserTool {
                     debug!("Eraser completed");
                     self.is_erasing = false;
                     state.is_active = false;
+                    if let Some(snapshot) = self.mask_snapshot.take() {
+                        snapshot.commit(document, "Erase")?;
+                    }
                 }
             }
             _ => {}
@@ -945,6 +1117,10 @@ impl Tool for EraserTool {
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -966,8 +1142,16 @@ impl EraserTool {
             position, self.eraser_size, self.eraser_hardness, mask_editing_mode
         );
 
-        // Get the active layer
-        let active_layer = document.active_layer_mut();
+        // In Quick Mask mode or on an alpha channel the eraser removes from that mask
+        if let Some(mask) = document.editing_mask_mut() {
+            return self.erase_circular_area_on_mask(position, mask);
+        }
+
+        // Get the active layer, keeping the selection readable while erasing
+        let selection = &document.selection;
+        let active_layer = document
+            .active_layer_index
+            .and_then(|index| document.layers.get_mut(index));
         if active_layer.is_none() {
             debug!("No active layer to erase on");
             return Ok(());
@@ -991,10 +1175,10 @@ impl EraserTool {
         }
 
         // Erase a circular area at the position
-        if mask_editing_mode {
-            self.erase_circular_area_on_mask(position, layer)?;
+        if let (true, Some(mask)) = (mask_editing_mode, layer.mask.as_mut()) {
+            self.erase_circular_area_on_mask(position, mask)?;
         } else {
-            self.erase_circular_area(position, layer)?;
+            self.erase_circular_area(position, layer, selection)?;
         }
         document.mark_dirty();
 
@@ -1028,8 +1212,14 @@ impl EraserTool {
         Ok(())
     }
 
-    /// Erase a circular area at the given position on the layer
-    fn erase_circular_area(&self, center: Point, layer: &mut psoc_core::Layer) -> ToolResult<()> {
+    /// Erase a circular area at the given position on the layer, faded by
+    /// the selection coverage
+    fn erase_circular_area(
+        &self,
+        center: Point,
+        layer: &mut psoc_core::Layer,
+        selection: &Selection,
+    ) -> ToolResult<()> {
         let radius = self.eraser_size / 2.0;
         let layer_dims = layer.dimensions();
 
@@ -1038,6 +1228,7 @@ impl EraserTool {
         }
 
         let (layer_width, layer_height) = layer_dims.unwrap();
+        let offset = layer.offset;
 
         // Calculate the bounding box of the eraser
         let min_x = ((center.x - radius).floor() as i32).max(0);
@@ -1058,7 +1249,8 @@ impl EraserTool {
 
                 if distance <= radius {
                     // Calculate eraser alpha based on distance and hardness
-                    let erase_strength = self.calculate_eraser_alpha(distance, radius);
+                    let erase_strength = self.calculate_eraser_alpha(distance, radius)
+                        * (selection.layer_coverage(x as u32, y as u32, offset) as f32 / 255.0);
 
                     if erase_strength > 0.0 {
                         // Apply erasing to the pixel
@@ -1137,17 +1329,12 @@ impl EraserTool {
     fn erase_circular_area_on_mask(
         &self,
         center: Point,
-        layer: &mut psoc_core::Layer,
+        mask: &mut psoc_core::PixelData,
     ) -> ToolResult<()> {
         let radius = self.eraser_size / 2.0;
 
         // Get mask dimensions
-        let mask_dims = layer.mask_dimensions();
-        if mask_dims.is_none() {
-            return Ok(());
-        }
-
-        let (mask_width, mask_height) = mask_dims.unwrap();
+        let (mask_width, mask_height) = mask.dimensions();
 
         // Calculate the bounding box of the eraser
         let min_x = ((center.x - radius).floor() as i32).max(0);
@@ -1172,7 +1359,7 @@ impl EraserTool {
 
                     if erase_strength > 0.0 {
                         // Apply erasing to the mask pixel
-                        self.erase_mask_pixel_at(x as u32, y as u32, erase_strength, layer)?;
+                        self.erase_mask_pixel_at(x as u32, y as u32, erase_strength, mask)?;
                     }
                 }
             }
@@ -1187,11 +1374,11 @@ impl EraserTool {
         x: u32,
         y: u32,
         erase_strength: f32,
-        layer: &mut psoc_core::Layer,
+        mask: &mut psoc_core::PixelData,
     ) -> ToolResult<()> {
         // Get the existing mask pixel
-        let existing_pixel = layer
-            .get_mask_pixel(x, y)
+        let existing_pixel = mask
+            .get_pixel(x, y)
             .unwrap_or(psoc_core::RgbaPixel::new(255, 255, 255, 255)); // Default to white (fully visible)
 
         // Calculate new mask value after erasing (darken the mask)
@@ -1208,19 +1395,76 @@ impl EraserTool {
         );
 
         // Set the erased mask pixel
-        layer.set_mask_pixel(x, y, erased_pixel)?;
+        mask.set_pixel(x, y, erased_pixel)?;
 
         Ok(())
     }
 }
 
-/// Lasso selection tool for making freeform selections
+/// How the lasso tool builds its outline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LassoMode {
+    /// Drag to draw a freehand outline
+    #[default]
+    Freehand,
+    /// Click to place vertices joined by straight edges
+    Polygonal,
+    /// Click to place anchors joined by paths that snap to strong edges
+    Magnetic,
+}
+
+impl LassoMode {
+    /// All lasso modes, in option order
+    const ALL: [LassoMode; 3] = [
+        LassoMode::Freehand,
+        LassoMode::Polygonal,
+        LassoMode::Magnetic,
+    ];
+}
+
+impl std::fmt::Display for LassoMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LassoMode::Freehand => write!(f, "Freehand"),
+            LassoMode::Polygonal => write!(f, "Polygonal"),
+            LassoMode::Magnetic => write!(f, "Magnetic"),
+        }
+    }
+}
+
+/// Maximum delay between the two clicks of a double-click
+const DOUBLE_CLICK_TIME: std::time::Duration = std::time::Duration::from_millis(400);
+/// Maximum distance in pixels between the two clicks of a double-click
+const DOUBLE_CLICK_DISTANCE: f32 = 4.0;
+/// Clicking within this many pixels of the first vertex closes the outline
+const CLOSE_DISTANCE: f32 = 6.0;
+
+/// Lasso selection tool for making freehand, polygonal and magnetic selections
+///
+/// Polygonal and magnetic outlines are built from clicks and finished by
+/// clicking the first vertex, double-clicking or pressing Enter. Escape
+/// cancels and Backspace removes the last vertex.
 #[derive(Debug)]
 pub struct LassoTool {
     current_path: Vec<Point>,
     is_selecting: bool,
     feather_radius: f32,
     anti_alias: bool,
+    mode: SelectionMode,
+    gesture: SelectionGesture,
+    lasso_mode: LassoMode,
+    /// Index into `current_path` of each placed vertex or anchor
+    anchors: Vec<usize>,
+    /// Time and position of the previous click, for double-click detection
+    last_click: Option<(std::time::Instant, Point)>,
+    /// Magnetic mode: how far from the cursor edges are detected, in pixels
+    width: u32,
+    /// Magnetic mode: minimum normalized edge strength to follow
+    edge_contrast: f32,
+    /// Magnetic mode: detect edges in the composite instead of the active layer
+    sample_merged: bool,
+    /// Magnetic mode: edges of the image being traced, computed on the first click
+    edges: Option<EdgeMap>,
 }
 
 impl LassoTool {
@@ -1230,8 +1474,217 @@ impl LassoTool {
             is_selecting: false,
             feather_radius: 0.0,
             anti_alias: true,
+            mode: SelectionMode::Replace,
+            gesture: SelectionGesture::default(),
+            lasso_mode: LassoMode::Freehand,
+            anchors: Vec::new(),
+            last_click: None,
+            width: 10,
+            edge_contrast: 0.1,
+            sample_merged: false,
+            edges: None,
+        }
+    }
+
+    /// Edges of the active layer, or of the composite when sampling merged
+    fn edge_map(&self, document: &Document) -> Option<EdgeMap> {
+        if !self.sample_merged {
+            if let Some(pixels) = document
+                .active_layer()
+                .and_then(|layer| layer.pixel_data.as_ref())
+            {
+                return Some(EdgeMap::from_pixels(pixels));
+            }
+        }
+
+        match psoc_core::RenderEngine::new().render_document(document) {
+            Ok(composite) => Some(EdgeMap::from_pixels(&composite)),
+            Err(e) => {
+                debug!("Magnetic lasso falls back to straight edges: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Move a point onto a nearby edge in magnetic mode
+    fn snap(&self, point: Point) -> Point {
+        match &self.edges {
+            Some(edges) => edges.snap(point, self.width, self.edge_contrast),
+            None => point,
+        }
+    }
+
+    /// Points joining `from` to `to`, excluding `from`
+    fn segment(&self, from: Point, to: Point) -> Vec<Point> {
+        match &self.edges {
+            Some(edges) => {
+                let to = edges.snap(to, self.width, self.edge_contrast);
+                let mut path = edges.shortest_path(from, to, self.edge_contrast);
+                if !path.is_empty() {
+                    path.remove(0);
+                }
+                path
+            }
+            None => vec![to],
+        }
+    }
+
+    /// Place a vertex or anchor, extending the outline to it
+    fn add_anchor(&mut self, position: Point) {
+        let Some(&last) = self.current_path.last() else {
+            return;
+        };
+        let segment = self.segment(last, position);
+        if segment.is_empty() {
+            return;
+        }
+        self.current_path.extend(segment);
+        self.anchors.push(self.current_path.len() - 1);
+    }
+
+    /// Remove the last vertex or anchor and the outline leading to it
+    fn remove_last_anchor(&mut self) {
+        self.anchors.pop();
+        match self.anchors.last() {
+            Some(&anchor) => self.current_path.truncate(anchor + 1),
+            None => self.current_path.clear(),
         }
     }
+
+    /// The outline so far, extended to the cursor and closed back to the start
+    fn closed_outline(&self, cursor: Option<Point>) -> Vec<Point> {
+        let mut outline = self.current_path.clone();
+        let (Some(&first), Some(&last)) = (outline.first(), outline.last()) else {
+            return outline;
+        };
+
+        let last = match cursor {
+            Some(cursor) => {
+                outline.extend(self.segment(last, cursor));
+                *outline.last().unwrap_or(&last)
+            }
+            None => last,
+        };
+
+        match &self.edges {
+            Some(edges) => {
+                let mut closing = edges.shortest_path(last, first, self.edge_contrast);
+                if !closing.is_empty() {
+                    closing.remove(0);
+                }
+                outline.extend(closing);
+            }
+            None => outline.push(first),
+        }
+        outline
+    }
+
+    /// Show the outline with the segment to the cursor
+    fn preview_outline(&self, document: &mut Document, cursor: Point) {
+        let outline = self.closed_outline(Some(cursor));
+        if outline.len() >= 4 {
+            self.gesture.preview(document, Selection::lasso(outline));
+        }
+    }
+
+    /// Turn the outline into the selection and end the gesture
+    fn finish_outline(&mut self, document: &mut Document, state: &mut ToolState) {
+        if self.current_path.len() >= 3 {
+            let selection = Selection::lasso(self.closed_outline(None));
+            debug!("Created {} lasso selection: {}", self.lasso_mode, selection);
+            self.gesture.commit(document, selection);
+        } else {
+            // Not enough vertices, drop the shape
+            self.gesture.cancel(document);
+        }
+        self.end_outline(state);
+    }
+
+    /// Abandon the outline, restoring the previous selection
+    fn cancel_outline(&mut self, document: &mut Document, state: &mut ToolState) {
+        debug!("Cancelled {} lasso selection", self.lasso_mode);
+        self.gesture.abort(document);
+        self.end_outline(state);
+    }
+
+    fn end_outline(&mut self, state: &mut ToolState) {
+        self.is_selecting = false;
+        state.is_active = false;
+        self.current_path.clear();
+        self.anchors.clear();
+        self.last_click = None;
+        self.edges = None;
+    }
+
+    /// Handle an event in polygonal or magnetic mode
+    fn handle_click_event(
+        &mut self,
+        event: ToolEvent,
+        document: &mut Document,
+        state: &mut ToolState,
+    ) -> ToolResult<()> {
+        match event {
+            ToolEvent::MousePressed {
+                position,
+                button: super::tool_trait::MouseButton::Left,
+                modifiers,
+            } => {
+                let now = std::time::Instant::now();
+                let double_click = self.last_click.is_some_and(|(time, last)| {
+                    now.duration_since(time) <= DOUBLE_CLICK_TIME
+                        && last.distance_to(&position) <= DOUBLE_CLICK_DISTANCE
+                });
+                self.last_click = Some((now, position));
+                state.last_position = Some(position);
+
+                if !self.is_selecting {
+                    debug!("{} lasso started at: {:?}", self.lasso_mode, position);
+                    self.gesture = SelectionGesture::begin(document, modifiers, self.mode);
+                    if self.lasso_mode == LassoMode::Magnetic {
+                        self.edges = self.edge_map(document);
+                    }
+                    self.current_path = vec![self.snap(position)];
+                    self.anchors = vec![0];
+                    self.is_selecting = true;
+                    state.is_active = true;
+                    return Ok(());
+                }
+
+                let closes = self.anchors.len() >= 3
+                    && self
+                        .current_path
+                        .first()
+                        .is_some_and(|first| first.distance_to(&position) <= CLOSE_DISTANCE);
+                if double_click || closes {
+                    self.finish_outline(document, state);
+                } else {
+                    self.add_anchor(position);
+                    self.preview_outline(document, position);
+                }
+            }
+            ToolEvent::MouseMoved { position, .. } | ToolEvent::MouseDragged { position, .. }
+                if self.is_selecting =>
+            {
+                state.last_position = Some(position);
+                self.preview_outline(document, position);
+            }
+            ToolEvent::KeyPressed { key, .. } if self.is_selecting => match key {
+                Key::Enter => self.finish_outline(document, state),
+                Key::Escape => self.cancel_outline(document, state),
+                Key::Backspace | Key::Delete => {
+                    self.remove_last_anchor();
+                    if self.anchors.is_empty() {
+                        self.cancel_outline(document, state);
+                    } else if let Some(position) = state.last_position {
+                        self.preview_outline(document, position);
+                    }
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        Ok(())
+    }
 }
 
 impl Default for LassoTool {
@@ -1250,7 +1703,7 @@ impl Tool for LassoTool {
     }
 
     fn description(&self) -> &'static str {
-        "Make freeform selections by drawing"
+        "Make freehand, polygonal or edge-snapping selections"
     }
 
     fn cursor(&self) -> ToolCursor {
@@ -1263,9 +1716,18 @@ impl Tool for LassoTool {
         document: &mut Document,
         state: &mut ToolState,
     ) -> ToolResult<()> {
+        if self.lasso_mode != LassoMode::Freehand {
+            return self.handle_click_event(event, document, state);
+        }
+
         match event {
-            ToolEvent::MousePressed { position, .. } => {
+            ToolEvent::MousePressed {
+                position,
+                modifiers,
+                ..
+            } => {
                 debug!("Lasso selection started at: {:?}", position);
+                self.gesture = SelectionGesture::begin(document, modifiers, self.mode);
                 self.current_path.clear();
                 self.current_path.push(position);
                 self.is_selecting = true;
@@ -1297,7 +1759,7 @@ impl Tool for LassoTool {
                             preview_path.push(*first_point);
                         }
                         let selection = Selection::lasso(preview_path);
-                        document.set_selection(selection);
+                        self.gesture.preview(document, selection);
                     }
                 }
             }
@@ -1323,10 +1785,10 @@ impl Tool for LassoTool {
                         }
                         let selection = Selection::lasso(final_path);
                         debug!("Created lasso selection: {}", selection);
-                        document.set_selection(selection);
+                        self.gesture.commit(document, selection);
                     } else {
-                        // Not enough points, clear selection
-                        document.set_selection(Selection::None);
+                        // Not enough points, drop the shape
+                        self.gesture.cancel(document);
                     }
 
                     self.current_path.clear();
@@ -1356,11 +1818,50 @@ impl Tool for LassoTool {
                 option_type: ToolOptionType::Bool,
                 default_value: ToolOptionValue::Bool(self.anti_alias),
             },
+            selection_mode_option(self.mode),
+            ToolOption {
+                name: "lasso_mode".to_string(),
+                display_name: "Lasso Mode".to_string(),
+                description: "Draw freehand, click straight edges or snap to image edges"
+                    .to_string(),
+                option_type: ToolOptionType::Enum(
+                    LassoMode::ALL.iter().map(|mode| mode.to_string()).collect(),
+                ),
+                default_value: ToolOptionValue::String(self.lasso_mode.to_string()),
+            },
+            ToolOption {
+                name: "width".to_string(),
+                display_name: "Width".to_string(),
+                description: "Distance from the cursor searched for edges in magnetic mode"
+                    .to_string(),
+                option_type: ToolOptionType::Int { min: 1, max: 40 },
+                default_value: ToolOptionValue::Int(self.width as i32),
+            },
+            ToolOption {
+                name: "edge_contrast".to_string(),
+                display_name: "Edge Contrast".to_string(),
+                description: "Minimum edge strength followed in magnetic mode".to_string(),
+                option_type: ToolOptionType::Float { min: 0.0, max: 1.0 },
+                default_value: ToolOptionValue::Float(self.edge_contrast),
+            },
+            ToolOption {
+                name: "sample_merged".to_string(),
+                display_name: "Sample Merged".to_string(),
+                description: "Detect edges in all visible layers instead of the active one"
+                    .to_string(),
+                option_type: ToolOptionType::Bool,
+                default_value: ToolOptionValue::Bool(self.sample_merged),
+            },
         ]
     }
 
     fn set_option(&mut self, name: &str, value: ToolOptionValue) -> ToolResult<()> {
         match name {
+            "mode" => {
+                if let Some(mode) = parse_selection_mode(&value) {
+                    self.mode = mode;
+                }
+            }
             "feather" => {
                 if let ToolOptionValue::Float(radius) = value {
                     self.feather_radius = radius.clamp(0.0, 50.0);
@@ -1371,6 +1872,40 @@ impl Tool for LassoTool {
                     self.anti_alias = enabled;
                 }
             }
+            "lasso_mode" => {
+                let mode = match value {
+                    ToolOptionValue::String(name) => LassoMode::ALL
+                        .into_iter()
+                        .find(|mode| mode.to_string() == name),
+                    ToolOptionValue::Choice(index) => LassoMode::ALL.get(index).copied(),
+                    _ => None,
+                };
+                if let Some(mode) = mode {
+                    if mode != self.lasso_mode {
+                        // An outline in progress belongs to the old mode
+                        self.current_path.clear();
+                        self.anchors.clear();
+                        self.is_selecting = false;
+                        self.edges = None;
+                    }
+                    self.lasso_mode = mode;
+                }
+            }
+            "width" => {
+                if let ToolOptionValue::Int(width) = value {
+                    self.width = width.clamp(1, 40) as u32;
+                }
+            }
+            "edge_contrast" => {
+                if let ToolOptionValue::Float(contrast) = value {
+                    self.edge_contrast = contrast.clamp(0.0, 1.0);
+                }
+            }
+            "sample_merged" => {
+                if let ToolOptionValue::Bool(enabled) = value {
+                    self.sample_merged = enabled;
+                }
+            }
             _ => {}
         }
         Ok(())
@@ -1380,10 +1915,19 @@ impl Tool for LassoTool {
         match name {
             "feather" => Some(ToolOptionValue::Float(self.feather_radius)),
             "anti_alias" => Some(ToolOptionValue::Bool(self.anti_alias)),
+            "mode" => Some(ToolOptionValue::String(self.mode.to_string())),
+            "lasso_mode" => Some(ToolOptionValue::String(self.lasso_mode.to_string())),
+            "width" => Some(ToolOptionValue::Int(self.width as i32)),
+            "edge_contrast" => Some(ToolOptionValue::Float(self.edge_contrast)),
+            "sample_merged" => Some(ToolOptionValue::Bool(self.sample_merged)),
             _ => None,
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -1522,6 +2066,10 @@ impl Tool for MoveTool {
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -1594,6 +2142,8 @@ pub struct MagicWandTool {
     contiguous: bool,
     anti_alias: bool,
     sample_merged: bool,
+    mode: SelectionMode,
+    metric: ToleranceMetric,
 }
 
 impl MagicWandTool {
@@ -1603,8 +2153,18 @@ impl MagicWandTool {
             contiguous: true,
             anti_alias: true,
             sample_merged: false,
+            mode: SelectionMode::Replace,
+            metric: ToleranceMetric::Rgb,
         }
     }
+
+    /// Magic wand parameters from the tool options
+    fn wand(&self) -> MagicWand {
+        MagicWand::new(self.tolerance)
+            .with_metric(self.metric)
+            .with_contiguous(self.contiguous)
+            .with_anti_alias(self.anti_alias)
+    }
 }
 
 impl Default for MagicWandTool {
@@ -1637,13 +2197,18 @@ impl Tool for MagicWandTool {
         state: &mut ToolState,
     ) -> ToolResult<()> {
         match event {
-            ToolEvent::MousePressed { position, .. } => {
+            ToolEvent::MousePressed {
+                position,
+                modifiers,
+                ..
+            } => {
                 debug!("Magic wand selection at: {:?}", position);
                 state.is_active = true;
                 state.last_position = Some(position);
 
                 // Perform magic wand selection
-                self.perform_magic_wand_selection(position, document)?;
+                let gesture = SelectionGesture::begin(document, modifiers, self.mode);
+                self.perform_magic_wand_selection(position, document, &gesture)?;
             }
             ToolEvent::MouseReleased { .. } => {
                 if state.is_active {
@@ -1689,11 +2254,29 @@ impl Tool for MagicWandTool {
                 option_type: ToolOptionType::Bool,
                 default_value: ToolOptionValue::Bool(self.sample_merged),
             },
+            selection_mode_option(self.mode),
+            ToolOption {
+                name: "metric".to_string(),
+                display_name: "Tolerance Metric".to_string(),
+                description: "Compare full color, brightness only or hue only".to_string(),
+                option_type: ToolOptionType::Enum(
+                    ToleranceMetric::ALL
+                        .iter()
+                        .map(|metric| metric.to_string())
+                        .collect(),
+                ),
+                default_value: ToolOptionValue::String(self.metric.to_string()),
+            },
         ]
     }
 
     fn set_option(&mut self, name: &str, value: ToolOptionValue) -> ToolResult<()> {
         match name {
+            "mode" => {
+                if let Some(mode) = parse_selection_mode(&value) {
+                    self.mode = mode;
+                }
+            }
             "tolerance" => {
                 if let ToolOptionValue::Float(tolerance) = value {
                     self.tolerance = tolerance.clamp(0.0, 255.0);
@@ -1714,6 +2297,18 @@ impl Tool for MagicWandTool {
                     self.sample_merged = enabled;
                 }
             }
+            "metric" => {
+                let metric = match value {
+                    ToolOptionValue::String(name) => ToleranceMetric::ALL
+                        .into_iter()
+                        .find(|metric| metric.to_string() == name),
+                    ToolOptionValue::Choice(index) => ToleranceMetric::ALL.get(index).copied(),
+                    _ => None,
+                };
+                if let Some(metric) = metric {
+                    self.metric = metric;
+                }
+            }
             _ => {}
         }
         Ok(())
@@ -1725,10 +2320,16 @@ impl Tool for MagicWandTool {
             "contiguous" => Some(ToolOptionValue::Bool(self.contiguous)),
             "anti_alias" => Some(ToolOptionValue::Bool(self.anti_alias)),
             "sample_merged" => Some(ToolOptionValue::Bool(self.sample_merged)),
+            "mode" => Some(ToolOptionValue::String(self.mode.to_string())),
+            "metric" => Some(ToolOptionValue::String(self.metric.to_string())),
             _ => None,
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -1736,155 +2337,97 @@ impl Tool for MagicWandTool {
 
 impl MagicWandTool {
     /// Perform magic wand selection at the given position
+    ///
+    /// Samples the active layer, or the composite of all visible layers when
+    /// sample merged is on. A layer is sampled where it lies on the canvas, and
+    /// the resulting selection always covers the whole canvas.
     fn perform_magic_wand_selection(
         &self,
         position: Point,
         document: &mut Document,
+        gesture: &SelectionGesture,
     ) -> ToolResult<()> {
-        // Get the active layer
-        let active_layer = document.active_layer();
-        if active_layer.is_none() {
-            debug!("No active layer for magic wand selection");
+        if position.x < 0.0 || position.y < 0.0 {
+            debug!("Magic wand position out of bounds");
             return Ok(());
         }
 
-        let layer = active_layer.unwrap();
-        if !layer.has_pixel_data() {
-            debug!("Active layer has no pixel data");
+        // Whole pixels the sampled image is shifted by on the canvas
+        let (offset_x, offset_y) = match document.active_layer() {
+            Some(layer) if !self.sample_merged => {
+                (layer.offset.x.round() as i64, layer.offset.y.round() as i64)
+            }
+            _ => (0, 0),
+        };
+        let x = position.x.floor() as i64 - offset_x;
+        let y = position.y.floor() as i64 - offset_y;
+        let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else {
+            debug!("Magic wand position outside the sampled layer");
             return Ok(());
-        }
+        };
 
-        let layer_dims = layer.dimensions();
-        if layer_dims.is_none() {
-            return Ok(());
-        }
-
-        let (width, height) = layer_dims.unwrap();
-        let x = position.x as u32;
-        let y = position.y as u32;
+        let composite;
+        let pixels = if self.sample_merged {
+            composite = match psoc_core::RenderEngine::new().render_document(document) {
+                Ok(composite) => composite,
+                Err(e) => {
+                    debug!("Magic wand could not sample merged image: {}", e);
+                    return Ok(());
+                }
+            };
+            &composite
+        } else {
+            match document
+                .active_layer()
+                .and_then(|layer| layer.pixel_data.as_ref())
+            {
+                Some(pixels) => pixels,
+                None => {
+                    debug!("No active layer pixels for magic wand selection");
+                    return Ok(());
+                }
+            }
+        };
 
-        // Check bounds
-        if x >= width || y >= height {
+        let Some(mask) = self.wand().select(pixels, x, y) else {
             debug!("Magic wand position out of bounds");
             return Ok(());
-        }
-
-        // Get the target color at the clicked position
-        let target_color = layer
-            .get_pixel(x, y)
-            .unwrap_or(psoc_core::RgbaPixel::transparent());
-        debug!("Magic wand target color: {:?}", target_color);
-
-        // Create a mask for the selection
-        let mut mask_data = vec![0u8; (width * height) as usize];
-
-        if self.contiguous {
-            // Flood fill algorithm for contiguous selection
-            self.flood_fill_selection(x, y, target_color, layer, &mut mask_data, width, height)?;
+        };
+        let (width, height) = document.dimensions();
+        let mask = if (mask.width, mask.height, offset_x, offset_y) == (width, height, 0, 0) {
+            mask
         } else {
-            // Select all similar colors regardless of connectivity
-            self.select_all_similar(target_color, layer, &mut mask_data, width, height)?;
-        }
+            place_mask_on_canvas(&mask, offset_x, offset_y, width, height)
+        };
 
-        // Create mask selection
-        let selection = Selection::mask(width, height, mask_data);
+        let selection = Selection::Mask(mask);
         debug!("Created magic wand selection: {}", selection);
-        document.set_selection(selection);
+        gesture.commit(document, selection);
 
         Ok(())
     }
+}
 
-    /// Flood fill algorithm for contiguous selection
-    #[allow(clippy::too_many_arguments)]
-    fn flood_fill_selection(
-        &self,
-        start_x: u32,
-        start_y: u32,
-        target_color: psoc_core::RgbaPixel,
-        layer: &psoc_core::Layer,
-        mask_data: &mut [u8],
-        width: u32,
-        height: u32,
-    ) -> ToolResult<()> {
-        let mut stack = Vec::new();
-        stack.push((start_x, start_y));
-
-        while let Some((x, y)) = stack.pop() {
-            if x >= width || y >= height {
-                continue;
-            }
-
-            let index = (y * width + x) as usize;
-            if index >= mask_data.len() || mask_data[index] > 0 {
-                continue; // Already processed
-            }
-
-            let pixel = layer
-                .get_pixel(x, y)
-                .unwrap_or(psoc_core::RgbaPixel::transparent());
-            if !self.colors_similar(pixel, target_color) {
-                continue;
-            }
-
-            // Mark as selected
-            mask_data[index] = 255;
-
-            // Add neighbors to stack
-            if x > 0 {
-                stack.push((x - 1, y));
-            }
-            if x < width - 1 {
-                stack.push((x + 1, y));
-            }
-            if y > 0 {
-                stack.push((x, y - 1));
-            }
-            if y < height - 1 {
-                stack.push((x, y + 1));
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Select all similar colors regardless of connectivity
-    fn select_all_similar(
-        &self,
-        target_color: psoc_core::RgbaPixel,
-        layer: &psoc_core::Layer,
-        mask_data: &mut [u8],
-        width: u32,
-        height: u32,
-    ) -> ToolResult<()> {
-        for y in 0..height {
-            for x in 0..width {
-                let pixel = layer
-                    .get_pixel(x, y)
-                    .unwrap_or(psoc_core::RgbaPixel::transparent());
-                if self.colors_similar(pixel, target_color) {
-                    let index = (y * width + x) as usize;
-                    if index < mask_data.len() {
-                        mask_data[index] = 255;
-                    }
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Check if two colors are similar within tolerance
-    fn colors_similar(&self, color1: psoc_core::RgbaPixel, color2: psoc_core::RgbaPixel) -> bool {
-        let dr = (color1.r as f32 - color2.r as f32).abs();
-        let dg = (color1.g as f32 - color2.g as f32).abs();
-        let db = (color1.b as f32 - color2.b as f32).abs();
-        let da = (color1.a as f32 - color2.a as f32).abs();
-
-        // Calculate color distance (simple Euclidean distance in RGBA space)
-        let distance = (dr * dr + dg * dg + db * db + da * da).sqrt();
-
-        distance <= self.tolerance
-    }
+/// Copy a layer-sized mask onto a canvas-sized one at the layer's offset
+fn place_mask_on_canvas(
+    mask: &MaskSelection,
+    offset_x: i64,
+    offset_y: i64,
+    width: u32,
+    height: u32,
+) -> MaskSelection {
+    let mut canvas = MaskSelection::new(width, height, vec![0; width as usize * height as usize]);
+    for y in 0..mask.height {
+        for x in 0..mask.width {
+            let canvas_x = x as i64 + offset_x;
+            let canvas_y = y as i64 + offset_y;
+            if let (Ok(canvas_x), Ok(canvas_y)) = (u32::try_from(canvas_x), u32::try_from(canvas_y))
+            {
+                canvas.set_pixel(canvas_x, canvas_y, mask.get_pixel(x, y));
+            }
+        }
+    }
+    canvas
 }
 
 #[cfg(test)]
@@ -2100,6 +2643,30 @@ mod tests {
         assert!(center_pixel.a > 0);
     }
 
+    #[test]
+    fn test_brush_painting_blends_by_selection_coverage() {
+        let mut brush = BrushTool::new();
+        brush.brush_size = 8.0;
+        brush.brush_hardness = 1.0;
+        brush.brush_color = RgbaPixel::new(255, 0, 0, 255);
+
+        let mut document = Document::new("Test".to_string(), 20, 20);
+        document.add_layer(Layer::new_pixel("Test Layer".to_string(), 20, 20));
+        document.set_active_layer(0).unwrap();
+        // The selection edge runs through the middle of column 10
+        document.set_selection(Selection::rectangle(0.0, 0.0, 10.5, 20.0));
+
+        brush
+            .paint_at_position(Point::new(10.0, 10.0), &mut document)
+            .unwrap();
+
+        let layer = document.active_layer().unwrap();
+        let alpha = |x| layer.get_pixel(x, 10).unwrap().a;
+        assert_eq!(alpha(9), 255);
+        assert!((126..=129).contains(&alpha(10)));
+        assert_eq!(alpha(11), 0);
+    }
+
     #[test]
     fn test_brush_stroke_painting() {
         let mut brush = BrushTool::new();
@@ -2132,6 +2699,151 @@ mod tests {
         assert!(mid_pixel.g > 0);
     }
 
+    #[test]
+    fn test_brush_and_eraser_paint_quick_mask() {
+        let mut brush = BrushTool::new();
+        brush.brush_size = 4.0;
+        brush.brush_color = RgbaPixel::new(255, 255, 255, 255);
+
+        let mut document = Document::new("Test".to_string(), 20, 20);
+        document.add_layer(Layer::new_pixel("Test Layer".to_string(), 20, 20));
+        document.set_active_layer(0).unwrap();
+        document.set_selection(Selection::rectangle(0.0, 0.0, 5.0, 5.0));
+        document.enter_quick_mask();
+
+        // Painting white adds to the selection without touching the layer
+        brush
+            .paint_at_position(Point::new(10.0, 10.0), &mut document)
+            .unwrap();
+        let mask = document.quick_mask.as_ref().unwrap();
+        assert_eq!(mask.get_pixel(10, 10).unwrap().r, 255);
+        assert_eq!(mask.get_pixel(18, 18).unwrap().r, 0);
+        assert_eq!(
+            document.active_layer().unwrap().get_pixel(10, 10),
+            Some(RgbaPixel::transparent())
+        );
+
+        // Erasing removes from the selection
+        let mut eraser = EraserTool::new();
+        eraser.eraser_size = 4.0;
+        eraser.eraser_hardness = 1.0;
+        eraser
+            .erase_at_position(Point::new(2.0, 2.0), &mut document)
+            .unwrap();
+        assert_eq!(
+            document
+                .quick_mask
+                .as_ref()
+                .unwrap()
+                .get_pixel(2, 2)
+                .unwrap()
+                .r,
+            0
+        );
+
+        document.exit_quick_mask();
+        assert!(document.selection.contains_point(Point::new(10.5, 10.5)));
+        assert!(!document.selection.contains_point(Point::new(2.5, 2.5)));
+        assert!(document.selection.contains_point(Point::new(4.5, 0.5)));
+    }
+
+    #[test]
+    fn test_quick_mask_strokes_can_be_undone() {
+        use crate::tools::tool_trait::{KeyModifiers, MouseButton};
+
+        let mut document = Document::new("Test".to_string(), 20, 20);
+        document.enter_quick_mask();
+        let before = document.quick_mask.clone().unwrap();
+        let mut state = ToolState::default();
+        let stroke = |tool: &mut dyn Tool, document: &mut Document, state: &mut ToolState| {
+            let position = Point::new(10.0, 10.0);
+            let modifiers = KeyModifiers::default();
+            tool.handle_event(
+                ToolEvent::MousePressed {
+                    position,
+                    button: MouseButton::Left,
+                    modifiers,
+                },
+                document,
+                state,
+            )
+            .unwrap();
+            tool.handle_event(
+                ToolEvent::MouseReleased {
+                    position,
+                    button: MouseButton::Left,
+                    modifiers,
+                },
+                document,
+                state,
+            )
+            .unwrap();
+        };
+        let mask_value = |document: &Document| {
+            document
+                .quick_mask
+                .as_ref()
+                .unwrap()
+                .get_pixel(10, 10)
+                .unwrap()
+                .r
+        };
+
+        // Without a selection the mask starts white, so erase before painting
+        let mut eraser = EraserTool::new();
+        stroke(&mut eraser, &mut document, &mut state);
+        assert_eq!(mask_value(&document), 0);
+
+        let mut brush = BrushTool::new();
+        brush.brush_color = RgbaPixel::new(255, 255, 255, 255);
+        stroke(&mut brush, &mut document, &mut state);
+        assert_eq!(mask_value(&document), 255);
+
+        // Each stroke is one history step, and undo keeps Quick Mask mode
+        assert!(document.undo().unwrap());
+        assert_eq!(mask_value(&document), 0);
+        assert!(document.undo().unwrap());
+        assert!(document.is_quick_mask_active());
+        assert_eq!(mask_value(&document), before.get_pixel(10, 10).unwrap().r);
+
+        assert!(document.redo().unwrap());
+        assert_eq!(mask_value(&document), 0);
+    }
+
+    #[test]
+    fn test_channel_strokes_can_be_undone() {
+        use crate::tools::tool_trait::{KeyModifiers, MouseButton};
+
+        let mut document = Document::new("Test".to_string(), 20, 20);
+        let index = document.save_selection_to_channel("Alpha 1".to_string());
+        document.set_active_channel(Some(index)).unwrap();
+        let before = document.channels[index].value(10, 10);
+
+        let mut eraser = EraserTool::new();
+        let mut state = ToolState::default();
+        for event in [
+            ToolEvent::MousePressed {
+                position: Point::new(10.0, 10.0),
+                button: MouseButton::Left,
+                modifiers: KeyModifiers::default(),
+            },
+            ToolEvent::MouseReleased {
+                position: Point::new(10.0, 10.0),
+                button: MouseButton::Left,
+                modifiers: KeyModifiers::default(),
+            },
+        ] {
+            eraser
+                .handle_event(event, &mut document, &mut state)
+                .unwrap();
+        }
+        assert_eq!(document.channels[index].value(10, 10), 0);
+        assert_eq!(document.undo_description(), Some("Erase Alpha 1"));
+
+        assert!(document.undo().unwrap());
+        assert_eq!(document.channels[index].value(10, 10), before);
+    }
+
     // Eraser Tool Tests
     #[test]
     fn test_eraser_tool_creation() {
@@ -3084,6 +3796,10 @@ impl Tool for TransformTool {
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -3109,7 +3825,7 @@ mod new_selection_tools_tests {
     fn test_ellipse_tool_options() {
         let tool = EllipseTool::new();
         let options = tool.options();
-        assert_eq!(options.len(), 2);
+        assert_eq!(options.len(), 3);
 
         let feather_option = &options[0];
         assert_eq!(feather_option.name, "feather");
@@ -3185,7 +3901,7 @@ mod new_selection_tools_tests {
     fn test_lasso_tool_options() {
         let tool = LassoTool::new();
         let options = tool.options();
-        assert_eq!(options.len(), 2);
+        assert_eq!(options.len(), 7);
 
         let feather_option = &options[0];
         assert_eq!(feather_option.name, "feather");
@@ -3254,6 +3970,320 @@ mod new_selection_tools_tests {
         }
     }
 
+    fn drag_select(
+        tool: &mut dyn Tool,
+        document: &mut Document,
+        from: Point,
+        to: Point,
+        modifiers: KeyModifiers,
+    ) {
+        let mut state = ToolState::default();
+        for event in [
+            ToolEvent::MousePressed {
+                position: from,
+                button: MouseButton::Left,
+                modifiers,
+            },
+            ToolEvent::MouseDragged {
+                position: to,
+                button: MouseButton::Left,
+                modifiers,
+            },
+            ToolEvent::MouseReleased {
+                position: to,
+                button: MouseButton::Left,
+                modifiers,
+            },
+        ] {
+            tool.handle_event(event, document, &mut state).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_selection_mode_for_modifiers() {
+        let shift = KeyModifiers {
+            shift: true,
+            ..Default::default()
+        };
+        let alt = KeyModifiers {
+            alt: true,
+            ..Default::default()
+        };
+        let both = KeyModifiers {
+            shift: true,
+            alt: true,
+            ..Default::default()
+        };
+        let default = SelectionMode::Subtract;
+        assert_eq!(
+            selection_mode_for(KeyModifiers::default(), default),
+            default
+        );
+        assert_eq!(selection_mode_for(shift, default), SelectionMode::Add);
+        assert_eq!(selection_mode_for(alt, default), SelectionMode::Subtract);
+        assert_eq!(selection_mode_for(both, default), SelectionMode::Intersect);
+    }
+
+    #[test]
+    fn test_select_tool_combines_with_modifiers() {
+        let mut tool = SelectTool::new();
+        let mut document = Document::new("Test".to_string(), 100, 100);
+        let plain = KeyModifiers::default();
+        let shift = KeyModifiers {
+            shift: true,
+            ..Default::default()
+        };
+        let alt = KeyModifiers {
+            alt: true,
+            ..Default::default()
+        };
+
+        drag_select(
+            &mut tool,
+            &mut document,
+            Point::new(0.0, 0.0),
+            Point::new(20.0, 20.0),
+            plain,
+        );
+        drag_select(
+            &mut tool,
+            &mut document,
+            Point::new(50.0, 50.0),
+            Point::new(70.0, 70.0),
+            shift,
+        );
+        assert!(document.selection.contains_point(Point::new(10.5, 10.5)));
+        assert!(document.selection.contains_point(Point::new(60.5, 60.5)));
+        assert!(!document.selection.contains_point(Point::new(35.5, 35.5)));
+
+        drag_select(
+            &mut tool,
+            &mut document,
+            Point::new(5.0, 5.0),
+            Point::new(15.0, 15.0),
+            alt,
+        );
+        assert!(!document.selection.contains_point(Point::new(10.5, 10.5)));
+        assert!(document.selection.contains_point(Point::new(2.5, 2.5)));
+        assert!(document.selection.contains_point(Point::new(60.5, 60.5)));
+
+        // Without modifiers the new rectangle replaces everything
+        drag_select(
+            &mut tool,
+            &mut document,
+            Point::new(80.0, 80.0),
+            Point::new(90.0, 90.0),
+            plain,
+        );
+        assert!(matches!(document.selection, Selection::Rectangle(_)));
+        assert!(!document.selection.contains_point(Point::new(60.5, 60.5)));
+    }
+
+    #[test]
+    fn test_selection_mode_option() {
+        let mut tool = EllipseTool::new();
+        assert_eq!(
+            tool.get_option("mode"),
+            Some(ToolOptionValue::String("Replace".to_string()))
+        );
+        tool.set_option("mode", ToolOptionValue::String("Intersect".to_string()))
+            .unwrap();
+        assert_eq!(tool.mode, SelectionMode::Intersect);
+
+        let mut document = Document::new("Test".to_string(), 100, 100);
+        document.set_selection(Selection::rectangle(0.0, 0.0, 50.0, 100.0));
+        drag_select(
+            &mut tool,
+            &mut document,
+            Point::new(20.0, 20.0),
+            Point::new(80.0, 80.0),
+            KeyModifiers::default(),
+        );
+        assert!(document.selection.contains_point(Point::new(40.5, 50.5)));
+        assert!(!document.selection.contains_point(Point::new(60.5, 50.5)));
+        assert!(!document.selection.contains_point(Point::new(10.5, 50.5)));
+    }
+
+    #[test]
+    fn test_lasso_alt_click_keeps_selection() {
+        let mut tool = LassoTool::new();
+        let mut document = Document::new("Test".to_string(), 100, 100);
+        document.set_selection(Selection::rectangle(0.0, 0.0, 50.0, 50.0));
+        let mut state = ToolState::default();
+        let alt = KeyModifiers {
+            alt: true,
+            ..Default::default()
+        };
+        for event in [
+            ToolEvent::MousePressed {
+                position: Point::new(10.0, 10.0),
+                button: MouseButton::Left,
+                modifiers: alt,
+            },
+            ToolEvent::MouseReleased {
+                position: Point::new(10.0, 10.0),
+                button: MouseButton::Left,
+                modifiers: alt,
+            },
+        ] {
+            tool.handle_event(event, &mut document, &mut state).unwrap();
+        }
+        assert!(matches!(document.selection, Selection::Rectangle(_)));
+    }
+
+    fn click(tool: &mut LassoTool, document: &mut Document, state: &mut ToolState, x: f32, y: f32) {
+        tool.handle_event(
+            ToolEvent::MousePressed {
+                position: Point::new(x, y),
+                button: MouseButton::Left,
+                modifiers: KeyModifiers::default(),
+            },
+            document,
+            state,
+        )
+        .unwrap();
+    }
+
+    fn press_key(tool: &mut LassoTool, document: &mut Document, state: &mut ToolState, key: Key) {
+        tool.handle_event(
+            ToolEvent::KeyPressed {
+                key,
+                modifiers: KeyModifiers::default(),
+            },
+            document,
+            state,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_polygonal_lasso_enter_closes_outline() {
+        let mut tool = LassoTool::new();
+        tool.set_option(
+            "lasso_mode",
+            ToolOptionValue::String("Polygonal".to_string()),
+        )
+        .unwrap();
+        let mut document = Document::new("Test".to_string(), 100, 100);
+        let mut state = ToolState::default();
+
+        click(&mut tool, &mut document, &mut state, 10.0, 10.0);
+        click(&mut tool, &mut document, &mut state, 90.0, 10.0);
+        click(&mut tool, &mut document, &mut state, 10.0, 90.0);
+        assert!(tool.is_selecting);
+        assert_eq!(tool.anchors.len(), 3);
+
+        press_key(&mut tool, &mut document, &mut state, Key::Enter);
+        assert!(!tool.is_selecting);
+        assert!(!state.is_active);
+        assert!(document.selection.contains_point(Point::new(30.0, 30.0)));
+        assert!(!document.selection.contains_point(Point::new(80.0, 80.0)));
+    }
+
+    #[test]
+    fn test_polygonal_lasso_double_click_and_first_vertex_close() {
+        let mut document = Document::new("Test".to_string(), 100, 100);
+        let mut state = ToolState::default();
+
+        let mut tool = LassoTool::new();
+        tool.set_option("lasso_mode", ToolOptionValue::Choice(1))
+            .unwrap();
+        click(&mut tool, &mut document, &mut state, 10.0, 10.0);
+        click(&mut tool, &mut document, &mut state, 90.0, 10.0);
+        click(&mut tool, &mut document, &mut state, 90.0, 90.0);
+        click(&mut tool, &mut document, &mut state, 90.0, 90.0);
+        assert!(!tool.is_selecting);
+        assert!(document.selection.contains_point(Point::new(70.0, 30.0)));
+        assert!(!document.selection.contains_point(Point::new(30.0, 70.0)));
+
+        // Clicking back on the first vertex closes too
+        document.clear_selection();
+        click(&mut tool, &mut document, &mut state, 10.0, 10.0);
+        click(&mut tool, &mut document, &mut state, 10.0, 90.0);
+        click(&mut tool, &mut document, &mut state, 90.0, 90.0);
+        click(&mut tool, &mut document, &mut state, 12.0, 12.0);
+        assert!(!tool.is_selecting);
+        assert!(document.selection.contains_point(Point::new(30.0, 70.0)));
+    }
+
+    #[test]
+    fn test_polygonal_lasso_backspace_and_escape() {
+        let mut tool = LassoTool::new();
+        tool.set_option(
+            "lasso_mode",
+            ToolOptionValue::String("Polygonal".to_string()),
+        )
+        .unwrap();
+        let mut document = Document::new("Test".to_string(), 100, 100);
+        let mut state = ToolState::default();
+
+        click(&mut tool, &mut document, &mut state, 10.0, 10.0);
+        click(&mut tool, &mut document, &mut state, 90.0, 10.0);
+        click(&mut tool, &mut document, &mut state, 50.0, 50.0);
+        press_key(&mut tool, &mut document, &mut state, Key::Backspace);
+        assert_eq!(tool.anchors.len(), 2);
+        assert_eq!(tool.current_path.len(), 2);
+
+        click(&mut tool, &mut document, &mut state, 10.0, 90.0);
+        press_key(&mut tool, &mut document, &mut state, Key::Enter);
+        assert!(document.selection.contains_point(Point::new(30.0, 40.0)));
+        assert!(!document.selection.contains_point(Point::new(80.0, 80.0)));
+
+        // Escape abandons an outline and keeps the previous selection
+        click(&mut tool, &mut document, &mut state, 60.0, 60.0);
+        click(&mut tool, &mut document, &mut state, 95.0, 60.0);
+        click(&mut tool, &mut document, &mut state, 95.0, 95.0);
+        press_key(&mut tool, &mut document, &mut state, Key::Escape);
+        assert!(!tool.is_selecting);
+        assert!(document.selection.contains_point(Point::new(30.0, 40.0)));
+        assert!(!document.selection.contains_point(Point::new(85.0, 75.0)));
+    }
+
+    #[test]
+    fn test_magnetic_lasso_follows_edges() {
+        let mut document = Document::new("Test".to_string(), 60, 60);
+        let mut layer = psoc_core::Layer::new_pixel("Square".to_string(), 60, 60);
+        for y in 0..60 {
+            for x in 0..60 {
+                let color = if (20..40).contains(&x) && (20..40).contains(&y) {
+                    RgbaPixel::white()
+                } else {
+                    RgbaPixel::black()
+                };
+                layer.set_pixel(x, y, color).unwrap();
+            }
+        }
+        document.add_layer(layer);
+        document.set_active_layer(0).unwrap();
+
+        let mut tool = LassoTool::new();
+        tool.set_option(
+            "lasso_mode",
+            ToolOptionValue::String("Magnetic".to_string()),
+        )
+        .unwrap();
+        let mut state = ToolState::default();
+
+        // Rough clicks a few pixels outside the square's corners
+        click(&mut tool, &mut document, &mut state, 16.0, 16.0);
+        click(&mut tool, &mut document, &mut state, 44.0, 16.0);
+        click(&mut tool, &mut document, &mut state, 44.0, 44.0);
+        click(&mut tool, &mut document, &mut state, 16.0, 44.0);
+        assert!(tool.edges.is_some());
+        // Every anchor snapped onto the square's outline
+        for &anchor in &tool.anchors {
+            let point = tool.current_path[anchor];
+            assert!((18.0..=42.0).contains(&point.x), "{:?}", point);
+            assert!((18.0..=42.0).contains(&point.y), "{:?}", point);
+        }
+
+        press_key(&mut tool, &mut document, &mut state, Key::Enter);
+        assert!(tool.edges.is_none());
+        assert!(document.selection.contains_point(Point::new(30.0, 30.0)));
+        assert!(!document.selection.contains_point(Point::new(10.0, 30.0)));
+        assert!(!document.selection.contains_point(Point::new(50.0, 50.0)));
+    }
+
     #[test]
     fn test_magic_wand_tool_creation() {
         let tool = MagicWandTool::new();
@@ -3269,7 +4299,7 @@ mod new_selection_tools_tests {
     fn test_magic_wand_tool_options() {
         let tool = MagicWandTool::new();
         let options = tool.options();
-        assert_eq!(options.len(), 4);
+        assert_eq!(options.len(), 6);
 
         let tolerance_option = &options[0];
         assert_eq!(tolerance_option.name, "tolerance");
@@ -3345,13 +4375,109 @@ mod new_selection_tools_tests {
         let color3 = psoc_core::RgbaPixel::new(200, 200, 200, 255);
 
         // Similar colors within tolerance
-        assert!(tool.colors_similar(color1, color2));
+        assert!(tool.wand().matches(color1, color2));
+
+        // Different colors outside tolerance
+        assert!(!tool.wand().matches(color1, color3));
+
+        // Same color
+        assert!(tool.wand().matches(color1, color1));
+    }
+
+    #[test]
+    fn test_magic_wand_sample_merged() {
+        use psoc_core::{Layer, PixelData};
+
+        let mut document = Document::new("Test".to_string(), 10, 10);
+        let mut background = Layer::new_pixel("Background".to_string(), 10, 10);
+        let mut pixels = PixelData::new_rgba(10, 10);
+        pixels.fill(RgbaPixel::rgb(200, 0, 0));
+        background.pixel_data = Some(pixels);
+        document.add_layer(background);
+        // A blue square on a transparent layer above
+        let mut top = Layer::new_pixel("Top".to_string(), 10, 10);
+        let mut pixels = PixelData::new_rgba(10, 10);
+        for y in 0..5 {
+            for x in 0..5 {
+                pixels.set_pixel(x, y, RgbaPixel::rgb(0, 0, 255)).unwrap();
+            }
+        }
+        top.pixel_data = Some(pixels);
+        document.add_layer(top);
+        document.set_active_layer(0).unwrap();
+
+        let mut tool = MagicWandTool::new();
+        tool.set_option("anti_alias", ToolOptionValue::Bool(false))
+            .unwrap();
+        let mut state = ToolState::default();
+        let press = ToolEvent::MousePressed {
+            position: Point::new(2.0, 2.0),
+            button: MouseButton::Left,
+            modifiers: KeyModifiers::default(),
+        };
+
+        // The active layer alone is uniformly red
+        tool.handle_event(press.clone(), &mut document, &mut state)
+            .unwrap();
+        assert_eq!(document.selection.coverage(8, 8), 255);
+
+        // The composite shows the blue square
+        tool.set_option("sample_merged", ToolOptionValue::Bool(true))
+            .unwrap();
+        tool.handle_event(press, &mut document, &mut state).unwrap();
+        assert_eq!(document.selection.coverage(2, 2), 255);
+        assert_eq!(document.selection.coverage(8, 8), 0);
+    }
+
+    #[test]
+    fn test_magic_wand_samples_offset_layer_in_place() {
+        let mut document = Document::new("Test".to_string(), 20, 20);
+        // Red on the left half, blue on the right, placed at (6, 4)
+        let mut layer = psoc_core::Layer::new_pixel("Offset".to_string(), 10, 10);
+        for y in 0..10 {
+            for x in 0..10 {
+                let color = if x < 5 {
+                    RgbaPixel::rgb(200, 0, 0)
+                } else {
+                    RgbaPixel::rgb(0, 0, 200)
+                };
+                layer.set_pixel(x, y, color).unwrap();
+            }
+        }
+        layer.set_position(Point::new(6.0, 4.0));
+        document.add_layer(layer);
+        document.set_active_layer(0).unwrap();
 
-        // Different colors outside tolerance
-        assert!(!tool.colors_similar(color1, color3));
+        let mut tool = MagicWandTool::new();
+        tool.set_option("anti_alias", ToolOptionValue::Bool(false))
+            .unwrap();
+        let mut state = ToolState::default();
+        // Canvas (13, 8) is layer pixel (7, 4): blue
+        let press = ToolEvent::MousePressed {
+            position: Point::new(13.5, 8.5),
+            button: MouseButton::Left,
+            modifiers: KeyModifiers::default(),
+        };
+        tool.handle_event(press, &mut document, &mut state).unwrap();
 
-        // Same color
-        assert!(tool.colors_similar(color1, color1));
+        let Selection::Mask(mask) = &document.selection else {
+            panic!("expected a mask selection, got {:?}", document.selection);
+        };
+        assert_eq!((mask.width, mask.height), (20, 20));
+        assert_eq!(document.selection.coverage(11, 4), 255);
+        assert_eq!(document.selection.coverage(15, 13), 255);
+        assert_eq!(document.selection.coverage(10, 8), 0);
+        assert_eq!(document.selection.coverage(16, 8), 0);
+        assert_eq!(document.selection.coverage(13, 3), 0);
+
+        // Clicking the canvas outside the layer selects nothing
+        let press = ToolEvent::MousePressed {
+            position: Point::new(2.0, 2.0),
+            button: MouseButton::Left,
+            modifiers: KeyModifiers::default(),
+        };
+        tool.handle_event(press, &mut document, &mut state).unwrap();
+        assert_eq!(document.selection.coverage(13, 8), 255);
     }
 
     // Text Tool Tests
@@ -3927,6 +5053,10 @@ impl Tool for TextTool {
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -3986,6 +5116,23 @@ impl GradientTool {
         &mut self.gradient_manager
     }
 
+    /// Use a preset from the gradient manager as the current gradient
+    ///
+    /// The stops, interpolation and repeat mode are copied; the gradient type
+    /// chosen in the tool options is kept.
+    pub fn select_preset(&mut self, name: &str) -> bool {
+        if !self.gradient_manager.set_current_gradient(name) {
+            return false;
+        }
+        if let Some(preset) = self.gradient_manager.current_gradient() {
+            self.current_gradient.stops = preset.stops.clone();
+            self.current_gradient.interpolation = preset.interpolation;
+            self.current_gradient.repeat = preset.repeat;
+            self.current_gradient.name = name.to_string();
+        }
+        true
+    }
+
     /// Start creating a gradient
     fn start_gradient_creation(&mut self, start_point: Point) {
         self.is_creating = true;
@@ -4065,6 +5212,13 @@ impl GradientTool {
             }
         };
 
+        // Pixels are faded by how much of them the selection covers
+        let selection = if self.apply_to_selection {
+            document.selection.clone()
+        } else {
+            Selection::None
+        };
+
         // Now get mutable access to the layer
         let active_layer = document.active_layer_mut();
         if active_layer.is_none() {
@@ -4094,7 +5248,10 @@ impl GradientTool {
                 let layer_y = apply_region.y as u32 + y;
 
                 if pixel_index < gradient_pixels.len() {
-                    let gradient_pixel = gradient_pixels[pixel_index];
+                    let mut gradient_pixel = gradient_pixels[pixel_index];
+                    let coverage = selection.layer_coverage(layer_x, layer_y, layer.offset);
+                    gradient_pixel.a =
+                        ((gradient_pixel.a as u32 * coverage as u32 + 127) / 255) as u8;
 
                     // Blend gradient pixel with existing layer pixel
                     if let Some(existing_pixel) = layer.get_pixel(layer_x, layer_y) {
@@ -4264,11 +5421,23 @@ impl Tool for GradientTool {
                 option_type: ToolOptionType::Bool,
                 default_value: ToolOptionValue::Bool(false),
             },
+            ToolOption {
+                name: "preset".to_string(),
+                display_name: "Preset".to_string(),
+                description: "Gradient preset, including imported gradients".to_string(),
+                option_type: ToolOptionType::Enum(self.gradient_manager.gradient_names()),
+                default_value: ToolOptionValue::String("Black to White".to_string()),
+            },
         ]
     }
 
     fn set_option(&mut self, name: &str, value: ToolOptionValue) -> ToolResult<()> {
         match name {
+            "preset" => {
+                if let ToolOptionValue::String(preset) = value {
+                    self.select_preset(&preset);
+                }
+            }
             "gradient_type" => {
                 if let ToolOptionValue::String(type_str) = value {
                     self.current_gradient.gradient_type = match type_str.as_str() {
@@ -4306,6 +5475,10 @@ impl Tool for GradientTool {
 
     fn get_option(&self, name: &str) -> Option<ToolOptionValue> {
         match name {
+            "preset" => self
+                .gradient_manager
+                .current_gradient_name()
+                .map(|preset| ToolOptionValue::String(preset.to_string())),
             "gradient_type" => {
                 let type_str = match self.current_gradient.gradient_type {
                     psoc_core::GradientType::Linear => "Linear",
@@ -4330,6 +5503,10 @@ impl Tool for GradientTool {
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -4360,7 +5537,7 @@ mod gradient_tool_tests {
     fn test_gradient_tool_options() {
         let tool = GradientTool::new();
         let options = tool.options();
-        assert_eq!(options.len(), 4);
+        assert_eq!(options.len(), 5);
 
         let gradient_type_option = &options[0];
         assert_eq!(gradient_type_option.name, "gradient_type");
@@ -4377,6 +5554,13 @@ mod gradient_tool_tests {
         let apply_to_selection_option = &options[3];
         assert_eq!(apply_to_selection_option.name, "apply_to_selection");
         assert_eq!(apply_to_selection_option.display_name, "Apply to Selection");
+
+        let preset_option = &options[4];
+        assert_eq!(preset_option.name, "preset");
+        assert!(matches!(
+            &preset_option.option_type,
+            ToolOptionType::Enum(presets) if presets.contains(&"Rainbow".to_string())
+        ));
     }
 
     #[test]
@@ -4541,6 +5725,36 @@ mod gradient_tool_tests {
         assert!(gradient_names.contains(&"Rainbow".to_string()));
     }
 
+    #[test]
+    fn test_gradient_tool_imported_preset() {
+        let mut tool = GradientTool::new();
+        let mut imported = psoc_core::Gradient::linear_two_color(
+            RgbaPixel::new(255, 0, 0, 255),
+            RgbaPixel::new(0, 0, 255, 255),
+        );
+        imported.name = "Brand".to_string();
+        imported.interpolation = psoc_core::InterpolationMethod::Hsv;
+        tool.gradient_manager_mut().import_gradients([imported]);
+        tool.set_option(
+            "gradient_type",
+            ToolOptionValue::String("Radial".to_string()),
+        )
+        .unwrap();
+
+        tool.set_option("preset", ToolOptionValue::String("Brand".to_string()))
+            .unwrap();
+        assert_eq!(
+            tool.get_option("preset"),
+            Some(ToolOptionValue::String("Brand".to_string()))
+        );
+        let gradient = tool.current_gradient();
+        assert_eq!(gradient.color_at(0.0), RgbaPixel::new(255, 0, 0, 255));
+        assert_eq!(gradient.interpolation, psoc_core::InterpolationMethod::Hsv);
+        assert_eq!(gradient.gradient_type, psoc_core::GradientType::Radial);
+
+        assert!(!tool.select_preset("Missing"));
+    }
+
     #[test]
     fn test_gradient_tool_blend_pixel() {
         let tool = GradientTool::new();
@@ -4593,6 +5807,26 @@ impl std::fmt::Display for ShapeMode {
     }
 }
 
+/// Set a shape fill pixel, blended over the layer by the selection coverage
+fn fill_pixel(
+    layer: &mut psoc_core::Layer,
+    x: u32,
+    y: u32,
+    color: RgbaPixel,
+    selection: &Selection,
+) -> ToolResult<()> {
+    let coverage = selection.layer_coverage(x, y, layer.offset);
+    if coverage == 0 {
+        return Ok(());
+    }
+    let pixel = match layer.get_pixel(x, y) {
+        Some(current) if coverage < 255 => current.lerp(color, coverage as f32 / 255.0),
+        _ => color,
+    };
+    layer.set_pixel(x, y, pixel)?;
+    Ok(())
+}
+
 /// Rectangle tool for drawing rectangles
 #[derive(Debug)]
 pub struct RectangleTool {
@@ -4685,7 +5919,11 @@ impl RectangleTool {
         end: Point,
         document: &mut Document,
     ) -> ToolResult<()> {
-        let active_layer = document.active_layer_mut();
+        // Keep the selection readable while drawing on the layer
+        let selection = &document.selection;
+        let active_layer = document
+            .active_layer_index
+            .and_then(|index| document.layers.get_mut(index));
         if active_layer.is_none() {
             debug!("No active layer to draw rectangle on");
             return Ok(());
@@ -4706,13 +5944,13 @@ impl RectangleTool {
         // Draw based on shape mode
         match self.shape_mode {
             ShapeMode::Fill => {
-                self.fill_rectangle(layer, min_x, min_y, max_x, max_y)?;
+                self.fill_rectangle(layer, selection, min_x, min_y, max_x, max_y)?;
             }
             ShapeMode::Stroke => {
                 self.stroke_rectangle(layer, min_x, min_y, max_x, max_y)?;
             }
             ShapeMode::Both => {
-                self.fill_rectangle(layer, min_x, min_y, max_x, max_y)?;
+                self.fill_rectangle(layer, selection, min_x, min_y, max_x, max_y)?;
                 self.stroke_rectangle(layer, min_x, min_y, max_x, max_y)?;
             }
         }
@@ -4721,10 +5959,11 @@ impl RectangleTool {
         Ok(())
     }
 
-    /// Fill rectangle with solid color
+    /// Fill rectangle with solid color, faded by the selection coverage
     fn fill_rectangle(
         &self,
         layer: &mut psoc_core::Layer,
+        selection: &Selection,
         min_x: i32,
         min_y: i32,
         max_x: i32,
@@ -4733,7 +5972,7 @@ impl RectangleTool {
         for y in min_y..=max_y {
             for x in min_x..=max_x {
                 if x >= 0 && y >= 0 {
-                    layer.set_pixel(x as u32, y as u32, self.fill_color)?;
+                    fill_pixel(layer, x as u32, y as u32, self.fill_color, selection)?;
                 }
             }
         }
@@ -4983,6 +6222,10 @@ impl Tool for RectangleTool {
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -5080,7 +6323,11 @@ impl EllipseShapeTool {
         end: Point,
         document: &mut Document,
     ) -> ToolResult<()> {
-        let active_layer = document.active_layer_mut();
+        // Keep the selection readable while drawing on the layer
+        let selection = &document.selection;
+        let active_layer = document
+            .active_layer_index
+            .and_then(|index| document.layers.get_mut(index));
         if active_layer.is_none() {
             debug!("No active layer to draw ellipse on");
             return Ok(());
@@ -5101,13 +6348,13 @@ impl EllipseShapeTool {
         // Draw based on shape mode
         match self.shape_mode {
             ShapeMode::Fill => {
-                self.fill_ellipse(layer, center_x, center_y, radius_x, radius_y)?;
+                self.fill_ellipse(layer, selection, center_x, center_y, radius_x, radius_y)?;
             }
             ShapeMode::Stroke => {
                 self.stroke_ellipse(layer, center_x, center_y, radius_x, radius_y)?;
             }
             ShapeMode::Both => {
-                self.fill_ellipse(layer, center_x, center_y, radius_x, radius_y)?;
+                self.fill_ellipse(layer, selection, center_x, center_y, radius_x, radius_y)?;
                 self.stroke_ellipse(layer, center_x, center_y, radius_x, radius_y)?;
             }
         }
@@ -5116,10 +6363,11 @@ impl EllipseShapeTool {
         Ok(())
     }
 
-    /// Fill ellipse with solid color
+    /// Fill ellipse with solid color, faded by the selection coverage
     fn fill_ellipse(
         &self,
         layer: &mut psoc_core::Layer,
+        selection: &Selection,
         center_x: f32,
         center_y: f32,
         radius_x: f32,
@@ -5140,7 +6388,7 @@ impl EllipseShapeTool {
                     let ellipse_eq =
                         (dx * dx) / (radius_x * radius_x) + (dy * dy) / (radius_y * radius_y);
                     if ellipse_eq <= 1.0 {
-                        layer.set_pixel(x as u32, y as u32, self.fill_color)?;
+                        fill_pixel(layer, x as u32, y as u32, self.fill_color, selection)?;
                     }
                 }
             }
@@ -5381,6 +6629,10 @@ impl Tool for EllipseShapeTool {
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -5773,6 +7025,10 @@ impl Tool for LineTool {
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -5869,7 +7125,11 @@ impl PolygonTool {
 
     /// Draw polygon on the active layer
     fn draw_polygon_on_layer(&self, document: &mut Document) -> ToolResult<()> {
-        let active_layer = document.active_layer_mut();
+        // Keep the selection readable while drawing on the layer
+        let selection = &document.selection;
+        let active_layer = document
+            .active_layer_index
+            .and_then(|index| document.layers.get_mut(index));
         if active_layer.is_none() {
             debug!("No active layer to draw polygon on");
             return Ok(());
@@ -5884,13 +7144,13 @@ impl PolygonTool {
         // Draw based on shape mode
         match self.shape_mode {
             ShapeMode::Fill => {
-                self.fill_polygon(layer)?;
+                self.fill_polygon(layer, selection)?;
             }
             ShapeMode::Stroke => {
                 self.stroke_polygon(layer)?;
             }
             ShapeMode::Both => {
-                self.fill_polygon(layer)?;
+                self.fill_polygon(layer, selection)?;
                 self.stroke_polygon(layer)?;
             }
         }
@@ -5899,8 +7159,8 @@ impl PolygonTool {
         Ok(())
     }
 
-    /// Fill polygon using scanline algorithm
-    fn fill_polygon(&self, layer: &mut psoc_core::Layer) -> ToolResult<()> {
+    /// Fill polygon using scanline algorithm, faded by the selection coverage
+    fn fill_polygon(&self, layer: &mut psoc_core::Layer, selection: &Selection) -> ToolResult<()> {
         if self.points.len() < 3 {
             return Ok(());
         }
@@ -5941,7 +7201,7 @@ impl PolygonTool {
 
                     for x in start_x..=end_x {
                         if x >= 0 && y >= 0 {
-                            layer.set_pixel(x as u32, y as u32, self.fill_color)?;
+                            fill_pixel(layer, x as u32, y as u32, self.fill_color, selection)?;
                         }
                     }
                 }
@@ -6253,6 +7513,10 @@ impl Tool for PolygonTool {
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -6410,6 +7674,31 @@ mod shape_tool_tests {
         assert_eq!(tool.end_point, None);
     }
 
+    #[test]
+    fn test_rectangle_fill_blends_by_selection_on_offset_layer() {
+        let mut tool = RectangleTool::new();
+        tool.shape_mode = ShapeMode::Fill;
+        tool.fill_color = RgbaPixel::new(255, 0, 0, 255);
+
+        let mut document = Document::new("Test".to_string(), 30, 30);
+        let mut layer = psoc_core::Layer::new_pixel("Moved".to_string(), 20, 20);
+        layer.set_position(Point::new(5.0, 0.0));
+        document.add_layer(layer);
+        document.set_active_layer(0).unwrap();
+        // The selection edge runs through the middle of document column 10
+        document.set_selection(Selection::rectangle(0.0, 0.0, 10.5, 30.0));
+
+        tool.draw_rectangle_on_layer(Point::new(0.0, 0.0), Point::new(19.0, 19.0), &mut document)
+            .unwrap();
+
+        // Layer column x sits on document column x + 5
+        let layer = document.active_layer().unwrap();
+        let alpha = |x| layer.get_pixel(x, 10).unwrap().a;
+        assert_eq!(alpha(4), 255);
+        assert!((126..=129).contains(&alpha(5)));
+        assert_eq!(alpha(6), 0);
+    }
+
     #[test]
     fn test_line_tool_angle_constraint() {
         let mut tool = LineTool::new();
@@ -6542,13 +7831,14 @@ impl CropTool {
                 x, y, width, height
             );
 
-            // TODO: Implement actual crop operation on document
-            // This would involve:
-            // 1. Creating a crop command
-            // 2. Applying it to all layers or selected layer
-            // 3. Updating document dimensions
+            if width < 1.0 || height < 1.0 {
+                debug!("Crop rectangle too small, ignoring");
+                return Ok(());
+            }
 
-            document.mark_dirty();
+            let crop_rect = psoc_core::Rect::new(x, y, width, height);
+            let command = crate::commands::CropDocumentCommand::new(crop_rect, document);
+            document.execute_command(Box::new(command))?;
         }
         Ok(())
     }
@@ -6698,6 +7988,10 @@ impl Tool for CropTool {
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -7142,6 +8436,10 @@ impl Tool for EyedropperTool {
         }
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -7330,3 +8628,503 @@ mod eyedropper_tests {
         assert!(!tool.pick_to_foreground);
     }
 }
+
+/// How close, in pixels, a click must be to an anchor or handle to grab it
+const PEN_HIT_DISTANCE: f32 = 5.0;
+
+/// What a pen tool drag is changing
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PenDrag {
+    /// Pull symmetric handles out of a just-placed anchor
+    NewAnchor { subpath: usize, anchor: usize },
+    /// Move an anchor together with its handles
+    MoveAnchor {
+        subpath: usize,
+        anchor: usize,
+        last: Point,
+    },
+    /// Move one handle; the opposite one mirrors it unless Alt is held
+    MoveHandle {
+        subpath: usize,
+        anchor: usize,
+        outgoing: bool,
+    },
+}
+
+/// Pen tool for drawing and editing bezier paths
+///
+/// Clicking adds corner anchors to the active path and dragging pulls out
+/// smooth handles. Clicking the first anchor closes the subpath, while
+/// Enter or Escape leave it open. Existing anchors and the handles of the
+/// selected anchor can be dragged, and Backspace deletes the selected anchor.
+#[derive(Debug, Default)]
+pub struct PenTool {
+    /// Path being edited, so edits stop when another path is activated
+    path: Option<usize>,
+    /// Subpath of the active path that clicks add anchors to
+    drawing: Option<usize>,
+    /// Selected anchor as subpath and anchor index
+    selected: Option<(usize, usize)>,
+    drag: Option<PenDrag>,
+    /// Active path before the current gesture, committed on release
+    snapshot: Option<PathSnapshot>,
+}
+
+impl PenTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selected anchor as subpath and anchor index
+    pub fn selected_anchor(&self) -> Option<(usize, usize)> {
+        self.selected
+    }
+
+    /// Forget the subpath and anchor being edited if the active path changed
+    fn sync(&mut self, document: &Document) {
+        if self.path != document.active_path {
+            self.path = document.active_path;
+            self.drawing = None;
+            self.selected = None;
+            self.drag = None;
+            self.snapshot = None;
+        }
+    }
+
+    /// Anchor of the active path under a position
+    fn hit_anchor(path: &Path, position: Point) -> Option<(usize, usize)> {
+        path.subpaths
+            .iter()
+            .enumerate()
+            .flat_map(|(s, subpath)| {
+                subpath
+                    .anchors
+                    .iter()
+                    .enumerate()
+                    .map(move |(a, anchor)| (s, a, anchor))
+            })
+            .find(|(_, _, anchor)| anchor.point.distance_to(&position) <= PEN_HIT_DISTANCE)
+            .map(|(s, a, _)| (s, a))
+    }
+
+    /// Handle of the selected anchor under a position; true for the outgoing one
+    fn hit_handle(&self, path: &Path, position: Point) -> Option<bool> {
+        let (s, a) = self.selected?;
+        let anchor = path.subpaths.get(s)?.anchors.get(a)?;
+        if anchor.is_corner() {
+            return None;
+        }
+        if anchor.handle_out.distance_to(&position) <= PEN_HIT_DISTANCE {
+            Some(true)
+        } else if anchor.handle_in.distance_to(&position) <= PEN_HIT_DISTANCE {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    fn press(&mut self, position: Point, document: &mut Document) {
+        let drawing = self.drawing;
+        let selected = self.selected;
+        let handle = document
+            .active_path()
+            .and_then(|path| self.hit_handle(path, position));
+        let path = document.active_path_or_new();
+
+        // Clicking the first anchor closes the subpath being drawn
+        if let Some(s) = drawing {
+            if let Some(subpath) = path.subpaths.get_mut(s) {
+                if !subpath.closed
+                    && subpath.anchors.len() >= 2
+                    && subpath.anchors[0].point.distance_to(&position) <= PEN_HIT_DISTANCE
+                {
+                    debug!("Pen tool closed subpath {}", s);
+                    subpath.closed = true;
+                    self.drawing = None;
+                    self.selected = Some((s, 0));
+                    self.drag = Some(PenDrag::NewAnchor {
+                        subpath: s,
+                        anchor: 0,
+                    });
+                    return;
+                }
+            }
+        }
+
+        if let (Some(outgoing), Some((subpath, anchor))) = (handle, selected) {
+            self.drag = Some(PenDrag::MoveHandle {
+                subpath,
+                anchor,
+                outgoing,
+            });
+            return;
+        }
+
+        if let Some((subpath, anchor)) = Self::hit_anchor(path, position) {
+            self.selected = Some((subpath, anchor));
+            self.drag = Some(PenDrag::MoveAnchor {
+                subpath,
+                anchor,
+                last: position,
+            });
+            return;
+        }
+
+        let s = match drawing.filter(|&s| s < path.subpaths.len()) {
+            Some(s) => s,
+            None => {
+                path.subpaths.push(Subpath::new());
+                path.subpaths.len() - 1
+            }
+        };
+        let anchors = &mut path.subpaths[s].anchors;
+        anchors.push(PathAnchor::corner(position));
+        let anchor = anchors.len() - 1;
+        debug!("Pen tool added anchor {} to subpath {}", anchor, s);
+        self.drawing = Some(s);
+        self.selected = Some((s, anchor));
+        self.drag = Some(PenDrag::NewAnchor { subpath: s, anchor });
+    }
+
+    fn anchor_mut(path: &mut Path, subpath: usize, anchor: usize) -> Option<&mut PathAnchor> {
+        path.subpaths.get_mut(subpath)?.anchors.get_mut(anchor)
+    }
+
+    fn drag_to(&mut self, position: Point, modifiers: KeyModifiers, document: &mut Document) {
+        let Some(drag) = self.drag else {
+            return;
+        };
+        let path = document.active_path_or_new();
+
+        match drag {
+            PenDrag::NewAnchor { subpath, anchor } => {
+                if let Some(anchor) = Self::anchor_mut(path, subpath, anchor) {
+                    *anchor = PathAnchor::smooth(anchor.point, position);
+                }
+            }
+            PenDrag::MoveAnchor {
+                subpath,
+                anchor,
+                last,
+            } => {
+                if let Some(anchor) = Self::anchor_mut(path, subpath, anchor) {
+                    anchor.translate(position.x - last.x, position.y - last.y);
+                }
+                self.drag = Some(PenDrag::MoveAnchor {
+                    subpath,
+                    anchor,
+                    last: position,
+                });
+            }
+            PenDrag::MoveHandle {
+                subpath,
+                anchor,
+                outgoing,
+            } => {
+                if let Some(anchor) = Self::anchor_mut(path, subpath, anchor) {
+                    let mirrored = Point::new(
+                        2.0 * anchor.point.x - position.x,
+                        2.0 * anchor.point.y - position.y,
+                    );
+                    let (handle, opposite) = if outgoing {
+                        (&mut anchor.handle_out, &mut anchor.handle_in)
+                    } else {
+                        (&mut anchor.handle_in, &mut anchor.handle_out)
+                    };
+                    *handle = position;
+                    if !modifiers.alt {
+                        *opposite = mirrored;
+                    }
+                }
+            }
+        }
+        document.mark_dirty();
+    }
+
+    /// Delete the selected anchor, dropping its subpath once it is empty
+    fn delete_selected(&mut self, document: &mut Document) {
+        let Some((s, a)) = self.selected.take() else {
+            return;
+        };
+        let path = document.active_path_or_new();
+        let Some(subpath) = path.subpaths.get_mut(s) else {
+            return;
+        };
+        if a < subpath.anchors.len() {
+            subpath.anchors.remove(a);
+        }
+        if subpath.anchors.len() < 3 {
+            subpath.closed = false;
+        }
+        if subpath.anchors.is_empty() {
+            path.subpaths.remove(s);
+            self.drawing = match self.drawing {
+                Some(d) if d == s => None,
+                Some(d) if d > s => Some(d - 1),
+                other => other,
+            };
+        } else if self.drawing == Some(s) {
+            // Keep extending from the new last anchor
+            self.selected = Some((s, subpath.anchors.len() - 1));
+        }
+        document.mark_dirty();
+    }
+}
+
+impl Tool for PenTool {
+    fn id(&self) -> &'static str {
+        "pen"
+    }
+
+    fn name(&self) -> &'static str {
+        "Pen Tool"
+    }
+
+    fn description(&self) -> &'static str {
+        "Draw and edit bezier paths"
+    }
+
+    fn cursor(&self) -> ToolCursor {
+        ToolCursor::Crosshair
+    }
+
+    fn handle_event(
+        &mut self,
+        event: ToolEvent,
+        document: &mut Document,
+        state: &mut ToolState,
+    ) -> ToolResult<()> {
+        self.sync(document);
+        match event {
+            ToolEvent::MousePressed {
+                position,
+                button: super::tool_trait::MouseButton::Left,
+                ..
+            } => {
+                self.snapshot = Some(PathSnapshot::capture(document));
+                self.press(position, document);
+                self.path = document.active_path;
+                state.is_active = true;
+                state.last_position = Some(position);
+            }
+            ToolEvent::MouseDragged {
+                position,
+                modifiers,
+                ..
+            } => {
+                self.drag_to(position, modifiers, document);
+                state.last_position = Some(position);
+            }
+            ToolEvent::MouseReleased { .. } => {
+                self.drag = None;
+                state.is_active = false;
+                if let Some(snapshot) = self.snapshot.take() {
+                    snapshot.commit(document, "Edit Path")?;
+                }
+            }
+            ToolEvent::KeyPressed { key, .. } => match key {
+                Key::Enter | Key::Escape => {
+                    self.drawing = None;
+                    self.selected = None;
+                }
+                Key::Backspace | Key::Delete => {
+                    let snapshot = PathSnapshot::capture(document);
+                    self.delete_selected(document);
+                    snapshot.commit(document, "Delete Anchor")?;
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod pen_tool_tests {
+    use super::super::tool_trait::{KeyModifiers, MouseButton, ToolEvent, ToolState};
+    use super::*;
+    use psoc_core::{Document, Point};
+
+    fn press(tool: &mut PenTool, document: &mut Document, x: f32, y: f32) {
+        let mut state = ToolState::default();
+        tool.handle_event(
+            ToolEvent::MousePressed {
+                position: Point::new(x, y),
+                button: MouseButton::Left,
+                modifiers: KeyModifiers::default(),
+            },
+            document,
+            &mut state,
+        )
+        .unwrap();
+    }
+
+    fn drag(tool: &mut PenTool, document: &mut Document, x: f32, y: f32) {
+        let mut state = ToolState::default();
+        tool.handle_event(
+            ToolEvent::MouseDragged {
+                position: Point::new(x, y),
+                button: MouseButton::Left,
+                modifiers: KeyModifiers::default(),
+            },
+            document,
+            &mut state,
+        )
+        .unwrap();
+    }
+
+    fn release(tool: &mut PenTool, document: &mut Document) {
+        let mut state = ToolState::default();
+        tool.handle_event(
+            ToolEvent::MouseReleased {
+                position: Point::new(0.0, 0.0),
+                button: MouseButton::Left,
+                modifiers: KeyModifiers::default(),
+            },
+            document,
+            &mut state,
+        )
+        .unwrap();
+    }
+
+    fn click(tool: &mut PenTool, document: &mut Document, x: f32, y: f32) {
+        press(tool, document, x, y);
+        release(tool, document);
+    }
+
+    fn press_key(tool: &mut PenTool, document: &mut Document, key: Key) {
+        let mut state = ToolState::default();
+        tool.handle_event(
+            ToolEvent::KeyPressed {
+                key,
+                modifiers: KeyModifiers::default(),
+            },
+            document,
+            &mut state,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_pen_clicks_build_closed_subpath() {
+        let mut tool = PenTool::new();
+        let mut document = Document::new("Test".to_string(), 100, 100);
+
+        click(&mut tool, &mut document, 10.0, 10.0);
+        click(&mut tool, &mut document, 50.0, 10.0);
+        click(&mut tool, &mut document, 50.0, 50.0);
+        // Clicking near the first anchor closes the subpath
+        click(&mut tool, &mut document, 12.0, 11.0);
+
+        let path = document.active_path().unwrap();
+        assert_eq!(path.name, "Path 1");
+        assert_eq!(path.subpaths.len(), 1);
+        assert!(path.subpaths[0].closed);
+        assert_eq!(path.anchor_count(), 3);
+        assert!(path.subpaths[0].anchors.iter().all(|a| a.is_corner()));
+
+        // The next click starts a new subpath
+        click(&mut tool, &mut document, 80.0, 80.0);
+        assert_eq!(document.active_path().unwrap().subpaths.len(), 2);
+    }
+
+    #[test]
+    fn test_pen_drag_pulls_and_edits_handles() {
+        let mut tool = PenTool::new();
+        let mut document = Document::new("Test".to_string(), 100, 100);
+
+        press(&mut tool, &mut document, 20.0, 20.0);
+        drag(&mut tool, &mut document, 30.0, 20.0);
+        release(&mut tool, &mut document);
+
+        let anchor = document.active_path().unwrap().subpaths[0].anchors[0];
+        assert_eq!(anchor.handle_out, Point::new(30.0, 20.0));
+        assert_eq!(anchor.handle_in, Point::new(10.0, 20.0));
+
+        // Dragging the selected anchor's handle mirrors the other one
+        press(&mut tool, &mut document, 30.0, 20.0);
+        drag(&mut tool, &mut document, 20.0, 30.0);
+        release(&mut tool, &mut document);
+        let anchor = document.active_path().unwrap().subpaths[0].anchors[0];
+        assert_eq!(anchor.handle_out, Point::new(20.0, 30.0));
+        assert_eq!(anchor.handle_in, Point::new(20.0, 10.0));
+
+        // Dragging the anchor moves its handles along
+        press(&mut tool, &mut document, 20.0, 20.0);
+        drag(&mut tool, &mut document, 25.0, 20.0);
+        release(&mut tool, &mut document);
+        let anchor = document.active_path().unwrap().subpaths[0].anchors[0];
+        assert_eq!(anchor.point, Point::new(25.0, 20.0));
+        assert_eq!(anchor.handle_out, Point::new(25.0, 30.0));
+    }
+
+    #[test]
+    fn test_pen_gestures_can_be_undone() {
+        let mut tool = PenTool::new();
+        let mut document = Document::new("Test".to_string(), 100, 100);
+
+        click(&mut tool, &mut document, 10.0, 10.0);
+        click(&mut tool, &mut document, 40.0, 10.0);
+        press(&mut tool, &mut document, 40.0, 10.0);
+        drag(&mut tool, &mut document, 60.0, 30.0);
+        release(&mut tool, &mut document);
+        press_key(&mut tool, &mut document, Key::Backspace);
+        assert_eq!(document.active_path().unwrap().anchor_count(), 1);
+        assert_eq!(document.undo_description(), Some("Delete Anchor"));
+
+        // Selecting an anchor without moving it records nothing
+        press(&mut tool, &mut document, 10.0, 10.0);
+        release(&mut tool, &mut document);
+        assert_eq!(document.undo_description(), Some("Delete Anchor"));
+
+        document.undo().unwrap();
+        let anchor = document.active_path().unwrap().subpaths[0].anchors[1];
+        assert_eq!(anchor.point, Point::new(60.0, 30.0));
+        document.undo().unwrap();
+        let anchor = document.active_path().unwrap().subpaths[0].anchors[1];
+        assert_eq!(anchor.point, Point::new(40.0, 10.0));
+        document.undo().unwrap();
+        assert_eq!(document.active_path().unwrap().anchor_count(), 1);
+
+        // Undoing the first anchor removes the path it created
+        document.undo().unwrap();
+        assert!(document.paths.is_empty());
+        assert!(document.active_path().is_none());
+
+        document.redo().unwrap();
+        document.redo().unwrap();
+        assert_eq!(document.active_path().unwrap().anchor_count(), 2);
+        assert_eq!(document.paths.len(), 1);
+    }
+
+    #[test]
+    fn test_pen_keys_end_and_delete() {
+        let mut tool = PenTool::new();
+        let mut document = Document::new("Test".to_string(), 100, 100);
+
+        click(&mut tool, &mut document, 10.0, 10.0);
+        click(&mut tool, &mut document, 40.0, 10.0);
+        press_key(&mut tool, &mut document, Key::Backspace);
+        assert_eq!(document.active_path().unwrap().anchor_count(), 1);
+
+        // Deleting continues from the previous anchor
+        click(&mut tool, &mut document, 40.0, 40.0);
+        assert_eq!(document.active_path().unwrap().subpaths[0].anchors.len(), 2);
+
+        // Enter leaves the subpath open and the next click starts another
+        press_key(&mut tool, &mut document, Key::Enter);
+        click(&mut tool, &mut document, 80.0, 80.0);
+        let path = document.active_path().unwrap();
+        assert!(!path.subpaths[0].closed);
+        assert_eq!(path.subpaths.len(), 2);
+    }
+}
@@ -12,10 +12,14 @@ use super::{
     canvas::{ImageCanvas, ImageData},
     components::{self, ColorHistory},
     dialogs::{
-        AboutDialog, AboutMessage, BrightnessContrastDialog, BrightnessContrastMessage,
+        apply_asset_edit, AboutDialog, AboutMessage, AnimationExportDialog, AnimationExportMessage,
+        AssetMessage, AssetsDialog, BrightnessContrastDialog, BrightnessContrastMessage,
         ColorPaletteDialog, ColorPaletteMessage, ColorPickerDialog, ColorPickerMessage,
-        GaussianBlurDialog, GaussianBlurMessage, GradientEditor, GradientEditorMessage,
-        PreferencesDialog, PreferencesMessage,
+        ColorRangeDialog, ColorRangeMessage, GaussianBlurDialog, GaussianBlurMessage,
+        GradientEditor, GradientEditorMessage, LayerExportDialog, LayerExportMessage,
+        ModifySelectionDialog, ModifySelectionMessage, PdfExportDialog, PdfExportMessage,
+        PreferencesDialog, PreferencesMessage, RecoveryDialog, RecoveryMessage, RefineEdgeDialog,
+        RefineEdgeMessage, RefineEdgeOutput,
     },
     icons::Icon,
     theme::{spacing, PsocTheme},
@@ -24,8 +28,8 @@ use crate::i18n::{Language, LocalizationManager};
 
 use crate::{
     shortcuts::{
-        iced_key_to_shortcut_key, iced_modifiers_to_shortcut_modifiers, ShortcutAction,
-        ShortcutManager,
+        iced_key_to_shortcut_key, iced_key_to_tool_key, iced_modifiers_to_shortcut_modifiers,
+        iced_modifiers_to_tool_modifiers, ShortcutAction, ShortcutManager,
     },
     tools::{
         tool_trait::{ToolOptionType, ToolOptionValue},
@@ -34,7 +38,7 @@ use crate::{
     PsocError, Result,
 };
 
-use psoc_core::{Command, Document, Layer};
+use psoc_core::{Document, Layer};
 
 // Type alias for complex layer information tuple
 #[allow(clippy::type_complexity)]
@@ -69,8 +73,24 @@ pub struct PsocApp {
     color_picker_dialog: ColorPickerDialog,
     /// Color Palette dialog
     color_palette_dialog: ColorPaletteDialog,
+    /// Color Range selection dialog
+    color_range_dialog: ColorRangeDialog,
+    /// Refine Edge dialog
+    refine_edge_dialog: RefineEdgeDialog,
+    /// Modify Selection dialog
+    modify_selection_dialog: ModifySelectionDialog,
     /// Preferences dialog
     preferences_dialog: PreferencesDialog,
+    /// Crash recovery dialog
+    recovery_dialog: RecoveryDialog,
+    /// PDF export options dialog
+    pdf_export_dialog: PdfExportDialog,
+    /// Export layers dialog
+    layer_export_dialog: LayerExportDialog,
+    /// Export animation dialog
+    animation_export_dialog: AnimationExportDialog,
+    /// Export assets dialog
+    assets_dialog: AssetsDialog,
     /// Image canvas for rendering
     canvas: ImageCanvas,
     /// Tool manager for handling editing tools
@@ -117,6 +137,14 @@ pub struct AppState {
     pub current_language: Language,
     /// Localization manager
     pub localization_manager: LocalizationManager,
+    /// Autosave snapshot manager, if a recovery directory is available
+    pub recovery_manager: Option<crate::file_io::RecoveryManager>,
+    /// Minutes between autosaves (0 disables autosave)
+    pub auto_save_interval: u16,
+    /// Remove GPS and other private metadata when saving
+    pub strip_private_metadata: bool,
+    /// Keyboard modifiers currently held, passed to tools with mouse events
+    pub keyboard_modifiers: crate::tools::tool_trait::KeyModifiers,
 }
 
 /// Status information for display
@@ -147,16 +175,46 @@ pub enum Message {
     OpenDocument,
     /// File selected for opening
     FileSelected(std::path::PathBuf),
-    /// Image loaded successfully
-    ImageLoaded(image::DynamicImage),
+    /// Layered, animated or HDR document loaded successfully
+    DocumentLoaded(Box<Document>),
     /// Save the current document
     SaveDocument,
     /// Save as (with file dialog)
     SaveAsDocument,
     /// File selected for saving
     SaveFileSelected(std::path::PathBuf),
-    /// Image saved successfully
-    ImageSaved,
+    /// Project saved to a file, with the document and revision that were written
+    ProjectSaved {
+        path: std::path::PathBuf,
+        document_id: uuid::Uuid,
+        revision: u64,
+    },
+    /// Document exported to a file that is not its save location
+    DocumentExported(std::path::PathBuf),
+    /// PDF export dialog messages
+    PdfExport(PdfExportMessage),
+    /// Export layers dialog messages
+    LayerExport(LayerExportMessage),
+    /// Export animation dialog messages
+    AnimationExport(AnimationExportMessage),
+    /// Layers or assets exported to the given files
+    FilesExported(Vec<std::path::PathBuf>),
+    /// Export assets dialog messages
+    Assets(AssetMessage),
+    /// Write an autosave snapshot of the current document
+    AutoSave,
+    /// Held keyboard modifiers changed
+    ModifiersChanged(crate::tools::tool_trait::KeyModifiers),
+    /// Key pressed outside any focused widget, forwarded to the active tool
+    ToolKeyPressed(
+        crate::tools::tool_trait::Key,
+        crate::tools::tool_trait::KeyModifiers,
+    ),
+    /// Autosave snapshot finished, with `None` if it was dropped because the
+    /// document was saved or closed while it was being written
+    AutoSaveCompleted(std::result::Result<Option<std::path::PathBuf>, String>),
+    /// Crash recovery dialog messages
+    Recovery(RecoveryMessage),
     /// Exit the application
     Exit,
     /// Change the current tool
@@ -183,6 +241,12 @@ pub enum Message {
     GradientEditor(GradientEditorMessage),
     /// Color Picker dialog messages
     ColorPicker(ColorPickerMessage),
+    /// Color Range dialog messages
+    ColorRange(ColorRangeMessage),
+    /// Refine Edge dialog messages
+    RefineEdge(RefineEdgeMessage),
+    /// Modify Selection dialog messages
+    ModifySelection(ModifySelectionMessage),
     /// Show color picker dialog
     ShowColorPicker,
     /// Color Palette dialog messages
@@ -209,6 +273,10 @@ pub enum Message {
     Shortcut(ShortcutAction),
     /// History panel messages
     History(HistoryMessage),
+    /// Channels panel messages
+    Channel(ChannelMessage),
+    /// Paths panel messages
+    Path(PathMessage),
     /// Language changed
     LanguageChanged(Language),
     /// Error occurred
@@ -224,6 +292,36 @@ pub enum HistoryMessage {
     ClearHistory,
 }
 
+/// Channels panel messages
+#[derive(Debug, Clone)]
+pub enum ChannelMessage {
+    /// Save the current selection as a new alpha channel
+    SaveSelection,
+    /// Load a channel as the selection, combined according to the held modifiers
+    LoadSelection(usize),
+    /// Start or stop editing a channel as a grayscale image
+    ToggleEditing(usize),
+    /// Delete a channel
+    DeleteChannel(usize),
+}
+
+/// Paths panel messages
+#[derive(Debug, Clone)]
+pub enum PathMessage {
+    /// Show and edit a path, or hide it if it is already active
+    ToggleActive(usize),
+    /// Convert the current selection into a new path
+    FromSelection,
+    /// Load a path as the selection, combined according to the held modifiers
+    LoadSelection(usize),
+    /// Stroke a path on the active layer with the brush
+    Stroke(usize),
+    /// Fill a path on the active layer with the foreground color
+    Fill(usize),
+    /// Delete a path
+    Delete(usize),
+}
+
 /// Layer-specific messages
 #[derive(Debug, Clone)]
 pub enum LayerMessage {
@@ -416,6 +514,10 @@ pub enum ViewMessage {
     RemoveVerticalGuide(usize),
     /// Clear all guides
     ClearGuides,
+    /// Set the tone mapping used to display HDR layers
+    SetToneMapping(psoc_core::ToneMapping),
+    /// Set the HDR display exposure in stops
+    SetExposure(f32),
 }
 
 impl Default for PsocApp {
@@ -429,7 +531,15 @@ impl Default for PsocApp {
             gradient_editor: GradientEditor::new(),
             color_picker_dialog: ColorPickerDialog::new(),
             color_palette_dialog: ColorPaletteDialog::new(),
+            color_range_dialog: ColorRangeDialog::new(),
+            refine_edge_dialog: RefineEdgeDialog::new(),
+            modify_selection_dialog: ModifySelectionDialog::new(),
             preferences_dialog: PreferencesDialog::new(),
+            recovery_dialog: RecoveryDialog::new(),
+            pdf_export_dialog: PdfExportDialog::new(),
+            layer_export_dialog: LayerExportDialog::new(),
+            animation_export_dialog: AnimationExportDialog::new(),
+            assets_dialog: AssetsDialog::new(),
             canvas: ImageCanvas::new(),
             tool_manager: ToolManager::new(),
             shortcut_manager: ShortcutManager::new(),
@@ -439,12 +549,31 @@ impl Default for PsocApp {
 
 impl Default for AppState {
     fn default() -> Self {
+        Self::with_preferences(&crate::ui::dialogs::preferences::UserPreferences::default())
+    }
+}
+
+impl AppState {
+    /// Create the startup state, configuring autosave from the given preferences
+    pub fn with_preferences(
+        preferences: &crate::ui::dialogs::preferences::UserPreferences,
+    ) -> Self {
         let mut localization_manager = LocalizationManager::new();
         if let Err(e) = localization_manager.initialize() {
             tracing::error!("Failed to initialize localization: {}", e);
         }
         let current_language = localization_manager.current_language();
 
+        let defaults = preferences.defaults.clone();
+        let recovery_manager =
+            match crate::preferences::PreferencesManager::default_recovery_manager(preferences) {
+                Ok(manager) => Some(manager),
+                Err(e) => {
+                    tracing::warn!("Crash recovery unavailable: {}", e);
+                    None
+                }
+            };
+
         Self {
             document_open: false,
             current_document: None,
@@ -463,6 +592,10 @@ impl Default for AppState {
             mask_editing_layer: None,
             current_language,
             localization_manager,
+            recovery_manager,
+            auto_save_interval: defaults.auto_save_interval,
+            strip_private_metadata: defaults.strip_private_metadata,
+            keyboard_modifiers: Default::default(),
         }
     }
 }
@@ -557,22 +690,72 @@ impl PsocApp {
             error!("Failed to initialize localization: {}", e);
         }
 
+        // Autosave, palettes and gradients start from the saved preferences
+        let saved_preferences = match crate::preferences::PreferencesManager::new() {
+            Ok(manager) => manager.preferences().clone(),
+            Err(e) => {
+                tracing::warn!("Failed to load saved preferences: {}", e);
+                crate::ui::dialogs::preferences::UserPreferences::default()
+            }
+        };
+        let state = AppState::with_preferences(&saved_preferences);
+        let mut tool_manager = ToolManager::new();
+        if let Some(gradient_tool) =
+            tool_manager.tool_mut::<crate::tools::GradientTool>(ToolType::Gradient)
+        {
+            gradient_tool
+                .gradient_manager_mut()
+                .import_gradients(saved_preferences.gradients);
+        }
+
+        // Look for snapshots left behind by a previous session that did not exit cleanly
+        let startup_task = match state.recovery_manager.clone() {
+            Some(manager) => Task::perform(
+                async move {
+                    tokio::task::spawn_blocking(move || {
+                        // Nothing is being written yet, so any temporary file
+                        // is from an autosave that was cut short
+                        if let Err(e) = manager.remove_temp_files() {
+                            tracing::warn!("Failed to remove interrupted snapshots: {}", e);
+                        }
+                        manager.list_recoverable()
+                    })
+                    .await
+                    .map_err(anyhow::Error::from)
+                    .and_then(|result| result)
+                },
+                |result| match result {
+                    Ok(entries) => Message::Recovery(RecoveryMessage::Found(entries)),
+                    Err(e) => Message::Error(format!("Failed to scan for recovery files: {}", e)),
+                },
+            ),
+            None => Task::none(),
+        };
+
         (
             Self {
-                state: AppState::default(),
+                state,
                 error_message: None,
                 about_dialog: AboutDialog::new(),
                 brightness_contrast_dialog: BrightnessContrastDialog::new(),
                 gaussian_blur_dialog: GaussianBlurDialog::new(),
                 gradient_editor: GradientEditor::new(),
                 color_picker_dialog: ColorPickerDialog::new(),
-                color_palette_dialog: ColorPaletteDialog::new(),
+                color_palette_dialog: ColorPaletteDialog::with_palettes(saved_preferences.palettes),
+                color_range_dialog: ColorRangeDialog::new(),
+                refine_edge_dialog: RefineEdgeDialog::new(),
+                modify_selection_dialog: ModifySelectionDialog::new(),
                 preferences_dialog: PreferencesDialog::new(),
+                recovery_dialog: RecoveryDialog::new(),
+                pdf_export_dialog: PdfExportDialog::new(),
+                layer_export_dialog: LayerExportDialog::new(),
+                animation_export_dialog: AnimationExportDialog::new(),
+                assets_dialog: AssetsDialog::new(),
                 canvas: ImageCanvas::new(),
-                tool_manager: ToolManager::new(),
+                tool_manager,
                 shortcut_manager: ShortcutManager::new(),
             },
-            Task::none(),
+            startup_task,
         )
     }
 
@@ -596,11 +779,37 @@ impl PsocApp {
     }
 
     fn update(&mut self, message: Message) -> Task<Message> {
+        let task = self.handle_message(message);
+        self.load_layers_in_use();
+        task
+    }
+
+    /// Decode pending project layers that a message made visible or active
+    fn load_layers_in_use(&mut self) {
+        let Some(document) = self.state.current_document.as_mut() else {
+            return;
+        };
+        if !document.has_pending_layers() {
+            return;
+        }
+
+        match document.load_layers_in_use() {
+            Ok(true) => self.canvas.set_document(document.clone()),
+            Ok(false) => {}
+            Err(e) => {
+                tracing::warn!("Failed to load layer: {:#}", e);
+                self.error_message = Some(format!("Failed to load layer: {:#}", e));
+            }
+        }
+    }
+
+    fn handle_message(&mut self, message: Message) -> Task<Message> {
         debug!("Processing message: {:?}", message);
 
         match message {
             Message::NewDocument => {
                 info!("Creating new document");
+                self.discard_recovery_snapshots();
 
                 // Create a new document with default dimensions
                 let document = Document::new("Untitled".to_string(), 800, 600);
@@ -617,7 +826,14 @@ impl PsocApp {
                     return Task::perform(
                         async {
                             rfd::AsyncFileDialog::new()
-                                .add_filter("Image Files", &["png", "jpg", "jpeg"])
+                                .add_filter(
+                                    "Image Files",
+                                    &["png", "jpg", "jpeg", "tif", "tiff", "webp"],
+                                )
+                                .add_filter(
+                                    "Layered, Animated and HDR Files",
+                                    &["gif", "exr", "hdr", "ora", "psoc"],
+                                )
                                 .pick_file()
                                 .await
                         },
@@ -638,59 +854,59 @@ impl PsocApp {
             Message::FileSelected(path) => {
                 info!("File selected: {}", path.display());
                 let file_manager = self.state.file_manager.clone();
+
+                // Documents keep their layers, color profile and embedded metadata
                 return Task::perform(
-                    async move { file_manager.import_image(&path).await },
+                    async move { file_manager.load_document(&path).await },
                     |result| match result {
-                        Ok(image) => Message::ImageLoaded(image),
-                        Err(e) => Message::Error(format!("Failed to load image: {}", e)),
+                        Ok(document) => Message::DocumentLoaded(Box::new(document)),
+                        Err(e) => Message::Error(format!("Failed to load document: {}", e)),
                     },
                 );
             }
-            Message::ImageLoaded(image) => {
+            Message::DocumentLoaded(document) => {
                 info!(
-                    width = image.width(),
-                    height = image.height(),
-                    "Image loaded successfully"
+                    title = %document.metadata.title,
+                    layers = document.layers.len(),
+                    "Document loaded successfully"
                 );
 
-                // Create document from image
-                match Document::from_image("Loaded Image".to_string(), &image) {
-                    Ok(document) => {
-                        // Set document in canvas for proper layer rendering
-                        self.canvas.set_document(document.clone());
-
-                        self.state.current_document = Some(document);
-                        self.state.current_image = Some(image);
-                        self.state.document_open = true;
-                        self.state.zoom_level = 1.0;
-                        self.state.pan_offset = (0.0, 0.0);
-
-                        // Sync canvas state
-                        self.sync_canvas_state();
+                self.discard_recovery_snapshots();
 
-                        self.error_message = None;
-                    }
-                    Err(e) => {
-                        self.error_message =
-                            Some(format!("Failed to create document from image: {}", e));
-                    }
-                }
+                let document = *document;
+                self.canvas.set_document(document.clone());
+                self.state.current_image = document.flatten().ok();
+                self.state.current_document = Some(document);
+                self.state.document_open = true;
+                self.state.zoom_level = 1.0;
+                self.state.pan_offset = (0.0, 0.0);
+                self.sync_canvas_state();
+                self.error_message = None;
             }
             Message::SaveDocument => {
                 info!("Saving document");
-                if let Some(ref image) = self.state.current_image {
-                    if let Some(ref path) = self.state.current_file_path {
+                if let Some(task) = self
+                    .state
+                    .current_file_path
+                    .clone()
+                    .and_then(|path| self.save_project_task(path))
+                {
+                    return task;
+                }
+                if let Some(task) = self
+                    .state
+                    .current_file_path
+                    .clone()
+                    .and_then(|path| self.export_exr_task(path))
+                {
+                    return task;
+                }
+                if self.state.current_document.is_some() {
+                    if let Some(path) = self.state.current_file_path.clone() {
                         // Save to existing path
-                        let file_manager = self.state.file_manager.clone();
-                        let image_clone = image.clone();
-                        let path_clone = path.clone();
-                        return Task::perform(
-                            async move { file_manager.export_image(&image_clone, &path_clone).await },
-                            |result| match result {
-                                Ok(()) => Message::ImageSaved,
-                                Err(e) => Message::Error(format!("Failed to save image: {}", e)),
-                            },
-                        );
+                        if let Some(task) = self.save_flattened_task(path) {
+                            return task;
+                        }
                     } else {
                         // No existing path, trigger Save As
                         return self.update(Message::SaveAsDocument);
@@ -701,14 +917,19 @@ impl PsocApp {
             }
             Message::SaveAsDocument => {
                 info!("Save As document");
-                if self.state.current_image.is_some() {
+                if self.state.current_document.is_some() {
                     #[cfg(feature = "gui")]
                     {
                         return Task::perform(
                             async {
                                 rfd::AsyncFileDialog::new()
+                                    .add_filter("PSOC Project Files", &["psoc"])
                                     .add_filter("PNG Files", &["png"])
                                     .add_filter("JPEG Files", &["jpg", "jpeg"])
+                                    .add_filter("TIFF Files", &["tif", "tiff"])
+                                    .add_filter("WebP Files", &["webp"])
+                                    .add_filter("OpenEXR Files", &["exr"])
+                                    .add_filter("PDF Files", &["pdf"])
                                     .save_file()
                                     .await
                             },
@@ -731,28 +952,99 @@ impl PsocApp {
             }
             Message::SaveFileSelected(path) => {
                 info!("Save file selected: {}", path.display());
-                if let Some(ref image) = self.state.current_image {
-                    let file_manager = self.state.file_manager.clone();
-                    let image_clone = image.clone();
-                    let path_clone = path.clone();
+                if psoc_file_formats::is_pdf_file(&path) {
+                    // PDF is an export; the options dialog starts it
+                    if let Some(document) = &self.state.current_document {
+                        self.pdf_export_dialog
+                            .show(path, document.icc_profile.is_some());
+                    } else {
+                        self.error_message = Some("No document to export".to_string());
+                    }
+                    return Task::none();
+                }
+                if let Some(task) = self.save_project_task(path.clone()) {
                     self.state.current_file_path = Some(path);
-                    return Task::perform(
-                        async move { file_manager.export_image(&image_clone, &path_clone).await },
-                        |result| match result {
-                            Ok(()) => Message::ImageSaved,
-                            Err(e) => Message::Error(format!("Failed to save image: {}", e)),
-                        },
-                    );
-                } else {
-                    self.error_message = Some("No document to save".to_string());
+                    return task;
                 }
+                if let Some(task) = self.export_exr_task(path.clone()) {
+                    self.state.current_file_path = Some(path);
+                    return task;
+                }
+                if let Some(task) = self.save_flattened_task(path.clone()) {
+                    self.state.current_file_path = Some(path);
+                    return task;
+                }
+                self.error_message = Some("No document to save".to_string());
+            }
+            Message::ProjectSaved {
+                path,
+                document_id,
+                revision,
+            } => {
+                info!("Project saved to {}", path.display());
+                self.error_message = None;
+                // Only a project keeps every layer, so only it makes the document
+                // clean, and only if nothing was edited while it was being written
+                let saved_current = self
+                    .state
+                    .current_document
+                    .as_ref()
+                    .is_some_and(|document| {
+                        document.id == document_id && document.revision == revision
+                    });
+                if saved_current {
+                    self.discard_recovery_snapshots();
+                    if let Some(ref mut document) = self.state.current_document {
+                        document.file_path = Some(path);
+                        document.mark_clean();
+                    }
+                }
+            }
+            Message::DocumentExported(path) => {
+                info!("Document exported to {}", path.display());
+                self.error_message = None;
+            }
+            Message::PdfExport(pdf_msg) => {
+                debug!("PDF export dialog message: {:?}", pdf_msg);
+                return self.handle_pdf_export_message(pdf_msg);
             }
-            Message::ImageSaved => {
-                info!("Image saved successfully");
+            Message::LayerExport(layer_export_msg) => {
+                debug!("Export layers dialog message: {:?}", layer_export_msg);
+                return self.handle_layer_export_message(layer_export_msg);
+            }
+            Message::AnimationExport(animation_msg) => {
+                debug!("Export animation dialog message: {:?}", animation_msg);
+                return self.handle_animation_export_message(animation_msg);
+            }
+            Message::FilesExported(paths) => {
+                info!("Exported {} files", paths.len());
                 self.error_message = None;
             }
+            Message::Assets(asset_msg) => {
+                debug!("Assets dialog message: {:?}", asset_msg);
+                return self.handle_asset_message(asset_msg);
+            }
+            Message::AutoSave => {
+                return self.auto_save();
+            }
+            Message::ModifiersChanged(modifiers) => {
+                self.state.keyboard_modifiers = modifiers;
+            }
+            Message::ToolKeyPressed(key, modifiers) => {
+                self.handle_tool_event(crate::tools::ToolEvent::KeyPressed { key, modifiers });
+            }
+            Message::AutoSaveCompleted(result) => match result {
+                Ok(Some(path)) => debug!("Autosaved document to {}", path.display()),
+                Ok(None) => debug!("Autosave dropped, document was saved or closed"),
+                Err(e) => tracing::warn!("Autosave failed: {}", e),
+            },
+            Message::Recovery(recovery_msg) => {
+                debug!("Recovery dialog message: {:?}", recovery_msg);
+                self.handle_recovery_message(recovery_msg);
+            }
             Message::Exit => {
                 info!("Exiting application");
+                self.discard_recovery_snapshots();
                 return iced::exit();
             }
             Message::ToolChanged(tool) => {
@@ -807,9 +1099,21 @@ impl PsocApp {
                 debug!("Gaussian Blur dialog message: {:?}", gb_msg);
                 self.handle_gaussian_blur_message(gb_msg);
             }
+            Message::ColorRange(cr_msg) => {
+                debug!("Color Range dialog message: {:?}", cr_msg);
+                self.handle_color_range_message(cr_msg);
+            }
+            Message::RefineEdge(re_msg) => {
+                debug!("Refine Edge dialog message: {:?}", re_msg);
+                self.handle_refine_edge_message(re_msg);
+            }
+            Message::ModifySelection(ms_msg) => {
+                debug!("Modify Selection dialog message: {:?}", ms_msg);
+                self.handle_modify_selection_message(ms_msg);
+            }
             Message::GradientEditor(ge_msg) => {
                 debug!("Gradient Editor dialog message: {:?}", ge_msg);
-                self.handle_gradient_editor_message(ge_msg);
+                return self.handle_gradient_editor_message(ge_msg);
             }
             Message::ColorPicker(cp_msg) => {
                 debug!("Color Picker dialog message: {:?}", cp_msg);
@@ -817,7 +1121,7 @@ impl PsocApp {
             }
             Message::ColorPalette(cpal_msg) => {
                 debug!("Color Palette dialog message: {:?}", cpal_msg);
-                self.handle_color_palette_message(cpal_msg);
+                return self.handle_color_palette_message(cpal_msg);
             }
             Message::ShowColorPicker => {
                 info!("Showing color picker dialog");
@@ -844,7 +1148,7 @@ impl PsocApp {
                             rfd::AsyncFileDialog::new()
                                 .add_filter(
                                     "Image Files",
-                                    &["png", "jpg", "jpeg", "bmp", "tiff", "webp"],
+                                    &["png", "jpg", "jpeg", "bmp", "tiff", "webp", "svg"],
                                 )
                                 .pick_file()
                                 .await
@@ -935,6 +1239,14 @@ impl PsocApp {
                 debug!("History message: {:?}", history_msg);
                 self.handle_history_message(history_msg);
             }
+            Message::Channel(channel_msg) => {
+                debug!("Channel message: {:?}", channel_msg);
+                self.handle_channel_message(channel_msg);
+            }
+            Message::Path(path_msg) => {
+                debug!("Path message: {:?}", path_msg);
+                self.handle_path_message(path_msg);
+            }
             Message::LanguageChanged(language) => {
                 debug!("Language changed to: {:?}", language);
                 self.handle_language_change(language);
@@ -949,7 +1261,7 @@ impl PsocApp {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        keyboard::on_key_press(|key, modifiers| {
+        let keyboard = keyboard::on_key_press(|key, modifiers| {
             // Convert iced key and modifiers to our types
             if let Some(shortcut_key) = iced_key_to_shortcut_key(&key) {
                 let shortcut_modifiers = iced_modifiers_to_shortcut_modifiers(modifiers);
@@ -961,7 +1273,33 @@ impl PsocApp {
                 }
             }
             None
-        })
+        });
+        let modifiers = iced::event::listen_with(|event, status, _window| match event {
+            iced::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => Some(
+                Message::ModifiersChanged(iced_modifiers_to_tool_modifiers(modifiers)),
+            ),
+            // Keys typed into a text input stay with it
+            iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. })
+                if status == iced::event::Status::Ignored =>
+            {
+                iced_key_to_tool_key(&key).map(|tool_key| {
+                    Message::ToolKeyPressed(tool_key, iced_modifiers_to_tool_modifiers(modifiers))
+                })
+            }
+            _ => None,
+        });
+        let keyboard = Subscription::batch([keyboard, modifiers]);
+
+        if self.state.auto_save_interval > 0 && self.state.recovery_manager.is_some() {
+            let interval =
+                std::time::Duration::from_secs(u64::from(self.state.auto_save_interval) * 60);
+            Subscription::batch([
+                keyboard,
+                iced::time::every(interval).map(|_| Message::AutoSave),
+            ])
+        } else {
+            keyboard
+        }
     }
 
     fn view(&self) -> Element<Message> {
@@ -995,6 +1333,18 @@ impl PsocApp {
             layers.push(self.gaussian_blur_dialog.view(Message::GaussianBlur));
         }
 
+        if self.color_range_dialog.visible {
+            layers.push(self.color_range_dialog.view(Message::ColorRange));
+        }
+
+        if self.refine_edge_dialog.visible {
+            layers.push(self.refine_edge_dialog.view(Message::RefineEdge));
+        }
+
+        if self.modify_selection_dialog.visible {
+            layers.push(self.modify_selection_dialog.view(Message::ModifySelection));
+        }
+
         if self.gradient_editor.visible {
             layers.push(
                 self.gradient_editor
@@ -1027,6 +1377,52 @@ impl PsocApp {
             );
         }
 
+        if self.recovery_dialog.visible {
+            layers.push(
+                self.recovery_dialog
+                    .view(&self.state.localization_manager)
+                    .map(Message::Recovery),
+            );
+        }
+
+        if self.assets_dialog.visible {
+            if let Some(document) = &self.state.current_document {
+                layers.push(
+                    self.assets_dialog
+                        .view(
+                            &document.assets,
+                            document.selection.bounds().is_some(),
+                            &self.state.localization_manager,
+                        )
+                        .map(Message::Assets),
+                );
+            }
+        }
+
+        if self.layer_export_dialog.visible {
+            layers.push(
+                self.layer_export_dialog
+                    .view(&self.state.localization_manager)
+                    .map(Message::LayerExport),
+            );
+        }
+
+        if self.animation_export_dialog.visible {
+            layers.push(
+                self.animation_export_dialog
+                    .view(&self.state.localization_manager)
+                    .map(Message::AnimationExport),
+            );
+        }
+
+        if self.pdf_export_dialog.visible {
+            layers.push(
+                self.pdf_export_dialog
+                    .view(&self.state.localization_manager)
+                    .map(Message::PdfExport),
+            );
+        }
+
         if layers.len() > 1 {
             iced::widget::stack(layers).into()
         } else {
@@ -1143,10 +1539,7 @@ impl PsocApp {
 
     /// Handle canvas-specific messages
     fn handle_canvas_message(&mut self, message: CanvasMessage) {
-        use crate::tools::{
-            tool_trait::{KeyModifiers, MouseButton},
-            ToolEvent,
-        };
+        use crate::tools::{tool_trait::MouseButton, ToolEvent};
         use psoc_core::Point;
 
         match message {
@@ -1161,7 +1554,7 @@ impl PsocApp {
 
                 let event = ToolEvent::MouseMoved {
                     position: Point::new(x, y),
-                    modifiers: KeyModifiers::default(),
+                    modifiers: self.state.keyboard_modifiers,
                 };
                 self.handle_tool_event(event);
             }
@@ -1170,7 +1563,7 @@ impl PsocApp {
                 let event = ToolEvent::MousePressed {
                     position: Point::new(x, y),
                     button: MouseButton::Left,
-                    modifiers: KeyModifiers::default(),
+                    modifiers: self.state.keyboard_modifiers,
                 };
                 self.handle_tool_event(event);
             }
@@ -1179,7 +1572,7 @@ impl PsocApp {
                 let event = ToolEvent::MouseReleased {
                     position: Point::new(x, y),
                     button: MouseButton::Left,
-                    modifiers: KeyModifiers::default(),
+                    modifiers: self.state.keyboard_modifiers,
                 };
                 self.handle_tool_event(event);
             }
@@ -1236,6 +1629,7 @@ impl PsocApp {
             Message::Adjustment(AdjustmentMessage::ShowAddNoise),
             Message::ShowColorPicker,
             Message::ShowColorPalette,
+            Message::ModifySelection(ModifySelectionMessage::Show),
             Message::ShowPreferences,
             Message::CreateSmartObject,
             Message::View(ViewMessage::ToggleRulers),
@@ -1291,6 +1685,11 @@ impl PsocApp {
                 Message::ToolChanged(ToolType::Eyedropper),
                 self.state.current_tool == ToolType::Eyedropper,
             ),
+            (
+                Icon::Pen,
+                Message::ToolChanged(ToolType::Pen),
+                self.state.current_tool == ToolType::Pen,
+            ),
             // Shape tools
             (
                 Icon::Rectangle,
@@ -1368,6 +1767,11 @@ impl PsocApp {
                 Message::ToolChanged(ToolType::Eyedropper),
                 self.state.current_tool == ToolType::Eyedropper,
             ),
+            (
+                Icon::Pen,
+                Message::ToolChanged(ToolType::Pen),
+                self.state.current_tool == ToolType::Pen,
+            ),
             // Shape tools
             (
                 Icon::Rectangle,
@@ -1392,6 +1796,8 @@ impl PsocApp {
         ];
 
         let layers_content = self.create_layers_content();
+        let channels_content = self.create_channels_content();
+        let paths_content = self.create_paths_content();
         let history_content = self.create_history_content();
 
         column![
@@ -1401,6 +1807,8 @@ impl PsocApp {
                 250.0
             ),
             column(layers_content).spacing(0),
+            channels_content,
+            paths_content,
             history_content,
         ]
         .spacing(spacing::SM)
@@ -1608,6 +2016,7 @@ impl PsocApp {
             ToolType::Line => "Line",
             ToolType::Polygon => "Polygon",
             ToolType::Eyedropper => "Eyedropper",
+            ToolType::Pen => "Pen",
         };
 
         let options = self.tool_manager.get_active_tool_options();
@@ -1843,6 +2252,9 @@ impl PsocApp {
                         None
                     }
                 }),
+                (layer_count > 0).then_some(Message::LayerExport(LayerExportMessage::Show)),
+                (layer_count > 0).then_some(Message::AnimationExport(AnimationExportMessage::Show)),
+                Some(Message::Assets(AssetMessage::Show)),
             )]
         } else {
             // No document open - return empty layer panel
@@ -1853,6 +2265,9 @@ impl PsocApp {
                 None,
                 None,
                 None,
+                None,
+                None,
+                None,
             )]
         }
     }
@@ -1876,6 +2291,228 @@ impl PsocApp {
         }
     }
 
+    /// Create the channels panel content
+    fn create_channels_content(&self) -> Element<'static, Message> {
+        let Some(ref document) = self.state.current_document else {
+            return components::channel_panel(vec![], None, None);
+        };
+
+        let channels = document
+            .channels
+            .iter()
+            .enumerate()
+            .map(|(index, channel)| {
+                (
+                    channel.name.clone(),
+                    document.active_channel == Some(index),
+                    Message::Channel(ChannelMessage::ToggleEditing(index)),
+                    Message::Channel(ChannelMessage::LoadSelection(index)),
+                )
+            })
+            .collect();
+
+        components::channel_panel(
+            channels,
+            Some(Message::Channel(ChannelMessage::SaveSelection)),
+            document
+                .active_channel
+                .map(|index| Message::Channel(ChannelMessage::DeleteChannel(index))),
+        )
+    }
+
+    /// Handle channels panel messages
+    fn handle_channel_message(&mut self, message: ChannelMessage) {
+        use crate::commands::{
+            DeleteChannelCommand, SaveSelectionToChannelCommand, SetSelectionCommand,
+        };
+
+        let Some(document) = self.state.current_document.as_mut() else {
+            self.error_message = Some("No document open".to_string());
+            return;
+        };
+
+        let result = match message {
+            ChannelMessage::SaveSelection => {
+                let command = SaveSelectionToChannelCommand::new(document.next_channel_name());
+                document.execute_command(Box::new(command))
+            }
+            ChannelMessage::LoadSelection(index) => {
+                let mode = crate::tools::tools::selection_mode_for(
+                    self.state.keyboard_modifiers,
+                    psoc_core::SelectionMode::Replace,
+                );
+                document
+                    .channel_selection(index, mode)
+                    .and_then(|selection| {
+                        let command = SetSelectionCommand::new(selection, document);
+                        document.execute_command(Box::new(command))
+                    })
+            }
+            ChannelMessage::ToggleEditing(index) => {
+                let editing = document.active_channel != Some(index);
+                document.set_active_channel(editing.then_some(index))
+            }
+            ChannelMessage::DeleteChannel(index) => {
+                let command = DeleteChannelCommand::new(index, document);
+                document.execute_command(Box::new(command))
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                self.canvas.set_document(document.clone());
+                self.error_message = None;
+            }
+            Err(e) => self.error_message = Some(format!("Channel operation failed: {}", e)),
+        }
+    }
+
+    /// Create the paths panel content
+    fn create_paths_content(&self) -> Element<'static, Message> {
+        let Some(ref document) = self.state.current_document else {
+            return components::path_panel(vec![], None, None);
+        };
+
+        let paths = document
+            .paths
+            .iter()
+            .enumerate()
+            .map(|(index, path)| {
+                (
+                    path.name.clone(),
+                    document.active_path == Some(index),
+                    Message::Path(PathMessage::ToggleActive(index)),
+                )
+            })
+            .collect();
+
+        let actions = document.active_path.map(|index| {
+            (
+                Message::Path(PathMessage::LoadSelection(index)),
+                Message::Path(PathMessage::Stroke(index)),
+                Message::Path(PathMessage::Fill(index)),
+                Message::Path(PathMessage::Delete(index)),
+            )
+        });
+
+        components::path_panel(
+            paths,
+            document
+                .has_selection()
+                .then_some(Message::Path(PathMessage::FromSelection)),
+            actions,
+        )
+    }
+
+    /// Brush size, hardness and color used to stroke paths
+    fn brush_settings(&self) -> (f32, f32, psoc_core::RgbaPixel) {
+        use crate::tools::tool_trait::{Tool, ToolOptionValue};
+
+        let brush = self
+            .tool_manager
+            .tool::<crate::tools::BrushTool>(ToolType::Brush);
+        let option = |name| brush.and_then(|brush| brush.get_option(name));
+        let size = match option("size") {
+            Some(ToolOptionValue::Float(size)) => size,
+            _ => 10.0,
+        };
+        let hardness = match option("hardness") {
+            Some(ToolOptionValue::Float(hardness)) => hardness,
+            _ => 1.0,
+        };
+        let color = match option("color") {
+            Some(ToolOptionValue::Color([r, g, b, a])) => psoc_core::RgbaPixel::new(r, g, b, a),
+            _ => psoc_core::RgbaPixel::new(0, 0, 0, 255),
+        };
+        (size, hardness, color)
+    }
+
+    /// Handle paths panel messages
+    fn handle_path_message(&mut self, message: PathMessage) {
+        use crate::commands::{
+            AddPathCommand, DeletePathCommand, FillPathCommand, SetSelectionCommand,
+            StrokePathCommand,
+        };
+
+        let (brush_size, brush_hardness, brush_color) = self.brush_settings();
+        let fill_color = self
+            .foreground_color()
+            .unwrap_or(psoc_core::RgbaPixel::new(0, 0, 0, 255));
+        let modifiers = self.state.keyboard_modifiers;
+
+        let Some(document) = self.state.current_document.as_mut() else {
+            self.error_message = Some("No document open".to_string());
+            return;
+        };
+        let path = |document: &Document, index: usize| {
+            document
+                .paths
+                .get(index)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("Path index out of bounds: {}", index))
+        };
+        let active_layer = |document: &Document| {
+            document
+                .active_layer_index
+                .ok_or_else(|| anyhow::anyhow!("No active layer"))
+        };
+
+        let result = match message {
+            PathMessage::ToggleActive(index) => {
+                let active = document.active_path != Some(index);
+                document.set_active_path(active.then_some(index))
+            }
+            PathMessage::FromSelection => {
+                let (width, height) = (document.size.width as u32, document.size.height as u32);
+                let path = document
+                    .selection
+                    .to_path(document.next_path_name(), width, height);
+                let command = AddPathCommand::new(path, document.paths.len());
+                document.execute_command(Box::new(command))
+            }
+            PathMessage::LoadSelection(index) => path(document, index).and_then(|path| {
+                let mode = crate::tools::tools::selection_mode_for(
+                    modifiers,
+                    psoc_core::SelectionMode::Replace,
+                );
+                let (width, height) = (document.size.width as u32, document.size.height as u32);
+                let selection = psoc_core::Selection::from_path(&path, width, height);
+                let selection = document.selection.combine(&selection, mode, width, height);
+                let command = SetSelectionCommand::new(selection, document);
+                document.execute_command(Box::new(command))
+            }),
+            PathMessage::Stroke(index) => path(document, index).and_then(|path| {
+                let layer = active_layer(document)?;
+                let command = StrokePathCommand::new(
+                    document,
+                    layer,
+                    path,
+                    brush_size,
+                    brush_hardness,
+                    brush_color,
+                );
+                document.execute_command(Box::new(command))
+            }),
+            PathMessage::Fill(index) => path(document, index).and_then(|path| {
+                let layer = active_layer(document)?;
+                let command = FillPathCommand::new(document, layer, path, fill_color);
+                document.execute_command(Box::new(command))
+            }),
+            PathMessage::Delete(index) => {
+                let command = DeletePathCommand::new(index, document);
+                document.execute_command(Box::new(command))
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                self.canvas.set_document(document.clone());
+                self.error_message = None;
+            }
+            Err(e) => self.error_message = Some(format!("Path operation failed: {}", e)),
+        }
+    }
+
     /// Handle language change
     fn handle_language_change(&mut self, language: Language) {
         info!("Changing language to: {:?}", language);
@@ -2464,7 +3101,7 @@ impl PsocApp {
 
     /// Apply brightness adjustment to the current document
     fn apply_brightness_adjustment(&mut self, brightness: f32) {
-        use crate::commands::ApplyAdjustmentCommand;
+        use crate::commands::execute_adjustment;
         use psoc_core::adjustment::{AdjustmentApplication, AdjustmentScope};
 
         if let Some(ref mut document) = self.state.current_document {
@@ -2477,8 +3114,7 @@ impl PsocApp {
                     active_layer_index,
                 );
 
-                let command = ApplyAdjustmentCommand::new(application);
-                if let Err(e) = command.execute(document) {
+                if let Err(e) = execute_adjustment(document, application) {
                     self.error_message = Some(format!("Failed to apply brightness: {}", e));
                 } else {
                     // Update canvas with the modified document
@@ -2496,7 +3132,7 @@ impl PsocApp {
 
     /// Apply contrast adjustment to the current document
     fn apply_contrast_adjustment(&mut self, contrast: f32) {
-        use crate::commands::ApplyAdjustmentCommand;
+        use crate::commands::execute_adjustment;
         use psoc_core::adjustment::{AdjustmentApplication, AdjustmentScope};
 
         if let Some(ref mut document) = self.state.current_document {
@@ -2509,8 +3145,7 @@ impl PsocApp {
                     active_layer_index,
                 );
 
-                let command = ApplyAdjustmentCommand::new(application);
-                if let Err(e) = command.execute(document) {
+                if let Err(e) = execute_adjustment(document, application) {
                     self.error_message = Some(format!("Failed to apply contrast: {}", e));
                 } else {
                     // Update canvas with the modified document
@@ -2528,7 +3163,7 @@ impl PsocApp {
 
     /// Apply HSL adjustment to the current document
     fn apply_hsl_adjustment(&mut self, hue: f32, saturation: f32, lightness: f32) {
-        use crate::commands::ApplyAdjustmentCommand;
+        use crate::commands::execute_adjustment;
         use psoc_core::adjustment::{AdjustmentApplication, AdjustmentScope};
 
         if let Some(ref mut document) = self.state.current_document {
@@ -2545,8 +3180,7 @@ impl PsocApp {
                     active_layer_index,
                 );
 
-                let command = ApplyAdjustmentCommand::new(application);
-                if let Err(e) = command.execute(document) {
+                if let Err(e) = execute_adjustment(document, application) {
                     self.error_message = Some(format!("Failed to apply HSL adjustment: {}", e));
                 } else {
                     // Update canvas with the modified document
@@ -2564,7 +3198,7 @@ impl PsocApp {
 
     /// Apply grayscale adjustment to the current document
     fn apply_grayscale_adjustment(&mut self, method: String, opacity: f32) {
-        use crate::commands::ApplyAdjustmentCommand;
+        use crate::commands::execute_adjustment;
         use psoc_core::adjustment::{AdjustmentApplication, AdjustmentScope};
 
         if let Some(ref mut document) = self.state.current_document {
@@ -2580,8 +3214,7 @@ impl PsocApp {
                     active_layer_index,
                 );
 
-                let command = ApplyAdjustmentCommand::new(application);
-                if let Err(e) = command.execute(document) {
+                if let Err(e) = execute_adjustment(document, application) {
                     self.error_message =
                         Some(format!("Failed to apply grayscale adjustment: {}", e));
                 } else {
@@ -2612,7 +3245,7 @@ impl PsocApp {
         highlights_magenta_green: f32,
         highlights_yellow_blue: f32,
     ) {
-        use crate::commands::ApplyAdjustmentCommand;
+        use crate::commands::execute_adjustment;
         use psoc_core::adjustment::{AdjustmentApplication, AdjustmentScope};
 
         if let Some(ref mut document) = self.state.current_document {
@@ -2636,8 +3269,7 @@ impl PsocApp {
                     active_layer_index,
                 );
 
-                let command = ApplyAdjustmentCommand::new(application);
-                if let Err(e) = command.execute(document) {
+                if let Err(e) = execute_adjustment(document, application) {
                     self.error_message =
                         Some(format!("Failed to apply color balance adjustment: {}", e));
                 } else {
@@ -2664,7 +3296,7 @@ impl PsocApp {
         blue_curve_points: Vec<(f32, f32)>,
         use_individual_curves: bool,
     ) {
-        use crate::commands::ApplyAdjustmentCommand;
+        use crate::commands::execute_adjustment;
         use psoc_core::adjustment::{AdjustmentApplication, AdjustmentScope};
 
         if let Some(ref mut document) = self.state.current_document {
@@ -2699,8 +3331,7 @@ impl PsocApp {
                     active_layer_index,
                 );
 
-                let command = ApplyAdjustmentCommand::new(application);
-                if let Err(e) = command.execute(document) {
+                if let Err(e) = execute_adjustment(document, application) {
                     self.error_message = Some(format!("Failed to apply curves adjustment: {}", e));
                 } else {
                     self.canvas.set_document(document.clone());
@@ -2726,7 +3357,7 @@ impl PsocApp {
         output_white: u8,
         per_channel: bool,
     ) {
-        use crate::commands::ApplyAdjustmentCommand;
+        use crate::commands::execute_adjustment;
         use psoc_core::adjustment::{AdjustmentApplication, AdjustmentScope};
 
         if let Some(ref mut document) = self.state.current_document {
@@ -2746,8 +3377,7 @@ impl PsocApp {
                     active_layer_index,
                 );
 
-                let command = ApplyAdjustmentCommand::new(application);
-                if let Err(e) = command.execute(document) {
+                if let Err(e) = execute_adjustment(document, application) {
                     self.error_message = Some(format!("Failed to apply levels adjustment: {}", e));
                 } else {
                     self.canvas.set_document(document.clone());
@@ -2764,7 +3394,7 @@ impl PsocApp {
 
     /// Apply Gaussian blur filter to the current document
     fn apply_gaussian_blur_filter(&mut self, radius: f32, quality: f32) {
-        use crate::commands::ApplyAdjustmentCommand;
+        use crate::commands::execute_adjustment;
         use psoc_core::adjustment::{AdjustmentApplication, AdjustmentScope};
 
         if let Some(ref mut document) = self.state.current_document {
@@ -2780,8 +3410,7 @@ impl PsocApp {
                     active_layer_index,
                 );
 
-                let command = ApplyAdjustmentCommand::new(application);
-                if let Err(e) = command.execute(document) {
+                if let Err(e) = execute_adjustment(document, application) {
                     self.error_message = Some(format!("Failed to apply Gaussian blur: {}", e));
                 } else {
                     self.canvas.set_document(document.clone());
@@ -2798,7 +3427,7 @@ impl PsocApp {
 
     /// Apply motion blur filter to the current document
     fn apply_motion_blur_filter(&mut self, distance: f32, angle: f32) {
-        use crate::commands::ApplyAdjustmentCommand;
+        use crate::commands::execute_adjustment;
         use psoc_core::adjustment::{AdjustmentApplication, AdjustmentScope};
 
         if let Some(ref mut document) = self.state.current_document {
@@ -2814,8 +3443,7 @@ impl PsocApp {
                     active_layer_index,
                 );
 
-                let command = ApplyAdjustmentCommand::new(application);
-                if let Err(e) = command.execute(document) {
+                if let Err(e) = execute_adjustment(document, application) {
                     self.error_message = Some(format!("Failed to apply motion blur: {}", e));
                 } else {
                     self.canvas.set_document(document.clone());
@@ -2832,7 +3460,7 @@ impl PsocApp {
 
     /// Apply unsharp mask filter to the current document
     fn apply_unsharp_mask_filter(&mut self, amount: f32, radius: f32, threshold: u8) {
-        use crate::commands::ApplyAdjustmentCommand;
+        use crate::commands::execute_adjustment;
         use psoc_core::adjustment::{AdjustmentApplication, AdjustmentScope};
 
         if let Some(ref mut document) = self.state.current_document {
@@ -2849,8 +3477,7 @@ impl PsocApp {
                     active_layer_index,
                 );
 
-                let command = ApplyAdjustmentCommand::new(application);
-                if let Err(e) = command.execute(document) {
+                if let Err(e) = execute_adjustment(document, application) {
                     self.error_message = Some(format!("Failed to apply unsharp mask: {}", e));
                 } else {
                     self.canvas.set_document(document.clone());
@@ -2867,7 +3494,7 @@ impl PsocApp {
 
     /// Apply sharpen filter to the current document
     fn apply_sharpen_filter(&mut self, strength: f32) {
-        use crate::commands::ApplyAdjustmentCommand;
+        use crate::commands::execute_adjustment;
         use psoc_core::adjustment::{AdjustmentApplication, AdjustmentScope};
 
         if let Some(ref mut document) = self.state.current_document {
@@ -2882,8 +3509,7 @@ impl PsocApp {
                     active_layer_index,
                 );
 
-                let command = ApplyAdjustmentCommand::new(application);
-                if let Err(e) = command.execute(document) {
+                if let Err(e) = execute_adjustment(document, application) {
                     self.error_message = Some(format!("Failed to apply sharpen filter: {}", e));
                 } else {
                     self.canvas.set_document(document.clone());
@@ -2906,7 +3532,7 @@ impl PsocApp {
         monochromatic: bool,
         seed: u32,
     ) {
-        use crate::commands::ApplyAdjustmentCommand;
+        use crate::commands::execute_adjustment;
         use psoc_core::adjustment::{AdjustmentApplication, AdjustmentScope};
 
         if let Some(ref mut document) = self.state.current_document {
@@ -2924,8 +3550,7 @@ impl PsocApp {
                     active_layer_index,
                 );
 
-                let command = ApplyAdjustmentCommand::new(application);
-                if let Err(e) = command.execute(document) {
+                if let Err(e) = execute_adjustment(document, application) {
                     self.error_message = Some(format!("Failed to apply add noise filter: {}", e));
                 } else {
                     self.canvas.set_document(document.clone());
@@ -2942,7 +3567,7 @@ impl PsocApp {
 
     /// Apply reduce noise filter to the current document
     fn apply_reduce_noise_filter(&mut self, strength: u8, preserve_details: f32) {
-        use crate::commands::ApplyAdjustmentCommand;
+        use crate::commands::execute_adjustment;
         use psoc_core::adjustment::{AdjustmentApplication, AdjustmentScope};
 
         if let Some(ref mut document) = self.state.current_document {
@@ -2958,8 +3583,7 @@ impl PsocApp {
                     active_layer_index,
                 );
 
-                let command = ApplyAdjustmentCommand::new(application);
-                if let Err(e) = command.execute(document) {
+                if let Err(e) = execute_adjustment(document, application) {
                     self.error_message =
                         Some(format!("Failed to apply reduce noise filter: {}", e));
                 } else {
@@ -2975,6 +3599,32 @@ impl PsocApp {
         }
     }
 
+    /// Enter or leave Quick Mask mode on the current document
+    fn toggle_quick_mask(&mut self) {
+        use crate::commands::{EnterQuickMaskCommand, ExitQuickMaskCommand};
+
+        let Some(document) = self.state.current_document.as_mut() else {
+            self.error_message = Some("No document open".to_string());
+            return;
+        };
+
+        let result = if document.is_quick_mask_active() {
+            let command = ExitQuickMaskCommand::new(document);
+            document.execute_command(Box::new(command))
+        } else {
+            document.execute_command(Box::new(EnterQuickMaskCommand::new()))
+        };
+
+        match result {
+            Ok(()) => {
+                info!("Quick Mask mode: {}", document.is_quick_mask_active());
+                self.canvas.set_document(document.clone());
+                self.error_message = None;
+            }
+            Err(e) => self.error_message = Some(format!("Failed to toggle Quick Mask: {}", e)),
+        }
+    }
+
     /// Handle tool events
     fn handle_tool_event(&mut self, event: crate::tools::ToolEvent) {
         if let Some(ref mut document) = self.state.current_document {
@@ -3198,49 +3848,357 @@ impl PsocApp {
         );
     }
 
-    /// Handle color picker dialog messages
-    fn handle_color_picker_message(&mut self, message: ColorPickerMessage) {
-        match message {
-            ColorPickerMessage::Apply => {
-                let selected_color = self.color_picker_dialog.current_color();
-
-                // Add color to history
-                self.state.color_history.add_color(selected_color);
-
-                // Apply color to current tool if applicable
-                self.apply_selected_color(selected_color);
+    /// Open the Color Range dialog, previewing against the flattened document
+    fn show_color_range(&mut self) {
+        let Some(document) = self.state.current_document.as_ref() else {
+            self.error_message = Some("No document open".to_string());
+            return;
+        };
 
-                self.color_picker_dialog.hide();
+        match psoc_core::RenderEngine::new().render_document(document) {
+            Ok(flattened) => self.color_range_dialog.show(Some(&flattened)),
+            Err(e) => {
+                tracing::warn!("Failed to render Color Range preview: {}", e);
+                self.color_range_dialog.show(None);
             }
-            ColorPickerMessage::Cancel => {
-                self.color_picker_dialog.hide();
-            }
-            _ => {
-                self.color_picker_dialog.update(message);
+        }
+    }
+
+    /// Current foreground color, used as a Color Range sample
+    fn foreground_color(&self) -> Option<psoc_core::RgbaPixel> {
+        match self.tool_manager.get_tool_option("color") {
+            Some(crate::tools::tool_trait::ToolOptionValue::Color([r, g, b, a])) => {
+                Some(psoc_core::RgbaPixel::new(r, g, b, a))
             }
+            _ => self.state.color_history.most_recent(),
         }
     }
 
-    /// Handle color palette dialog messages
-    fn handle_color_palette_message(&mut self, message: ColorPaletteMessage) {
+    /// Handle Color Range dialog messages
+    fn handle_color_range_message(&mut self, message: ColorRangeMessage) {
         match message {
-            ColorPaletteMessage::SelectColor(color) => {
-                // Add color to history
-                self.state.color_history.add_color(color);
+            ColorRangeMessage::Show => self.show_color_range(),
+            ColorRangeMessage::AddSample => match self.foreground_color() {
+                Some(color) => self.color_range_dialog.add_sample(color),
+                None => self.error_message = Some("No foreground color to sample".to_string()),
+            },
+            ColorRangeMessage::Apply => {
+                use crate::commands::SetSelectionCommand;
 
-                // Apply color to current tool if applicable
-                self.apply_selected_color(color);
+                let range = self.color_range_dialog.range().clone();
+                self.color_range_dialog.update(ColorRangeMessage::Apply);
 
-                // Optionally close the palette dialog
-                // self.color_palette_dialog.hide();
-            }
-            ColorPaletteMessage::Close => {
-                self.color_palette_dialog.hide();
+                let Some(document) = self.state.current_document.as_mut() else {
+                    self.error_message = Some("No document open".to_string());
+                    return;
+                };
+
+                let result = range.select_in_document(document).and_then(|mask| {
+                    let command =
+                        SetSelectionCommand::new(psoc_core::Selection::Mask(mask), document);
+                    document.execute_command(Box::new(command))
+                });
+
+                match result {
+                    Ok(()) => {
+                        self.canvas.set_document(document.clone());
+                        self.error_message = None;
+                    }
+                    Err(e) => {
+                        self.error_message = Some(format!("Failed to select color range: {}", e))
+                    }
+                }
+            }
+            _ => self.color_range_dialog.update(message),
+        }
+    }
+
+    /// Open the Modify Selection dialog for the current selection
+    fn show_modify_selection(&mut self) {
+        match self.state.current_document.as_ref() {
+            Some(document) if document.has_selection() => self.modify_selection_dialog.show(),
+            Some(_) => self.error_message = Some("Modify Selection needs a selection".to_string()),
+            None => self.error_message = Some("No document open".to_string()),
+        }
+    }
+
+    /// Handle Modify Selection dialog messages
+    fn handle_modify_selection_message(&mut self, message: ModifySelectionMessage) {
+        match message {
+            ModifySelectionMessage::Show => self.show_modify_selection(),
+            ModifySelectionMessage::Apply => {
+                use crate::commands::ModifySelectionCommand;
+
+                let modification = self.modify_selection_dialog.modification();
+                self.modify_selection_dialog
+                    .update(ModifySelectionMessage::Apply);
+
+                let Some(document) = self.state.current_document.as_mut() else {
+                    self.error_message = Some("No document open".to_string());
+                    return;
+                };
+
+                let command = ModifySelectionCommand::new(modification, document);
+                match document.execute_command(Box::new(command)) {
+                    Ok(()) => {
+                        self.canvas.set_document(document.clone());
+                        self.error_message = None;
+                    }
+                    Err(e) => {
+                        self.error_message = Some(format!("Failed to modify selection: {}", e))
+                    }
+                }
+            }
+            _ => self.modify_selection_dialog.update(message),
+        }
+    }
+
+    /// Open the Refine Edge dialog for the current selection
+    fn show_refine_edge(&mut self) {
+        match self.state.current_document.as_ref() {
+            Some(document) if document.has_selection() => self.refine_edge_dialog.show(),
+            Some(_) => self.error_message = Some("Refine Edge needs a selection".to_string()),
+            None => self.error_message = Some("No document open".to_string()),
+        }
+    }
+
+    /// Handle Refine Edge dialog messages
+    fn handle_refine_edge_message(&mut self, message: RefineEdgeMessage) {
+        match message {
+            RefineEdgeMessage::Show => self.show_refine_edge(),
+            RefineEdgeMessage::Apply => {
+                use crate::commands::{AddLayerCommand, CompositeCommand, SetSelectionCommand};
+
+                let refine = *self.refine_edge_dialog.refine();
+                let output = self.refine_edge_dialog.output;
+                let decontaminate = self.refine_edge_dialog.decontaminate_amount();
+                self.refine_edge_dialog.update(RefineEdgeMessage::Apply);
+
+                let Some(document) = self.state.current_document.as_mut() else {
+                    self.error_message = Some("No document open".to_string());
+                    return;
+                };
+
+                let result = psoc_core::RenderEngine::new()
+                    .render_document(document)
+                    .and_then(|image| {
+                        let alpha = refine.refine(&document.selection, &image);
+                        match output {
+                            RefineEdgeOutput::Selection => {
+                                let command = SetSelectionCommand::new(
+                                    psoc_core::Selection::Mask(alpha),
+                                    document,
+                                );
+                                document.execute_command(Box::new(command))
+                            }
+                            RefineEdgeOutput::NewLayerWithMask => {
+                                let layer = refine.output_layer(
+                                    "Refined Selection".to_string(),
+                                    &image,
+                                    &alpha,
+                                    decontaminate,
+                                )?;
+                                let index = document
+                                    .active_layer_index
+                                    .map_or(document.layers.len(), |index| index + 1);
+                                // The mask now carries the selection, so drop it
+                                let deselect =
+                                    SetSelectionCommand::new(psoc_core::Selection::None, document);
+                                document.execute_command(Box::new(CompositeCommand::new(
+                                    "Refine Edge".to_string(),
+                                    vec![
+                                        Box::new(AddLayerCommand::new(layer, index)),
+                                        Box::new(deselect),
+                                    ],
+                                )))?;
+                                document.set_active_layer(index)
+                            }
+                        }
+                    });
+
+                match result {
+                    Ok(()) => {
+                        self.canvas.set_document(document.clone());
+                        self.error_message = None;
+                    }
+                    Err(e) => self.error_message = Some(format!("Failed to refine edge: {}", e)),
+                }
+            }
+            _ => self.refine_edge_dialog.update(message),
+        }
+    }
+
+    /// Handle color picker dialog messages
+    fn handle_color_picker_message(&mut self, message: ColorPickerMessage) {
+        match message {
+            ColorPickerMessage::Apply => {
+                let selected_color = self.color_picker_dialog.current_color();
+
+                // Add color to history
+                self.state.color_history.add_color(selected_color);
+
+                // Apply color to current tool if applicable
+                self.apply_selected_color(selected_color);
+
+                self.color_picker_dialog.hide();
+            }
+            ColorPickerMessage::Cancel => {
+                self.color_picker_dialog.hide();
             }
             _ => {
+                self.color_picker_dialog.update(message);
+            }
+        }
+    }
+
+    /// Handle color palette dialog messages
+    fn handle_color_palette_message(&mut self, message: ColorPaletteMessage) -> Task<Message> {
+        match message {
+            ColorPaletteMessage::SelectColor(color) => {
+                // Add color to history
+                self.state.color_history.add_color(color);
+
+                // Apply color to current tool if applicable
+                self.apply_selected_color(color);
+
+                // Optionally close the palette dialog
+                // self.color_palette_dialog.hide();
+            }
+            ColorPaletteMessage::Close => {
+                self.color_palette_dialog.hide();
+            }
+            ColorPaletteMessage::ImportPalette => {
+                #[cfg(feature = "gui")]
+                let task = Task::perform(
+                    async {
+                        rfd::AsyncFileDialog::new()
+                            .add_filter("Palette Files", &["gpl", "aco", "ase", "hex", "txt"])
+                            .pick_file()
+                            .await
+                    },
+                    |file| match file {
+                        Some(file) => Message::ColorPalette(
+                            ColorPaletteMessage::PaletteFileSelected(file.path().to_path_buf()),
+                        ),
+                        None => Message::Error("No palette file selected".to_string()),
+                    },
+                );
+                #[cfg(not(feature = "gui"))]
+                let task = {
+                    self.error_message = Some("File dialogs require GUI feature".to_string());
+                    Task::none()
+                };
+                return task;
+            }
+            ColorPaletteMessage::PaletteFileSelected(path) => {
+                let file_manager = self.state.file_manager.clone();
+                return Task::perform(
+                    async move { file_manager.import_palette(&path).await },
+                    |result| match result {
+                        Ok(palette) => {
+                            Message::ColorPalette(ColorPaletteMessage::PaletteImported(palette))
+                        }
+                        Err(e) => Message::Error(format!("Failed to import palette: {}", e)),
+                    },
+                );
+            }
+            ColorPaletteMessage::ExportPalette => {
+                let Some(palette) = self.color_palette_dialog.current_palette() else {
+                    return Task::none();
+                };
+                #[cfg(feature = "gui")]
+                let task = {
+                    let mut dialog =
+                        rfd::AsyncFileDialog::new().set_file_name(format!("{}.gpl", palette.name));
+                    for format in psoc_file_formats::PaletteFormat::ALL {
+                        dialog = dialog.add_filter(format.description(), &[format.extension()]);
+                    }
+                    Task::perform(async move { dialog.save_file().await }, |file| match file {
+                        Some(file) => Message::ColorPalette(
+                            ColorPaletteMessage::ExportPathSelected(file.path().to_path_buf()),
+                        ),
+                        None => Message::Error("No export file selected".to_string()),
+                    })
+                };
+                #[cfg(not(feature = "gui"))]
+                let task = {
+                    let _ = palette;
+                    self.error_message = Some("File dialogs require GUI feature".to_string());
+                    Task::none()
+                };
+                return task;
+            }
+            ColorPaletteMessage::ExportPathSelected(mut path) => {
+                let Some(palette) = self.color_palette_dialog.current_palette() else {
+                    return Task::none();
+                };
+                let palette = psoc_file_formats::Palette::from(palette);
+                if !psoc_file_formats::is_palette_file(&path) {
+                    path.set_extension(psoc_file_formats::PaletteFormat::Gpl.extension());
+                }
+                let file_manager = self.state.file_manager.clone();
+                return Task::perform(
+                    async move {
+                        file_manager
+                            .export_palette(palette, &path)
+                            .await
+                            .map(|_| path)
+                    },
+                    |result| match result {
+                        Ok(path) => Message::FilesExported(vec![path]),
+                        Err(e) => Message::Error(format!("Failed to export palette: {}", e)),
+                    },
+                );
+            }
+            _ => {
+                let before = self.color_palette_dialog.custom_palettes();
                 self.color_palette_dialog.update(message);
+                if self.color_palette_dialog.custom_palettes() != before {
+                    self.save_palettes();
+                }
             }
         }
+        Task::none()
+    }
+
+    /// Store the custom color palettes in the user preferences file
+    fn save_palettes(&self) {
+        let palettes = self.color_palette_dialog.custom_palettes();
+        self.update_saved_preferences("color palettes", |preferences| {
+            preferences.palettes = palettes;
+        });
+    }
+
+    /// Store the user gradient presets in the user preferences file
+    fn save_gradients(&self) {
+        let gradients = self.user_gradients();
+        self.update_saved_preferences("gradients", |preferences| {
+            preferences.gradients = gradients;
+        });
+    }
+
+    /// Rewrite part of the saved preferences without touching the rest
+    fn update_saved_preferences(
+        &self,
+        what: &str,
+        update: impl FnOnce(&mut crate::ui::dialogs::preferences::UserPreferences),
+    ) {
+        let result = crate::preferences::PreferencesManager::new().and_then(|mut manager| {
+            let mut preferences = manager.preferences().clone();
+            update(&mut preferences);
+            manager.update_preferences(preferences)
+        });
+        if let Err(e) = result {
+            tracing::warn!("Failed to save {}: {}", what, e);
+        }
+    }
+
+    /// User gradient presets held by the gradient tool
+    fn user_gradients(&self) -> Vec<psoc_core::Gradient> {
+        self.tool_manager
+            .tool::<crate::tools::GradientTool>(ToolType::Gradient)
+            .map(|tool| tool.gradient_manager().user_gradients())
+            .unwrap_or_default()
     }
 
     /// Apply selected color to current tool
@@ -3293,7 +4251,17 @@ impl PsocApp {
                 show_status_bar: true, // TODO: Get from actual setting
             },
             performance: PerformancePreferences::default(),
-            defaults: DefaultPreferences::default(),
+            defaults: DefaultPreferences {
+                auto_save_interval: self.state.auto_save_interval,
+                auto_save_retention: self
+                    .state
+                    .recovery_manager
+                    .as_ref()
+                    .map(|manager| manager.retention() as u16)
+                    .unwrap_or(crate::file_io::DEFAULT_RECOVERY_RETENTION as u16),
+                strip_private_metadata: self.state.strip_private_metadata,
+                ..DefaultPreferences::default()
+            },
             advanced: AdvancedPreferences {
                 debug_mode: self.state.debug_mode,
                 log_level: "Info".to_string(), // TODO: Get from actual log level
@@ -3302,6 +4270,8 @@ impl PsocApp {
                 crash_reporting: true,
                 telemetry: false,
             },
+            palettes: self.color_palette_dialog.custom_palettes(),
+            gradients: self.user_gradients(),
         }
     }
 
@@ -3325,6 +4295,375 @@ impl PsocApp {
         }
     }
 
+    /// Write an autosave snapshot of the current document if it has unsaved changes
+    fn auto_save(&self) -> Task<Message> {
+        let (Some(manager), Some(document)) = (
+            self.state.recovery_manager.clone(),
+            self.state.current_document.as_ref(),
+        ) else {
+            return Task::none();
+        };
+
+        if !document.is_dirty {
+            return Task::none();
+        }
+
+        // Taken now, so a save or close before the snapshot is written
+        // discards it instead of leaving it to be offered for recovery
+        let generation = manager.generation(document.id);
+        let document = document.clone();
+        Task::perform(
+            async move {
+                tokio::task::spawn_blocking(move || manager.autosave_at(&document, generation))
+                    .await
+                    .map_err(anyhow::Error::from)
+                    .and_then(|result| result)
+                    .map_err(|e| e.to_string())
+            },
+            Message::AutoSaveCompleted,
+        )
+    }
+
+    /// Remove autosave snapshots of the current document
+    fn discard_recovery_snapshots(&self) {
+        if let (Some(manager), Some(document)) = (
+            self.state.recovery_manager.as_ref(),
+            self.state.current_document.as_ref(),
+        ) {
+            if let Err(e) = manager.discard(document.id) {
+                tracing::warn!("Failed to discard recovery snapshots: {}", e);
+            }
+        }
+    }
+
+    /// Save the current document as a project when saving to a `.psoc` path
+    ///
+    /// This is the only save that keeps the full document, so it is the only
+    /// one that reports back with the revision it wrote.
+    fn save_project_task(&self, path: std::path::PathBuf) -> Option<Task<Message>> {
+        if !psoc_file_formats::FileFormat::from_path(&path)
+            .is_some_and(|format| format.is_project())
+        {
+            return None;
+        }
+        let document = self.state.current_document.clone()?;
+        let file_manager = self.state.file_manager.clone();
+        let document_id = document.id;
+        let revision = document.revision;
+
+        Some(Task::perform(
+            async move {
+                file_manager
+                    .save_project(&document, &path)
+                    .await
+                    .map(|()| path)
+            },
+            move |result| match result {
+                Ok(path) => Message::ProjectSaved {
+                    path,
+                    document_id,
+                    revision,
+                },
+                Err(e) => Message::Error(format!("Failed to save project: {}", e)),
+            },
+        ))
+    }
+
+    /// Export the current document to OpenEXR when saving to an `.exr` path
+    ///
+    /// EXR is written from the document so HDR layers keep their float values.
+    fn export_exr_task(&self, path: std::path::PathBuf) -> Option<Task<Message>> {
+        if psoc_file_formats::HdrFormat::from_path(&path) != Some(psoc_file_formats::HdrFormat::Exr)
+        {
+            return None;
+        }
+        let document = self.state.current_document.clone()?;
+        let file_manager = self.state.file_manager.clone();
+
+        Some(Task::perform(
+            async move {
+                file_manager
+                    .export_exr(&document, &path)
+                    .await
+                    .map(|()| path)
+            },
+            |result| match result {
+                Ok(path) => Message::DocumentExported(path),
+                Err(e) => Message::Error(format!("Failed to save image: {}", e)),
+            },
+        ))
+    }
+
+    /// Export the flattened document with its embedded metadata
+    fn save_flattened_task(&self, path: std::path::PathBuf) -> Option<Task<Message>> {
+        let document = self.state.current_document.clone()?;
+        let file_manager = self.state.file_manager.clone();
+        let strip_private_metadata = self.state.strip_private_metadata;
+
+        Some(Task::perform(
+            async move {
+                file_manager
+                    .export_flattened_with_metadata(&document, &path, strip_private_metadata)
+                    .await
+                    .map(|()| path)
+            },
+            |result| match result {
+                Ok(path) => Message::DocumentExported(path),
+                Err(e) => Message::Error(format!("Failed to save image: {}", e)),
+            },
+        ))
+    }
+
+    /// Handle PDF export dialog messages
+    fn handle_pdf_export_message(&mut self, message: PdfExportMessage) -> Task<Message> {
+        if !matches!(message, PdfExportMessage::Export) {
+            self.pdf_export_dialog.update(message);
+            return Task::none();
+        }
+
+        let Some((path, options)) = self.pdf_export_dialog.take_request() else {
+            return Task::none();
+        };
+        let Some(document) = self.state.current_document.clone() else {
+            self.error_message = Some("No document to export".to_string());
+            return Task::none();
+        };
+        let file_manager = self.state.file_manager.clone();
+
+        Task::perform(
+            async move {
+                file_manager
+                    .export_pdf(&document, &path, &options)
+                    .await
+                    .map(|()| path)
+            },
+            |result| match result {
+                Ok(path) => Message::DocumentExported(path),
+                Err(e) => Message::Error(format!("Failed to export PDF: {}", e)),
+            },
+        )
+    }
+
+    /// Handle export layers dialog messages
+    fn handle_layer_export_message(&mut self, message: LayerExportMessage) -> Task<Message> {
+        match message {
+            LayerExportMessage::Export => {
+                self.layer_export_dialog.update(message);
+                #[cfg(feature = "gui")]
+                let task = Task::perform(
+                    async { rfd::AsyncFileDialog::new().pick_folder().await },
+                    |folder| match folder {
+                        Some(folder) => Message::LayerExport(
+                            LayerExportMessage::DirectorySelected(folder.path().to_path_buf()),
+                        ),
+                        None => Message::Error("No export folder selected".to_string()),
+                    },
+                );
+                #[cfg(not(feature = "gui"))]
+                let task = {
+                    self.error_message = Some("File dialogs require GUI feature".to_string());
+                    Task::none()
+                };
+                task
+            }
+            LayerExportMessage::DirectorySelected(directory) => {
+                let Some(document) = self.state.current_document.clone() else {
+                    self.error_message = Some("No document to export".to_string());
+                    return Task::none();
+                };
+                let options = self.layer_export_dialog.options_for(&document);
+                let file_manager = self.state.file_manager.clone();
+
+                Task::perform(
+                    async move {
+                        file_manager
+                            .export_layers(&document, &directory, &options)
+                            .await
+                    },
+                    |result| match result {
+                        Ok(paths) => Message::FilesExported(paths),
+                        Err(e) => Message::Error(format!("Failed to export layers: {}", e)),
+                    },
+                )
+            }
+            message => {
+                self.layer_export_dialog.update(message);
+                Task::none()
+            }
+        }
+    }
+
+    /// Handle export animation dialog messages
+    fn handle_animation_export_message(
+        &mut self,
+        message: AnimationExportMessage,
+    ) -> Task<Message> {
+        match message {
+            AnimationExportMessage::Show => {
+                if let Some(document) = &self.state.current_document {
+                    self.animation_export_dialog.show(document);
+                }
+                Task::none()
+            }
+            AnimationExportMessage::Export => {
+                self.animation_export_dialog.update(message);
+                #[cfg(feature = "gui")]
+                let task = Task::perform(
+                    async {
+                        rfd::AsyncFileDialog::new()
+                            .add_filter("GIF Files", &["gif"])
+                            .add_filter("Animated PNG Files", &["png", "apng"])
+                            .add_filter("WebP Files", &["webp"])
+                            .save_file()
+                            .await
+                    },
+                    |file| match file {
+                        Some(file) => Message::AnimationExport(
+                            AnimationExportMessage::FileSelected(file.path().to_path_buf()),
+                        ),
+                        None => Message::Error("No export location selected".to_string()),
+                    },
+                );
+                #[cfg(not(feature = "gui"))]
+                let task = {
+                    self.error_message = Some("File dialogs require GUI feature".to_string());
+                    Task::none()
+                };
+                task
+            }
+            AnimationExportMessage::FileSelected(path) => {
+                if psoc_file_formats::AnimationFormat::from_path(&path).is_none() {
+                    self.error_message =
+                        Some(format!("Unsupported animation format: {}", path.display()));
+                    return Task::none();
+                }
+                let Some(document) = self.state.current_document.as_ref() else {
+                    self.error_message = Some("No document to export".to_string());
+                    return Task::none();
+                };
+                let document = self.animation_export_dialog.frame_document(document);
+                let layer_indices = self.animation_export_dialog.layer_indices();
+                let options = self.animation_export_dialog.options.clone();
+                let file_manager = self.state.file_manager.clone();
+
+                Task::perform(
+                    async move {
+                        file_manager
+                            .export_animation(&document, layer_indices, &path, options)
+                            .await
+                            .map(|()| path)
+                    },
+                    |result| match result {
+                        Ok(path) => Message::DocumentExported(path),
+                        Err(e) => Message::Error(format!("Failed to export animation: {}", e)),
+                    },
+                )
+            }
+            message => {
+                self.animation_export_dialog.update(message);
+                Task::none()
+            }
+        }
+    }
+
+    /// Handle export assets dialog messages
+    fn handle_asset_message(&mut self, message: AssetMessage) -> Task<Message> {
+        match message {
+            AssetMessage::ExportAll => {
+                #[cfg(feature = "gui")]
+                let task = Task::perform(
+                    async { rfd::AsyncFileDialog::new().pick_folder().await },
+                    |folder| match folder {
+                        Some(folder) => Message::Assets(AssetMessage::DirectorySelected(
+                            folder.path().to_path_buf(),
+                        )),
+                        None => Message::Error("No export folder selected".to_string()),
+                    },
+                );
+                #[cfg(not(feature = "gui"))]
+                let task = {
+                    self.error_message = Some("File dialogs require GUI feature".to_string());
+                    Task::none()
+                };
+                task
+            }
+            AssetMessage::DirectorySelected(directory) => {
+                let Some(document) = self.state.current_document.clone() else {
+                    self.error_message = Some("No document to export".to_string());
+                    return Task::none();
+                };
+                let file_manager = self.state.file_manager.clone();
+
+                Task::perform(
+                    async move { file_manager.export_assets(&document, &directory).await },
+                    |result| match result {
+                        Ok(paths) => Message::FilesExported(paths),
+                        Err(e) => Message::Error(format!("Failed to export assets: {}", e)),
+                    },
+                )
+            }
+            message => {
+                self.assets_dialog.update(&message);
+                if let Some(document) = &mut self.state.current_document {
+                    apply_asset_edit(document, &message);
+                }
+                Task::none()
+            }
+        }
+    }
+
+    /// Handle crash recovery dialog messages
+    fn handle_recovery_message(&mut self, message: RecoveryMessage) {
+        let Some(manager) = self.state.recovery_manager.clone() else {
+            self.recovery_dialog.hide();
+            return;
+        };
+
+        match message {
+            RecoveryMessage::Restore(index) => {
+                let Some(entry) = self.recovery_dialog.take_entry(index) else {
+                    return;
+                };
+
+                match manager.restore(&entry) {
+                    Ok(document) => {
+                        info!(title = %entry.title, "Restored document from recovery snapshot");
+                        self.canvas.set_document(document.clone());
+                        self.state.current_file_path = entry.original_path.clone();
+                        self.state.current_image = document.flatten().ok();
+                        self.state.current_document = Some(document);
+                        self.state.document_open = true;
+                        self.state.zoom_level = 1.0;
+                        self.state.pan_offset = (0.0, 0.0);
+                        self.sync_canvas_state();
+                        self.error_message = None;
+                    }
+                    Err(e) => {
+                        self.error_message = Some(format!("Failed to restore document: {}", e));
+                    }
+                }
+            }
+            RecoveryMessage::Discard(index) => {
+                if let Some(entry) = self.recovery_dialog.take_entry(index) {
+                    if let Err(e) = manager.discard(entry.document_id) {
+                        self.error_message =
+                            Some(format!("Failed to discard recovery snapshot: {}", e));
+                    }
+                }
+            }
+            RecoveryMessage::DiscardAll => {
+                self.recovery_dialog.update(message);
+                if let Err(e) = manager.discard_all() {
+                    self.error_message =
+                        Some(format!("Failed to discard recovery snapshots: {}", e));
+                }
+            }
+            RecoveryMessage::Found(_) | RecoveryMessage::Hide => {
+                self.recovery_dialog.update(message);
+            }
+        }
+    }
+
     /// Apply preferences to application state
     fn apply_preferences(&mut self, preferences: crate::ui::dialogs::preferences::UserPreferences) {
         // Apply interface preferences
@@ -3338,6 +4677,13 @@ impl PsocApp {
         // Apply debug mode
         self.state.debug_mode = preferences.advanced.debug_mode;
 
+        // Apply autosave settings
+        self.state.auto_save_interval = preferences.defaults.auto_save_interval;
+        self.state.strip_private_metadata = preferences.defaults.strip_private_metadata;
+        if let Some(ref mut manager) = self.state.recovery_manager {
+            manager.set_retention(preferences.defaults.auto_save_retention as usize);
+        }
+
         // TODO: Apply other preferences like performance settings, defaults, etc.
         // This would involve updating various subsystems
 
@@ -3395,6 +4741,15 @@ impl PsocApp {
                 self.canvas.clear_guides();
                 debug!("Cleared all guides");
             }
+            ViewMessage::SetToneMapping(tone_mapping) => {
+                self.canvas.set_tone_mapping(tone_mapping);
+                debug!("Set HDR tone mapping to: {:?}", tone_mapping);
+            }
+            ViewMessage::SetExposure(exposure) => {
+                let tone_mapping = self.canvas.tone_mapping().with_exposure(exposure);
+                self.canvas.set_tone_mapping(tone_mapping);
+                debug!("Set HDR exposure to: {:.1} stops", exposure);
+            }
         }
     }
 
@@ -3480,6 +4835,9 @@ impl PsocApp {
                 // TODO: Implement deselect all
                 debug!("Deselect All not yet implemented");
             }
+            ShortcutAction::ToggleQuickMask => self.toggle_quick_mask(),
+            ShortcutAction::ColorRange => self.show_color_range(),
+            ShortcutAction::RefineEdge => self.show_refine_edge(),
 
             // Tool operations
             ShortcutAction::SelectTool => {
@@ -3522,6 +4880,14 @@ impl PsocApp {
                     self.error_message = None;
                 }
             }
+            ShortcutAction::PenTool => {
+                self.state.current_tool = ToolType::Pen;
+                if let Err(e) = self.tool_manager.set_active_tool(ToolType::Pen) {
+                    self.error_message = Some(format!("Failed to switch tool: {}", e));
+                } else {
+                    self.error_message = None;
+                }
+            }
 
             // View operations
             ShortcutAction::ZoomIn => {
@@ -3590,7 +4956,7 @@ impl PsocApp {
     }
 
     /// Handle gradient editor dialog messages
-    fn handle_gradient_editor_message(&mut self, message: GradientEditorMessage) {
+    fn handle_gradient_editor_message(&mut self, message: GradientEditorMessage) -> Task<Message> {
         match message {
             GradientEditorMessage::Show => {
                 self.gradient_editor.show();
@@ -3598,44 +4964,125 @@ impl PsocApp {
             GradientEditorMessage::Hide => {
                 self.gradient_editor.hide();
             }
-            GradientEditorMessage::SetGradientType(_) => {
-                self.gradient_editor.update(message);
-            }
-            GradientEditorMessage::SetInterpolationMethod(_) => {
-                self.gradient_editor.update(message);
-            }
-            GradientEditorMessage::ToggleRepeat(_) => {
-                self.gradient_editor.update(message);
-            }
-            GradientEditorMessage::AddColorStop => {
-                self.gradient_editor.update(message);
+            GradientEditorMessage::Apply => {
+                if let Some(gradient) = self.gradient_editor.update(message) {
+                    info!("Applied gradient: {}", gradient.name);
+                    if let Some(gradient_tool) = self
+                        .tool_manager
+                        .tool_mut::<crate::tools::GradientTool>(ToolType::Gradient)
+                    {
+                        gradient_tool.set_gradient(gradient);
+                    }
+                }
             }
-            GradientEditorMessage::RemoveColorStop(_) => {
-                self.gradient_editor.update(message);
+            GradientEditorMessage::Import => {
+                #[cfg(feature = "gui")]
+                let task = Task::perform(
+                    async {
+                        rfd::AsyncFileDialog::new()
+                            .add_filter("Gradient Files", &["ggr", "grd"])
+                            .pick_file()
+                            .await
+                    },
+                    |file| match file {
+                        Some(file) => Message::GradientEditor(
+                            GradientEditorMessage::ImportFileSelected(file.path().to_path_buf()),
+                        ),
+                        None => Message::Error("No gradient file selected".to_string()),
+                    },
+                );
+                #[cfg(not(feature = "gui"))]
+                let task = {
+                    self.error_message = Some("File dialogs require GUI feature".to_string());
+                    Task::none()
+                };
+                return task;
             }
-            GradientEditorMessage::UpdateColorStopPosition(_, _) => {
-                self.gradient_editor.update(message);
+            GradientEditorMessage::ImportFileSelected(path) => {
+                let file_manager = self.state.file_manager.clone();
+                return Task::perform(
+                    async move { file_manager.import_gradients(&path).await },
+                    |result| match result {
+                        Ok(gradients) => {
+                            Message::GradientEditor(GradientEditorMessage::Imported(gradients))
+                        }
+                        Err(e) => Message::Error(format!("Failed to import gradients: {}", e)),
+                    },
+                );
             }
-            GradientEditorMessage::UpdateColorStopColor(_, _) => {
-                self.gradient_editor.update(message);
+            GradientEditorMessage::Imported(gradients) => {
+                let Some(gradient_tool) = self
+                    .tool_manager
+                    .tool_mut::<crate::tools::GradientTool>(ToolType::Gradient)
+                else {
+                    return Task::none();
+                };
+                let names = gradient_tool
+                    .gradient_manager_mut()
+                    .import_gradients(gradients);
+                info!("Imported {} gradients", names.len());
+                let Some(first) = names.first() else {
+                    return Task::none();
+                };
+                gradient_tool.select_preset(first);
+                let imported: Vec<_> = names
+                    .iter()
+                    .filter_map(|name| gradient_tool.gradient_manager().get_gradient(name))
+                    .cloned()
+                    .collect();
+                self.gradient_editor
+                    .update(GradientEditorMessage::Imported(imported));
+                self.save_gradients();
             }
-            GradientEditorMessage::SelectColorStop(_) => {
-                self.gradient_editor.update(message);
+            GradientEditorMessage::Export => {
+                #[cfg(feature = "gui")]
+                let task = {
+                    let dialog = rfd::AsyncFileDialog::new()
+                        .add_filter("GIMP Gradient", &["ggr"])
+                        .set_file_name(format!(
+                            "{}.ggr",
+                            self.gradient_editor.current_gradient.name
+                        ));
+                    Task::perform(async move { dialog.save_file().await }, |file| match file {
+                        Some(file) => Message::GradientEditor(
+                            GradientEditorMessage::ExportPathSelected(file.path().to_path_buf()),
+                        ),
+                        None => Message::Error("No export file selected".to_string()),
+                    })
+                };
+                #[cfg(not(feature = "gui"))]
+                let task = {
+                    self.error_message = Some("File dialogs require GUI feature".to_string());
+                    Task::none()
+                };
+                return task;
             }
-            GradientEditorMessage::Apply => {
-                if let Some(gradient) = self.gradient_editor.update(message) {
-                    // Apply gradient to current tool if it's a gradient tool
-                    // TODO: Implement gradient application to tool
-                    info!("Applied gradient: {}", gradient.name);
+            GradientEditorMessage::ExportPathSelected(mut path) => {
+                if psoc_file_formats::GradientFormat::from_path(&path)
+                    != Some(psoc_file_formats::GradientFormat::Ggr)
+                {
+                    path.set_extension(psoc_file_formats::GradientFormat::Ggr.extension());
                 }
+                let gradient = self.gradient_editor.current_gradient.clone();
+                let file_manager = self.state.file_manager.clone();
+                return Task::perform(
+                    async move {
+                        file_manager
+                            .export_gradient(gradient, &path)
+                            .await
+                            .map(|_| path)
+                    },
+                    |result| match result {
+                        Ok(path) => Message::FilesExported(vec![path]),
+                        Err(e) => Message::Error(format!("Failed to export gradient: {}", e)),
+                    },
+                );
             }
-            GradientEditorMessage::Cancel => {
-                self.gradient_editor.update(message);
-            }
-            GradientEditorMessage::Reset => {
+            _ => {
                 self.gradient_editor.update(message);
             }
         }
+        Task::none()
     }
 
     /// Handle creating a smart object from an image file
@@ -3644,6 +5091,15 @@ impl PsocApp {
         path: std::path::PathBuf,
         embed_content: bool,
     ) {
+        let is_svg = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+        if is_svg {
+            self.handle_create_smart_object_from_svg(path);
+            return;
+        }
+
         if let Some(document) = &mut self.state.current_document {
             // Load the image to get its dimensions
             match image::open(&path) {
@@ -3717,6 +5173,52 @@ impl PsocApp {
         }
     }
 
+    /// Handle creating a smart object from an SVG file
+    ///
+    /// SVG content is always embedded so it can be rasterized at the current
+    /// scale instead of being resampled.
+    fn handle_create_smart_object_from_svg(&mut self, path: std::path::PathBuf) {
+        let Some(document) = &mut self.state.current_document else {
+            self.error_message = Some("No document open".to_string());
+            return;
+        };
+
+        let loaded = std::fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|svg_data| Ok((psoc_core::svg_size(&svg_data)?, svg_data)));
+        let (original_size, svg_data) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                self.error_message = Some(format!("Failed to load SVG: {}", e));
+                return;
+            }
+        };
+
+        let layer_name = path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .unwrap_or("Smart Object")
+            .to_string();
+        let content_type = psoc_core::layer::SmartObjectContentType::EmbeddedSvg {
+            original_path: Some(path),
+            svg_data,
+        };
+
+        document.add_layer(psoc_core::Layer::new_smart_object(
+            layer_name,
+            content_type,
+            original_size,
+            psoc_core::geometry::Point::new(0.0, 0.0),
+        ));
+
+        if let Err(e) = document.set_active_layer(document.layer_count() - 1) {
+            self.error_message = Some(format!("Failed to set active layer: {}", e));
+        } else {
+            self.canvas.set_document(document.clone());
+            self.error_message = None;
+        }
+    }
+
     /// Handle replacing smart object content
     fn handle_replace_smart_object_content(
         &mut self,
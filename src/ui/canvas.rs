@@ -126,6 +126,16 @@ impl ImageCanvas {
         self.image_data = None;
     }
 
+    /// Set the tone mapping used to display HDR layers
+    pub fn set_tone_mapping(&mut self, tone_mapping: psoc_core::ToneMapping) {
+        self.renderer.set_tone_mapping(tone_mapping);
+    }
+
+    /// Get the tone mapping used to display HDR layers
+    pub fn tone_mapping(&self) -> psoc_core::ToneMapping {
+        self.renderer.tone_mapping()
+    }
+
     /// Clear the canvas
     pub fn clear(&mut self) {
         debug!("Clearing canvas");
@@ -411,8 +421,22 @@ impl canvas::Program<Message> for ImageCanvas {
         // Draw document or image if available
         if let Some(ref document) = self.document {
             self.draw_document(&mut frame, content_bounds, document);
-            // Draw selection overlay
-            self.draw_selection(&mut frame, content_bounds, document);
+            // Draw the Quick Mask tint or the alpha channel being edited in place of
+            // the selection outline
+            let editing_channel = document
+                .active_channel
+                .and_then(|index| document.channels.get(index));
+            if let Some(ref quick_mask) = document.quick_mask {
+                self.draw_quick_mask(&mut frame, content_bounds, document, quick_mask);
+            } else if let Some(channel) = editing_channel {
+                self.draw_alpha_channel(&mut frame, content_bounds, document, &channel.pixel_data);
+            } else {
+                self.draw_selection(&mut frame, content_bounds, document);
+            }
+            // Draw the path shown in the paths panel
+            if let Some(path) = document.active_path() {
+                self.draw_active_path(&mut frame, content_bounds, document, path);
+            }
             // Draw guides
             if self.state.show_guides {
                 self.draw_guides(&mut frame, content_bounds, document);
@@ -1026,6 +1050,94 @@ impl ImageCanvas {
         }
     }
 
+    /// Tint the unselected parts of the Quick Mask in translucent red
+    fn draw_quick_mask(
+        &self,
+        frame: &mut Frame,
+        bounds: Rectangle,
+        document: &Document,
+        quick_mask: &psoc_core::PixelData,
+    ) {
+        let doc_width = document.size.width * self.state.zoom;
+        let doc_height = document.size.height * self.state.zoom;
+        let doc_x = (bounds.width - doc_width) / 2.0 + self.state.pan_offset.x;
+        let doc_y = (bounds.height - doc_height) / 2.0 + self.state.pan_offset.y;
+
+        // Sample the mask on the same grid used to draw the image
+        let (mask_width, mask_height) = quick_mask.dimensions();
+        let sample_size = 8.0 * self.state.zoom.clamp(0.1, 1.0);
+        if sample_size < 2.0 || mask_width == 0 || mask_height == 0 {
+            return;
+        }
+        let samples_x = (doc_width / sample_size) as u32;
+        let samples_y = (doc_height / sample_size) as u32;
+
+        for sy in 0..samples_y {
+            for sx in 0..samples_x {
+                let mask_x = (sx * mask_width / samples_x.max(1)).min(mask_width - 1);
+                let mask_y = (sy * mask_height / samples_y.max(1)).min(mask_height - 1);
+                let value = quick_mask
+                    .get_pixel(mask_x, mask_y)
+                    .map_or(0, |pixel| pixel.r);
+                if value == 255 {
+                    continue;
+                }
+
+                let opacity = 0.5 * (1.0 - value as f32 / 255.0);
+                frame.fill_rectangle(
+                    Point::new(
+                        doc_x + sx as f32 * sample_size,
+                        doc_y + sy as f32 * sample_size,
+                    ),
+                    Size::new(sample_size, sample_size),
+                    Color::from_rgba(1.0, 0.0, 0.0, opacity),
+                );
+            }
+        }
+    }
+
+    /// Draw the alpha channel being edited as an opaque grayscale image
+    fn draw_alpha_channel(
+        &self,
+        frame: &mut Frame,
+        bounds: Rectangle,
+        document: &Document,
+        channel: &psoc_core::PixelData,
+    ) {
+        let doc_width = document.size.width * self.state.zoom;
+        let doc_height = document.size.height * self.state.zoom;
+        let doc_x = (bounds.width - doc_width) / 2.0 + self.state.pan_offset.x;
+        let doc_y = (bounds.height - doc_height) / 2.0 + self.state.pan_offset.y;
+
+        // Sample the channel on the same grid used to draw the image
+        let (channel_width, channel_height) = channel.dimensions();
+        let sample_size = 8.0 * self.state.zoom.clamp(0.1, 1.0);
+        if sample_size < 2.0 || channel_width == 0 || channel_height == 0 {
+            return;
+        }
+        let samples_x = (doc_width / sample_size) as u32;
+        let samples_y = (doc_height / sample_size) as u32;
+
+        for sy in 0..samples_y {
+            for sx in 0..samples_x {
+                let channel_x = (sx * channel_width / samples_x.max(1)).min(channel_width - 1);
+                let channel_y = (sy * channel_height / samples_y.max(1)).min(channel_height - 1);
+                let value = channel
+                    .get_pixel(channel_x, channel_y)
+                    .map_or(0, |pixel| pixel.r);
+
+                frame.fill_rectangle(
+                    Point::new(
+                        doc_x + sx as f32 * sample_size,
+                        doc_y + sy as f32 * sample_size,
+                    ),
+                    Size::new(sample_size, sample_size),
+                    Color::from_rgb8(value, value, value),
+                );
+            }
+        }
+    }
+
     /// Draw marching ants selection border
     fn draw_marching_ants(&self, frame: &mut Frame, x: f32, y: f32, width: f32, height: f32) {
         let stroke_width = 1.0;
@@ -1142,6 +1254,76 @@ impl ImageCanvas {
         self.draw_selection_handles(frame, bounds_x, bounds_y, bounds_width, bounds_height);
     }
 
+    /// Draw a vector path with its anchors and handles
+    fn draw_active_path(
+        &self,
+        frame: &mut Frame,
+        bounds: Rectangle,
+        document: &Document,
+        path: &psoc_core::Path,
+    ) {
+        let zoom = self.state.zoom;
+        let doc_x = (bounds.width - document.size.width * zoom) / 2.0 + self.state.pan_offset.x;
+        let doc_y = (bounds.height - document.size.height * zoom) / 2.0 + self.state.pan_offset.y;
+        let to_screen =
+            |point: psoc_core::Point| Point::new(doc_x + point.x * zoom, doc_y + point.y * zoom);
+
+        let outline = Path::new(|builder| {
+            for subpath in &path.subpaths {
+                let Some(first) = subpath.anchors.first() else {
+                    continue;
+                };
+                builder.move_to(to_screen(first.point));
+                for [_, control_a, control_b, to] in subpath.segments() {
+                    builder.bezier_curve_to(
+                        to_screen(control_a),
+                        to_screen(control_b),
+                        to_screen(to),
+                    );
+                }
+                if subpath.closed {
+                    builder.close();
+                }
+            }
+        });
+        frame.stroke(
+            &outline,
+            Stroke::default().with_width(3.0).with_color(Color::WHITE),
+        );
+        frame.stroke(
+            &outline,
+            Stroke::default().with_width(1.0).with_color(Color::BLACK),
+        );
+
+        // Handles of smooth anchors, then the anchors on top
+        let handle_stroke = Stroke::default()
+            .with_width(1.0)
+            .with_color(Color::from_rgb(0.2, 0.5, 1.0));
+        for anchor in path.subpaths.iter().flat_map(|subpath| &subpath.anchors) {
+            if anchor.is_corner() {
+                continue;
+            }
+            let point = to_screen(anchor.point);
+            for handle in [anchor.handle_in, anchor.handle_out] {
+                let handle = to_screen(handle);
+                frame.stroke(&Path::line(point, handle), handle_stroke);
+                frame.fill(&Path::circle(handle, 2.5), Color::from_rgb(0.2, 0.5, 1.0));
+            }
+        }
+        for anchor in path.subpaths.iter().flat_map(|subpath| &subpath.anchors) {
+            let point = to_screen(anchor.point);
+            let square = Path::rectangle(
+                Point::new(point.x - 3.0, point.y - 3.0),
+                Size::new(6.0, 6.0),
+            );
+            frame.fill(&square, Color::WHITE);
+            frame.stroke(
+                &square,
+                Stroke::default().with_width(1.0).with_color(Color::BLACK),
+            );
+        }
+    }
+
     /// Draw lasso selection
     fn draw_lasso_selection(
         &self,
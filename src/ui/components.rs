@@ -685,6 +685,7 @@ pub fn layer_item_simple<Message: Clone + 'static>(
 
 /// Create an advanced layer panel with controls
 #[allow(clippy::type_complexity)]
+#[allow(clippy::too_many_arguments)]
 pub fn layer_panel<Message: Clone + 'static>(
     layers: Vec<LayerInfoSimple<Message>>, // (name, visible, selected, opacity, blend_mode, layer_type, has_mask, toggle_vis, select)
     add_layer: Message,
@@ -692,6 +693,9 @@ pub fn layer_panel<Message: Clone + 'static>(
     duplicate_layer: Option<Message>,
     move_up: Option<Message>,
     move_down: Option<Message>,
+    export_layers: Option<Message>,
+    export_animation: Option<Message>,
+    show_assets: Option<Message>,
 ) -> Element<'static, Message> {
     let mut content = Vec::new();
 
@@ -710,6 +714,18 @@ pub fn layer_panel<Message: Clone + 'static>(
         button(text("Dup").size(10.0))
             .on_press_maybe(duplicate_layer)
             .padding([4.0, 8.0]),
+        button(text("Exp").size(10.0))
+            .on_press_maybe(export_layers)
+            .padding([4.0, 8.0])
+            .style(button::secondary),
+        button(text("Anim").size(10.0))
+            .on_press_maybe(export_animation)
+            .padding([4.0, 8.0])
+            .style(button::secondary),
+        button(text("Assets").size(10.0))
+            .on_press_maybe(show_assets)
+            .padding([4.0, 8.0])
+            .style(button::secondary),
         Space::new(Length::Fill, Length::Shrink),
         button(text("↑").size(10.0))
             .on_press_maybe(move_up)
@@ -773,6 +789,150 @@ pub fn layer_panel<Message: Clone + 'static>(
     side_panel("Layers".to_string(), content, 250.0)
 }
 
+/// Channel entry for the channels panel: (name, is_editing, toggle_editing, load_selection)
+pub type ChannelInfo<Message> = (String, bool, Message, Message);
+
+/// Create the channels panel listing the document's alpha channels
+pub fn channel_panel<Message: Clone + 'static>(
+    channels: Vec<ChannelInfo<Message>>,
+    save_selection: Option<Message>,
+    delete_channel: Option<Message>,
+) -> Element<'static, Message> {
+    let mut content = Vec::new();
+
+    let controls = row![
+        button(text("Save Selection").size(10.0))
+            .on_press_maybe(save_selection)
+            .padding([4.0, 8.0]),
+        button(text("Del").size(10.0))
+            .on_press_maybe(delete_channel)
+            .padding([4.0, 8.0]),
+    ]
+    .spacing(4.0)
+    .align_y(iced::alignment::Vertical::Center);
+
+    content.push(container(controls).padding(8.0).into());
+
+    if channels.is_empty() {
+        content.push(
+            container(
+                text("No channels")
+                    .size(12.0)
+                    .style(|_theme| iced::widget::text::Style {
+                        color: Some(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+                    }),
+            )
+            .padding(16.0)
+            .center_x(Length::Fill)
+            .into(),
+        );
+    } else {
+        for (name, is_editing, toggle_editing, load_selection) in channels {
+            let channel_button = button(text(name).size(12.0))
+                .on_press(toggle_editing)
+                .width(Length::Fill)
+                .style(if is_editing {
+                    button::primary
+                } else {
+                    button::secondary
+                });
+
+            content.push(
+                container(
+                    row![
+                        channel_button,
+                        button(text("Load").size(10.0))
+                            .on_press(load_selection)
+                            .padding([4.0, 8.0]),
+                    ]
+                    .spacing(8.0)
+                    .align_y(iced::alignment::Vertical::Center),
+                )
+                .padding([2.0, 8.0])
+                .into(),
+            );
+        }
+    }
+
+    side_panel("Channels".to_string(), content, 250.0)
+}
+
+/// Path entry for the paths panel: (name, is_active, toggle_active)
+pub type PathInfo<Message> = (String, bool, Message);
+
+/// Actions on the active path: (load_selection, stroke, fill, delete)
+pub type PathActions<Message> = (Message, Message, Message, Message);
+
+/// Create the paths panel listing the document's vector paths
+pub fn path_panel<Message: Clone + 'static>(
+    paths: Vec<PathInfo<Message>>,
+    from_selection: Option<Message>,
+    actions: Option<PathActions<Message>>,
+) -> Element<'static, Message> {
+    let mut content = Vec::new();
+
+    let (load_selection, stroke, fill, delete) = match actions {
+        Some((load, stroke, fill, delete)) => (Some(load), Some(stroke), Some(fill), Some(delete)),
+        None => (None, None, None, None),
+    };
+    let action_button = |label: &'static str, message: Option<Message>| {
+        button(text(label).size(10.0))
+            .on_press_maybe(message)
+            .padding([4.0, 8.0])
+    };
+
+    let controls = column![
+        row![
+            action_button("From Selection", from_selection),
+            action_button("Del", delete),
+        ]
+        .spacing(4.0),
+        row![
+            action_button("Load", load_selection),
+            action_button("Stroke", stroke),
+            action_button("Fill", fill),
+        ]
+        .spacing(4.0),
+    ]
+    .spacing(4.0);
+
+    content.push(container(controls).padding(8.0).into());
+
+    if paths.is_empty() {
+        content.push(
+            container(
+                text("No paths")
+                    .size(12.0)
+                    .style(|_theme| iced::widget::text::Style {
+                        color: Some(iced::Color::from_rgb(0.5, 0.5, 0.5)),
+                    }),
+            )
+            .padding(16.0)
+            .center_x(Length::Fill)
+            .into(),
+        );
+    } else {
+        for (name, is_active, toggle_active) in paths {
+            content.push(
+                container(
+                    button(text(name).size(12.0))
+                        .on_press(toggle_active)
+                        .width(Length::Fill)
+                        .style(if is_active {
+                            button::primary
+                        } else {
+                            button::secondary
+                        }),
+                )
+                .padding([2.0, 8.0])
+                .into(),
+            );
+        }
+    }
+
+    side_panel("Paths".to_string(), content, 250.0)
+}
+
 /// Create a modern canvas area placeholder
 pub fn canvas_placeholder<Message: 'static>(
     zoom_level: f32,
@@ -1253,6 +1413,7 @@ pub fn localized_menu_bar<Message: Clone + 'static>(
     add_noise: Message,
     show_color_picker: Message,
     show_color_palette: Message,
+    modify_selection: Message,
     show_preferences: Message,
     create_smart_object: Message,
     toggle_rulers: Message,
@@ -1403,6 +1564,20 @@ pub fn localized_menu_bar<Message: Clone + 'static>(
                 .spacing(8.0)
             )
             .padding(8.0),
+            // Select menu section
+            container(
+                row![
+                    button(text(t("menu-select-modify")).size(12.0).style(|_theme| {
+                        iced::widget::text::Style {
+                            color: Some(iced::Color::WHITE),
+                        }
+                    }))
+                    .on_press(modify_selection)
+                    .padding([4.0, 8.0]),
+                ]
+                .spacing(8.0)
+            )
+            .padding(8.0),
             // Layer menu section
             container(
                 row![button(text("Smart Object").size(12.0).style(|_theme| {
@@ -0,0 +1,334 @@
+//! Export animation dialog for PSOC Image Editor
+
+use iced::{
+    widget::{
+        button, checkbox, column, container, horizontal_rule, row, scrollable, text, text_input,
+        Space,
+    },
+    Alignment, Element, Length,
+};
+use psoc_core::Document;
+use psoc_file_formats::{AnimationOptions, LoopCount};
+use std::path::PathBuf;
+
+use crate::i18n::LocalizationManager;
+use crate::ui::theme::spacing;
+
+/// Messages for the export animation dialog
+#[derive(Debug, Clone)]
+pub enum AnimationExportMessage {
+    /// Show the dialog for the current document
+    Show,
+    /// Toggle whether a layer is exported as a frame
+    FrameToggled(usize, bool),
+    /// Frame delay text input changed (milliseconds)
+    DelayChanged(String),
+    /// Toggle using the frame delay for every frame
+    OverrideDelaysToggled(bool),
+    /// Loop count text input changed (0 loops forever)
+    LoopCountChanged(String),
+    /// Palette size text input changed
+    MaxColorsChanged(String),
+    /// Toggle sharing one palette across all frames
+    GlobalPaletteToggled(bool),
+    /// Quantizer speed text input changed
+    QuantizerSpeedChanged(String),
+    /// Pick a destination file and export
+    Export,
+    /// Destination file chosen
+    FileSelected(PathBuf),
+    /// Close the dialog without exporting
+    Cancel,
+}
+
+/// Dialog choosing the frames, timing and palette of an animation export
+#[derive(Debug, Clone, Default)]
+pub struct AnimationExportDialog {
+    /// Whether the dialog is visible
+    pub visible: bool,
+    /// Layer names, bottom to top, with whether each is exported as a frame
+    pub frames: Vec<(String, bool)>,
+    /// Current export options
+    pub options: AnimationOptions,
+    /// Use the frame delay for every frame instead of only layers without one
+    pub override_delays: bool,
+    /// Frame delay text input value
+    pub delay_text: String,
+    /// Loop count text input value
+    pub loop_text: String,
+    /// Palette size text input value
+    pub colors_text: String,
+    /// Quantizer speed text input value
+    pub speed_text: String,
+}
+
+impl AnimationExportDialog {
+    /// Create a new export animation dialog
+    pub fn new() -> Self {
+        let options = AnimationOptions::default();
+        Self {
+            delay_text: options.default_delay_ms.to_string(),
+            loop_text: "0".to_string(),
+            colors_text: options.palette.max_colors.to_string(),
+            speed_text: options.palette.quantizer_speed.to_string(),
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Show the dialog with every layer of the document selected as a frame
+    pub fn show(&mut self, document: &Document) {
+        self.frames = document
+            .layers
+            .iter()
+            .map(|layer| (layer.name.clone(), true))
+            .collect();
+        self.visible = true;
+    }
+
+    /// Hide the dialog
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Indices of the layers exported as frames, in playback order
+    pub fn layer_indices(&self) -> Vec<usize> {
+        self.frames
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (_, selected))| selected.then_some(index))
+            .collect()
+    }
+
+    /// Copy of the document to export, without stored delays if they are overridden
+    pub fn frame_document(&self, document: &Document) -> Document {
+        let mut document = document.clone();
+        if self.override_delays {
+            for layer in &mut document.layers {
+                layer.metadata.frame_delay_ms = None;
+            }
+        }
+        document
+    }
+
+    /// Update the dialog state
+    ///
+    /// Showing, picking the file and exporting are handled by the application.
+    pub fn update(&mut self, message: AnimationExportMessage) {
+        match message {
+            AnimationExportMessage::FrameToggled(index, selected) => {
+                if let Some(frame) = self.frames.get_mut(index) {
+                    frame.1 = selected;
+                }
+            }
+            AnimationExportMessage::DelayChanged(value) => {
+                if let Ok(delay) = value.trim().parse::<u32>() {
+                    self.options.default_delay_ms = delay.max(1);
+                }
+                self.delay_text = value;
+            }
+            AnimationExportMessage::OverrideDelaysToggled(enabled) => {
+                self.override_delays = enabled;
+            }
+            AnimationExportMessage::LoopCountChanged(value) => {
+                if let Ok(count) = value.trim().parse::<u16>() {
+                    self.options.loop_count = match count {
+                        0 => LoopCount::Infinite,
+                        count => LoopCount::Finite(count),
+                    };
+                }
+                self.loop_text = value;
+            }
+            AnimationExportMessage::MaxColorsChanged(value) => {
+                if let Ok(colors) = value.trim().parse::<u16>() {
+                    self.options.palette.max_colors = colors.clamp(2, 256);
+                }
+                self.colors_text = value;
+            }
+            AnimationExportMessage::GlobalPaletteToggled(enabled) => {
+                self.options.palette.global_palette = enabled;
+            }
+            AnimationExportMessage::QuantizerSpeedChanged(value) => {
+                if let Ok(speed) = value.trim().parse::<i32>() {
+                    self.options.palette.quantizer_speed = speed.clamp(1, 30);
+                }
+                self.speed_text = value;
+            }
+            AnimationExportMessage::Export | AnimationExportMessage::Cancel => self.hide(),
+            AnimationExportMessage::Show | AnimationExportMessage::FileSelected(_) => {}
+        }
+    }
+
+    /// Render the export animation dialog
+    pub fn view<'a>(
+        &'a self,
+        localization: &'a LocalizationManager,
+    ) -> Element<'a, AnimationExportMessage> {
+        if !self.visible {
+            return Space::new(Length::Shrink, Length::Shrink).into();
+        }
+
+        let frames = self.frames.iter().enumerate().fold(
+            column![].spacing(spacing::XS),
+            |frames, (index, (name, selected))| {
+                frames.push(
+                    checkbox(name.as_str(), *selected)
+                        .on_toggle(move |selected| {
+                            AnimationExportMessage::FrameToggled(index, selected)
+                        })
+                        .size(14),
+                )
+            },
+        );
+
+        let number_row =
+            |label: &str, value: &'a str, on_input: fn(String) -> AnimationExportMessage| {
+                row![
+                    text(localization.translate(label))
+                        .size(14)
+                        .width(Length::Fill),
+                    text_input("", value)
+                        .on_input(on_input)
+                        .width(Length::Fixed(80.0))
+                        .size(12),
+                ]
+                .spacing(spacing::SM)
+                .align_y(Alignment::Center)
+            };
+
+        let options = column![
+            text(localization.translate("animation-export-frames")).size(14),
+            scrollable(frames).height(Length::Fixed(160.0)),
+            number_row(
+                "animation-export-delay",
+                &self.delay_text,
+                AnimationExportMessage::DelayChanged
+            ),
+            checkbox(
+                localization.translate("animation-export-override-delays"),
+                self.override_delays
+            )
+            .on_toggle(AnimationExportMessage::OverrideDelaysToggled)
+            .size(14),
+            number_row(
+                "animation-export-loops",
+                &self.loop_text,
+                AnimationExportMessage::LoopCountChanged
+            ),
+            number_row(
+                "animation-export-colors",
+                &self.colors_text,
+                AnimationExportMessage::MaxColorsChanged
+            ),
+            checkbox(
+                localization.translate("animation-export-global-palette"),
+                self.options.palette.global_palette
+            )
+            .on_toggle(AnimationExportMessage::GlobalPaletteToggled)
+            .size(14),
+            number_row(
+                "animation-export-quantizer-speed",
+                &self.speed_text,
+                AnimationExportMessage::QuantizerSpeedChanged
+            ),
+        ]
+        .spacing(spacing::SM);
+
+        let has_frames = self.frames.iter().any(|(_, selected)| *selected);
+        let button_row = row![
+            Space::new(Length::Fill, Length::Shrink),
+            button(text(localization.translate("animation-export-cancel")).size(14))
+                .on_press(AnimationExportMessage::Cancel)
+                .style(button::secondary),
+            button(text(localization.translate("animation-export-export")).size(14))
+                .on_press_maybe(has_frames.then_some(AnimationExportMessage::Export))
+                .style(button::primary),
+        ]
+        .spacing(spacing::SM)
+        .align_y(Alignment::Center);
+
+        let content = column![
+            text(localization.translate("animation-export-title"))
+                .size(20)
+                .style(text::primary),
+            horizontal_rule(1),
+            options,
+            horizontal_rule(1),
+            button_row,
+        ]
+        .spacing(spacing::MD)
+        .padding(spacing::LG)
+        .max_width(420);
+
+        container(content)
+            .center_x(Length::Fill)
+            .center_y(Length::Fill)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use psoc_core::Layer;
+
+    fn document() -> Document {
+        let mut document = Document::new("Anim".to_string(), 4, 4);
+        for name in ["Frame 1", "Frame 2", "Frame 3"] {
+            let mut layer = Layer::new_pixel(name.to_string(), 4, 4);
+            layer.metadata.frame_delay_ms = Some(40);
+            document.add_layer(layer);
+        }
+        document
+    }
+
+    #[test]
+    fn test_show_selects_every_layer() {
+        let mut dialog = AnimationExportDialog::new();
+        dialog.show(&document());
+        assert!(dialog.visible);
+        assert_eq!(dialog.layer_indices(), vec![0, 1, 2]);
+
+        dialog.update(AnimationExportMessage::FrameToggled(1, false));
+        assert_eq!(dialog.layer_indices(), vec![0, 2]);
+    }
+
+    #[test]
+    fn test_text_inputs_update_options() {
+        let mut dialog = AnimationExportDialog::new();
+        dialog.update(AnimationExportMessage::LoopCountChanged("3".to_string()));
+        assert_eq!(dialog.options.loop_count, LoopCount::Finite(3));
+        dialog.update(AnimationExportMessage::LoopCountChanged("0".to_string()));
+        assert_eq!(dialog.options.loop_count, LoopCount::Infinite);
+
+        dialog.update(AnimationExportMessage::MaxColorsChanged("1000".to_string()));
+        assert_eq!(dialog.options.palette.max_colors, 256);
+
+        // Unparsable input keeps the last valid delay
+        dialog.update(AnimationExportMessage::DelayChanged("250".to_string()));
+        dialog.update(AnimationExportMessage::DelayChanged("fast".to_string()));
+        assert_eq!(dialog.options.default_delay_ms, 250);
+        assert_eq!(dialog.delay_text, "fast");
+    }
+
+    #[test]
+    fn test_override_delays_clears_stored_delays() {
+        let document = document();
+        let mut dialog = AnimationExportDialog::new();
+        assert_eq!(
+            dialog.frame_document(&document).layers[0]
+                .metadata
+                .frame_delay_ms,
+            Some(40)
+        );
+
+        dialog.update(AnimationExportMessage::OverrideDelaysToggled(true));
+        let frames = dialog.frame_document(&document);
+        assert!(frames
+            .layers
+            .iter()
+            .all(|layer| layer.metadata.frame_delay_ms.is_none()));
+    }
+}
@@ -0,0 +1,349 @@
+//! Export assets dialog for PSOC Image Editor
+
+use iced::{
+    widget::{
+        button, column, container, horizontal_rule, pick_list, row, scrollable, text, text_input,
+        Space,
+    },
+    Alignment, Element, Length,
+};
+use psoc_core::{Asset, AssetFormat, AssetPreset, Document};
+use std::path::PathBuf;
+
+use crate::i18n::LocalizationManager;
+use crate::ui::theme::spacing;
+
+/// Scales offered for asset presets
+const PRESET_SCALES: [f32; 7] = [0.5, 0.75, 1.0, 1.5, 2.0, 3.0, 4.0];
+
+/// Formats offered for asset presets
+const PRESET_FORMATS: [AssetFormat; 4] = [
+    AssetFormat::Png,
+    AssetFormat::Jpeg,
+    AssetFormat::Tiff,
+    AssetFormat::Webp,
+];
+
+/// Messages for the assets dialog
+#[derive(Debug, Clone)]
+pub enum AssetMessage {
+    /// Show the dialog
+    Show,
+    /// Hide the dialog
+    Hide,
+    /// Add the active layer as an asset
+    AddActiveLayer,
+    /// Add the bounds of the current selection as a slice
+    AddSliceFromSelection,
+    /// Rename the asset at index
+    Rename(usize, String),
+    /// Remove the asset at index
+    Remove(usize),
+    /// Add a preset to the asset at index
+    AddPreset(usize),
+    /// Remove a preset (asset index, preset index)
+    RemovePreset(usize, usize),
+    /// Change a preset scale
+    SetScale(usize, usize, f32),
+    /// Change a preset file name suffix
+    SetSuffix(usize, usize, String),
+    /// Change a preset format
+    SetFormat(usize, usize, AssetFormat),
+    /// Pick a destination folder and export every asset
+    ExportAll,
+    /// Destination folder chosen
+    DirectorySelected(PathBuf),
+}
+
+/// Dialog listing the document's export assets and their presets
+#[derive(Debug, Clone, Default)]
+pub struct AssetsDialog {
+    /// Whether the dialog is visible
+    pub visible: bool,
+}
+
+impl AssetsDialog {
+    /// Create a new assets dialog
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update the dialog visibility
+    ///
+    /// Asset edits are applied to the document with [`apply_asset_edit`];
+    /// exporting is handled by the application.
+    pub fn update(&mut self, message: &AssetMessage) {
+        match message {
+            AssetMessage::Show => self.visible = true,
+            AssetMessage::Hide => self.visible = false,
+            _ => {}
+        }
+    }
+
+    /// Render the assets dialog for a document's assets
+    pub fn view<'a>(
+        &'a self,
+        assets: &'a [Asset],
+        has_selection: bool,
+        localization: &'a LocalizationManager,
+    ) -> Element<'a, AssetMessage> {
+        if !self.visible {
+            return Space::new(Length::Shrink, Length::Shrink).into();
+        }
+
+        let mut asset_list = column![].spacing(spacing::MD);
+        if assets.is_empty() {
+            asset_list = asset_list.push(text(localization.translate("assets-empty")).size(12));
+        }
+        for (index, asset) in assets.iter().enumerate() {
+            let mut presets = column![].spacing(spacing::XS);
+            for (preset_index, preset) in asset.presets.iter().enumerate() {
+                presets = presets.push(
+                    row![
+                        pick_list(PRESET_SCALES, Some(preset.scale), move |scale| {
+                            AssetMessage::SetScale(index, preset_index, scale)
+                        })
+                        .text_size(12),
+                        text("x").size(12),
+                        text_input("@2x", &preset.suffix)
+                            .on_input(move |suffix| {
+                                AssetMessage::SetSuffix(index, preset_index, suffix)
+                            })
+                            .width(Length::Fixed(80.0))
+                            .size(12),
+                        pick_list(PRESET_FORMATS, Some(preset.format), move |format| {
+                            AssetMessage::SetFormat(index, preset_index, format)
+                        })
+                        .text_size(12),
+                        text(psoc_file_formats::asset_file_name(index, asset, preset))
+                            .size(11)
+                            .width(Length::Fill),
+                        button(text("×").size(12))
+                            .on_press(AssetMessage::RemovePreset(index, preset_index))
+                            .style(button::secondary),
+                    ]
+                    .spacing(spacing::SM)
+                    .align_y(Alignment::Center),
+                );
+            }
+
+            asset_list = asset_list.push(
+                column![
+                    row![
+                        text_input("asset", &asset.name)
+                            .on_input(move |name| AssetMessage::Rename(index, name))
+                            .size(14),
+                        button(text(localization.translate("assets-add-preset")).size(12))
+                            .on_press(AssetMessage::AddPreset(index))
+                            .style(button::secondary),
+                        button(text(localization.translate("assets-remove")).size(12))
+                            .on_press(AssetMessage::Remove(index))
+                            .style(button::secondary),
+                    ]
+                    .spacing(spacing::SM)
+                    .align_y(Alignment::Center),
+                    presets,
+                ]
+                .spacing(spacing::XS),
+            );
+        }
+
+        let button_row = row![
+            button(text(localization.translate("assets-add-layer")).size(14))
+                .on_press(AssetMessage::AddActiveLayer)
+                .style(button::secondary),
+            button(text(localization.translate("assets-add-slice")).size(14))
+                .on_press_maybe(has_selection.then_some(AssetMessage::AddSliceFromSelection))
+                .style(button::secondary),
+            Space::new(Length::Fill, Length::Shrink),
+            button(text(localization.translate("assets-close")).size(14))
+                .on_press(AssetMessage::Hide)
+                .style(button::secondary),
+            button(text(localization.translate("assets-export-all")).size(14))
+                .on_press_maybe((!assets.is_empty()).then_some(AssetMessage::ExportAll))
+                .style(button::primary),
+        ]
+        .spacing(spacing::SM)
+        .align_y(Alignment::Center);
+
+        let content = column![
+            text(localization.translate("assets-title"))
+                .size(20)
+                .style(text::primary),
+            horizontal_rule(1),
+            scrollable(asset_list).height(Length::Shrink),
+            horizontal_rule(1),
+            button_row,
+        ]
+        .spacing(spacing::MD)
+        .padding(spacing::LG)
+        .max_width(640);
+
+        container(content)
+            .center_x(Length::Fill)
+            .center_y(Length::Fill)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+/// Apply an asset edit to a document
+///
+/// Returns whether the document changed. Visibility and export messages are
+/// ignored.
+pub fn apply_asset_edit(document: &mut Document, message: &AssetMessage) -> bool {
+    match message {
+        AssetMessage::AddActiveLayer => {
+            let Some(layer) = document.active_layer() else {
+                return false;
+            };
+            let layer_id = layer.id;
+            let already_added = document.assets.iter().any(
+                |asset| matches!(asset.source, psoc_core::AssetSource::Layer(id) if id == layer_id),
+            );
+            if already_added {
+                return false;
+            }
+            let name = layer.name.clone();
+            document.add_asset(Asset::from_layer(name, layer_id));
+            true
+        }
+        AssetMessage::AddSliceFromSelection => {
+            let Some(bounds) = document.selection.bounds() else {
+                return false;
+            };
+            let name = format!("slice-{}", document.assets.len() + 1);
+            document.add_asset(Asset::from_slice(name, bounds));
+            true
+        }
+        AssetMessage::Remove(index) => {
+            let Some(id) = document.assets.get(*index).map(|asset| asset.id) else {
+                return false;
+            };
+            document.remove_asset(id).is_some()
+        }
+        AssetMessage::Rename(index, name) => {
+            edit_asset(document, *index, |asset| asset.name = name.clone())
+        }
+        AssetMessage::AddPreset(index) => edit_asset(document, *index, |asset| {
+            let scale = asset.presets.len() as f32 + 1.0;
+            asset.presets.push(AssetPreset::new(
+                scale,
+                format!("@{}x", scale),
+                AssetFormat::Png,
+            ));
+        }),
+        AssetMessage::RemovePreset(index, preset) => edit_asset(document, *index, |asset| {
+            if *preset < asset.presets.len() {
+                asset.presets.remove(*preset);
+            }
+        }),
+        AssetMessage::SetScale(index, preset, scale) => {
+            edit_preset(document, *index, *preset, |p| p.scale = *scale)
+        }
+        AssetMessage::SetSuffix(index, preset, suffix) => {
+            edit_preset(document, *index, *preset, |p| p.suffix = suffix.clone())
+        }
+        AssetMessage::SetFormat(index, preset, format) => {
+            edit_preset(document, *index, *preset, |p| p.format = *format)
+        }
+        AssetMessage::Show
+        | AssetMessage::Hide
+        | AssetMessage::ExportAll
+        | AssetMessage::DirectorySelected(_) => false,
+    }
+}
+
+fn edit_asset(document: &mut Document, index: usize, edit: impl FnOnce(&mut Asset)) -> bool {
+    let Some(asset) = document.assets.get_mut(index) else {
+        return false;
+    };
+    edit(asset);
+    document.mark_dirty();
+    true
+}
+
+fn edit_preset(
+    document: &mut Document,
+    index: usize,
+    preset: usize,
+    edit: impl FnOnce(&mut AssetPreset),
+) -> bool {
+    let exists = document
+        .assets
+        .get(index)
+        .is_some_and(|asset| preset < asset.presets.len());
+    exists && edit_asset(document, index, |asset| edit(&mut asset.presets[preset]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use psoc_core::{AssetSource, Layer, Selection};
+
+    fn document() -> Document {
+        let mut document = Document::new("Icons".to_string(), 32, 32);
+        document.add_layer(Layer::new_pixel("Logo".to_string(), 32, 32));
+        document.set_active_layer(0).unwrap();
+        document
+    }
+
+    #[test]
+    fn test_add_active_layer_once() {
+        let mut document = document();
+        assert!(apply_asset_edit(
+            &mut document,
+            &AssetMessage::AddActiveLayer
+        ));
+        assert!(!apply_asset_edit(
+            &mut document,
+            &AssetMessage::AddActiveLayer
+        ));
+
+        assert_eq!(document.assets.len(), 1);
+        assert_eq!(document.assets[0].name, "Logo");
+        assert_eq!(
+            document.assets[0].source,
+            AssetSource::Layer(document.layers[0].id)
+        );
+        assert_eq!(document.assets[0].presets, AssetPreset::densities());
+    }
+
+    #[test]
+    fn test_slice_needs_selection() {
+        let mut document = document();
+        assert!(!apply_asset_edit(
+            &mut document,
+            &AssetMessage::AddSliceFromSelection
+        ));
+
+        document.set_selection(Selection::rectangle(2.0, 4.0, 8.0, 6.0));
+        assert!(apply_asset_edit(
+            &mut document,
+            &AssetMessage::AddSliceFromSelection
+        ));
+        assert!(matches!(document.assets[0].source, AssetSource::Slice(_)));
+    }
+
+    #[test]
+    fn test_edit_presets() {
+        let mut document = document();
+        apply_asset_edit(&mut document, &AssetMessage::AddActiveLayer);
+
+        apply_asset_edit(&mut document, &AssetMessage::SetScale(0, 0, 0.5));
+        apply_asset_edit(
+            &mut document,
+            &AssetMessage::SetFormat(0, 0, AssetFormat::Webp),
+        );
+        apply_asset_edit(&mut document, &AssetMessage::RemovePreset(0, 2));
+        assert!(!apply_asset_edit(
+            &mut document,
+            &AssetMessage::SetSuffix(0, 5, "@9x".to_string())
+        ));
+
+        let presets = &document.assets[0].presets;
+        assert_eq!(presets.len(), 2);
+        assert_eq!(presets[0], AssetPreset::new(0.5, "", AssetFormat::Webp));
+    }
+}
@@ -5,8 +5,10 @@ use iced::{
     Element, Length,
 };
 use psoc_core::RgbaPixel;
+use psoc_file_formats::Palette;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 use crate::ui::theme::spacing;
 
@@ -35,14 +37,20 @@ pub enum ColorPaletteMessage {
     PaletteNameChanged(String),
     /// Import palette from file
     ImportPalette,
+    /// Palette file chosen for import
+    PaletteFileSelected(PathBuf),
+    /// Palette read from a file
+    PaletteImported(Palette),
     /// Export palette to file
     ExportPalette,
+    /// Destination chosen for exporting the current palette
+    ExportPathSelected(PathBuf),
     /// Close dialog
     Close,
 }
 
 /// A color palette
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ColorPalette {
     /// Palette name
     pub name: String,
@@ -93,6 +101,22 @@ impl ColorPalette {
     }
 }
 
+impl From<Palette> for ColorPalette {
+    fn from(palette: Palette) -> Self {
+        Self {
+            name: palette.name,
+            colors: palette.colors,
+            built_in: false,
+        }
+    }
+}
+
+impl From<&ColorPalette> for Palette {
+    fn from(palette: &ColorPalette) -> Self {
+        Palette::new(palette.name.clone(), palette.colors.clone())
+    }
+}
+
 /// Color palette dialog state
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ColorPaletteDialog {
@@ -165,6 +189,56 @@ impl ColorPaletteDialog {
         }
     }
 
+    /// Create a dialog with previously saved custom palettes
+    pub fn with_palettes(palettes: Vec<ColorPalette>) -> Self {
+        let mut dialog = Self::new();
+        for palette in palettes {
+            dialog.add_custom_palette(palette);
+        }
+        dialog.current_palette = Some("Basic Colors".to_string());
+        dialog
+    }
+
+    /// Palettes created or imported by the user, sorted by name
+    ///
+    /// These are the palettes stored in the user preferences.
+    pub fn custom_palettes(&self) -> Vec<ColorPalette> {
+        let mut palettes: Vec<ColorPalette> = self
+            .palettes
+            .values()
+            .filter(|palette| !palette.built_in)
+            .cloned()
+            .collect();
+        palettes.sort_by(|a, b| a.name.cmp(&b.name));
+        palettes
+    }
+
+    /// Add a custom palette and make it current
+    ///
+    /// A custom palette with the same name is replaced; a name taken by a
+    /// built-in palette gets a numeric suffix. Returns the name used.
+    pub fn add_custom_palette(&mut self, mut palette: ColorPalette) -> String {
+        palette.built_in = false;
+        let base = palette.name.trim().to_string();
+        let base = if base.is_empty() {
+            "Imported".to_string()
+        } else {
+            base
+        };
+
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while self.palettes.get(&name).is_some_and(|p| p.built_in) {
+            name = format!("{} {}", base, suffix);
+            suffix += 1;
+        }
+
+        palette.name = name.clone();
+        self.palettes.insert(name.clone(), palette);
+        self.current_palette = Some(name.clone());
+        name
+    }
+
     /// Generate web safe colors (216 colors)
     fn generate_web_safe_colors() -> Vec<RgbaPixel> {
         let mut colors = Vec::new();
@@ -291,11 +365,14 @@ impl ColorPaletteDialog {
             ColorPaletteMessage::PaletteNameChanged(name) => {
                 self.new_palette_name = name;
             }
-            ColorPaletteMessage::ImportPalette => {
-                // Import palette from file (implementation depends on file system)
+            ColorPaletteMessage::PaletteImported(palette) => {
+                self.add_custom_palette(palette.into());
             }
-            ColorPaletteMessage::ExportPalette => {
-                // Export current palette to file (implementation depends on file system)
+            ColorPaletteMessage::ImportPalette
+            | ColorPaletteMessage::PaletteFileSelected(_)
+            | ColorPaletteMessage::ExportPalette
+            | ColorPaletteMessage::ExportPathSelected(_) => {
+                // File dialogs and palette file I/O are handled by the parent
             }
             ColorPaletteMessage::Close => {
                 self.hide();
@@ -414,7 +491,10 @@ impl ColorPaletteDialog {
                 .on_press(message_mapper(ColorPaletteMessage::ImportPalette))
                 .style(button::secondary),
             button("Export")
-                .on_press(message_mapper(ColorPaletteMessage::ExportPalette))
+                .on_press_maybe(
+                    self.current_palette()
+                        .map(|_| message_mapper(ColorPaletteMessage::ExportPalette)),
+                )
                 .style(button::secondary),
             Space::new(Length::Fill, Length::Shrink),
             button("Close")
@@ -444,3 +524,45 @@ impl ColorPaletteDialog {
             .into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_imported_palette_becomes_current_custom_palette() {
+        let mut dialog = ColorPaletteDialog::new();
+        dialog.update(ColorPaletteMessage::PaletteImported(Palette::new(
+            "Brand",
+            vec![RgbaPixel::new(1, 2, 3, 255)],
+        )));
+
+        assert_eq!(dialog.current_palette, Some("Brand".to_string()));
+        let custom = dialog.custom_palettes();
+        assert_eq!(custom.len(), 1);
+        assert_eq!(custom[0].colors, [RgbaPixel::new(1, 2, 3, 255)]);
+        assert!(!custom[0].built_in);
+    }
+
+    #[test]
+    fn test_import_keeps_built_in_palettes() {
+        let mut dialog = ColorPaletteDialog::new();
+        let name = dialog.add_custom_palette(ColorPalette::new("Grayscale".to_string()));
+
+        assert_eq!(name, "Grayscale 2");
+        assert!(dialog.palettes["Grayscale"].built_in);
+
+        // Re-importing a custom palette replaces it
+        let mut updated = ColorPalette::new("Grayscale 2".to_string());
+        updated.add_color(RgbaPixel::new(9, 9, 9, 255));
+        assert_eq!(dialog.add_custom_palette(updated), "Grayscale 2");
+        assert_eq!(dialog.custom_palettes().len(), 1);
+    }
+
+    #[test]
+    fn test_restore_saved_palettes() {
+        let saved = vec![ColorPalette::new("Team".to_string())];
+        let dialog = ColorPaletteDialog::with_palettes(saved.clone());
+        assert_eq!(dialog.custom_palettes(), saved);
+    }
+}
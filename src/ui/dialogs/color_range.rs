@@ -0,0 +1,487 @@
+//! Color Range selection dialog for PSOC Image Editor
+
+use iced::{
+    widget::{button, column, container, image, pick_list, row, slider, text, text_input, Space},
+    Element, Length,
+};
+use psoc_core::{ColorRange, ColorRangeTarget, PixelData, RgbaPixel};
+
+use super::super::theme::spacing;
+
+/// Longest side of the downscaled image used for the mask preview
+const PREVIEW_SIZE: u32 = 240;
+
+/// Kind of color range target shown in the "Select" list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorRangeMode {
+    /// Colors close to the sampled colors
+    SampledColors,
+    /// Dark tones
+    Shadows,
+    /// Middle tones
+    Midtones,
+    /// Bright tones
+    Highlights,
+    /// Skin tones
+    SkinTones,
+}
+
+impl ColorRangeMode {
+    /// All modes in display order
+    pub const ALL: [ColorRangeMode; 5] = [
+        ColorRangeMode::SampledColors,
+        ColorRangeMode::Shadows,
+        ColorRangeMode::Midtones,
+        ColorRangeMode::Highlights,
+        ColorRangeMode::SkinTones,
+    ];
+
+    fn of(target: &ColorRangeTarget) -> Self {
+        match target {
+            ColorRangeTarget::SampledColors(_) => ColorRangeMode::SampledColors,
+            ColorRangeTarget::Shadows => ColorRangeMode::Shadows,
+            ColorRangeTarget::Midtones => ColorRangeMode::Midtones,
+            ColorRangeTarget::Highlights => ColorRangeMode::Highlights,
+            ColorRangeTarget::SkinTones => ColorRangeMode::SkinTones,
+        }
+    }
+}
+
+impl std::fmt::Display for ColorRangeMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorRangeMode::SampledColors => write!(f, "Sampled Colors"),
+            ColorRangeMode::Shadows => write!(f, "Shadows"),
+            ColorRangeMode::Midtones => write!(f, "Midtones"),
+            ColorRangeMode::Highlights => write!(f, "Highlights"),
+            ColorRangeMode::SkinTones => write!(f, "Skin Tones"),
+        }
+    }
+}
+
+/// Color Range selection dialog component
+#[derive(Debug, Clone)]
+pub struct ColorRangeDialog {
+    /// Whether the dialog is visible
+    pub visible: bool,
+    /// Current color range parameters
+    pub range: ColorRange,
+    /// Sampled colors, kept while another mode is selected
+    pub samples: Vec<RgbaPixel>,
+    /// Fuzziness text input value
+    pub fuzziness_text: String,
+    /// Downscaled flattened image the preview is computed from
+    source: Option<PixelData>,
+    /// Grayscale preview of the resulting mask
+    preview: Option<image::Handle>,
+}
+
+/// Messages for the Color Range dialog
+#[derive(Debug, Clone)]
+pub enum ColorRangeMessage {
+    /// Show the dialog
+    Show,
+    /// Hide the dialog
+    Hide,
+    /// Selection mode changed
+    ModeChanged(ColorRangeMode),
+    /// Fuzziness value changed via slider
+    FuzzinessChanged(f32),
+    /// Fuzziness text input changed
+    FuzzinessTextChanged(String),
+    /// Toggle inverting the selection
+    ToggleInvert,
+    /// Add the current foreground color as a sample (handled by parent)
+    AddSample,
+    /// Remove all sampled colors
+    ClearSamples,
+    /// Replace the selection with the color range
+    Apply,
+    /// Cancel and close dialog
+    Cancel,
+}
+
+impl Default for ColorRangeDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ColorRangeDialog {
+    /// Create a new Color Range dialog
+    pub fn new() -> Self {
+        let range = ColorRange::default();
+        Self {
+            visible: false,
+            fuzziness_text: format!("{:.0}", range.fuzziness),
+            range,
+            samples: Vec::new(),
+            source: None,
+            preview: None,
+        }
+    }
+
+    /// Show the dialog, previewing against the given flattened image
+    pub fn show(&mut self, image: Option<&PixelData>) {
+        self.visible = true;
+        self.source = image.map(thumbnail);
+        self.refresh_preview();
+    }
+
+    /// Hide the dialog and drop the preview image
+    pub fn hide(&mut self) {
+        self.visible = false;
+        self.source = None;
+        self.preview = None;
+    }
+
+    /// Currently selected mode
+    pub fn mode(&self) -> ColorRangeMode {
+        ColorRangeMode::of(&self.range.target)
+    }
+
+    /// Switch the selection mode
+    pub fn set_mode(&mut self, mode: ColorRangeMode) {
+        self.range.target = match mode {
+            ColorRangeMode::SampledColors => ColorRangeTarget::SampledColors(self.samples.clone()),
+            ColorRangeMode::Shadows => ColorRangeTarget::Shadows,
+            ColorRangeMode::Midtones => ColorRangeTarget::Midtones,
+            ColorRangeMode::Highlights => ColorRangeTarget::Highlights,
+            ColorRangeMode::SkinTones => ColorRangeTarget::SkinTones,
+        };
+        self.refresh_preview();
+    }
+
+    /// Set the fuzziness value
+    pub fn set_fuzziness(&mut self, fuzziness: f32) {
+        let clamped = fuzziness.clamp(0.0, ColorRange::MAX_FUZZINESS);
+        self.range.fuzziness = clamped;
+        self.fuzziness_text = format!("{:.0}", clamped);
+        self.refresh_preview();
+    }
+
+    /// Set fuzziness from text input
+    pub fn set_fuzziness_text(&mut self, text: String) {
+        if let Ok(value) = text.parse::<f32>() {
+            self.set_fuzziness(value);
+        }
+        self.fuzziness_text = text;
+    }
+
+    /// Toggle inverting the selection
+    pub fn toggle_invert(&mut self) {
+        self.range.invert = !self.range.invert;
+        self.refresh_preview();
+    }
+
+    /// Add a sampled color and switch to sampled colors mode
+    pub fn add_sample(&mut self, color: RgbaPixel) {
+        if !self.samples.contains(&color) {
+            self.samples.push(color);
+        }
+        self.set_mode(ColorRangeMode::SampledColors);
+    }
+
+    /// Remove all sampled colors
+    pub fn clear_samples(&mut self) {
+        self.samples.clear();
+        if self.mode() == ColorRangeMode::SampledColors {
+            self.set_mode(ColorRangeMode::SampledColors);
+        }
+    }
+
+    /// Current color range parameters
+    pub fn range(&self) -> &ColorRange {
+        &self.range
+    }
+
+    /// Grayscale preview of the resulting mask, if an image is loaded
+    pub fn preview(&self) -> Option<&image::Handle> {
+        self.preview.as_ref()
+    }
+
+    fn refresh_preview(&mut self) {
+        self.preview = self.source.as_ref().map(|source| {
+            let mask = self.range.preview(source);
+            let (width, height) = mask.dimensions();
+            let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+            for y in 0..height {
+                for x in 0..width {
+                    let pixel = mask.get_pixel(x, y).unwrap_or_else(RgbaPixel::black);
+                    rgba.extend_from_slice(&pixel.to_array());
+                }
+            }
+            image::Handle::from_rgba(width, height, rgba)
+        });
+    }
+
+    /// Update the dialog state based on a message
+    pub fn update(&mut self, message: ColorRangeMessage) {
+        match message {
+            ColorRangeMessage::Show => self.show(None),
+            ColorRangeMessage::Hide | ColorRangeMessage::Cancel | ColorRangeMessage::Apply => {
+                self.hide()
+            }
+            ColorRangeMessage::ModeChanged(mode) => self.set_mode(mode),
+            ColorRangeMessage::FuzzinessChanged(value) => self.set_fuzziness(value),
+            ColorRangeMessage::FuzzinessTextChanged(text) => self.set_fuzziness_text(text),
+            ColorRangeMessage::ToggleInvert => self.toggle_invert(),
+            ColorRangeMessage::AddSample => {
+                // Sampling the foreground color is handled by parent
+            }
+            ColorRangeMessage::ClearSamples => self.clear_samples(),
+        }
+    }
+
+    /// Render the Color Range dialog
+    pub fn view<Message>(
+        &self,
+        message_mapper: fn(ColorRangeMessage) -> Message,
+    ) -> Element<'_, Message>
+    where
+        Message: Clone + 'static,
+    {
+        if !self.visible {
+            return Space::new(Length::Shrink, Length::Shrink).into();
+        }
+
+        let white = |_theme: &iced::Theme| iced::widget::text::Style {
+            color: Some(iced::Color::WHITE),
+        };
+
+        let preview: Element<Message> = match &self.preview {
+            Some(handle) => image(handle.clone())
+                .width(Length::Fixed(PREVIEW_SIZE as f32))
+                .height(Length::Fixed(PREVIEW_SIZE as f32))
+                .into(),
+            None => text("No image to preview").size(12.0).style(white).into(),
+        };
+
+        let mut swatches = row![].spacing(spacing::XS);
+        for sample in &self.samples {
+            let color = iced::Color::from_rgb8(sample.r, sample.g, sample.b);
+            swatches = swatches.push(
+                container(Space::new(Length::Fixed(16.0), Length::Fixed(16.0))).style(
+                    move |_theme| iced::widget::container::Style {
+                        background: Some(iced::Background::Color(color)),
+                        ..Default::default()
+                    },
+                ),
+            );
+        }
+
+        let content = container(
+            column![
+                // Header
+                container(text("Color Range").size(18.0).style(white))
+                    .padding(spacing::MD)
+                    .width(Length::Fill),
+                // Mode selection
+                container(
+                    row![
+                        text("Select").size(14.0).style(white),
+                        pick_list(ColorRangeMode::ALL, Some(self.mode()), move |mode| {
+                            message_mapper(ColorRangeMessage::ModeChanged(mode))
+                        })
+                        .text_size(12.0),
+                    ]
+                    .spacing(spacing::SM)
+                    .align_y(iced::alignment::Vertical::Center)
+                )
+                .padding(spacing::MD)
+                .width(Length::Fill),
+                // Sampled colors
+                container(
+                    row![
+                        button(text("Add Foreground").size(12.0))
+                            .on_press(message_mapper(ColorRangeMessage::AddSample))
+                            .padding([4.0, 8.0]),
+                        button(text("Clear").size(12.0))
+                            .on_press(message_mapper(ColorRangeMessage::ClearSamples))
+                            .padding([4.0, 8.0]),
+                        swatches,
+                    ]
+                    .spacing(spacing::SM)
+                    .align_y(iced::alignment::Vertical::Center)
+                )
+                .padding(spacing::MD)
+                .width(Length::Fill),
+                // Fuzziness control
+                container(
+                    column![
+                        text("Fuzziness").size(14.0).style(white),
+                        row![
+                            slider(
+                                0.0..=ColorRange::MAX_FUZZINESS,
+                                self.range.fuzziness,
+                                move |value| {
+                                    message_mapper(ColorRangeMessage::FuzzinessChanged(value))
+                                }
+                            )
+                            .width(Length::FillPortion(3)),
+                            text_input("40", &self.fuzziness_text)
+                                .on_input(move |text| {
+                                    message_mapper(ColorRangeMessage::FuzzinessTextChanged(text))
+                                })
+                                .width(Length::FillPortion(1))
+                                .size(12.0),
+                        ]
+                        .spacing(spacing::SM)
+                        .align_y(iced::alignment::Vertical::Center),
+                    ]
+                    .spacing(spacing::XS)
+                )
+                .padding(spacing::MD)
+                .width(Length::Fill),
+                // Mask preview
+                container(preview).center_x(Length::Fill),
+                // Invert toggle
+                container(
+                    row![
+                        button(text("Invert").size(12.0))
+                            .on_press(message_mapper(ColorRangeMessage::ToggleInvert))
+                            .padding([4.0, 8.0]),
+                        text(if self.range.invert { "On" } else { "Off" })
+                            .size(12.0)
+                            .style(|_theme| iced::widget::text::Style {
+                                color: Some(if self.range.invert {
+                                    iced::Color::from_rgb(0.0, 1.0, 0.0)
+                                } else {
+                                    iced::Color::from_rgb(0.7, 0.7, 0.7)
+                                })
+                            }),
+                    ]
+                    .spacing(spacing::SM)
+                    .align_y(iced::alignment::Vertical::Center)
+                )
+                .padding(spacing::MD)
+                .width(Length::Fill),
+                // Action buttons
+                container(
+                    row![
+                        Space::new(Length::Fill, Length::Shrink),
+                        button(text("Cancel").size(12.0))
+                            .on_press(message_mapper(ColorRangeMessage::Cancel))
+                            .padding([6.0, 12.0]),
+                        button(text("OK").size(12.0))
+                            .on_press(message_mapper(ColorRangeMessage::Apply))
+                            .padding([6.0, 12.0]),
+                    ]
+                    .spacing(spacing::SM)
+                    .align_y(iced::alignment::Vertical::Center)
+                )
+                .padding(spacing::MD)
+                .width(Length::Fill),
+            ]
+            .spacing(spacing::SM),
+        )
+        .padding(spacing::LG)
+        .width(Length::Fixed(400.0))
+        .style(container::bordered_box);
+
+        // Create modal overlay
+        container(
+            container(content)
+                .center_x(Length::Fill)
+                .center_y(Length::Fill),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(|_theme| iced::widget::container::Style {
+            background: Some(iced::Background::Color(iced::Color::from_rgba(
+                0.0, 0.0, 0.0, 0.7,
+            ))),
+            ..Default::default()
+        })
+        .into()
+    }
+}
+
+/// Nearest-neighbour downscale so the preview stays fast on large documents
+fn thumbnail(image: &PixelData) -> PixelData {
+    let (width, height) = image.dimensions();
+    let scale = (PREVIEW_SIZE as f32 / width.max(height).max(1) as f32).min(1.0);
+    let thumb_width = ((width as f32 * scale).round() as u32).max(1);
+    let thumb_height = ((height as f32 * scale).round() as u32).max(1);
+
+    let mut thumb = PixelData::new_rgba(thumb_width, thumb_height);
+    for y in 0..thumb_height {
+        for x in 0..thumb_width {
+            let src_x = ((x as f32 / scale) as u32).min(width.saturating_sub(1));
+            let src_y = ((y as f32 / scale) as u32).min(height.saturating_sub(1));
+            if let Some(pixel) = image.get_pixel(src_x, src_y) {
+                let _ = thumb.set_pixel(x, y, pixel);
+            }
+        }
+    }
+    thumb
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_range_dialog_modes_and_samples() {
+        let mut dialog = ColorRangeDialog::new();
+        assert!(!dialog.visible);
+        assert_eq!(dialog.mode(), ColorRangeMode::SampledColors);
+
+        dialog.update(ColorRangeMessage::ModeChanged(ColorRangeMode::Highlights));
+        assert_eq!(dialog.range().target, ColorRangeTarget::Highlights);
+
+        // Adding a sample switches back and keeps earlier samples
+        dialog.add_sample(RgbaPixel::rgb(255, 0, 0));
+        dialog.update(ColorRangeMessage::ModeChanged(ColorRangeMode::Shadows));
+        dialog.add_sample(RgbaPixel::rgb(0, 255, 0));
+        assert_eq!(
+            dialog.range().target,
+            ColorRangeTarget::SampledColors(vec![
+                RgbaPixel::rgb(255, 0, 0),
+                RgbaPixel::rgb(0, 255, 0)
+            ])
+        );
+
+        dialog.update(ColorRangeMessage::ClearSamples);
+        assert_eq!(
+            dialog.range().target,
+            ColorRangeTarget::SampledColors(Vec::new())
+        );
+    }
+
+    #[test]
+    fn test_color_range_dialog_fuzziness() {
+        let mut dialog = ColorRangeDialog::new();
+
+        dialog.update(ColorRangeMessage::FuzzinessChanged(500.0));
+        assert_eq!(dialog.range().fuzziness, ColorRange::MAX_FUZZINESS);
+        assert_eq!(dialog.fuzziness_text, "200");
+
+        dialog.update(ColorRangeMessage::FuzzinessTextChanged("25".to_string()));
+        assert_eq!(dialog.range().fuzziness, 25.0);
+
+        dialog.update(ColorRangeMessage::FuzzinessTextChanged("abc".to_string()));
+        assert_eq!(dialog.fuzziness_text, "abc");
+        assert_eq!(dialog.range().fuzziness, 25.0);
+    }
+
+    #[test]
+    fn test_color_range_dialog_preview() {
+        let mut dialog = ColorRangeDialog::new();
+        dialog.show(None);
+        assert!(dialog.visible);
+        assert!(dialog.preview().is_none());
+
+        let image = PixelData::new_rgba(1000, 500);
+        dialog.show(Some(&image));
+        assert_eq!(
+            dialog.source.as_ref().unwrap().dimensions(),
+            (PREVIEW_SIZE, 120)
+        );
+        assert!(dialog.preview().is_some());
+
+        dialog.update(ColorRangeMessage::Cancel);
+        assert!(!dialog.visible);
+        assert!(dialog.preview().is_none());
+    }
+}
@@ -5,12 +5,14 @@
 //! - Manage color stops
 //! - Preview gradients in real-time
 //! - Select gradient types and interpolation methods
+//! - Import GIMP and Photoshop gradient libraries and export to GIMP
 
 use iced::{
     widget::{button, column, container, row, text, Space},
     Element, Length,
 };
 use psoc_core::{Gradient, GradientType, InterpolationMethod, RgbaPixel};
+use std::path::PathBuf;
 
 use crate::ui::theme::spacing;
 
@@ -43,6 +45,16 @@ pub enum GradientEditorMessage {
     Cancel,
     /// Reset to default gradient
     Reset,
+    /// Import a `.ggr` or `.grd` gradient file
+    Import,
+    /// Gradient file chosen for import
+    ImportFileSelected(PathBuf),
+    /// Gradients read from a file, already added to the presets
+    Imported(Vec<Gradient>),
+    /// Export the edited gradient as a `.ggr` file
+    Export,
+    /// Destination chosen for exporting the edited gradient
+    ExportPathSelected(PathBuf),
 }
 
 /// Gradient editor dialog state
@@ -195,6 +207,20 @@ impl GradientEditor {
                 self.has_changes = true;
                 None
             }
+            GradientEditorMessage::Imported(gradients) => {
+                if let Some(gradient) = gradients.into_iter().next() {
+                    self.current_gradient = gradient;
+                    self.selected_stop = None;
+                }
+                None
+            }
+            GradientEditorMessage::Import
+            | GradientEditorMessage::ImportFileSelected(_)
+            | GradientEditorMessage::Export
+            | GradientEditorMessage::ExportPathSelected(_) => {
+                // File dialogs and gradient file I/O are handled by the parent
+                None
+            }
         }
     }
 
@@ -265,6 +291,8 @@ impl GradientEditor {
         let buttons = row![
             button("Add Stop").on_press(message_mapper(GradientEditorMessage::AddColorStop)),
             button("Reset").on_press(message_mapper(GradientEditorMessage::Reset)),
+            button("Import").on_press(message_mapper(GradientEditorMessage::Import)),
+            button("Export").on_press(message_mapper(GradientEditorMessage::Export)),
             Space::new(Length::Fill, Length::Shrink),
             button("Cancel").on_press(message_mapper(GradientEditorMessage::Cancel)),
             button("Apply").on_press(message_mapper(GradientEditorMessage::Apply)),
@@ -301,3 +329,28 @@ impl GradientEditor {
             .into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_imported_gradients_show_the_first() {
+        let mut editor = GradientEditor::new();
+        let mut first = Gradient::linear_two_color(
+            RgbaPixel::new(255, 0, 0, 255),
+            RgbaPixel::new(0, 0, 255, 255),
+        );
+        first.name = "Imported".to_string();
+        let second = Gradient::default();
+
+        let applied = editor.update(GradientEditorMessage::Imported(vec![first, second]));
+        assert!(applied.is_none());
+        assert_eq!(editor.current_gradient.name, "Imported");
+        assert!(editor.selected_stop.is_none());
+
+        // An empty import leaves the edited gradient alone
+        editor.update(GradientEditorMessage::Imported(Vec::new()));
+        assert_eq!(editor.current_gradient.name, "Imported");
+    }
+}
@@ -0,0 +1,208 @@
+//! Export layers dialog for PSOC Image Editor
+
+use iced::{
+    widget::{
+        button, checkbox, column, container, horizontal_rule, pick_list, row, text, text_input,
+        Space,
+    },
+    Alignment, Element, Length,
+};
+use psoc_core::Document;
+use psoc_file_formats::{LayerExportOptions, SupportedFormat, DEFAULT_LAYER_FILENAME_TEMPLATE};
+use std::path::PathBuf;
+
+use crate::i18n::LocalizationManager;
+use crate::ui::theme::spacing;
+
+/// Formats offered for layer export
+const EXPORT_FORMATS: [SupportedFormat; 4] = [
+    SupportedFormat::Png,
+    SupportedFormat::Jpeg,
+    SupportedFormat::Tiff,
+    SupportedFormat::Webp,
+];
+
+/// Messages for the export layers dialog
+#[derive(Debug, Clone)]
+pub enum LayerExportMessage {
+    /// Show the dialog
+    Show,
+    /// File name template changed
+    TemplateChanged(String),
+    /// Output format selected
+    FormatSelected(SupportedFormat),
+    /// Toggle trimming to content
+    TrimToggled(bool),
+    /// Toggle exporting only the active layer
+    ActiveLayerOnlyToggled(bool),
+    /// Pick a destination folder and export
+    Export,
+    /// Destination folder chosen
+    DirectorySelected(PathBuf),
+    /// Close the dialog without exporting
+    Cancel,
+}
+
+/// Dialog for exporting layers to individual files
+#[derive(Debug, Clone)]
+pub struct LayerExportDialog {
+    /// Whether the dialog is visible
+    pub visible: bool,
+    /// Export options, kept between exports
+    pub options: LayerExportOptions,
+    /// Export only the active layer instead of every visible layer
+    pub active_layer_only: bool,
+}
+
+impl Default for LayerExportDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LayerExportDialog {
+    /// Create a new export layers dialog
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            options: LayerExportOptions::default(),
+            active_layer_only: false,
+        }
+    }
+
+    /// Export options for a document, restricted to its active layer if requested
+    pub fn options_for(&self, document: &Document) -> LayerExportOptions {
+        let mut options = self.options.clone();
+        if self.active_layer_only {
+            options.layer_indices = Some(document.active_layer_index.into_iter().collect());
+        }
+        if options.template.trim().is_empty() {
+            options.template = DEFAULT_LAYER_FILENAME_TEMPLATE.to_string();
+        }
+        options
+    }
+
+    /// Update the dialog state
+    ///
+    /// Picking the folder and exporting are handled by the application.
+    pub fn update(&mut self, message: LayerExportMessage) {
+        match message {
+            LayerExportMessage::Show => self.visible = true,
+            LayerExportMessage::TemplateChanged(template) => self.options.template = template,
+            LayerExportMessage::FormatSelected(format) => self.options.format = format,
+            LayerExportMessage::TrimToggled(enabled) => self.options.trim_to_content = enabled,
+            LayerExportMessage::ActiveLayerOnlyToggled(enabled) => {
+                self.active_layer_only = enabled;
+            }
+            LayerExportMessage::Export | LayerExportMessage::Cancel => self.visible = false,
+            LayerExportMessage::DirectorySelected(_) => {}
+        }
+    }
+
+    /// Render the export layers dialog
+    pub fn view<'a>(
+        &'a self,
+        localization: &'a LocalizationManager,
+    ) -> Element<'a, LayerExportMessage> {
+        if !self.visible {
+            return Space::new(Length::Shrink, Length::Shrink).into();
+        }
+
+        let options = column![
+            text(localization.translate("layer-export-template")).size(14),
+            text_input(DEFAULT_LAYER_FILENAME_TEMPLATE, &self.options.template)
+                .on_input(LayerExportMessage::TemplateChanged)
+                .size(12),
+            text(localization.translate("layer-export-template-help")).size(11),
+            row![
+                text(localization.translate("layer-export-format"))
+                    .size(14)
+                    .width(Length::Fill),
+                pick_list(
+                    EXPORT_FORMATS,
+                    Some(self.options.format),
+                    LayerExportMessage::FormatSelected
+                )
+                .text_size(12),
+            ]
+            .spacing(spacing::SM)
+            .align_y(Alignment::Center),
+            checkbox(
+                localization.translate("layer-export-trim"),
+                self.options.trim_to_content
+            )
+            .on_toggle(LayerExportMessage::TrimToggled)
+            .size(14),
+            checkbox(
+                localization.translate("layer-export-active-only"),
+                self.active_layer_only
+            )
+            .on_toggle(LayerExportMessage::ActiveLayerOnlyToggled)
+            .size(14),
+        ]
+        .spacing(spacing::SM);
+
+        let button_row = row![
+            Space::new(Length::Fill, Length::Shrink),
+            button(text(localization.translate("layer-export-cancel")).size(14))
+                .on_press(LayerExportMessage::Cancel)
+                .style(button::secondary),
+            button(text(localization.translate("layer-export-export")).size(14))
+                .on_press(LayerExportMessage::Export)
+                .style(button::primary),
+        ]
+        .spacing(spacing::SM)
+        .align_y(Alignment::Center);
+
+        let content = column![
+            text(localization.translate("layer-export-title"))
+                .size(20)
+                .style(text::primary),
+            horizontal_rule(1),
+            options,
+            horizontal_rule(1),
+            button_row,
+        ]
+        .spacing(spacing::MD)
+        .padding(spacing::LG)
+        .max_width(420);
+
+        container(content)
+            .center_x(Length::Fill)
+            .center_y(Length::Fill)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use psoc_core::Layer;
+
+    #[test]
+    fn test_options_for_active_layer_only() {
+        let mut document = Document::new("Doc".to_string(), 4, 4);
+        document.add_layer(Layer::new_pixel("A".to_string(), 4, 4));
+        document.add_layer(Layer::new_pixel("B".to_string(), 4, 4));
+        document.set_active_layer(1).unwrap();
+
+        let mut dialog = LayerExportDialog::new();
+        assert_eq!(dialog.options_for(&document).layer_indices, None);
+
+        dialog.update(LayerExportMessage::ActiveLayerOnlyToggled(true));
+        assert_eq!(dialog.options_for(&document).layer_indices, Some(vec![1]));
+    }
+
+    #[test]
+    fn test_empty_template_falls_back_to_default() {
+        let document = Document::new("Doc".to_string(), 4, 4);
+        let mut dialog = LayerExportDialog::new();
+        dialog.update(LayerExportMessage::TemplateChanged("  ".to_string()));
+        assert_eq!(
+            dialog.options_for(&document).template,
+            DEFAULT_LAYER_FILENAME_TEMPLATE
+        );
+    }
+}
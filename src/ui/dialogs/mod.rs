@@ -3,29 +3,61 @@
 #[cfg(feature = "gui")]
 pub mod about;
 #[cfg(feature = "gui")]
+pub mod animation_export;
+#[cfg(feature = "gui")]
+pub mod assets;
+#[cfg(feature = "gui")]
 pub mod brightness_contrast;
 #[cfg(feature = "gui")]
 pub mod color_palette;
 #[cfg(feature = "gui")]
 pub mod color_picker;
 #[cfg(feature = "gui")]
+pub mod color_range;
+#[cfg(feature = "gui")]
 pub mod gaussian_blur;
 #[cfg(feature = "gui")]
 pub mod gradient_editor;
 #[cfg(feature = "gui")]
+pub mod layer_export;
+#[cfg(feature = "gui")]
+pub mod modify_selection;
+#[cfg(feature = "gui")]
+pub mod pdf_export;
+#[cfg(feature = "gui")]
 pub mod preferences;
+#[cfg(feature = "gui")]
+pub mod recovery;
+#[cfg(feature = "gui")]
+pub mod refine_edge;
 
 #[cfg(feature = "gui")]
 pub use about::{AboutDialog, AboutMessage};
 #[cfg(feature = "gui")]
+pub use animation_export::{AnimationExportDialog, AnimationExportMessage};
+#[cfg(feature = "gui")]
+pub use assets::{apply_asset_edit, AssetMessage, AssetsDialog};
+#[cfg(feature = "gui")]
 pub use brightness_contrast::{BrightnessContrastDialog, BrightnessContrastMessage};
 #[cfg(feature = "gui")]
 pub use color_palette::{ColorPalette, ColorPaletteDialog, ColorPaletteMessage};
 #[cfg(feature = "gui")]
 pub use color_picker::{ColorPickerDialog, ColorPickerMessage};
 #[cfg(feature = "gui")]
+pub use color_range::{ColorRangeDialog, ColorRangeMessage, ColorRangeMode};
+#[cfg(feature = "gui")]
 pub use gaussian_blur::{GaussianBlurDialog, GaussianBlurMessage};
 #[cfg(feature = "gui")]
 pub use gradient_editor::{GradientEditor, GradientEditorMessage};
 #[cfg(feature = "gui")]
+pub use layer_export::{LayerExportDialog, LayerExportMessage};
+#[cfg(feature = "gui")]
+pub use modify_selection::{ModifySelectionDialog, ModifySelectionKind, ModifySelectionMessage};
+#[cfg(feature = "gui")]
+pub use pdf_export::{PdfExportDialog, PdfExportMessage};
+#[cfg(feature = "gui")]
 pub use preferences::{PreferencesDialog, PreferencesMessage};
+#[cfg(feature = "gui")]
+pub use recovery::{RecoveryDialog, RecoveryMessage};
+#[cfg(feature = "gui")]
+pub use refine_edge::{RefineEdgeDialog, RefineEdgeMessage, RefineEdgeOutput};
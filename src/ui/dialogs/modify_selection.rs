@@ -0,0 +1,270 @@
+//! Modify Selection dialog for PSOC Image Editor
+
+use iced::{
+    widget::{button, column, container, pick_list, row, slider, text, Space},
+    Element, Length,
+};
+use psoc_core::SelectionModification;
+
+use super::super::theme::spacing;
+
+/// Largest feather radius or pixel amount offered by the dialog
+pub const MAX_MODIFY_AMOUNT: f32 = 100.0;
+
+/// Which refinement the dialog applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModifySelectionKind {
+    /// Soften the edge
+    #[default]
+    Feather,
+    /// Grow the selection
+    Expand,
+    /// Shrink the selection
+    Contract,
+    /// Select a band around the edge
+    Border,
+    /// Round off corners and remove specks
+    Smooth,
+}
+
+impl ModifySelectionKind {
+    /// All kinds in display order
+    pub const ALL: [ModifySelectionKind; 5] = [
+        ModifySelectionKind::Feather,
+        ModifySelectionKind::Expand,
+        ModifySelectionKind::Contract,
+        ModifySelectionKind::Border,
+        ModifySelectionKind::Smooth,
+    ];
+}
+
+impl std::fmt::Display for ModifySelectionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModifySelectionKind::Feather => write!(f, "Feather"),
+            ModifySelectionKind::Expand => write!(f, "Expand"),
+            ModifySelectionKind::Contract => write!(f, "Contract"),
+            ModifySelectionKind::Border => write!(f, "Border"),
+            ModifySelectionKind::Smooth => write!(f, "Smooth"),
+        }
+    }
+}
+
+/// Modify Selection dialog component
+#[derive(Debug, Clone)]
+pub struct ModifySelectionDialog {
+    /// Whether the dialog is visible
+    pub visible: bool,
+    /// Refinement to apply
+    pub kind: ModifySelectionKind,
+    /// Radius or width in pixels
+    pub amount: f32,
+}
+
+/// Messages for the Modify Selection dialog
+#[derive(Debug, Clone)]
+pub enum ModifySelectionMessage {
+    /// Show the dialog
+    Show,
+    /// Hide the dialog
+    Hide,
+    /// Refinement changed
+    KindChanged(ModifySelectionKind),
+    /// Amount changed via slider
+    AmountChanged(f32),
+    /// Modify the selection
+    Apply,
+    /// Cancel and close dialog
+    Cancel,
+}
+
+impl Default for ModifySelectionDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModifySelectionDialog {
+    /// Create a new Modify Selection dialog
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            kind: ModifySelectionKind::default(),
+            amount: 2.0,
+        }
+    }
+
+    /// Show the dialog
+    pub fn show(&mut self) {
+        self.visible = true;
+    }
+
+    /// Hide the dialog
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Refinement described by the current settings
+    pub fn modification(&self) -> SelectionModification {
+        let pixels = self.amount.round() as u32;
+        match self.kind {
+            ModifySelectionKind::Feather => SelectionModification::Feather {
+                radius: self.amount,
+            },
+            ModifySelectionKind::Expand => SelectionModification::Expand { pixels },
+            ModifySelectionKind::Contract => SelectionModification::Contract { pixels },
+            ModifySelectionKind::Border => SelectionModification::Border { width: pixels },
+            ModifySelectionKind::Smooth => SelectionModification::Smooth { radius: pixels },
+        }
+    }
+
+    /// Update the dialog state based on a message
+    pub fn update(&mut self, message: ModifySelectionMessage) {
+        match message {
+            ModifySelectionMessage::Show => self.show(),
+            ModifySelectionMessage::Hide
+            | ModifySelectionMessage::Cancel
+            | ModifySelectionMessage::Apply => self.hide(),
+            ModifySelectionMessage::KindChanged(kind) => self.kind = kind,
+            ModifySelectionMessage::AmountChanged(amount) => {
+                self.amount = amount.clamp(1.0, MAX_MODIFY_AMOUNT);
+            }
+        }
+    }
+
+    /// Render the Modify Selection dialog
+    pub fn view<Message>(
+        &self,
+        message_mapper: fn(ModifySelectionMessage) -> Message,
+    ) -> Element<'_, Message>
+    where
+        Message: Clone + 'static,
+    {
+        if !self.visible {
+            return Space::new(Length::Shrink, Length::Shrink).into();
+        }
+
+        let white = |_theme: &iced::Theme| iced::widget::text::Style {
+            color: Some(iced::Color::WHITE),
+        };
+        let amount_label = match self.kind {
+            ModifySelectionKind::Feather | ModifySelectionKind::Smooth => "Radius",
+            ModifySelectionKind::Border => "Width",
+            ModifySelectionKind::Expand | ModifySelectionKind::Contract => "Amount",
+        };
+
+        let content = container(
+            column![
+                // Header
+                container(text("Modify Selection").size(18.0).style(white))
+                    .padding(spacing::MD)
+                    .width(Length::Fill),
+                // Refinement
+                container(
+                    row![
+                        text("Modify").size(14.0).style(white),
+                        pick_list(ModifySelectionKind::ALL, Some(self.kind), move |kind| {
+                            message_mapper(ModifySelectionMessage::KindChanged(kind))
+                        })
+                        .text_size(12.0),
+                    ]
+                    .spacing(spacing::SM)
+                    .align_y(iced::alignment::Vertical::Center)
+                )
+                .padding(spacing::MD)
+                .width(Length::Fill),
+                // Amount
+                container(
+                    column![
+                        text(amount_label).size(14.0).style(white),
+                        row![
+                            slider(1.0..=MAX_MODIFY_AMOUNT, self.amount, move |value| {
+                                message_mapper(ModifySelectionMessage::AmountChanged(value))
+                            })
+                            .width(Length::FillPortion(3)),
+                            container(
+                                text(format!("{:.0} px", self.amount))
+                                    .size(12.0)
+                                    .style(white)
+                            )
+                            .width(Length::FillPortion(1)),
+                        ]
+                        .spacing(spacing::SM)
+                        .align_y(iced::alignment::Vertical::Center),
+                    ]
+                    .spacing(spacing::XS),
+                )
+                .padding(spacing::MD)
+                .width(Length::Fill),
+                // Action buttons
+                container(
+                    row![
+                        Space::new(Length::Fill, Length::Shrink),
+                        button(text("Cancel").size(12.0))
+                            .on_press(message_mapper(ModifySelectionMessage::Cancel))
+                            .padding([6.0, 12.0]),
+                        button(text("OK").size(12.0))
+                            .on_press(message_mapper(ModifySelectionMessage::Apply))
+                            .padding([6.0, 12.0]),
+                    ]
+                    .spacing(spacing::SM)
+                    .align_y(iced::alignment::Vertical::Center)
+                )
+                .padding(spacing::MD)
+                .width(Length::Fill),
+            ]
+            .spacing(spacing::SM),
+        )
+        .padding(spacing::LG)
+        .width(Length::Fixed(400.0))
+        .style(container::bordered_box);
+
+        // Create modal overlay
+        container(
+            container(content)
+                .center_x(Length::Fill)
+                .center_y(Length::Fill),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(|_theme| iced::widget::container::Style {
+            background: Some(iced::Background::Color(iced::Color::from_rgba(
+                0.0, 0.0, 0.0, 0.7,
+            ))),
+            ..Default::default()
+        })
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modify_selection_dialog_builds_modification() {
+        let mut dialog = ModifySelectionDialog::new();
+        assert!(!dialog.visible);
+        assert_eq!(
+            dialog.modification(),
+            SelectionModification::Feather { radius: 2.0 }
+        );
+
+        dialog.update(ModifySelectionMessage::KindChanged(
+            ModifySelectionKind::Border,
+        ));
+        dialog.update(ModifySelectionMessage::AmountChanged(4.4));
+        assert_eq!(
+            dialog.modification(),
+            SelectionModification::Border { width: 4 }
+        );
+
+        dialog.update(ModifySelectionMessage::AmountChanged(500.0));
+        assert_eq!(dialog.amount, MAX_MODIFY_AMOUNT);
+
+        dialog.update(ModifySelectionMessage::Show);
+        assert!(dialog.visible);
+        dialog.update(ModifySelectionMessage::Apply);
+        assert!(!dialog.visible);
+    }
+}
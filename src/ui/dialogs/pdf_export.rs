@@ -0,0 +1,202 @@
+//! PDF export options dialog for PSOC Image Editor
+
+use iced::{
+    widget::{button, checkbox, column, container, horizontal_rule, row, text, text_input, Space},
+    Alignment, Element, Length,
+};
+use psoc_file_formats::PdfExportOptions;
+use std::path::PathBuf;
+
+use crate::i18n::LocalizationManager;
+use crate::ui::theme::spacing;
+
+/// Messages for the PDF export dialog
+#[derive(Debug, Clone)]
+pub enum PdfExportMessage {
+    /// Bleed text input changed (millimetres)
+    BleedChanged(String),
+    /// Toggle crop marks
+    CropMarksToggled(bool),
+    /// Toggle the ICC output intent
+    OutputIntentToggled(bool),
+    /// Export with the current options
+    Export,
+    /// Close the dialog without exporting
+    Cancel,
+}
+
+/// Dialog collecting bleed, crop mark and output intent options for a PDF export
+#[derive(Debug, Clone, Default)]
+pub struct PdfExportDialog {
+    /// Whether the dialog is visible
+    pub visible: bool,
+    /// Destination of the export
+    pub path: Option<PathBuf>,
+    /// Current export options
+    pub options: PdfExportOptions,
+    /// Bleed text input value
+    pub bleed_text: String,
+    /// Whether the document has an ICC profile to use as output intent
+    pub has_profile: bool,
+}
+
+impl PdfExportDialog {
+    /// Create a new PDF export dialog
+    pub fn new() -> Self {
+        Self {
+            bleed_text: "0".to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Show the dialog for an export to path, keeping the last used options
+    pub fn show(&mut self, path: PathBuf, has_profile: bool) {
+        self.path = Some(path);
+        self.has_profile = has_profile;
+        self.visible = true;
+    }
+
+    /// Hide the dialog
+    pub fn hide(&mut self) {
+        self.visible = false;
+        self.path = None;
+    }
+
+    /// Hide the dialog and return the export destination and options
+    pub fn take_request(&mut self) -> Option<(PathBuf, PdfExportOptions)> {
+        let path = self.path.take()?;
+        self.visible = false;
+        Some((path, self.options.clone()))
+    }
+
+    /// Update the dialog state
+    ///
+    /// Exporting is handled by the application.
+    pub fn update(&mut self, message: PdfExportMessage) {
+        match message {
+            PdfExportMessage::BleedChanged(value) => {
+                if let Ok(bleed) = value.trim().parse::<f32>() {
+                    self.options.bleed_mm = bleed.max(0.0);
+                }
+                self.bleed_text = value;
+            }
+            PdfExportMessage::CropMarksToggled(enabled) => self.options.crop_marks = enabled,
+            PdfExportMessage::OutputIntentToggled(enabled) => {
+                self.options.output_intent = enabled;
+            }
+            PdfExportMessage::Cancel => self.hide(),
+            PdfExportMessage::Export => {}
+        }
+    }
+
+    /// Render the PDF export dialog
+    pub fn view<'a>(
+        &'a self,
+        localization: &'a LocalizationManager,
+    ) -> Element<'a, PdfExportMessage> {
+        if !self.visible {
+            return Space::new(Length::Shrink, Length::Shrink).into();
+        }
+
+        let destination = self
+            .path
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+
+        let mut output_intent = checkbox(
+            localization.translate("pdf-export-output-intent"),
+            self.options.output_intent && self.has_profile,
+        )
+        .size(14);
+        if self.has_profile {
+            output_intent = output_intent.on_toggle(PdfExportMessage::OutputIntentToggled);
+        }
+
+        let options = column![
+            row![
+                text(localization.translate("pdf-export-bleed"))
+                    .size(14)
+                    .width(Length::Fill),
+                text_input("0", &self.bleed_text)
+                    .on_input(PdfExportMessage::BleedChanged)
+                    .width(Length::Fixed(80.0))
+                    .size(12),
+            ]
+            .spacing(spacing::SM)
+            .align_y(Alignment::Center),
+            checkbox(
+                localization.translate("pdf-export-crop-marks"),
+                self.options.crop_marks
+            )
+            .on_toggle(PdfExportMessage::CropMarksToggled)
+            .size(14),
+            output_intent,
+        ]
+        .spacing(spacing::SM);
+
+        let button_row = row![
+            Space::new(Length::Fill, Length::Shrink),
+            button(text(localization.translate("pdf-export-cancel")).size(14))
+                .on_press(PdfExportMessage::Cancel)
+                .style(button::secondary),
+            button(text(localization.translate("pdf-export-export")).size(14))
+                .on_press(PdfExportMessage::Export)
+                .style(button::primary),
+        ]
+        .spacing(spacing::SM)
+        .align_y(Alignment::Center);
+
+        let content = column![
+            text(localization.translate("pdf-export-title"))
+                .size(20)
+                .style(text::primary),
+            text(destination).size(11),
+            horizontal_rule(1),
+            options,
+            horizontal_rule(1),
+            button_row,
+        ]
+        .spacing(spacing::MD)
+        .padding(spacing::LG)
+        .max_width(420);
+
+        container(content)
+            .center_x(Length::Fill)
+            .center_y(Length::Fill)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bleed_text_updates_options() {
+        let mut dialog = PdfExportDialog::new();
+        dialog.update(PdfExportMessage::BleedChanged("3".to_string()));
+        assert_eq!(dialog.options.bleed_mm, 3.0);
+
+        // Unparsable input keeps the last valid bleed
+        dialog.update(PdfExportMessage::BleedChanged("3.".to_string()));
+        dialog.update(PdfExportMessage::BleedChanged("abc".to_string()));
+        assert_eq!(dialog.options.bleed_mm, 3.0);
+        assert_eq!(dialog.bleed_text, "abc");
+    }
+
+    #[test]
+    fn test_take_request_hides_dialog() {
+        let mut dialog = PdfExportDialog::new();
+        assert!(dialog.take_request().is_none());
+
+        dialog.show(PathBuf::from("proof.pdf"), true);
+        dialog.update(PdfExportMessage::CropMarksToggled(true));
+        let (path, options) = dialog.take_request().unwrap();
+        assert_eq!(path, PathBuf::from("proof.pdf"));
+        assert!(options.crop_marks);
+        assert!(!dialog.visible);
+    }
+}
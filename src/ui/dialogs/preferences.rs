@@ -11,6 +11,7 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 use crate::i18n::{Language, LocalizationManager};
+use crate::ui::dialogs::color_palette::ColorPalette;
 use crate::ui::theme::{spacing, PsocTheme};
 
 /// User preferences structure
@@ -24,6 +25,12 @@ pub struct UserPreferences {
     pub defaults: DefaultPreferences,
     /// Advanced preferences
     pub advanced: AdvancedPreferences,
+    /// Custom color palettes, including imported ones
+    #[serde(default)]
+    pub palettes: Vec<ColorPalette>,
+    /// User gradient presets, including imported ones
+    #[serde(default)]
+    pub gradients: Vec<psoc_core::Gradient>,
 }
 
 /// Interface-related preferences
@@ -71,6 +78,9 @@ pub struct DefaultPreferences {
     pub default_tool: String,
     /// Auto-save interval in minutes (0 = disabled)
     pub auto_save_interval: u16,
+    /// Number of recovery snapshots kept per document (1 to 20)
+    #[serde(default = "default_auto_save_retention")]
+    pub auto_save_retention: u16,
     /// Maximum undo history entries (10 to 1000)
     pub max_undo_history: u16,
     /// Default image format for new documents
@@ -81,6 +91,9 @@ pub struct DefaultPreferences {
     pub confirm_close_unsaved: bool,
     /// Remember window size and position
     pub remember_window_state: bool,
+    /// Remove GPS coordinates and serial numbers when saving
+    #[serde(default)]
+    pub strip_private_metadata: bool,
 }
 
 /// Advanced preferences
@@ -133,15 +146,22 @@ impl Default for DefaultPreferences {
         Self {
             default_tool: "Select".to_string(),
             auto_save_interval: 5,
+            auto_save_retention: default_auto_save_retention(),
             max_undo_history: 100,
             default_image_format: "PNG".to_string(),
             default_canvas_color: [1.0, 1.0, 1.0, 1.0], // White
             confirm_close_unsaved: true,
             remember_window_state: true,
+            strip_private_metadata: false,
         }
     }
 }
 
+/// Default number of recovery snapshots kept per document
+fn default_auto_save_retention() -> u16 {
+    crate::file_io::DEFAULT_RECOVERY_RETENTION as u16
+}
+
 impl Default for AdvancedPreferences {
     fn default() -> Self {
         Self {
@@ -260,6 +280,8 @@ pub enum DefaultsMessage {
     DefaultToolChanged(String),
     /// Auto-save interval changed
     AutoSaveIntervalChanged(u16),
+    /// Auto-save retention changed
+    AutoSaveRetentionChanged(u16),
     /// Max undo history changed
     MaxUndoHistoryChanged(u16),
     /// Default image format changed
@@ -270,6 +292,8 @@ pub enum DefaultsMessage {
     ConfirmCloseUnsavedToggled(bool),
     /// Remember window state toggled
     RememberWindowStateToggled(bool),
+    /// Strip private metadata on export toggled
+    StripPrivateMetadataToggled(bool),
 }
 
 /// Advanced preference messages
@@ -443,6 +467,9 @@ impl PreferencesDialog {
             DefaultsMessage::AutoSaveIntervalChanged(interval) => {
                 self.preferences.defaults.auto_save_interval = interval.clamp(0, 60);
             }
+            DefaultsMessage::AutoSaveRetentionChanged(retention) => {
+                self.preferences.defaults.auto_save_retention = retention.clamp(1, 20);
+            }
             DefaultsMessage::MaxUndoHistoryChanged(max) => {
                 self.preferences.defaults.max_undo_history = max.clamp(10, 1000);
             }
@@ -458,6 +485,9 @@ impl PreferencesDialog {
             DefaultsMessage::RememberWindowStateToggled(remember) => {
                 self.preferences.defaults.remember_window_state = remember;
             }
+            DefaultsMessage::StripPrivateMetadataToggled(strip) => {
+                self.preferences.defaults.strip_private_metadata = strip;
+            }
         }
     }
 
@@ -815,6 +845,17 @@ impl PreferencesDialog {
             ))
         });
 
+        // Auto-save retention slider
+        let retention_slider = slider(
+            1.0..=20.0,
+            defaults.auto_save_retention as f32,
+            |retention| {
+                PreferencesMessage::DefaultsChanged(DefaultsMessage::AutoSaveRetentionChanged(
+                    retention as u16,
+                ))
+            },
+        );
+
         // Max undo history slider
         let undo_slider = slider(10.0..=1000.0, defaults.max_undo_history as f32, |max| {
             PreferencesMessage::DefaultsChanged(DefaultsMessage::MaxUndoHistoryChanged(max as u16))
@@ -854,6 +895,15 @@ impl PreferencesDialog {
             ]
             .spacing(spacing::SM)
             .align_y(Alignment::Center),
+            // Auto-save Retention
+            row![
+                text(localization.translate("preferences-auto-save-retention"))
+                    .width(Length::Fixed(150.0)),
+                retention_slider,
+                text(format!("{}", defaults.auto_save_retention)).width(Length::Fixed(80.0))
+            ]
+            .spacing(spacing::SM)
+            .align_y(Alignment::Center),
             // Max Undo History
             row![
                 text(localization.translate("preferences-max-undo")).width(Length::Fixed(150.0)),
@@ -872,6 +922,13 @@ impl PreferencesDialog {
                 localization.translate("preferences-remember-window"),
                 defaults.remember_window_state
             ),
+            checkbox(
+                localization.translate("preferences-strip-private-metadata"),
+                defaults.strip_private_metadata
+            )
+            .on_toggle(|strip| PreferencesMessage::DefaultsChanged(
+                DefaultsMessage::StripPrivateMetadataToggled(strip)
+            )),
         ]
         .spacing(spacing::SM)
         .into()
@@ -1036,6 +1093,7 @@ mod tests {
         // Test defaults
         assert_eq!(preferences.defaults.default_tool, "Select");
         assert_eq!(preferences.defaults.auto_save_interval, 5);
+        assert_eq!(preferences.defaults.auto_save_retention, 3);
         assert_eq!(preferences.defaults.max_undo_history, 100);
         assert_eq!(preferences.defaults.default_image_format, "PNG");
         assert!(preferences.defaults.confirm_close_unsaved);
@@ -1122,6 +1180,13 @@ mod tests {
         dialog.update_defaults(DefaultsMessage::AutoSaveIntervalChanged(100));
         assert_eq!(dialog.preferences.defaults.auto_save_interval, 60); // Clamped to max
 
+        // Test auto-save retention clamping
+        dialog.update_defaults(DefaultsMessage::AutoSaveRetentionChanged(50));
+        assert_eq!(dialog.preferences.defaults.auto_save_retention, 20); // Clamped to max
+
+        dialog.update_defaults(DefaultsMessage::AutoSaveRetentionChanged(0));
+        assert_eq!(dialog.preferences.defaults.auto_save_retention, 1); // Clamped to min
+
         // Test max undo history clamping
         dialog.update_defaults(DefaultsMessage::MaxUndoHistoryChanged(2000));
         assert_eq!(dialog.preferences.defaults.max_undo_history, 1000); // Clamped to max
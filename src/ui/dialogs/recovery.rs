@@ -0,0 +1,207 @@
+//! Crash recovery dialog for PSOC Image Editor
+
+use iced::{
+    widget::{button, column, container, horizontal_rule, row, text, Space},
+    Alignment, Element, Length,
+};
+
+use crate::file_io::RecoveryEntry;
+use crate::i18n::LocalizationManager;
+use crate::ui::theme::spacing;
+
+/// Messages for the recovery dialog
+#[derive(Debug, Clone)]
+pub enum RecoveryMessage {
+    /// Recoverable documents were found at startup
+    Found(Vec<RecoveryEntry>),
+    /// Restore the entry at index
+    Restore(usize),
+    /// Discard the entry at index
+    Discard(usize),
+    /// Discard every entry
+    DiscardAll,
+    /// Hide the dialog, keeping the snapshots for the next launch
+    Hide,
+}
+
+/// Dialog offering to restore documents left behind by a crash
+#[derive(Debug, Clone, Default)]
+pub struct RecoveryDialog {
+    /// Whether the dialog is visible
+    pub visible: bool,
+    /// Recoverable documents, newest first
+    pub entries: Vec<RecoveryEntry>,
+}
+
+impl RecoveryDialog {
+    /// Create a new recovery dialog
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show the dialog for the given entries; nothing is shown if there are none
+    pub fn show(&mut self, entries: Vec<RecoveryEntry>) {
+        self.visible = !entries.is_empty();
+        self.entries = entries;
+    }
+
+    /// Hide the dialog
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Remove and return the entry at index, hiding the dialog once empty
+    pub fn take_entry(&mut self, index: usize) -> Option<RecoveryEntry> {
+        if index >= self.entries.len() {
+            return None;
+        }
+
+        let entry = self.entries.remove(index);
+        if self.entries.is_empty() {
+            self.hide();
+        }
+        Some(entry)
+    }
+
+    /// Update the dialog state
+    ///
+    /// Restoring and discarding touch the filesystem and are handled by the application.
+    pub fn update(&mut self, message: RecoveryMessage) {
+        match message {
+            RecoveryMessage::Found(entries) => self.show(entries),
+            RecoveryMessage::DiscardAll => {
+                self.entries.clear();
+                self.hide();
+            }
+            RecoveryMessage::Hide => self.hide(),
+            RecoveryMessage::Restore(_) | RecoveryMessage::Discard(_) => {}
+        }
+    }
+
+    /// Render the recovery dialog
+    pub fn view<'a>(
+        &'a self,
+        localization: &'a LocalizationManager,
+    ) -> Element<'a, RecoveryMessage> {
+        if !self.visible {
+            return Space::new(Length::Shrink, Length::Shrink).into();
+        }
+
+        let mut entry_list = column![].spacing(spacing::SM);
+        for (index, entry) in self.entries.iter().enumerate() {
+            let location = entry
+                .original_path
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_else(|| localization.translate("untitled"));
+
+            entry_list = entry_list.push(
+                row![
+                    column![
+                        text(&entry.title).size(14),
+                        text(format!(
+                            "{} · {} {}",
+                            location,
+                            entry.minutes_ago(),
+                            localization.translate("recovery-minutes-ago")
+                        ))
+                        .size(11),
+                    ]
+                    .width(Length::Fill),
+                    button(text(localization.translate("recovery-restore")).size(12))
+                        .on_press(RecoveryMessage::Restore(index))
+                        .style(button::primary),
+                    button(text(localization.translate("recovery-discard")).size(12))
+                        .on_press(RecoveryMessage::Discard(index))
+                        .style(button::secondary),
+                ]
+                .spacing(spacing::SM)
+                .align_y(Alignment::Center),
+            );
+        }
+
+        let button_row = row![
+            button(text(localization.translate("recovery-discard-all")).size(14))
+                .on_press(RecoveryMessage::DiscardAll)
+                .style(button::secondary),
+            Space::new(Length::Fill, Length::Shrink),
+            button(text(localization.translate("recovery-later")).size(14))
+                .on_press(RecoveryMessage::Hide)
+                .style(button::secondary),
+        ]
+        .spacing(spacing::SM)
+        .align_y(Alignment::Center);
+
+        let content = column![
+            text(localization.translate("recovery-title"))
+                .size(20)
+                .style(text::primary),
+            text(localization.translate("recovery-description")).size(12),
+            horizontal_rule(1),
+            entry_list,
+            horizontal_rule(1),
+            button_row,
+        ]
+        .spacing(spacing::MD)
+        .padding(spacing::LG)
+        .max_width(600);
+
+        container(content)
+            .center_x(Length::Fill)
+            .center_y(Length::Fill)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::SystemTime;
+
+    fn entry(title: &str) -> RecoveryEntry {
+        RecoveryEntry {
+            document_id: uuid::Uuid::new_v4(),
+            title: title.to_string(),
+            original_path: None,
+            saved_at: SystemTime::now(),
+            snapshot_path: PathBuf::from(format!("{}.psoc", title)),
+        }
+    }
+
+    #[test]
+    fn test_recovery_dialog_only_shows_with_entries() {
+        let mut dialog = RecoveryDialog::new();
+        dialog.update(RecoveryMessage::Found(Vec::new()));
+        assert!(!dialog.visible);
+
+        dialog.update(RecoveryMessage::Found(vec![entry("A")]));
+        assert!(dialog.visible);
+    }
+
+    #[test]
+    fn test_recovery_dialog_take_entry() {
+        let mut dialog = RecoveryDialog::new();
+        dialog.show(vec![entry("A"), entry("B")]);
+
+        assert_eq!(dialog.take_entry(1).unwrap().title, "B");
+        assert!(dialog.visible);
+        assert!(dialog.take_entry(5).is_none());
+        assert_eq!(dialog.take_entry(0).unwrap().title, "A");
+        assert!(!dialog.visible);
+    }
+
+    #[test]
+    fn test_recovery_dialog_discard_all_and_hide() {
+        let mut dialog = RecoveryDialog::new();
+        dialog.show(vec![entry("A")]);
+        dialog.update(RecoveryMessage::Hide);
+        assert!(!dialog.visible);
+        assert_eq!(dialog.entries.len(), 1);
+
+        dialog.update(RecoveryMessage::DiscardAll);
+        assert!(dialog.entries.is_empty());
+    }
+}
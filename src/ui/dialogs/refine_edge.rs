@@ -0,0 +1,336 @@
+//! Refine Edge dialog for PSOC Image Editor
+
+use iced::{
+    widget::{button, column, container, pick_list, row, slider, text, Space},
+    Element, Length,
+};
+use psoc_core::RefineEdge;
+
+use super::super::theme::spacing;
+
+/// Where the refined selection goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RefineEdgeOutput {
+    /// Replace the current selection
+    #[default]
+    Selection,
+    /// Add a layer holding the image, masked by the refined selection
+    NewLayerWithMask,
+}
+
+impl RefineEdgeOutput {
+    /// All outputs in display order
+    pub const ALL: [RefineEdgeOutput; 2] = [
+        RefineEdgeOutput::Selection,
+        RefineEdgeOutput::NewLayerWithMask,
+    ];
+}
+
+impl std::fmt::Display for RefineEdgeOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RefineEdgeOutput::Selection => write!(f, "Selection"),
+            RefineEdgeOutput::NewLayerWithMask => write!(f, "New Layer with Layer Mask"),
+        }
+    }
+}
+
+/// Refine Edge dialog component
+#[derive(Debug, Clone)]
+pub struct RefineEdgeDialog {
+    /// Whether the dialog is visible
+    pub visible: bool,
+    /// Current refine edge parameters
+    pub refine: RefineEdge,
+    /// Where the result goes
+    pub output: RefineEdgeOutput,
+    /// Whether edge colors are decontaminated
+    pub decontaminate: bool,
+    /// Decontamination amount in percent
+    pub amount: f32,
+}
+
+/// Messages for the Refine Edge dialog
+#[derive(Debug, Clone)]
+pub enum RefineEdgeMessage {
+    /// Show the dialog
+    Show,
+    /// Hide the dialog
+    Hide,
+    /// Edge radius changed via slider
+    RadiusChanged(f32),
+    /// Smoothness changed via slider
+    SmoothnessChanged(f32),
+    /// Output target changed
+    OutputChanged(RefineEdgeOutput),
+    /// Toggle decontaminating colors
+    ToggleDecontaminate,
+    /// Decontamination amount changed via slider
+    AmountChanged(f32),
+    /// Refine the selection
+    Apply,
+    /// Cancel and close dialog
+    Cancel,
+}
+
+impl Default for RefineEdgeDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RefineEdgeDialog {
+    /// Create a new Refine Edge dialog
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            refine: RefineEdge::default(),
+            output: RefineEdgeOutput::default(),
+            decontaminate: false,
+            amount: 50.0,
+        }
+    }
+
+    /// Show the dialog
+    pub fn show(&mut self) {
+        self.visible = true;
+    }
+
+    /// Hide the dialog
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Current refine edge parameters
+    pub fn refine(&self) -> &RefineEdge {
+        &self.refine
+    }
+
+    /// Decontamination amount from 0.0 to 1.0, if colors are decontaminated
+    ///
+    /// Decontaminated colors can only be output to a layer.
+    pub fn decontaminate_amount(&self) -> Option<f32> {
+        (self.decontaminate && self.output == RefineEdgeOutput::NewLayerWithMask)
+            .then_some(self.amount / 100.0)
+    }
+
+    /// Update the dialog state based on a message
+    pub fn update(&mut self, message: RefineEdgeMessage) {
+        match message {
+            RefineEdgeMessage::Show => self.show(),
+            RefineEdgeMessage::Hide | RefineEdgeMessage::Cancel | RefineEdgeMessage::Apply => {
+                self.hide()
+            }
+            RefineEdgeMessage::RadiusChanged(radius) => {
+                self.refine = RefineEdge::new(radius.round() as u32, self.refine.smoothness);
+            }
+            RefineEdgeMessage::SmoothnessChanged(smoothness) => {
+                self.refine = RefineEdge::new(self.refine.radius, smoothness);
+            }
+            RefineEdgeMessage::OutputChanged(output) => {
+                self.output = output;
+                if output == RefineEdgeOutput::Selection {
+                    self.decontaminate = false;
+                }
+            }
+            RefineEdgeMessage::ToggleDecontaminate => {
+                self.decontaminate = !self.decontaminate;
+                if self.decontaminate {
+                    self.output = RefineEdgeOutput::NewLayerWithMask;
+                }
+            }
+            RefineEdgeMessage::AmountChanged(amount) => {
+                self.amount = amount.clamp(0.0, 100.0);
+            }
+        }
+    }
+
+    /// Render the Refine Edge dialog
+    pub fn view<Message>(
+        &self,
+        message_mapper: fn(RefineEdgeMessage) -> Message,
+    ) -> Element<'_, Message>
+    where
+        Message: Clone + 'static,
+    {
+        if !self.visible {
+            return Space::new(Length::Shrink, Length::Shrink).into();
+        }
+
+        let white = |_theme: &iced::Theme| iced::widget::text::Style {
+            color: Some(iced::Color::WHITE),
+        };
+
+        let labelled_slider = |label: &'static str,
+                               value_text: String,
+                               slider: iced::widget::Slider<'static, f32, Message>|
+         -> Element<'static, Message> {
+            container(
+                column![
+                    text(label).size(14.0).style(white),
+                    row![
+                        slider.width(Length::FillPortion(3)),
+                        container(text(value_text).size(12.0).style(white))
+                            .width(Length::FillPortion(1)),
+                    ]
+                    .spacing(spacing::SM)
+                    .align_y(iced::alignment::Vertical::Center),
+                ]
+                .spacing(spacing::XS),
+            )
+            .padding(spacing::MD)
+            .width(Length::Fill)
+            .into()
+        };
+
+        let radius = labelled_slider(
+            "Radius",
+            format!("{} px", self.refine.radius),
+            slider(
+                1.0..=RefineEdge::MAX_RADIUS as f32,
+                self.refine.radius as f32,
+                move |value| message_mapper(RefineEdgeMessage::RadiusChanged(value)),
+            ),
+        );
+        let smoothness = labelled_slider(
+            "Smooth",
+            format!("{:.0}", self.refine.smoothness),
+            slider(
+                0.0..=RefineEdge::MAX_SMOOTHNESS,
+                self.refine.smoothness,
+                move |value| message_mapper(RefineEdgeMessage::SmoothnessChanged(value)),
+            ),
+        );
+        let amount = labelled_slider(
+            "Amount",
+            format!("{:.0}%", self.amount),
+            slider(0.0..=100.0, self.amount, move |value| {
+                message_mapper(RefineEdgeMessage::AmountChanged(value))
+            }),
+        );
+
+        let content = container(
+            column![
+                // Header
+                container(text("Refine Edge").size(18.0).style(white))
+                    .padding(spacing::MD)
+                    .width(Length::Fill),
+                radius,
+                smoothness,
+                // Decontaminate toggle
+                container(
+                    row![
+                        button(text("Decontaminate Colors").size(12.0))
+                            .on_press(message_mapper(RefineEdgeMessage::ToggleDecontaminate))
+                            .padding([4.0, 8.0]),
+                        text(if self.decontaminate { "On" } else { "Off" })
+                            .size(12.0)
+                            .style(|_theme| iced::widget::text::Style {
+                                color: Some(if self.decontaminate {
+                                    iced::Color::from_rgb(0.0, 1.0, 0.0)
+                                } else {
+                                    iced::Color::from_rgb(0.7, 0.7, 0.7)
+                                })
+                            }),
+                    ]
+                    .spacing(spacing::SM)
+                    .align_y(iced::alignment::Vertical::Center)
+                )
+                .padding(spacing::MD)
+                .width(Length::Fill),
+                amount,
+                // Output selection
+                container(
+                    row![
+                        text("Output To").size(14.0).style(white),
+                        pick_list(RefineEdgeOutput::ALL, Some(self.output), move |output| {
+                            message_mapper(RefineEdgeMessage::OutputChanged(output))
+                        })
+                        .text_size(12.0),
+                    ]
+                    .spacing(spacing::SM)
+                    .align_y(iced::alignment::Vertical::Center)
+                )
+                .padding(spacing::MD)
+                .width(Length::Fill),
+                // Action buttons
+                container(
+                    row![
+                        Space::new(Length::Fill, Length::Shrink),
+                        button(text("Cancel").size(12.0))
+                            .on_press(message_mapper(RefineEdgeMessage::Cancel))
+                            .padding([6.0, 12.0]),
+                        button(text("OK").size(12.0))
+                            .on_press(message_mapper(RefineEdgeMessage::Apply))
+                            .padding([6.0, 12.0]),
+                    ]
+                    .spacing(spacing::SM)
+                    .align_y(iced::alignment::Vertical::Center)
+                )
+                .padding(spacing::MD)
+                .width(Length::Fill),
+            ]
+            .spacing(spacing::SM),
+        )
+        .padding(spacing::LG)
+        .width(Length::Fixed(400.0))
+        .style(container::bordered_box);
+
+        // Create modal overlay
+        container(
+            container(content)
+                .center_x(Length::Fill)
+                .center_y(Length::Fill),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(|_theme| iced::widget::container::Style {
+            background: Some(iced::Background::Color(iced::Color::from_rgba(
+                0.0, 0.0, 0.0, 0.7,
+            ))),
+            ..Default::default()
+        })
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refine_edge_dialog_parameters() {
+        let mut dialog = RefineEdgeDialog::new();
+        assert!(!dialog.visible);
+
+        dialog.update(RefineEdgeMessage::RadiusChanged(500.0));
+        assert_eq!(dialog.refine().radius, RefineEdge::MAX_RADIUS);
+        dialog.update(RefineEdgeMessage::SmoothnessChanged(35.0));
+        assert_eq!(dialog.refine().smoothness, 35.0);
+        assert_eq!(dialog.refine().radius, RefineEdge::MAX_RADIUS);
+
+        dialog.update(RefineEdgeMessage::Show);
+        assert!(dialog.visible);
+        dialog.update(RefineEdgeMessage::Cancel);
+        assert!(!dialog.visible);
+    }
+
+    #[test]
+    fn test_decontaminate_requires_layer_output() {
+        let mut dialog = RefineEdgeDialog::new();
+        assert_eq!(dialog.decontaminate_amount(), None);
+
+        // Turning decontamination on switches to layer output
+        dialog.update(RefineEdgeMessage::ToggleDecontaminate);
+        assert_eq!(dialog.output, RefineEdgeOutput::NewLayerWithMask);
+        dialog.update(RefineEdgeMessage::AmountChanged(80.0));
+        assert_eq!(dialog.decontaminate_amount(), Some(0.8));
+
+        // Going back to selection output turns it off
+        dialog.update(RefineEdgeMessage::OutputChanged(
+            RefineEdgeOutput::Selection,
+        ));
+        assert!(!dialog.decontaminate);
+        assert_eq!(dialog.decontaminate_amount(), None);
+    }
+}
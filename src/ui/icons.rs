@@ -36,6 +36,7 @@ pub enum Icon {
     Shape,
     Gradient,
     Crop,
+    Pen,
 
     // Shape tools
     Rectangle,
@@ -129,6 +130,7 @@ impl Icon {
             Icon::Shape => '◇',
             Icon::Gradient => '▦',
             Icon::Crop => '✂',
+            Icon::Pen => '✒',
 
             // Shape tools
             Icon::Rectangle => '▭',
@@ -232,6 +234,7 @@ impl Icon {
             Icon::Shape => "Shape",
             Icon::Gradient => "Gradient",
             Icon::Crop => "Crop",
+            Icon::Pen => "Pen",
             Icon::Rectangle => "Rectangle",
             Icon::Ellipse => "Ellipse",
             Icon::Line => "Line",
@@ -1,5 +1,6 @@
 //! Integration tests for UI components
 
+use psoc::ui::dialogs::preferences::UserPreferences;
 use psoc::ui::{AppState, LayerMessage, Message, PsocTheme};
 use psoc::ToolType;
 
@@ -13,6 +14,21 @@ fn test_app_state_creation() {
     assert!(matches!(state.theme, PsocTheme::Dark));
 }
 
+#[test]
+fn test_app_state_uses_saved_autosave_preferences() {
+    let mut preferences = UserPreferences::default();
+    preferences.defaults.auto_save_interval = 12;
+    preferences.defaults.auto_save_retention = 7;
+    preferences.defaults.strip_private_metadata = true;
+
+    let state = AppState::with_preferences(&preferences);
+    assert_eq!(state.auto_save_interval, 12);
+    assert!(state.strip_private_metadata);
+    if let Some(manager) = state.recovery_manager {
+        assert_eq!(manager.retention(), 7);
+    }
+}
+
 #[test]
 fn test_app_state_tool_changes() {
     let mut state = AppState {
@@ -11,7 +11,7 @@ use psoc_core::{
     geometry::{Point, Size},
     layer::{InterpolationQuality, Layer, LayerType, SmartObjectContentType, SmartTransform},
     // pixel::{PixelData, RgbaPixel}, // For future pixel-based tests
-    smart_object::SmartObjectManager,
+    smart_object::{svg_size, SmartObjectManager},
     Document,
     RenderEngine,
 };
@@ -315,3 +315,49 @@ fn test_non_smart_object_layer_methods() {
         .is_err());
     assert!(pixel_layer.reset_smart_object_transform().is_err());
 }
+
+/// 4x4 SVG whose left half is a solid red rectangle
+const HALF_RED_SVG: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="4">
+<rect x="0" y="0" width="2" height="4" fill="red"/>
+</svg>"#;
+
+#[test]
+fn test_svg_smart_object_stays_crisp_when_scaled_up() {
+    let content_type = SmartObjectContentType::EmbeddedSvg {
+        original_path: Some(PathBuf::from("logo.svg")),
+        svg_data: HALF_RED_SVG.as_bytes().to_vec(),
+    };
+    let original_size = svg_size(HALF_RED_SVG.as_bytes()).unwrap();
+    assert_eq!(original_size, Size::new(4.0, 4.0));
+
+    let mut manager = SmartObjectManager::new();
+    let transform = SmartTransform {
+        scale: (8.0, 8.0),
+        interpolation_quality: InterpolationQuality::High,
+        ..SmartTransform::default()
+    };
+    let rendered = manager
+        .render_smart_object(&content_type, original_size, &transform, None)
+        .unwrap();
+    assert_eq!(rendered.dimensions(), (32, 32));
+
+    // The edge at x = 16 is rasterized, not resampled, so both sides are exact
+    for y in [0, 16, 31] {
+        let inside = rendered.get_pixel(15, y).unwrap();
+        let outside = rendered.get_pixel(16, y).unwrap();
+        assert_eq!((inside.r, inside.g, inside.b, inside.a), (255, 0, 0, 255));
+        assert_eq!(outside.a, 0);
+    }
+}
+
+#[test]
+fn test_svg_smart_object_rejects_invalid_data() {
+    let content_type = SmartObjectContentType::EmbeddedSvg {
+        original_path: None,
+        svg_data: b"not an svg".to_vec(),
+    };
+
+    let mut manager = SmartObjectManager::new();
+    assert!(manager.load_content(&content_type).is_err());
+    assert!(svg_size(b"not an svg").is_err());
+}